@@ -1,7 +1,10 @@
 use {
     crate::{
+        char::CharExt,
+        completion::{fuzzy_match, CompletionItem, CompletionProvider, FuzzyMatch, WordCompletionProvider},
         decoration::Decoration,
         layout::{BlockElement, WrappedElement},
+        regex::is_word_char,
         selection::Affinity,
         session::Session,
         settings::Settings,
@@ -145,6 +148,28 @@ live_design!{
         draw_decoration: {
           //  draw_depth: 2.0,
         }
+        draw_find_match: {
+            color: #x9E7A2A66,
+        }
+        draw_info_bg: {
+            color: #3a
+        }
+        draw_info_text: {
+            text_style: <THEME_FONT_CODE> {}
+            color: #C0C0C0
+        }
+        draw_completion_bg: {
+            color: #x2E2E2E
+        }
+        draw_completion_selection: {
+            color: #x094771
+        }
+        draw_completion_text: {
+            text_style: <THEME_FONT_CODE> {}
+        }
+        completion_text_color: #C0C0C0,
+        completion_match_color: #x4FC1FF,
+        completion_detail_color: #8,
         draw_selection: {
            // draw_depth: 3.0,
         }
@@ -236,6 +261,28 @@ pub struct CodeEditor {
     #[live]
     draw_decoration: DrawDecoration,
     #[live]
+    draw_find_match: DrawColor,
+    #[live]
+    info_draw_list: DrawList2d,
+    #[live]
+    draw_info_bg: DrawColor,
+    #[live]
+    draw_info_text: DrawText,
+    #[live]
+    completion_draw_list: DrawList2d,
+    #[live]
+    draw_completion_bg: DrawColor,
+    #[live]
+    draw_completion_selection: DrawColor,
+    #[live]
+    draw_completion_text: DrawText,
+    #[live]
+    completion_text_color: Vec4,
+    #[live]
+    completion_match_color: Vec4,
+    #[live]
+    completion_detail_color: Vec4,
+    #[live]
     draw_selection: DrawSelection,
     #[live]
     draw_cursor: DrawColor,
@@ -270,6 +317,20 @@ pub struct CodeEditor {
     #[rust]
     blink_timer: Timer,
     
+    #[live(0.5)]
+    hover_delay: f64,
+    #[rust]
+    hover_timer: Timer,
+    #[rust]
+    hover_position: Option<Position>,
+    #[rust]
+    info: Option<(Position, String)>,
+    
+    #[rust(Box::new(WordCompletionProvider::default()) as Box<dyn CompletionProvider>)]
+    completion_provider: Box<dyn CompletionProvider>,
+    #[rust]
+    completion: Option<CompletionState>,
+    
     #[rust]
     line_end: usize,
 }
@@ -285,6 +346,48 @@ enum KeepCursorInView {
     Off
 }
 
+struct CompletionState {
+    /// The start of the word that is being completed.
+    start: Position,
+    items: Vec<CompletionItem>,
+    /// The indices of the items that match what was typed, with how they match, best first.
+    matches: Vec<(usize, FuzzyMatch)>,
+    selected_index: usize,
+    scroll_index: usize,
+    /// Set when the popup was closed with escape, so that it stays closed while the same word is
+    /// being typed.
+    is_dismissed: bool,
+    rect: Rect,
+}
+
+impl CompletionState {
+    fn is_visible(&self) -> bool {
+        !self.is_dismissed && !self.matches.is_empty()
+    }
+    
+    fn select(&mut self, index: usize) {
+        self.selected_index = index;
+        if index < self.scroll_index {
+            self.scroll_index = index;
+        }
+        else if index >= self.scroll_index + MAX_VISIBLE_COMPLETION_COUNT {
+            self.scroll_index = index + 1 - MAX_VISIBLE_COMPLETION_COUNT;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CompletionTrigger {
+    /// Start completing if a word char was typed.
+    Typing,
+    /// Start completing, even without a prefix, or reopen a dismissed popup.
+    Explicit,
+    /// Only refilter if the cursor is still in the same word.
+    Refilter,
+}
+
+const MAX_VISIBLE_COMPLETION_COUNT: usize = 10;
+
 impl KeepCursorInView {
     fn is_once(&self) -> bool {
         match self {
@@ -335,6 +438,49 @@ impl Widget for CodeEditor {
 #[derive(Clone, PartialEq, WidgetRef)]
 pub struct CodeEditorRef(WidgetRef);
 
+/// Returns the start of the word before the cursor, and the part of it before the cursor, if
+/// there is a single cursor without a selection.
+fn word_before_cursor(session: &Session) -> Option<(Position, String)> {
+    let selections = session.selections();
+    if selections.len() != 1 || !selections[0].is_empty() {
+        return None
+    }
+    let cursor = selections[0].cursor.position;
+    let text = session.document().as_text();
+    let line = &text.as_lines()[cursor.line_index][..cursor.byte_index];
+    let start = line
+        .char_indices()
+        .rev()
+        .take_while( | (_, char) | is_word_char(*char))
+        .last()
+        .map_or(cursor.byte_index, | (byte_index, _) | byte_index);
+    Some((
+        Position {line_index: cursor.line_index, byte_index: start},
+        line[start..].to_string()
+    ))
+}
+
+fn filter_completions(state: &mut CompletionState, prefix: &str) {
+    state.matches = state
+        .items
+        .iter()
+        .enumerate()
+        .filter_map( | (index, item) | Some((index, fuzzy_match(prefix, item.filter_text()) ?)))
+        .collect();
+    // the sort is stable, so items that score the same stay in the order they were given in
+    state.matches.sort_by( | (index_0, match_0), (index_1, match_1) | {
+        match_1.score.cmp(&match_0.score).then_with( || {
+            state.items[*index_0].label.len().cmp(&state.items[*index_1].label.len())
+        })
+    });
+    // leave out exact matches of what was typed when that is all there is
+    if state.matches.len() == 1 && state.items[state.matches[0].0].label == prefix {
+        state.matches.clear();
+    }
+    state.selected_index = 0;
+    state.scroll_index = 0;
+}
+
 impl CodeEditor {
     pub fn draw(&mut self, cx: &mut Cx2d, session: &mut Session) {
         // This needs to be called first to ensure the session is up to date.
@@ -475,10 +621,13 @@ impl CodeEditor {
         self.draw_bg.draw_abs(cx, cx.turtle().unscrolled_rect());
         
         self.draw_gutter(cx, session);
+        self.draw_find_match_layer(cx, session);
         self.draw_selection_layer(cx, session);
         self.draw_text_layer(cx, session);
         self.draw_indent_guide_layer(cx, session);
         self.draw_decoration_layer(cx, session);
+        self.draw_info(cx, session);
+        self.draw_completion(cx, session);
         
         // Get the last added selection.
         // Get the normalized cursor position. To go from normalized to screen position, multiply by
@@ -508,6 +657,61 @@ impl CodeEditor {
         self.redraw(cx);
     }
     
+    pub fn scroll_cursor_into_view(&mut self, cx: &mut Cx) {
+        self.keep_cursor_in_view = KeepCursorInView::JumpToPosition;
+        self.redraw(cx);
+    }
+    
+    /// Shows a box with some text, such as hover information, just below the given position.
+    /// The box is hidden again on the next key press or click.
+    pub fn show_info(&mut self, cx: &mut Cx, position: Position, text: String) {
+        self.info = Some((position, text));
+        self.redraw(cx);
+    }
+    
+    pub fn hide_info(&mut self, cx: &mut Cx) {
+        if self.info.take().is_some() {
+            self.redraw(cx);
+        }
+    }
+    
+    /// Sets where the completion popup gets its candidates from. The default offers the words in
+    /// the document.
+    pub fn set_completion_provider(&mut self, completion_provider: Box<dyn CompletionProvider>) {
+        self.completion_provider = completion_provider;
+    }
+    
+    /// Adds completions from a source that answers asynchronously, such as a language server.
+    /// `position` is the cursor position they were requested for. They are dropped if the cursor
+    /// has since moved to another word.
+    pub fn show_completions(&mut self, cx: &mut Cx, session: &Session, position: Position, items: Vec<CompletionItem>) {
+        let Some((start, prefix)) = word_before_cursor(session) else {
+            return
+        };
+        let Some(state) = &mut self.completion else {
+            return
+        };
+        if state.start != start || position.line_index != start.line_index || position < start {
+            return
+        }
+        // the async items go first, they are usually better than what the provider came up with
+        let mut new_items: Vec<CompletionItem> = items;
+        for item in state.items.drain(..) {
+            if !new_items.iter().any( | new_item | new_item.label == item.label) {
+                new_items.push(item);
+            }
+        }
+        state.items = new_items;
+        filter_completions(state, &prefix);
+        self.redraw(cx);
+    }
+    
+    pub fn close_completion(&mut self, cx: &mut Cx) {
+        if self.completion.take().is_some() {
+            self.redraw(cx);
+        }
+    }
+    
     pub fn reset_font_size(&mut self) {
         self.draw_gutter.text_style.font_size = 9.0;
         self.draw_text.text_style.font_size = 9.0;
@@ -579,10 +783,29 @@ impl CodeEditor {
             }
             self.blink_timer = cx.start_timeout(self.blink_speed)
         }
+        if self.hover_timer.is_event(event).is_some() {
+            if let Some(position) = self.hover_position {
+                dispatch_action(cx, CodeEditorAction::Hover(position));
+            }
+        }
+        let mut hit = event.hits(cx, self.scroll_bars.area());
+        if let Hit::KeyDown(_) | Hit::TextInput(_) | Hit::FingerDown(_) = hit {
+            self.hide_info(cx);
+        }
+        // keys the editor doesn't use can still run a command
+        let mut key_down_handled = matches!(hit, Hit::KeyDown(_));
+        if self.handle_completion_hit(cx, &hit, session, dispatch_action) {
+            if key_down_handled {
+                cx.set_key_down_handled();
+            }
+            hit = Hit::Nothing;
+        }
         let mut keyboard_moved_cursor = false;
-        match event.hits(cx, self.scroll_bars.area()) {
+        let mut completion_trigger = None;
+        match hit {
             Hit::KeyFocusLost(_) => {
                 self.animator_play(cx, id!(focus.off));
+                self.close_completion(cx);
             }
             Hit::KeyFocus(_) => {
                 self.animator_play(cx, id!(focus.on));
@@ -593,6 +816,7 @@ impl CodeEditor {
                 ..
             }) => {
                 session.fold();
+                session.clear_snippet_tabstops();
                 if !self.keep_cursor_in_view.is_locked() {
                     self.keep_cursor_in_view = KeepCursorInView::LockStart;
                 }
@@ -648,6 +872,16 @@ impl CodeEditor {
                     self.redraw(cx);
                 }
             }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyF,
+                modifiers: KeyModifiers {control, logo, shift, ..},
+                ..
+            }) => {
+                // with shift this is find in files, which is up to the embedder
+                if (control || logo) && !shift {
+                    dispatch_action(cx, CodeEditorAction::Find);
+                }
+            }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::KeyA,
                 modifiers: KeyModifiers {control, logo, ..},
@@ -658,6 +892,28 @@ impl CodeEditor {
                     self.redraw(cx);
                 }
             }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::F12,
+                ..
+            }) => {
+                dispatch_action(cx, CodeEditorAction::GoToDefinition(self.cursor_position(session)));
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::Space,
+                modifiers: KeyModifiers {control: true, ..},
+                ..
+            }) => {
+                completion_trigger = Some(CompletionTrigger::Explicit);
+            }
+            Hit::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyO,
+                modifiers: KeyModifiers {control, logo, shift: true, ..},
+                ..
+            }) => {
+                if control || logo {
+                    dispatch_action(cx, CodeEditorAction::DocumentSymbols);
+                }
+            }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::ArrowLeft,
                 modifiers:
@@ -675,6 +931,7 @@ impl CodeEditor {
                     session.move_left(!shift);
                 }
                 keyboard_moved_cursor = true;
+                completion_trigger = Some(CompletionTrigger::Refilter);
                 self.redraw(cx);
             }
             Hit::KeyDown(KeyEvent {
@@ -695,6 +952,7 @@ impl CodeEditor {
                 }
 
                 keyboard_moved_cursor = true;
+                completion_trigger = Some(CompletionTrigger::Refilter);
                 self.redraw(cx);
             }
             Hit::KeyDown(KeyEvent {
@@ -719,6 +977,12 @@ impl CodeEditor {
                 session.insert(input.into());
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
+                completion_trigger = Some(if input.chars().all(is_word_char) {
+                    CompletionTrigger::Typing
+                }
+                else {
+                    CompletionTrigger::Refilter
+                });
                 dispatch_action(cx, CodeEditorAction::TextDidChange);
             }
             Hit::KeyDown(KeyEvent {
@@ -735,10 +999,16 @@ impl CodeEditor {
                 modifiers: KeyModifiers {shift: false, ..},
                 ..
             }) => {
-                session.indent();
-                self.redraw(cx);
-                keyboard_moved_cursor = true;
-                dispatch_action(cx, CodeEditorAction::TextDidChange);
+                if session.select_next_snippet_tabstop() {
+                    self.redraw(cx);
+                    keyboard_moved_cursor = true;
+                }
+                else {
+                    session.indent();
+                    self.redraw(cx);
+                    keyboard_moved_cursor = true;
+                    dispatch_action(cx, CodeEditorAction::TextDidChange);
+                }
             }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::Tab,
@@ -757,6 +1027,7 @@ impl CodeEditor {
                 session.delete();
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
+                completion_trigger = Some(CompletionTrigger::Refilter);
                 dispatch_action(cx, CodeEditorAction::TextDidChange);
             }
             Hit::KeyDown(KeyEvent {
@@ -766,6 +1037,7 @@ impl CodeEditor {
                 session.backspace();
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
+                completion_trigger = Some(CompletionTrigger::Refilter);
                 dispatch_action(cx, CodeEditorAction::TextDidChange);
             }
             Hit::TextCopy(ce) => {
@@ -812,13 +1084,18 @@ impl CodeEditor {
             }
             Hit::FingerDown(FingerDownEvent {
                 abs,
-                modifiers: KeyModifiers {alt, ..},
+                modifiers: KeyModifiers {alt, control, logo, ..},
                 tap_count,
                 ..
             }) => {
                 cx.set_key_focus(self.scroll_bars.area());
+                self.close_completion(cx);
                 if let Some((cursor, affinity)) = self.pick(session, abs) {
-                    if alt {
+                    if control || logo {
+                        session.set_selection(cursor, affinity, 1);
+                        dispatch_action(cx, CodeEditorAction::GoToDefinition(cursor));
+                    }
+                    else if alt {
                         session.add_selection(cursor, affinity, tap_count);
                     } else {
                         session.set_selection(cursor, affinity, tap_count);
@@ -832,8 +1109,27 @@ impl CodeEditor {
                 self.reset_cursor_blinker(cx);
                 self.keep_cursor_in_view = KeepCursorInView::Off;
             }
-            Hit::FingerHoverIn(_) | Hit::FingerHoverOver(_) => {
+            Hit::FingerHoverIn(FingerHoverEvent {abs, ..}) | Hit::FingerHoverOver(FingerHoverEvent {abs, ..}) => {
                 cx.set_cursor(MouseCursor::Text);
+                let position = self.pick(session, abs).map( | (position, _) | position);
+                if position != self.hover_position {
+                    // whatever we showed for the old position no longer applies
+                    if self.info.is_some() && self.info.as_ref().map( | (position, _) | *position) == self.hover_position {
+                        self.hide_info(cx);
+                    }
+                    self.hover_position = position;
+                    cx.stop_timer(self.hover_timer);
+                    if position.is_some() {
+                        self.hover_timer = cx.start_timeout(self.hover_delay);
+                    }
+                }
+            }
+            Hit::FingerHoverOut(_) => {
+                cx.stop_timer(self.hover_timer);
+                if self.info.is_some() && self.info.as_ref().map( | (position, _) | *position) == self.hover_position {
+                    self.hide_info(cx);
+                }
+                self.hover_position = None;
             }
             Hit::FingerMove(FingerMoveEvent {abs, ..}) => {
                 self.reset_cursor_blinker(cx);
@@ -847,7 +1143,15 @@ impl CodeEditor {
                     self.redraw(cx);
                 }
             }
-            _ => {}
+            _ => key_down_handled = false
+        }
+        if key_down_handled {
+            cx.set_key_down_handled();
+        }
+        match completion_trigger {
+            Some(trigger) => self.update_completion(cx, session, trigger, dispatch_action),
+            None if keyboard_moved_cursor => self.close_completion(cx),
+            None => ()
         }
         if keyboard_moved_cursor{
             self.keep_cursor_in_view = KeepCursorInView::Once;
@@ -865,6 +1169,252 @@ impl CodeEditor {
         }
     }
     
+    fn cursor_position(&self, session: &Session) -> Position {
+        session.selections()[session.last_added_selection_index().unwrap()].cursor.position
+    }
+    
+    fn update_completion(
+        &mut self,
+        cx: &mut Cx,
+        session: &Session,
+        trigger: CompletionTrigger,
+        dispatch_action: &mut dyn FnMut(&mut Cx, CodeEditorAction),
+    ) {
+        let Some((start, prefix)) = word_before_cursor(session) else {
+            self.close_completion(cx);
+            return
+        };
+        match &mut self.completion {
+            Some(state) if state.start == start => {
+                if trigger == CompletionTrigger::Explicit {
+                    state.is_dismissed = false;
+                    dispatch_action(cx, CodeEditorAction::Complete(self.cursor_position(session)));
+                }
+            }
+            _ => {
+                let should_start = match trigger {
+                    CompletionTrigger::Typing => !prefix.is_empty(),
+                    CompletionTrigger::Explicit => true,
+                    CompletionTrigger::Refilter => false,
+                };
+                if !should_start {
+                    self.close_completion(cx);
+                    return
+                }
+                let items = self.completion_provider.complete(session.document(), start, &prefix);
+                self.completion = Some(CompletionState {
+                    start,
+                    items,
+                    matches: Vec::new(),
+                    selected_index: 0,
+                    scroll_index: 0,
+                    is_dismissed: false,
+                    rect: Rect::default(),
+                });
+                dispatch_action(cx, CodeEditorAction::Complete(self.cursor_position(session)));
+            }
+        }
+        filter_completions(self.completion.as_mut().unwrap(), &prefix);
+        self.redraw(cx);
+    }
+    
+    /// Handles the keys and clicks that go to the completion popup while it is visible. Returns
+    /// whether the hit was used.
+    fn handle_completion_hit(
+        &mut self,
+        cx: &mut Cx,
+        hit: &Hit,
+        session: &mut Session,
+        dispatch_action: &mut dyn FnMut(&mut Cx, CodeEditorAction),
+    ) -> bool {
+        let Some(state) = &mut self.completion else {
+            return false
+        };
+        if !state.is_visible() {
+            return false
+        }
+        let match_count = state.matches.len();
+        match hit {
+            Hit::KeyDown(KeyEvent {key_code: KeyCode::ArrowDown, ..}) => {
+                state.select((state.selected_index + 1) % match_count);
+            }
+            Hit::KeyDown(KeyEvent {key_code: KeyCode::ArrowUp, ..}) => {
+                state.select((state.selected_index + match_count - 1) % match_count);
+            }
+            Hit::KeyDown(KeyEvent {key_code: KeyCode::PageDown, ..}) => {
+                state.select((state.selected_index + MAX_VISIBLE_COMPLETION_COUNT).min(match_count - 1));
+            }
+            Hit::KeyDown(KeyEvent {key_code: KeyCode::PageUp, ..}) => {
+                state.select(state.selected_index.saturating_sub(MAX_VISIBLE_COMPLETION_COUNT));
+            }
+            Hit::KeyDown(KeyEvent {key_code: KeyCode::Escape, ..}) => {
+                state.is_dismissed = true;
+            }
+            Hit::KeyDown(KeyEvent {key_code: KeyCode::ReturnKey | KeyCode::Tab, modifiers: KeyModifiers {shift: false, ..}, ..}) => {
+                let index = state.selected_index;
+                self.accept_completion(cx, session, index, dispatch_action);
+                return true
+            }
+            Hit::FingerDown(FingerDownEvent {abs, ..}) if state.rect.contains(*abs) => {
+                let row = ((abs.y - state.rect.pos.y) / self.cell_size.y).max(0.0) as usize;
+                let index = (state.scroll_index + row).min(match_count - 1);
+                self.accept_completion(cx, session, index, dispatch_action);
+                return true
+            }
+            _ => return false
+        }
+        self.redraw(cx);
+        true
+    }
+    
+    fn accept_completion(
+        &mut self,
+        cx: &mut Cx,
+        session: &mut Session,
+        index: usize,
+        dispatch_action: &mut dyn FnMut(&mut Cx, CodeEditorAction),
+    ) {
+        let Some(state) = self.completion.take() else {
+            return
+        };
+        let cursor = self.cursor_position(session);
+        let snippet = state.items[state.matches[index].0].to_snippet();
+        session.insert_snippet(state.start, cursor - state.start, &snippet);
+        self.keep_cursor_in_view = KeepCursorInView::Once;
+        self.reset_cursor_blinker(cx);
+        self.redraw(cx);
+        dispatch_action(cx, CodeEditorAction::TextDidChange);
+    }
+    
+    fn draw_completion(&mut self, cx: &mut Cx2d, session: &Session) {
+        const MAX_COLUMN_COUNT: usize = 60;
+        
+        let Some(state) = &mut self.completion else {
+            return
+        };
+        if !state.is_visible() || state.start.line_index >= session.document().as_text().as_lines().len() {
+            return
+        }
+        let visible_matches = &state.matches[state.scroll_index..state.matches.len().min(state.scroll_index + MAX_VISIBLE_COMPLETION_COUNT)];
+        let label_column_count = visible_matches
+            .iter()
+            .map( | (index, _) | state.items[*index].label.column_count())
+            .max()
+            .unwrap_or(0);
+        let column_count = visible_matches
+            .iter()
+            .map( | (index, _) | {
+                let item = &state.items[*index];
+                label_column_count + item.detail.as_ref().map_or(0, | detail | detail.column_count() + 2)
+            })
+            .max()
+            .unwrap_or(0)
+            .min(MAX_COLUMN_COUNT);
+        
+        let (x, y) = session.layout().logical_to_normalized_position(state.start, Affinity::After);
+        let line_height = session.layout().line(state.start.line_index).scale();
+        let pad = dvec2(4.0, 0.0);
+        let size = dvec2(column_count as f64 * self.cell_size.x + 2.0 * pad.x, visible_matches.len() as f64 * self.cell_size.y);
+        let origin = self.viewport_rect.pos - self.scroll_bars.get_scroll_pos();
+        let mut pos = dvec2(x, y + line_height) * self.cell_size + origin - dvec2(pad.x, 0.0);
+        // flip above the line if there is no room below it
+        if pos.y + size.y > self.viewport_rect.pos.y + self.viewport_rect.size.y {
+            pos.y = y * self.cell_size.y + origin.y - size.y;
+        }
+        state.rect = Rect {pos, size};
+        
+        self.completion_draw_list.begin_overlay_reuse(cx);
+        cx.begin_pass_sized_turtle(Layout::flow_down());
+        self.draw_completion_bg.draw_abs(cx, state.rect);
+        self.draw_completion_text.text_style.font_size = self.draw_text.text_style.font_size;
+        for (row, (index, fuzzy_match)) in visible_matches.iter().enumerate() {
+            let row_pos = pos + dvec2(pad.x, row as f64 * self.cell_size.y);
+            if state.scroll_index + row == state.selected_index {
+                self.draw_completion_selection.draw_abs(cx, Rect {
+                    pos: dvec2(pos.x, row_pos.y),
+                    size: dvec2(size.x, self.cell_size.y)
+                });
+            }
+            let item = &state.items[*index];
+            // draw the label in runs, highlighting the chars that matched
+            let mut column = 0;
+            let mut run_start = 0;
+            let mut run_is_match = false;
+            let mut run_column = 0;
+            for (byte_index, char) in item.label.char_indices().chain([(item.label.len(), ' ')]) {
+                let is_match = fuzzy_match.indices.binary_search(&byte_index).is_ok();
+                let is_end = byte_index == item.label.len() || column >= MAX_COLUMN_COUNT;
+                if is_end || (byte_index > 0 && is_match != run_is_match) {
+                    self.draw_completion_text.color = if run_is_match {
+                        self.completion_match_color
+                    }
+                    else {
+                        self.completion_text_color
+                    };
+                    self.draw_completion_text.draw_abs(
+                        cx,
+                        row_pos + dvec2(run_column as f64 * self.cell_size.x, 0.0),
+                        &item.label[run_start..byte_index]
+                    );
+                    run_start = byte_index;
+                    run_column = column;
+                }
+                if is_end {
+                    break;
+                }
+                run_is_match = is_match;
+                column += char.column_count();
+            }
+            if let Some(detail) = &item.detail {
+                let detail_column = label_column_count + 2;
+                if detail_column < column_count {
+                    let detail: String = detail.chars().take(column_count - detail_column).collect();
+                    self.draw_completion_text.color = self.completion_detail_color;
+                    self.draw_completion_text.draw_abs(
+                        cx,
+                        row_pos + dvec2(detail_column as f64 * self.cell_size.x, 0.0),
+                        &detail
+                    );
+                }
+            }
+        }
+        cx.end_pass_sized_turtle();
+        self.completion_draw_list.end(cx);
+    }
+    
+    fn draw_info(&mut self, cx: &mut Cx2d, session: &Session) {
+        const MAX_LINE_COUNT: usize = 20;
+        const MAX_COLUMN_COUNT: usize = 100;
+        
+        let Some((position, text)) = &self.info else {
+            return
+        };
+        if position.line_index >= session.document().as_text().as_lines().len() {
+            return
+        }
+        let (x, y) = session.layout().logical_to_normalized_position(*position, Affinity::After);
+        let line_height = session.layout().line(position.line_index).scale();
+        let pad = dvec2(6.0, 4.0);
+        let pos = dvec2(x, y + line_height) * self.cell_size + self.viewport_rect.pos - self.scroll_bars.get_scroll_pos();
+        let lines: Vec<String> = text
+            .lines()
+            .take(MAX_LINE_COUNT)
+            .map( | line | line.chars().take(MAX_COLUMN_COUNT).collect())
+            .collect();
+        let column_count = lines.iter().map( | line | line.column_count()).max().unwrap_or(0);
+        let size = dvec2(column_count as f64, lines.len() as f64) * self.cell_size + 2.0 * pad;
+        
+        self.info_draw_list.begin_overlay_reuse(cx);
+        cx.begin_pass_sized_turtle(Layout::flow_down());
+        self.draw_info_bg.draw_abs(cx, Rect {pos, size});
+        self.draw_info_text.text_style.font_size = self.draw_text.text_style.font_size;
+        for (index, line) in lines.iter().enumerate() {
+            self.draw_info_text.draw_abs(cx, pos + pad + dvec2(0.0, index as f64 * self.cell_size.y), line);
+        }
+        cx.end_pass_sized_turtle();
+        self.info_draw_list.end(cx);
+    }
+    
     fn draw_gutter(&mut self, cx: &mut Cx2d, session: &Session) {
         let mut line_index = self.line_start;
         let mut origin_y = session.layout().line(self.line_start).y();
@@ -1058,8 +1608,23 @@ impl CodeEditor {
     }
     
     fn draw_decoration_layer(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
-        let mut active_decoration = None;
         let decorations = session.document().decorations();
+        self.draw_decorations(cx, session, &decorations, DecorationStyle::Underline);
+    }
+    
+    fn draw_find_match_layer(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+        let find_matches = session.find_matches();
+        self.draw_decorations(cx, session, &find_matches, DecorationStyle::Highlight);
+    }
+    
+    fn draw_decorations(
+        &mut self,
+        cx: &mut Cx2d<'_>,
+        session: &Session,
+        decorations: &[Decoration],
+        style: DecorationStyle,
+    ) {
+        let mut active_decoration = None;
         let mut decorations = decorations.iter();
         while decorations.as_slice().first().map_or(false, | decoration | {
             decoration.end().line_index < self.line_start
@@ -1076,6 +1641,7 @@ impl CodeEditor {
         }
         DrawDecorationLayer {
             code_editor: self,
+            style,
             active_decoration,
             decorations,
         }
@@ -1251,10 +1817,23 @@ impl CodeEditor {
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum CodeEditorAction {
     TextDidChange,
+    Find,
+    /// The mouse rested on a position for a while.
+    Hover(Position),
+    GoToDefinition(Position),
+    Complete(Position),
+    DocumentSymbols,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum DecorationStyle {
+    Underline,
+    Highlight,
 }
 
 struct DrawDecorationLayer<'a> {
     code_editor: &'a mut CodeEditor,
+    style: DecorationStyle,
     active_decoration: Option<ActiveDecoration>,
     decorations: Iter<'a, Decoration>,
 }
@@ -1419,20 +1998,21 @@ impl<'a> DrawDecorationLayer<'a> {
     ) {
         let start_x = mem::take(&mut self.active_decoration.as_mut().unwrap().start_x);
         let (x, y) = line.grid_to_normalized_position(row_index, column_index);
-        self.code_editor.draw_decoration.draw_abs(
-            cx,
-            Rect {
-                pos: DVec2 {
-                    x: start_x,
-                    y: origin_y + y,
-                } *self.code_editor.cell_size
-                    + self.code_editor.viewport_rect.pos,
-                size: DVec2 {
-                    x: x - start_x,
-                    y: line.scale(),
-                } *self.code_editor.cell_size,
-            },
-        );
+        let rect = Rect {
+            pos: DVec2 {
+                x: start_x,
+                y: origin_y + y,
+            } *self.code_editor.cell_size
+                + self.code_editor.viewport_rect.pos,
+            size: DVec2 {
+                x: x - start_x,
+                y: line.scale(),
+            } *self.code_editor.cell_size,
+        };
+        match self.style {
+            DecorationStyle::Underline => self.code_editor.draw_decoration.draw_abs(cx, rect),
+            DecorationStyle::Highlight => self.code_editor.draw_find_match.draw_abs(cx, rect),
+        }
     }
 }
 
@@ -0,0 +1,635 @@
+use {
+    crate::{document::Document, regex::is_word_char, text::Position},
+    makepad_widgets::{LiveFieldKind, LiveRegistry},
+    std::{collections::HashSet, ops::Range},
+};
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CompletionItem {
+    pub label: String,
+    pub detail: Option<String>,
+    /// The text that replaces the word being completed. If `is_snippet` is set, this is in
+    /// snippet syntax, see `Snippet::parse`.
+    pub insert_text: String,
+    pub is_snippet: bool,
+    /// The text that is matched against what was typed, if it differs from the label.
+    pub filter_text: Option<String>,
+}
+
+impl CompletionItem {
+    pub fn new(label: impl Into<String>) -> Self {
+        let label = label.into();
+        Self {
+            insert_text: label.clone(),
+            label,
+            ..Self::default()
+        }
+    }
+
+    pub fn snippet(label: impl Into<String>, snippet: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            insert_text: snippet.into(),
+            is_snippet: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_detail(self, detail: impl Into<String>) -> Self {
+        Self {
+            detail: Some(detail.into()),
+            ..self
+        }
+    }
+
+    pub fn filter_text(&self) -> &str {
+        self.filter_text.as_deref().unwrap_or(&self.label)
+    }
+
+    pub fn to_snippet(&self) -> Snippet {
+        if self.is_snippet {
+            Snippet::parse(&self.insert_text)
+        } else {
+            Snippet::plain(&self.insert_text)
+        }
+    }
+}
+
+/// A source of completion candidates.
+///
+/// Providers return every candidate that makes sense at `position`, which is the start of the
+/// word being completed. The code editor does the filtering and sorting, so a provider is free to
+/// ignore `prefix`, which is the part of the word before the cursor.
+pub trait CompletionProvider {
+    fn complete(
+        &mut self,
+        document: &Document,
+        position: Position,
+        prefix: &str,
+    ) -> Vec<CompletionItem>;
+}
+
+/// Offers the candidates of both providers, the first one's first.
+impl<A: CompletionProvider, B: CompletionProvider> CompletionProvider for (A, B) {
+    fn complete(
+        &mut self,
+        document: &Document,
+        position: Position,
+        prefix: &str,
+    ) -> Vec<CompletionItem> {
+        let mut items = self.0.complete(document, position, prefix);
+        items.extend(self.1.complete(document, position, prefix));
+        items
+    }
+}
+
+/// Offers the words in the document, nearest to the position first.
+#[derive(Clone, Debug)]
+pub struct WordCompletionProvider {
+    pub min_word_len: usize,
+    // the words of the document version that was last completed in, with their positions
+    cache: Option<(u64, Vec<(Position, String)>)>,
+}
+
+impl Default for WordCompletionProvider {
+    fn default() -> Self {
+        Self {
+            min_word_len: 3,
+            cache: None,
+        }
+    }
+}
+
+impl WordCompletionProvider {
+    fn words(document: &Document) -> Vec<(Position, String)> {
+        let text = document.as_text();
+        let mut words = Vec::new();
+        for (line_index, line) in text.as_lines().iter().enumerate() {
+            let mut start = None;
+            for (byte_index, char) in line.char_indices().chain([(line.len(), ' ')]) {
+                match (start, is_word_char(char)) {
+                    (None, true) => start = Some(byte_index),
+                    (Some(word_start), false) => {
+                        start = None;
+                        let word = &line[word_start..byte_index];
+                        if !word.starts_with(|char: char| char.is_ascii_digit()) {
+                            let position = Position {
+                                line_index,
+                                byte_index: word_start,
+                            };
+                            words.push((position, word.to_string()));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        words
+    }
+}
+
+impl CompletionProvider for WordCompletionProvider {
+    fn complete(
+        &mut self,
+        document: &Document,
+        position: Position,
+        _prefix: &str,
+    ) -> Vec<CompletionItem> {
+        let version = document.version();
+        if !matches!(self.cache, Some((cached_version, _)) if cached_version == version) {
+            self.cache = Some((version, Self::words(document)));
+        }
+        let (_, words) = self.cache.as_ref().unwrap();
+        let mut words: Vec<_> = words
+            .iter()
+            // leave out the word that is being completed
+            .filter(|&(word_position, word)| {
+                *word_position != position && word.chars().count() >= self.min_word_len
+            })
+            .map(|(word_position, word)| {
+                (word_position.line_index.abs_diff(position.line_index), word)
+            })
+            .collect();
+        words.sort_by_key(|&(distance, _)| distance);
+        let mut seen = HashSet::new();
+        words
+            .into_iter()
+            .filter(|&(_, word)| seen.insert(word))
+            .map(|(_, word)| CompletionItem::new(word.as_str()))
+            .collect()
+    }
+}
+
+/// Offers the names of the components and their properties that are known to a `LiveRegistry`,
+/// for editing `live_design!` blocks.
+#[derive(Clone, Debug, Default)]
+pub struct LiveCompletionProvider {
+    items: Vec<CompletionItem>,
+}
+
+impl LiveCompletionProvider {
+    pub fn new(live_registry: &LiveRegistry) -> Self {
+        let mut components = HashSet::new();
+        let mut properties = HashSet::new();
+        for live_type_info in live_registry.live_type_infos.values() {
+            if live_type_info.live_ignore {
+                continue;
+            }
+            let type_name = live_type_info.type_name.to_string();
+            for field in &live_type_info.fields {
+                if let LiveFieldKind::Live | LiveFieldKind::LiveOption = field.live_field_kind {
+                    properties.insert(field.id.to_string());
+                }
+            }
+            components.insert(type_name);
+        }
+        let mut items: Vec<CompletionItem> = components
+            .into_iter()
+            .map(|name| CompletionItem::new(name).with_detail("component"))
+            .chain(
+                properties
+                    .into_iter()
+                    .map(|name| CompletionItem::new(name).with_detail("property")),
+            )
+            .collect();
+        items.sort_by(|item_0, item_1| item_0.label.cmp(&item_1.label));
+        Self { items }
+    }
+}
+
+impl CompletionProvider for LiveCompletionProvider {
+    fn complete(&mut self, _: &Document, _: Position, _: &str) -> Vec<CompletionItem> {
+        self.items.clone()
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// The byte indices of the matched chars in the candidate.
+    pub indices: Vec<usize>,
+}
+
+/// Matches `pattern` against `candidate` if the chars of the pattern occur in the candidate in
+/// order, ignoring case. Matches at the start of the candidate, at word boundaries, and runs of
+/// consecutive chars score higher, and skipped chars score lower.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let mut score = 0;
+    let mut indices = Vec::new();
+    let mut candidate_chars = candidate.char_indices();
+    let mut prev_char = None;
+    let mut prev_matched = false;
+    for pattern_char in pattern.chars() {
+        let mut skipped = 0;
+        loop {
+            let (byte_index, char) = candidate_chars.next()?;
+            let is_match =
+                char == pattern_char || char.to_lowercase().eq(pattern_char.to_lowercase());
+            if is_match {
+                score += 1;
+                if char == pattern_char {
+                    score += 1;
+                }
+                if byte_index == 0 {
+                    score += 8;
+                } else if prev_matched {
+                    score += 5;
+                } else if is_word_start(prev_char, char) {
+                    score += 6;
+                }
+                score -= skipped.min(4);
+                indices.push(byte_index);
+                prev_char = Some(char);
+                prev_matched = true;
+                break;
+            }
+            skipped += 1;
+            prev_char = Some(char);
+            prev_matched = false;
+        }
+    }
+    // prefer shorter candidates among equally good matches
+    score -= (candidate.chars().count() - indices.len()).min(8) as i64 / 4;
+    Some(FuzzyMatch { score, indices })
+}
+
+fn is_word_start(prev_char: Option<char>, char: char) -> bool {
+    match prev_char {
+        Some(prev_char) => {
+            !is_word_char(prev_char)
+                || prev_char == '_' && char != '_'
+                || prev_char.is_lowercase() && char.is_uppercase()
+        }
+        None => true,
+    }
+}
+
+/// The text to insert for a completion, with its tab stops.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Snippet {
+    text: String,
+    tabstops: Vec<Range<usize>>,
+}
+
+impl Snippet {
+    pub fn plain(text: &str) -> Self {
+        Self {
+            text: text.replace('\r', ""),
+            tabstops: Vec::new(),
+        }
+    }
+
+    /// Parses a snippet in the syntax that language servers use: `$1` or `${1:placeholder}` is a
+    /// tab stop, `${1|one,two|}` is a tab stop with choices, of which the first one is inserted,
+    /// and `$0` is where the cursor ends up. Variables such as `$TM_FILENAME` are replaced by their
+    /// default, if any, and `\` escapes `$`, `}` and `\`.
+    pub fn parse(snippet: &str) -> Self {
+        let mut parser = SnippetParser {
+            chars: snippet.chars().filter(|&char| char != '\r').collect(),
+            index: 0,
+            text: String::new(),
+            tabstops: Vec::new(),
+        };
+        parser.parse(false);
+        let mut tabstops = parser.tabstops;
+        // $0 comes last, and only the first occurrence of every tab stop is visited
+        tabstops.sort_by_key(|&(index, ref range)| (index == 0, index, range.start));
+        tabstops.dedup_by_key(|&mut (index, _)| index);
+        Self {
+            text: parser.text,
+            tabstops: tabstops.into_iter().map(|(_, range)| range).collect(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The byte ranges of the tab stops in the text, in the order in which they are visited.
+    pub fn tabstops(&self) -> &[Range<usize>] {
+        &self.tabstops
+    }
+}
+
+struct SnippetParser {
+    chars: Vec<char>,
+    index: usize,
+    text: String,
+    tabstops: Vec<(usize, Range<usize>)>,
+}
+
+impl SnippetParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn parse(&mut self, in_placeholder: bool) {
+        while let Some(char) = self.peek() {
+            match char {
+                '\\' if matches!(
+                    self.chars.get(self.index + 1),
+                    Some('$' | '}' | '\\' | ',' | '|')
+                ) =>
+                {
+                    self.text.push(self.chars[self.index + 1]);
+                    self.index += 2;
+                }
+                '}' if in_placeholder => return,
+                '$' => self.parse_dollar(),
+                _ => {
+                    self.text.push(char);
+                    self.index += 1;
+                }
+            }
+        }
+    }
+
+    fn parse_dollar(&mut self) {
+        let start = self.index;
+        self.index += 1;
+        match self.peek() {
+            Some(char) if char.is_ascii_digit() => {
+                let index = self.parse_number();
+                let position = self.text.len();
+                self.tabstops.push((index, position..position));
+            }
+            Some('{') => {
+                self.index += 1;
+                match self.peek() {
+                    Some(char) if char.is_ascii_digit() => {
+                        let index = self.parse_number();
+                        let position = self.text.len();
+                        match self.peek() {
+                            Some(':') => {
+                                self.index += 1;
+                                self.parse(true);
+                            }
+                            Some('|') => {
+                                self.index += 1;
+                                self.parse_choice();
+                            }
+                            _ => {}
+                        }
+                        self.tabstops.push((index, position..self.text.len()));
+                    }
+                    Some(char) if is_word_char(char) => {
+                        self.parse_name();
+                        if self.peek() == Some(':') {
+                            self.index += 1;
+                            self.parse(true);
+                        }
+                    }
+                    _ => {
+                        self.index = start + 1;
+                        self.text.push('$');
+                        return;
+                    }
+                }
+                if self.peek() == Some('}') {
+                    self.index += 1;
+                }
+            }
+            Some(char) if is_word_char(char) => {
+                self.parse_name();
+            }
+            _ => self.text.push('$'),
+        }
+    }
+
+    fn parse_number(&mut self) -> usize {
+        let mut number = 0usize;
+        while let Some(digit) = self.peek().and_then(|char| char.to_digit(10)) {
+            number = number.saturating_mul(10).saturating_add(digit as usize);
+            self.index += 1;
+        }
+        number
+    }
+
+    fn parse_name(&mut self) {
+        while self.peek().is_some_and(is_word_char) {
+            self.index += 1;
+        }
+    }
+
+    fn parse_choice(&mut self) {
+        let mut is_first = true;
+        while let Some(char) = self.peek() {
+            self.index += 1;
+            match char {
+                '|' => break,
+                ',' => is_first = false,
+                '\\' => {
+                    if let Some(char) = self.peek() {
+                        self.index += 1;
+                        if is_first {
+                            self.text.push(char);
+                        }
+                    }
+                }
+                _ => {
+                    if is_first {
+                        self.text.push(char);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            decoration::DecorationSet,
+            selection::Affinity,
+            session::Session,
+            text::{Length, Text},
+            tokenizer::Language,
+        },
+    };
+
+    fn parse(snippet: &str) -> (String, Vec<(usize, usize)>) {
+        let snippet = Snippet::parse(snippet);
+        let tabstops = snippet
+            .tabstops()
+            .iter()
+            .map(|range| (range.start, range.end))
+            .collect();
+        (snippet.text().to_string(), tabstops)
+    }
+
+    fn session(text: &str) -> Session {
+        Session::new(Document::new(
+            Text::from(text),
+            DecorationSet::new(),
+            Language::Rust,
+        ))
+    }
+
+    fn position(line_index: usize, byte_index: usize) -> Position {
+        Position {
+            line_index,
+            byte_index,
+        }
+    }
+
+    #[test]
+    fn fuzzy_match_indices() {
+        assert_eq!(
+            fuzzy_match("gtd", "go_to_definition").unwrap().indices,
+            [0, 3, 6]
+        );
+        // the first matching char is taken, even if a later one is at a word start
+        assert_eq!(
+            fuzzy_match("gtd", "get_text_data").unwrap().indices,
+            [0, 2, 9]
+        );
+        assert_eq!(fuzzy_match("GD", "getData").unwrap().indices, [0, 3]);
+        assert_eq!(fuzzy_match("é", "aÉ").unwrap().indices, [1]);
+        assert_eq!(fuzzy_match("", "abc").unwrap().indices, []);
+        assert_eq!(fuzzy_match("ba", "abc"), None);
+        assert_eq!(fuzzy_match("abcd", "abc"), None);
+    }
+
+    #[test]
+    fn fuzzy_match_scores() {
+        let score = |pattern, candidate| fuzzy_match(pattern, candidate).unwrap().score;
+        // the start of the candidate, then word starts, then anywhere
+        assert!(score("te", "text") > score("te", "late"));
+        assert!(score("td", "text_data") > score("td", "tabulated"));
+        assert!(score("td", "textData") > score("td", "tabulated"));
+        assert!(score("ab", "x_ab") > score("ab", "xab"));
+        // consecutive chars
+        assert!(score("abc", "abcxx") > score("abc", "axbxc"));
+        // the same case
+        assert!(score("a", "a") > score("a", "A"));
+        // shorter candidates
+        assert!(score("ab", "ab") > score("ab", "abcdefghij"));
+    }
+
+    #[test]
+    fn parse_tabstops() {
+        assert_eq!(parse("plain text"), ("plain text".into(), vec![]));
+        assert_eq!(
+            parse("foo(${1:a}, ${2:b})$0"),
+            ("foo(a, b)".into(), vec![(4, 5), (7, 8), (9, 9)])
+        );
+        // $0 is visited last, whatever its place in the text
+        assert_eq!(
+            parse("$0a$2b$1"),
+            ("ab".into(), vec![(2, 2), (1, 1), (0, 0)])
+        );
+        // only the first occurrence of a tab stop is visited
+        assert_eq!(parse("${1:x} $1 ${1:y}"), ("x  y".into(), vec![(0, 1)]));
+        assert_eq!(parse("$10$9"), ("".into(), vec![(0, 0), (0, 0)]));
+    }
+
+    #[test]
+    fn parse_nested_placeholders() {
+        assert_eq!(
+            parse("${1:outer ${2:inner} ${3:x$0}} end"),
+            (
+                "outer inner x end".into(),
+                vec![(0, 13), (6, 11), (12, 13), (13, 13)]
+            )
+        );
+        // variables are replaced by their default
+        assert_eq!(
+            parse("${1:${TM_FILENAME:name}}.$VAR"),
+            ("name.".into(), vec![(0, 4)])
+        );
+    }
+
+    #[test]
+    fn parse_choices() {
+        assert_eq!(parse("${1|one,two|}!"), ("one!".into(), vec![(0, 3)]));
+        assert_eq!(parse("${1|a\\,b,c|}"), ("a,b".into(), vec![(0, 3)]));
+        assert_eq!(parse("${1|a\\|b|}"), ("a|b".into(), vec![(0, 3)]));
+    }
+
+    #[test]
+    fn parse_escapes() {
+        assert_eq!(parse("\\$1 \\} \\\\ \\a"), ("$1 } \\ \\a".into(), vec![]));
+        assert_eq!(parse("${1:a\\}b}"), ("a}b".into(), vec![(0, 3)]));
+        // a dollar that doesn't start anything is kept
+        assert_eq!(parse("$ ${} $"), ("$ ${} $".into(), vec![]));
+        assert_eq!(parse("a\r\nb"), ("a\nb".into(), vec![]));
+    }
+
+    #[test]
+    fn insert_snippet_is_one_undo_step() {
+        let mut session = session("fn main() {\n    \n}");
+        session.set_selection(position(1, 4), Affinity::Before, 1);
+        session.insert(Text::from("pri"));
+        session.insert_snippet(
+            position(1, 4),
+            Length {
+                line_count: 0,
+                byte_count: 3,
+            },
+            &Snippet::parse("println!(\"${1:{\\}}\", ${2:x});$0"),
+        );
+        let text = || session.document().as_text().to_string();
+        assert_eq!(text(), "fn main() {\n    println!(\"{}\", x);\n}");
+        let selection = session.selections()[0];
+        assert_eq!(
+            (selection.anchor, selection.cursor.position),
+            (position(1, 14), position(1, 16))
+        );
+        assert!(session.select_next_snippet_tabstop());
+        let selection = session.selections()[0];
+        assert_eq!(
+            (selection.anchor, selection.cursor.position),
+            (position(1, 19), position(1, 20))
+        );
+        assert!(session.select_next_snippet_tabstop());
+        assert!(!session.has_snippet_tabstops());
+
+        assert!(session.undo());
+        assert_eq!(
+            session.document().as_text().to_string(),
+            "fn main() {\n    pri\n}"
+        );
+        assert!(session.redo());
+        assert_eq!(
+            session.document().as_text().to_string(),
+            "fn main() {\n    println!(\"{}\", x);\n}"
+        );
+    }
+
+    #[test]
+    fn word_completions() {
+        let mut session = session("alpha beta\ngamma 123abc al\nalpha delta");
+        let mut provider = WordCompletionProvider::default();
+        let labels = |provider: &mut WordCompletionProvider, session: &Session, position| {
+            provider
+                .complete(session.document(), position, "")
+                .into_iter()
+                .map(|item| item.label)
+                .collect::<Vec<_>>()
+        };
+        // nearest first, without the word being completed, short words, or numbers
+        assert_eq!(
+            labels(&mut provider, &session, position(2, 6)),
+            ["alpha", "gamma", "beta"]
+        );
+        assert_eq!(
+            labels(&mut provider, &session, position(0, 0)),
+            ["beta", "gamma", "alpha", "delta"]
+        );
+
+        // the cached words are dropped when the document changes
+        session.set_selection(position(1, 0), Affinity::Before, 1);
+        session.insert(Text::from("epsilon "));
+        assert_eq!(
+            labels(&mut provider, &session, position(2, 6)),
+            ["alpha", "epsilon", "gamma", "beta"]
+        );
+        provider.min_word_len = 5;
+        assert_eq!(
+            labels(&mut provider, &session, position(2, 6)),
+            ["alpha", "epsilon", "gamma"]
+        );
+    }
+}
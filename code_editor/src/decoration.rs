@@ -90,6 +90,10 @@ impl DecorationSet {
         self.decorations.clear();
     }
 
+    pub fn remove_decorations(&mut self, id: usize) {
+        self.decorations.retain(|decoration| decoration.id != id);
+    }
+
     pub fn apply_edit(&mut self, edit: &Edit) {
         for decoration in &mut self.decorations {
             *decoration = decoration.apply_edit(edit);
@@ -11,16 +11,19 @@ use {
         str::StrExt,
         text::{Change, Drift, Edit, Length, Position, Text},
         token::{Token, TokenKind},
-        tokenizer::Tokenizer,
+        tokenizer::{Language, Tokenizer},
     },
     std::{
-        cell::{Ref, RefCell},
+        cell::{Cell, Ref, RefCell},
         cmp::Ordering,
         collections::HashMap,
         iter,
         ops::Range,
         rc::Rc,
-        sync::mpsc::Sender,
+        sync::{
+            atomic::{AtomicU64, Ordering as AtomicOrdering},
+            mpsc::Sender,
+        },
     },
 };
 
@@ -28,7 +31,7 @@ use {
 pub struct Document(Rc<DocumentInner>);
 
 impl Document {
-    pub fn new(text: Text, decorations:DecorationSet) -> Self {
+    pub fn new(text: Text, decorations:DecorationSet, language: Language) -> Self {
         let line_count = text.as_lines().len();
         let tokens: Vec<_> = (0..line_count)
             .map(|line| tokenize(&text.as_lines()[line]).collect::<Vec<_>>())
@@ -41,9 +44,11 @@ impl Document {
                 inline_inlays: (0..line_count).map(|_| Vec::new()).collect(),
                 block_inlays: Vec::new(),
             }),
-            tokenizer: RefCell::new(Tokenizer::new(line_count)),
+            tokenizer: RefCell::new(Tokenizer::new(language, line_count)),
             decorations: RefCell::new(decorations),
             edit_senders: RefCell::new(HashMap::new()),
+            change_senders: RefCell::new(Vec::new()),
+            version: Cell::new(next_version()),
         }));
         inner.update_indent_state();
         inner.0.tokenizer.borrow_mut().update(
@@ -57,6 +62,12 @@ impl Document {
         Ref::map(self.0.history.borrow(), |history| history.as_text())
     }
 
+    /// A number that changes on every edit. Versions are unique across documents, so equal
+    /// versions mean the same text.
+    pub fn version(&self) -> u64 {
+        self.0.version.get()
+    }
+
     pub fn layout(&self) -> Ref<'_, DocumentLayout> {
         self.0.layout.borrow()
     }
@@ -158,6 +169,34 @@ impl Document {
         self.update_after_edit(origin_id, None, &edits);
     }
 
+    /// Edits each of the given ranges, which must be sorted and non-overlapping, as a single
+    /// group. The ranges are edited back to front, so that editing one range never moves the start
+    /// of the ones that are still to be edited.
+    pub fn edit_ranges(
+        &self,
+        origin_id: SessionId,
+        kind: EditKind,
+        selections: &SelectionSet,
+        ranges: &[(Position, Length)],
+        mut f: impl FnMut(Editor<'_>, Position, Length),
+    ) {
+        let mut history = self.0.history.borrow_mut();
+        history.push_or_extend_group(origin_id, kind, selections);
+        let mut edits = Vec::new();
+        for &(position, length) in ranges.iter().rev() {
+            f(
+                Editor {
+                    history: &mut *history,
+                    edits: &mut edits,
+                },
+                position,
+                length,
+            );
+        }
+        drop(history);
+        self.update_after_edit(origin_id, None, &edits);
+    }
+
     pub fn add_decoration(&mut self, decoration: Decoration) {
         self.0.decorations.borrow_mut().add_decoration(decoration);
     }
@@ -166,6 +205,16 @@ impl Document {
         self.0.decorations.borrow_mut().clear()
     }
 
+    pub fn remove_decorations(&mut self, id: usize) {
+        self.0.decorations.borrow_mut().remove_decorations(id)
+    }
+
+    /// Adds a sender that receives the changes of every edit to the document, in the order they
+    /// were applied. Senders whose receiver has been dropped are removed.
+    pub fn add_change_sender(&mut self, change_sender: Sender<Vec<Change>>) {
+        self.0.change_senders.borrow_mut().push(change_sender);
+    }
+
     pub fn add_session(
         &mut self,
         session_id: SessionId,
@@ -331,6 +380,7 @@ impl Document {
         selections: Option<SelectionSet>,
         edits: &[Edit],
     ) {
+        self.0.version.set(next_version());
         let mut layout = self.0.layout.borrow_mut();
         for edit in edits {
             match edit.change {
@@ -370,6 +420,11 @@ impl Document {
             decorations.apply_edit(edit);
         }
         drop(decorations);
+        self.0.change_senders.borrow_mut().retain(|change_sender| {
+            change_sender
+                .send(edits.iter().map(|edit| edit.change.clone()).collect())
+                .is_ok()
+        });
         for (&session_id, edit_sender) in &*self.0.edit_senders.borrow() {
             if session_id == origin_id {
                 edit_sender
@@ -669,6 +724,13 @@ struct DocumentInner {
     tokenizer: RefCell<Tokenizer>,
     decorations: RefCell<DecorationSet>,
     edit_senders: RefCell<HashMap<SessionId, Sender<(Option<SelectionSet>, Vec<Edit>)>>>,
+    change_senders: RefCell<Vec<Sender<Vec<Change>>>>,
+    version: Cell<u64>,
+}
+
+fn next_version() -> u64 {
+    static NEXT_VERSION: AtomicU64 = AtomicU64::new(0);
+    NEXT_VERSION.fetch_add(1, AtomicOrdering::Relaxed)
 }
 
 fn tokenize(text: &str) -> impl Iterator<Item = Token> + '_ {
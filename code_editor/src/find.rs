@@ -0,0 +1,123 @@
+use crate::regex::{self, Captures, Regex, RegexError};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct FindOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+}
+
+/// A compiled search query.
+///
+/// Matches never span more than one line. In plain mode the query is matched literally, and
+/// replacements are inserted as is. In regex mode `$n` in a replacement refers to capture group
+/// `n` of the match.
+#[derive(Clone, Debug)]
+pub struct FindQuery {
+    pattern: String,
+    options: FindOptions,
+    regex: Regex,
+}
+
+impl FindQuery {
+    pub fn new(pattern: &str, options: FindOptions) -> Result<Self, RegexError> {
+        let regex = if options.regex {
+            if options.whole_word {
+                Regex::new(&format!("\\b(?:{})\\b", pattern), options.case_sensitive)?
+            } else {
+                Regex::new(pattern, options.case_sensitive)?
+            }
+        } else {
+            // Only require a word boundary next to the word chars of the query, so that a whole
+            // word search for `-x` still finds it after a space.
+            let mut escaped = Regex::escape(pattern);
+            if options.whole_word {
+                if pattern.chars().next().is_some_and(regex::is_word_char) {
+                    escaped.insert_str(0, "\\b");
+                }
+                if pattern.chars().next_back().is_some_and(regex::is_word_char) {
+                    escaped.push_str("\\b");
+                }
+            }
+            Regex::new(&escaped, options.case_sensitive)?
+        };
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            options,
+            regex,
+        })
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn options(&self) -> FindOptions {
+        self.options
+    }
+
+    /// Returns the captures of all non-empty matches on the given line.
+    pub fn captures_in_line(&self, line: &str) -> Vec<Captures> {
+        self.regex
+            .captures_iter(line)
+            .filter(|captures| !captures.range().is_empty())
+            .collect()
+    }
+
+    /// Returns the text that should replace the match with the given captures on `line`.
+    pub fn replacement(&self, line: &str, captures: &Captures, replacement: &str) -> String {
+        if !self.options.regex {
+            return replacement.to_owned();
+        }
+        let mut string = String::new();
+        captures.expand(line, replacement, &mut string);
+        string
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, options: FindOptions, line: &str) -> Vec<(usize, usize)> {
+        FindQuery::new(pattern, options)
+            .unwrap()
+            .captures_in_line(line)
+            .iter()
+            .map(|captures| (captures.range().start, captures.range().end))
+            .collect()
+    }
+
+    #[test]
+    fn whole_word() {
+        let plain = FindOptions {
+            whole_word: true,
+            ..FindOptions::default()
+        };
+        let regex = FindOptions {
+            regex: true,
+            ..plain
+        };
+        assert_eq!(find("ab", plain, "ab abc cab ab"), [(0, 2), (11, 13)]);
+        assert_eq!(find("-x", plain, "a -x a-x -xy"), [(2, 4), (6, 8)]);
+        // a later alternative can still be the whole word
+        assert_eq!(find("ab|abc", regex, "abc"), [(0, 3)]);
+        assert_eq!(find("a.", regex, "ab abc"), [(0, 2)]);
+    }
+
+    #[test]
+    fn replacement() {
+        let line = "let x = 1;";
+        let options = FindOptions {
+            regex: true,
+            case_sensitive: true,
+            ..FindOptions::default()
+        };
+        let query = FindQuery::new("(\\w+) = (\\d)", options).unwrap();
+        let captures = query.captures_in_line(line);
+        assert_eq!(query.replacement(line, &captures[0], "$2 = $1"), "1 = x");
+        let query = FindQuery::new("x", FindOptions::default()).unwrap();
+        let captures = query.captures_in_line(line);
+        assert_eq!(query.replacement(line, &captures[0], "$1"), "$1");
+    }
+}
@@ -0,0 +1,300 @@
+use {
+    crate::find::{FindOptions, FindQuery},
+    makepad_widgets::*,
+};
+
+live_design!{
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    FindBarButton = <Button> {
+        width: Fit,
+        height: Fit,
+        padding: {left: 8, right: 8, top: 4, bottom: 4}
+    }
+
+    FindBarInput = <TextInput> {
+        width: 250,
+        height: Fit,
+        on_focus_select_all: true,
+        draw_bg: {
+            color: #2
+            border_width: 1.0
+            border_color: #x00000044
+        }
+    }
+
+    FindBar = {{FindBar}} {
+        width: Fill,
+        height: Fit,
+        flow: Down,
+        spacing: 2,
+        padding: 4,
+        show_bg: true,
+        draw_bg: {color: #3}
+
+        <View> {
+            width: Fill,
+            height: Fit,
+            flow: Right,
+            spacing: 4,
+            align: {y: 0.5}
+            query = <FindBarInput> {empty_message: "Find"}
+            case_sensitive = <CheckBox> {text: "Aa"}
+            whole_word = <CheckBox> {text: "Word"}
+            regex = <CheckBox> {text: ".*"}
+            prev = <FindBarButton> {text: "Prev"}
+            next = <FindBarButton> {text: "Next"}
+            select_all = <FindBarButton> {text: "Select All"}
+            status = <Label> {
+                width: Fill,
+                draw_text: {color: #a}
+                text: ""
+            }
+            close = <FindBarButton> {text: "Close"}
+        }
+        <View> {
+            width: Fill,
+            height: Fit,
+            flow: Right,
+            spacing: 4,
+            align: {y: 0.5}
+            replacement = <FindBarInput> {empty_message: "Replace"}
+            replace = <FindBarButton> {text: "Replace"}
+            replace_all = <FindBarButton> {text: "Replace All"}
+        }
+    }
+}
+
+/// A bar to find and replace text in a code editor session.
+///
+/// The bar only turns user input into `FindBarAction`s; the owner applies them to the session,
+/// and reports the number of matches back through `set_match_count`.
+#[derive(Live)]
+pub struct FindBar {
+    #[deref]
+    view: View,
+    #[rust]
+    is_open: bool,
+    #[rust]
+    error: Option<String>,
+}
+
+impl LiveHook for FindBar {
+    fn before_live_design(cx: &mut Cx) {
+        register_widget!(cx, FindBar)
+    }
+}
+
+#[derive(Clone, Debug, WidgetAction)]
+pub enum FindBarAction {
+    /// The query or the options changed. The query is `None` if it is empty or invalid.
+    Find(Option<FindQuery>),
+    FindNext,
+    FindPrev,
+    SelectAll,
+    Replace(String),
+    ReplaceAll(String),
+    Close,
+    None,
+}
+
+impl Widget for FindBar {
+    fn redraw(&mut self, cx: &mut Cx) {
+        self.view.redraw(cx);
+    }
+
+    fn handle_widget_event_with(
+        &mut self,
+        cx: &mut Cx,
+        event: &Event,
+        dispatch_action: &mut dyn FnMut(&mut Cx, WidgetActionItem),
+    ) {
+        if !self.is_open {
+            return;
+        }
+        let uid = self.widget_uid();
+        let actions = self.view.handle_widget_event(cx, event);
+        let mut dispatch = | cx: &mut Cx, action: FindBarAction | {
+            dispatch_action(cx, WidgetActionItem::new(action.into(), uid));
+        };
+
+        let query = self.view.text_input(id!(query));
+        if let Some(item) = actions.find_single_action(query.widget_uid()) {
+            match item.action() {
+                TextInputAction::Return(_) => dispatch(cx, FindBarAction::FindNext),
+                TextInputAction::Escape => dispatch(cx, FindBarAction::Close),
+                _ => ()
+            }
+        }
+        let replacement = self.view.text_input(id!(replacement));
+        if let Some(item) = actions.find_single_action(replacement.widget_uid()) {
+            match item.action() {
+                TextInputAction::Return(text) => dispatch(cx, FindBarAction::Replace(text)),
+                TextInputAction::Escape => dispatch(cx, FindBarAction::Close),
+                _ => ()
+            }
+        }
+        if query.changed(&actions).is_some()
+            || self.view.check_box(id!(case_sensitive)).changed(&actions).is_some()
+            || self.view.check_box(id!(whole_word)).changed(&actions).is_some()
+            || self.view.check_box(id!(regex)).changed(&actions).is_some()
+        {
+            let query = self.find_query(cx);
+            dispatch(cx, FindBarAction::Find(query));
+        }
+        if self.view.button(id!(next)).clicked(&actions) {
+            dispatch(cx, FindBarAction::FindNext);
+        }
+        if self.view.button(id!(prev)).clicked(&actions) {
+            dispatch(cx, FindBarAction::FindPrev);
+        }
+        if self.view.button(id!(select_all)).clicked(&actions) {
+            dispatch(cx, FindBarAction::SelectAll);
+        }
+        if self.view.button(id!(replace)).clicked(&actions) {
+            dispatch(cx, FindBarAction::Replace(replacement.text()));
+        }
+        if self.view.button(id!(replace_all)).clicked(&actions) {
+            dispatch(cx, FindBarAction::ReplaceAll(replacement.text()));
+        }
+        if self.view.button(id!(close)).clicked(&actions) {
+            dispatch(cx, FindBarAction::Close);
+        }
+        for action in actions {
+            dispatch_action(cx, action);
+        }
+    }
+
+    fn walk(&mut self, cx: &mut Cx) -> Walk {
+        self.view.walk(cx)
+    }
+
+    fn draw_walk_widget(&mut self, cx: &mut Cx2d, walk: Walk) -> WidgetDraw {
+        if !self.is_open {
+            return WidgetDraw::done();
+        }
+        self.view.draw_walk_widget(cx, walk)
+    }
+
+    fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet) {
+        self.view.find_widgets(path, cached, results);
+    }
+}
+
+impl FindBar {
+    /// Opens the bar and focuses the query input. If `query` is given, it replaces the current
+    /// query.
+    pub fn open(&mut self, cx: &mut Cx, query: Option<&str>) {
+        self.is_open = true;
+        let query_input = self.view.text_input(id!(query));
+        if let Some(query) = query {
+            query_input.set_text(query);
+        }
+        if let Some(query_input) = query_input.borrow() {
+            query_input.set_key_focus(cx);
+        }
+        self.view.redraw(cx);
+    }
+
+    pub fn close(&mut self, cx: &mut Cx) {
+        self.is_open = false;
+        self.view.redraw(cx);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    pub fn options(&mut self, cx: &Cx) -> FindOptions {
+        FindOptions {
+            case_sensitive: self.view.check_box(id!(case_sensitive)).selected(cx),
+            whole_word: self.view.check_box(id!(whole_word)).selected(cx),
+            regex: self.view.check_box(id!(regex)).selected(cx),
+        }
+    }
+
+    /// Compiles the current query, or returns `None` if it is empty or not a valid pattern. The
+    /// error, if any, is shown in place of the match count.
+    pub fn find_query(&mut self, cx: &mut Cx) -> Option<FindQuery> {
+        let pattern = self.view.text_input(id!(query)).text();
+        self.error = None;
+        if pattern.is_empty() {
+            return None;
+        }
+        match FindQuery::new(&pattern, self.options(cx)) {
+            Ok(query) => Some(query),
+            Err(error) => {
+                self.error = Some(error.to_string());
+                self.view.label(id!(status)).set_text_and_redraw(cx, &self.error.clone().unwrap());
+                None
+            }
+        }
+    }
+
+    /// Shows which match is selected, if any, out of `count` matches.
+    pub fn set_match_count(&mut self, cx: &mut Cx, selected_index: Option<usize>, count: usize) {
+        if self.error.is_some() {
+            return;
+        }
+        let text = match (selected_index, count) {
+            (_, 0) if self.view.text_input(id!(query)).text().is_empty() => String::new(),
+            (_, 0) => "No results".to_string(),
+            (Some(index), count) => format!("{} of {}", index + 1, count),
+            (None, count) => format!("{} matches", count),
+        };
+        self.view.label(id!(status)).set_text_and_redraw(cx, &text);
+    }
+}
+
+#[derive(Clone, PartialEq, WidgetRef)]
+pub struct FindBarRef(WidgetRef);
+
+impl FindBarRef {
+    pub fn action(&self, actions: &WidgetActions) -> FindBarAction {
+        // The bar shares its uid with the view it derefs to, so skip the view's own actions.
+        let uid = self.widget_uid();
+        actions
+            .iter()
+            .filter(| item | item.widget_uid == uid)
+            .map(| item | item.action::<FindBarAction>())
+            .find(| action | !matches!(action, FindBarAction::None))
+            .unwrap_or(FindBarAction::None)
+    }
+
+    pub fn open(&self, cx: &mut Cx, query: Option<&str>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.open(cx, query);
+        }
+    }
+
+    pub fn close(&self, cx: &mut Cx) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.close(cx);
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        if let Some(inner) = self.borrow() {
+            inner.is_open()
+        }
+        else {
+            false
+        }
+    }
+
+    pub fn find_query(&self, cx: &mut Cx) -> Option<FindQuery> {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.find_query(cx)
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn set_match_count(&self, cx: &mut Cx, selected_index: Option<usize>, count: usize) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_match_count(cx, selected_index, count);
+        }
+    }
+}
@@ -1,10 +1,14 @@
+pub use makepad_regex as regex;
 pub use makepad_widgets;
 use makepad_widgets::*;
 
 pub mod char;
 pub mod code_editor;
+pub mod completion;
 pub mod decoration;
 pub mod document;
+pub mod find;
+pub mod find_bar;
 pub mod history;
 pub mod inlays;
 pub mod iter;
@@ -20,10 +24,12 @@ pub mod widgets;
 pub mod wrap;
 
 pub use self::{
-    code_editor::CodeEditor, document::Document, history::History, layout::Line,
-    selection::Selection, session::Session, settings::Settings, token::Token, tokenizer::Tokenizer,
+    code_editor::CodeEditor, completion::CompletionProvider, document::Document,
+    history::History, layout::Line, selection::Selection, session::Session, settings::Settings,
+    token::Token, tokenizer::Tokenizer,
 };
 
 pub fn live_design(cx: &mut Cx) {
     crate::code_editor::live_design(cx);
+    crate::find_bar::live_design(cx);
 }
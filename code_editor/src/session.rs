@@ -1,7 +1,11 @@
 use {
     crate::{
         char::CharExt,
+        completion::Snippet,
+        decoration::{Decoration, DecorationSet},
         document::Document,
+        find::FindQuery,
+        regex::Captures,
         history::EditKind,
         layout::{BlockElement, Layout, WrappedElement},
         selection::{Affinity, Cursor, SelectionSet},
@@ -15,7 +19,7 @@ use {
         cell::{Ref, RefCell},
         collections::HashSet,
         fmt::Write,
-        iter, mem,
+        iter, mem, ops,
         rc::Rc,
         sync::{atomic, atomic::AtomicUsize, mpsc, mpsc::Receiver},
     },
@@ -32,6 +36,8 @@ pub struct Session {
     folding_lines: HashSet<usize>,
     folded_lines: HashSet<usize>,
     unfolding_lines: HashSet<usize>,
+    find_state: RefCell<FindState>,
+    snippet_tabstops: RefCell<Vec<Decoration>>,
     edit_receiver: Receiver<(Option<SelectionSet>, Vec<Edit>)>,
 }
 
@@ -62,7 +68,12 @@ impl Session {
             folding_lines: HashSet::new(),
             folded_lines: HashSet::new(),
             unfolding_lines: HashSet::new(),
-
+            find_state: RefCell::new(FindState {
+                query: None,
+                line_captures: Vec::new(),
+                matches: DecorationSet::new(),
+            }),
+            snippet_tabstops: RefCell::new(Vec::new()),
             edit_receiver,
         };
         for line in 0..line_count {
@@ -113,6 +124,24 @@ impl Session {
         })
     }
 
+    pub fn find_query(&self) -> Ref<'_, Option<FindQuery>> {
+        Ref::map(self.find_state.borrow(), |find_state| &find_state.query)
+    }
+
+    pub fn find_matches(&self) -> Ref<'_, [Decoration]> {
+        Ref::map(self.find_state.borrow(), |find_state| {
+            find_state.matches.as_decorations()
+        })
+    }
+
+    /// The index of the find match that the last added selection covers exactly, if any.
+    pub fn selected_find_match_index(&self) -> Option<usize> {
+        let selection = self.selections()[self.last_added_selection_index()?];
+        self.find_matches().iter().position(|decoration| {
+            decoration.start() == selection.start() && decoration.end() == selection.end()
+        })
+    }
+
     pub fn set_wrap_column(&mut self, wrap_column: Option<usize>) {
         if self.wrap_column == wrap_column {
             return;
@@ -215,11 +244,13 @@ impl Session {
         selection_state.last_added_selection_index = Some(0);
         selection_state.injected_char_stack.clear();
         drop(selection_state);
+        self.snippet_tabstops.borrow_mut().clear();
         self.update_highlighted_delimiter_positions();
         self.document.force_new_group();
     }
 
     pub fn add_selection(&mut self, position: Position, affinity: Affinity, _tap_count: u32) {
+        self.snippet_tabstops.borrow_mut().clear();
         let mut selection_state = self.selection_state.borrow_mut();
         selection_state.last_added_selection_index = Some(
             selection_state
@@ -749,14 +780,195 @@ impl Session {
         string
     }
 
+    /// Sets the query to search for, or clears the search if `query` is `None`. The matches are
+    /// kept up to date as the text changes.
+    pub fn set_find_query(&mut self, query: Option<FindQuery>) {
+        let line_count = self.document.as_text().as_lines().len();
+        let mut find_state = self.find_state.borrow_mut();
+        find_state.query = query;
+        find_state.line_captures = (0..line_count).map(|_| None).collect();
+        drop(find_state);
+        self.update_find_matches();
+    }
+
+    /// Selects the first match that starts at or after the start of the last added selection,
+    /// wrapping around at the end of the text. This keeps the selection in place while the query
+    /// is being typed.
+    pub fn find_nearest(&mut self) -> bool {
+        let position = self.selections()[self.last_added_selection_index().unwrap()].start();
+        self.find_from(position)
+    }
+
+    /// Selects the first match after the last added selection, wrapping around at the end of the
+    /// text.
+    pub fn find_next(&mut self) -> bool {
+        let position = self.selections()[self.last_added_selection_index().unwrap()].end();
+        self.find_from(position)
+    }
+
+    /// Selects the last match before the last added selection, wrapping around at the start of
+    /// the text.
+    pub fn find_prev(&mut self) -> bool {
+        let position = self.selections()[self.last_added_selection_index().unwrap()].start();
+        let matches = self.find_matches();
+        let index = matches
+            .iter()
+            .rposition(|decoration| decoration.start() < position)
+            .or(matches.len().checked_sub(1));
+        drop(matches);
+        match index {
+            Some(index) => {
+                self.select_find_match(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the current selections with one selection for each match.
+    pub fn select_all_occurrences(&mut self) -> bool {
+        let matches = self.find_matches();
+        if matches.is_empty() {
+            return false;
+        }
+        let mut selections = SelectionSet::new();
+        for (index, decoration) in matches.iter().enumerate() {
+            let selection = Selection {
+                cursor: Cursor::from(decoration.end()),
+                anchor: decoration.start(),
+            };
+            if index == 0 {
+                selections.set_selection(selection);
+            } else {
+                selections.add_selection(selection);
+            }
+        }
+        drop(matches);
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.last_added_selection_index = Some(selections.len() - 1);
+        selection_state.selections = selections;
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document.force_new_group();
+        true
+    }
+
+    /// Replaces the match that is currently selected, if any, and then selects the next match.
+    pub fn replace(&mut self, replacement: &str) -> bool {
+        match self.selected_find_match_index() {
+            Some(index) => {
+                let end = self.replace_find_matches(index..index + 1, replacement);
+                self.handle_changes();
+                // Don't search the replacement itself, in case it contains the query.
+                if let Some(end) = end {
+                    self.find_from(end);
+                }
+                true
+            }
+            None => {
+                self.find_next();
+                false
+            }
+        }
+    }
+
+    /// Replaces all matches as a single undo group, and returns the number of replaced matches.
+    pub fn replace_all(&mut self, replacement: &str) -> usize {
+        let match_count = self.find_matches().len();
+        self.replace_find_matches(0..match_count, replacement);
+        self.handle_changes();
+        match_count
+    }
+
+    /// Replaces the range of the given start and length by the text of a snippet, as a single
+    /// undo step, and selects its first tab stop. If there is no tab stop, the cursor ends up
+    /// after the inserted text.
+    pub fn insert_snippet(&mut self, start: Position, length: Length, snippet: &Snippet) {
+        let text = snippet.text();
+        self.selection_state
+            .borrow_mut()
+            .injected_char_stack
+            .clear();
+        self.document.edit_ranges(
+            self.id,
+            EditKind::Other,
+            &self.selection_state.borrow().selections,
+            &[(start, length)],
+            |mut editor, position, length| {
+                if length != Length::zero() {
+                    editor.apply_edit(Edit {
+                        change: Change::Delete(position, length),
+                        drift: Drift::Before,
+                    });
+                }
+                if !text.is_empty() {
+                    editor.apply_edit(Edit {
+                        change: Change::Insert(position, Text::from(text)),
+                        drift: Drift::Before,
+                    });
+                }
+            },
+        );
+        // Bring the layout up to date before we select anything in the new text.
+        self.handle_changes();
+        let mut tabstops: Vec<Decoration> = snippet
+            .tabstops()
+            .iter()
+            .map(|range| {
+                Decoration::new(
+                    0,
+                    start + str_length(&text[..range.start]),
+                    start + str_length(&text[..range.end]),
+                )
+            })
+            .collect();
+        let end = start + str_length(text);
+        if tabstops.is_empty() {
+            tabstops.push(Decoration::new(0, end, end));
+        }
+        tabstops.reverse();
+        *self.snippet_tabstops.borrow_mut() = tabstops;
+        self.select_next_snippet_tabstop();
+    }
+
+    /// Whether a snippet has been inserted that still has tab stops to visit.
+    pub fn has_snippet_tabstops(&self) -> bool {
+        !self.snippet_tabstops.borrow().is_empty()
+    }
+
+    /// Selects the next tab stop of the last inserted snippet, if any.
+    pub fn select_next_snippet_tabstop(&mut self) -> bool {
+        let Some(tabstop) = self.snippet_tabstops.borrow_mut().pop() else {
+            return false;
+        };
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.selections.set_selection(Selection {
+            anchor: tabstop.start(),
+            cursor: Cursor::from(tabstop.end()),
+        });
+        selection_state.last_added_selection_index = Some(0);
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document.force_new_group();
+        true
+    }
+
+    pub fn clear_snippet_tabstops(&mut self) {
+        self.snippet_tabstops.borrow_mut().clear();
+    }
+
     pub fn undo(&mut self) -> bool {
         self.selection_state.borrow_mut().injected_char_stack.clear();
+        self.snippet_tabstops.borrow_mut().clear();
         self.document
             .undo(self.id, &self.selection_state.borrow().selections)
     }
 
     pub fn redo(&mut self) -> bool {
         self.selection_state.borrow_mut().injected_char_stack.clear();
+        self.snippet_tabstops.borrow_mut().clear();
         self.document
             .redo(self.id, &self.selection_state.borrow().selections)
     }
@@ -864,7 +1076,158 @@ impl Session {
             }
         }
         drop(selection_state);
+        for tabstop in self.snippet_tabstops.borrow_mut().iter_mut() {
+            for edit in edits {
+                *tabstop = tabstop.apply_edit(edit);
+            }
+        }
         self.update_highlighted_delimiter_positions();
+        self.update_find_matches_after_edit(edits);
+    }
+
+    fn find_from(&mut self, position: Position) -> bool {
+        let matches = self.find_matches();
+        let index = matches
+            .iter()
+            .position(|decoration| decoration.start() >= position)
+            .or(if matches.is_empty() { None } else { Some(0) });
+        drop(matches);
+        match index {
+            Some(index) => {
+                self.select_find_match(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn select_find_match(&mut self, index: usize) {
+        let decoration = self.find_matches()[index];
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.selections.set_selection(Selection {
+            cursor: Cursor::from(decoration.end()),
+            anchor: decoration.start(),
+        });
+        selection_state.last_added_selection_index = Some(0);
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document.force_new_group();
+    }
+
+    // Returns the position right after the first replacement, if any.
+    fn replace_find_matches(
+        &mut self,
+        indices: ops::Range<usize>,
+        replacement: &str,
+    ) -> Option<Position> {
+        let find_state = self.find_state.borrow();
+        let query = find_state.query.as_ref()?;
+        let text = self.document.as_text();
+        let mut ranges = Vec::new();
+        let mut replacements = Vec::new();
+        for decoration in &find_state.matches[indices] {
+            let start = decoration.start();
+            let end = decoration.end();
+            let line = &text.as_lines()[start.line_index];
+            let captures = find_state.line_captures[start.line_index]
+                .iter()
+                .flatten()
+                .find(|captures| captures.range() == (start.byte_index..end.byte_index));
+            if let Some(captures) = captures {
+                ranges.push((start, end - start));
+                replacements.push(Text::from(query.replacement(line, captures, replacement)));
+            }
+        }
+        drop(text);
+        drop(find_state);
+        let end = ranges
+            .first()
+            .map(|&(start, _)| start + replacements[0].length());
+        self.selection_state
+            .borrow_mut()
+            .injected_char_stack
+            .clear();
+        let mut replacements = replacements.into_iter().rev();
+        self.document.edit_ranges(
+            self.id,
+            EditKind::Other,
+            &self.selection_state.borrow().selections,
+            &ranges,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                let replacement = replacements.next().unwrap();
+                if !replacement.is_empty() {
+                    editor.apply_edit(Edit {
+                        change: Change::Insert(position, replacement),
+                        drift: Drift::Before,
+                    });
+                }
+            },
+        );
+        end
+    }
+
+    // Only the lines that an edit touched are searched again.
+    fn update_find_matches_after_edit(&self, edits: &[Edit]) {
+        let mut find_state = self.find_state.borrow_mut();
+        if find_state.query.is_none() {
+            return;
+        }
+        for edit in edits {
+            match edit.change {
+                Change::Insert(point, ref text) => {
+                    find_state.line_captures[point.line_index] = None;
+                    let line = point.line_index + 1;
+                    let line_count = text.length().line_count;
+                    find_state
+                        .line_captures
+                        .splice(line..line, (0..line_count).map(|_| None));
+                }
+                Change::Delete(start, length) => {
+                    find_state.line_captures[start.line_index] = None;
+                    let start_line = start.line_index + 1;
+                    let end_line = start_line + length.line_count;
+                    find_state.line_captures.drain(start_line..end_line);
+                }
+            }
+        }
+        drop(find_state);
+        self.update_find_matches();
+    }
+
+    fn update_find_matches(&self) {
+        let mut find_state = self.find_state.borrow_mut();
+        let find_state = &mut *find_state;
+        find_state.matches.clear();
+        let Some(query) = &find_state.query else {
+            return;
+        };
+        let text = self.document.as_text();
+        for (line_index, (line, captures)) in text
+            .as_lines()
+            .iter()
+            .zip(&mut find_state.line_captures)
+            .enumerate()
+        {
+            let captures = captures.get_or_insert_with(|| query.captures_in_line(line));
+            for range in captures.iter().map(|captures| captures.range()) {
+                find_state.matches.add_decoration(Decoration::new(
+                    0,
+                    Position {
+                        line_index,
+                        byte_index: range.start,
+                    },
+                    Position {
+                        line_index,
+                        byte_index: range.end,
+                    },
+                ));
+            }
+        }
     }
 
     fn update_y(&self) {
@@ -984,6 +1347,14 @@ struct SelectionState {
     highlighted_delimiter_positions: HashSet<Position>,
 }
 
+#[derive(Debug)]
+struct FindState {
+    query: Option<FindQuery>,
+    // The captures of the matches on each line, `None` for lines that need to be searched again.
+    line_captures: Vec<Option<Vec<Captures>>>,
+    matches: DecorationSet,
+}
+
 pub fn reindent(string: &str, f: impl FnOnce(usize) -> usize) -> (usize, usize, String) {
     let indentation = string.indent().unwrap_or("");
     let indentation_column_count = indentation.column_count();
@@ -997,6 +1368,19 @@ pub fn reindent(string: &str, f: impl FnOnce(usize) -> usize) -> (usize, usize,
     )
 }
 
+fn str_length(string: &str) -> Length {
+    match string.rfind('\n') {
+        Some(index) => Length {
+            line_count: string.matches('\n').count(),
+            byte_count: string.len() - index - 1,
+        },
+        None => Length {
+            line_count: 0,
+            byte_count: string.len(),
+        },
+    }
+}
+
 fn new_indentation(column_count: usize) -> String {
     iter::repeat(' ').take(column_count).collect()
 }
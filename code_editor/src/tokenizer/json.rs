@@ -0,0 +1,168 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, LanguageTokenizer},
+};
+
+/// Tokenizes JSON, with the comments that configuration files often allow.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonTokenizer;
+
+impl LanguageTokenizer for JsonTokenizer {
+    type State = JsonState;
+
+    fn next(&self, state: JsonState, cursor: &mut Cursor) -> (JsonState, TokenKind) {
+        match state {
+            JsonState::Initial => initial(cursor),
+            JsonState::BlockCommentTail => block_comment_tail(cursor),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum JsonState {
+    #[default]
+    Initial,
+    BlockCommentTail,
+}
+
+fn initial(cursor: &mut Cursor) -> (JsonState, TokenKind) {
+    let kind = match (cursor.peek(0), cursor.peek(1)) {
+        ('/', '/') => {
+            cursor.skip_to_end();
+            TokenKind::Comment
+        }
+        ('/', '*') => {
+            cursor.skip(2);
+            return block_comment_tail(cursor);
+        }
+        ('"', _) => {
+            cursor.skip(1);
+            loop {
+                match cursor.peek(0) {
+                    '"' => {
+                        cursor.skip(1);
+                        break;
+                    }
+                    '\0' => break,
+                    '\\' => cursor.skip(2),
+                    _ => cursor.skip(1),
+                }
+            }
+            // object keys are set apart from string values
+            let mut index = 0;
+            while cursor.peek(index).is_whitespace() {
+                index += 1;
+            }
+            if cursor.peek(index) == ':' {
+                TokenKind::Identifier
+            } else {
+                TokenKind::String
+            }
+        }
+        ('{' | '}' | '[' | ']', _) => {
+            cursor.skip(1);
+            TokenKind::Delimiter
+        }
+        (':' | ',', _) => {
+            cursor.skip(1);
+            TokenKind::Punctuator
+        }
+        ('-', char) if char.is_ascii_digit() => number(cursor),
+        (char, _) if char.is_ascii_digit() => number(cursor),
+        (char, _) if char.is_ascii_alphabetic() => {
+            let start = cursor.index();
+            cursor.skip_while(|char| char.is_ascii_alphanumeric());
+            match cursor.skipped_since(start) {
+                "true" | "false" | "null" => TokenKind::OtherKeyword,
+                _ => TokenKind::Unknown,
+            }
+        }
+        (char, _) if char.is_whitespace() => {
+            cursor.skip_while(|char| char.is_whitespace());
+            TokenKind::Whitespace
+        }
+        _ => {
+            cursor.skip(1);
+            TokenKind::Unknown
+        }
+    };
+    (JsonState::Initial, kind)
+}
+
+fn block_comment_tail(cursor: &mut Cursor) -> (JsonState, TokenKind) {
+    loop {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('*', '/') => {
+                cursor.skip(2);
+                break (JsonState::Initial, TokenKind::Comment);
+            }
+            ('\0', _) => break (JsonState::BlockCommentTail, TokenKind::Comment),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+fn number(cursor: &mut Cursor) -> TokenKind {
+    cursor.skip_if(|char| char == '-');
+    cursor.skip_digits(10);
+    if cursor.peek(0) == '.' {
+        cursor.skip(1);
+        cursor.skip_digits(10);
+    }
+    if (cursor.peek(0) == 'e' || cursor.peek(0) == 'E') && !cursor.skip_exponent() {
+        return TokenKind::Unknown;
+    }
+    TokenKind::Number
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tokenize, TokenKind::*};
+
+    #[test]
+    fn tokens() {
+        let text = "{\n  \"name\": \"make\\\"pad\", /* a\nblock comment */ \"n\": -1.5e3,\n  \"ok\": [true, null, 1e] // done\n}";
+        assert_eq!(
+            tokenize(JsonTokenizer, text),
+            [
+                vec![(Delimiter, "{")],
+                vec![
+                    (Identifier, "\"name\""),
+                    (Punctuator, ":"),
+                    (String, "\"make\\\"pad\""),
+                    (Punctuator, ","),
+                    (Comment, "/* a"),
+                ],
+                vec![
+                    (Comment, "block comment */"),
+                    (Identifier, "\"n\""),
+                    (Punctuator, ":"),
+                    (Number, "-1.5e3"),
+                    (Punctuator, ","),
+                ],
+                vec![
+                    (Identifier, "\"ok\""),
+                    (Punctuator, ":"),
+                    (Delimiter, "["),
+                    (OtherKeyword, "true"),
+                    (Punctuator, ","),
+                    (OtherKeyword, "null"),
+                    (Punctuator, ","),
+                    (Unknown, "1e"),
+                    (Delimiter, "]"),
+                    (Comment, "// done"),
+                ],
+                vec![(Delimiter, "}")],
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_string() {
+        // a string doesn't continue on the next line
+        assert_eq!(
+            tokenize(JsonTokenizer, "\"a\n1"),
+            [vec![(String, "\"a")], vec![(Number, "1")]]
+        );
+    }
+}
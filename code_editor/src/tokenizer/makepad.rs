@@ -0,0 +1,214 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{CharExt, Cursor, LanguageTokenizer},
+};
+
+/// Tokenizes the Makepad DSL, as used in `live_design!` blocks and shaders.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MakepadTokenizer;
+
+impl LanguageTokenizer for MakepadTokenizer {
+    type State = MakepadState;
+
+    fn next(&self, state: MakepadState, cursor: &mut Cursor) -> (MakepadState, TokenKind) {
+        match state {
+            MakepadState::Initial => initial(cursor),
+            MakepadState::BlockCommentTail { depth } => block_comment_tail(cursor, depth),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum MakepadState {
+    #[default]
+    Initial,
+    BlockCommentTail {
+        depth: usize,
+    },
+}
+
+fn initial(cursor: &mut Cursor) -> (MakepadState, TokenKind) {
+    let kind = match (cursor.peek(0), cursor.peek(1)) {
+        ('/', '/') => {
+            cursor.skip_to_end();
+            TokenKind::Comment
+        }
+        ('/', '*') => {
+            cursor.skip(2);
+            return block_comment_tail(cursor, 0);
+        }
+        ('"', _) => {
+            cursor.skip(1);
+            loop {
+                match (cursor.peek(0), cursor.peek(1)) {
+                    ('"', _) => {
+                        cursor.skip(1);
+                        break TokenKind::String;
+                    }
+                    ('\0', _) => break TokenKind::String,
+                    ('\\', '"') | ('\\', '\\') => cursor.skip(2),
+                    _ => cursor.skip(1),
+                }
+            }
+        }
+        // colors such as #f00, #x00ff00 and #0000
+        ('#', char) if char.is_ascii_hexdigit() || char == 'x' => {
+            cursor.skip(1);
+            cursor.skip_while(|char| char.is_ascii_alphanumeric());
+            TokenKind::Number
+        }
+        ('-', '>')
+        | (':', ':')
+        | ('=', '=')
+        | ('!', '=')
+        | ('<', '=')
+        | ('>', '=')
+        | ('&', '&')
+        | ('|', '|')
+        | ('+', '=')
+        | ('-', '=')
+        | ('*', '=')
+        | ('/', '=') => {
+            cursor.skip(2);
+            TokenKind::Punctuator
+        }
+        ('(' | ')' | '[' | ']' | '{' | '}', _) => {
+            cursor.skip(1);
+            TokenKind::Delimiter
+        }
+        (char, _) if char.is_identifier_start() => identifier_or_keyword(cursor),
+        (char, _) if char.is_ascii_digit() => number(cursor),
+        ('.', char) if char.is_ascii_digit() => number(cursor),
+        (char, _) if char.is_whitespace() => {
+            cursor.skip_while(|char| char.is_whitespace());
+            TokenKind::Whitespace
+        }
+        (char, _) if char.is_ascii_punctuation() => {
+            cursor.skip(1);
+            TokenKind::Punctuator
+        }
+        _ => {
+            cursor.skip(1);
+            TokenKind::Unknown
+        }
+    };
+    (MakepadState::Initial, kind)
+}
+
+fn block_comment_tail(cursor: &mut Cursor, mut depth: usize) -> (MakepadState, TokenKind) {
+    loop {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('/', '*') => {
+                cursor.skip(2);
+                depth += 1;
+            }
+            ('*', '/') => {
+                cursor.skip(2);
+                if depth == 0 {
+                    break (MakepadState::Initial, TokenKind::Comment);
+                }
+                depth -= 1;
+            }
+            ('\0', _) => break (MakepadState::BlockCommentTail { depth }, TokenKind::Comment),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+fn identifier_or_keyword(cursor: &mut Cursor) -> TokenKind {
+    let start = cursor.index();
+    cursor.skip_while(|char| char.is_identifier_continue());
+    let string = cursor.skipped_since(start);
+    match string {
+        "if" | "else" | "match" | "return" => TokenKind::BranchKeyword,
+        "for" | "while" | "loop" | "break" | "continue" => TokenKind::LoopKeyword,
+        "fn" | "let" | "mut" | "const" | "self" | "Self" | "use" | "import" | "pub" | "struct"
+        | "impl" | "in" | "as" | "true" | "false" | "instance" | "uniform" | "varying"
+        | "texture" | "geometry" => TokenKind::OtherKeyword,
+        "bool" | "int" | "float" | "vec2" | "vec3" | "vec4" | "ivec2" | "ivec3" | "ivec4"
+        | "bvec2" | "bvec3" | "bvec4" | "mat2" | "mat3" | "mat4" | "texture2D" => {
+            TokenKind::Typename
+        }
+        _ => {
+            let mut chars = string.chars();
+            if chars.next().unwrap().is_uppercase() {
+                match chars.next() {
+                    Some(char) if char.is_uppercase() => TokenKind::Constant,
+                    _ => TokenKind::Typename,
+                }
+            } else {
+                TokenKind::Identifier
+            }
+        }
+    }
+}
+
+fn number(cursor: &mut Cursor) -> TokenKind {
+    cursor.skip_digits(10);
+    if cursor.peek(0) == '.' && cursor.peek(1).is_ascii_digit() {
+        cursor.skip(1);
+        cursor.skip_digits(10);
+    }
+    if (cursor.peek(0) == 'e' || cursor.peek(0) == 'E') && !cursor.skip_exponent() {
+        return TokenKind::Unknown;
+    }
+    cursor.skip_suffix();
+    TokenKind::Number
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tokenize, TokenKind::*};
+
+    #[test]
+    fn tokens() {
+        let text = "DrawQuad = {{DrawQuad}} {\n    color: #f00, /* nested /* block */\nstill comment */ fn pixel(self) -> vec4 {\n        return mix(THEME_COLOR, #x00ff00, 1.5) // end\n    }\n}";
+        assert_eq!(
+            tokenize(MakepadTokenizer, text),
+            [
+                vec![
+                    (Typename, "DrawQuad"),
+                    (Punctuator, "="),
+                    (Delimiter, "{"),
+                    (Delimiter, "{"),
+                    (Typename, "DrawQuad"),
+                    (Delimiter, "}"),
+                    (Delimiter, "}"),
+                    (Delimiter, "{"),
+                ],
+                vec![
+                    (Identifier, "color"),
+                    (Punctuator, ":"),
+                    (Number, "#f00"),
+                    (Punctuator, ","),
+                    (Comment, "/* nested /* block */"),
+                ],
+                vec![
+                    (Comment, "still comment */"),
+                    (OtherKeyword, "fn"),
+                    (Identifier, "pixel"),
+                    (Delimiter, "("),
+                    (OtherKeyword, "self"),
+                    (Delimiter, ")"),
+                    (Punctuator, "->"),
+                    (Typename, "vec4"),
+                    (Delimiter, "{"),
+                ],
+                vec![
+                    (BranchKeyword, "return"),
+                    (Identifier, "mix"),
+                    (Delimiter, "("),
+                    (Constant, "THEME_COLOR"),
+                    (Punctuator, ","),
+                    (Number, "#x00ff00"),
+                    (Punctuator, ","),
+                    (Number, "1.5"),
+                    (Delimiter, ")"),
+                    (Comment, "// end"),
+                ],
+                vec![(Delimiter, "}")],
+                vec![(Delimiter, "}")],
+            ]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, LanguageTokenizer},
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MarkdownTokenizer;
+
+impl LanguageTokenizer for MarkdownTokenizer {
+    type State = MarkdownState;
+
+    fn next(&self, state: MarkdownState, cursor: &mut Cursor) -> (MarkdownState, TokenKind) {
+        match state {
+            MarkdownState::Initial => initial(cursor),
+            MarkdownState::FencedCode { fence, len } => fenced_code(cursor, fence, len),
+            MarkdownState::HtmlCommentTail => html_comment_tail(cursor),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum MarkdownState {
+    #[default]
+    Initial,
+    /// Inside a code block that started with `len` backticks or tildes.
+    FencedCode {
+        fence: char,
+        len: usize,
+    },
+    HtmlCommentTail,
+}
+
+fn initial(cursor: &mut Cursor) -> (MarkdownState, TokenKind) {
+    if cursor.peek(0).is_whitespace() {
+        cursor.skip_while(|char| char.is_whitespace());
+        return (MarkdownState::Initial, TokenKind::Whitespace);
+    }
+    if cursor.is_at_line_start() {
+        if let Some((state, kind)) = block_start(cursor) {
+            return (state, kind);
+        }
+    }
+    let rest = cursor.remaining();
+    let kind = match cursor.peek(0) {
+        '`' => {
+            let len = rest.chars().take_while(|char| *char == '`').count();
+            let fence = &rest[..len];
+            match rest[len..].find(fence) {
+                Some(end) => {
+                    skip_bytes(cursor, len + end + len);
+                    TokenKind::String
+                }
+                None => {
+                    cursor.skip(len);
+                    TokenKind::Punctuator
+                }
+            }
+        }
+        '*' | '_' => {
+            let char = cursor.peek(0);
+            let len = rest.chars().take_while(|c| *c == char).count();
+            let delimiter = &rest[..len];
+            match rest[len..].find(delimiter) {
+                Some(end) if end > 0 => {
+                    skip_bytes(cursor, len + end + len);
+                    TokenKind::OtherKeyword
+                }
+                _ => {
+                    cursor.skip(len);
+                    TokenKind::Punctuator
+                }
+            }
+        }
+        '!' | '[' => match link_len(rest) {
+            Some(len) => {
+                skip_bytes(cursor, len);
+                TokenKind::Constant
+            }
+            None => {
+                cursor.skip(1);
+                TokenKind::Punctuator
+            }
+        },
+        '<' if rest.starts_with("<!--") => {
+            cursor.skip(4);
+            return html_comment_tail(cursor);
+        }
+        '\\' => {
+            cursor.skip(2);
+            TokenKind::Identifier
+        }
+        _ => {
+            cursor.skip(1);
+            cursor.skip_while(|char| {
+                !char.is_whitespace() && !matches!(char, '`' | '*' | '!' | '[' | '<' | '\\')
+            });
+            TokenKind::Identifier
+        }
+    };
+    (MarkdownState::Initial, kind)
+}
+
+// headings, code fences, list items, block quotes and thematic breaks
+fn block_start(cursor: &mut Cursor) -> Option<(MarkdownState, TokenKind)> {
+    let rest = cursor.remaining();
+    let char = cursor.peek(0);
+    let len = rest.chars().take_while(|c| *c == char).count();
+    let after = rest[len * char.len_utf8()..].chars().next().unwrap_or(' ');
+    match char {
+        '`' | '~' if len >= 3 => {
+            cursor.skip_to_end();
+            Some((
+                MarkdownState::FencedCode { fence: char, len },
+                TokenKind::String,
+            ))
+        }
+        '#' if len <= 6 && after.is_whitespace() => {
+            cursor.skip_to_end();
+            Some((MarkdownState::Initial, TokenKind::Typename))
+        }
+        '-' | '*' | '_' if rest.trim_end().chars().all(|c| c == char || c == ' ') && len >= 3 => {
+            cursor.skip_to_end();
+            Some((MarkdownState::Initial, TokenKind::Punctuator))
+        }
+        '-' | '*' | '+' if len == 1 && after.is_whitespace() => {
+            cursor.skip(1);
+            Some((MarkdownState::Initial, TokenKind::Punctuator))
+        }
+        '>' => {
+            cursor.skip(len);
+            Some((MarkdownState::Initial, TokenKind::Punctuator))
+        }
+        '0'..='9' => {
+            let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            let mut chars = rest[digits..].chars();
+            match (chars.next(), chars.next()) {
+                (Some('.' | ')'), None) | (Some('.' | ')'), Some(' ' | '\t')) => {
+                    cursor.skip(digits + 1);
+                    Some((MarkdownState::Initial, TokenKind::Punctuator))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn fenced_code(cursor: &mut Cursor, fence: char, len: usize) -> (MarkdownState, TokenKind) {
+    let line = cursor.remaining().trim();
+    let is_closing_fence = line.len() >= len && line.chars().all(|char| char == fence);
+    cursor.skip_to_end();
+    if is_closing_fence {
+        (MarkdownState::Initial, TokenKind::String)
+    } else {
+        (MarkdownState::FencedCode { fence, len }, TokenKind::String)
+    }
+}
+
+fn html_comment_tail(cursor: &mut Cursor) -> (MarkdownState, TokenKind) {
+    match cursor.remaining().find("-->") {
+        Some(end) => {
+            skip_bytes(cursor, end + 3);
+            (MarkdownState::Initial, TokenKind::Comment)
+        }
+        None => {
+            cursor.skip_to_end();
+            (MarkdownState::HtmlCommentTail, TokenKind::Comment)
+        }
+    }
+}
+
+// the length of a [text](url) link or ![alt](url) image at the start of the string
+fn link_len(string: &str) -> Option<usize> {
+    let start = if string.starts_with("![") { 1 } else { 0 };
+    if !string[start..].starts_with('[') {
+        return None;
+    }
+    let text_end = start + string[start..].find("](")?;
+    let url_end = text_end + string[text_end..].find(')')?;
+    Some(url_end + 1)
+}
+
+fn skip_bytes(cursor: &mut Cursor, len: usize) {
+    let count = cursor.remaining()[..len].chars().count();
+    cursor.skip(count);
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tokenize, TokenKind::*};
+
+    #[test]
+    fn tokens() {
+        let text = "# Title\nSome *em* and `code` with [a link](http://x).\n- item\n1. first\n> quote\n***";
+        assert_eq!(
+            tokenize(MarkdownTokenizer, text),
+            [
+                vec![(Typename, "# Title")],
+                vec![
+                    (Identifier, "Some"),
+                    (OtherKeyword, "*em*"),
+                    (Identifier, "and"),
+                    (String, "`code`"),
+                    (Identifier, "with"),
+                    (Constant, "[a link](http://x)"),
+                    (Identifier, "."),
+                ],
+                vec![(Punctuator, "-"), (Identifier, "item")],
+                vec![(Punctuator, "1."), (Identifier, "first")],
+                vec![(Punctuator, ">"), (Identifier, "quote")],
+                vec![(Punctuator, "***")],
+            ]
+        );
+    }
+
+    #[test]
+    fn fenced_code() {
+        let text = "```rust\n/* not a comment\n\n```\n````\n```\n````\n# Title";
+        assert_eq!(
+            tokenize(MarkdownTokenizer, text),
+            [
+                vec![(String, "```rust")],
+                vec![(String, "/* not a comment")],
+                vec![],
+                vec![(String, "```")],
+                vec![(String, "````")],
+                // a shorter fence doesn't close it
+                vec![(String, "```")],
+                vec![(String, "````")],
+                vec![(Typename, "# Title")],
+            ]
+        );
+    }
+
+    #[test]
+    fn html_comments() {
+        let text = "a <!-- b\n# not a title\nc --> d";
+        assert_eq!(
+            tokenize(MarkdownTokenizer, text),
+            [
+                vec![(Identifier, "a"), (Comment, "<!-- b")],
+                vec![(Comment, "# not a title")],
+                vec![(Comment, "c -->"), (Identifier, "d")],
+            ]
+        );
+    }
+}
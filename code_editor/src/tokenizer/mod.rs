@@ -0,0 +1,9 @@
+pub mod json;
+pub mod makepad;
+pub mod markdown;
+pub mod plain_text;
+pub mod rust;
+pub mod tokenizer;
+pub mod toml;
+
+pub use self::tokenizer::*;
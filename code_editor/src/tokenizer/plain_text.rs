@@ -0,0 +1,45 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, LanguageTokenizer},
+};
+
+/// Splits lines into words and whitespace, for files in a language we don't know.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlainTextTokenizer;
+
+impl LanguageTokenizer for PlainTextTokenizer {
+    type State = ();
+
+    fn next(&self, _state: (), cursor: &mut Cursor) -> ((), TokenKind) {
+        if cursor.peek(0).is_whitespace() {
+            cursor.skip_while(|char| char.is_whitespace());
+            ((), TokenKind::Whitespace)
+        } else {
+            cursor.skip_while(|char| !char.is_whitespace());
+            ((), TokenKind::Unknown)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tokenize, TokenKind::*};
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize(PlainTextTokenizer, "echo \"a b\" # c\n\n  /* ünïcode */"),
+            [
+                vec![
+                    (Unknown, "echo"),
+                    (Unknown, "\"a"),
+                    (Unknown, "b\""),
+                    (Unknown, "#"),
+                    (Unknown, "c"),
+                ],
+                vec![],
+                vec![(Unknown, "/*"), (Unknown, "ünïcode"), (Unknown, "*/")],
+            ]
+        );
+    }
+}
@@ -0,0 +1,413 @@
+use {
+    crate::{
+        text::{Change, Text},
+        token::TokenKind,
+        tokenizer::{
+            json::JsonTokenizer, makepad::MakepadTokenizer, markdown::MarkdownTokenizer,
+            plain_text::PlainTextTokenizer, rust::RustTokenizer, toml::TomlTokenizer,
+        },
+        Token,
+    },
+    std::fmt,
+};
+
+/// A tokenizer for a single language.
+///
+/// Lines are tokenized one token at a time, starting from the state at the end of the previous
+/// line. The state at the start and end of each line is cached, so that after an edit only the
+/// lines whose start state changed need to be tokenized again.
+pub trait LanguageTokenizer: fmt::Debug {
+    type State: Clone + fmt::Debug + Default + Eq;
+
+    /// Skips over the next token on the line, which is never empty, and returns its kind
+    /// together with the state for the rest of the line.
+    fn next(&self, state: Self::State, cursor: &mut Cursor) -> (Self::State, TokenKind);
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Language {
+    #[default]
+    Rust,
+    Makepad,
+    Toml,
+    Json,
+    Markdown,
+    PlainText,
+}
+
+impl Language {
+    /// The language for a file extension. Rust files also get the Makepad DSL inside their
+    /// `live_design!` blocks.
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.to_ascii_lowercase().as_str() {
+            "rs" => Self::Rust,
+            "glsl" | "vert" | "frag" => Self::Makepad,
+            "toml" => Self::Toml,
+            "json" => Self::Json,
+            "md" | "markdown" => Self::Markdown,
+            _ => Self::PlainText,
+        }
+    }
+
+    pub fn from_path(path: &str) -> Self {
+        let file_name = path.rsplit(['/', '\\']).next().unwrap();
+        match file_name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => Self::from_extension(extension),
+            _ => Self::PlainText,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Tokenizer {
+    language: Option<Language>,
+    lines: Box<dyn LineTokenizer>,
+}
+
+impl Tokenizer {
+    pub fn new(language: Language, line_count: usize) -> Self {
+        let lines: Box<dyn LineTokenizer> = match language {
+            Language::Rust => Box::new(Lines::new(RustTokenizer, line_count)),
+            Language::Makepad => Box::new(Lines::new(MakepadTokenizer, line_count)),
+            Language::Toml => Box::new(Lines::new(TomlTokenizer, line_count)),
+            Language::Json => Box::new(Lines::new(JsonTokenizer, line_count)),
+            Language::Markdown => Box::new(Lines::new(MarkdownTokenizer, line_count)),
+            Language::PlainText => Box::new(Lines::new(PlainTextTokenizer, line_count)),
+        };
+        Self {
+            language: Some(language),
+            lines,
+        }
+    }
+
+    pub fn with_language_tokenizer<T>(tokenizer: T, line_count: usize) -> Self
+    where
+        T: LanguageTokenizer + 'static,
+    {
+        Self {
+            language: None,
+            lines: Box::new(Lines::new(tokenizer, line_count)),
+        }
+    }
+
+    /// The built-in language, or `None` for a custom tokenizer.
+    pub fn language(&self) -> Option<Language> {
+        self.language
+    }
+
+    pub fn apply_change(&mut self, change: &Change) {
+        self.lines.apply_change(change);
+    }
+
+    pub fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]) {
+        self.lines.update(text, tokens);
+    }
+}
+
+trait LineTokenizer: fmt::Debug {
+    fn apply_change(&mut self, change: &Change);
+
+    fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]);
+}
+
+#[derive(Debug)]
+struct Lines<T: LanguageTokenizer> {
+    tokenizer: T,
+    state: Vec<Option<(T::State, T::State)>>,
+}
+
+impl<T: LanguageTokenizer> Lines<T> {
+    fn new(tokenizer: T, line_count: usize) -> Self {
+        Self {
+            tokenizer,
+            state: (0..line_count).map(|_| None).collect(),
+        }
+    }
+}
+
+impl<T: LanguageTokenizer> LineTokenizer for Lines<T> {
+    fn apply_change(&mut self, change: &Change) {
+        match *change {
+            Change::Insert(point, ref text) => {
+                self.state[point.line_index] = None;
+                let line_count = text.length().line_count;
+                if line_count > 0 {
+                    let line = point.line_index + 1;
+                    self.state.splice(line..line, (0..line_count).map(|_| None));
+                }
+            }
+            Change::Delete(start, length) => {
+                self.state[start.line_index] = None;
+                let line_count = length.line_count;
+                if line_count > 0 {
+                    let start_line = start.line_index + 1;
+                    let end_line = start_line + line_count;
+                    self.state.drain(start_line..end_line);
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]) {
+        let mut state = T::State::default();
+        for line in 0..text.as_lines().len() {
+            match &self.state[line] {
+                Some((start_state, end_state)) if state == *start_state => {
+                    state = end_state.clone();
+                }
+                _ => {
+                    let start_state = state.clone();
+                    let mut new_tokens = Vec::new();
+                    let mut cursor = Cursor::new(&text.as_lines()[line]);
+                    while cursor.peek(0) != '\0' {
+                        let start = cursor.index;
+                        let (next_state, kind) = self.tokenizer.next(state, &mut cursor);
+                        state = next_state;
+                        let end = cursor.index;
+                        assert!(start < end);
+                        new_tokens.push(Token {
+                            len: end - start,
+                            kind,
+                        });
+                    }
+                    self.state[line] = Some((start_state, state.clone()));
+                    tokens[line] = new_tokens;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Cursor<'a> {
+    string: &'a str,
+    index: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(string: &'a str) -> Self {
+        Cursor { string, index: 0 }
+    }
+
+    /// The byte index of the cursor in the line.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The text that was skipped since the given byte index.
+    pub fn skipped_since(&self, start: usize) -> &'a str {
+        &self.string[start..self.index]
+    }
+
+    /// The rest of the line after the cursor.
+    pub fn remaining(&self) -> &'a str {
+        &self.string[self.index..]
+    }
+
+    /// Whether only whitespace comes before the cursor on this line.
+    pub fn is_at_line_start(&self) -> bool {
+        self.string[..self.index].trim().is_empty()
+    }
+
+    pub fn peek(&self, index: usize) -> char {
+        self.string[self.index..].chars().nth(index).unwrap_or('\0')
+    }
+
+    pub fn skip(&mut self, count: usize) {
+        self.index = self.string[self.index..]
+            .char_indices()
+            .nth(count)
+            .map_or(self.string.len(), |(index, _)| self.index + index);
+    }
+
+    pub fn skip_if<P>(&mut self, predicate: P) -> bool
+    where
+        P: FnOnce(char) -> bool,
+    {
+        if predicate(self.peek(0)) {
+            self.skip(1);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn skip_while<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(char) -> bool,
+    {
+        while self.peek(0) != '\0' && self.skip_if(&mut predicate) {}
+    }
+
+    pub fn skip_to_end(&mut self) {
+        self.index = self.string.len();
+    }
+
+    pub fn skip_exponent(&mut self) -> bool {
+        debug_assert!(self.peek(0) == 'E' || self.peek(0) == 'e');
+        self.skip(1);
+        if self.peek(0) == '+' || self.peek(0) == '-' {
+            self.skip(1);
+        }
+        self.skip_digits(10)
+    }
+
+    pub fn skip_digits(&mut self, radix: u32) -> bool {
+        let mut has_skip_digits = false;
+        loop {
+            match self.peek(0) {
+                '_' => {
+                    self.skip(1);
+                }
+                char if char.is_digit(radix) => {
+                    self.skip(1);
+                    has_skip_digits = true;
+                }
+                _ => break,
+            }
+        }
+        has_skip_digits
+    }
+
+    pub fn skip_suffix(&mut self) -> bool {
+        if self.peek(0).is_identifier_start() {
+            self.skip(1);
+            while self.skip_if(|char| char.is_identifier_continue()) {}
+            return true;
+        }
+        false
+    }
+}
+
+pub trait CharExt {
+    fn is_identifier_start(self) -> bool;
+    fn is_identifier_continue(self) -> bool;
+}
+
+impl CharExt for char {
+    fn is_identifier_start(self) -> bool {
+        match self {
+            'A'..='Z' | '_' | 'a'..='z' => true,
+            _ => false,
+        }
+    }
+
+    fn is_identifier_continue(self) -> bool {
+        match self {
+            '0'..='9' | 'A'..='Z' | '_' | 'a'..='z' => true,
+            _ => false,
+        }
+    }
+}
+
+/// Tokenizes a text from the first line, as a document would. Each line becomes its tokens with
+/// the text they cover, without the whitespace.
+#[cfg(test)]
+pub(crate) fn tokenize<T>(tokenizer: T, text: &str) -> Vec<Vec<(TokenKind, &str)>>
+where
+    T: LanguageTokenizer + 'static,
+{
+    let lines: Vec<&str> = text.split('\n').collect();
+    let text = Text::from(text);
+    let mut tokens = vec![Vec::new(); lines.len()];
+    Tokenizer::with_language_tokenizer(tokenizer, lines.len()).update(&text, &mut tokens);
+    lines_of_tokens(&lines, &tokens)
+}
+
+#[cfg(test)]
+fn lines_of_tokens<'a>(lines: &[&'a str], tokens: &[Vec<Token>]) -> Vec<Vec<(TokenKind, &'a str)>> {
+    lines
+        .iter()
+        .zip(tokens)
+        .map(|(line, tokens)| {
+            let mut start = 0;
+            tokens
+                .iter()
+                .filter_map(|token| {
+                    let string = &line[start..start + token.len];
+                    start += token.len;
+                    (token.kind != TokenKind::Whitespace).then_some((token.kind, string))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::text::{Length, Position},
+        TokenKind::*,
+    };
+
+    #[test]
+    fn language_from_path() {
+        assert_eq!(Language::from_path("src/main.rs"), Language::Rust);
+        assert_eq!(Language::from_path("Cargo.TOML"), Language::Toml);
+        assert_eq!(
+            Language::from_path("C:\\shaders\\blur.frag"),
+            Language::Makepad
+        );
+        assert_eq!(Language::from_path("docs/README.md"), Language::Markdown);
+        assert_eq!(Language::from_path("dir.json/notes"), Language::PlainText);
+        assert_eq!(Language::from_path(".gitignore"), Language::PlainText);
+        assert_eq!(Language::from_path("run.sh"), Language::PlainText);
+    }
+
+    #[test]
+    fn update_after_change() {
+        let mut text = Text::from("a\n/* b\nc */ d\ne");
+        let mut tokens = vec![Vec::new(); 4];
+        let mut tokenizer = Tokenizer::new(Language::Json, 4);
+        tokenizer.update(&text, &mut tokens);
+
+        // closing the comment on the second line ends it there
+        let change = Change::Insert(
+            Position {
+                line_index: 1,
+                byte_index: 4,
+            },
+            Text::from(" */"),
+        );
+        tokenizer.apply_change(&change);
+        text.apply_change(change);
+        tokenizer.update(&text, &mut tokens);
+        let lines: Vec<&str> = text.as_lines().iter().map(|line| line.as_str()).collect();
+        assert_eq!(
+            lines_of_tokens(&lines, &tokens),
+            [
+                vec![(Unknown, "a")],
+                vec![(Comment, "/* b */")],
+                vec![
+                    (Unknown, "c"),
+                    (Unknown, "*"),
+                    (Unknown, "/"),
+                    (Unknown, "d")
+                ],
+                vec![(Unknown, "e")],
+            ]
+        );
+
+        // joining the first two lines keeps the comment
+        let change = Change::Delete(
+            Position {
+                line_index: 0,
+                byte_index: 1,
+            },
+            Length {
+                line_count: 1,
+                byte_count: 0,
+            },
+        );
+        tokenizer.apply_change(&change);
+        text.apply_change(change);
+        tokens.remove(1);
+        tokenizer.update(&text, &mut tokens);
+        let lines: Vec<&str> = text.as_lines().iter().map(|line| line.as_str()).collect();
+        assert_eq!(
+            lines_of_tokens(&lines, &tokens)[0],
+            [(Unknown, "a"), (Comment, "/* b */")]
+        );
+    }
+}
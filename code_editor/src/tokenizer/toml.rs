@@ -0,0 +1,210 @@
+use crate::{
+    token::TokenKind,
+    tokenizer::{Cursor, LanguageTokenizer},
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TomlTokenizer;
+
+impl LanguageTokenizer for TomlTokenizer {
+    type State = TomlState;
+
+    fn next(&self, state: TomlState, cursor: &mut Cursor) -> (TomlState, TokenKind) {
+        match state {
+            TomlState::Initial => initial(cursor),
+            TomlState::MultiLineStringTail { quote } => multi_line_string_tail(cursor, quote),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum TomlState {
+    #[default]
+    Initial,
+    /// Inside a `"""` or `'''` string that continues on the next line.
+    MultiLineStringTail { quote: char },
+}
+
+fn initial(cursor: &mut Cursor) -> (TomlState, TokenKind) {
+    let kind = match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+        ('#', _, _) => {
+            cursor.skip_to_end();
+            TokenKind::Comment
+        }
+        ('"', '"', '"') | ('\'', '\'', '\'') => {
+            let quote = cursor.peek(0);
+            cursor.skip(3);
+            return multi_line_string_tail(cursor, quote);
+        }
+        ('"', _, _) | ('\'', _, _) => {
+            let quote = cursor.peek(0);
+            cursor.skip(1);
+            loop {
+                match (cursor.peek(0), cursor.peek(1)) {
+                    (char, _) if char == quote => {
+                        cursor.skip(1);
+                        break;
+                    }
+                    ('\0', _) => break,
+                    ('\\', _) if quote == '"' => cursor.skip(2),
+                    _ => cursor.skip(1),
+                }
+            }
+            TokenKind::String
+        }
+        // a [table] or [[array.of.tables]] header
+        ('[', _, _) if cursor.is_at_line_start() => {
+            cursor.skip_while(|char| char != ']' && char != '#');
+            cursor.skip_while(|char| char == ']');
+            TokenKind::Typename
+        }
+        ('[' | ']' | '{' | '}', _, _) => {
+            cursor.skip(1);
+            TokenKind::Delimiter
+        }
+        ('=' | '.' | ',', _, _) => {
+            cursor.skip(1);
+            TokenKind::Punctuator
+        }
+        ('+' | '-', char, _) if char.is_ascii_digit() => {
+            cursor.skip(1);
+            value(cursor)
+        }
+        (char, _, _) if char.is_ascii_digit() => value(cursor),
+        (char, _, _) if is_bare_key_char(char) => {
+            let start = cursor.index();
+            cursor.skip_while(is_bare_key_char);
+            match cursor.skipped_since(start) {
+                "true" | "false" | "inf" | "nan" if !is_key(cursor) => TokenKind::OtherKeyword,
+                _ => TokenKind::Identifier,
+            }
+        }
+        (char, _, _) if char.is_whitespace() => {
+            cursor.skip_while(|char| char.is_whitespace());
+            TokenKind::Whitespace
+        }
+        _ => {
+            cursor.skip(1);
+            TokenKind::Unknown
+        }
+    };
+    (TomlState::Initial, kind)
+}
+
+fn multi_line_string_tail(cursor: &mut Cursor, quote: char) -> (TomlState, TokenKind) {
+    loop {
+        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+            (char_0, char_1, char_2) if char_0 == quote && char_1 == quote && char_2 == quote => {
+                cursor.skip(3);
+                // up to two more quotes can be part of the string
+                cursor.skip_if(|char| char == quote);
+                cursor.skip_if(|char| char == quote);
+                break (TomlState::Initial, TokenKind::String);
+            }
+            ('\0', _, _) => break (TomlState::MultiLineStringTail { quote }, TokenKind::String),
+            ('\\', _, _) if quote == '"' => cursor.skip(2),
+            _ => cursor.skip(1),
+        }
+    }
+}
+
+// numbers, dates and times
+fn value(cursor: &mut Cursor) -> TokenKind {
+    cursor.skip_while(|char| {
+        char.is_ascii_alphanumeric() || matches!(char, '_' | '.' | ':' | '-' | '+')
+    });
+    if is_key(cursor) {
+        TokenKind::Identifier
+    } else {
+        TokenKind::Number
+    }
+}
+
+fn is_bare_key_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_' || char == '-'
+}
+
+// whether the word before the cursor is followed by a `=` or `.`, which makes it a key
+fn is_key(cursor: &Cursor) -> bool {
+    let mut index = 0;
+    while cursor.peek(index) == ' ' || cursor.peek(index) == '\t' {
+        index += 1;
+    }
+    cursor.peek(index) == '=' || cursor.peek(index) == '.'
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tokenizer::tokenize, TokenKind::*};
+
+    #[test]
+    fn tokens() {
+        let text = "# config\n[package]\nname = \"makepad\" # trailing\nversion.workspace = true\n[[bin]]\ndate = 1979-05-27T07:32:00Z\nliteral = 'C:\\path'\nn = -42\ninf = inf";
+        assert_eq!(
+            tokenize(TomlTokenizer, text),
+            [
+                vec![(Comment, "# config")],
+                vec![(Typename, "[package]")],
+                vec![
+                    (Identifier, "name"),
+                    (Punctuator, "="),
+                    (String, "\"makepad\""),
+                    (Comment, "# trailing"),
+                ],
+                vec![
+                    (Identifier, "version"),
+                    (Punctuator, "."),
+                    (Identifier, "workspace"),
+                    (Punctuator, "="),
+                    (OtherKeyword, "true"),
+                ],
+                vec![(Typename, "[[bin]]")],
+                vec![
+                    (Identifier, "date"),
+                    (Punctuator, "="),
+                    (Number, "1979-05-27T07:32:00Z"),
+                ],
+                vec![
+                    (Identifier, "literal"),
+                    (Punctuator, "="),
+                    (String, "'C:\\path'"),
+                ],
+                vec![(Identifier, "n"), (Punctuator, "="), (Number, "-42")],
+                vec![
+                    (Identifier, "inf"),
+                    (Punctuator, "="),
+                    (OtherKeyword, "inf")
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_line_strings() {
+        let text = "a = \"\"\"\nmulti \"line\" \\\"\"\"\ntext\"\"\"\"\" b\nc = '''\n\\'''\nd = [1, {e = 2}]";
+        assert_eq!(
+            tokenize(TomlTokenizer, text),
+            [
+                vec![(Identifier, "a"), (Punctuator, "="), (String, "\"\"\"")],
+                vec![(String, "multi \"line\" \\\"\"\"")],
+                vec![(String, "text\"\"\"\"\""), (Identifier, "b")],
+                vec![(Identifier, "c"), (Punctuator, "="), (String, "'''")],
+                // a backslash doesn't escape in a literal string
+                vec![(String, "\\'''")],
+                vec![
+                    (Identifier, "d"),
+                    (Punctuator, "="),
+                    (Delimiter, "["),
+                    (Number, "1"),
+                    (Punctuator, ","),
+                    (Delimiter, "{"),
+                    (Identifier, "e"),
+                    (Punctuator, "="),
+                    (Number, "2"),
+                    (Delimiter, "}"),
+                    (Delimiter, "]"),
+                ],
+            ]
+        );
+    }
+}
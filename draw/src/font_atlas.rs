@@ -8,6 +8,7 @@ pub use {
         io::prelude::*,
         fs::File,
         collections::HashMap,
+        ops::Range,
     },
     crate::{
         makepad_platform::*,
@@ -151,6 +152,48 @@ impl CxFontsAtlas {
     pub fn get_internal_font_atlas_texture_id(&self) -> TextureId {
         self.texture_id
     }
+    
+    // the first font of a fallback chain that has a glyph for c
+    pub fn covering_font_id(&self, mut font_ids: impl Iterator<Item = usize>, c: char) -> Option<usize> {
+        font_ids.find( | font_id | self.fonts[*font_id].as_ref().map_or(false, | cxfont | cxfont.has_glyph(c)))
+    }
+    
+    // the advance of c in ems, from the first font of the fallback chain that covers it
+    pub fn char_advance_em(&mut self, font_ids: impl Iterator<Item = usize>, c: char) -> Option<f64> {
+        let font_id = self.covering_font_id(font_ids, c)?;
+        let cxfont = self.fonts[font_id].as_mut().unwrap();
+        let glyph_id = cxfont.owned_font_face.with_ref( | face | face.glyph_index(c))?.0 as usize;
+        let units_per_em = cxfont.ttf_font.units_per_em;
+        let glyph = cxfont.get_glyph_by_id(glyph_id).ok()?;
+        Some(glyph.horizontal_metrics.advance_width / units_per_em)
+    }
+    
+    // Splits text into runs that can each be shaped with a single font of the fallback chain.
+    // Every character goes to the first font that covers it. Whitespace, joiners, variation
+    // selectors and characters no font covers stay in the run they are in, so they don't break
+    // up a run or get separated from the character they modify.
+    pub fn font_runs(&self, font_ids: impl Iterator<Item = usize> + Clone, text: &str) -> Vec<(usize, Range<usize>)> {
+        let mut runs: Vec<(usize, Range<usize>)> = Vec::new();
+        for (index, c) in text.char_indices() {
+            let end = index + c.len_utf8();
+            let font_id = match c {
+                '\u{200c}' | '\u{200d}' | '\u{fe00}'..='\u{fe0f}' => None,
+                c if c.is_whitespace() || c.is_control() => None,
+                c => self.covering_font_id(font_ids.clone(), c)
+            };
+            if let Some((run_font_id, range)) = runs.last_mut() {
+                if font_id.is_none() || font_id == Some(*run_font_id) {
+                    range.end = end;
+                    continue;
+                }
+            }
+            let font_id = font_id.or_else( || font_ids.clone().find( | font_id | self.fonts[*font_id].is_some()));
+            if let Some(font_id) = font_id {
+                runs.push((font_id, index..end));
+            }
+        }
+        runs
+    }
 }
 
 impl DrawTrapezoidVector {
@@ -327,7 +370,15 @@ pub struct CxFont {
 
 pub struct ShapeCache {
     pub keys: VecDeque<(Direction, Rc<str>)>,
-    pub glyph_ids: HashMap<(Direction, Rc<str>), Vec<usize>>,
+    pub glyphs: HashMap<(Direction, Rc<str>), Vec<ShapedGlyph>>,
+}
+
+// A glyph out of rustybuzz. cluster is the byte offset in the shaped string of the
+// first character the glyph was made from, glyphs come in visual order.
+#[derive(Clone, Copy, Debug)]
+pub struct ShapedGlyph {
+    pub glyph_id: usize,
+    pub cluster: usize,
 }
 
 impl ShapeCache {
@@ -337,13 +388,13 @@ impl ShapeCache {
     pub fn new() -> Self {
         Self {
             keys: VecDeque::new(),
-            glyph_ids: HashMap::new(),
+            glyphs: HashMap::new(),
         }
     }
 
     // If there is an entry for the given key in the cache, returns the corresponding list of
-    // glyph indices for that key. Otherwise, uses the given UnicodeBuffer and OwnedFace to
-    // compute the list of glyph indices for the key, inserts that in the cache and then returns
+    // shaped glyphs for that key. Otherwise, uses the given UnicodeBuffer and OwnedFace to
+    // compute the list of shaped glyphs for the key, inserts that in the cache and then returns
     // the corresponding list.
     //
     // This method takes a UnicodeBuffer by value, and then returns the same buffer by value. This
@@ -354,16 +405,16 @@ impl ShapeCache {
     //
     // Note that owned_font_face should be the same as the CxFont to which this cache belongs,
     // otherwise you will not get correct results.
-    pub fn get_or_compute_glyph_ids(
+    pub fn get_or_compute_glyphs(
         &mut self, 
         key: (Direction, &str),
         mut rustybuzz_buffer: UnicodeBuffer,
         owned_font_face: &crate::owned_font_face::OwnedFace
-    ) -> (&[usize], UnicodeBuffer) {
-        if !self.glyph_ids.contains_key(&key as &dyn ShapeCacheKey) {
+    ) -> (&[ShapedGlyph], UnicodeBuffer) {
+        if !self.glyphs.contains_key(&key as &dyn ShapeCacheKey) {
             if self.keys.len() == Self::MAX_SIZE {
                 for run in self.keys.drain(..Self::MAX_SIZE / 2) {
-                    self.glyph_ids.remove(&run);
+                    self.glyphs.remove(&run);
                 }
             }
 
@@ -371,14 +422,17 @@ impl ShapeCache {
             rustybuzz_buffer.set_direction(direction);
             rustybuzz_buffer.push_str(string);
             let glyph_buffer = owned_font_face.with_ref( | face | rustybuzz::shape(face, &[], rustybuzz_buffer));
-            let glyph_ids: Vec<_> = glyph_buffer.glyph_infos().iter().map( | glyph | glyph.glyph_id as usize).collect();
+            let glyphs: Vec<_> = glyph_buffer.glyph_infos().iter().map( | glyph | ShapedGlyph {
+                glyph_id: glyph.glyph_id as usize,
+                cluster: glyph.cluster as usize
+            }).collect();
             rustybuzz_buffer = glyph_buffer.clear();
 
             let owned_string: Rc<str> = string.into();
             self.keys.push_back((direction, owned_string.clone()));
-            self.glyph_ids.insert((direction, owned_string), glyph_ids);
+            self.glyphs.insert((direction, owned_string), glyphs);
         }
-        (&self.glyph_ids[&key as &dyn ShapeCacheKey], rustybuzz_buffer)
+        (&self.glyphs[&key as &dyn ShapeCacheKey], rustybuzz_buffer)
     }
 }
 
@@ -506,6 +560,10 @@ impl CxFont {
         self.atlas_pages.len() - 1
     }
 
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.owned_font_face.with_ref( | face | face.glyph_index(c).is_some())
+    }
+    
     pub fn get_glyph(&mut self, c:char)->Option<&Glyph>{
         if c < '\u{10000}' {
             Some(self.get_glyph_by_id(self.owned_font_face.with_ref(|face| face.glyph_index(c))?.0 as usize).unwrap())
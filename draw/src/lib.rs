@@ -20,9 +20,13 @@ pub use crate::{
         Axis,
         Layout,
         Walk,
+        Constraint,
         Align,
         Padding,
         Flow,
+        Grid,
+        GridTrack,
+        GridCell,
         Size,
         TurtleAlignRange,
         DeferWalk
@@ -50,7 +54,7 @@ pub use crate::{
         //draw_shape::{DrawShape, Shape, Fill},
         draw_icon::DrawIcon,
         draw_quad::DrawQuad,
-        draw_text::DrawText,
+        draw_text::{DrawText, TextSpan, TextSpanRect, TextStyle},
         draw_color::DrawColor,
     },
     geometry::{
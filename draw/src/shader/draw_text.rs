@@ -1,8 +1,10 @@
 use {
+    std::ops::Range,
+    unicode_bidi::{BidiInfo, ParagraphInfo},
     crate::{
         makepad_platform::*,
         turtle::{Walk, Size, Align},
-        font_atlas::{CxFontsAtlasTodo, CxFont, CxFontsAtlas, Font},
+        font_atlas::{CxFontsAtlasTodo, CxFontsAtlas, Font},
         draw_list_2d::ManyInstances,
         geometry::GeometryQuad2D,
         cx_2d::Cx2d
@@ -114,6 +116,8 @@ live_design!{
 #[live_ignore]
 pub struct TextStyle {
     #[live()] pub font: Font,
+    // tried in order for characters the font has no glyph for
+    #[live] pub font_fallbacks: Vec<Font>,
     #[live(9.0)] pub font_size: f64,
     #[live(1.0)] pub brightness: f32,
     #[live(0.6)] pub curve: f32,
@@ -122,6 +126,13 @@ pub struct TextStyle {
     #[live(1.3)] pub height_factor: f64,
 }
 
+impl TextStyle {
+    // the font followed by its fallbacks, in lookup order
+    pub fn font_ids(&self) -> impl Iterator<Item = usize> + Clone + '_ {
+        std::iter::once(&self.font).chain(self.font_fallbacks.iter()).filter_map( | font | font.font_id)
+    }
+}
+
 #[derive(Clone, Live, LiveHook)]
 #[live_ignore]
 pub enum TextWrap {
@@ -130,6 +141,61 @@ pub enum TextWrap {
     Line
 }
 
+// A run of text in a rich text block. The style fields are None to use the DrawText's
+// own style, a span with a link id can be found back through hit testing.
+#[derive(Clone, Default)]
+pub struct TextSpan {
+    pub text: String,
+    pub font: Option<Font>,
+    pub font_size: Option<f64>,
+    pub color: Option<Vec4>,
+    pub underline: bool,
+    pub link: Option<LiveId>,
+}
+
+impl TextSpan {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+    
+    pub fn with_font(self, font: Font) -> Self {Self {font: Some(font), ..self}}
+    pub fn with_font_size(self, font_size: f64) -> Self {Self {font_size: Some(font_size), ..self}}
+    pub fn with_color(self, color: Vec4) -> Self {Self {color: Some(color), ..self}}
+    pub fn with_underline(self) -> Self {Self {underline: true, ..self}}
+    pub fn with_link(self, link: LiveId) -> Self {Self {link: Some(link), ..self}}
+}
+
+// Where a piece of a span was drawn. A span that wraps has one for every line it is on.
+#[derive(Clone, Debug)]
+pub struct TextSpanRect {
+    pub span: usize,
+    pub rect: Rect,
+    pub baseline: f64,
+}
+
+impl TextSpanRect {
+    // the index of the span at pos
+    pub fn find(rects: &[TextSpanRect], pos: DVec2) -> Option<usize> {
+        rects.iter().find( | rect | rect.rect.contains(pos)).map( | rect | rect.span)
+    }
+}
+
+struct SpanWord {
+    span: usize,
+    range: Range<usize>,
+    x: f64,
+    width: f64,
+}
+
+struct SpanLine {
+    words: Vec<SpanWord>,
+    width: f64,
+    font_size: f64,
+}
+
 struct WordIterator<'a> {
     char_iter: Option<std::str::CharIndices<'a >>,
     eval_width: f64,
@@ -138,7 +204,7 @@ struct WordIterator<'a> {
     last_is_whitespace: bool,
     last_char: char,
     last_index: usize,
-    font_size_total: f64,
+    font_size_em: f64,
 }
 
 struct WordIteratorItem {
@@ -149,7 +215,7 @@ struct WordIteratorItem {
 }
 
 impl<'a> WordIterator<'a> {
-    fn new(char_iter: std::str::CharIndices<'a>, eval_width: f64, font_size_total: f64) -> Self {
+    fn new(char_iter: std::str::CharIndices<'a>, eval_width: f64, font_size_em: f64) -> Self {
         Self {
             eval_width,
             char_iter: Some(char_iter),
@@ -158,10 +224,10 @@ impl<'a> WordIterator<'a> {
             word_start: 0,
             last_char: '\0',
             last_index: 0,
-            font_size_total
+            font_size_em
         }
     }
-    fn next_word(&mut self, font: &mut CxFont) -> Option<WordIteratorItem> {
+    fn next_word(&mut self, fonts_atlas: &mut CxFontsAtlas, text_style: &TextStyle) -> Option<WordIteratorItem> {
         if let Some(char_iter) = &mut self.char_iter {
             while let Some((i, c)) = char_iter.next() {
                 self.last_index = i;
@@ -173,8 +239,8 @@ impl<'a> WordIterator<'a> {
                     with_newline: false
                 };
                 
-                let adv = if let Some(glyph) = font.get_glyph(c) {
-                    glyph.horizontal_metrics.advance_width * self.font_size_total
+                let adv = if let Some(advance) = fonts_atlas.char_advance_em(text_style.font_ids(), c) {
+                    advance * self.font_size_em
                 }else {0.0};
                 
                 if c == '\r' {
@@ -228,6 +294,40 @@ pub enum Overflow {
     #[live] None
 }*/
 
+// Tracks the character index of a byte offset in a text. Glyphs are visited in visual order,
+// which stays close to logical order, so moving from the last offset only counts the
+// characters in between instead of everything before the offset.
+#[derive(Default)]
+struct CharCursor {
+    byte_index: usize,
+    char_index: usize,
+}
+
+impl CharCursor {
+    fn starting_at(char_index: usize) -> Self {
+        Self {byte_index: 0, char_index}
+    }
+    
+    fn seek(&mut self, text: &str, byte_index: usize) -> usize {
+        if byte_index >= self.byte_index {
+            self.char_index += text[self.byte_index..byte_index].chars().count();
+        }
+        else {
+            self.char_index -= text[byte_index..self.byte_index].chars().count();
+        }
+        self.byte_index = byte_index;
+        self.char_index
+    }
+}
+
+struct DrawnGlyph {
+    x: f64,
+    y: f64,
+    advance: f64,
+    char_index: usize,
+    is_rtl: bool,
+}
+
 pub struct TextGeom {
     pub eval_width: f64,
     pub eval_height: f64,
@@ -259,6 +359,9 @@ pub struct DrawText {
     #[calc] pub delta: Vec2,
     #[calc] pub font_size: f32,
     #[calc] pub advance: f32,
+    // the logical character a glyph was made from, and if it runs right to left
+    #[calc] pub char_index: f32,
+    #[calc] pub char_rtl: f32,
 }
 
 impl LiveHook for DrawText {
@@ -322,46 +425,57 @@ impl DrawText {
         self.draw_vars.user_uniforms[1] = self.text_style.curve;
     }
     
+    // draws chunk as a single line, chunk should not contain newlines
     fn draw_inner(&mut self, cx: &mut Cx2d, pos: DVec2, chunk: &str, fonts_atlas: &mut CxFontsAtlas) {
+        let bidi_info = BidiInfo::new(chunk, None);
+        self.draw_bidi_range(cx, pos, &bidi_info, 0..chunk.len(), &mut CharCursor::default(), fonts_atlas);
+    }
+    
+    // Draws a range of text laid out as one line, returns where the line ends. The range is
+    // split at paragraph boundaries and trailing line breaks are left out.
+    fn draw_bidi_range(&mut self, cx: &mut Cx2d, pos: DVec2, bidi_info: &BidiInfo, range: Range<usize>, chars: &mut CharCursor, fonts_atlas: &mut CxFontsAtlas) -> f64 {
         if !self.draw_vars.can_instance()
             || pos.x.is_nan()
             || pos.y.is_nan()
             || self.text_style.font.font_id.is_none() {
-            return
+            return pos.x
         }
         //self.draw_clip = cx.turtle().draw_clip().into();
         //let in_many = self.many_instances.is_some();
         let font_id = self.text_style.font.font_id.unwrap();
         
         if fonts_atlas.fonts[font_id].is_none() {
-            return
+            return pos.x
         }
         
         //cx.debug.rect_r(Rect{pos:dvec2(1.0,2.0), size:dvec2(200.0,300.0)});
-        let mut walk_x = pos.x;
-        if walk_x.is_infinite() || walk_x.is_nan() {
-            return
+        if pos.x.is_infinite() {
+            return pos.x
         }
-        //let mut char_offset = char_offset;
         
         if !self.many_instances.is_some() {
             self.begin_many_instances_internal(cx, fonts_atlas);
         }
         
-        let cxfont = fonts_atlas.fonts[font_id].as_mut().unwrap();
+        let mut walk_x = pos.x;
+        for para in &bidi_info.paragraphs {
+            let start = para.range.start.max(range.start);
+            let mut end = para.range.end.min(range.end);
+            while end > start && bidi_info.text[start..end].ends_with(['\n', '\r']) {
+                end -= 1;
+            }
+            if start < end {
+                walk_x = self.draw_bidi_line(cx, dvec2(walk_x, pos.y), bidi_info, para, start..end, chars, fonts_atlas);
+            }
+        }
+        walk_x
+    }
+    
+    fn draw_bidi_line(&mut self, cx: &mut Cx2d, pos: DVec2, bidi_info: &BidiInfo, para: &ParagraphInfo, line: Range<usize>, chars: &mut CharCursor, fonts_atlas: &mut CxFontsAtlas) -> f64 {
+        let mut walk_x = pos.x;
         let dpi_factor = cx.current_dpi_factor();
         
-        let atlas_page_id = cxfont.get_atlas_page_id(dpi_factor, self.text_style.font_size);
-        
-        let font = &mut cxfont.ttf_font;
-        let owned_font_face = &cxfont.owned_font_face;
-        
-        let font_size_logical = self.text_style.font_size * 96.0 / (72.0 * font.units_per_em);
-        let font_size_pixels = font_size_logical * dpi_factor;
-        
-        let atlas_page = &mut cxfont.atlas_pages[atlas_page_id];
-        
-        let mi = if let Some(mi) = &mut self.many_instances {mi} else {return};
+        let mi = if let Some(mi) = &mut self.many_instances {mi} else {return walk_x};
         let zbias_step = 0.00001;
         let mut char_depth = self.draw_depth;
         
@@ -370,38 +484,53 @@ impl DrawText {
         // This relies on the UBA ("Unicode Bidirectional Algorithm")
         // (see http://www.unicode.org/reports/tr9/#Basic_Display_Algorithm),
         // as implemented by `unicode_bidi`, to slice the text into substrings
-        // that can be individually shaped, then assembled visually.
-        let bidi_info = unicode_bidi::BidiInfo::new(chunk, None);
+        // that can be individually shaped, then assembled visually. The levels
+        // come from the whole paragraph, the reordering only happens within the line.
+        let (adjusted_levels, runs) = bidi_info.visual_runs(para, line);
         
-        // NOTE(eddyb) the caller of `draw_inner` has already processed the text,
-        // such that `chunk` won't contain e.g. any `\n`.
-        if bidi_info.paragraphs.len() == 1 {
-            let runs_with_level_and_range = {
-                let para = &bidi_info.paragraphs[0];
-                // Split `chunk` into "runs" (that differ in their LTR/RTL "level").
-                let (adjusted_levels, runs) = bidi_info.visual_runs(para, para.range.clone());
-                runs.into_iter().map(move | run_range | (adjusted_levels[run_range.start], run_range))
-            };
-            
-            for (run_level, run_range) in runs_with_level_and_range {
+        for run_range in runs {
+            let run_level = adjusted_levels[run_range.start];
+            let run_text = &bidi_info.text[run_range.clone()];
+            // Each run is split again into the parts a single font of the fallback
+            // chain covers. Those are in logical order, so RTL runs draw them reversed.
+            let mut font_runs = fonts_atlas.font_runs(self.text_style.font_ids(), run_text);
+            if run_level.is_rtl() {
+                font_runs.reverse();
+            }
+            for (font_id, font_range) in font_runs {
+                let font_start = run_range.start + font_range.start;
+                let font_text = &run_text[font_range];
+                
+                let cxfont = fonts_atlas.fonts[font_id].as_mut().unwrap();
+                let atlas_page_id = cxfont.get_atlas_page_id(dpi_factor, self.text_style.font_size);
+                
+                let font = &mut cxfont.ttf_font;
+                let owned_font_face = &cxfont.owned_font_face;
+                
+                let font_size_logical = self.text_style.font_size * 96.0 / (72.0 * font.units_per_em);
+                let font_size_pixels = font_size_logical * dpi_factor;
+                
+                let atlas_page = &mut cxfont.atlas_pages[atlas_page_id];
+                
                 // FIXME(eddyb) UBA/`unicode_bidi` only offers a LTR/RTL distinction,
                 // even if `rustybuzz` has vertical `Direction`s as well.
-                let (glyph_ids, new_rustybuzz_buffer) = cxfont
+                let (glyphs, new_rustybuzz_buffer) = cxfont
                     .shape_cache
-                    .get_or_compute_glyph_ids(
+                    .get_or_compute_glyphs(
                     (
                             if run_level.is_rtl() {
                                 rustybuzz::Direction::RightToLeft
                             } else {
                                 rustybuzz::Direction::LeftToRight
                             },
-                            &bidi_info.text[run_range]
+                            font_text
                         ),
                         rustybuzz_buffer,
                         owned_font_face
                     );
                 rustybuzz_buffer = new_rustybuzz_buffer;
-                for &glyph_id in glyph_ids {
+                for shaped in glyphs {
+                    let glyph_id = shaped.glyph_id;
                     let glyph = owned_font_face.with_ref(|face| font.get_glyph_by_id(face, glyph_id).unwrap());
                     
                     let advance = glyph.horizontal_metrics.advance_width * font_size_logical * self.font_scale;
@@ -470,14 +599,17 @@ impl DrawText {
                     self.delta.y = delta_y as f32;
                     self.font_size = self.text_style.font_size as f32;
                     self.advance = advance as f32; //char_offset as f32;
+                    self.char_index = chars.seek(bidi_info.text, font_start + shaped.cluster) as f32;
+                    self.char_rtl = if run_level.is_rtl() {1.0} else {0.0};
                     char_depth += zbias_step;
                     mi.instances.extend_from_slice(self.draw_vars.as_slice());
                     walk_x += advance;
                 }
             }
         }
-        
+        walk_x
     }
+    
     pub fn compute_geom(&self, cx: &Cx2d, walk: Walk, text: &str) -> Option<TextGeom> {
         self.compute_geom_inner(cx, walk, text, &mut *cx.fonts_atlas_rc.0.borrow_mut())
     }
@@ -490,15 +622,16 @@ impl DrawText {
             return None
         }
         
-        let font_size_logical = self.text_style.font_size * 96.0 / (72.0 * fonts_atlas.fonts[font_id].as_ref().unwrap().ttf_font.units_per_em);
+        // advances come from whichever font of the fallback chain covers a character, so measure in ems
+        let font_size_em = self.text_style.font_size * 96.0 / 72.0;
         let line_height = self.text_style.font_size * self.text_style.height_factor * self.font_scale;
-        let eval_width = cx.turtle().eval_width(walk.width, walk.margin, cx.turtle().layout().flow);
-        let eval_height = cx.turtle().eval_height(walk.height, walk.margin, cx.turtle().layout().flow);
+        let eval_size = cx.turtle().eval_walk_size(&walk);
+        let (eval_width, eval_height) = (eval_size.x, eval_size.y);
         
         match if walk.width.is_fit() {&TextWrap::Line}else {&self.wrap} {
             TextWrap::Ellipsis => {
-                let ellip_width = if let Some(glyph) = fonts_atlas.fonts[font_id].as_mut().unwrap().get_glyph('.') {
-                    glyph.horizontal_metrics.advance_width * font_size_logical * self.font_scale
+                let ellip_width = if let Some(advance) = fonts_atlas.char_advance_em(self.text_style.font_ids(), '.') {
+                    advance * font_size_em * self.font_scale
                 }
                 else {
                     0.0
@@ -511,8 +644,8 @@ impl DrawText {
                     if measured_width + ellip_width * 3.0 < eval_width {
                         ellip_pt = Some((i, measured_width, 3));
                     }
-                    if let Some(glyph) = fonts_atlas.fonts[font_id].as_mut().unwrap().get_glyph(c) {
-                        let adv = glyph.horizontal_metrics.advance_width * font_size_logical * self.font_scale;
+                    if let Some(advance) = fonts_atlas.char_advance_em(self.text_style.font_ids(), c) {
+                        let adv = advance * font_size_em * self.font_scale;
                         // ok so now what.
                         if measured_width + adv >= eval_width { // we have to drop back to ellip_pt
                             // if we don't have an ellip_pt, set it to 0
@@ -548,8 +681,8 @@ impl DrawText {
                 let mut measured_width = 0.0;
                 let mut measured_height = line_height;
                 
-                let mut iter = WordIterator::new(text.char_indices(), eval_width, font_size_logical * self.font_scale);
-                while let Some(word) = iter.next_word(fonts_atlas.fonts[font_id].as_mut().unwrap()) {
+                let mut iter = WordIterator::new(text.char_indices(), eval_width, font_size_em * self.font_scale);
+                while let Some(word) = iter.next_word(fonts_atlas, &self.text_style) {
                     if measured_width + word.width >= eval_width {
                         measured_height += line_height * self.text_style.line_spacing;
                         measured_width = word.width;
@@ -581,8 +714,8 @@ impl DrawText {
                     if c == '\n' {
                         measured_height += line_height * self.text_style.line_spacing;
                     }
-                    if let Some(glyph) = fonts_atlas.fonts[font_id].as_mut().unwrap().get_glyph(c) {
-                        let adv = glyph.horizontal_metrics.advance_width * font_size_logical * self.font_scale;
+                    if let Some(advance) = fonts_atlas.char_advance_em(self.text_style.font_ids(), c) {
+                        let adv = advance * font_size_em * self.font_scale;
                         measured_width += adv;
                     }
                     if measured_width > max_width {
@@ -602,10 +735,10 @@ impl DrawText {
     
     
     pub fn draw_walk(&mut self, cx: &mut Cx2d, walk: Walk, align: Align, text: &str) {
-        let font_id = if let Some(font_id) = self.text_style.font.font_id{font_id}else{
+        if self.text_style.font.font_id.is_none() {
             //log!("Draw text without font");
             return
-        };
+        }
         let fonts_atlas_rc = cx.fonts_atlas_rc.clone();
         let mut fonts_atlas = fonts_atlas_rc.0.borrow_mut();
         let fonts_atlas = &mut*fonts_atlas;
@@ -634,6 +767,8 @@ impl DrawText {
                         let rect = cx.walk_turtle(Walk {
                             abs_pos: walk.abs_pos,
                             margin: walk.margin,
+                            cell: walk.cell,
+                            constraint: walk.constraint,
                             width: Size::Fixed(geom.eval_width),
                             height: Size::Fixed(height)
                         });
@@ -645,6 +780,8 @@ impl DrawText {
                         let rect = cx.walk_turtle(Walk {
                             abs_pos: walk.abs_pos,
                             margin: walk.margin,
+                            cell: walk.cell,
+                            constraint: walk.constraint,
                             width: Size::Fixed(geom.eval_width),
                             height: Size::Fixed(
                                 if walk.height.is_fit() {
@@ -659,31 +796,52 @@ impl DrawText {
                     }
                 }
                 TextWrap::Word => {
-                    let font_size_logical = self.text_style.font_size * 96.0 / (72.0 * fonts_atlas.fonts[font_id].as_ref().unwrap().ttf_font.units_per_em);
+                    let font_size_em = self.text_style.font_size * 96.0 / 72.0;
                     let line_height = self.text_style.font_size * self.text_style.height_factor * self.font_scale;
                     
                     let rect = cx.walk_turtle(Walk {
                         abs_pos: walk.abs_pos,
                         margin: walk.margin,
+                        cell: walk.cell,
+                        constraint: walk.constraint,
                         width: Size::Fixed(geom.eval_width),
                         height: Size::Fixed(geom.measured_height)
                     });
-                    let mut pos = dvec2(0.0, 0.0);
                     
-                    let mut iter = WordIterator::new(text.char_indices(), geom.eval_width, font_size_logical * self.font_scale);
-                    while let Some(word) = iter.next_word(fonts_atlas.fonts[font_id].as_mut().unwrap()) {
-                        if pos.x + word.width >= geom.eval_width {
-                            pos.y += line_height * self.text_style.line_spacing;
-                            pos.x = 0.0;
+                    // collect the lines first, bidi reordering works on whole lines
+                    let mut lines = Vec::new();
+                    let mut line = 0..0;
+                    let mut line_width = 0.0;
+                    let mut iter = WordIterator::new(text.char_indices(), geom.eval_width, font_size_em * self.font_scale);
+                    while let Some(word) = iter.next_word(fonts_atlas, &self.text_style) {
+                        if line_width + word.width >= geom.eval_width {
+                            lines.push((line, line_width));
+                            line = word.start..word.start;
+                            line_width = 0.0;
                         }
-                        self.draw_inner(cx, rect.pos + pos, &text[word.start..word.end], fonts_atlas);
-                        pos.x += word.width;
+                        line.end = word.end;
+                        line_width += word.width;
                         
                         if word.with_newline {
-                            pos.y += line_height * self.text_style.line_spacing;
-                            pos.x = 0.0;
+                            lines.push((line, line_width));
+                            line = word.end..word.end;
+                            line_width = 0.0;
                         }
                     }
+                    lines.push((line, line_width));
+                    
+                    let bidi_info = BidiInfo::new(text, None);
+                    let mut chars = CharCursor::default();
+                    let mut pos = dvec2(0.0, 0.0);
+                    for (line, line_width) in lines {
+                        // lines of right to left paragraphs start at the right edge
+                        let is_rtl = line.end > line.start && bidi_info.paragraphs.iter().any( | para | {
+                            para.range.contains(&(line.end - 1)) && para.level.is_rtl()
+                        });
+                        pos.x = if is_rtl {(geom.eval_width - line_width).max(0.0)} else {0.0};
+                        self.draw_bidi_range(cx, rect.pos + pos, &bidi_info, line, &mut chars, fonts_atlas);
+                        pos.y += line_height * self.text_style.line_spacing;
+                    }
                 }
                 TextWrap::Line => {
                     let line_height = self.text_style.font_size * self.text_style.height_factor * self.font_scale;
@@ -691,13 +849,20 @@ impl DrawText {
                     let rect = cx.walk_turtle(Walk {
                         abs_pos: walk.abs_pos,
                         margin: walk.margin,
+                        cell: walk.cell,
+                        constraint: walk.constraint,
                         width: Size::Fixed(geom.measured_width),
                         height: Size::Fixed(height)
                     });
                     // lets do our y alignment
+                    let bidi_info = BidiInfo::new(text, None);
+                    let mut chars = CharCursor::default();
                     let mut ypos = 0.0;
+                    let mut start = 0;
                     for line in text.split('\n') {
-                        self.draw_inner(cx, rect.pos + dvec2(0.0, y_align + ypos), line, fonts_atlas);
+                        let range = start..start + line.len();
+                        start = range.end + 1;
+                        self.draw_bidi_range(cx, rect.pos + dvec2(0.0, y_align + ypos), &bidi_info, range, &mut chars, fonts_atlas);
                         ypos += line_height * self.text_style.line_spacing;
                     }
                     
@@ -709,84 +874,250 @@ impl DrawText {
         }
     }
     
-    pub fn closest_offset(&self, cx: &Cx, pos: DVec2) -> Option<usize> {
+    // reads back where every glyph was drawn, in visual order
+    fn read_glyphs(&self, cx: &Cx) -> Option<Vec<DrawnGlyph >> {
         let area = &self.draw_vars.area;
         
         if !area.is_valid(cx) {
             return None
         }
-
-        let line_spacing = self.get_line_spacing();
+        
         let rect_pos = area.get_read_ref(cx, live_id!(rect_pos), ShaderTy::Vec2).unwrap();
         let delta = area.get_read_ref(cx, live_id!(delta), ShaderTy::Vec2).unwrap();
         let advance = area.get_read_ref(cx, live_id!(advance), ShaderTy::Float).unwrap();
-
-        let mut last_y = None;
-        for i in 0..rect_pos.repeat {
+        let char_index = area.get_read_ref(cx, live_id!(char_index), ShaderTy::Float).unwrap();
+        let char_rtl = area.get_read_ref(cx, live_id!(char_rtl), ShaderTy::Float).unwrap();
+        
+        Some((0..rect_pos.repeat).map( | i | {
             let index = rect_pos.stride * i;
-            let x = rect_pos.buffer[index + 0] as f64 - delta.buffer[index + 0] as f64;
-            let y = rect_pos.buffer[index + 1] - delta.buffer[index + 1];
-            if last_y.is_none() {last_y = Some(y)}
-            let advance = advance.buffer[index + 0] as f64;
-            if i > 0 && y > last_y.unwrap() && pos.y < last_y.unwrap() as f64 + line_spacing as f64 {
-                return Some(i - 1)
+            DrawnGlyph {
+                x: (rect_pos.buffer[index + 0] - delta.buffer[index + 0]) as f64,
+                y: (rect_pos.buffer[index + 1] - delta.buffer[index + 1]) as f64,
+                advance: advance.buffer[index + 0] as f64,
+                char_index: char_index.buffer[index + 0] as usize,
+                is_rtl: char_rtl.buffer[index + 0] > 0.5
             }
-            if pos.x < x + advance * 0.5 && pos.y < y as f64 + line_spacing as f64 {
-                return Some(i)
+        }).collect())
+    }
+    
+    // the glyph of the character at index, or the closest one before it if that character
+    // has no glyph of its own (a line break, the inside of a ligature, the end of the text)
+    fn glyph_at_index(glyphs: &[DrawnGlyph], index: usize) -> Option<&DrawnGlyph> {
+        let mut found: Option<&DrawnGlyph> = None;
+        for glyph in glyphs {
+            if glyph.char_index <= index && found.map_or(true, | found | glyph.char_index > found.char_index) {
+                found = Some(glyph);
             }
-            last_y = Some(y)
         }
-        return Some(rect_pos.repeat);
-        
+        found
     }
     
-    pub fn get_selection_rects(&self, cx: &Cx, start: usize, end: usize, shift: DVec2, pad: DVec2) -> Vec<Rect> {
-        let area = &self.draw_vars.area;
+    // Lays out spans as one word wrapped block through the turtle and draws it. Returns
+    // where every span ended up, for hit testing and decorations like underlines. Character
+    // offsets, as used by closest_offset and the selection, count through the spans in order.
+    pub fn draw_spans_walk(&mut self, cx: &mut Cx2d, walk: Walk, align: Align, spans: &[TextSpan]) -> Vec<TextSpanRect> {
+        let mut rects = Vec::new();
+        if self.text_style.font.font_id.is_none() {
+            return rects
+        }
+        let fonts_atlas_rc = cx.fonts_atlas_rc.clone();
+        let mut fonts_atlas = fonts_atlas_rc.0.borrow_mut();
+        let fonts_atlas = &mut*fonts_atlas;
         
-        if !area.is_valid(cx) {
-            return Vec::new();
+        let eval_size = cx.turtle().eval_walk_size(&walk);
+        let (eval_width, eval_height) = (eval_size.x, eval_size.y);
+        let wrap_width = if walk.width.is_fit() {f64::INFINITY} else {eval_width};
+        
+        let base_font = self.text_style.font.clone();
+        let base_font_size = self.text_style.font_size;
+        let base_color = self.color;
+        
+        // break the spans into words and flow those into lines
+        let new_line = || SpanLine {words: Vec::new(), width: 0.0, font_size: 0.0};
+        let mut lines = vec![new_line()];
+        for (span_index, span) in spans.iter().enumerate() {
+            if span.text.len() == 0 {
+                continue;
+            }
+            self.apply_span_style(span, &base_font, base_font_size);
+            let font_size = self.text_style.font_size;
+            let font_size_em = font_size * 96.0 / 72.0 * self.font_scale;
+            let mut iter = WordIterator::new(span.text.char_indices(), wrap_width, font_size_em);
+            while let Some(word) = iter.next_word(fonts_atlas, &self.text_style) {
+                if lines.last().unwrap().width > 0.0 && lines.last().unwrap().width + word.width >= wrap_width {
+                    lines.push(new_line());
+                }
+                let line = lines.last_mut().unwrap();
+                line.font_size = line.font_size.max(font_size);
+                match line.words.last_mut() {
+                    Some(last) if last.span == span_index && last.range.end == word.start => {
+                        last.range.end = word.end;
+                        last.width += word.width;
+                    }
+                    _ => line.words.push(SpanWord {
+                        span: span_index,
+                        range: word.start..word.end,
+                        x: line.width,
+                        width: word.width
+                    })
+                }
+                line.width += word.width;
+                if word.with_newline {
+                    lines.push(new_line());
+                }
+            }
         }
         
-        let rect_pos = area.get_read_ref(cx, live_id!(rect_pos), ShaderTy::Vec2).unwrap();
-        let delta = area.get_read_ref(cx, live_id!(delta), ShaderTy::Vec2).unwrap();
-        let advance = area.get_read_ref(cx, live_id!(advance), ShaderTy::Float).unwrap();
+        let height_factor = self.text_style.height_factor * self.font_scale;
+        let line_height = | font_size: f64 | {
+            if font_size == 0.0 {base_font_size * height_factor} else {font_size * height_factor}
+        };
+        let mut line_ys = Vec::with_capacity(lines.len());
+        let mut measured_height = 0.0;
+        let mut measured_width: f64 = 0.0;
+        for (index, line) in lines.iter().enumerate() {
+            if index > 0 {
+                measured_height += line_height(lines[index - 1].font_size) * self.text_style.line_spacing;
+            }
+            line_ys.push(measured_height);
+            measured_width = measured_width.max(line.width);
+        }
+        measured_height += line_height(lines.last().unwrap().font_size);
         
-        if rect_pos.repeat == 0 || start >= rect_pos.repeat{
-            return Vec::new();
+        let width = if walk.width.is_fit() {measured_width} else {eval_width};
+        let height = if walk.height.is_fit() {measured_height} else {eval_height};
+        let rect = cx.walk_turtle(Walk {
+            abs_pos: walk.abs_pos,
+            margin: walk.margin,
+            cell: walk.cell,
+            constraint: walk.constraint,
+            width: Size::Fixed(width),
+            height: Size::Fixed(height)
+        });
+        let y_align = (height - measured_height) * align.y;
+        
+        let bidi_infos: Vec<_> = spans.iter().map( | span | BidiInfo::new(&span.text, None)).collect();
+        // character indices run on across spans, so offsets address the text as a whole
+        let mut char_start = 0;
+        let mut span_chars: Vec<_> = spans.iter().map( | span | {
+            let chars = CharCursor::starting_at(char_start);
+            char_start += span.text.chars().count();
+            chars
+        }).collect();
+        for (line, line_y) in lines.iter().zip(line_ys) {
+            let x_align = (width - line.width) * align.x;
+            for word in &line.words {
+                let span = &spans[word.span];
+                self.apply_span_style(span, &base_font, base_font_size);
+                self.color = span.color.unwrap_or(base_color);
+                // differently sized spans share a baseline
+                let font_size = self.text_style.font_size;
+                let y_drop = (line.font_size - font_size) * self.text_style.top_drop * self.font_scale;
+                let pos = rect.pos + dvec2(x_align + word.x, y_align + line_y);
+                self.draw_bidi_range(cx, pos + dvec2(0.0, y_drop), &bidi_infos[word.span], word.range.clone(), &mut span_chars[word.span], fonts_atlas);
+                rects.push(TextSpanRect {
+                    span: word.span,
+                    rect: Rect {pos, size: dvec2(word.width, line_height(line.font_size))},
+                    baseline: pos.y + line.font_size * self.text_style.top_drop * self.font_scale
+                });
+            }
         }
-        // alright now we go and walk from start to end and collect our selection rects
         
-        let index = start * rect_pos.stride;
-        let start_x = rect_pos.buffer[index + 0] - delta.buffer[index + 0]; // + advance.buffer[index + 0] * pos;
-        let start_y = rect_pos.buffer[index + 1] - delta.buffer[index + 1];
+        self.text_style.font = base_font;
+        self.text_style.font_size = base_font_size;
+        self.color = base_color;
+        if self.many_instances.is_some() {
+            self.end_many_instances(cx)
+        }
+        rects
+    }
+    
+    fn apply_span_style(&mut self, span: &TextSpan, base_font: &Font, base_font_size: f64) {
+        self.text_style.font = span.font.clone().unwrap_or_else( || base_font.clone());
+        self.text_style.font_size = span.font_size.unwrap_or(base_font_size);
+    }
+    
+    // the character offset closest to pos. glyphs of right to left runs have their
+    // logical start on the right, so which half of a glyph is hit decides the offset.
+    pub fn closest_offset(&self, cx: &Cx, pos: DVec2) -> Option<usize> {
+        let glyphs = self.read_glyphs(cx)?;
+        if glyphs.len() == 0 {
+            return Some(0)
+        }
         let line_spacing = self.get_line_spacing();
-        let mut last_y = start_y;
-        let mut min_x = start_x;
-        let mut last_x = start_x;
-        let mut last_advance = advance.buffer[index + 0];
-        let mut out = Vec::new();
-        for index in start..end {
-            if index >= rect_pos.repeat{
-                break;
+        let same_line = | a: f64, b: f64 | (a - b).abs() < line_spacing * 0.5;
+        
+        let mut line_y = glyphs[0].y;
+        for glyph in &glyphs {
+            if glyph.y <= pos.y {
+                line_y = glyph.y;
             }
-            let index = index * rect_pos.stride;
-            let end_x = rect_pos.buffer[index + 0] - delta.buffer[index + 0];
-            let end_y = rect_pos.buffer[index + 1] - delta.buffer[index + 1];
-            last_advance = advance.buffer[index + 0];
-            if end_y > last_y { // emit rect
+        }
+        
+        let mut first: Option<&DrawnGlyph> = None;
+        let mut last: Option<&DrawnGlyph> = None;
+        let mut min_x = f64::INFINITY;
+        for glyph in glyphs.iter().filter( | glyph | same_line(glyph.y, line_y)) {
+            if pos.x >= glyph.x && pos.x < glyph.x + glyph.advance {
+                let left_half = pos.x < glyph.x + glyph.advance * 0.5;
+                return Some(if left_half != glyph.is_rtl {glyph.char_index} else {glyph.char_index + 1})
+            }
+            if first.map_or(true, | first | glyph.char_index < first.char_index) {
+                first = Some(glyph);
+            }
+            if last.map_or(true, | last | glyph.char_index > last.char_index) {
+                last = Some(glyph);
+            }
+            min_x = min_x.min(glyph.x);
+        }
+        // outside of the line it goes to the line's start or end, which side is which
+        // depends on the direction the line starts in
+        let (first, last) = (first.unwrap(), last.unwrap());
+        let line_start = first.char_index;
+        // a wrapped line ends in front of its last character, past that is the next line
+        let line_end = if glyphs.iter().any( | glyph | glyph.char_index == last.char_index + 1) {
+            last.char_index
+        }
+        else {
+            last.char_index + 1
+        };
+        Some(if (pos.x < min_x) != first.is_rtl {line_start} else {line_end})
+    }
+    
+    // the rects covering the characters start..end. with mixed directions a selection
+    // can be split up visually, so a line can have more than one rect.
+    pub fn get_selection_rects(&self, cx: &Cx, start: usize, end: usize, shift: DVec2, pad: DVec2) -> Vec<Rect> {
+        let glyphs = if let Some(glyphs) = self.read_glyphs(cx) {glyphs} else {
+            return Vec::new();
+        };
+        let line_spacing = self.get_line_spacing();
+        
+        let mut out = Vec::new();
+        // min_x, max_x and y of the rect being built
+        let mut span: Option<(f64, f64, f64)> = None;
+        for glyph in &glyphs {
+            let selected = glyph.char_index >= start && glyph.char_index < end;
+            if let Some((min_x, max_x, y)) = span {
+                if selected && (glyph.y - y).abs() < line_spacing * 0.5 {
+                    span = Some((min_x.min(glyph.x), max_x.max(glyph.x + glyph.advance), y));
+                    continue;
+                }
                 out.push(Rect {
-                    pos: dvec2(min_x as f64, last_y as f64) + shift,
-                    size: dvec2((last_x - min_x + last_advance) as f64, line_spacing) + pad
+                    pos: dvec2(min_x, y) + shift,
+                    size: dvec2(max_x - min_x, line_spacing) + pad
                 });
-                min_x = end_x;
-                last_y = end_y;
+                span = None;
+            }
+            if selected {
+                span = Some((glyph.x, glyph.x + glyph.advance, glyph.y));
             }
-            last_x = end_x;
         }
-        out.push(Rect {
-            pos: dvec2(min_x as f64, last_y as f64) + shift,
-            size: dvec2((last_x - min_x + last_advance) as f64, line_spacing) + pad
-        });
+        if let Some((min_x, max_x, y)) = span {
+            out.push(Rect {
+                pos: dvec2(min_x, y) + shift,
+                size: dvec2(max_x - min_x, line_spacing) + pad
+            });
+        }
         out
     }
     
@@ -800,33 +1131,33 @@ impl DrawText {
         rect_pos.repeat
     }
     
+    // the position of the cursor in front of the character at index, pos moves it
+    // that fraction of the character's advance in its reading direction
     pub fn get_cursor_pos(&self, cx: &Cx, pos: f32, index: usize) -> Option<DVec2> {
-        let area = &self.draw_vars.area;
-        
-        if !area.is_valid(cx) {
-            return None
-        }
-        
-        let rect_pos = area.get_read_ref(cx, live_id!(rect_pos), ShaderTy::Vec2).unwrap();
-        let delta = area.get_read_ref(cx, live_id!(delta), ShaderTy::Vec2).unwrap();
-        let advance = area.get_read_ref(cx, live_id!(advance), ShaderTy::Float).unwrap();
-        
-        if rect_pos.repeat == 0 {
-            return None
-        }
-        if index >= rect_pos.repeat {
-            // lets get the last one and advance
-            let index = (rect_pos.repeat - 1) * rect_pos.stride;
-            let x = rect_pos.buffer[index + 0] - delta.buffer[index + 0] + advance.buffer[index + 0];
-            let y = rect_pos.buffer[index + 1] - delta.buffer[index + 1];
-            Some(dvec2(x as f64, y as f64))
+        let glyphs = self.read_glyphs(cx)?;
+        if let Some(glyph) = Self::glyph_at_index(&glyphs, index) {
+            let pos = pos as f64;
+            let x = if glyph.char_index == index {
+                if glyph.is_rtl {glyph.x + glyph.advance * (1.0 - pos)} else {glyph.x + glyph.advance * pos}
+            }
+            else { // behind the character before it
+                if glyph.is_rtl {glyph.x} else {glyph.x + glyph.advance}
+            };
+            return Some(dvec2(x, glyph.y))
         }
-        else {
-            let index = index * rect_pos.stride;
-            let x = rect_pos.buffer[index + 0] - delta.buffer[index + 0] + advance.buffer[index + 0] * pos;
-            let y = rect_pos.buffer[index + 1] - delta.buffer[index + 1];
-            Some(dvec2(x as f64, y as f64))
+        // index is in front of the first glyph
+        let first = glyphs.iter().min_by_key( | glyph | glyph.char_index)?;
+        Some(dvec2(if first.is_rtl {first.x + first.advance} else {first.x}, first.y))
+    }
+    
+    // if the character at index, or the one before it, runs right to left
+    pub fn get_is_rtl(&self, cx: &Cx, index: usize) -> bool {
+        if let Some(glyphs) = self.read_glyphs(cx) {
+            if let Some(glyph) = Self::glyph_at_index(&glyphs, index) {
+                return glyph.is_rtl
+            }
         }
+        false
     }
     
     pub fn get_line_spacing(&self) -> f64 {
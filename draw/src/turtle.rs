@@ -38,6 +38,24 @@ pub struct Walk {
     #[live] pub margin: Margin,
     #[live] pub width: Size,
     #[live] pub height: Size,
+    #[live] pub cell: GridCell,
+    #[live] pub constraint: Constraint,
+}
+
+// Limits on the size a Walk evaluates to, applied after Fill and Fit are resolved.
+// With an aspect ratio the height follows the width, unless the width is Fit.
+#[derive(Copy, Clone, Default, Debug, Live, LiveHook)]
+#[live_ignore]
+pub struct Constraint {
+    #[live] pub min_width: Option<f64>,
+    #[live] pub max_width: Option<f64>,
+    #[live] pub min_height: Option<f64>,
+    #[live] pub max_height: Option<f64>,
+    // width divided by height
+    #[live] pub aspect_ratio: Option<f64>,
+    // when the children of a Right or Down turtle run past its end, the ones that shrink give
+    // up the overflow in proportion to shrink times their size, but not past their minimum
+    #[live] pub shrink: f64,
 }
 
 #[derive(Clone, Copy, Default, Debug, Live, LiveHook)]
@@ -74,26 +92,68 @@ impl Default for Axis {
 pub enum Flow {
     #[pick] Right,
     Down,
-    Overlay
+    Overlay,
+    // like Right, but starts a new row when a child doesn't fit the width anymore
+    RightWrap,
+    // places children in the cells of a Grid, set with Turtle::set_grid
+    Grid
+}
+
+#[derive(Copy, Clone, Debug, Live, LiveHook)]
+#[live_ignore]
+pub enum GridTrack {
+    #[pick] Fit,
+    #[live(100.0)] Fixed(f64),
+    // shares the space the other tracks leave by weight
+    #[live(1.0)] Weight(f64)
+}
+
+// Column and row templates of a grid. Tracks past the end of a template are Fit,
+// without columns the grid is one weighted column.
+#[derive(Clone, Default, Debug, Live, LiveHook)]
+#[live_ignore]
+pub struct Grid {
+    #[live] pub columns: Vec<GridTrack>,
+    #[live] pub rows: Vec<GridTrack>,
 }
 
+// Where a child goes in a grid. Unset columns and rows are filled in by placing
+// the child in the next free cell, left to right and top to bottom.
+#[derive(Copy, Clone, Debug, Live, LiveHook)]
+#[live_ignore]
+pub struct GridCell {
+    #[live] pub column: Option<usize>,
+    #[live] pub row: Option<usize>,
+    #[live(1usize)] pub column_span: usize,
+    #[live(1usize)] pub row_span: usize,
+}
+
+// Fill takes a part of the space its siblings leave, in proportion to its weight among
+// the other fills. A bare Fill weighs 1
 #[derive(Copy, Clone, Debug, Live)]
 #[live_ignore]
 pub enum Size {
-    #[pick] Fill,
+    #[pick(1.0)] Fill(f64),
     #[live(200.0)] Fixed(f64),
     Fit,
-    All
+    All,
 }
 
 #[derive(Clone, Debug)]
 pub enum DeferWalk{
     Unresolved{
         defer_index: usize,
+        // the direction the fill was deferred in
+        axis: Axis,
         margin: Margin,
         other_axis: Size,
+        constraint: Constraint,
         pos: DVec2
     },
+    Cell{
+        item: usize,
+        walk: Walk
+    },
     Resolved(Walk)
 }
 
@@ -112,6 +172,9 @@ pub struct TurtleWalk {
     align_start: usize,
     defer_index: usize,
     rect: Rect,
+    shrink: f64,
+    // the size it can't shrink past, its margin included
+    min_size: DVec2,
 }
 
 #[derive(Clone, Default, Debug)]
@@ -128,7 +191,42 @@ pub struct Turtle {
     height: f64,
     width_used: f64,
     height_used: f64,
-    guard_area: Area
+    guard_area: Area,
+    grid: Option<TurtleGrid>,
+    defer_fills: Vec<DeferFill>,
+}
+
+// the weight and bounds along the flow of a deferred fill, or of how much a child shrinks
+#[derive(Clone, Copy, Debug)]
+struct DeferFill {
+    weight: f64,
+    min: f64,
+    max: f64,
+}
+
+#[derive(Clone, Debug)]
+struct GridItem {
+    column: usize,
+    row: usize,
+    column_span: usize,
+    row_span: usize,
+    // where it was drawn, before the final track sizes were known
+    pos: DVec2,
+    size: DVec2,
+    walk_index: Option<usize>,
+    deferred: bool,
+}
+
+#[derive(Clone, Default, Debug)]
+struct TurtleGrid {
+    columns: Vec<GridTrack>,
+    rows: Vec<GridTrack>,
+    // the largest content of a single track seen so far
+    column_fit: Vec<f64>,
+    row_fit: Vec<f64>,
+    items: Vec<GridItem>,
+    next_cell: (usize, usize),
+    pending: Option<usize>,
 }
 
 impl<'a> Cx2d<'a> {
@@ -151,10 +249,7 @@ impl<'a> Cx2d<'a> {
         let turtle = self.turtles.last_mut().unwrap();
         let defer_index = turtle.defer_count;
         let pos = turtle.pos;
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk_size(&walk);
         let margin_size = walk.margin.size();
         match turtle.layout.flow {
             Flow::Right if walk.width.is_fill() => {
@@ -163,10 +258,13 @@ impl<'a> Cx2d<'a> {
                 turtle.update_width_max(turtle.pos.x, 0.0);
                 turtle.update_height_max(turtle.pos.y, size.y + margin_size.y);
                 turtle.defer_count += 1;
+                turtle.defer_fills.push(DeferFill::new(walk.width, walk.constraint.min_width, walk.constraint.max_width));
                 Some(DeferWalk::Unresolved{
                     defer_index,
+                    axis: Axis::Horizontal,
                     margin: walk.margin,
                     other_axis: walk.height,
+                    constraint: walk.constraint,
                     pos: pos + spacing
                 })
             },
@@ -176,13 +274,22 @@ impl<'a> Cx2d<'a> {
                 turtle.update_width_max(turtle.pos.x, size.x + margin_size.x);
                 turtle.update_height_max(turtle.pos.y, 0.0);
                 turtle.defer_count += 1;
+                turtle.defer_fills.push(DeferFill::new(walk.height, walk.constraint.min_height, walk.constraint.max_height));
                 Some(DeferWalk::Unresolved {
                     defer_index,
+                    axis: Axis::Vertical,
                     margin: walk.margin,
                     other_axis: walk.width,
+                    constraint: walk.constraint,
                     pos: pos + spacing
                 })
             },
+            Flow::Grid if walk.width.is_fill() || walk.height.is_fill() => {
+                let grid = turtle.grid.as_mut().unwrap();
+                let item = grid.place(walk.cell);
+                grid.items[item].deferred = true;
+                Some(DeferWalk::Cell {item, walk})
+            },
             _ => {
                 None
             }
@@ -209,6 +316,8 @@ impl<'a> Cx2d<'a> {
             width_used: layout.padding.left,
             height_used: layout.padding.top,
             guard_area: Area::Empty,
+            grid: TurtleGrid::for_layout(&layout),
+            defer_fills: Vec::new(),
         };
         self.turtles.push(turtle);
     }
@@ -238,14 +347,19 @@ impl<'a> Cx2d<'a> {
     }
     
     pub fn begin_turtle_with_guard(&mut self, walk: Walk, layout: Layout, guard_area: Area) {
+        if let Some(parent) = self.turtles.last_mut() {
+            if walk.abs_pos.is_none() {
+                parent.place_in_grid(walk.cell);
+            }
+        }
         let (origin, width, height, draw_clip) = if let Some(parent) = self.turtles.last() {
             
             let o = walk.margin.left_top() + if let Some(pos) = walk.abs_pos {pos} else {
                 parent.pos + parent.child_spacing(self.turtle_walks.len()) 
             };
             
-            let w = parent.eval_width(walk.width, walk.margin, parent.layout.flow);
-            let h = parent.eval_height(walk.height, walk.margin, parent.layout.flow);
+            let size = parent.eval_walk_size(&walk);
+            let (w, h) = (size.x, size.y);
             
             // figure out new clipping rect
             let (x0, x1) = if layout.clip_x {
@@ -271,8 +385,8 @@ impl<'a> Cx2d<'a> {
         }
         else {
             let o = DVec2 {x: walk.margin.left, y: walk.margin.top};
-            let w = walk.width.fixed_or_nan();
-            let h = walk.height.fixed_or_nan();
+            let size = walk.constraint.constrain(dvec2(walk.width.fixed_or_nan(), walk.height.fixed_or_nan()));
+            let (w, h) = (size.x, size.y);
             
             (o, w, h, (dvec2(o.x, o.y), dvec2(o.x + w, o.y + h)))
         };
@@ -294,6 +408,8 @@ impl<'a> Cx2d<'a> {
             width_used: layout.padding.left,
             height_used: layout.padding.top,
             guard_area,
+            grid: TurtleGrid::for_layout(&layout),
+            defer_fills: Vec::new(),
         };
         
         self.turtles.push(turtle);
@@ -313,34 +429,43 @@ impl<'a> Cx2d<'a> {
     }
     
     pub fn end_turtle_with_guard(&mut self, guard_area: Area) -> Rect {
-        let turtle = self.turtles.pop().unwrap();
+        let mut turtle = self.turtles.pop().unwrap();
         if guard_area != turtle.guard_area {
             panic!("End turtle guard area misaligned!, begin/end pair not matched begin {:?} end {:?}", turtle.guard_area, guard_area)
         }
         
-        // computed height
-        let w = if turtle.width.is_nan() {
-            Size::Fixed(turtle.width_used + turtle.layout.padding.right - turtle.layout.scroll.x)
+        // a grid is as large as its tracks
+        if let Some(grid) = &turtle.grid {
+            let (columns, rows) = turtle.grid_track_sizes(grid);
+            turtle.width_used = turtle.layout.padding.left + track_span(&columns, 0, columns.len(), turtle.layout.spacing);
+            turtle.height_used = turtle.layout.padding.top + track_span(&rows, 0, rows.len(), turtle.layout.spacing);
         }
-        else {
-            Size::Fixed(turtle.width)
-        };
         
-        let h = if turtle.height.is_nan() {
-            Size::Fixed(turtle.height_used + turtle.layout.padding.bottom - turtle.layout.scroll.y)
+        self.shrink_turtle_walks(&mut turtle);
+        
+        // computed height
+        let used = dvec2(
+            if turtle.width.is_nan() {turtle.width_used + turtle.layout.padding.right - turtle.layout.scroll.x} else {turtle.width},
+            if turtle.height.is_nan() {turtle.height_used + turtle.layout.padding.bottom - turtle.layout.scroll.y} else {turtle.height}
+        );
+        let size = turtle.walk.constraint.constrain(used);
+        // a fit turtle that got resized by its constraint aligns in its final size
+        if turtle.width.is_nan() && size.x != used.x {
+            turtle.width = size.x;
         }
-        else {
-            Size::Fixed(turtle.height)
-        };
+        if turtle.height.is_nan() && size.y != used.y {
+            turtle.height = size.y;
+        }
+        let w = Size::Fixed(size.x);
+        let h = Size::Fixed(size.y);
         
         match turtle.layout.flow {
             Flow::Right => {
                 if turtle.defer_count > 0 {
-                    let left = turtle.width_left();
-                    let part = left / turtle.defer_count as f64;
+                    let parts = turtle.defer_parts();
                     for i in turtle.turtle_walks_start..self.turtle_walks.len() {
                         let walk = &self.turtle_walks[i];
-                        let shift_x = walk.defer_index as f64 * part;
+                        let shift_x = parts[0..walk.defer_index].iter().sum::<f64>();
                         let shift_y = turtle.layout.align.y * (turtle.padded_height_or_used() - walk.rect.size.y);
                         let align_start = walk.align_start;
                         let align_end = self.get_turtle_walk_align_end(i);
@@ -360,12 +485,11 @@ impl<'a> Cx2d<'a> {
             },
             Flow::Down => {
                 if turtle.defer_count > 0 {
-                    let left = turtle.height_left();
-                    let part = left / turtle.defer_count as f64;
+                    let parts = turtle.defer_parts();
                     for i in turtle.turtle_walks_start..self.turtle_walks.len() {
                         let walk = &self.turtle_walks[i];
                         let shift_x = turtle.layout.align.x * (turtle.padded_width_or_used() - walk.rect.size.x);
-                        let shift_y = walk.defer_index as f64 * part;
+                        let shift_y = parts[0..walk.defer_index].iter().sum::<f64>();
                         let align_start = walk.align_start;
                         let align_end = self.get_turtle_walk_align_end(i);
                         self.move_align_list(shift_x, shift_y, align_start, align_end, false, turtle.shift);
@@ -392,6 +516,44 @@ impl<'a> Cx2d<'a> {
                     self.move_align_list(shift_x, shift_y, align_start, align_end, false, turtle.shift);
                 }
             }
+            Flow::RightWrap => {
+                // rows are aligned on their own, children within the height of their row
+                let left = turtle.origin.x + turtle.layout.padding.left;
+                let mut row_start = turtle.turtle_walks_start;
+                while row_start < self.turtle_walks.len() {
+                    let row_y = self.turtle_walks[row_start].rect.pos.y;
+                    let mut row_end = row_start;
+                    let mut row_height: f64 = 0.0;
+                    while row_end < self.turtle_walks.len() && self.turtle_walks[row_end].rect.pos.y == row_y {
+                        row_height = row_height.max(self.turtle_walks[row_end].rect.size.y);
+                        row_end += 1;
+                    }
+                    let row_width = self.turtle_walks[row_end - 1].rect.pos.x + self.turtle_walks[row_end - 1].rect.size.x - left;
+                    for i in row_start..row_end {
+                        let walk = &self.turtle_walks[i];
+                        let shift_x = turtle.layout.align.x * (turtle.padded_width_or_used() - row_width);
+                        let shift_y = turtle.layout.align.y * (row_height - walk.rect.size.y);
+                        let align_start = walk.align_start;
+                        let align_end = self.get_turtle_walk_align_end(i);
+                        self.move_align_list(shift_x, shift_y, align_start, align_end, false, turtle.shift);
+                    }
+                    row_start = row_end;
+                }
+            }
+            Flow::Grid => {
+                // move everything from where it was drawn into its final cell
+                let grid = turtle.grid.as_ref().unwrap();
+                let (columns, rows) = turtle.grid_track_sizes(grid);
+                for item in &grid.items {
+                    if let Some(i) = item.walk_index {
+                        let cell = turtle.grid_cell_rect(item, &columns, &rows);
+                        let shift = cell.pos + (cell.size - item.size) * dvec2(turtle.layout.align.x, turtle.layout.align.y) - item.pos;
+                        let align_start = self.turtle_walks[i].align_start;
+                        let align_end = self.get_turtle_walk_align_end(i);
+                        self.move_align_list(shift.x, shift.y, align_start, align_end, false, turtle.shift);
+                    }
+                }
+            }
         }
 
         self.turtle_walks.truncate(turtle.turtle_walks_start);
@@ -406,6 +568,83 @@ impl<'a> Cx2d<'a> {
         rect
     }
     
+    // Children of a sized Right or Down turtle that run past its end give up the overflow
+    // between the ones that shrink, in proportion to shrink times size like flex-shrink.
+    // They are drawn already, so they get cut off at their new size instead of laid out
+    // again, and everything after them moves back.
+    fn shrink_turtle_walks(&mut self, turtle: &mut Turtle) {
+        let horizontal = match turtle.layout.flow {
+            Flow::Right => true,
+            Flow::Down => false,
+            _ => return
+        };
+        let along = | v: DVec2 | if horizontal {v.x} else {v.y};
+        let overflow = if horizontal {
+            turtle.width_used + turtle.layout.padding.right - turtle.width
+        }
+        else {
+            turtle.height_used + turtle.layout.padding.bottom - turtle.height
+        };
+        // a fit turtle doesn't overflow
+        if overflow.is_nan() || overflow <= 0.0 {
+            return
+        }
+        let walks = turtle.turtle_walks_start..self.turtle_walks.len();
+        let fills: Vec<DeferFill> = self.turtle_walks[walks.clone()].iter().map( | walk | {
+            let size = along(walk.rect.size);
+            // only a child with a turtle of its own can be cut off
+            let has_turtle = matches!(self.align_list.get(walk.align_start), Some(AlignEntry::BeginTurtle(..)));
+            if walk.shrink > 0.0 && size.is_finite() && has_turtle {
+                DeferFill {weight: walk.shrink * size, min: 0.0, max: (size - along(walk.min_size)).max(0.0)}
+            }
+            else {
+                DeferFill {weight: 0.0, min: 0.0, max: 0.0}
+            }
+        }).collect();
+        let cuts = share_space(overflow, &fills);
+        // where every cut child ended, the children from there on move back by its cut
+        let cut_ends: Vec<(f64, f64)> = walks.clone().zip(&cuts).filter( | (_, cut) | **cut > 0.0).map( | (i, cut) | {
+            let rect = self.turtle_walks[i].rect;
+            (along(rect.pos + rect.size), *cut)
+        }).collect();
+        if cut_ends.is_empty() {
+            return
+        }
+        for (i, cut) in walks.zip(cuts) {
+            let start = along(self.turtle_walks[i].rect.pos);
+            let shift: f64 = cut_ends.iter().filter( | (end, _) | *end <= start + 0.001).map( | (_, cut) | cut).sum();
+            let delta = if horizontal {dvec2(-shift, 0.0)} else {dvec2(0.0, -shift)};
+            let cut_size = if horizontal {dvec2(cut, 0.0)} else {dvec2(0.0, cut)};
+            let align_start = self.turtle_walks[i].align_start;
+            let align_end = self.get_turtle_walk_align_end(i);
+            self.move_align_list(delta.x, delta.y, align_start, align_end, false, dvec2(0.0, 0.0));
+            let walk = &mut self.turtle_walks[i];
+            walk.rect.pos += delta;
+            if cut > 0.0 {
+                walk.rect.size -= cut_size;
+                let end = walk.rect.pos + walk.rect.size;
+                if let Some(AlignEntry::BeginTurtle(clip0, clip1)) = self.align_list.get_mut(align_start) {
+                    // without a clip of its own it gets one along the flow
+                    if horizontal {
+                        clip0.x = if clip0.x.is_nan() {walk.rect.pos.x} else {clip0.x};
+                        clip1.x = if clip1.x.is_nan() {end.x} else {clip1.x - cut};
+                    }
+                    else {
+                        clip0.y = if clip0.y.is_nan() {walk.rect.pos.y} else {clip0.y};
+                        clip1.y = if clip1.y.is_nan() {end.y} else {clip1.y - cut};
+                    }
+                }
+            }
+        }
+        let total: f64 = cut_ends.iter().map( | (_, cut) | cut).sum();
+        if horizontal {
+            turtle.width_used -= total;
+        }
+        else {
+            turtle.height_used -= total;
+        }
+    }
+    
     pub fn walk_turtle(&mut self, walk: Walk) -> Rect {
         self.walk_turtle_move(walk, self.align_list.len())
     }
@@ -442,28 +681,31 @@ impl<'a> Cx2d<'a> {
      fn walk_turtle_move(&mut self, walk: Walk, align_start: usize) -> Rect {
         
         let turtle = self.turtles.last_mut().unwrap();
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        if walk.abs_pos.is_none() {
+            turtle.place_in_grid(walk.cell);
+        }
+        let size = turtle.eval_walk_size(&walk);
         
         if let Some(pos) = walk.abs_pos {
             self.turtle_walks.push(TurtleWalk {
                 align_start,
                 defer_index: 0,
-                rect: Rect {pos, size: size + walk.margin.size()}
+                rect: Rect {pos, size: size + walk.margin.size()},
+                shrink: 0.0,
+                min_size: dvec2(0.0, 0.0)
             });
             
             match turtle.layout.flow {
-                Flow::Right=>turtle.update_height_max(pos.y, size.y + walk.margin.size().y),
+                Flow::Right | Flow::RightWrap=>turtle.update_height_max(pos.y, size.y + walk.margin.size().y),
                 Flow::Down=>turtle.update_width_max(pos.x, size.x + walk.margin.size().x),
                 _=>()
             }
             Rect {pos: pos + walk.margin.left_top(), size}
         }
         else {
-            let spacing = turtle.child_spacing(self.turtle_walks.len());
-            let pos = turtle.pos;
+            let mut spacing = turtle.child_spacing(self.turtle_walks.len());
+            let mut pos = turtle.pos;
+            let mut wrap_shift = None;
         
             let margin_size = walk.margin.size();
             match turtle.layout.flow {
@@ -493,13 +735,48 @@ impl<'a> Cx2d<'a> {
                     turtle.update_width_max(turtle.pos.x, size.x);
                     turtle.update_height_max(turtle.pos.y,size.y);
                 }
+                Flow::RightWrap => {
+                    let row_start = turtle.origin.x + turtle.layout.padding.left;
+                    let row_end = turtle.origin.x + turtle.width - turtle.layout.padding.right;
+                    if pos.x > row_start && pos.x + spacing.x + size.x + margin_size.x > row_end {
+                        let new_pos = dvec2(row_start, turtle.origin.y + turtle.height_used + turtle.layout.spacing);
+                        // a turtle that ends here has already been drawn at the old position
+                        wrap_shift = Some(new_pos - (pos + spacing));
+                        pos = new_pos;
+                        spacing = dvec2(0.0, 0.0);
+                    }
+                    turtle.pos = dvec2(pos.x + size.x + margin_size.x + spacing.x, pos.y);
+                    turtle.update_width_max(turtle.pos.x, 0.0);
+                    turtle.update_height_max(turtle.pos.y, size.y + margin_size.y);
+                }
+                Flow::Grid => {
+                    let grid = turtle.grid.as_mut().unwrap();
+                    let item = &mut grid.items[grid.pending.take().unwrap()];
+                    item.size = size + margin_size;
+                    item.walk_index = Some(self.turtle_walks.len());
+                    pos = item.pos;
+                    if item.column_span == 1 {
+                        grid.column_fit[item.column] = grid.column_fit[item.column].max(item.size.x);
+                    }
+                    if item.row_span == 1 {
+                        grid.row_fit[item.row] = grid.row_fit[item.row].max(item.size.y);
+                    }
+                }
             };
             
             self.turtle_walks.push(TurtleWalk {
                 align_start,
                 defer_index: turtle.defer_count,
-                rect: Rect {pos, size: size + margin_size}
+                rect: Rect {pos, size: size + margin_size},
+                shrink: walk.constraint.shrink,
+                min_size: dvec2(
+                    walk.constraint.min_width.unwrap_or(0.0),
+                    walk.constraint.min_height.unwrap_or(0.0)
+                ) + margin_size
             });
+            if let Some(shift) = wrap_shift {
+                self.move_align_list(shift.x, shift.y, align_start, self.align_list.len(), false, dvec2(0.0, 0.0));
+            }
             Rect {pos: pos + walk.margin.left_top() + spacing, size}
         }
     }
@@ -509,10 +786,7 @@ impl<'a> Cx2d<'a> {
             return Rect::default()
         }
         let turtle = self.turtles.last().unwrap();
-        let size = dvec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk_size(&walk);
         
         if let Some(pos) = walk.abs_pos {
             Rect {pos: pos + walk.margin.left_top(), size}
@@ -718,16 +992,74 @@ impl Turtle {
         self.pos = pos
     }
     
+    // sets the tracks of a Flow::Grid turtle, call it before drawing any children
+    pub fn set_grid(&mut self, grid: &Grid) {
+        if let Some(turtle_grid) = &mut self.grid {
+            turtle_grid.columns = grid.columns.clone();
+            turtle_grid.rows = grid.rows.clone();
+            turtle_grid.column_fit.resize(turtle_grid.column_count(), 0.0);
+        }
+    }
+    
+    // reserves the cell the next child is drawn in and moves there
+    fn place_in_grid(&mut self, cell: GridCell) {
+        let Some(grid) = &mut self.grid else {return};
+        if grid.pending.is_some() {
+            return
+        }
+        let item = grid.place(cell);
+        grid.pending = Some(item);
+        let grid = self.grid.as_ref().unwrap();
+        let (columns, rows) = self.grid_track_sizes(grid);
+        let pos = self.grid_cell_rect(&grid.items[item], &columns, &rows).pos;
+        self.grid.as_mut().unwrap().items[item].pos = pos;
+        self.pos = pos;
+    }
+    
+    fn pending_cell_size(&self) -> DVec2 {
+        if let Some(grid) = &self.grid {
+            if let Some(item) = grid.pending {
+                let (columns, rows) = self.grid_track_sizes(grid);
+                return self.grid_cell_rect(&grid.items[item], &columns, &rows).size
+            }
+        }
+        dvec2(f64::NAN, f64::NAN)
+    }
+    
+    fn grid_track_sizes(&self, grid: &TurtleGrid) -> (Vec<f64>, Vec<f64>) {
+        let available = self.size() - self.layout.padding.size();
+        let row_count = grid.row_fit.len().max(grid.rows.len());
+        (
+            track_sizes(&grid.columns, &grid.column_fit, grid.column_count(), available.x, self.layout.spacing),
+            track_sizes(&grid.rows, &grid.row_fit, row_count, available.y, self.layout.spacing)
+        )
+    }
+    
+    fn grid_cell_rect(&self, item: &GridItem, columns: &[f64], rows: &[f64]) -> Rect {
+        let spacing = self.layout.spacing;
+        let origin = self.origin + self.layout.padding.left_top();
+        Rect {
+            pos: origin + dvec2(
+                track_span(columns, 0, item.column, spacing) + if item.column > 0 {spacing} else {0.0},
+                track_span(rows, 0, item.row, spacing) + if item.row > 0 {spacing} else {0.0}
+            ),
+            size: dvec2(
+                track_span(columns, item.column, item.column + item.column_span, spacing),
+                track_span(rows, item.row, item.row + item.row_span, spacing)
+            )
+        }
+    }
+    
     fn child_spacing(&self, walks_len: usize) -> DVec2 {
         if self.turtle_walks_start < walks_len || self.defer_count > 0 {
             match self.layout.flow {
-                Flow::Right => {
+                Flow::Right | Flow::RightWrap => {
                     dvec2(self.layout.spacing, 0.0)
                 }
                 Flow::Down => {
                     dvec2(0.0, self.layout.spacing)
                 }
-                Flow::Overlay => {
+                Flow::Overlay | Flow::Grid => {
                     dvec2(0.0, 0.0)
                 }
             }
@@ -761,15 +1093,45 @@ impl Turtle {
         self.layout.scroll
     }
     
+    // the size of a walk in this turtle, with its constraint applied
+    pub fn eval_walk_size(&self, walk: &Walk) -> DVec2 {
+        let size = dvec2(
+            self.eval_width(walk.width, walk.margin, self.layout.flow),
+            self.eval_height(walk.height, walk.margin, self.layout.flow)
+        );
+        walk.constraint.constrain(size)
+    }
+    
+    // splits the space left over among the deferred fills by weight, as far as their bounds allow
+    fn defer_parts(&self) -> Vec<f64> {
+        let left = match self.layout.flow {
+            Flow::Down => self.height_left(),
+            _ => self.width_left()
+        };
+        // without a finite size there is nothing to share, the fills fit their content
+        if !left.is_finite() {
+            return vec![f64::NAN; self.defer_fills.len()]
+        }
+        share_space(left, &self.defer_fills)
+    }
+    
     pub fn eval_width(&self, width: Size, margin: Margin, flow: Flow) -> f64 {
         return match width {
             Size::Fit => std::f64::NAN,
             Size::Fixed(v) => max_zero_keep_nan(v),
-            Size::Fill => {
+            Size::Fill(_) => {
                 match flow {
                     Flow::Right => {
                         max_zero_keep_nan(self.width_left() - margin.width())
                     },
+                    Flow::RightWrap => {
+                        // the rest of the current row, after the spacing to the previous child
+                        let spacing = if self.pos.x > self.origin.x + self.layout.padding.left {self.layout.spacing} else {0.0};
+                        max_zero_keep_nan(self.origin.x + self.width - self.layout.padding.right - self.pos.x - spacing - margin.width())
+                    }
+                    Flow::Grid => {
+                        max_zero_keep_nan(self.pending_cell_size().x - margin.width())
+                    }
                     Flow::Down | Flow::Overlay => {
                         let r = max_zero_keep_nan(self.width - self.layout.padding.width() - margin.width());
                         if r.is_nan() {
@@ -787,9 +1149,12 @@ impl Turtle {
         return match height {
             Size::Fit => std::f64::NAN,
             Size::Fixed(v) => max_zero_keep_nan(v),
-            Size::Fill => {
+            Size::Fill(_) => {
                 match flow {
-                    Flow::Right | Flow::Overlay => {
+                    Flow::Grid => {
+                        max_zero_keep_nan(self.pending_cell_size().y - margin.height())
+                    }
+                    Flow::Right | Flow::RightWrap | Flow::Overlay => {
                         let r = max_zero_keep_nan(self.height - self.layout.padding.height() - margin.height());
                         if r.is_nan() {
                             return self.height_used - margin.height() - self.layout.padding.bottom
@@ -872,35 +1237,209 @@ impl Turtle {
     }
 }
 
+impl TurtleGrid {
+    fn for_layout(layout: &Layout) -> Option<Self> {
+        if let Flow::Grid = layout.flow {
+            Some(Self {column_fit: vec![0.0], ..Self::default()})
+        }
+        else {
+            None
+        }
+    }
+    
+    fn column_count(&self) -> usize {
+        self.columns.len().max(1)
+    }
+    
+    fn is_free(&self, column: usize, row: usize, column_span: usize, row_span: usize) -> bool {
+        self.items.iter().all( | item | {
+            column >= item.column + item.column_span || item.column >= column + column_span ||
+            row >= item.row + item.row_span || item.row >= row + row_span
+        })
+    }
+    
+    fn place(&mut self, cell: GridCell) -> usize {
+        let column_count = self.column_count();
+        let column_span = cell.column_span.clamp(1, column_count);
+        let row_span = cell.row_span.max(1);
+        
+        // a deferred child comes back for the cell that was kept for it
+        if let (Some(column), Some(row)) = (cell.column, cell.row) {
+            if let Some(index) = self.items.iter().position( | item | {
+                item.deferred && item.walk_index.is_none() && item.column == column && item.row == row
+            }) {
+                return index
+            }
+        }
+        
+        let (column, row) = match (cell.column, cell.row) {
+            (Some(column), Some(row)) => (column.min(column_count - column_span), row),
+            (Some(column), None) => {
+                let column = column.min(column_count - column_span);
+                let row = (0..).find( | row | self.is_free(column, *row, column_span, row_span)).unwrap();
+                (column, row)
+            }
+            (None, row) => {
+                let (mut column, mut row) = match row {
+                    Some(row) => (0, row),
+                    None => self.next_cell
+                };
+                let asked_row = row;
+                while column + column_span > column_count || !self.is_free(column, row, column_span, row_span) {
+                    column += 1;
+                    if column + column_span > column_count {
+                        column = 0;
+                        row += 1;
+                    }
+                }
+                match cell.row {
+                    // a full row pushes the child down to the next row with room
+                    Some(_) if row != asked_row => {
+                        error!("Grid row {} has no room for a child spanning {} columns, placed it in row {}", asked_row, column_span, row);
+                    }
+                    Some(_) => (),
+                    None => self.next_cell = (column + column_span, row)
+                }
+                (column, row)
+            }
+        };
+        if self.row_fit.len() < row + row_span {
+            self.row_fit.resize(row + row_span, 0.0);
+        }
+        self.items.push(GridItem {
+            column,
+            row,
+            column_span,
+            row_span,
+            pos: dvec2(0.0, 0.0),
+            size: dvec2(0.0, 0.0),
+            walk_index: None,
+            deferred: false
+        });
+        self.items.len() - 1
+    }
+}
+
+fn track_sizes(tracks: &[GridTrack], fit: &[f64], count: usize, available: f64, spacing: f64) -> Vec<f64> {
+    let track = | i: usize | match tracks.get(i) {
+        Some(track) => *track,
+        None if tracks.is_empty() && count == 1 => GridTrack::Weight(1.0),
+        None => GridTrack::Fit
+    };
+    let fit = | i: usize | fit.get(i).copied().unwrap_or(0.0);
+    let mut used = spacing * count.saturating_sub(1) as f64;
+    let mut weights = 0.0;
+    for i in 0..count {
+        match track(i) {
+            GridTrack::Fit => used += fit(i),
+            GridTrack::Fixed(v) => used += v,
+            GridTrack::Weight(w) => weights += w
+        }
+    }
+    let left = (available - used).max(0.0);
+    (0..count).map( | i | match track(i) {
+        GridTrack::Fit => fit(i),
+        GridTrack::Fixed(v) => v,
+        // without a size to share weighted tracks fit their content
+        GridTrack::Weight(_) if available.is_nan() => fit(i),
+        GridTrack::Weight(w) => left * w / weights
+    }).collect()
+}
+
+// Splits space by weight, as far as the bounds of the parts allow. Every round fixes at
+// least one part: the ones on the side the bounds push the total to, or all of them when
+// no bound is hit. The space has to be finite.
+fn share_space(space: f64, fills: &[DeferFill]) -> Vec<f64> {
+    let mut parts = vec![f64::NAN; fills.len()];
+    let mut left = space;
+    while parts.iter().any( | part | part.is_nan()) {
+        let weights: f64 = fills.iter().zip(&parts).filter( | (_, part) | part.is_nan()).map( | (fill, _) | fill.weight).sum();
+        let share = | fill: &DeferFill | if weights > 0.0 {left * (fill.weight / weights)} else {0.0};
+        let mut violation = 0.0;
+        for (fill, part) in fills.iter().zip(&parts) {
+            if part.is_nan() {
+                violation += share(fill).clamp(fill.min, fill.max) - share(fill);
+            }
+        }
+        let mut fixed = Vec::new();
+        for (i, fill) in fills.iter().enumerate() {
+            let part = share(fill);
+            if parts[i].is_nan() && (violation == 0.0 || violation > 0.0 && part < fill.min || violation < 0.0 && part > fill.max) {
+                fixed.push((i, part.clamp(fill.min, fill.max)));
+            }
+        }
+        for (i, part) in fixed {
+            parts[i] = part;
+            left -= part;
+        }
+    }
+    parts
+}
+
+// the size of tracks start..end with the spacing between them
+fn track_span(tracks: &[f64], start: usize, end: usize, spacing: f64) -> f64 {
+    let end = end.min(tracks.len());
+    if start >= end {
+        return 0.0
+    }
+    tracks[start..end].iter().sum::<f64>() + spacing * (end - start - 1) as f64
+}
+
 impl DeferWalk {
     
     pub fn resolve(&mut self, cx: &Cx2d) -> Walk {
         match self{
             Self::Resolved(walk)=>{*walk},
-            Self::Unresolved{pos, defer_index, margin, other_axis}=>{
+            Self::Unresolved{pos, defer_index, axis, margin, other_axis, constraint}=>{
+                // only Right and Down turtles defer, the axis says which one it was. Resolved
+                // in a turtle without deferred fills the walk fits its content instead
                 let turtle = cx.turtles.last().unwrap();
-                let walk = match turtle.layout.flow {
-                    Flow::Right => {
-                        let left = turtle.width_left();
-                        let part = left / turtle.defer_count as f64;
+                let parts = turtle.defer_parts();
+                let part = parts.get(*defer_index).copied().unwrap_or(f64::NAN);
+                let offset = parts.iter().take(*defer_index).sum::<f64>();
+                let offset = if offset.is_nan() {0.0} else {offset};
+                let walk = match axis {
+                    Axis::Horizontal => {
                         Walk {
-                            abs_pos: Some(*pos + dvec2(part * *defer_index as f64, 0.)),
+                            abs_pos: Some(*pos + dvec2(offset, 0.)),
                             margin: *margin,
                             width: Size::Fixed(part),
-                            height: *other_axis
+                            height: *other_axis,
+                            cell: GridCell::default(),
+                            constraint: *constraint
                         }
                     },
-                    Flow::Down => {
-                        let left = turtle.height_left();
-                        let part = left / turtle.defer_count as f64;
+                    Axis::Vertical => {
                         Walk {
-                            abs_pos: Some(*pos + dvec2(0., part * *defer_index as f64)),
+                            abs_pos: Some(*pos + dvec2(0., offset)),
                             margin: *margin,
                             height: Size::Fixed(part),
-                            width: *other_axis
+                            width: *other_axis,
+                            cell: GridCell::default(),
+                            constraint: *constraint
                         }
                     }
-                    Flow::Overlay => panic!()
+                };
+                *self = DeferWalk::Resolved(walk);
+                walk
+            }
+            Self::Cell{item, walk}=>{
+                // the fit tracks are measured now, so fill can take the final size of the cell
+                let turtle = cx.turtles.last().unwrap();
+                let grid = turtle.grid.as_ref().unwrap();
+                let (columns, rows) = turtle.grid_track_sizes(grid);
+                let item = &grid.items[*item];
+                let rect = turtle.grid_cell_rect(item, &columns, &rows);
+                let walk = Walk {
+                    width: if walk.width.is_fill() {Size::Fixed(max_zero_keep_nan(rect.size.x - walk.margin.width()))} else {walk.width},
+                    height: if walk.height.is_fill() {Size::Fixed(max_zero_keep_nan(rect.size.y - walk.margin.height()))} else {walk.height},
+                    cell: GridCell {
+                        column: Some(item.column),
+                        row: Some(item.row),
+                        column_span: item.column_span,
+                        row_span: item.row_span
+                    },
+                    ..*walk
                 };
                 *self = DeferWalk::Resolved(walk);
                 walk
@@ -984,6 +1523,8 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(0.0),
             height: Size::Fixed(0.0),
+            cell: GridCell::default(),
+            constraint: Constraint::default(),
         }
     }
     
@@ -993,6 +1534,8 @@ impl Walk {
             margin: Margin::default(),
             width: w,
             height: h,
+            cell: GridCell::default(),
+            constraint: Constraint::default(),
         }
     }
 
@@ -1003,6 +1546,8 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(w),
             height: Size::Fixed(h),
+            cell: GridCell::default(),
+            constraint: Constraint::default(),
         }
     }
         
@@ -1012,6 +1557,8 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fixed(size.x),
             height: Size::Fixed(size.y),
+            cell: GridCell::default(),
+            constraint: Constraint::default(),
         }
     }
     
@@ -1021,6 +1568,8 @@ impl Walk {
             margin: Margin::default(),
             width: Size::Fit,
             height: Size::Fit,
+            cell: GridCell::default(),
+            constraint: Constraint::default(),
         }
     }
     
@@ -1028,8 +1577,10 @@ impl Walk {
         Self {
             abs_pos: None,
             margin: Margin::default(),
-            width: Size::Fill,
-            height: Size::Fill,
+            width: Size::fill(),
+            height: Size::fill(),
+            cell: GridCell::default(),
+            constraint: Constraint::default(),
         }
     }
     
@@ -1037,8 +1588,10 @@ impl Walk {
         Self {
             abs_pos: None,
             margin: Margin::default(),
-            width: Size::Fill,
+            width: Size::fill(),
             height: Size::Fit,
+            cell: GridCell::default(),
+            constraint: Constraint::default(),
         }
     }
     
@@ -1115,6 +1668,57 @@ impl LiveHook for Padding {
     }
 }
 
+impl Constraint {
+    // clamps a size and applies the aspect ratio, sizes that aren't known yet (NaN) stay unknown
+    pub fn constrain(&self, size: DVec2) -> DVec2 {
+        let mut size = dvec2(self.clamp_width(size.x), self.clamp_height(size.y));
+        if let Some(aspect_ratio) = self.aspect_ratio.filter( | v | *v > 0.0) {
+            if !size.x.is_nan() {
+                size.y = self.clamp_height(size.x / aspect_ratio);
+                size.x = self.clamp_width(size.y * aspect_ratio);
+            }
+            else if !size.y.is_nan() {
+                size.x = self.clamp_width(size.y * aspect_ratio);
+                size.y = self.clamp_height(size.x / aspect_ratio);
+            }
+        }
+        size
+    }
+    
+    pub fn clamp_width(&self, width: f64) -> f64 {
+        clamp_keep_nan(width, self.min_width, self.max_width)
+    }
+    
+    pub fn clamp_height(&self, height: f64) -> f64 {
+        clamp_keep_nan(height, self.min_height, self.max_height)
+    }
+    
+}
+
+impl DeferFill {
+    // bounds and weights that aren't finite numbers are left out, so sharing always ends
+    fn new(size: Size, min: Option<f64>, max: Option<f64>) -> Self {
+        let min = min.filter( | v | v.is_finite()).unwrap_or(0.0).max(0.0);
+        let weight = size.fill_weight();
+        Self {
+            weight: if weight.is_finite() {weight} else {1.0},
+            min,
+            max: max.filter( | v | !v.is_nan()).unwrap_or(f64::INFINITY).max(min)
+        }
+    }
+}
+
+impl Default for GridCell {
+    fn default() -> Self {
+        Self {
+            column: None,
+            row: None,
+            column_span: 1,
+            row_span: 1
+        }
+    }
+}
+
 impl Default for Flow {
     fn default() -> Self {Self::Down}
 }
@@ -1152,6 +1756,10 @@ impl LiveHook for Size {
                 *self = Self::Fixed(*v as f64);
                 Some(index + 1)
             }
+            LiveValue::BareEnum(live_id!(Fill)) => {
+                *self = Self::fill();
+                Some(index + 1)
+            }
             _ => None
         }
     }
@@ -1159,11 +1767,15 @@ impl LiveHook for Size {
 
 impl Default for Size {
     fn default() -> Self {
-        Size::Fill
+        Size::fill()
     }
 }
 
 impl Size {
+    pub fn fill() -> Self {
+        Self::Fill(1.0)
+    }
+    
     pub fn fixed_or_zero(&self) -> f64 {
         match self {
             Self::Fixed(v) => *v,
@@ -1194,10 +1806,26 @@ impl Size {
     
     pub fn is_fill(&self) -> bool {
         match self {
-            Self::Fill => true,
+            Self::Fill(_) => true,
             _ => false
         }
     }
+    
+    pub fn fill_weight(&self) -> f64 {
+        match self {
+            Self::Fill(weight) => weight.max(0.0),
+            _ => 0.0
+        }
+    }
+}
+
+fn clamp_keep_nan(v: f64, min: Option<f64>, max: Option<f64>) -> f64 {
+    if v.is_nan() {
+        return v
+    }
+    // the minimum wins over the maximum
+    let v = if let Some(max) = max {v.min(max)} else {v};
+    if let Some(min) = min {v.max(min)} else {v}
 }
 
 fn max_zero_keep_nan(v: f64) -> f64 {
@@ -1210,3 +1838,140 @@ fn max_zero_keep_nan(v: f64) -> f64 {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn cell(column: Option<usize>, row: Option<usize>, column_span: usize) -> GridCell {
+        GridCell {column, row, column_span, row_span: 1}
+    }
+    
+    fn grid(columns: usize) -> TurtleGrid {
+        TurtleGrid {
+            columns: vec![GridTrack::Fit; columns],
+            column_fit: vec![0.0; columns],
+            ..TurtleGrid::default()
+        }
+    }
+    
+    fn placed(grid: &TurtleGrid, item: usize) -> (usize, usize) {
+        (grid.items[item].column, grid.items[item].row)
+    }
+    
+    #[test]
+    fn test_grid_auto_placement() {
+        let mut grid = grid(3);
+        let a = grid.place(cell(None, None, 1));
+        let b = grid.place(cell(None, None, 2));
+        let c = grid.place(cell(None, None, 2));
+        let d = grid.place(cell(None, None, 1));
+        assert_eq!(placed(&grid, a), (0, 0));
+        assert_eq!(placed(&grid, b), (1, 0));
+        // doesn't fit the rest of row 0
+        assert_eq!(placed(&grid, c), (0, 1));
+        assert_eq!(placed(&grid, d), (2, 1));
+        // a span wider than the grid is cut to the grid
+        let e = grid.place(cell(None, None, 5));
+        assert_eq!(placed(&grid, e), (0, 2));
+        assert_eq!(grid.items[e].column_span, 3);
+    }
+    
+    #[test]
+    fn test_grid_full_row() {
+        let mut grid = grid(2);
+        let a = grid.place(cell(None, Some(0), 1));
+        let b = grid.place(cell(None, Some(0), 1));
+        assert_eq!(placed(&grid, a), (0, 0));
+        assert_eq!(placed(&grid, b), (1, 0));
+        // row 0 is full, the child moves down instead of overlapping
+        let c = grid.place(cell(None, Some(0), 1));
+        assert_eq!(placed(&grid, c), (0, 1));
+        let d = grid.place(cell(None, Some(0), 2));
+        assert_eq!(placed(&grid, d), (0, 2));
+        for (i, item) in grid.items.iter().enumerate() {
+            assert!(grid.items[..i].iter().all( | other | {
+                item.row != other.row || item.column >= other.column + other.column_span || other.column >= item.column + item.column_span
+            }));
+        }
+    }
+    
+    #[test]
+    fn test_grid_column_placement() {
+        let mut grid = grid(3);
+        let a = grid.place(cell(Some(1), None, 1));
+        let b = grid.place(cell(Some(1), None, 1));
+        let c = grid.place(cell(Some(2), Some(4), 2));
+        assert_eq!(placed(&grid, a), (1, 0));
+        assert_eq!(placed(&grid, b), (1, 1));
+        // moved left so the span fits
+        assert_eq!(placed(&grid, c), (1, 4));
+        assert_eq!(grid.row_fit.len(), 5);
+    }
+    
+    fn deferring_turtle(flow: Flow, size: f64, fills: &[(Size, Option<f64>, Option<f64>)]) -> Turtle {
+        Turtle {
+            layout: Layout {flow, ..Layout::default()},
+            width: size,
+            height: size,
+            defer_count: fills.len(),
+            defer_fills: fills.iter().map( | (size, min, max) | DeferFill::new(*size, *min, *max)).collect(),
+            ..Turtle::default()
+        }
+    }
+    
+    #[test]
+    fn test_defer_parts() {
+        let turtle = deferring_turtle(Flow::Right, 300.0, &[(Size::fill(), None, None), (Size::fill(), None, None), (Size::fill(), None, None)]);
+        assert_eq!(turtle.defer_parts(), vec![100.0, 100.0, 100.0]);
+        // bounds take their part first, the rest is shared by the others
+        let turtle = deferring_turtle(Flow::Down, 300.0, &[(Size::fill(), None, Some(40.0)), (Size::fill(), Some(200.0), None), (Size::fill(), None, None)]);
+        assert_eq!(turtle.defer_parts(), vec![40.0, 200.0, 60.0]);
+        // minimums that don't fit still get their size
+        let turtle = deferring_turtle(Flow::Right, 100.0, &[(Size::fill(), Some(80.0), None), (Size::fill(), Some(80.0), None)]);
+        assert_eq!(turtle.defer_parts(), vec![80.0, 80.0]);
+    }
+    
+    #[test]
+    fn test_defer_parts_without_finite_space() {
+        for size in [f64::INFINITY, f64::NAN] {
+            let turtle = deferring_turtle(Flow::Right, size, &[(Size::fill(), Some(10.0), Some(20.0)), (Size::fill(), None, None)]);
+            assert!(turtle.defer_parts().iter().all( | part | part.is_nan()));
+        }
+        // bounds that aren't finite don't keep the sharing from ending
+        let turtle = deferring_turtle(Flow::Right, 100.0, &[(Size::fill(), Some(f64::INFINITY), Some(f64::NAN)), (Size::fill(), Some(f64::NAN), Some(10.0))]);
+        assert_eq!(turtle.defer_parts(), vec![90.0, 10.0]);
+    }
+    
+    #[test]
+    fn test_shrink_shares() {
+        // shrinking weighs in the size, like flex-shrink
+        let shrink = | size: f64, factor: f64, min: f64 | DeferFill {weight: size * factor, min: 0.0, max: size - min};
+        assert_eq!(share_space(60.0, &[shrink(100.0, 1.0, 0.0), shrink(200.0, 1.0, 0.0)]), vec![20.0, 40.0]);
+        assert_eq!(share_space(60.0, &[shrink(100.0, 2.0, 0.0), shrink(200.0, 1.0, 0.0), shrink(50.0, 0.0, 0.0)]), vec![30.0, 30.0, 0.0]);
+        // a child at its minimum leaves the rest to the others
+        assert_eq!(share_space(60.0, &[shrink(100.0, 1.0, 0.0), shrink(200.0, 1.0, 180.0)]), vec![40.0, 20.0]);
+        // more overflow than there is to give
+        assert_eq!(share_space(500.0, &[shrink(100.0, 1.0, 10.0), shrink(200.0, 1.0, 50.0)]), vec![90.0, 150.0]);
+    }
+    
+    #[test]
+    fn test_fill_weights() {
+        let turtle = deferring_turtle(Flow::Right, 400.0, &[(Size::Fill(1.0), None, None), (Size::Fill(3.0), None, None), (Size::Fill(-1.0), None, None)]);
+        assert_eq!(turtle.defer_parts(), vec![100.0, 300.0, 0.0]);
+        let turtle = deferring_turtle(Flow::Right, 400.0, &[(Size::Fill(1.0), None, None), (Size::Fill(3.0), None, Some(100.0))]);
+        assert_eq!(turtle.defer_parts(), vec![300.0, 100.0]);
+    }
+    
+    #[test]
+    fn test_track_sizes() {
+        let tracks = [GridTrack::Fixed(10.0), GridTrack::Fit, GridTrack::Weight(1.0), GridTrack::Weight(3.0)];
+        let fit = [0.0, 20.0, 5.0, 5.0];
+        assert_eq!(track_sizes(&tracks, &fit, 4, 120.0, 2.0), vec![10.0, 20.0, 21.0, 63.0]);
+        // without space to share, weighted tracks fit their content
+        assert_eq!(track_sizes(&tracks, &fit, 4, f64::NAN, 2.0), vec![10.0, 20.0, 5.0, 5.0]);
+        // no template is one column taking everything
+        assert_eq!(track_sizes(&[], &[7.0], 1, 50.0, 2.0), vec![50.0]);
+        assert_eq!(track_span(&[10.0, 20.0, 30.0], 1, 3, 2.0), 52.0);
+    }
+}
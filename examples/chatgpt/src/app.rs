@@ -28,7 +28,7 @@ live_design!{
                 }
             }
             
-            message_label = <Label> {
+            message_label = <Markdown> {
                 width: 300,
                 height: Fit
                 draw_text: {
@@ -96,7 +96,7 @@ impl AppMain for App {
         for event in event.network_responses() {
             match &event.response {
                 NetworkResponse::HttpResponse(response) => {
-                    let label = self.ui.label(id!(message_label));
+                    let label = self.ui.markdown(id!(message_label));
                     match event.request_id {
                         live_id!(SendChatMessage) => {
                             if response.status_code == 200 {
@@ -111,7 +111,7 @@ impl AppMain for App {
                     }
                 }
                 NetworkResponse::HttpRequestError(error) => {
-                    let label = self.ui.label(id!(message_label));
+                    let label = self.ui.markdown(id!(message_label));
                     label.set_text_and_redraw(cx, &format!("Failed to connect with OpenAI {:?}", error));
                 }
                 _ => ()
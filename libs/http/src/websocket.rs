@@ -34,6 +34,7 @@ pub struct WebSocket {
     mask_counter: usize,
     is_ping: bool,
     is_pong: bool,
+    is_close: bool,
     is_partial: bool,
     is_text: bool,
     is_masked: bool,
@@ -57,6 +58,12 @@ pub enum WebSocketError<'a> {
 pub const PING_MESSAGE:[u8;2] = [128 | 9,0];
 pub const PONG_MESSAGE:[u8;2] = [128 | 10,0];
 
+pub const OPCODE_TEXT: u8 = 1;
+pub const OPCODE_BINARY: u8 = 2;
+pub const OPCODE_CLOSE: u8 = 8;
+pub const OPCODE_PING: u8 = 9;
+pub const OPCODE_PONG: u8 = 10;
+
 pub struct BinaryMessageHeader{
     len: usize,
     data:[u8;10]
@@ -107,6 +114,7 @@ impl WebSocket {
             mask_counter: 0,
             is_ping: false,
             is_pong: false,
+            is_close: false,
             is_masked: false,
             is_partial: false,
             is_text: false,
@@ -114,19 +122,53 @@ impl WebSocket {
         }
     }
     
-    pub fn create_upgrade_response(key: &str) -> String {
+    pub fn create_accept_key(key: &str) -> String {
         let to_hash = format!("{}258EAFA5-E914-47DA-95CA-C5AB0DC85B11", key);
         let mut sha1 = Sha1::new();
         sha1.update(to_hash.as_bytes());
         let out_bytes = sha1.finalise();
-        let base64 = base64_encode(&out_bytes);
+        base64_encode(&out_bytes)
+    }
+    
+    pub fn create_upgrade_response(key: &str) -> String {
         let response_ack = format!(
             "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
-            base64
+            Self::create_accept_key(key)
         );
         response_ack
     }
     
+    // the client side of the handshake, key is 16 random bytes base64 encoded
+    pub fn create_upgrade_request(host: &str, path: &str, key: &str, extra_headers: &str) -> String {
+        format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n{}\r\n",
+            path,
+            host,
+            key,
+            extra_headers
+        )
+    }
+    
+    // frames sent by a client have to be masked
+    pub fn create_client_frame(opcode: u8, data: &[u8], mask: [u8; 4]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(data.len() + 14);
+        frame.push(128 | opcode);
+        if data.len() < 126 {
+            frame.push(128 | data.len() as u8);
+        }
+        else if data.len() < 65536 {
+            frame.push(128 | 126);
+            frame.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        }
+        else {
+            frame.push(128 | 127);
+            frame.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        }
+        frame.extend_from_slice(&mask);
+        frame.extend(data.iter().enumerate().map( | (i, byte) | byte ^ mask[i & 3]));
+        frame
+    }
+    
     fn parse_head(&mut self, input: &[u8]) -> bool {
         while self.head_expected > 0
             && self.input_read < input.len()
@@ -149,6 +191,7 @@ impl WebSocket {
             State::Opcode => {
                 self.is_ping = false;
                 self.is_pong = false;
+                self.is_close = false;
                 self.is_partial = false;
                 self.is_text = false;
                 self.is_masked = false;
@@ -176,8 +219,9 @@ impl WebSocket {
                         self.to_state(State::Len1);
                     }
                     else if opcode == 8 {
-                        result(Ok(WebSocketMessage::Close));
-                        break;
+                        // read the status code as well, so the frame is only reported once
+                        self.is_close = true;
+                        self.to_state(State::Len1);
                     }
                     else if opcode == 9 {
                         self.is_ping = true;
@@ -269,6 +313,9 @@ impl WebSocket {
                         if self.is_ping {
                             result(Ok(WebSocketMessage::Ping(&self.data)));
                         }
+                        else if self.is_close {
+                            result(Ok(WebSocketMessage::Close));
+                        }
                         else if self.is_pong {
                             result(Ok(WebSocketMessage::Pong(&self.data)));
                         }
@@ -299,3 +346,75 @@ impl Default for WebSocket {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Message {
+        Ping(Vec<u8>),
+        Text(String),
+        Binary(Vec<u8>),
+        Close,
+        Error,
+    }
+
+    fn parse_chunks(web_socket: &mut WebSocket, chunks: &[&[u8]]) -> Vec<Message> {
+        let mut out = Vec::new();
+        for chunk in chunks {
+            web_socket.parse(chunk, | result | out.push(match result {
+                Ok(WebSocketMessage::Ping(data)) => Message::Ping(data.to_vec()),
+                Ok(WebSocketMessage::Pong(_)) => unreachable!(),
+                Ok(WebSocketMessage::Text(text)) => Message::Text(text.to_string()),
+                Ok(WebSocketMessage::Binary(data)) => Message::Binary(data.to_vec()),
+                Ok(WebSocketMessage::Close) => Message::Close,
+                Err(_) => Message::Error,
+            }));
+        }
+        out
+    }
+
+    #[test]
+    fn test_accept_key() {
+        // the example from RFC 6455 section 1.3
+        assert_eq!(WebSocket::create_accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_client_frame_lengths() {
+        for len in [0, 125, 126, 65535, 65536] {
+            let data: Vec<u8> = (0..len).map( | i | i as u8).collect();
+            let frame = WebSocket::create_client_frame(OPCODE_BINARY, &data, [1, 2, 3, 4]);
+            let head_len = if len < 126 {2} else if len < 65536 {4} else {10};
+            assert_eq!(frame.len(), head_len + 4 + len);
+            assert_eq!(frame[0], 128 | OPCODE_BINARY);
+            let messages = parse_chunks(&mut WebSocket::new(), &[&frame]);
+            assert_eq!(messages, vec![Message::Binary(data)]);
+        }
+    }
+
+    #[test]
+    fn test_parse_split_input() {
+        let mut stream = WebSocket::create_client_frame(OPCODE_TEXT, "héllo".as_bytes(), [9, 8, 7, 6]);
+        stream.extend(WebSocket::create_client_frame(OPCODE_PING, b"p", [0, 0, 0, 0]));
+        // a server frame is not masked
+        stream.extend([128 | OPCODE_TEXT, 2, b'h', b'i']);
+        stream.extend([128 | OPCODE_CLOSE, 0]);
+        let chunks: Vec<&[u8]> = stream.chunks(1).collect();
+        let messages = parse_chunks(&mut WebSocket::new(), &chunks);
+        assert_eq!(messages, vec![
+            Message::Text("héllo".to_string()),
+            Message::Ping(b"p".to_vec()),
+            Message::Text("hi".to_string()),
+            Message::Close,
+        ]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let frame = WebSocket::create_client_frame(OPCODE_TEXT, &[0xff, 0xfe], [1, 1, 1, 1]);
+        assert_eq!(parse_chunks(&mut WebSocket::new(), &[&frame]), vec![Message::Error]);
+        assert_eq!(parse_chunks(&mut WebSocket::new(), &[&[128 | 3, 0]]), vec![Message::Error]);
+    }
+}
@@ -638,7 +638,6 @@ trait WriteTypes {
     fn push16(&mut self,d: u16);
     fn push16b(&mut self,d: u16);
     fn push32(&mut self,d: u32);
-    fn push32b(&mut self,d: u32);
 }
 
 impl WriteTypes for Vec<u8> {
@@ -656,12 +655,6 @@ impl WriteTypes for Vec<u8> {
         self.push(((d >> 16) & 255) as u8);
         self.push((d >> 24) as u8);
     }
-    fn push32b(&mut self,d: u32) {
-        self.push((d >> 24) as u8);
-        self.push(((d >> 16) & 255) as u8);
-        self.push(((d >> 8) & 255) as u8);
-        self.push((d & 255) as u8);
-    }
 }
 
 pub fn encode(image: &ImageBuffer) -> Result<Vec<u8>,String> {
@@ -718,3 +711,35 @@ pub fn encode(image: &ImageBuffer) -> Result<Vec<u8>,String> {
     }
     Ok(dst)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: usize, height: usize, pixel: impl Fn(usize, usize) -> u32) -> ImageBuffer {
+        let mut image = ImageBuffer::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                image.data[y * width + x] = pixel(x, y);
+            }
+        }
+        image
+    }
+
+    fn round_trip(image: &ImageBuffer) {
+        let encoded = encode(image).unwrap();
+        assert_eq!(encoded.len(), 122 + image.width * image.height * 4);
+        assert_eq!(test(&encoded), Some((image.width, image.height)));
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!((decoded.width, decoded.height), (image.width, image.height));
+        assert_eq!(decoded.data, image.data);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&image(1, 1, | _, _ | 0x80FF4020));
+        // the rows are written top down, so a gradient shows if they come back flipped
+        round_trip(&image(37, 23, | x, y | 0xFF000000 | ((x * 7) << 16 | (y * 11) << 8 | (x ^ y)) as u32));
+        round_trip(&image(3, 64, | x, y | (x as u32).wrapping_mul(2654435761) ^ (y as u32).wrapping_mul(40503)));
+    }
+}
@@ -4,7 +4,7 @@
 
 mod image;
 pub use image::*;
-//pub mod bmp;
-//pub mod png;
+pub mod bmp;
+pub mod png;
 pub mod jpeg;
 
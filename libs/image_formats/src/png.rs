@@ -1039,6 +1039,175 @@ pub fn decode(src: &[u8]) -> Result<ImageBuffer, String> {
     }
 }
 
-pub fn encode(_image: &ImageBuffer) -> Result<Vec<u8>, String> {
-    Err("not implemented".to_string())
-}
\ No newline at end of file
+// Deflate is done by makepad-miniz
+const CRC_TABLE: [u32; 256] = make_crc_table();
+
+const fn make_crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if (c & 1) != 0 { 0xEDB88320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(src: &[u8]) -> u32 {
+    let mut c = 0xFFFFFFFFu32;
+    for b in src {
+        c = CRC_TABLE[((c ^ (*b as u32)) & 255) as usize] ^ (c >> 8);
+    }
+    c ^ 0xFFFFFFFF
+}
+
+fn push_be32(dst: &mut Vec<u8>, d: u32) {
+    dst.extend_from_slice(&d.to_be_bytes());
+}
+
+fn push_chunk(dst: &mut Vec<u8>, chunk_type: u32, data: &[u8]) {
+    push_be32(dst, data.len() as u32);
+    let start = dst.len();
+    push_be32(dst, chunk_type);
+    dst.extend_from_slice(data);
+    let crc = crc32(&dst[start..]);
+    push_be32(dst, crc);
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if (pa <= pb) && (pa <= pc) {
+        a
+    }
+    else if pb <= pc {
+        b
+    }
+    else {
+        c
+    }
+}
+
+// filters one scanline with all 5 filter types and keeps the one with the
+// smallest sum of absolute differences, as suggested by the PNG spec
+fn filter(dst: &mut Vec<u8>, line: &[u8], prev: &[u8], bpp: usize, scratch: &mut [Vec<u8>; 5]) {
+    let mut best = 0;
+    let mut best_sum = u64::MAX;
+    for (ftype, out) in scratch.iter_mut().enumerate() {
+        out.clear();
+        let mut sum = 0u64;
+        for i in 0..line.len() {
+            let a = if i >= bpp { line[i - bpp] } else { 0 };
+            let b = prev[i];
+            let c = if i >= bpp { prev[i - bpp] } else { 0 };
+            let predicted = match ftype {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) >> 1) as u8,
+                _ => paeth(a, b, c),
+            };
+            let v = line[i].wrapping_sub(predicted);
+            sum += (v as i8).unsigned_abs() as u64;
+            out.push(v);
+        }
+        if sum < best_sum {
+            best_sum = sum;
+            best = ftype;
+        }
+    }
+    dst.push(best as u8);
+    dst.extend_from_slice(&scratch[best]);
+}
+
+pub fn encode(image: &ImageBuffer) -> Result<Vec<u8>, String> {
+    if (image.width == 0) || (image.height == 0) || (image.data.len() < image.width * image.height) {
+        return Err("invalid image size".to_string());
+    }
+    let stride = image.width * 4;
+    let mut prev = vec![0u8; stride];
+    let mut line = vec![0u8; stride];
+    let mut scratch: [Vec<u8>; 5] = Default::default();
+    let mut raw_data: Vec<u8> = Vec::with_capacity((stride + 1) * image.height);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let c = image.data[y * image.width + x];
+            line[x * 4] = ((c >> 16) & 255) as u8;
+            line[x * 4 + 1] = ((c >> 8) & 255) as u8;
+            line[x * 4 + 2] = (c & 255) as u8;
+            line[x * 4 + 3] = (c >> 24) as u8;
+        }
+        filter(&mut raw_data, &line, &prev, 4, &mut scratch);
+        std::mem::swap(&mut line, &mut prev);
+    }
+    let zipped_data = makepad_miniz::compress_to_vec_zlib(&raw_data, 6);
+
+    let mut dst: Vec<u8> = Vec::with_capacity(zipped_data.len() + 57);
+    dst.extend_from_slice(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]);
+    let mut ihdr: Vec<u8> = Vec::with_capacity(13);
+    push_be32(&mut ihdr, image.width as u32);
+    push_be32(&mut ihdr, image.height as u32);
+    ihdr.extend_from_slice(&TYPE_RGBA8.to_be_bytes());
+    ihdr.push(0); // compression
+    ihdr.push(0); // filter
+    ihdr.push(0); // interlace
+    push_chunk(&mut dst, 0x49484452, &ihdr); // IHDR
+    push_chunk(&mut dst, 0x49444154, &zipped_data); // IDAT
+    push_chunk(&mut dst, 0x49454E44, &[]); // IEND
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: usize, height: usize, pixel: impl Fn(usize, usize) -> u32) -> ImageBuffer {
+        let mut image = ImageBuffer::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                image.data[y * width + x] = pixel(x, y);
+            }
+        }
+        image
+    }
+
+    fn round_trip(image: &ImageBuffer) {
+        let encoded = encode(image).unwrap();
+        assert_eq!(test(&encoded), Some((image.width, image.height)));
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!((decoded.width, decoded.height), (image.width, image.height));
+        assert_eq!(decoded.data, image.data);
+    }
+
+    #[test]
+    fn test_crc() {
+        assert_eq!(crc32(b"IEND"), 0xAE426082);
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&image(1, 1, | _, _ | 0x80FF4020));
+        // gradients and noise end up using every filter type
+        round_trip(&image(37, 23, | x, y | 0xFF000000 | ((x * 7) << 16 | (y * 11) << 8 | (x ^ y)) as u32));
+        round_trip(&image(64, 64, | x, y | {
+            let v = (x as u32).wrapping_mul(2654435761) ^ (y as u32).wrapping_mul(40503);
+            v.rotate_left((x + y) as u32 & 31)
+        }));
+    }
+
+    #[test]
+    fn test_encode_invalid() {
+        assert!(encode(&ImageBuffer::new(0, 4)).is_err());
+        let mut image = ImageBuffer::new(4, 4);
+        image.data.truncate(3);
+        assert!(encode(&image).is_err());
+    }
+}
@@ -0,0 +1,2 @@
+mod markdown;
+pub use crate::markdown::*;
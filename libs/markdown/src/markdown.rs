@@ -0,0 +1,935 @@
+// A CommonMark subset parser: ATX and setext headings, paragraphs, emphasis,
+// inline code, links and autolinks, bullet and ordered lists, block quotes,
+// fenced and indented code blocks and thematic breaks.
+// Nested containers are flattened: every block records how deep it sits in
+// block quotes and lists, so a renderer can walk the blocks top to bottom.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MarkdownStyle {
+    pub strong: bool,
+    pub emphasis: bool,
+    pub code: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkdownInline {
+    pub text: String,
+    pub style: MarkdownStyle,
+    pub link: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkdownListMarker {
+    Bullet,
+    Number(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkdownBlockKind {
+    Paragraph,
+    Heading(usize),
+    ListItem(MarkdownListMarker),
+    CodeBlock {lang: String},
+    Rule,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkdownBlock {
+    pub kind: MarkdownBlockKind,
+    pub quote_depth: usize,
+    // the number of list items this block is inside, a list item counts itself
+    pub list_depth: usize,
+    // a code block holds its code as one inline
+    pub inlines: Vec<MarkdownInline>,
+}
+
+impl MarkdownBlock {
+    pub fn text(&self) -> String {
+        self.inlines.iter().map( | inline | inline.text.as_str()).collect()
+    }
+}
+
+// A parsed document that can be extended at the end, for text that streams in.
+// Parsing resumes from the last point where no block was open, so appending
+// only reparses the trailing block.
+#[derive(Clone, Default)]
+pub struct MarkdownDoc {
+    pub blocks: Vec<MarkdownBlock>,
+    source: String,
+    stable_offset: usize,
+    stable_blocks: usize,
+    stable_parser: MarkdownParser,
+}
+
+impl MarkdownDoc {
+    pub fn parse(source: &str) -> Self {
+        let mut doc = Self::default();
+        doc.append(source);
+        doc
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn append(&mut self, text: &str) {
+        self.source.push_str(text);
+        self.blocks.truncate(self.stable_blocks);
+        let mut parser = self.stable_parser.clone();
+        let mut offset = self.stable_offset;
+        while offset < self.source.len() {
+            let (end, next) = match self.source[offset..].find('\n') {
+                Some(len) => (offset + len, offset + len + 1),
+                None => (self.source.len(), self.source.len())
+            };
+            let line = self.source[offset..end].trim_end_matches('\r');
+            parser.line(line, &mut self.blocks);
+            offset = next;
+            // the last line can still grow, so it never counts as stable
+            if end < self.source.len() && parser.open.is_none() {
+                self.stable_offset = offset;
+                self.stable_blocks = self.blocks.len();
+                self.stable_parser = parser.clone();
+            }
+        }
+        parser.flush(&mut self.blocks);
+    }
+}
+
+pub fn parse_markdown(source: &str) -> Vec<MarkdownBlock> {
+    MarkdownDoc::parse(source).blocks
+}
+
+#[derive(Clone)]
+enum OpenBlock {
+    Paragraph {kind: MarkdownBlockKind, quote_depth: usize, list_depth: usize, text: String},
+    Fence {fence: char, fence_len: usize, indent: usize, lang: String, quote_depth: usize, list_depth: usize, code: String},
+    IndentedCode {quote_depth: usize, list_depth: usize, code: String},
+}
+
+#[derive(Clone, Default)]
+struct MarkdownParser {
+    // content columns of the open list items
+    lists: Vec<usize>,
+    quote_depth: usize,
+    open: Option<OpenBlock>,
+}
+
+impl MarkdownParser {
+    fn line(&mut self, line: &str, blocks: &mut Vec<MarkdownBlock>) {
+        let line = line.replace('\t', "    ");
+
+        // code fences take every line until they are closed
+        if let Some(OpenBlock::Fence {fence, fence_len, indent, quote_depth, code, ..}) = &mut self.open {
+            let (_, rest) = strip_quotes(&line, *quote_depth);
+            let rest = strip_indent(rest, self.lists.last().copied().unwrap_or(0));
+            if is_fence_close(rest, *fence, *fence_len) {
+                self.flush(blocks);
+            }
+            else {
+                code.push_str(strip_indent(rest, *indent));
+                code.push('\n');
+            }
+            return
+        }
+
+        let (quote_depth, rest) = strip_quotes(&line, usize::MAX);
+        if rest.trim().is_empty() {
+            self.flush(blocks);
+            if quote_depth != self.quote_depth {
+                self.lists.clear();
+                self.quote_depth = quote_depth;
+            }
+            return
+        }
+        let indent = rest.len() - rest.trim_start_matches(' ').len();
+        let text = rest.trim_start_matches(' ');
+
+        if quote_depth != self.quote_depth {
+            // a paragraph in a quote continues lazily on lines without the >
+            if quote_depth < self.quote_depth && self.is_paragraph_open() && !starts_block(text) {
+                self.append_paragraph(text);
+                return
+            }
+            self.flush(blocks);
+            self.lists.clear();
+            self.quote_depth = quote_depth;
+        }
+
+        let contained = self.lists.iter().take_while( | column | indent >= **column).count();
+        let base = if contained > 0 {self.lists[contained - 1]} else {0};
+
+        if indent < base + 4 {
+            if let Some((marker, marker_len)) = list_marker(text) {
+                // only a list starting at 1 can interrupt a paragraph, so numbers in running text stay text
+                let interrupts = match marker {
+                    MarkdownListMarker::Number(number) => number == 1,
+                    MarkdownListMarker::Bullet => true
+                };
+                let in_paragraph = matches!(self.open, Some(OpenBlock::Paragraph {kind: MarkdownBlockKind::Paragraph, ..}));
+                if !in_paragraph || contained > 0 || interrupts {
+                    self.flush(blocks);
+                    self.lists.truncate(contained);
+                    let after = &text[marker_len..];
+                    let item_text = after.trim_start_matches(' ');
+                    let spaces = after.len() - item_text.len();
+                    let spaces = if spaces == 0 || spaces > 4 || item_text.is_empty() {1} else {spaces};
+                    self.lists.push(indent + marker_len + spaces);
+                    self.open = Some(OpenBlock::Paragraph {
+                        kind: MarkdownBlockKind::ListItem(marker),
+                        quote_depth,
+                        list_depth: self.lists.len(),
+                        text: item_text.to_string()
+                    });
+                    return
+                }
+            }
+        }
+
+        if contained < self.lists.len() && self.is_paragraph_open() && !starts_block(text) {
+            self.append_paragraph(text);
+            return
+        }
+        self.lists.truncate(contained);
+        let list_depth = self.lists.len();
+        let indent = indent - base;
+
+        if indent >= 4 {
+            if self.is_paragraph_open() {
+                self.append_paragraph(text);
+                return
+            }
+            let code_line = &rest[base + 4..];
+            if let Some(OpenBlock::IndentedCode {code, ..}) = &mut self.open {
+                code.push_str(code_line);
+                code.push('\n');
+                return
+            }
+            self.flush(blocks);
+            self.open = Some(OpenBlock::IndentedCode {quote_depth, list_depth, code: format!("{}\n", code_line)});
+            return
+        }
+
+        if let Some((fence, fence_len, lang)) = fence_open(text) {
+            self.flush(blocks);
+            self.open = Some(OpenBlock::Fence {fence, fence_len, indent, lang, quote_depth, list_depth, code: String::new()});
+            return
+        }
+
+        if let Some((level, heading)) = atx_heading(text) {
+            self.flush(blocks);
+            blocks.push(MarkdownBlock {
+                kind: MarkdownBlockKind::Heading(level),
+                quote_depth,
+                list_depth,
+                inlines: parse_inlines(heading)
+            });
+            return
+        }
+
+        if let Some(OpenBlock::Paragraph {kind: kind @ MarkdownBlockKind::Paragraph, ..}) = &mut self.open {
+            let underline = text.trim_end();
+            if underline.chars().all( | c | c == '=') {
+                *kind = MarkdownBlockKind::Heading(1);
+                self.flush(blocks);
+                return
+            }
+            if underline.chars().all( | c | c == '-') {
+                *kind = MarkdownBlockKind::Heading(2);
+                self.flush(blocks);
+                return
+            }
+        }
+
+        if is_rule(text) {
+            self.flush(blocks);
+            blocks.push(MarkdownBlock {kind: MarkdownBlockKind::Rule, quote_depth, list_depth, inlines: Vec::new()});
+            return
+        }
+
+        if self.is_paragraph_open() {
+            self.append_paragraph(text);
+            return
+        }
+        self.flush(blocks);
+        self.open = Some(OpenBlock::Paragraph {
+            kind: MarkdownBlockKind::Paragraph,
+            quote_depth,
+            list_depth,
+            text: text.to_string()
+        });
+    }
+
+    fn is_paragraph_open(&self) -> bool {
+        matches!(self.open, Some(OpenBlock::Paragraph {..}))
+    }
+
+    fn append_paragraph(&mut self, line: &str) {
+        if let Some(OpenBlock::Paragraph {text, ..}) = &mut self.open {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(line);
+        }
+    }
+
+    fn flush(&mut self, blocks: &mut Vec<MarkdownBlock>) {
+        match self.open.take() {
+            Some(OpenBlock::Paragraph {kind, quote_depth, list_depth, text}) => {
+                blocks.push(MarkdownBlock {kind, quote_depth, list_depth, inlines: parse_inlines(text.trim_end())});
+            }
+            Some(OpenBlock::Fence {lang, quote_depth, list_depth, code, ..}) => {
+                blocks.push(code_block(lang, quote_depth, list_depth, code));
+            }
+            Some(OpenBlock::IndentedCode {quote_depth, list_depth, code}) => {
+                blocks.push(code_block(String::new(), quote_depth, list_depth, code));
+            }
+            None => ()
+        }
+    }
+}
+
+fn code_block(lang: String, quote_depth: usize, list_depth: usize, mut code: String) -> MarkdownBlock {
+    if code.ends_with('\n') {
+        code.pop();
+    }
+    MarkdownBlock {
+        kind: MarkdownBlockKind::CodeBlock {lang},
+        quote_depth,
+        list_depth,
+        inlines: vec![MarkdownInline {
+            text: code,
+            style: MarkdownStyle {code: true, ..Default::default()},
+            link: None
+        }]
+    }
+}
+
+fn strip_quotes(line: &str, max_depth: usize) -> (usize, &str) {
+    let mut rest = line;
+    let mut depth = 0;
+    while depth < max_depth {
+        let text = rest.trim_start_matches(' ');
+        if rest.len() - text.len() >= 4 || !text.starts_with('>') {
+            break;
+        }
+        rest = &text[1..];
+        rest = rest.strip_prefix(' ').unwrap_or(rest);
+        depth += 1;
+    }
+    (depth, rest)
+}
+
+fn strip_indent(line: &str, max: usize) -> &str {
+    let spaces = line.len() - line.trim_start_matches(' ').len();
+    &line[spaces.min(max)..]
+}
+
+fn starts_block(text: &str) -> bool {
+    text.starts_with('>')
+        || list_marker(text).is_some()
+        || fence_open(text).is_some()
+        || atx_heading(text).is_some()
+        || is_rule(text)
+}
+
+fn list_marker(text: &str) -> Option<(MarkdownListMarker, usize)> {
+    let bytes = text.as_bytes();
+    let len = match bytes.first() {
+        Some(b'-') | Some(b'*') | Some(b'+') => Some((MarkdownListMarker::Bullet, 1)),
+        Some(b'0'..=b'9') => {
+            let digits = bytes.iter().take_while( | b | b.is_ascii_digit()).count();
+            match bytes.get(digits) {
+                Some(b'.') | Some(b')') if digits <= 9 => {
+                    Some((MarkdownListMarker::Number(text[..digits].parse().unwrap()), digits + 1))
+                }
+                _ => None
+            }
+        }
+        _ => None
+    };
+    // the marker has to be followed by a space or end the line, and a rule is not a list
+    match len {
+        Some((marker, len)) if (bytes.len() == len || bytes[len] == b' ') && !is_rule(text) => Some((marker, len)),
+        _ => None
+    }
+}
+
+fn fence_open(text: &str) -> Option<(char, usize, String)> {
+    let fence = text.chars().next()?;
+    if fence != '`' && fence != '~' {
+        return None
+    }
+    let fence_len = text.chars().take_while( | c | *c == fence).count();
+    let info = text[fence_len..].trim();
+    if fence_len < 3 || fence == '`' && info.contains('`') {
+        return None
+    }
+    Some((fence, fence_len, info.split_whitespace().next().unwrap_or("").to_string()))
+}
+
+fn is_fence_close(line: &str, fence: char, fence_len: usize) -> bool {
+    let text = line.trim_start_matches(' ');
+    if line.len() - text.len() >= 4 {
+        return false
+    }
+    let len = text.chars().take_while( | c | *c == fence).count();
+    len >= fence_len && text[len..].trim().is_empty()
+}
+
+fn atx_heading(text: &str) -> Option<(usize, &str)> {
+    let level = text.chars().take_while( | c | *c == '#').count();
+    if level == 0 || level > 6 {
+        return None
+    }
+    let rest = &text[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None
+    }
+    let mut heading = rest.trim();
+    // an optional closing sequence of #'s
+    let closing = heading.trim_end_matches('#');
+    if closing.is_empty() || closing.ends_with(' ') {
+        heading = closing.trim_end();
+    }
+    Some((level, heading))
+}
+
+fn is_rule(text: &str) -> bool {
+    let mut chars = text.chars().filter( | c | *c != ' ');
+    match chars.next() {
+        Some(rule) if rule == '-' || rule == '*' || rule == '_' => {
+            let mut count = 1;
+            for c in chars {
+                if c != rule {
+                    return false
+                }
+                count += 1;
+            }
+            count >= 3
+        }
+        _ => false
+    }
+}
+
+enum InlineTok {
+    Text(String),
+    Code(String),
+    Delim {
+        delim: char,
+        count: usize,
+        orig_count: usize,
+        can_open: bool,
+        can_close: bool,
+        opens: [usize; 2],
+        closes: [usize; 2],
+    },
+    Link(String, Vec<InlineTok>),
+}
+
+pub fn parse_inlines(text: &str) -> Vec<MarkdownInline> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut toks = tokenize_inlines(&chars);
+    process_emphasis(&mut toks);
+    let mut out = Vec::new();
+    let mut depths = [0, 0];
+    flatten_inlines(&toks, &mut depths, None, &mut out);
+    out
+}
+
+fn push_text(toks: &mut Vec<InlineTok>, c: char) {
+    if let Some(InlineTok::Text(text)) = toks.last_mut() {
+        text.push(c);
+    }
+    else {
+        toks.push(InlineTok::Text(c.to_string()));
+    }
+}
+
+fn tokenize_inlines(chars: &[char]) -> Vec<InlineTok> {
+    let mut toks = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if i + 1 < chars.len() && chars[i + 1].is_ascii_punctuation() => {
+                push_text(&mut toks, chars[i + 1]);
+                i += 2;
+            }
+            '\\' if i + 1 < chars.len() && chars[i + 1] == '\n' => {
+                push_text(&mut toks, '\n');
+                i += 2;
+            }
+            '\n' => {
+                // two trailing spaces make a hard break, otherwise lines are joined
+                let mut hard = false;
+                if let Some(InlineTok::Text(text)) = toks.last_mut() {
+                    let trimmed = text.trim_end_matches(' ').len();
+                    hard = text.len() - trimmed >= 2;
+                    text.truncate(trimmed);
+                }
+                push_text(&mut toks, if hard {'\n'} else {' '});
+                i += 1;
+            }
+            '`' => {
+                let run = count_run(chars, i, '`');
+                if let Some(end) = find_code_end(chars, i + run, run) {
+                    let mut code: String = chars[i + run..end].iter().map( | c | if *c == '\n' {' '} else {*c}).collect();
+                    if code.len() > 2 && code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty() {
+                        code = code[1..code.len() - 1].to_string();
+                    }
+                    toks.push(InlineTok::Code(code));
+                    i = end + run;
+                }
+                else {
+                    for _ in 0..run {
+                        push_text(&mut toks, '`');
+                    }
+                    i += run;
+                }
+            }
+            '*' | '_' => {
+                let run = count_run(chars, i, c);
+                let before = if i > 0 {chars[i - 1]} else {' '};
+                let after = chars.get(i + run).copied().unwrap_or(' ');
+                let left_flanking = !after.is_whitespace()
+                    && (!is_punctuation(after) || before.is_whitespace() || is_punctuation(before));
+                let right_flanking = !before.is_whitespace()
+                    && (!is_punctuation(before) || after.is_whitespace() || is_punctuation(after));
+                let (can_open, can_close) = if c == '*' {
+                    (left_flanking, right_flanking)
+                }
+                else {
+                    // underscores don't do emphasis inside words
+                    (
+                        left_flanking && (!right_flanking || is_punctuation(before)),
+                        right_flanking && (!left_flanking || is_punctuation(after))
+                    )
+                };
+                toks.push(InlineTok::Delim {
+                    delim: c,
+                    count: run,
+                    orig_count: run,
+                    can_open,
+                    can_close,
+                    opens: [0, 0],
+                    closes: [0, 0]
+                });
+                i += run;
+            }
+            '!' if chars.get(i + 1) == Some(&'[') => {
+                // images have no place in text, their alt text stands in for them
+                if let Some((inner, _, end)) = parse_link(chars, i + 1) {
+                    toks.extend(tokenize_inlines(&chars[inner]));
+                    i = end;
+                }
+                else {
+                    push_text(&mut toks, c);
+                    i += 1;
+                }
+            }
+            '[' => {
+                if let Some((inner, url, end)) = parse_link(chars, i) {
+                    let mut inner = tokenize_inlines(&chars[inner]);
+                    process_emphasis(&mut inner);
+                    toks.push(InlineTok::Link(url, inner));
+                    i = end;
+                }
+                else {
+                    push_text(&mut toks, c);
+                    i += 1;
+                }
+            }
+            '<' => {
+                if let Some((url, end)) = parse_autolink(chars, i) {
+                    toks.push(InlineTok::Link(url.clone(), vec![InlineTok::Text(url)]));
+                    i = end;
+                }
+                else {
+                    push_text(&mut toks, c);
+                    i += 1;
+                }
+            }
+            '&' => {
+                if let Some((entity, len)) = parse_entity(chars, i) {
+                    push_text(&mut toks, entity);
+                    i += len;
+                }
+                else {
+                    push_text(&mut toks, c);
+                    i += 1;
+                }
+            }
+            _ => {
+                push_text(&mut toks, c);
+                i += 1;
+            }
+        }
+    }
+    toks
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+fn count_run(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..].iter().take_while( | d | **d == c).count()
+}
+
+fn find_code_end(chars: &[char], start: usize, run: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '`' {
+            let len = count_run(chars, i, '`');
+            if len == run {
+                return Some(i)
+            }
+            i += len;
+        }
+        else {
+            i += 1;
+        }
+    }
+    None
+}
+
+// [text](url "title"), returns the text range, the url and the index after the link
+fn parse_link(chars: &[char], start: usize) -> Option<(std::ops::Range<usize>, String, usize)> {
+    let mut depth = 0;
+    let mut i = start;
+    let close = loop {
+        match chars.get(i)? {
+            '\\' => i += 1,
+            '`' => {
+                let run = count_run(chars, i, '`');
+                if let Some(end) = find_code_end(chars, i + run, run) {
+                    i = end + run - 1;
+                }
+                else {
+                    i += run - 1;
+                }
+            }
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break i
+                }
+            }
+            _ => ()
+        }
+        i += 1;
+    };
+    if chars.get(close + 1) != Some(&'(') {
+        return None
+    }
+    let mut i = close + 2;
+    let skip_spaces = | mut i: usize | {
+        while i < chars.len() && chars[i].is_whitespace() {i += 1}
+        i
+    };
+    i = skip_spaces(i);
+    let mut url = String::new();
+    if chars.get(i) == Some(&'<') {
+        i += 1;
+        while *chars.get(i)? != '>' {
+            url.push(chars[i]);
+            i += 1;
+        }
+        i += 1;
+    }
+    else {
+        let mut parens = 0;
+        while i < chars.len() && !chars[i].is_whitespace() {
+            match chars[i] {
+                '(' => parens += 1,
+                ')' if parens == 0 => break,
+                ')' => parens -= 1,
+                '\\' if i + 1 < chars.len() && chars[i + 1].is_ascii_punctuation() => i += 1,
+                _ => ()
+            }
+            url.push(chars[i]);
+            i += 1;
+        }
+    }
+    i = skip_spaces(i);
+    if let Some(quote) = chars.get(i).copied().filter( | c | *c == '"' || *c == '\'' || *c == '(') {
+        let end_quote = if quote == '(' {')'} else {quote};
+        i += 1;
+        while *chars.get(i)? != end_quote {
+            i += if chars[i] == '\\' {2} else {1};
+        }
+        i = skip_spaces(i + 1);
+    }
+    if chars.get(i) != Some(&')') {
+        return None
+    }
+    Some((start + 1..close, url, i + 1))
+}
+
+// <https://example.com> or <name@example.com>
+fn parse_autolink(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let end = start + 1 + chars[start + 1..].iter().position( | c | *c == '>' || *c == '<' || c.is_whitespace())?;
+    if chars[end] != '>' {
+        return None
+    }
+    let text: String = chars[start + 1..end].iter().collect();
+    let scheme_len = text.find(':')?;
+    let is_uri = scheme_len >= 2 && text[..scheme_len].chars().all( | c | c.is_ascii_alphanumeric() || c == '+' || c == '.' || c == '-');
+    if is_uri {
+        return Some((text, end + 1))
+    }
+    None
+}
+
+fn parse_entity(chars: &[char], start: usize) -> Option<(char, usize)> {
+    let end = start + chars[start..].iter().take(10).position( | c | *c == ';')?;
+    let name: String = chars[start + 1..end].iter().collect();
+    let c = match name.as_str() {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => {
+            let code = if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok()
+            }
+            else if let Some(dec) = name.strip_prefix('#') {
+                dec.parse().ok()
+            }
+            else {
+                None
+            };
+            char::from_u32(code?)?
+        }
+    };
+    Some((c, end - start + 1))
+}
+
+// pairs up emphasis delimiter runs, following the CommonMark delimiter algorithm
+fn process_emphasis(toks: &mut [InlineTok]) {
+    for closer in 0..toks.len() {
+        loop {
+            let (delim, closer_count, closer_orig, closer_can_open) = match &toks[closer] {
+                InlineTok::Delim {delim, count, orig_count, can_close: true, can_open, ..} if *count > 0 => {
+                    (*delim, *count, *orig_count, *can_open)
+                }
+                _ => break
+            };
+            let opener = (0..closer).rev().find( | opener | match &toks[*opener] {
+                InlineTok::Delim {delim: d, count, orig_count, can_open: true, can_close, ..} if *d == delim && *count > 0 => {
+                    let both = *can_close || closer_can_open;
+                    !(both && (orig_count + closer_orig) % 3 == 0 && !(orig_count % 3 == 0 && closer_orig % 3 == 0))
+                }
+                _ => false
+            });
+            let Some(opener) = opener else {break};
+            let used = if closer_count >= 2 && matches!(toks[opener], InlineTok::Delim {count, ..} if count >= 2) {2} else {1};
+            let slot = if used == 2 {0} else {1};
+            if let InlineTok::Delim {count, opens, ..} = &mut toks[opener] {
+                *count -= used;
+                opens[slot] += 1;
+            }
+            if let InlineTok::Delim {count, closes, ..} = &mut toks[closer] {
+                *count -= used;
+                closes[slot] += 1;
+            }
+            for tok in &mut toks[opener + 1..closer] {
+                if let InlineTok::Delim {can_open, can_close, ..} = tok {
+                    *can_open = false;
+                    *can_close = false;
+                }
+            }
+        }
+    }
+}
+
+fn flatten_inlines(toks: &[InlineTok], depths: &mut [usize; 2], link: Option<&str>, out: &mut Vec<MarkdownInline>) {
+    let push = | out: &mut Vec<MarkdownInline>, text: &str, depths: &[usize; 2], code: bool | {
+        let style = MarkdownStyle {strong: depths[0] > 0, emphasis: depths[1] > 0, code};
+        let link = link.map( | link | link.to_string());
+        if let Some(last) = out.last_mut() {
+            if last.style == style && last.link == link {
+                last.text.push_str(text);
+                return
+            }
+        }
+        out.push(MarkdownInline {text: text.to_string(), style, link});
+    };
+    for tok in toks {
+        match tok {
+            InlineTok::Text(text) => push(out, text, depths, false),
+            InlineTok::Code(code) => push(out, code, depths, true),
+            InlineTok::Delim {delim, count, opens, closes, ..} => {
+                depths[0] -= closes[0];
+                depths[1] -= closes[1];
+                if *count > 0 {
+                    push(out, &delim.to_string().repeat(*count), depths, false);
+                }
+                depths[0] += opens[0];
+                depths[1] += opens[1];
+            }
+            InlineTok::Link(url, inner) => flatten_inlines(inner, depths, Some(url), out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> MarkdownInline {
+        MarkdownInline {text: text.to_string(), style: MarkdownStyle::default(), link: None}
+    }
+
+    fn styled(text: &str, strong: bool, emphasis: bool, code: bool) -> MarkdownInline {
+        MarkdownInline {text: text.to_string(), style: MarkdownStyle {strong, emphasis, code}, link: None}
+    }
+
+    fn kinds(blocks: &[MarkdownBlock]) -> Vec<MarkdownBlockKind> {
+        blocks.iter().map( | block | block.kind.clone()).collect()
+    }
+
+    #[test]
+    fn test_headings() {
+        let blocks = parse_markdown("# One #\n###### Six\n####### seven\n#nope\n\nSetext\n===\nTwo\n---");
+        assert_eq!(kinds(&blocks), vec![
+            MarkdownBlockKind::Heading(1),
+            MarkdownBlockKind::Heading(6),
+            MarkdownBlockKind::Paragraph,
+            MarkdownBlockKind::Heading(1),
+            MarkdownBlockKind::Heading(2),
+        ]);
+        assert_eq!(blocks[0].text(), "One");
+        assert_eq!(blocks[1].text(), "Six");
+        assert_eq!(blocks[2].text(), "####### seven #nope");
+        assert_eq!(blocks[3].text(), "Setext");
+        assert_eq!(blocks[4].text(), "Two");
+    }
+
+    #[test]
+    fn test_lists() {
+        let blocks = parse_markdown("- a\n  continued\n  - nested\n3. three\n\ntext 2. not a list\n1) one");
+        assert_eq!(kinds(&blocks), vec![
+            MarkdownBlockKind::ListItem(MarkdownListMarker::Bullet),
+            MarkdownBlockKind::ListItem(MarkdownListMarker::Bullet),
+            MarkdownBlockKind::ListItem(MarkdownListMarker::Number(3)),
+            MarkdownBlockKind::Paragraph,
+            MarkdownBlockKind::ListItem(MarkdownListMarker::Number(1)),
+        ]);
+        assert_eq!(blocks[0].text(), "a continued");
+        assert_eq!(blocks[0].list_depth, 1);
+        assert_eq!(blocks[1].text(), "nested");
+        assert_eq!(blocks[1].list_depth, 2);
+        assert_eq!(blocks[2].list_depth, 1);
+        assert_eq!(blocks[3].text(), "text 2. not a list");
+        assert_eq!(blocks[3].list_depth, 0);
+        // a rule is not a bullet
+        assert_eq!(kinds(&parse_markdown("- - -")), vec![MarkdownBlockKind::Rule]);
+    }
+
+    #[test]
+    fn test_quotes() {
+        let blocks = parse_markdown("> quoted\nlazy\n> > deeper\n\nafter");
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].text(), "quoted lazy");
+        assert_eq!(blocks[0].quote_depth, 1);
+        assert_eq!(blocks[1].text(), "deeper");
+        assert_eq!(blocks[1].quote_depth, 2);
+        assert_eq!(blocks[2].text(), "after");
+        assert_eq!(blocks[2].quote_depth, 0);
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let blocks = parse_markdown("```rust\nfn main() {\n\n    # not a heading\n```\n    indented\n    code\n\n~~~\nunclosed");
+        assert_eq!(kinds(&blocks), vec![
+            MarkdownBlockKind::CodeBlock {lang: "rust".to_string()},
+            MarkdownBlockKind::CodeBlock {lang: String::new()},
+            MarkdownBlockKind::CodeBlock {lang: String::new()},
+        ]);
+        assert_eq!(blocks[0].inlines, vec![styled("fn main() {\n\n    # not a heading", false, false, true)]);
+        assert_eq!(blocks[1].text(), "indented\ncode");
+        assert_eq!(blocks[2].text(), "unclosed");
+        // an indented line continues a paragraph instead of starting code
+        assert_eq!(parse_markdown("para\n    more")[0].text(), "para more");
+    }
+
+    #[test]
+    fn test_emphasis() {
+        assert_eq!(parse_inlines("a *b* **c** ***d***"), vec![
+            text("a "),
+            styled("b", false, true, false),
+            text(" "),
+            styled("c", true, false, false),
+            text(" "),
+            styled("d", true, true, false),
+        ]);
+        // underscores inside words and unmatched delimiters stay text
+        assert_eq!(parse_inlines("snake_case_name"), vec![text("snake_case_name")]);
+        assert_eq!(parse_inlines("2 * 3 *x"), vec![text("2 * 3 *x")]);
+        assert_eq!(parse_inlines("**a*"), vec![text("*"), styled("a", false, true, false)]);
+    }
+
+    #[test]
+    fn test_code_spans_and_escapes() {
+        assert_eq!(parse_inlines("`a*b*` ``c`d`` \\*e\\* &amp; &#x41; &bogus;"), vec![
+            styled("a*b*", false, false, true),
+            text(" "),
+            styled("c`d", false, false, true),
+            text(" *e* & A &bogus;"),
+        ]);
+        assert_eq!(parse_inlines("` a `"), vec![styled("a", false, false, true)]);
+        assert_eq!(parse_inlines("`open"), vec![text("`open")]);
+    }
+
+    #[test]
+    fn test_links() {
+        let link = | text: &str, url: &str, strong: bool | MarkdownInline {
+            text: text.to_string(),
+            style: MarkdownStyle {strong, ..Default::default()},
+            link: Some(url.to_string())
+        };
+        assert_eq!(parse_inlines("see [the **docs**](https://a.b/c_(d) \"title\") or <https://x.y>"), vec![
+            text("see "),
+            link("the ", "https://a.b/c_(d)", false),
+            link("docs", "https://a.b/c_(d)", true),
+            text(" or "),
+            link("https://x.y", "https://x.y", false),
+        ]);
+        // images are replaced by their alt text, broken links stay text
+        assert_eq!(parse_inlines("![alt](img.png) [no link] <not a link>"), vec![text("alt [no link] <not a link>")]);
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        assert_eq!(parse_markdown("soft\nbreak  \nhard\\\nescaped")[0].inlines, vec![text("soft break\nhard\nescaped")]);
+    }
+
+    #[test]
+    fn test_append_matches_parse() {
+        let source = "# Title\n\nSome *text* that\nstreams in.\n\n- item one\n- item [two](http://x)\n\n```\ncode\n```\n> quote\n";
+        for split in 0..=source.len() {
+            if !source.is_char_boundary(split) {
+                continue;
+            }
+            let mut doc = MarkdownDoc::parse(&source[..split]);
+            doc.append(&source[split..]);
+            assert_eq!(doc.blocks, parse_markdown(source), "split at {}", split);
+        }
+        // appending char by char
+        let mut doc = MarkdownDoc::default();
+        for c in source.chars() {
+            doc.append(&c.to_string());
+        }
+        assert_eq!(doc.blocks, parse_markdown(source));
+        assert_eq!(doc.source(), source);
+    }
+}
@@ -0,0 +1,1022 @@
+//! A small backtracking regular expression engine for searching within a single line.
+//!
+//! Supported syntax: literals, `.`, character classes (`[a-z]`, `[^...]`), the escapes `\d`, `\w`,
+//! `\s`, `\D`, `\W`, `\S`, `\t`, `\n` and `\r`, the anchors `^`, `$`, `\b` and `\B`, capturing and
+//! non-capturing groups, alternation, and the greedy and lazy quantifiers `*`, `+`, `?` and
+//! `{n,m}`. Every (instruction, position) pair is visited at most once per search, so a search
+//! takes time linear in the length of the program times the length of the string.
+
+use std::{error, fmt, ops::Range};
+
+const MAX_REPEAT_COUNT: usize = 1000;
+const MAX_PROGRAM_LEN: usize = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct Regex {
+    program: Vec<Inst>,
+    group_count: usize,
+    case_sensitive: bool,
+}
+
+impl Regex {
+    pub fn new(pattern: &str, case_sensitive: bool) -> Result<Self, RegexError> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            index: 0,
+            group_count: 1,
+        };
+        let node = parser.parse_alternation()?;
+        if parser.index < parser.chars.len() {
+            return Err(RegexError::UnmatchedCloseParen);
+        }
+        let mut compiler = Compiler { program: Vec::new() };
+        compiler.program.push(Inst::Save(0));
+        compiler.compile(&node)?;
+        compiler.program.push(Inst::Save(1));
+        compiler.program.push(Inst::Match);
+        Ok(Self {
+            program: compiler.program,
+            group_count: parser.group_count,
+            case_sensitive,
+        })
+    }
+
+    /// Escapes all characters in `string` that have a special meaning in a pattern.
+    pub fn escape(string: &str) -> String {
+        let mut escaped = String::with_capacity(string.len());
+        for char in string.chars() {
+            if is_meta_char(char) {
+                escaped.push('\\');
+            }
+            escaped.push(char);
+        }
+        escaped
+    }
+
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// The number of capture groups, including the implicit group for the whole match.
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    pub fn is_match(&self, string: &str) -> bool {
+        self.find_at(string, 0).is_some()
+    }
+
+    /// Finds the leftmost match that starts at or after the byte index `start`.
+    pub fn find_at(&self, string: &str, start: usize) -> Option<Captures> {
+        let mut cache = Cache::new(self, string, start);
+        self.find_at_with_cache(string, start, &mut cache)
+    }
+
+    /// Iterates over all non-overlapping matches in `string`.
+    pub fn captures_iter<'a>(&'a self, string: &'a str) -> CapturesIter<'a> {
+        CapturesIter {
+            regex: self,
+            string,
+            position: Some(0),
+            last_match_end: None,
+            cache: None,
+        }
+    }
+
+    fn find_at_with_cache(&self, string: &str, start: usize, cache: &mut Cache) -> Option<Captures> {
+        // A failed (instruction, position) pair fails for every start position, so the visited
+        // set is kept while we try the next start, but a match leaves visited pairs that can
+        // still match.
+        cache.visited.clear();
+        let mut position = start;
+        loop {
+            if let Some(captures) = self.run(string, position, cache) {
+                return Some(captures);
+            }
+            match string[position..].chars().next() {
+                Some(char) => position += char.len_utf8(),
+                None => return None,
+            }
+        }
+    }
+
+    fn run(&self, string: &str, position: usize, cache: &mut Cache) -> Option<Captures> {
+        let Cache {
+            visited,
+            slots,
+            stack,
+        } = cache;
+        for slot in slots.iter_mut() {
+            *slot = None;
+        }
+        stack.clear();
+        stack.push(Job::Inst { pc: 0, position });
+        while let Some(job) = stack.pop() {
+            let (mut pc, mut position) = match job {
+                Job::Inst { pc, position } => (pc, position),
+                Job::Restore { slot, value } => {
+                    slots[slot] = value;
+                    continue;
+                }
+            };
+            loop {
+                // If we got here before, we already know that this path leads nowhere.
+                if !visited.insert(pc, position) {
+                    break;
+                }
+                match self.program[pc] {
+                    Inst::Char(expected) => match string[position..].chars().next() {
+                        Some(char) if chars_eq(char, expected, self.case_sensitive) => {
+                            position += char.len_utf8();
+                            pc += 1;
+                        }
+                        _ => break,
+                    },
+                    Inst::Any => match string[position..].chars().next() {
+                        Some(char) => {
+                            position += char.len_utf8();
+                            pc += 1;
+                        }
+                        None => break,
+                    },
+                    Inst::Class(ref class) => match string[position..].chars().next() {
+                        Some(char) if class.matches(char, self.case_sensitive) => {
+                            position += char.len_utf8();
+                            pc += 1;
+                        }
+                        _ => break,
+                    },
+                    Inst::Assert(assertion) => {
+                        if !assertion.holds(string, position) {
+                            break;
+                        }
+                        pc += 1;
+                    }
+                    Inst::Split(first, second) => {
+                        stack.push(Job::Inst {
+                            pc: second,
+                            position,
+                        });
+                        pc = first;
+                    }
+                    Inst::Jump(target) => pc = target,
+                    Inst::Save(slot) => {
+                        stack.push(Job::Restore {
+                            slot,
+                            value: slots[slot],
+                        });
+                        slots[slot] = Some(position);
+                        pc += 1;
+                    }
+                    Inst::Match => {
+                        return Some(Captures {
+                            slots: slots.to_vec(),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CapturesIter<'a> {
+    regex: &'a Regex,
+    string: &'a str,
+    position: Option<usize>,
+    last_match_end: Option<usize>,
+    cache: Option<Cache>,
+}
+
+impl<'a> Iterator for CapturesIter<'a> {
+    type Item = Captures;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let position = self.position?;
+            let cache = self
+                .cache
+                .get_or_insert_with(|| Cache::new(self.regex, self.string, 0));
+            let captures = match self.regex.find_at_with_cache(self.string, position, cache) {
+                Some(captures) => captures,
+                None => {
+                    self.position = None;
+                    return None;
+                }
+            };
+            let range = captures.range();
+            if range.is_empty() {
+                // Step over the next char so that we don't find the same empty match again.
+                self.position = self.string[range.end..]
+                    .chars()
+                    .next()
+                    .map(|char| range.end + char.len_utf8());
+                // An empty match right where the previous match ended doesn't count.
+                if Some(range.end) == self.last_match_end {
+                    continue;
+                }
+            } else {
+                self.position = Some(range.end);
+            }
+            self.last_match_end = Some(range.end);
+            return Some(captures);
+        }
+    }
+}
+
+/// The byte ranges of a match and its capture groups.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Captures {
+    slots: Vec<Option<usize>>,
+}
+
+impl Captures {
+    /// The byte range of the whole match.
+    pub fn range(&self) -> Range<usize> {
+        self.get(0).unwrap()
+    }
+
+    /// The byte range of the given capture group, if it participated in the match.
+    pub fn get(&self, index: usize) -> Option<Range<usize>> {
+        match (self.slots.get(index * 2)?, self.slots.get(index * 2 + 1)?) {
+            (&Some(start), &Some(end)) => Some(start..end),
+            _ => None,
+        }
+    }
+
+    /// Appends `replacement` to `output`, with `$n` and `${n}` replaced by the text of capture group
+    /// `n` in `string`, and `$$` replaced by a single `$`.
+    pub fn expand(&self, string: &str, replacement: &str, output: &mut String) {
+        let mut rest = replacement;
+        while let Some(index) = rest.find('$') {
+            output.push_str(&rest[..index]);
+            rest = &rest[index + 1..];
+            if let Some(tail) = rest.strip_prefix('$') {
+                output.push('$');
+                rest = tail;
+                continue;
+            }
+            let (digits, tail) = match rest.strip_prefix('{') {
+                Some(braced) => match braced.find('}') {
+                    Some(end) if end > 0 && braced[..end].bytes().all(|byte| byte.is_ascii_digit()) => {
+                        (&braced[..end], &braced[end + 1..])
+                    }
+                    _ => ("", rest),
+                },
+                None => {
+                    let end = rest
+                        .find(|char: char| !char.is_ascii_digit())
+                        .unwrap_or(rest.len());
+                    (&rest[..end], &rest[end..])
+                }
+            };
+            if digits.is_empty() {
+                output.push('$');
+                continue;
+            }
+            if let Some(range) = digits.parse().ok().and_then(|index| self.get(index)) {
+                output.push_str(&string[range]);
+            }
+            rest = tail;
+        }
+        output.push_str(rest);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RegexError {
+    UnexpectedEnd,
+    UnmatchedOpenParen,
+    UnmatchedCloseParen,
+    UnmatchedOpenBracket,
+    InvalidRange,
+    InvalidRepeat,
+    NothingToRepeat,
+    UnsupportedGroup,
+    TooLarge,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::UnexpectedEnd => "unexpected end of pattern",
+            Self::UnmatchedOpenParen => "unmatched `(`",
+            Self::UnmatchedCloseParen => "unmatched `)`",
+            Self::UnmatchedOpenBracket => "unmatched `[`",
+            Self::InvalidRange => "invalid character range",
+            Self::InvalidRepeat => "invalid repetition count",
+            Self::NothingToRepeat => "nothing to repeat",
+            Self::UnsupportedGroup => "unsupported group syntax",
+            Self::TooLarge => "pattern is too large",
+        })
+    }
+}
+
+impl error::Error for RegexError {}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        is_greedy: bool,
+    },
+}
+
+impl Node {
+    fn can_match_empty(&self) -> bool {
+        match self {
+            Self::Empty | Self::Assert(_) => true,
+            Self::Char(_) | Self::Any | Self::Class(_) => false,
+            Self::Group(node, _) => node.can_match_empty(),
+            Self::Concat(nodes) => nodes.iter().all(|node| node.can_match_empty()),
+            Self::Alternation(nodes) => nodes.iter().any(|node| node.can_match_empty()),
+            Self::Repeat { node, min, .. } => *min == 0 || node.can_match_empty(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Assertion {
+    LineStart,
+    LineEnd,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+impl Assertion {
+    fn holds(self, string: &str, position: usize) -> bool {
+        match self {
+            Self::LineStart => position == 0,
+            Self::LineEnd => position == string.len(),
+            Self::WordBoundary | Self::NotWordBoundary => {
+                let is_word_before = string[..position]
+                    .chars()
+                    .next_back()
+                    .is_some_and(is_word_char);
+                let is_word_after = string[position..].chars().next().is_some_and(is_word_char);
+                (is_word_before != is_word_after) == (self == Self::WordBoundary)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Class {
+    is_negated: bool,
+    items: Vec<ClassItem>,
+}
+
+impl Class {
+    fn matches(&self, char: char, case_sensitive: bool) -> bool {
+        let matches = |char| self.items.iter().any(|item| item.matches(char));
+        let is_match = matches(char)
+            || !case_sensitive
+                && (char.to_lowercase().any(matches) || char.to_uppercase().any(matches));
+        is_match != self.is_negated
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ClassItem {
+    Range(char, char),
+    Perl(PerlClass, bool),
+}
+
+impl ClassItem {
+    fn matches(self, char: char) -> bool {
+        match self {
+            Self::Range(start, end) => (start..=end).contains(&char),
+            Self::Perl(class, is_negated) => class.matches(char) != is_negated,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum PerlClass {
+    Digit,
+    Word,
+    Space,
+}
+
+impl PerlClass {
+    fn matches(self, char: char) -> bool {
+        match self {
+            Self::Digit => char.is_ascii_digit(),
+            Self::Word => is_word_char(char),
+            Self::Space => char.is_whitespace(),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    group_count: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Result<char, RegexError> {
+        let char = self.peek().ok_or(RegexError::UnexpectedEnd)?;
+        self.index += 1;
+        Ok(char)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = vec![self.parse_concat()?];
+        while self.eat('|') {
+            nodes.push(self.parse_concat()?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            Node::Alternation(nodes)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(char) = self.peek() {
+            if char == '|' || char == ')' {
+                break;
+            }
+            let node = self.parse_atom()?;
+            nodes.push(self.parse_repeat(node)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_repeat(&mut self, mut node: Node) -> Result<Node, RegexError> {
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => {
+                    self.index += 1;
+                    (0, None)
+                }
+                Some('+') => {
+                    self.index += 1;
+                    (1, None)
+                }
+                Some('?') => {
+                    self.index += 1;
+                    (0, Some(1))
+                }
+                Some('{') => match self.parse_counted_repeat()? {
+                    Some(counts) => counts,
+                    None => return Ok(node),
+                },
+                _ => return Ok(node),
+            };
+            if matches!(node, Node::Empty | Node::Assert(_)) {
+                return Err(RegexError::NothingToRepeat);
+            }
+            let is_greedy = !self.eat('?');
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+                is_greedy,
+            };
+        }
+    }
+
+    // Parses `{n}`, `{n,}` or `{n,m}`. A `{` that doesn't start a valid repeat is a literal.
+    fn parse_counted_repeat(&mut self) -> Result<Option<(usize, Option<usize>)>, RegexError> {
+        let start = self.index;
+        self.index += 1;
+        let min = self.parse_count();
+        let max = if self.eat(',') {
+            self.parse_count()
+        } else {
+            min
+        };
+        if min.is_none() || !self.eat('}') {
+            self.index = start;
+            return Ok(None);
+        }
+        let min = min.unwrap();
+        if min > MAX_REPEAT_COUNT || max.is_some_and(|max| max < min || max > MAX_REPEAT_COUNT) {
+            return Err(RegexError::InvalidRepeat);
+        }
+        Ok(Some((min, max)))
+    }
+
+    fn parse_count(&mut self) -> Option<usize> {
+        let start = self.index;
+        while self.peek().is_some_and(|char| char.is_ascii_digit()) {
+            self.index += 1;
+        }
+        self.chars[start..self.index]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        Ok(match self.next()? {
+            '(' => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err(RegexError::UnsupportedGroup);
+                    }
+                    None
+                } else {
+                    self.group_count += 1;
+                    Some(self.group_count - 1)
+                };
+                let node = self.parse_alternation()?;
+                if !self.eat(')') {
+                    return Err(RegexError::UnmatchedOpenParen);
+                }
+                Node::Group(Box::new(node), index)
+            }
+            '[' => Node::Class(self.parse_class()?),
+            '.' => Node::Any,
+            '^' => Node::Assert(Assertion::LineStart),
+            '$' => Node::Assert(Assertion::LineEnd),
+            '*' | '+' | '?' => return Err(RegexError::NothingToRepeat),
+            '\\' => match self.next()? {
+                'b' => Node::Assert(Assertion::WordBoundary),
+                'B' => Node::Assert(Assertion::NotWordBoundary),
+                char => match perl_class(char) {
+                    Some(item) => Node::Class(Class {
+                        is_negated: false,
+                        items: vec![item],
+                    }),
+                    None => Node::Char(escaped_char(char)),
+                },
+            },
+            char => Node::Char(char),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Class, RegexError> {
+        let mut class = Class {
+            is_negated: self.eat('^'),
+            items: Vec::new(),
+        };
+        // A `]` right at the start is a literal.
+        let mut is_first = true;
+        loop {
+            let char = self.next().map_err(|_| RegexError::UnmatchedOpenBracket)?;
+            if char == ']' && !is_first {
+                return Ok(class);
+            }
+            is_first = false;
+            let start = if char == '\\' {
+                let char = self.next().map_err(|_| RegexError::UnmatchedOpenBracket)?;
+                if let Some(item) = perl_class(char) {
+                    class.items.push(item);
+                    continue;
+                }
+                escaped_char(char)
+            } else {
+                char
+            };
+            let end = if self.peek() == Some('-') && self.chars.get(self.index + 1) != Some(&']') {
+                self.index += 1;
+                match self.next().map_err(|_| RegexError::UnmatchedOpenBracket)? {
+                    '\\' => escaped_char(self.next().map_err(|_| RegexError::UnmatchedOpenBracket)?),
+                    char => char,
+                }
+            } else {
+                start
+            };
+            if end < start {
+                return Err(RegexError::InvalidRange);
+            }
+            class.items.push(ClassItem::Range(start, end));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Split(usize, usize),
+    Jump(usize),
+    Save(usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn compile(&mut self, node: &Node) -> Result<(), RegexError> {
+        if self.program.len() > MAX_PROGRAM_LEN {
+            return Err(RegexError::TooLarge);
+        }
+        match *node {
+            Node::Empty => {}
+            Node::Char(char) => self.program.push(Inst::Char(char)),
+            Node::Any => self.program.push(Inst::Any),
+            Node::Class(ref class) => self.program.push(Inst::Class(class.clone())),
+            Node::Assert(assertion) => self.program.push(Inst::Assert(assertion)),
+            Node::Group(ref node, index) => match index {
+                Some(index) => {
+                    self.program.push(Inst::Save(index * 2));
+                    self.compile(node)?;
+                    self.program.push(Inst::Save(index * 2 + 1));
+                }
+                None => self.compile(node)?,
+            },
+            Node::Concat(ref nodes) => {
+                for node in nodes {
+                    self.compile(node)?;
+                }
+            }
+            Node::Alternation(ref nodes) => {
+                let mut jumps = Vec::new();
+                for (index, node) in nodes.iter().enumerate() {
+                    if index + 1 < nodes.len() {
+                        let split = self.push_placeholder();
+                        self.compile(node)?;
+                        jumps.push(self.push_placeholder());
+                        let next = self.program.len();
+                        self.program[split] = Inst::Split(split + 1, next);
+                    } else {
+                        self.compile(node)?;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                ref node,
+                min,
+                max,
+                is_greedy,
+            } => {
+                match max {
+                    None => {
+                        // The last required repetition loops back to its own start, and a loop
+                        // whose body can match the empty string is compiled as `(x+)?` so that an
+                        // empty iteration still sets the captures in it, like the `regex` crate.
+                        for _ in 1..min {
+                            self.compile(node)?;
+                        }
+                        let skip = if min == 0 && node.can_match_empty() {
+                            Some(self.push_placeholder())
+                        } else {
+                            None
+                        };
+                        if min == 0 && skip.is_none() {
+                            // L0: split L1, L2; L1: node; jump L0; L2:
+                            let split = self.push_placeholder();
+                            self.compile(node)?;
+                            self.program.push(Inst::Jump(split));
+                            let end = self.program.len();
+                            self.program[split] = self.split(split + 1, end, is_greedy);
+                        } else {
+                            // L0: node; split L0, L1; L1:
+                            let start = self.program.len();
+                            self.compile(node)?;
+                            let split = self.push_placeholder();
+                            self.program[split] = self.split(start, split + 1, is_greedy);
+                        }
+                        if let Some(skip) = skip {
+                            let end = self.program.len();
+                            self.program[skip] = self.split(skip + 1, end, is_greedy);
+                        }
+                    }
+                    Some(max) => {
+                        for _ in 0..min {
+                            self.compile(node)?;
+                        }
+                        // Each optional repetition can skip all the remaining ones.
+                        let mut splits = Vec::new();
+                        for _ in min..max {
+                            splits.push(self.push_placeholder());
+                            self.compile(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = self.split(split + 1, end, is_greedy);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push_placeholder(&mut self) -> usize {
+        self.program.push(Inst::Match);
+        self.program.len() - 1
+    }
+
+    fn split(&self, body: usize, next: usize, is_greedy: bool) -> Inst {
+        if is_greedy {
+            Inst::Split(body, next)
+        } else {
+            Inst::Split(next, body)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Job {
+    Inst { pc: usize, position: usize },
+    Restore { slot: usize, value: Option<usize> },
+}
+
+// The buffers a search needs, reused across the matches of a `CapturesIter`.
+#[derive(Clone, Debug)]
+struct Cache {
+    visited: Visited,
+    slots: Vec<Option<usize>>,
+    stack: Vec<Job>,
+}
+
+impl Cache {
+    fn new(regex: &Regex, string: &str, start: usize) -> Self {
+        Self {
+            visited: Visited::new(regex.program.len(), start..string.len() + 1),
+            slots: vec![None; regex.group_count * 2],
+            stack: Vec::new(),
+        }
+    }
+}
+
+// A bitset of (instruction, position) pairs for the positions in the window being searched.
+#[derive(Clone, Debug)]
+struct Visited {
+    window: Range<usize>,
+    bits: Vec<u64>,
+    // The words that have bits set, so that clearing costs no more than the search did.
+    dirty_words: Vec<usize>,
+}
+
+impl Visited {
+    fn new(pc_count: usize, window: Range<usize>) -> Self {
+        Self {
+            bits: vec![0; (pc_count * window.len()).div_ceil(64)],
+            window,
+            dirty_words: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, pc: usize, position: usize) -> bool {
+        let index = pc * self.window.len() + position - self.window.start;
+        let mask = 1 << (index % 64);
+        let word = &mut self.bits[index / 64];
+        if *word & mask != 0 {
+            return false;
+        }
+        if *word == 0 {
+            self.dirty_words.push(index / 64);
+        }
+        *word |= mask;
+        true
+    }
+
+    fn clear(&mut self) {
+        for word in self.dirty_words.drain(..) {
+            self.bits[word] = 0;
+        }
+    }
+}
+
+fn perl_class(char: char) -> Option<ClassItem> {
+    Some(match char {
+        'd' => ClassItem::Perl(PerlClass::Digit, false),
+        'D' => ClassItem::Perl(PerlClass::Digit, true),
+        'w' => ClassItem::Perl(PerlClass::Word, false),
+        'W' => ClassItem::Perl(PerlClass::Word, true),
+        's' => ClassItem::Perl(PerlClass::Space, false),
+        'S' => ClassItem::Perl(PerlClass::Space, true),
+        _ => return None,
+    })
+}
+
+fn escaped_char(char: char) -> char {
+    match char {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        '0' => '\0',
+        char => char,
+    }
+}
+
+fn chars_eq(char: char, expected: char, case_sensitive: bool) -> bool {
+    char == expected
+        || !case_sensitive && char.to_lowercase().eq(expected.to_lowercase())
+}
+
+fn is_meta_char(char: char) -> bool {
+    matches!(
+        char,
+        '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$'
+    )
+}
+
+pub fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, string: &str) -> Option<Range<usize>> {
+        Regex::new(pattern, true).unwrap().find_at(string, 0).map(|captures| captures.range())
+    }
+
+    fn find_all<'a>(pattern: &str, string: &'a str) -> Vec<&'a str> {
+        Regex::new(pattern, true)
+            .unwrap()
+            .captures_iter(string)
+            .map(|captures| &string[captures.range()])
+            .collect()
+    }
+
+    fn groups(captures: &Captures, group_count: usize) -> Vec<Option<Range<usize>>> {
+        (0..group_count).map(|index| captures.get(index)).collect()
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Regex::new("(a", true).unwrap_err(), RegexError::UnmatchedOpenParen);
+        assert_eq!(Regex::new("a)", true).unwrap_err(), RegexError::UnmatchedCloseParen);
+        assert_eq!(Regex::new("[a", true).unwrap_err(), RegexError::UnmatchedOpenBracket);
+        assert_eq!(Regex::new("[z-a]", true).unwrap_err(), RegexError::InvalidRange);
+        assert_eq!(Regex::new("a{3,2}", true).unwrap_err(), RegexError::InvalidRepeat);
+        assert_eq!(Regex::new("a{1001}", true).unwrap_err(), RegexError::InvalidRepeat);
+        assert_eq!(Regex::new("*a", true).unwrap_err(), RegexError::NothingToRepeat);
+        assert_eq!(Regex::new("^*", true).unwrap_err(), RegexError::NothingToRepeat);
+        assert_eq!(Regex::new("(?=a)", true).unwrap_err(), RegexError::UnsupportedGroup);
+        assert_eq!(Regex::new("a\\", true).unwrap_err(), RegexError::UnexpectedEnd);
+        assert_eq!(Regex::new("(?:a{1000}){1000}", true).unwrap_err(), RegexError::TooLarge);
+    }
+
+    #[test]
+    fn parse_literal_braces_and_brackets() {
+        assert_eq!(find("a{", "a{"), Some(0..2));
+        assert_eq!(find("a{x}", "a{x}"), Some(0..4));
+        assert_eq!(find("a{,2}", "a{,2}"), Some(0..5));
+        assert_eq!(find("[]a]+", "x]a]"), Some(1..4));
+        assert_eq!(find("[a-]+", "b-a-"), Some(1..4));
+        assert_eq!(find("[\\]\\-]+", "a]-"), Some(1..3));
+    }
+
+    #[test]
+    fn classes_and_escapes() {
+        assert_eq!(find_all("\\d+", "a12 b345"), ["12", "345"]);
+        assert_eq!(find_all("\\w+", "foo_bar baz"), ["foo_bar", "baz"]);
+        assert_eq!(find_all("[^\\s]+", " a  bc "), ["a", "bc"]);
+        assert_eq!(find_all("[\\d.]+", "v1.25 x"), ["1.25"]);
+        assert_eq!(find("\\t", "a\tb"), Some(1..2));
+        assert_eq!(find("a.c", "aéc"), Some(0..4));
+    }
+
+    #[test]
+    fn anchors_and_word_boundaries() {
+        assert_eq!(find("^a", "ba"), None);
+        assert_eq!(find("a$", "ab a"), Some(3..4));
+        assert_eq!(find_all("\\bab\\b", "ab abc cab ab"), ["ab", "ab"]);
+        assert_eq!(find_all("\\Bb\\B", "abc b"), ["b"]);
+    }
+
+    #[test]
+    fn alternation_prefers_the_first_branch() {
+        assert_eq!(find("ab|abc", "abc"), Some(0..2));
+        assert_eq!(find("abc|ab", "abc"), Some(0..3));
+    }
+
+    #[test]
+    fn greedy_and_lazy_repeats() {
+        assert_eq!(find("a+", "aaa"), Some(0..3));
+        assert_eq!(find("a+?", "aaa"), Some(0..1));
+        assert_eq!(find("a{2}", "aaa"), Some(0..2));
+        assert_eq!(find("a{1,2}?", "aaa"), Some(0..1));
+        assert_eq!(find("a{2,}", "aaaa"), Some(0..4));
+        assert_eq!(find("<.*>", "<a><b>"), Some(0..6));
+        assert_eq!(find("<.*?>", "<a><b>"), Some(0..3));
+    }
+
+    #[test]
+    fn capture_groups() {
+        let regex = Regex::new("(a)|(b)", true).unwrap();
+        assert_eq!(regex.group_count(), 3);
+        let captures = regex.find_at("b", 0).unwrap();
+        assert_eq!(groups(&captures, 3), [Some(0..1), None, Some(0..1)]);
+        // the last iteration of a repeated group wins
+        let captures = Regex::new("(\\w)+", true).unwrap().find_at("abc", 0).unwrap();
+        assert_eq!(captures.get(1), Some(2..3));
+        // an empty iteration of a loop still sets its groups
+        let captures = Regex::new("(a*)*", true).unwrap().find_at("b", 0).unwrap();
+        assert_eq!(groups(&captures, 2), [Some(0..0), Some(0..0)]);
+        assert_eq!(Regex::new("(?:a)(b)", true).unwrap().group_count(), 2);
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let regex = Regex::new("straße[a-c]", false).unwrap();
+        assert!(regex.is_match("STRAßEB"));
+        assert!(!Regex::new("abc", true).unwrap().is_match("ABC"));
+    }
+
+    #[test]
+    fn find_at_starts_at_a_byte_index() {
+        let regex = Regex::new("é", true).unwrap();
+        assert_eq!(regex.find_at("éaé", 2).map(|captures| captures.range()), Some(3..5));
+        assert_eq!(regex.find_at("éaé", 5), None);
+    }
+
+    #[test]
+    fn empty_matches() {
+        assert_eq!(find_all("a*", "baab"), ["", "aa", ""]);
+        assert_eq!(find_all("", "aé"), ["", "", ""]);
+        assert_eq!(find_all("\\b", "ab cd").len(), 4);
+    }
+
+    #[test]
+    fn escape_and_expand() {
+        let pattern = Regex::escape("a.b*(c)");
+        assert_eq!(pattern, "a\\.b\\*\\(c\\)");
+        assert_eq!(find(&pattern, "xa.b*(c)"), Some(1..8));
+        let string = "john smith";
+        let captures = Regex::new("(\\w+) (\\w+)", true).unwrap().find_at(string, 0).unwrap();
+        let mut output = String::new();
+        captures.expand(string, "$2, ${1}$$ $9 $x", &mut output);
+        assert_eq!(output, "smith, john$  $x");
+    }
+
+    // All matches with their capture groups, as the `regex` crate finds them.
+    #[test]
+    fn matches_and_groups() {
+        type Groups = &'static [Option<Range<usize>>];
+        #[rustfmt::skip]
+        const CASES: &[(&str, bool, &str, &[Groups])] = &[
+            ("(a*)*", true, "b", &[&[Some(0..0), Some(0..0)], &[Some(1..1), Some(1..1)]]),
+            ("(a*)*", true, "aab", &[&[Some(0..2), Some(0..2)], &[Some(3..3), Some(3..3)]]),
+            ("(a*)+", true, "ab", &[&[Some(0..1), Some(0..1)], &[Some(2..2), Some(2..2)]]),
+            ("(|a)*", true, "aa", &[&[Some(0..0), Some(0..0)], &[Some(1..1), Some(1..1)], &[Some(2..2), Some(2..2)]]),
+            ("(?:a|)*b", true, "aab", &[&[Some(0..3)]]),
+            ("()*", true, "ab", &[&[Some(0..0), Some(0..0)], &[Some(1..1), Some(1..1)], &[Some(2..2), Some(2..2)]]),
+            ("(a|ab)(c|bcd)", true, "abcd", &[&[Some(0..4), Some(0..1), Some(1..4)]]),
+            ("a*", true, "baab", &[&[Some(0..0)], &[Some(1..3)], &[Some(4..4)]]),
+            ("a*?", true, "aa", &[&[Some(0..0)], &[Some(1..1)], &[Some(2..2)]]),
+            ("\\b", true, "ab cd", &[&[Some(0..0)], &[Some(2..2)], &[Some(3..3)], &[Some(5..5)]]),
+            ("\\B", true, "ab c", &[&[Some(1..1)]]),
+            ("(\\w+)\\s*", true, "é aé", &[&[Some(0..3), Some(0..2)], &[Some(3..6), Some(3..6)]]),
+            ("[^a]", true, "éaÉ", &[&[Some(0..2)], &[Some(3..5)]]),
+            ("(a|b)+", false, "aAbB", &[&[Some(0..4), Some(3..4)]]),
+            ("É", false, "éÉ", &[&[Some(0..2)], &[Some(2..4)]]),
+            ("(a+)|b*", true, "baab", &[&[Some(0..1), None], &[Some(1..3), Some(1..3)], &[Some(3..4), None]]),
+            ("x*", true, "", &[&[Some(0..0)]]),
+            ("(a?){2}", true, "a", &[&[Some(0..1), Some(1..1)]]),
+            ("(b*){2,}", true, "ab", &[&[Some(0..0), Some(0..0)], &[Some(1..2), Some(2..2)]]),
+            ("$", true, "ab", &[&[Some(2..2)]]),
+            ("^|a", true, "aa", &[&[Some(0..0)], &[Some(1..2)]]),
+            ("(?:(a)|b)*", true, "ab", &[&[Some(0..2), Some(0..1)]]),
+            ("(a)|b", true, "ab", &[&[Some(0..1), Some(0..1)], &[Some(1..2), None]]),
+            ("((a)|b)+", true, "ab", &[&[Some(0..2), Some(1..2), Some(0..1)]]),
+            ("(a*)(b?)", true, "ba", &[&[Some(0..1), Some(0..0), Some(0..1)], &[Some(1..2), Some(1..2), Some(2..2)]]),
+        ];
+        for (pattern, case_sensitive, string, expected) in CASES {
+            let regex = Regex::new(pattern, *case_sensitive).unwrap();
+            let matches: Vec<_> = regex
+                .captures_iter(string)
+                .map(|captures| groups(&captures, regex.group_count()))
+                .collect();
+            assert_eq!(matches, *expected, "{:?} on {:?}", pattern, string);
+        }
+    }
+}
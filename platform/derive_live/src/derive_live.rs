@@ -169,7 +169,9 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
                     if field.name == "abs_pos" ||
                       field.name == "margin" ||
                       field.name == "width" ||
-                      field.name == "height" {
+                      field.name == "height" ||
+                      field.name == "cell" ||
+                      field.name == "constraint" {
                           return error_result(&format!("Name collision between walk splat and {}", field.name));
                       }
                 }
@@ -177,6 +179,8 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
                 tb.add("        live_id!(margin)=>self.").ident(&field.name).add(".margin.apply(cx, apply_from, index, nodes),");
                 tb.add("        live_id!(width)=>self.").ident(&field.name).add(".width.apply(cx, apply_from, index, nodes),");
                 tb.add("        live_id!(height)=>self.").ident(&field.name).add(".height.apply(cx, apply_from, index, nodes),");
+                tb.add("        live_id!(cell)=>self.").ident(&field.name).add(".cell.apply(cx, apply_from, index, nodes),");
+                tb.add("        live_id!(constraint)=>self.").ident(&field.name).add(".constraint.apply(cx, apply_from, index, nodes),");
             }
             else if field.attrs[0].name == "layout" {
                 for field in &fields {
@@ -0,0 +1,1347 @@
+use {
+    std::{
+        rc::Rc,
+        collections::BTreeMap,
+    },
+    crate::{
+        makepad_live_id::{LiveId, live_id},
+        shader_ast::*,
+        shader_registry::ShaderRegistry,
+        swizzle::Swizzle,
+    }
+};
+
+// A tree walking interpreter for analysed draw shaders. This is what the
+// headless backend uses to run vertex() and pixel() on the CPU. It consumes
+// the same AST the GLSL/HLSL/Metal generators do, so anything that compiles
+// for the GPU runs here as well (just a lot slower).
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Void,
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    Bvec(usize, [bool; 4]),
+    Ivec(usize, [i32; 4]),
+    Vec(usize, [f32; 4]),
+    // column major, dim*dim entries used
+    Mat(usize, [f32; 16]),
+    Struct(Vec<Value>),
+    Array(Vec<Value>),
+    Texture(usize),
+    DrawShader,
+    Closure(Rc<InterpClosure>),
+}
+
+#[derive(Debug)]
+pub struct InterpClosure {
+    fn_ptr: FnPtr,
+    closure_def_index: ClosureDefIndex,
+    locals: Vec<Local>,
+    closure_args: Rc<Vec<Option<Value >> >,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum NumKind {
+    Bool,
+    Int,
+    Float
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Shape {
+    Scalar,
+    Vec(usize),
+    Mat(usize)
+}
+
+impl Shape {
+    fn len(&self) -> usize {
+        match self {
+            Shape::Scalar => 1,
+            Shape::Vec(n) => *n,
+            Shape::Mat(n) => n * n
+        }
+    }
+}
+
+struct Num {
+    kind: NumKind,
+    shape: Shape,
+    v: [f32; 16]
+}
+
+impl Num {
+    fn pack(kind: NumKind, shape: Shape, v: &[f32; 16]) -> Value {
+        match shape {
+            Shape::Scalar => match kind {
+                NumKind::Bool => Value::Bool(v[0] != 0.0),
+                NumKind::Int => Value::Int(v[0] as i32),
+                NumKind::Float => Value::Float(v[0]),
+            }
+            Shape::Vec(n) => match kind {
+                NumKind::Bool => Value::Bvec(n, [v[0] != 0.0, v[1] != 0.0, v[2] != 0.0, v[3] != 0.0]),
+                NumKind::Int => Value::Ivec(n, [v[0] as i32, v[1] as i32, v[2] as i32, v[3] as i32]),
+                NumKind::Float => Value::Vec(n, [v[0], v[1], v[2], v[3]]),
+            }
+            Shape::Mat(n) => Value::Mat(n, *v)
+        }
+    }
+
+    fn at(&self, index: usize) -> f32 {
+        if let Shape::Scalar = self.shape {self.v[0]} else {self.v[index]}
+    }
+}
+
+impl Value {
+    pub fn zero(ty: &Ty, shader_registry: &ShaderRegistry) -> Value {
+        match ty {
+            Ty::Void => Value::Void,
+            Ty::Bool => Value::Bool(false),
+            Ty::Int => Value::Int(0),
+            Ty::Float | Ty::Enum(_) => Value::Float(0.0),
+            Ty::Bvec2 => Value::Bvec(2, [false; 4]),
+            Ty::Bvec3 => Value::Bvec(3, [false; 4]),
+            Ty::Bvec4 => Value::Bvec(4, [false; 4]),
+            Ty::Ivec2 => Value::Ivec(2, [0; 4]),
+            Ty::Ivec3 => Value::Ivec(3, [0; 4]),
+            Ty::Ivec4 => Value::Ivec(4, [0; 4]),
+            Ty::Vec2 => Value::Vec(2, [0.0; 4]),
+            Ty::Vec3 => Value::Vec(3, [0.0; 4]),
+            Ty::Vec4 => Value::Vec(4, [0.0; 4]),
+            Ty::Mat2 => Value::Mat(2, [0.0; 16]),
+            Ty::Mat3 => Value::Mat(3, [0.0; 16]),
+            Ty::Mat4 => Value::Mat(4, [0.0; 16]),
+            Ty::Texture2D => Value::Texture(0),
+            Ty::Array {elem_ty, len} => Value::Array(
+                (0..*len).map( | _ | Value::zero(elem_ty, shader_registry)).collect()
+            ),
+            Ty::Struct(struct_ptr) => {
+                let struct_def = shader_registry.structs.get(struct_ptr).unwrap();
+                Value::Struct(struct_def.fields.iter().map( | field | {
+                    Value::zero(field.ty_expr.ty.borrow().as_ref().unwrap(), shader_registry)
+                }).collect())
+            }
+            Ty::DrawShader(_) => Value::DrawShader,
+            Ty::ClosureDef(_) | Ty::ClosureDecl => Value::Void,
+        }
+    }
+
+    pub fn from_slots(ty: &Ty, slots: &[f32]) -> Value {
+        fn get(slots: &[f32], index: usize) -> f32 {
+            slots.get(index).cloned().unwrap_or(0.0)
+        }
+        fn vec(slots: &[f32], n: usize) -> [f32; 4] {
+            let mut v = [0.0; 4];
+            for i in 0..n {v[i] = get(slots, i)}
+            v
+        }
+        fn mat(slots: &[f32], n: usize) -> [f32; 16] {
+            let mut v = [0.0; 16];
+            for i in 0..n * n {v[i] = get(slots, i)}
+            v
+        }
+        match ty {
+            Ty::Bool => Value::Bool(get(slots, 0) > 0.5),
+            Ty::Int => Value::Int(get(slots, 0) as i32),
+            Ty::Float | Ty::Enum(_) => Value::Float(get(slots, 0)),
+            Ty::Bvec2 | Ty::Bvec3 | Ty::Bvec4 => {
+                let n = ty.slots();
+                let v = vec(slots, n);
+                Value::Bvec(n, [v[0] > 0.5, v[1] > 0.5, v[2] > 0.5, v[3] > 0.5])
+            }
+            Ty::Ivec2 | Ty::Ivec3 | Ty::Ivec4 => {
+                let n = ty.slots();
+                let v = vec(slots, n);
+                Value::Ivec(n, [v[0] as i32, v[1] as i32, v[2] as i32, v[3] as i32])
+            }
+            Ty::Vec2 => Value::Vec(2, vec(slots, 2)),
+            Ty::Vec3 => Value::Vec(3, vec(slots, 3)),
+            Ty::Vec4 => Value::Vec(4, vec(slots, 4)),
+            Ty::Mat2 => Value::Mat(2, mat(slots, 2)),
+            Ty::Mat3 => Value::Mat(3, mat(slots, 3)),
+            Ty::Mat4 => Value::Mat(4, mat(slots, 4)),
+            Ty::Array {elem_ty, len} => {
+                let elem_slots = elem_ty.slots();
+                Value::Array((0..*len).map( | i | {
+                    Value::from_slots(elem_ty, slots.get(i * elem_slots..).unwrap_or(&[]))
+                }).collect())
+            }
+            _ => Value::Void
+        }
+    }
+
+    pub fn to_slots(&self, out: &mut Vec<f32>) {
+        match self {
+            Value::Bool(v) => out.push(if *v {1.0} else {0.0}),
+            Value::Int(v) => out.push(*v as f32),
+            Value::Float(v) => out.push(*v),
+            Value::Bvec(n, v) => for i in 0..*n {out.push(if v[i] {1.0} else {0.0})},
+            Value::Ivec(n, v) => for i in 0..*n {out.push(v[i] as f32)},
+            Value::Vec(n, v) => out.extend_from_slice(&v[0..*n]),
+            Value::Mat(n, v) => out.extend_from_slice(&v[0..n * n]),
+            Value::Struct(v) | Value::Array(v) => for item in v {item.to_slots(out)},
+            _ => ()
+        }
+    }
+
+    pub fn to_bool(&self) -> bool {
+        match self {
+            Value::Bool(v) => *v,
+            Value::Int(v) => *v != 0,
+            Value::Float(v) => *v != 0.0,
+            _ => false
+        }
+    }
+
+    pub fn to_int(&self) -> i32 {
+        match self {
+            Value::Bool(v) => *v as i32,
+            Value::Int(v) => *v,
+            Value::Float(v) => *v as i32,
+            _ => 0
+        }
+    }
+
+    pub fn to_f32(&self) -> f32 {
+        match self {
+            Value::Bool(v) => if *v {1.0} else {0.0},
+            Value::Int(v) => *v as f32,
+            Value::Float(v) => *v,
+            Value::Vec(_, v) => v[0],
+            _ => 0.0
+        }
+    }
+
+    pub fn to_vec4(&self) -> [f32; 4] {
+        match self {
+            Value::Vec(_, v) => *v,
+            Value::Float(v) => [*v; 4],
+            _ => [0.0; 4]
+        }
+    }
+
+    fn num(&self) -> Option<Num> {
+        let mut v = [0.0; 16];
+        let (kind, shape) = match self {
+            Value::Bool(b) => {v[0] = if *b {1.0} else {0.0}; (NumKind::Bool, Shape::Scalar)}
+            Value::Int(i) => {v[0] = *i as f32; (NumKind::Int, Shape::Scalar)}
+            Value::Float(f) => {v[0] = *f; (NumKind::Float, Shape::Scalar)}
+            Value::Bvec(n, b) => {
+                for i in 0..*n {v[i] = if b[i] {1.0} else {0.0}}
+                (NumKind::Bool, Shape::Vec(*n))
+            }
+            Value::Ivec(n, b) => {
+                for i in 0..*n {v[i] = b[i] as f32}
+                (NumKind::Int, Shape::Vec(*n))
+            }
+            Value::Vec(n, b) => {
+                v[0..4].copy_from_slice(b);
+                (NumKind::Float, Shape::Vec(*n))
+            }
+            Value::Mat(n, m) => {
+                v = *m;
+                (NumKind::Float, Shape::Mat(*n))
+            }
+            _ => return None
+        };
+        Some(Num {kind, shape, v})
+    }
+
+    fn swizzle(&self, swizzle: &Swizzle) -> Value {
+        let num = if let Some(num) = self.num() {num} else {return Value::Void};
+        let mut v = [0.0; 16];
+        for (i, index) in swizzle.iter().enumerate() {
+            v[i] = num.v[*index];
+        }
+        let shape = if swizzle.len() == 1 {Shape::Scalar} else {Shape::Vec(swizzle.len())};
+        Num::pack(num.kind, shape, &v)
+    }
+
+    fn set_swizzle(&mut self, swizzle: &Swizzle, value: &Value) {
+        let mut num = if let Some(num) = self.num() {num} else {return};
+        let src = if let Some(src) = value.num() {src} else {return};
+        for (i, index) in swizzle.iter().enumerate() {
+            num.v[*index] = src.at(i);
+        }
+        *self = Num::pack(num.kind, num.shape, &num.v);
+    }
+
+    fn index(&self, index: usize) -> Value {
+        match self {
+            Value::Array(v) => v.get(index).cloned().unwrap_or(Value::Void),
+            Value::Mat(n, m) if index < *n => {
+                let mut v = [0.0; 4];
+                v[0..*n].copy_from_slice(&m[index * n..index * n + n]);
+                Value::Vec(*n, v)
+            }
+            Value::Bvec(n, v) if index < *n => Value::Bool(v[index]),
+            Value::Ivec(n, v) if index < *n => Value::Int(v[index]),
+            Value::Vec(n, v) if index < *n => Value::Float(v[index]),
+            _ => Value::Void
+        }
+    }
+
+    fn set_index(&mut self, index: usize, value: Value) {
+        match self {
+            Value::Array(v) => if index < v.len() {v[index] = value},
+            Value::Mat(n, m) if index < *n => {
+                let col = value.to_vec4();
+                m[index * *n..index * *n + *n].copy_from_slice(&col[0..*n]);
+            }
+            Value::Bvec(n, v) if index < *n => v[index] = value.to_bool(),
+            Value::Ivec(n, v) if index < *n => v[index] = value.to_int(),
+            Value::Vec(n, v) if index < *n => v[index] = value.to_f32(),
+            _ => ()
+        }
+    }
+}
+
+impl From<&Val> for Value {
+    fn from(val: &Val) -> Value {
+        match val {
+            Val::Bool(v) => Value::Bool(*v),
+            Val::Int(v) => Value::Int(*v),
+            Val::Float(v) => Value::Float(*v),
+            Val::Vec4(v) => Value::Vec(4, [v.x, v.y, v.z, v.w]),
+        }
+    }
+}
+
+pub trait ShaderSampler {
+    fn sample2d(&self, slot: usize, pos: [f32; 2]) -> [f32; 4];
+    fn sample2d_rt(&self, slot: usize, pos: [f32; 2]) -> [f32; 4];
+}
+
+// Derivatives are computed the way a GPU does it: by differencing against the
+// neighbouring pixel. The rasteriser first runs the pixel shader for the
+// pixel to the right and the one below with `record` set, which collects the
+// argument of every dFdx/dFdy call in order. The real run then differences
+// against those.
+#[derive(Default)]
+pub struct ShaderDerivatives {
+    pub record: bool,
+    pub recorded: Vec<Value>,
+    pub dx: Vec<Value>,
+    pub dy: Vec<Value>,
+    pub cursor: usize,
+}
+
+pub struct ShaderInterpEnv<'a> {
+    // indexed like DrawShaderDef::fields
+    pub fields: &'a mut [Value],
+    pub live_uniforms: &'a [f32],
+    pub sampler: &'a dyn ShaderSampler,
+    pub derivatives: &'a mut ShaderDerivatives,
+}
+
+#[derive(Clone, Debug)]
+struct Local {
+    ident: Ident,
+    shadow: Option<ScopeSymShadow>,
+    value: Value
+}
+
+struct Frame<'a> {
+    fn_def: &'a FnDef,
+    const_table_offset: Option<usize>,
+    locals: Vec<Local>,
+    closure_args: Rc<Vec<Option<Value >> >,
+}
+
+impl<'a> Frame<'a> {
+    fn find(&self, ident: Ident, shadow: ScopeSymShadow) -> Option<usize> {
+        self.locals.iter().rposition( | local | {
+            local.ident == ident && (local.shadow.is_none() || local.shadow == Some(shadow))
+        })
+    }
+}
+
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value)
+}
+
+pub struct ShaderInterp<'a> {
+    pub shader_registry: &'a ShaderRegistry,
+    pub draw_shader_def: &'a DrawShaderDef,
+    pub const_table: &'a DrawShaderConstTable,
+    live_offsets: BTreeMap<ValuePtr, usize>,
+}
+
+impl<'a> ShaderInterp<'a> {
+    pub fn new(shader_registry: &'a ShaderRegistry, draw_shader_def: &'a DrawShaderDef, const_table: &'a DrawShaderConstTable) -> Self {
+        // same ordering as the live uniform buffer in CxDrawShaderMapping
+        let mut live_offsets = BTreeMap::new();
+        let mut slots = 0;
+        for (value_ptr, ty) in draw_shader_def.all_live_refs.borrow().iter() {
+            live_offsets.insert(*value_ptr, slots);
+            slots += ty.slots();
+        }
+        Self {
+            shader_registry,
+            draw_shader_def,
+            const_table,
+            live_offsets
+        }
+    }
+
+    pub fn uses_derivatives(&self) -> bool {
+        self.draw_shader_def.pixel_fns.borrow().iter().any( | fn_ptr | {
+            let fn_def = self.shader_registry.all_fns.get(fn_ptr).unwrap();
+            let builtin_deps = fn_def.builtin_deps.borrow();
+            builtin_deps.as_ref().map_or(false, | deps | {
+                deps.contains(&Ident(live_id!(dFdx))) || deps.contains(&Ident(live_id!(dFdy)))
+            })
+        })
+    }
+
+    pub fn field_index(&self, ident: Ident) -> Option<usize> {
+        self.draw_shader_def.fields.iter().position( | field | field.ident == ident)
+    }
+
+    pub fn call_vertex(&self, env: &mut ShaderInterpEnv) -> Value {
+        self.call_entry(env, Ident(live_id!(vertex)))
+    }
+
+    pub fn call_pixel(&self, env: &mut ShaderInterpEnv) -> Value {
+        self.call_entry(env, Ident(live_id!(pixel)))
+    }
+
+    fn call_entry(&self, env: &mut ShaderInterpEnv, ident: Ident) -> Value {
+        let fn_def = if let Some(fn_def) = self.shader_registry.draw_shader_method_decl_from_ident(self.draw_shader_def, ident) {
+            fn_def
+        }
+        else {
+            return Value::Void
+        };
+        let args = fn_def.params.iter().map( | _ | Value::DrawShader).collect();
+        self.call_fn(env, fn_def, args, &mut Vec::new())
+    }
+
+    fn call_fn(&self, env: &mut ShaderInterpEnv, fn_def: &FnDef, args: Vec<Value>, inout: &mut Vec<Value>) -> Value {
+        let mut frame = Frame {
+            fn_def,
+            const_table_offset: self.const_table.offsets.get(&fn_def.fn_ptr).cloned(),
+            locals: Vec::new(),
+            closure_args: Rc::new(Vec::new()),
+        };
+        let mut closure_args = Vec::new();
+        for (param, arg) in fn_def.params.iter().zip(args.into_iter()) {
+            if let TyExprKind::ClosureDecl {..} = &param.ty_expr.kind {
+                closure_args.push(Some(arg));
+            }
+            else {
+                closure_args.push(None);
+                frame.locals.push(Local {ident: param.ident, shadow: param.shadow.get(), value: arg});
+            }
+        }
+        frame.closure_args = Rc::new(closure_args);
+
+        let ret = match self.exec_block_inner(env, &mut frame, &fn_def.block) {
+            Flow::Return(value) => value,
+            _ => Value::Void
+        };
+        // hand back the final values of the inout params
+        for param in &fn_def.params {
+            if param.is_inout {
+                let value = frame.locals.iter().find( | local | local.ident == param.ident && local.shadow == param.shadow.get())
+                    .map( | local | local.value.clone()).unwrap_or(Value::Void);
+                inout.push(value);
+            }
+        }
+        ret
+    }
+
+    fn exec_block(&self, env: &mut ShaderInterpEnv, frame: &mut Frame, block: &Block) -> Flow {
+        let len = frame.locals.len();
+        let flow = self.exec_block_inner(env, frame, block);
+        frame.locals.truncate(len);
+        flow
+    }
+
+    fn exec_block_inner(&self, env: &mut ShaderInterpEnv, frame: &mut Frame, block: &Block) -> Flow {
+        for stmt in &block.stmts {
+            match self.exec_stmt(env, frame, stmt) {
+                Flow::Normal => (),
+                flow => return flow
+            }
+        }
+        Flow::Normal
+    }
+
+    fn exec_stmt(&self, env: &mut ShaderInterpEnv, frame: &mut Frame, stmt: &Stmt) -> Flow {
+        match stmt {
+            Stmt::Break {..} => Flow::Break,
+            Stmt::Continue {..} => Flow::Continue,
+            Stmt::For {ident, from_expr, to_expr, step_expr, block, ..} => {
+                fn const_int(expr: &Expr) -> i32 {
+                    expr.const_val.borrow().as_ref().and_then( | v | v.as_ref()).and_then( | v | v.to_int()).unwrap_or(0)
+                }
+                let from = const_int(from_expr);
+                let to = const_int(to_expr);
+                let step = if let Some(step_expr) = step_expr {
+                    const_int(step_expr)
+                } else if from < to {1} else {-1};
+                if step == 0 {
+                    return Flow::Normal
+                }
+                // mirrors the loop bounds the generators emit
+                let mut i = if from <= to {from} else {from - 1};
+                let len = frame.locals.len();
+                frame.locals.push(Local {ident: *ident, shadow: None, value: Value::Int(i)});
+                while if from <= to {i < to} else {i >= to} {
+                    frame.locals[len].value = Value::Int(i);
+                    match self.exec_block(env, frame, block) {
+                        Flow::Break => break,
+                        Flow::Return(value) => {
+                            frame.locals.truncate(len);
+                            return Flow::Return(value)
+                        }
+                        _ => ()
+                    }
+                    i += step;
+                }
+                frame.locals.truncate(len);
+                Flow::Normal
+            }
+            Stmt::If {expr, block_if_true, block_if_false, ..} => {
+                if self.eval(env, frame, expr).to_bool() {
+                    self.exec_block(env, frame, block_if_true)
+                }
+                else if let Some(block_if_false) = block_if_false {
+                    self.exec_block(env, frame, block_if_false)
+                }
+                else {
+                    Flow::Normal
+                }
+            }
+            Stmt::Match {expr, matches, ..} => {
+                let value = self.eval(env, frame, expr).to_f32();
+                for match_item in matches {
+                    if let Some(enum_value) = match_item.enum_value.get() {
+                        if (value - enum_value as f32).abs() < 0.5 {
+                            return self.exec_block(env, frame, &match_item.block)
+                        }
+                    }
+                }
+                Flow::Normal
+            }
+            Stmt::Let {ty, shadow, ident, expr, ..} => {
+                let value = if let Some(expr) = expr {
+                    self.eval(env, frame, expr)
+                }
+                else {
+                    Value::zero(ty.borrow().as_ref().unwrap(), self.shader_registry)
+                };
+                frame.locals.push(Local {ident: *ident, shadow: shadow.get(), value});
+                Flow::Normal
+            }
+            Stmt::Return {expr, ..} => {
+                if let Some(expr) = expr {
+                    Flow::Return(self.eval(env, frame, expr))
+                }
+                else {
+                    Flow::Return(Value::Void)
+                }
+            }
+            Stmt::Block {block, ..} => self.exec_block(env, frame, block),
+            Stmt::Expr {expr, ..} => {
+                self.eval(env, frame, expr);
+                Flow::Normal
+            }
+        }
+    }
+
+    fn eval(&self, env: &mut ShaderInterpEnv, frame: &mut Frame, expr: &Expr) -> Value {
+        if let Some(Some(val)) = expr.const_val.borrow().as_ref() {
+            if let (Some(index), Some(offset)) = (expr.const_index.get(), frame.const_table_offset) {
+                let table = &self.const_table.table;
+                match val {
+                    Val::Float(_) if offset + index < table.len() => {
+                        return Value::Float(table[offset + index])
+                    }
+                    Val::Vec4(_) if offset + index + 4 <= table.len() => {
+                        let mut v = [0.0; 4];
+                        v.copy_from_slice(&table[offset + index..offset + index + 4]);
+                        return Value::Vec(4, v)
+                    }
+                    _ => ()
+                }
+            }
+            return val.into()
+        }
+        match &expr.kind {
+            ExprKind::Cond {expr, expr_if_true, expr_if_false, ..} => {
+                if self.eval(env, frame, expr).to_bool() {
+                    self.eval(env, frame, expr_if_true)
+                }
+                else {
+                    self.eval(env, frame, expr_if_false)
+                }
+            }
+            ExprKind::Bin {op, left_expr, right_expr, ..} => {
+                match op {
+                    BinOp::Assign => {
+                        let value = self.eval(env, frame, right_expr);
+                        self.assign(env, frame, left_expr, value.clone());
+                        value
+                    }
+                    BinOp::AddAssign | BinOp::SubAssign | BinOp::MulAssign | BinOp::DivAssign => {
+                        let left = self.eval(env, frame, left_expr);
+                        let right = self.eval(env, frame, right_expr);
+                        let op = match op {
+                            BinOp::AddAssign => BinOp::Add,
+                            BinOp::SubAssign => BinOp::Sub,
+                            BinOp::MulAssign => BinOp::Mul,
+                            _ => BinOp::Div
+                        };
+                        let value = bin_op(op, &left, &right);
+                        self.assign(env, frame, left_expr, value.clone());
+                        value
+                    }
+                    BinOp::Or => Value::Bool(
+                        self.eval(env, frame, left_expr).to_bool() || self.eval(env, frame, right_expr).to_bool()
+                    ),
+                    BinOp::And => Value::Bool(
+                        self.eval(env, frame, left_expr).to_bool() && self.eval(env, frame, right_expr).to_bool()
+                    ),
+                    _ => {
+                        let left = self.eval(env, frame, left_expr);
+                        let right = self.eval(env, frame, right_expr);
+                        bin_op(*op, &left, &right)
+                    }
+                }
+            }
+            ExprKind::Un {op, expr, ..} => {
+                let value = self.eval(env, frame, expr);
+                match op {
+                    UnOp::Not => match value {
+                        Value::Bvec(n, v) => Value::Bvec(n, [!v[0], !v[1], !v[2], !v[3]]),
+                        value => Value::Bool(!value.to_bool())
+                    },
+                    UnOp::Neg => map_num(&[value], | v | -v[0])
+                }
+            }
+            ExprKind::Field {expr: base, field_ident, ..} => {
+                match base.ty.borrow().as_ref() {
+                    Some(Ty::DrawShader(_)) => {
+                        if let Some(index) = self.field_index(*field_ident) {
+                            env.fields[index].clone()
+                        }
+                        else {
+                            Value::Void
+                        }
+                    }
+                    Some(Ty::Struct(struct_ptr)) => {
+                        let index = self.struct_field_index(*struct_ptr, *field_ident);
+                        match self.eval(env, frame, base) {
+                            Value::Struct(fields) => fields.into_iter().nth(index).unwrap_or(Value::Void),
+                            _ => Value::Void
+                        }
+                    }
+                    _ => {
+                        let value = self.eval(env, frame, base);
+                        match Swizzle::parse(*field_ident) {
+                            Some(swizzle) => value.swizzle(&swizzle),
+                            None => Value::Void
+                        }
+                    }
+                }
+            }
+            ExprKind::Index {expr, index_expr, ..} => {
+                let value = self.eval(env, frame, expr);
+                let index = self.eval(env, frame, index_expr).to_int();
+                value.index(index.max(0) as usize)
+            }
+            ExprKind::MethodCall {ident, arg_exprs, ..} => {
+                let fn_def = match arg_exprs[0].ty.borrow().as_ref() {
+                    Some(Ty::Struct(struct_ptr)) => self.shader_registry.struct_method_decl_from_ident(
+                        self.shader_registry.structs.get(struct_ptr).unwrap(),
+                        *ident
+                    ),
+                    Some(Ty::DrawShader(shader_ptr)) => self.shader_registry.draw_shader_method_decl_from_ident(
+                        self.shader_registry.draw_shader_defs.get(shader_ptr).unwrap(),
+                        *ident
+                    ),
+                    _ => None
+                };
+                if let Some(fn_def) = fn_def {
+                    self.eval_call(env, frame, fn_def, arg_exprs)
+                }
+                else {
+                    Value::Void
+                }
+            }
+            ExprKind::PlainCall {fn_ptr, arg_exprs, param_index, ..} => {
+                if let Some(param_index) = param_index.get() {
+                    self.eval_closure_call(env, frame, param_index, arg_exprs)
+                }
+                else if let Some(fn_def) = fn_ptr.and_then( | fn_ptr | self.shader_registry.all_fns.get(&fn_ptr)) {
+                    self.eval_call(env, frame, fn_def, arg_exprs)
+                }
+                else {
+                    Value::Void
+                }
+            }
+            ExprKind::BuiltinCall {ident, arg_exprs, ..} => {
+                let args: Vec<Value> = arg_exprs.iter().map( | arg_expr | self.eval(env, frame, arg_expr)).collect();
+                self.builtin(env, *ident, &args)
+            }
+            ExprKind::ClosureDef(closure_def_index) => {
+                Value::Closure(Rc::new(InterpClosure {
+                    fn_ptr: frame.fn_def.fn_ptr,
+                    closure_def_index: *closure_def_index,
+                    locals: frame.locals.clone(),
+                    closure_args: frame.closure_args.clone(),
+                }))
+            }
+            ExprKind::ConsCall {ty_lit, arg_exprs, ..} => {
+                let args: Vec<Value> = arg_exprs.iter().map( | arg_expr | self.eval(env, frame, arg_expr)).collect();
+                cons_call(*ty_lit, &args)
+            }
+            ExprKind::StructCons {struct_ptr, args, ..} => {
+                let struct_def = self.shader_registry.structs.get(struct_ptr).unwrap();
+                let mut fields = Vec::new();
+                for field in &struct_def.fields {
+                    if let Some((_, expr)) = args.iter().find( | (ident, _) | *ident == field.ident) {
+                        fields.push(self.eval(env, frame, expr));
+                    }
+                    else {
+                        fields.push(Value::zero(field.ty_expr.ty.borrow().as_ref().unwrap(), self.shader_registry));
+                    }
+                }
+                Value::Struct(fields)
+            }
+            ExprKind::Var {kind, ..} => match kind.get() {
+                Some(VarKind::Local {ident, shadow}) | Some(VarKind::MutLocal {ident, shadow}) => {
+                    if let Some(index) = frame.find(ident, shadow) {
+                        frame.locals[index].value.clone()
+                    }
+                    else {
+                        Value::Void
+                    }
+                }
+                Some(VarKind::LiveValue(value_ptr)) => {
+                    let offset = self.live_offsets.get(&value_ptr).cloned().unwrap_or(0);
+                    Value::from_slots(
+                        expr.ty.borrow().as_ref().unwrap(),
+                        env.live_uniforms.get(offset..).unwrap_or(&[])
+                    )
+                }
+                None => Value::Void
+            },
+            ExprKind::Lit {lit, ..} => (&lit.to_val()).into(),
+        }
+    }
+
+    fn struct_field_index(&self, struct_ptr: StructPtr, ident: Ident) -> usize {
+        let struct_def = self.shader_registry.structs.get(&struct_ptr).unwrap();
+        struct_def.fields.iter().position( | field | field.ident == ident).unwrap_or(0)
+    }
+
+    fn eval_call(&self, env: &mut ShaderInterpEnv, frame: &mut Frame, fn_def: &FnDef, arg_exprs: &[Expr]) -> Value {
+        let args: Vec<Value> = arg_exprs.iter().map( | arg_expr | self.eval(env, frame, arg_expr)).collect();
+        let mut inout = Vec::new();
+        let ret = self.call_fn(env, fn_def, args, &mut inout);
+        let mut inout = inout.into_iter();
+        for (param, arg_expr) in fn_def.params.iter().zip(arg_exprs.iter()) {
+            if param.is_inout {
+                if let Some(value) = inout.next() {
+                    self.assign(env, frame, arg_expr, value);
+                }
+            }
+        }
+        ret
+    }
+
+    fn eval_closure_call(&self, env: &mut ShaderInterpEnv, frame: &mut Frame, param_index: usize, arg_exprs: &[Expr]) -> Value {
+        let closure = match frame.closure_args.get(param_index) {
+            Some(Some(Value::Closure(closure))) => closure.clone(),
+            _ => return Value::Void
+        };
+        let decl_params = match &frame.fn_def.params[param_index].ty_expr.kind {
+            TyExprKind::ClosureDecl {params, ..} => params,
+            _ => return Value::Void
+        };
+        let call_def = self.shader_registry.all_fns.get(&closure.fn_ptr).unwrap();
+        let closure_def = &call_def.closure_defs[closure.closure_def_index.0];
+
+        let mut closure_frame = Frame {
+            fn_def: call_def,
+            // the const table offsets are per fn, don't guess for closure bodies
+            const_table_offset: None,
+            locals: closure.locals.clone(),
+            closure_args: closure.closure_args.clone(),
+        };
+        for (param, arg_expr) in closure_def.params.iter().zip(arg_exprs.iter()) {
+            let value = self.eval(env, frame, arg_expr);
+            closure_frame.locals.push(Local {ident: param.ident, shadow: param.shadow.get(), value});
+        }
+        let ret = match &closure_def.kind {
+            ClosureDefKind::Expr(expr) => self.eval(env, &mut closure_frame, expr),
+            ClosureDefKind::Block(block) => match self.exec_block_inner(env, &mut closure_frame, block) {
+                Flow::Return(value) => value,
+                _ => Value::Void
+            }
+        };
+        for (index, (param, arg_expr)) in closure_def.params.iter().zip(arg_exprs.iter()).enumerate() {
+            if decl_params.get(index).map_or(false, | p | p.is_inout) {
+                if let Some(i) = closure_frame.find(param.ident, param.shadow.get().unwrap_or(ScopeSymShadow(0))) {
+                    let value = closure_frame.locals[i].value.clone();
+                    self.assign(env, frame, arg_expr, value);
+                }
+            }
+        }
+        ret
+    }
+
+    fn assign(&self, env: &mut ShaderInterpEnv, frame: &mut Frame, expr: &Expr, value: Value) {
+        match &expr.kind {
+            ExprKind::Var {kind, ..} => match kind.get() {
+                Some(VarKind::Local {ident, shadow}) | Some(VarKind::MutLocal {ident, shadow}) => {
+                    if let Some(index) = frame.find(ident, shadow) {
+                        frame.locals[index].value = value;
+                    }
+                }
+                _ => ()
+            },
+            ExprKind::Field {expr: base, field_ident, ..} => {
+                match base.ty.borrow().as_ref() {
+                    Some(Ty::DrawShader(_)) => {
+                        if let Some(index) = self.field_index(*field_ident) {
+                            env.fields[index] = value;
+                        }
+                    }
+                    Some(Ty::Struct(struct_ptr)) => {
+                        let index = self.struct_field_index(*struct_ptr, *field_ident);
+                        let mut base_value = self.eval(env, frame, base);
+                        if let Value::Struct(fields) = &mut base_value {
+                            if index < fields.len() {
+                                fields[index] = value;
+                            }
+                        }
+                        self.assign(env, frame, base, base_value);
+                    }
+                    _ => {
+                        if let Some(swizzle) = Swizzle::parse(*field_ident) {
+                            let mut base_value = self.eval(env, frame, base);
+                            base_value.set_swizzle(&swizzle, &value);
+                            self.assign(env, frame, base, base_value);
+                        }
+                    }
+                }
+            }
+            ExprKind::Index {expr: base, index_expr, ..} => {
+                let index = self.eval(env, frame, index_expr).to_int().max(0) as usize;
+                let mut base_value = self.eval(env, frame, base);
+                base_value.set_index(index, value);
+                self.assign(env, frame, base, base_value);
+            }
+            _ => ()
+        }
+    }
+
+    fn builtin(&self, env: &mut ShaderInterpEnv, ident: Ident, args: &[Value]) -> Value {
+        fn arg(args: &[Value], index: usize) -> Value {
+            args.get(index).cloned().unwrap_or(Value::Void)
+        }
+        match ident.0 {
+            live_id!(sample2d) | live_id!(sample2d_rt) => {
+                let slot = if let Value::Texture(slot) = arg(args, 0) {slot} else {return Value::Vec(4, [0.0; 4])};
+                let pos = arg(args, 1).to_vec4();
+                let pos = [pos[0], pos[1]];
+                Value::Vec(4, if ident.0 == live_id!(sample2d) {
+                    env.sampler.sample2d(slot, pos)
+                }
+                else {
+                    env.sampler.sample2d_rt(slot, pos)
+                })
+            }
+            live_id!(dFdx) | live_id!(dFdy) => {
+                let value = arg(args, 0);
+                let derivatives = &mut env.derivatives;
+                if derivatives.record {
+                    derivatives.recorded.push(value.clone());
+                    return map_num(&[value], | _ | 0.0)
+                }
+                let index = derivatives.cursor;
+                derivatives.cursor += 1;
+                if ident.0 == live_id!(dFdx) {
+                    match derivatives.dx.get(index) {
+                        Some(right) => bin_op(BinOp::Sub, right, &value),
+                        None => map_num(&[value], | _ | 0.0)
+                    }
+                }
+                else {
+                    // window y points up, our rows go down
+                    match derivatives.dy.get(index) {
+                        Some(below) => bin_op(BinOp::Sub, &value, below),
+                        None => map_num(&[value], | _ | 0.0)
+                    }
+                }
+            }
+            live_id!(length) => Value::Float(dot(&arg(args, 0), &arg(args, 0)).sqrt()),
+            live_id!(distance) => {
+                let d = bin_op(BinOp::Sub, &arg(args, 0), &arg(args, 1));
+                Value::Float(dot(&d, &d).sqrt())
+            }
+            live_id!(dot) => Value::Float(dot(&arg(args, 0), &arg(args, 1))),
+            live_id!(cross) => {
+                let a = arg(args, 0).to_vec4();
+                let b = arg(args, 1).to_vec4();
+                Value::Vec(3, [
+                    a[1] * b[2] - b[1] * a[2],
+                    a[2] * b[0] - b[2] * a[0],
+                    a[0] * b[1] - b[0] * a[1],
+                    0.0
+                ])
+            }
+            live_id!(normalize) => {
+                let a = arg(args, 0);
+                let len = dot(&a, &a).sqrt();
+                map_num(&[a], | v | v[0] / len)
+            }
+            live_id!(reflect) => {
+                let i = arg(args, 0);
+                let n = arg(args, 1);
+                let d = 2.0 * dot(&n, &i);
+                map_num(&[i, n], | v | v[0] - d * v[1])
+            }
+            live_id!(refract) => {
+                let i = arg(args, 0);
+                let n = arg(args, 1);
+                let eta = arg(args, 2).to_f32();
+                let d = dot(&n, &i);
+                let k = 1.0 - eta * eta * (1.0 - d * d);
+                if k < 0.0 {
+                    map_num(&[i], | _ | 0.0)
+                }
+                else {
+                    map_num(&[i, n], | v | eta * v[0] - (eta * d + k.sqrt()) * v[1])
+                }
+            }
+            live_id!(faceforward) => {
+                let n = arg(args, 0);
+                if dot(&arg(args, 2), &arg(args, 1)) < 0.0 {n} else {map_num(&[n], | v | -v[0])}
+            }
+            live_id!(all) | live_id!(any) => {
+                let mut out = Vec::new();
+                arg(args, 0).to_slots(&mut out);
+                Value::Bool(if ident.0 == live_id!(all) {
+                    out.iter().all( | v | *v != 0.0)
+                }
+                else {
+                    out.iter().any( | v | *v != 0.0)
+                })
+            }
+            live_id!(not) => map_cmp(&[arg(args, 0)], | v | v[0] == 0.0),
+            live_id!(equal) => map_cmp(args, | v | v[0] == v[1]),
+            live_id!(notEqual) => map_cmp(args, | v | v[0] != v[1]),
+            live_id!(lessThan) => map_cmp(args, | v | v[0] < v[1]),
+            live_id!(lessThanEqual) => map_cmp(args, | v | v[0] <= v[1]),
+            live_id!(greaterThan) => map_cmp(args, | v | v[0] > v[1]),
+            live_id!(greaterThanEqual) => map_cmp(args, | v | v[0] >= v[1]),
+            live_id!(transpose) => match arg(args, 0) {
+                Value::Mat(n, m) => {
+                    let mut t = [0.0; 16];
+                    for c in 0..n {
+                        for r in 0..n {
+                            t[c * n + r] = m[r * n + c];
+                        }
+                    }
+                    Value::Mat(n, t)
+                }
+                value => value
+            },
+            live_id!(inverse) => match arg(args, 0) {
+                Value::Mat(n, m) => Value::Mat(n, mat_inverse(n, &m)),
+                value => value
+            },
+            live_id!(matrixCompMult) => map_num(args, | v | v[0] * v[1]),
+            _ => {
+                let f: fn(&[f32]) -> f32 = match ident.0 {
+                    live_id!(abs) => | v | v[0].abs(),
+                    live_id!(acos) => | v | v[0].acos(),
+                    live_id!(asin) => | v | v[0].asin(),
+                    live_id!(atan) => if args.len() == 2 {| v | v[0].atan2(v[1])} else {| v | v[0].atan()},
+                    live_id!(ceil) => | v | v[0].ceil(),
+                    live_id!(cos) => | v | v[0].cos(),
+                    live_id!(degrees) => | v | v[0].to_degrees(),
+                    live_id!(exp) => | v | v[0].exp(),
+                    live_id!(exp2) => | v | v[0].exp2(),
+                    live_id!(floor) => | v | v[0].floor(),
+                    live_id!(fract) => | v | v[0] - v[0].floor(),
+                    live_id!(inversesqrt) => | v | 1.0 / v[0].sqrt(),
+                    live_id!(log) => | v | v[0].ln(),
+                    live_id!(log2) => | v | v[0].log2(),
+                    live_id!(radians) => | v | v[0].to_radians(),
+                    live_id!(sign) => | v | if v[0] > 0.0 {1.0} else if v[0] < 0.0 {-1.0} else {0.0},
+                    live_id!(sin) => | v | v[0].sin(),
+                    live_id!(sqrt) => | v | v[0].sqrt(),
+                    live_id!(tan) => | v | v[0].tan(),
+                    live_id!(max) => | v | v[0].max(v[1]),
+                    live_id!(min) => | v | v[0].min(v[1]),
+                    live_id!(mod) => | v | v[0] - v[1] * (v[0] / v[1]).floor(),
+                    live_id!(pow) => | v | v[0].powf(v[1]),
+                    live_id!(step) => | v | if v[1] < v[0] {0.0} else {1.0},
+                    live_id!(clamp) => | v | v[0].max(v[1]).min(v[2]),
+                    live_id!(mix) => | v | v[0] + (v[1] - v[0]) * v[2],
+                    live_id!(smoothstep) => | v | {
+                        let t = ((v[2] - v[0]) / (v[1] - v[0])).max(0.0).min(1.0);
+                        t * t * (3.0 - 2.0 * t)
+                    },
+                    _ => return Value::Void
+                };
+                map_num(args, f)
+            }
+        }
+    }
+}
+
+fn dot(a: &Value, b: &Value) -> f32 {
+    match (a.num(), b.num()) {
+        (Some(a), Some(b)) => {
+            let mut sum = 0.0;
+            for i in 0..a.shape.len() {
+                sum += a.at(i) * b.at(i);
+            }
+            sum
+        }
+        _ => 0.0
+    }
+}
+
+// component wise op, scalars broadcast to the widest argument
+fn map_num(args: &[Value], f: impl Fn(&[f32]) -> f32) -> Value {
+    let nums: Vec<Num> = args.iter().filter_map( | arg | arg.num()).collect();
+    if nums.len() != args.len() || nums.is_empty() {
+        return Value::Void
+    }
+    let shape = nums.iter().map( | num | num.shape).find( | shape | *shape != Shape::Scalar).unwrap_or(Shape::Scalar);
+    let kind = if nums.iter().all( | num | num.kind == NumKind::Int) {NumKind::Int} else {NumKind::Float};
+    let mut v = [0.0; 16];
+    let mut input = [0.0; 4];
+    for i in 0..shape.len() {
+        for (j, num) in nums.iter().enumerate().take(4) {
+            input[j] = num.at(i);
+        }
+        v[i] = f(&input[0..nums.len().min(4)]);
+    }
+    Num::pack(kind, shape, &v)
+}
+
+fn map_cmp(args: &[Value], f: impl Fn(&[f32]) -> bool) -> Value {
+    let nums: Vec<Num> = args.iter().filter_map( | arg | arg.num()).collect();
+    if nums.len() != args.len() || nums.is_empty() {
+        return Value::Void
+    }
+    let shape = nums[0].shape;
+    let mut v = [0.0; 16];
+    let mut input = [0.0; 2];
+    for i in 0..shape.len() {
+        for (j, num) in nums.iter().enumerate().take(2) {
+            input[j] = num.at(i);
+        }
+        v[i] = if f(&input[0..nums.len().min(2)]) {1.0} else {0.0};
+    }
+    Num::pack(NumKind::Bool, shape, &v)
+}
+
+fn bin_op(op: BinOp, left: &Value, right: &Value) -> Value {
+    let (l, r) = match (left.num(), right.num()) {
+        (Some(l), Some(r)) => (l, r),
+        _ => return Value::Void
+    };
+    match op {
+        BinOp::Eq | BinOp::Ne => {
+            let len = l.shape.len().max(r.shape.len());
+            let equal = (0..len).all( | i | l.at(i) == r.at(i));
+            Value::Bool(if let BinOp::Eq = op {equal} else {!equal})
+        }
+        BinOp::Lt => Value::Bool(l.v[0] < r.v[0]),
+        BinOp::Le => Value::Bool(l.v[0] <= r.v[0]),
+        BinOp::Gt => Value::Bool(l.v[0] > r.v[0]),
+        BinOp::Ge => Value::Bool(l.v[0] >= r.v[0]),
+        BinOp::Mul => match (l.shape, r.shape) {
+            (Shape::Mat(n), Shape::Mat(_)) => {
+                let mut v = [0.0; 16];
+                for c in 0..n {
+                    for row in 0..n {
+                        v[c * n + row] = (0..n).map( | k | l.v[k * n + row] * r.v[c * n + k]).sum();
+                    }
+                }
+                Value::Mat(n, v)
+            }
+            (Shape::Mat(n), Shape::Vec(_)) => {
+                let mut v = [0.0; 16];
+                for row in 0..n {
+                    v[row] = (0..n).map( | k | l.v[k * n + row] * r.v[k]).sum();
+                }
+                Num::pack(NumKind::Float, Shape::Vec(n), &v)
+            }
+            (Shape::Vec(_), Shape::Mat(n)) => {
+                let mut v = [0.0; 16];
+                for c in 0..n {
+                    v[c] = (0..n).map( | k | l.v[k] * r.v[c * n + k]).sum();
+                }
+                Num::pack(NumKind::Float, Shape::Vec(n), &v)
+            }
+            _ => arith(op, &l, &r)
+        },
+        _ => arith(op, &l, &r)
+    }
+}
+
+fn arith(op: BinOp, l: &Num, r: &Num) -> Value {
+    let shape = if l.shape != Shape::Scalar {l.shape} else {r.shape};
+    let is_int = l.kind == NumKind::Int && r.kind == NumKind::Int;
+    let mut v = [0.0; 16];
+    for i in 0..shape.len() {
+        let (a, b) = (l.at(i), r.at(i));
+        v[i] = if is_int {
+            let (a, b) = (a as i32, b as i32);
+            (match op {
+                BinOp::Add => a.wrapping_add(b),
+                BinOp::Sub => a.wrapping_sub(b),
+                BinOp::Mul => a.wrapping_mul(b),
+                BinOp::Div => if b == 0 {0} else {a.wrapping_div(b)},
+                _ => 0
+            }) as f32
+        }
+        else {
+            match op {
+                BinOp::Add => a + b,
+                BinOp::Sub => a - b,
+                BinOp::Mul => a * b,
+                BinOp::Div => a / b,
+                _ => 0.0
+            }
+        }
+    }
+    Num::pack(if is_int {NumKind::Int} else {NumKind::Float}, shape, &v)
+}
+
+fn cons_call(ty_lit: TyLit, args: &[Value]) -> Value {
+    let (kind, shape) = match ty_lit {
+        TyLit::Bool => (NumKind::Bool, Shape::Scalar),
+        TyLit::Int => (NumKind::Int, Shape::Scalar),
+        TyLit::Float => (NumKind::Float, Shape::Scalar),
+        TyLit::Bvec2 => (NumKind::Bool, Shape::Vec(2)),
+        TyLit::Bvec3 => (NumKind::Bool, Shape::Vec(3)),
+        TyLit::Bvec4 => (NumKind::Bool, Shape::Vec(4)),
+        TyLit::Ivec2 => (NumKind::Int, Shape::Vec(2)),
+        TyLit::Ivec3 => (NumKind::Int, Shape::Vec(3)),
+        TyLit::Ivec4 => (NumKind::Int, Shape::Vec(4)),
+        TyLit::Vec2 => (NumKind::Float, Shape::Vec(2)),
+        TyLit::Vec3 => (NumKind::Float, Shape::Vec(3)),
+        TyLit::Vec4 => (NumKind::Float, Shape::Vec(4)),
+        TyLit::Mat2 => (NumKind::Float, Shape::Mat(2)),
+        TyLit::Mat3 => (NumKind::Float, Shape::Mat(3)),
+        TyLit::Mat4 => (NumKind::Float, Shape::Mat(4)),
+        TyLit::Texture2D => return Value::Void
+    };
+    let mut v = [0.0; 16];
+    let nums: Vec<Num> = args.iter().filter_map( | arg | arg.num()).collect();
+    if nums.len() == 1 && nums[0].shape == Shape::Scalar {
+        let s = nums[0].v[0];
+        match shape {
+            // mat(x) is a diagonal matrix
+            Shape::Mat(n) => for i in 0..n {v[i * n + i] = s},
+            _ => for i in 0..shape.len() {v[i] = s}
+        }
+    }
+    else if let (1, Shape::Mat(n), Shape::Mat(src)) = (nums.len(), shape, nums.first().map_or(Shape::Scalar, | num | num.shape)) {
+        for c in 0..n {
+            for r in 0..n {
+                v[c * n + r] = if c < src && r < src {nums[0].v[c * src + r]} else if c == r {1.0} else {0.0};
+            }
+        }
+    }
+    else {
+        let mut i = 0;
+        for num in &nums {
+            for j in 0..num.shape.len() {
+                if i < 16 {
+                    v[i] = num.v[j];
+                    i += 1;
+                }
+            }
+        }
+    }
+    match kind {
+        NumKind::Int => for x in v.iter_mut() {*x = x.trunc()},
+        NumKind::Bool => for x in v.iter_mut() {*x = if *x != 0.0 {1.0} else {0.0}},
+        NumKind::Float => ()
+    }
+    Num::pack(kind, shape, &v)
+}
+
+fn mat_inverse(n: usize, m: &[f32; 16]) -> [f32; 16] {
+    // gauss-jordan on an n*n column major matrix
+    let mut a = [[0.0f32; 8]; 4];
+    for r in 0..n {
+        for c in 0..n {
+            a[r][c] = m[c * n + r];
+        }
+        a[r][n + r] = 1.0;
+    }
+    for col in 0..n {
+        let mut pivot = col;
+        for r in col + 1..n {
+            if a[r][col].abs() > a[pivot][col].abs() {
+                pivot = r;
+            }
+        }
+        a.swap(col, pivot);
+        let p = a[col][col];
+        if p == 0.0 {
+            return [0.0; 16]
+        }
+        for c in 0..2 * n {
+            a[col][c] /= p;
+        }
+        for r in 0..n {
+            if r != col {
+                let f = a[r][col];
+                for c in 0..2 * n {
+                    a[r][c] -= f * a[col][c];
+                }
+            }
+        }
+    }
+    let mut out = [0.0; 16];
+    for r in 0..n {
+        for c in 0..n {
+            out[c * n + r] = a[r][n + c];
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::makepad_live_compiler::*,
+    };
+
+    struct NoSampler;
+
+    impl ShaderSampler for NoSampler {
+        fn sample2d(&self, _slot: usize, _pos: [f32; 2]) -> [f32; 4] {[0.0; 4]}
+        fn sample2d_rt(&self, _slot: usize, _pos: [f32; 2]) -> [f32; 4] {[0.0; 4]}
+    }
+
+    struct DrawTest;
+
+    // parses and analyses a draw shader the way the platform does for a live_design! block
+    fn analyse(source: &str) -> (LiveRegistry, ShaderRegistry, DrawShaderPtr) {
+        let module_id = LiveModuleId::from_str("test::interpret").unwrap();
+        let live_type_info = LiveTypeInfo {
+            live_type: std::any::TypeId::of::<DrawTest>(),
+            type_name: live_id!(DrawTest),
+            module_id,
+            live_ignore: true,
+            fields: Vec::new(),
+        };
+        let mut live_registry = LiveRegistry::default();
+        if let Err(err) = live_registry.register_live_file("interpret.rs", "", module_id, source.to_string(), vec![live_type_info], TextPos::default()) {
+            panic!("{}", err);
+        }
+        let mut errors = Vec::new();
+        live_registry.expand_all_documents(&mut errors);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let draw_shader_ptr = DrawShaderPtr(live_registry.module_id_and_name_to_ptr(module_id, live_id!(DrawTest)).unwrap());
+        let mut shader_registry = ShaderRegistry::new();
+        shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | _, _, _, _, _, _ | {}).unwrap();
+        (live_registry, shader_registry, draw_shader_ptr)
+    }
+
+    fn run(source: &str, fields: &[(LiveId, Value)]) -> ([f32; 4], [f32; 4]) {
+        let (_live_registry, shader_registry, draw_shader_ptr) = analyse(source);
+        let const_table = shader_registry.compute_const_table(draw_shader_ptr);
+        let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+        let interp = ShaderInterp::new(&shader_registry, draw_shader_def, &const_table);
+        let mut values: Vec<Value> = draw_shader_def.fields.iter().map( | field | {
+            Value::zero(field.ty_expr.ty.borrow().as_ref().unwrap(), &shader_registry)
+        }).collect();
+        for (id, value) in fields {
+            values[interp.field_index(Ident(*id)).unwrap()] = value.clone();
+        }
+        let mut derivatives = ShaderDerivatives::default();
+        let mut env = ShaderInterpEnv {
+            fields: &mut values,
+            live_uniforms: &[],
+            sampler: &NoSampler,
+            derivatives: &mut derivatives,
+        };
+        let vertex = interp.call_vertex(&mut env).to_vec4();
+        let pixel = interp.call_pixel(&mut env).to_vec4();
+        (vertex, pixel)
+    }
+
+    #[test]
+    fn test_arithmetic_and_builtins() {
+        let (vertex, pixel) = run(r#"
+            DrawTest = {{DrawTest}} {
+                instance scale: 2.0
+                fn vertex(self) -> vec4 {
+                    let v = vec2(1.0, 2.0) * self.scale;
+                    return vec4(v, max(v.x, v.y) - 1.0, 1.0);
+                }
+                fn pixel(self) -> vec4 {
+                    return vec4(clamp(1.5, 0.0, 1.0), mix(0.0, 1.0, 0.25), abs(-0.5), dot(vec2(1.0, 2.0), vec2(3.0, 4.0)));
+                }
+            }
+        "#, &[(live_id!(scale), Value::Float(3.0))]);
+        assert_eq!(vertex, [3.0, 6.0, 5.0, 1.0]);
+        assert_eq!(pixel, [1.0, 0.25, 0.5, 11.0]);
+    }
+
+    #[test]
+    fn test_control_flow_and_functions() {
+        let (vertex, pixel) = run(r#"
+            DrawTest = {{DrawTest}} {
+                fn sum_to(self, n: float) -> float {
+                    let sum = 0.0;
+                    for i in 0..10 {
+                        if n <= float(i) {
+                            break;
+                        }
+                        sum += float(i);
+                    }
+                    return sum;
+                }
+                fn vertex(self) -> vec4 {
+                    return vec4(self.sum_to(4.0), self.sum_to(100.0), 0.0, 1.0);
+                }
+                fn pixel(self) -> vec4 {
+                    let c = vec4(0.0);
+                    c.xz = vec2(1.0, 0.5);
+                    if c.x > 0.5 {
+                        c.w = 1.0;
+                    }
+                    else {
+                        c.w = 0.0;
+                    }
+                    return c;
+                }
+            }
+        "#, &[]);
+        assert_eq!(vertex, [6.0, 45.0, 0.0, 1.0]);
+        assert_eq!(pixel, [1.0, 0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_slots_round_trip() {
+        let value = Value::from_slots(&Ty::Vec3, &[1.0, 2.0, 3.0, 4.0]);
+        let mut slots = Vec::new();
+        value.to_slots(&mut slots);
+        assert_eq!(slots, [1.0, 2.0, 3.0]);
+        assert_eq!(Value::from_slots(&Ty::Float, &[]).to_f32(), 0.0);
+    }
+}
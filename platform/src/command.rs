@@ -0,0 +1,463 @@
+use {
+    std::fmt,
+    crate::{
+        makepad_live_id::LiveId,
+        event::{KeyCode, KeyEvent, KeyModifiers},
+    },
+};
+
+// The command registry maps key chords to commands. A keydown goes to the
+// widget with key focus first, if that widget doesn't mark it handled and it
+// matches a chord it is sent as an Event::Command to the whole app. Bindings
+// can be changed at runtime or loaded from a config file in the form:
+//
+//   # comment
+//   save = Cmd+S
+//   find_in_files = Cmd+Shift+F, F3
+//   close_tab =
+//
+// Cmd is the command key on apple platforms and control everywhere else.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct KeyChord {
+    pub key_code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(key_code: KeyCode) -> Self {
+        Self {key_code, modifiers: KeyModifiers::default()}
+    }
+
+    pub fn cmd(key_code: KeyCode) -> Self {
+        Self::new(key_code).with_cmd()
+    }
+
+    pub fn with_cmd(mut self) -> Self {
+        if cmd_is_logo() {
+            self.modifiers.logo = true;
+        }
+        else {
+            self.modifiers.control = true;
+        }
+        self
+    }
+
+    pub fn with_shift(mut self) -> Self {
+        self.modifiers.shift = true;
+        self
+    }
+
+    pub fn with_alt(mut self) -> Self {
+        self.modifiers.alt = true;
+        self
+    }
+
+    pub fn with_control(mut self) -> Self {
+        self.modifiers.control = true;
+        self
+    }
+
+    pub fn matches(&self, key_event: &KeyEvent) -> bool {
+        self.key_code == key_event.key_code && self.modifiers == key_event.modifiers
+    }
+
+    pub fn parse(chord: &str) -> Result<Self, String> {
+        let mut key_code = None;
+        let mut out = Self::default();
+        for part in chord.split('+').map( | part | part.trim()) {
+            if key_code.is_some() {
+                return Err(format!("key chord {} has modifiers after its key", chord))
+            }
+            match part.to_lowercase().as_str() {
+                "cmd" => out = out.with_cmd(),
+                "ctrl" | "control" => out.modifiers.control = true,
+                "shift" => out.modifiers.shift = true,
+                "alt" | "option" => out.modifiers.alt = true,
+                "logo" | "super" | "meta" | "win" => out.modifiers.logo = true,
+                _ => if let Some(code) = key_code_from_name(part) {
+                    key_code = Some(code);
+                }
+                else {
+                    return Err(format!("key chord {} has unknown key {}", chord, part))
+                }
+            }
+        }
+        if let Some(key_code) = key_code {
+            out.key_code = key_code;
+            Ok(out)
+        }
+        else {
+            Err(format!("key chord {} has no key", chord))
+        }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let m = &self.modifiers;
+        if m.control {write!(f, "Ctrl+") ?;}
+        if m.alt {write!(f, "Alt+") ?;}
+        if m.shift {write!(f, "Shift+") ?;}
+        if m.logo {write!(f, "{}+", if cmd_is_logo() {"Cmd"} else {"Super"}) ?;}
+        write!(f, "{}", key_code_name(self.key_code))
+    }
+}
+
+fn cmd_is_logo() -> bool {
+    cfg!(any(target_os = "macos", target_os = "ios"))
+}
+
+#[derive(Clone, Debug)]
+pub struct CxCommand {
+    pub id: LiveId,
+    pub name: String,
+    pub defaults: Vec<KeyChord>,
+    pub chords: Vec<KeyChord>,
+    // a shortcut of a menu item, it is sent as Event::MacosMenuCommand like a pick from the menu
+    pub menu_item: bool,
+}
+
+#[derive(Default)]
+pub struct CxCommands {
+    commands: Vec<CxCommand>,
+}
+
+impl CxCommands {
+    // declares a command with its default chords, re-registering a command resets
+    // its defaults but keeps chords that were bound to it already
+    pub fn register(&mut self, name: &str, defaults: &[KeyChord]) -> LiveId {
+        let id = LiveId::from_str_with_lut(name).unwrap_or(LiveId::from_str(name));
+        self.register_id(id, name, defaults);
+        id
+    }
+
+    // like register, for a command that already has an id. The name is what
+    // the bindings config calls it
+    pub fn register_id(&mut self, id: LiveId, name: &str, defaults: &[KeyChord]) {
+        if let Some(command) = self.commands.iter_mut().find( | command | command.id == id) {
+            if command.chords == command.defaults {
+                command.chords = defaults.to_vec();
+            }
+            command.defaults = defaults.to_vec();
+            command.name = name.to_string();
+        }
+        else {
+            self.commands.push(CxCommand {
+                id,
+                name: name.to_string(),
+                defaults: defaults.to_vec(),
+                chords: defaults.to_vec(),
+                menu_item: false,
+            });
+        }
+    }
+
+    // registers the shortcut of a menu item, named after its id or the id's
+    // value when it was never interned
+    pub fn register_menu_item(&mut self, id: LiveId, defaults: &[KeyChord]) {
+        let name = id.as_string( | name | name.map( | name | name.to_string())).unwrap_or_else( || format!("{:016x}", id.0));
+        self.register_id(id, &name, defaults);
+        if let Some(command) = self.commands.iter_mut().find( | command | command.id == id) {
+            command.menu_item = true;
+        }
+    }
+
+    pub fn is_menu_item(&self, command: LiveId) -> bool {
+        self.commands.iter().any( | c | c.id == command && c.menu_item)
+    }
+
+    pub fn bind(&mut self, command: LiveId, chords: &[KeyChord]) {
+        if let Some(command) = self.commands.iter_mut().find( | c | c.id == command) {
+            command.chords = chords.to_vec();
+        }
+    }
+
+    pub fn reset_bindings(&mut self) {
+        for command in &mut self.commands {
+            command.chords = command.defaults.clone();
+        }
+    }
+
+    pub fn commands(&self) -> &[CxCommand] {
+        &self.commands
+    }
+
+    pub fn chords(&self, command: LiveId) -> &[KeyChord] {
+        self.commands.iter().find( | c | c.id == command).map( | c | c.chords.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn command_for_key(&self, key_event: &KeyEvent) -> Option<LiveId> {
+        self.commands.iter().find( | c | c.chords.iter().any( | chord | chord.matches(key_event))).map( | c | c.id)
+    }
+
+    // applies a bindings config on top of the current bindings. Lines naming
+    // unknown commands are errors, every other line is still applied
+    pub fn load_bindings(&mut self, config: &str) -> Result<(), String> {
+        let mut errors = Vec::new();
+        for (line_nr, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, chords)) = line.split_once('=') else {
+                errors.push(format!("line {}: expected command = chords", line_nr + 1));
+                continue;
+            };
+            let name = name.trim();
+            let Some(id) = self.commands.iter().find( | c | c.name == name).map( | c | c.id) else {
+                errors.push(format!("line {}: unknown command {}", line_nr + 1, name));
+                continue;
+            };
+            let chords: Result<Vec<KeyChord>, String> = chords.split(',')
+                .map( | chord | chord.trim())
+                .filter( | chord | !chord.is_empty())
+                .map(KeyChord::parse)
+                .collect();
+            match chords {
+                Ok(chords) => self.bind(id, &chords),
+                Err(e) => errors.push(format!("line {}: {}", line_nr + 1, e))
+            }
+        }
+        if errors.is_empty() {Ok(())} else {Err(errors.join("\n"))}
+    }
+
+    // writes all current bindings in the format load_bindings reads
+    pub fn save_bindings(&self) -> String {
+        let mut out = String::new();
+        for command in &self.commands {
+            let chords: Vec<String> = command.chords.iter().map( | chord | chord.to_string()).collect();
+            out.push_str(format!("{} = {}", command.name, chords.join(", ")).trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Escape, "Escape"),
+    (KeyCode::Backtick, "`"),
+    (KeyCode::Key0, "0"),
+    (KeyCode::Key1, "1"),
+    (KeyCode::Key2, "2"),
+    (KeyCode::Key3, "3"),
+    (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"),
+    (KeyCode::Key6, "6"),
+    (KeyCode::Key7, "7"),
+    (KeyCode::Key8, "8"),
+    (KeyCode::Key9, "9"),
+    (KeyCode::Minus, "-"),
+    (KeyCode::Equals, "="),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::KeyQ, "Q"),
+    (KeyCode::KeyW, "W"),
+    (KeyCode::KeyE, "E"),
+    (KeyCode::KeyR, "R"),
+    (KeyCode::KeyT, "T"),
+    (KeyCode::KeyY, "Y"),
+    (KeyCode::KeyU, "U"),
+    (KeyCode::KeyI, "I"),
+    (KeyCode::KeyO, "O"),
+    (KeyCode::KeyP, "P"),
+    (KeyCode::LBracket, "["),
+    (KeyCode::RBracket, "]"),
+    (KeyCode::ReturnKey, "Enter"),
+    (KeyCode::KeyA, "A"),
+    (KeyCode::KeyS, "S"),
+    (KeyCode::KeyD, "D"),
+    (KeyCode::KeyF, "F"),
+    (KeyCode::KeyG, "G"),
+    (KeyCode::KeyH, "H"),
+    (KeyCode::KeyJ, "J"),
+    (KeyCode::KeyK, "K"),
+    (KeyCode::KeyL, "L"),
+    (KeyCode::Semicolon, ";"),
+    (KeyCode::Quote, "'"),
+    (KeyCode::Backslash, "\\"),
+    (KeyCode::KeyZ, "Z"),
+    (KeyCode::KeyX, "X"),
+    (KeyCode::KeyC, "C"),
+    (KeyCode::KeyV, "V"),
+    (KeyCode::KeyB, "B"),
+    (KeyCode::KeyN, "N"),
+    (KeyCode::KeyM, "M"),
+    (KeyCode::Comma, "Comma"),
+    (KeyCode::Period, "."),
+    (KeyCode::Slash, "/"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::F1, "F1"),
+    (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"),
+    (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"),
+    (KeyCode::F6, "F6"),
+    (KeyCode::F7, "F7"),
+    (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"),
+    (KeyCode::F11, "F11"),
+    (KeyCode::F12, "F12"),
+    (KeyCode::PrintScreen, "PrintScreen"),
+    (KeyCode::ScrollLock, "ScrollLock"),
+    (KeyCode::Pause, "Pause"),
+    (KeyCode::Insert, "Insert"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::Numpad0, "Numpad0"),
+    (KeyCode::Numpad1, "Numpad1"),
+    (KeyCode::Numpad2, "Numpad2"),
+    (KeyCode::Numpad3, "Numpad3"),
+    (KeyCode::Numpad4, "Numpad4"),
+    (KeyCode::Numpad5, "Numpad5"),
+    (KeyCode::Numpad6, "Numpad6"),
+    (KeyCode::Numpad7, "Numpad7"),
+    (KeyCode::Numpad8, "Numpad8"),
+    (KeyCode::Numpad9, "Numpad9"),
+    (KeyCode::NumpadEquals, "NumpadEquals"),
+    (KeyCode::NumpadSubtract, "NumpadSubtract"),
+    (KeyCode::NumpadAdd, "NumpadAdd"),
+    (KeyCode::NumpadDecimal, "NumpadDecimal"),
+    (KeyCode::NumpadMultiply, "NumpadMultiply"),
+    (KeyCode::NumpadDivide, "NumpadDivide"),
+    (KeyCode::NumpadEnter, "NumpadEnter"),
+    (KeyCode::ArrowUp, "Up"),
+    (KeyCode::ArrowDown, "Down"),
+    (KeyCode::ArrowLeft, "Left"),
+    (KeyCode::ArrowRight, "Right"),
+];
+
+fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES.iter().find( | (_, n) | n.eq_ignore_ascii_case(name)).map( | (key_code, _) | *key_code)
+}
+
+fn key_code_name(key_code: KeyCode) -> &'static str {
+    KEY_NAMES.iter().find( | (k, _) | *k == key_code).map( | (_, name) | *name).unwrap_or("Unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_event(key_code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent {key_code, modifiers, ..Default::default()}
+    }
+
+    fn commands() -> CxCommands {
+        let mut commands = CxCommands::default();
+        commands.register("save", &[KeyChord::cmd(KeyCode::KeyS)]);
+        commands.register("find_in_files", &[KeyChord::cmd(KeyCode::KeyF).with_shift()]);
+        commands
+    }
+
+    #[test]
+    fn parse_chords() {
+        assert_eq!(KeyChord::parse("F3"), Ok(KeyChord::new(KeyCode::F3)));
+        assert_eq!(KeyChord::parse("Ctrl+Shift+S"), Ok(KeyChord::new(KeyCode::KeyS).with_control().with_shift()));
+        assert_eq!(KeyChord::parse(" alt + escape "), Ok(KeyChord::new(KeyCode::Escape).with_alt()));
+        assert_eq!(KeyChord::parse("shift+ctrl+s"), KeyChord::parse("Ctrl+Shift+S"));
+        assert_eq!(KeyChord::parse("Cmd+S"), Ok(KeyChord::cmd(KeyCode::KeyS)));
+        assert!(KeyChord::parse("Super+S").unwrap().modifiers.logo);
+    }
+
+    #[test]
+    fn parse_chord_errors() {
+        assert!(KeyChord::parse("").is_err());
+        assert!(KeyChord::parse("Ctrl+Shift").is_err());
+        assert!(KeyChord::parse("Ctrl+Banana").is_err());
+        assert!(KeyChord::parse("S+Ctrl").is_err());
+        assert!(KeyChord::parse("S+T").is_err());
+    }
+
+    #[test]
+    fn chord_display_parses_back() {
+        for chord in [
+            KeyChord::cmd(KeyCode::KeyS),
+            KeyChord::new(KeyCode::F3),
+            KeyChord::new(KeyCode::Comma).with_control().with_alt().with_shift(),
+        ] {
+            assert_eq!(KeyChord::parse(&chord.to_string()), Ok(chord));
+        }
+    }
+
+    #[test]
+    fn chords_match_exact_modifiers() {
+        let chord = KeyChord::new(KeyCode::KeyS).with_control();
+        let control = KeyModifiers {control: true, ..Default::default()};
+        let control_shift = KeyModifiers {control: true, shift: true, ..Default::default()};
+        assert!(chord.matches(&key_event(KeyCode::KeyS, control)));
+        assert!(!chord.matches(&key_event(KeyCode::KeyS, control_shift)));
+        assert!(!chord.matches(&key_event(KeyCode::KeyT, control)));
+    }
+
+    #[test]
+    fn load_bindings() {
+        let mut commands = commands();
+        let save = LiveId::from_str("save");
+        let find_in_files = LiveId::from_str("find_in_files");
+        commands.load_bindings("
+            # a comment
+            save = Ctrl+Alt+S, F2
+            find_in_files =
+        ").unwrap();
+        assert_eq!(commands.chords(save), &[
+            KeyChord::new(KeyCode::KeyS).with_control().with_alt(),
+            KeyChord::new(KeyCode::F2)
+        ]);
+        assert!(commands.chords(find_in_files).is_empty());
+        assert_eq!(commands.command_for_key(&key_event(KeyCode::F2, KeyModifiers::default())), Some(save));
+        commands.reset_bindings();
+        assert_eq!(commands.chords(save), &[KeyChord::cmd(KeyCode::KeyS)]);
+    }
+
+    #[test]
+    fn load_bindings_reports_bad_lines() {
+        let mut commands = commands();
+        let err = commands.load_bindings("
+            open = Ctrl+O
+            save = Ctrl+Banana
+            no equals sign
+            find_in_files = F3
+        ").unwrap_err();
+        let errors: Vec<&str> = err.lines().collect();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("line 2:") && errors[0].contains("unknown command open"));
+        assert!(errors[1].starts_with("line 3:") && errors[1].contains("Banana"));
+        assert!(errors[2].starts_with("line 4:"));
+        // the good line still applies and the bad chord leaves save alone
+        assert_eq!(commands.chords(LiveId::from_str("find_in_files")), &[KeyChord::new(KeyCode::F3)]);
+        assert_eq!(commands.chords(LiveId::from_str("save")), &[KeyChord::cmd(KeyCode::KeyS)]);
+    }
+
+    #[test]
+    fn save_bindings_loads_back() {
+        let mut commands = commands();
+        commands.bind(LiveId::from_str("save"), &[KeyChord::new(KeyCode::F2), KeyChord::cmd(KeyCode::KeyS).with_shift()]);
+        commands.bind(LiveId::from_str("find_in_files"), &[]);
+        let saved = commands.save_bindings();
+        let mut loaded = self::commands();
+        loaded.load_bindings(&saved).unwrap();
+        for command in commands.commands() {
+            assert_eq!(loaded.chords(command.id), command.chords.as_slice());
+        }
+    }
+    #[test]
+    fn register_menu_items_by_id() {
+        let mut commands = commands();
+        // from_str doesn't intern, so this id has no name to print
+        let item = LiveId::from_str("menu_item_without_a_name");
+        commands.register_menu_item(item, &[KeyChord::cmd(KeyCode::KeyK)]);
+        assert!(commands.is_menu_item(item));
+        assert!(!commands.is_menu_item(LiveId::from_str("save")));
+        assert_eq!(commands.command_for_key(&key_event(KeyCode::KeyK, KeyChord::cmd(KeyCode::KeyK).modifiers)), Some(item));
+
+        let name = format!("{:016x}", item.0);
+        assert!(commands.save_bindings().contains(&format!("{} = ", name)));
+        commands.load_bindings(&format!("{} = F4", name)).unwrap();
+        assert_eq!(commands.chords(item), &[KeyChord::new(KeyCode::F4)]);
+    }
+}
@@ -15,6 +15,7 @@ use {
             LiveRegistry,
             LiveFileChange
         },
+        makepad_live_id::LiveId,
         makepad_shader_compiler::ShaderRegistry,
         draw_shader::CxDrawShaders,
         draw_matrix::CxDrawMatrixPool,
@@ -30,6 +31,7 @@ use {
             NextFrame,
         },
         cx_api::CxOsOp,
+        command::CxCommands,
         area::Area,
         gpu_info::GpuInfo,
         window::CxWindowPool,
@@ -76,6 +78,7 @@ pub struct Cx {
     pub (crate) next_frame_id: u64,
     
     pub keyboard: CxKeyboard,
+    pub commands: CxCommands,
     pub fingers: CxFingers,
     pub (crate) ime_area: Area,
     pub (crate) drag_drop: CxDragDrop,
@@ -88,6 +91,8 @@ pub struct Cx {
     
     pub (crate) triggers: HashMap<Area, Vec<Trigger >>,
     
+    pub (crate) menu_commands: Vec<LiveId>,
+    
     pub live_registry: Rc<RefCell<LiveRegistry >>,
 
     pub (crate) live_file_change_receiver: std::sync::mpsc::Receiver<Vec<LiveFileChange>>,
@@ -146,6 +151,7 @@ pub enum OsType {
     Android(AndroidParams),
     LinuxWindow (LinuxWindowParams),
     LinuxDirect,
+    LinuxHeadless,
     Web(WebParams)
 }
 
@@ -228,6 +234,7 @@ impl Cx {
             next_frame_id: 1,
             
             keyboard: Default::default(),
+            commands: Default::default(),
             fingers: Default::default(),
             drag_drop: Default::default(),
             ime_area: Default::default(),
@@ -239,6 +246,7 @@ impl Cx {
             dependencies: Default::default(),
             
             triggers: Default::default(),
+            menu_commands: Default::default(),
             
             live_registry: Rc::new(RefCell::new(LiveRegistry::default())),
             
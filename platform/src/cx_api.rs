@@ -15,6 +15,7 @@ use {
             Trigger,
             NextFrame,
             HttpRequest,
+            ClipboardData,
         },
         draw_list::DrawListId,
         window::WindowId,
@@ -60,7 +61,7 @@ pub enum CxOsOp {
     XrStartPresenting,
     XrStopPresenting,
     
-    ShowTextIME(Area, DVec2),
+    ShowTextIME(Area, DVec2, f64),
     HideTextIME,
     SetCursor(MouseCursor),
     StartTimer {timer_id: u64, interval: f64, repeats: bool},
@@ -70,6 +71,8 @@ pub enum CxOsOp {
     StartDragging(Vec<DragItem>),
     UpdateMacosMenu(MacosMenu),
     ShowClipboardActions(String),
+    CopyToClipboard(ClipboardData),
+    ReadClipboard{request_id: LiveId, mime_types: Vec<String>},
 
     HttpRequest{request_id: LiveId, request:HttpRequest},
 
@@ -117,6 +120,11 @@ impl Cx {
         self.platform_ops.push(CxOsOp::UpdateMacosMenu(menu));
     }
     
+    // sends Event::MacosMenuCommand after the current event, for menus drawn by the app itself
+    pub fn send_menu_command(&mut self, command: LiveId) {
+        self.menu_commands.push(command);
+    }
+    
     pub fn quit(&mut self){
         self.platform_ops.push(CxOsOp::Quit);
     }
@@ -127,10 +135,12 @@ impl Cx {
         }
     }
     
-    pub fn show_text_ime(&mut self, area: Area, pos: DVec2) {
+    // pos is the top of the text cursor relative to the area, the candidate
+    // window goes below the line that starts there
+    pub fn show_text_ime(&mut self, area: Area, pos: DVec2, line_height: f64) {
         if !self.keyboard.text_ime_dismissed {
             self.ime_area = area;
-            self.platform_ops.push(CxOsOp::ShowTextIME(area, pos));
+            self.platform_ops.push(CxOsOp::ShowTextIME(area, pos, line_height));
         }
     }
     
@@ -148,6 +158,19 @@ impl Cx {
         self.platform_ops.push(CxOsOp::ShowClipboardActions(selected));
     }
 
+    pub fn copy_to_clipboard(&mut self, data: ClipboardData) {
+        self.platform_ops.push(CxOsOp::CopyToClipboard(data));
+    }
+    
+    // the formats that are available come back as Event::Clipboard with the same request_id,
+    // an empty one where the clipboard can't be read
+    pub fn read_clipboard(&mut self, request_id: LiveId, mime_types: &[&str]) {
+        self.platform_ops.push(CxOsOp::ReadClipboard {
+            request_id,
+            mime_types: mime_types.iter().map( | m | m.to_string()).collect()
+        });
+    }
+
     pub fn start_dragging(&mut self, items: Vec<DragItem>) {
         self.platform_ops.iter().for_each( | p | {
             if let CxOsOp::StartDragging{..} = p {
@@ -395,6 +418,10 @@ impl Cx {
         self.keyboard.revert_key_focus();
     }
     
+    pub fn set_key_down_handled(&mut self) {
+        self.keyboard.set_key_down_handled();
+    }
+    
     pub fn has_key_focus(&self, focus_area: Area) -> bool {
         self.keyboard.has_key_focus(focus_area)
     }
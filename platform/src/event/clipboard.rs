@@ -0,0 +1,213 @@
+use {
+    std::rc::Rc,
+    crate::{
+        makepad_live_id::*,
+        event::Event,
+    },
+};
+
+pub const MIME_TEXT_PLAIN: &str = "text/plain";
+pub const MIME_TEXT_HTML: &str = "text/html";
+pub const MIME_IMAGE_PNG: &str = "image/png";
+pub const MIME_URI_LIST: &str = "text/uri-list";
+
+/// The contents of the clipboard, one buffer per mime type, in order of preference.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClipboardData {
+    pub formats: Vec<ClipboardFormat>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClipboardFormat {
+    pub mime_type: String,
+    pub data: Rc<Vec<u8>>,
+}
+
+/// Sent in response to `Cx::read_clipboard` with the formats that were available.
+#[derive(Clone, Debug)]
+pub struct ClipboardEvent {
+    pub request_id: LiveId,
+    pub data: ClipboardData,
+}
+
+impl ClipboardData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_text(text: &str) -> Self {
+        Self::new().with_text(text)
+    }
+
+    pub fn with_format(mut self, mime_type: &str, data: Vec<u8>) -> Self {
+        self.set_format(mime_type, data);
+        self
+    }
+
+    pub fn with_text(self, text: &str) -> Self {
+        self.with_format(MIME_TEXT_PLAIN, text.as_bytes().to_vec())
+    }
+
+    pub fn with_html(self, html: &str) -> Self {
+        self.with_format(MIME_TEXT_HTML, html.as_bytes().to_vec())
+    }
+
+    pub fn with_png(self, png: Vec<u8>) -> Self {
+        self.with_format(MIME_IMAGE_PNG, png)
+    }
+
+    pub fn with_file_paths(self, paths: &[String]) -> Self {
+        let mut uri_list = String::new();
+        for path in paths {
+            uri_list.push_str("file://");
+            uri_list.push_str(&encode_uri_path(path));
+            uri_list.push_str("\r\n");
+        }
+        self.with_format(MIME_URI_LIST, uri_list.into_bytes())
+    }
+
+    pub fn set_format(&mut self, mime_type: &str, data: Vec<u8>) {
+        let data = Rc::new(data);
+        if let Some(format) = self.formats.iter_mut().find( | f | f.mime_type == mime_type) {
+            format.data = data;
+        }
+        else {
+            self.formats.push(ClipboardFormat {
+                mime_type: mime_type.to_string(),
+                data
+            });
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.formats.is_empty()
+    }
+
+    pub fn mime_types(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map( | f | f.mime_type.as_str())
+    }
+
+    pub fn get(&self, mime_type: &str) -> Option<&Rc<Vec<u8>>> {
+        self.formats.iter().find( | f | f.mime_type == mime_type).map( | f | &f.data)
+    }
+
+    pub fn text(&self) -> Option<String> {
+        self.get(MIME_TEXT_PLAIN).map( | data | String::from_utf8_lossy(data).into_owned())
+    }
+
+    pub fn html(&self) -> Option<String> {
+        self.get(MIME_TEXT_HTML).map( | data | String::from_utf8_lossy(data).into_owned())
+    }
+
+    pub fn png(&self) -> Option<&Rc<Vec<u8>>> {
+        self.get(MIME_IMAGE_PNG)
+    }
+
+    /// The local file paths in the text/uri-list format, other uris are skipped.
+    pub fn file_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        if let Some(data) = self.get(MIME_URI_LIST) {
+            for line in String::from_utf8_lossy(data).lines() {
+                let line = line.trim();
+                // lines starting with # are comments
+                if line.starts_with('#') {
+                    continue;
+                }
+                if let Some(path) = line.strip_prefix("file://") {
+                    // skip the hostname, if any
+                    if let Some(start) = path.find('/') {
+                        paths.push(decode_uri_path(&path[start..]));
+                    }
+                }
+            }
+        }
+        paths
+    }
+}
+
+impl Event {
+    pub fn clipboard(&self, request_id: LiveId) -> Option<&ClipboardData> {
+        match self {
+            Event::Clipboard(e) if e.request_id == request_id => Some(&e.data),
+            _ => None
+        }
+    }
+}
+
+// percent encodes a file path for a text/uri-list
+pub(crate) fn encode_uri_path(path: &str) -> String {
+    let mut out = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    out
+}
+
+pub(crate) fn decode_uri_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then( | hex | u8::from_str_radix(hex, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri_paths_round_trip() {
+        for path in [
+            "/home/user/file.rs",
+            "/tmp/with space/100% done#1?.txt",
+            "/home/ärger/日本語/файл",
+            "C:/Users/me/a+b=c.png",
+        ] {
+            let encoded = encode_uri_path(path);
+            assert!(encoded.is_ascii() && !encoded.contains(' ') && !encoded.contains('#'));
+            assert_eq!(decode_uri_path(&encoded), path);
+        }
+        assert_eq!(encode_uri_path("/a b/ü"), "/a%20b/%C3%BC");
+    }
+
+    #[test]
+    fn decode_uri_path_escapes() {
+        assert_eq!(decode_uri_path("/a%20b"), "/a b");
+        assert_eq!(decode_uri_path("/%e6%97%a5"), "/日");
+        // broken escapes are kept as they are
+        assert_eq!(decode_uri_path("/100%"), "/100%");
+        assert_eq!(decode_uri_path("/%zz/%4"), "/%zz/%4");
+    }
+
+    #[test]
+    fn file_paths_round_trip() {
+        let paths = vec!["/tmp/a b.txt".to_string(), "/home/ärger/日本語.md".to_string()];
+        assert_eq!(ClipboardData::new().with_file_paths(&paths).file_paths(), paths);
+    }
+
+    #[test]
+    fn file_paths_from_uri_list() {
+        let data = ClipboardData::new().with_format(MIME_URI_LIST, b"# copied from a file manager\r\n\
+            file:///tmp/one%20two.txt\r\n\
+            https://example.com/not/a/file\r\n\
+            \r\n\
+            file://localhost/home/caf%C3%A9\n\
+            #file:///commented/out\r\n".to_vec());
+        assert_eq!(data.file_paths(), vec!["/tmp/one two.txt".to_string(), "/home/café".to_string()]);
+        assert!(ClipboardData::from_text("file:///tmp/a").file_paths().is_empty());
+    }
+}
@@ -16,6 +16,7 @@ use {
             drag_drop::*,
             network::*,
             video_decoding::*,
+            clipboard::*,
         },
         animator::Ease,
         audio::AudioDevicesEvent,
@@ -59,13 +60,16 @@ pub enum Event {
     Signal,
     Trigger(TriggerEvent),
     MacosMenuCommand(LiveId),
+    Command(LiveId),
     KeyFocus(KeyFocusEvent),
     KeyFocusLost(KeyFocusEvent),
     KeyDown(KeyEvent),
     KeyUp(KeyEvent),
     TextInput(TextInputEvent),
+    TextPreedit(TextPreeditEvent),
     TextCopy(TextClipboardEvent),
     TextCut(TextClipboardEvent),
+    Clipboard(ClipboardEvent),
     
     Drag(DragEvent),
     Drop(DropEvent),
@@ -92,6 +96,7 @@ pub enum Hit{
     KeyUp(KeyEvent),
     Trigger(TriggerHitEvent),
     TextInput(TextInputEvent),
+    TextPreedit(TextPreeditEvent),
     TextCopy(TextClipboardEvent),
     TextCut(TextClipboardEvent),
     
@@ -634,6 +634,11 @@ impl Event {
                     return Hit::TextInput(ti.clone())
                 }
             },
+            Event::TextPreedit(tp) => {
+                if cx.keyboard.has_key_focus(area) {
+                    return Hit::TextPreedit(tp.clone())
+                }
+            },
             Event::TextCopy(tc) => {
                 if cx.keyboard.has_key_focus(area) {
                     return Hit::TextCopy(tc.clone());
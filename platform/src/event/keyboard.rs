@@ -22,7 +22,8 @@ pub struct CxKeyboard {
     pub (crate) key_focus: Area,
     #[allow(dead_code)]
     pub (crate) keys_down: Vec<KeyEvent>,
-    pub (crate) text_ime_dismissed: bool
+    pub (crate) text_ime_dismissed: bool,
+    pub (crate) key_down_handled: bool,
 }
 
 impl CxKeyboard {
@@ -56,6 +57,11 @@ impl CxKeyboard {
         self.text_ime_dismissed = false;
     }
     
+    // a key down the focused widget used doesn't also run a command
+    pub fn set_key_down_handled(&mut self) {
+        self.key_down_handled = true;
+    }
+    
     pub (crate) fn update_area(&mut self, old_area: Area, new_area: Area) {
         if self.key_focus == old_area {
             self.key_focus = new_area
@@ -113,13 +119,22 @@ pub struct KeyFocusEvent {
     pub focus: Area,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, SerBin, DeBin, SerJson, DeJson)]
 pub struct TextInputEvent {
     pub input: String,
     pub replace_last: bool,
     pub was_paste: bool
 }
 
+// the text an input method is composing, shown underlined at the cursor
+// until it is committed with a TextInputEvent. An empty text ends the composition
+#[derive(Clone, Debug, Default, SerBin, DeBin, SerJson, DeJson)]
+pub struct TextPreeditEvent {
+    pub text: String,
+    // caret position within the preedit text, in chars
+    pub cursor: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct TextClipboardEvent {
     pub response: Rc<RefCell<Option<String>>>
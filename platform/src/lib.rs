@@ -31,6 +31,7 @@ mod pass;
 mod texture;
 mod cursor;
 mod macos_menu;
+mod command;
 mod animator;
 mod gpu_info;
 mod geometry;
@@ -54,6 +55,9 @@ pub use makepad_objc_sys;
 #[cfg(target_os = "windows")]
 pub use ::makepad_windows as windows;
 
+#[cfg(target_os = "linux")]
+pub use makepad_http;
+
 pub use makepad_futures;
  
 pub use {
@@ -175,7 +179,11 @@ pub use {
             KeyEvent,
             KeyFocusEvent,
             TextInputEvent,
+            TextPreeditEvent,
             TextClipboardEvent,
+            ClipboardData,
+            ClipboardFormat,
+            ClipboardEvent,
             WindowCloseRequestedEvent,
             WindowClosedEvent,
             WindowDragQueryResponse,
@@ -195,6 +203,11 @@ pub use {
         },
         cursor::MouseCursor,
         macos_menu::MacosMenu,
+        command::{
+            CxCommands,
+            CxCommand,
+            KeyChord
+        },
         draw_matrix::DrawMatrix,
         window::WindowHandle,
         pass::{
@@ -298,7 +298,7 @@ impl Cx {
                 CxOsOp::XrStopPresenting => {
                     //todo!()
                 },
-                CxOsOp::ShowTextIME(_area, _pos) => {
+                CxOsOp::ShowTextIME(_area, _pos, _line_height) => {
                     IosApp::show_keyboard();
                 },
                 CxOsOp::HideTextIME => {
@@ -320,6 +320,13 @@ impl Cx {
                 CxOsOp::ShowClipboardActions(_request) => {
                     crate::log!("Show clipboard actions not supported yet");
                 }
+                CxOsOp::CopyToClipboard(_data) => {
+                    crate::log!("Copy to clipboard not supported yet");
+                }
+                CxOsOp::ReadClipboard{request_id, ..} => {
+                    crate::log!("Read clipboard not supported yet");
+                    self.call_empty_clipboard_event(request_id);
+                }
                 CxOsOp::WebSocketOpen{request_id, request}=>{
                     web_socket_open(request_id, request, self.os.network_response.sender.clone());
                 }
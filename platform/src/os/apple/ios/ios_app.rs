@@ -195,6 +195,7 @@ impl IosApp {
         let new_geom = WindowGeom {
             xr_is_presenting: false,
             is_topmost: false,
+            is_maximized: false,
             is_fullscreen: true,
             can_fullscreen: false,
             inner_size: new_size,
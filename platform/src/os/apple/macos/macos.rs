@@ -484,7 +484,7 @@ impl Cx {
                 CxOsOp::XrStopPresenting => {
                     //todo!()
                 },
-                CxOsOp::ShowTextIME(area, pos) => {
+                CxOsOp::ShowTextIME(area, pos, _line_height) => {
                     let pos = area.get_clipped_rect(self).pos + pos;
                     metal_windows.iter_mut().for_each( | w | {
                         w.cocoa_window.set_ime_spot(pos);
@@ -518,6 +518,13 @@ impl Cx {
                 CxOsOp::ShowClipboardActions(_request) => {
                     crate::log!("Show clipboard actions not supported yet");
                 }
+                CxOsOp::CopyToClipboard(_data) => {
+                    crate::log!("Copy to clipboard not supported yet");
+                }
+                CxOsOp::ReadClipboard {request_id, ..} => {
+                    crate::log!("Read clipboard not supported yet");
+                    self.call_empty_clipboard_event(request_id);
+                }
                 CxOsOp::WebSocketOpen {request_id, request} => {
                     web_socket_open(request_id, request, self.os.network_response.sender.clone());
                 }
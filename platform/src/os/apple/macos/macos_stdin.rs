@@ -142,6 +142,9 @@ impl Cx {
                 HostToStdin::KeyUp(e) => {
                     self.call_event_handler(&Event::KeyUp(e));
                 }
+                HostToStdin::TextInput(e) => {
+                    self.call_event_handler(&Event::TextInput(e));
+                }
                 HostToStdin::MouseDown(e) => {
                     self.fingers.process_tap_count(
                         dvec2(e.x, e.y),
@@ -342,6 +345,9 @@ impl Cx {
                 CxOsOp::SetCursor(cursor) => {
                     let _ = io::stdout().write_all(StdinToHost::SetCursor(cursor).to_json().as_bytes());
                 },
+                CxOsOp::ReadClipboard {request_id, ..} => {
+                    self.call_empty_clipboard_event(request_id);
+                }
                 _ => ()
                 /*
                 CxOsOp::CloseWindow(_window_id) => {},
@@ -353,7 +359,7 @@ impl Cx {
                 CxOsOp::SetTopmost(_window_id, _is_topmost) => {}
                 CxOsOp::XrStartPresenting(_) => {},
                 CxOsOp::XrStopPresenting(_) => {},
-                CxOsOp::ShowTextIME(_area, _pos) => {},
+                CxOsOp::ShowTextIME(_area, _pos, _line_height) => {},
                 CxOsOp::HideTextIME => {},
                 CxOsOp::SetCursor(_cursor) => {},
                 CxOsOp::StartTimer {timer_id, interval, repeats} => {},
@@ -233,6 +233,7 @@ impl MacosWindow {
         WindowGeom {
             xr_is_presenting: false,
             is_topmost: false,
+            is_maximized: false,
             is_fullscreen: self.is_fullscreen,
             can_fullscreen: false,
             inner_size: self.get_inner_size(),
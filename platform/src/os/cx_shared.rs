@@ -14,7 +14,10 @@ use {
             Event,
             KeyFocusEvent,
             NextFrameEvent,
+            ClipboardEvent,
+            ClipboardData,
         },
+        makepad_live_id::LiveId,
     }
 };
 
@@ -143,11 +146,40 @@ impl Cx {
     }
     
     pub (crate) fn call_event_handler(&mut self, event: &Event) {
+        if let Event::KeyDown(_) = event {
+            self.keyboard.key_down_handled = false;
+        }
         self.inner_call_event_handler(event);
         self.inner_key_focus_change();
         self.handle_triggers();
+        for command in std::mem::take(&mut self.menu_commands) {
+            self.call_event_handler(&Event::MacosMenuCommand(command));
+        }
+        // a key bound to a command runs it when the focused widget didn't use the key
+        if let Event::KeyDown(key_event) = event {
+            if !self.keyboard.key_down_handled {
+                if let Some(command) = self.commands.command_for_key(key_event) {
+                    // menu shortcuts arrive like picks from the menu, so apps handle them once
+                    if self.commands.is_menu_item(command) {
+                        self.call_event_handler(&Event::MacosMenuCommand(command));
+                    }
+                    else {
+                        self.call_event_handler(&Event::Command(command));
+                    }
+                }
+            }
+        }
     }
 
+    // a clipboard read that can't be done still gets its Event::Clipboard, without formats
+    #[allow(dead_code)]
+    pub (crate) fn call_empty_clipboard_event(&mut self, request_id: LiveId) {
+        self.call_event_handler(&Event::Clipboard(ClipboardEvent {
+            request_id,
+            data: ClipboardData::new()
+        }));
+    }
+    
     // helpers
     
     /*
@@ -10,6 +10,7 @@ use {
         area::Area,
         event::{
             KeyEvent,
+            TextInputEvent,
             ScrollEvent,
             MouseDownEvent,
             MouseUpEvent,
@@ -406,6 +407,7 @@ pub enum HostToStdin{
     MouseMove(StdinMouseMove),
     KeyDown(KeyEvent),
     KeyUp(KeyEvent),
+    TextInput(TextInputEvent),
     Scroll(StdinScroll),
     ReloadFile{
         file:String,
@@ -0,0 +1,223 @@
+use {
+    crate::{
+        makepad_live_id::*,
+        makepad_math::*,
+        makepad_live_compiler::LiveFileChange,
+        cx::Cx,
+        cx_api::CxOsOp,
+        thread::Signal,
+        window::CxWindowPool,
+        event::{
+            Event,
+            TimerEvent,
+            WindowGeom,
+            WindowGeomChangeEvent,
+        },
+        os::cx_stdin::HostToStdin,
+    }
+};
+
+// Drives a Cx without any OS backend, for scripted UI tests. Input arrives as
+// the same HostToStdin messages Studio sends to an app running over stdin and
+// is dispatched synchronously. Drawing runs the full draw and layout pass so
+// areas and rects can be inspected, but nothing is rendered. Time only moves
+// forward when the test asks for it, which makes animations deterministic.
+
+pub const TEST_FRAME_TIME: f64 = 1.0 / 60.0;
+
+struct TestTimer {
+    timer_id: u64,
+    interval: f64,
+    next_time: f64,
+    repeats: bool,
+}
+
+pub struct CxTestDriver {
+    time: f64,
+    dpi_factor: f64,
+    inner_size: DVec2,
+    timers: Vec<TestTimer>,
+}
+
+impl CxTestDriver {
+    pub fn new(inner_size: DVec2, dpi_factor: f64) -> Self {
+        Self {
+            time: 0.0,
+            dpi_factor,
+            inner_size,
+            timers: Vec::new(),
+        }
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    // sends Construct and runs until the first frame has been drawn
+    pub fn start(&mut self, cx: &mut Cx) {
+        cx.call_event_handler(&Event::Construct);
+        cx.redraw_all();
+        self.step(cx);
+    }
+
+    pub fn send(&mut self, cx: &mut Cx, msg: HostToStdin) {
+        match msg {
+            HostToStdin::ReloadFile {file, contents} => {
+                let _ = cx.live_file_change_sender.send(vec![LiveFileChange {
+                    file_name: file,
+                    content: contents
+                }]);
+                if cx.handle_live_edit() {
+                    cx.call_event_handler(&Event::LiveEdit);
+                    cx.redraw_all();
+                }
+            }
+            HostToStdin::KeyDown(e) => {
+                cx.keyboard.process_key_down(e.clone());
+                cx.call_event_handler(&Event::KeyDown(e));
+            }
+            HostToStdin::KeyUp(e) => {
+                cx.keyboard.process_key_up(e.clone());
+                cx.call_event_handler(&Event::KeyUp(e));
+            }
+            HostToStdin::TextInput(e) => {
+                cx.call_event_handler(&Event::TextInput(e));
+            }
+            HostToStdin::MouseDown(e) => {
+                cx.fingers.process_tap_count(dvec2(e.x, e.y), e.time);
+                cx.fingers.mouse_down(e.button);
+                cx.call_event_handler(&Event::MouseDown(e.into()));
+            }
+            HostToStdin::MouseMove(e) => {
+                cx.call_event_handler(&Event::MouseMove(e.into()));
+                cx.fingers.cycle_hover_area(live_id!(mouse).into());
+                cx.fingers.switch_captures();
+            }
+            HostToStdin::MouseUp(e) => {
+                let button = e.button;
+                cx.call_event_handler(&Event::MouseUp(e.into()));
+                cx.fingers.mouse_up(button);
+                cx.fingers.cycle_hover_area(live_id!(mouse).into());
+            }
+            HostToStdin::Scroll(e) => {
+                cx.call_event_handler(&Event::Scroll(e.into()))
+            }
+            HostToStdin::WindowGeomChange {dpi_factor, inner_width, inner_height} => {
+                self.dpi_factor = dpi_factor;
+                self.inner_size = dvec2(inner_width, inner_height);
+                let window_id = CxWindowPool::id_zero();
+                let old_geom = cx.windows[window_id].window_geom.clone();
+                let new_geom = self.window_geom();
+                cx.windows[window_id].window_geom = new_geom.clone();
+                cx.call_event_handler(&Event::WindowGeomChange(WindowGeomChangeEvent {
+                    window_id,
+                    old_geom,
+                    new_geom
+                }));
+                cx.redraw_all();
+            }
+            HostToStdin::Tick {time, ..} => {
+                self.advance_to(cx, time);
+            }
+            // there is nothing to present to
+            HostToStdin::Swapchain(_) => ()
+        }
+    }
+
+    // moves the clock forward in frame sized steps, firing timers and next frames along the way
+    pub fn advance_to(&mut self, cx: &mut Cx, time: f64) {
+        while self.time < time {
+            self.time = (self.time + TEST_FRAME_TIME).min(time);
+            self.step(cx);
+        }
+        self.step(cx);
+    }
+
+    // advances time until nothing is animating or redrawing anymore, or max_time has passed
+    pub fn settle(&mut self, cx: &mut Cx, max_time: f64) {
+        let end_time = self.time + max_time;
+        self.step(cx);
+        while self.time < end_time && (cx.new_next_frames.len() != 0 || cx.need_redrawing() || cx.platform_ops.len() != 0) {
+            self.time += TEST_FRAME_TIME;
+            self.step(cx);
+        }
+    }
+
+    // one iteration of an event loop: signals, timers, platform ops, next frame and draw
+    pub fn step(&mut self, cx: &mut Cx) {
+        if Signal::check_and_clear_ui_signal() {
+            cx.call_event_handler(&Event::Signal);
+        }
+        self.handle_platform_ops(cx);
+
+        let mut fired = Vec::new();
+        for timer in &mut self.timers {
+            if timer.next_time <= self.time {
+                fired.push(timer.timer_id);
+                timer.next_time += timer.interval.max(TEST_FRAME_TIME);
+            }
+        }
+        self.timers.retain( | timer | timer.repeats || !fired.contains(&timer.timer_id));
+        for timer_id in fired {
+            cx.call_event_handler(&Event::Timer(TimerEvent {
+                timer_id,
+                time: Some(self.time)
+            }));
+        }
+
+        if cx.new_next_frames.len() != 0 {
+            cx.call_next_frame_event(self.time);
+        }
+        self.handle_platform_ops(cx);
+        if cx.need_redrawing() {
+            cx.call_draw_event();
+            // nothing gets compiled or painted, the draw lists hold all the layout results
+            cx.draw_shaders.compile_set.clear();
+            cx.repaint_id += 1;
+            for pass_id in cx.passes.id_iter() {
+                cx.passes[pass_id].paint_dirty = false;
+            }
+        }
+        self.handle_platform_ops(cx);
+    }
+
+    fn window_geom(&self) -> WindowGeom {
+        WindowGeom {
+            dpi_factor: self.dpi_factor,
+            inner_size: self.inner_size,
+            outer_size: self.inner_size,
+            ..Default::default()
+        }
+    }
+
+    fn handle_platform_ops(&mut self, cx: &mut Cx) {
+        while let Some(op) = cx.platform_ops.pop() {
+            match op {
+                CxOsOp::CreateWindow(window_id) => {
+                    let window = &mut cx.windows[window_id];
+                    window.window_geom = self.window_geom();
+                    window.is_created = true;
+                },
+                CxOsOp::CloseWindow(window_id) => {
+                    cx.windows[window_id].is_created = false;
+                },
+                CxOsOp::StartTimer {timer_id, interval, repeats} => {
+                    self.timers.retain( | timer | timer.timer_id != timer_id);
+                    self.timers.push(TestTimer {
+                        timer_id,
+                        interval,
+                        next_time: self.time + interval,
+                        repeats
+                    });
+                },
+                CxOsOp::StopTimer(timer_id) => {
+                    self.timers.retain( | timer | timer.timer_id != timer_id);
+                },
+                CxOsOp::ReadClipboard {request_id, ..} => {
+                    cx.call_empty_clipboard_event(request_id);
+                }
+                _ => ()
+            }
+        }
+    }
+}
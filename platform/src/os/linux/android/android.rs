@@ -101,6 +101,7 @@ impl Cx {
                             xr_is_presenting: false,
                             is_fullscreen: true,
                             is_topmost: true,
+                            is_maximized: false,
                             position: dvec2(0.0, 0.0),
                             inner_size: size,
                             outer_size: size,
@@ -651,6 +652,7 @@ impl Cx {
                         xr_is_presenting: false,
                         is_fullscreen: true,
                         is_topmost: true,
+                        is_maximized: false,
                         position: dvec2(0.0, 0.0),
                         inner_size: size,
                         outer_size: size,
@@ -666,7 +668,7 @@ impl Cx {
                 CxOsOp::StopTimer(timer_id) => {
                     self.os.timers.remove(&timer_id);
                 },
-                CxOsOp::ShowTextIME(_area, _pos) => {
+                CxOsOp::ShowTextIME(_area, _pos, _line_height) => {
                     //self.os.keyboard_trigger_position = area.get_clipped_rect(self).pos;
                     unsafe {android_jni::to_java_show_keyboard(true);}
                 },
@@ -704,6 +706,9 @@ impl Cx {
                         android_jni::to_java_cleanup_video_decoding(env, video_id);
                     }
                 }
+                CxOsOp::ReadClipboard {request_id, ..} => {
+                    self.call_empty_clipboard_event(request_id);
+                }
                 _ => ()
             }
         }
@@ -1,15 +1,20 @@
 use {
     std::rc::Rc,
     std::cell::RefCell,
+    std::collections::{HashMap, HashSet},
     self::super::{
         direct_event::*,
         egl_drm::{Egl, Drm},
         raw_input::RawInput,
     },
     self::super::super::{
+        headless::cpu_raster::CxHeadless,
         gl_sys,
         select_timer::SelectTimers,
-        linux_media::CxLinuxMedia
+        linux_media::CxLinuxMedia,
+        linux_decoding::CxLinuxDecoding,
+        linux_http::make_http_request,
+        linux_websocket::LinuxWebSocket,
     },
     crate::{
         cx_api::{CxOsOp, CxOsApi},
@@ -20,6 +25,7 @@ use {
             TimerEvent,
             Event,
             WindowGeom,
+            NetworkResponseChannel,
         },
         window::CxWindowPool,
         pass::CxPassParent,
@@ -72,6 +78,10 @@ impl Cx {
         
         let mut cx = cx.borrow_mut();
         
+        if Cx::headless_requested() {
+            return cx.headless_event_loop();
+        }
+        
         cx.os_type = OsType::LinuxDirect;
         cx.gpu_info.performance = GpuPerformance::Tier1;
         
@@ -180,6 +190,8 @@ impl Cx {
                         self.handle_media_signals();
                         self.call_event_handler(&Event::Signal);
                     }
+                    self.handle_networking_events();
+                    self.handle_video_decoding_events();
                 }
                 else {
                     self.call_event_handler(&Event::Timer(e))
@@ -282,6 +294,7 @@ impl Cx {
                         xr_is_presenting: false,
                         is_fullscreen: true,
                         is_topmost: true,
+                        is_maximized: false,
                         position: dvec2(0.0, 0.0),
                         inner_size: size,
                         outer_size: size
@@ -297,6 +310,39 @@ impl Cx {
                 CxOsOp::StopTimer(timer_id) => {
                     direct_app.timers.stop_timer(timer_id);
                 },
+                CxOsOp::HttpRequest {request_id, request} => {
+                    self.os.pending_http_requests.insert(request_id);
+                    make_http_request(request_id, request, self.os.network_response.sender.clone());
+                },
+                CxOsOp::WebSocketOpen {request_id, request} => {
+                    let web_socket = LinuxWebSocket::open(request_id, request, self.os.network_response.sender.clone());
+                    self.os.web_sockets.insert(request_id, web_socket);
+                }
+                CxOsOp::WebSocketSendBinary {request_id, data} => {
+                    if let Some(web_socket) = self.os.web_sockets.get(&request_id) {
+                        web_socket.send_binary(data);
+                    }
+                }
+                CxOsOp::WebSocketSendString {request_id, data} => {
+                    if let Some(web_socket) = self.os.web_sockets.get(&request_id) {
+                        web_socket.send_string(data);
+                    }
+                }
+                CxOsOp::InitializeVideoDecoding(video_id, video, chunk_size) => {
+                    self.os.decoding.initialize(video_id, video, chunk_size);
+                },
+                CxOsOp::DecodeNextVideoChunk(video_id, max_frames_to_decode) => {
+                    self.os.decoding.decode_next_chunk(video_id, max_frames_to_decode);
+                },
+                CxOsOp::FetchNextVideoFrames(video_id, number_frames) => {
+                    self.os.decoding.fetch_next_frames(video_id, number_frames);
+                },
+                CxOsOp::CleanupVideoDecoding(video_id) => {
+                    self.os.decoding.cleanup(video_id);
+                },
+                CxOsOp::ReadClipboard {request_id, ..} => {
+                    self.call_empty_clipboard_event(request_id);
+                }
                 _ => ()
             }
         }
@@ -319,5 +365,11 @@ impl CxOsApi for Cx {
 #[derive(Default)]
 pub struct CxOs {
     pub (crate) media: CxLinuxMedia,
+    pub (crate) network_response: NetworkResponseChannel,
+    pub (crate) web_sockets: HashMap<LiveId, LinuxWebSocket>,
+    // http requests that haven't had their response or error yet
+    pub (crate) pending_http_requests: HashSet<LiveId>,
+    pub (crate) decoding: CxLinuxDecoding,
+    pub (crate) headless: Option<CxHeadless>,
 }
 
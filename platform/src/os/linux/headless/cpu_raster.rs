@@ -0,0 +1,799 @@
+use {
+    std::collections::HashMap,
+    crate::{
+        makepad_live_id::*,
+        makepad_math::*,
+        makepad_shader_compiler::{
+            shader_ast::*,
+            interpret::*,
+        },
+        cx::Cx,
+        draw_list::DrawListId,
+        draw_shader::DrawShaderInputs,
+        pass::{PassId, PassClearColor, PassClearDepth},
+        texture::TextureId,
+        window::WindowId,
+    }
+};
+
+// The software rasteriser behind the headless backend. It walks the same
+// draw lists the OpenGL backend does and runs the analysed draw shaders
+// through the shader interpreter, one vertex and one pixel at a time. It
+// mirrors the GL state makepad uses everywhere: premultiplied alpha blending
+// and a LEQUAL depth test when the target has a depth buffer.
+
+#[derive(Clone, Default)]
+pub struct HeadlessImage {
+    pub width: usize,
+    pub height: usize,
+    // 0xAARRGGBB, premultiplied, rows top to bottom
+    pub pixels: Vec<u32>,
+}
+
+impl HeadlessImage {
+    pub fn pixel(&self, x: usize, y: usize) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None
+        }
+        self.pixels.get(y * self.width + x).cloned()
+    }
+}
+
+#[derive(Default)]
+pub struct HeadlessSurface {
+    pub image: HeadlessImage,
+    color: Vec<[f32; 4]>,
+    depth: Vec<f32>,
+}
+
+impl HeadlessSurface {
+    // returns true if the surface was (re)allocated
+    fn resize(&mut self, width: usize, height: usize) -> bool {
+        if self.image.width == width && self.image.height == height && self.color.len() == width * height {
+            return false
+        }
+        self.image.width = width;
+        self.image.height = height;
+        self.image.pixels = vec![0; width * height];
+        self.color = vec![[0.0; 4]; width * height];
+        if self.depth.len() != 0 {
+            self.depth = vec![1.0; width * height];
+        }
+        true
+    }
+
+    fn enable_depth(&mut self) -> bool {
+        if self.depth.len() != self.color.len() {
+            self.depth = vec![1.0; self.color.len()];
+            return true
+        }
+        false
+    }
+
+    fn clear_color(&mut self, color: Vec4) {
+        for pixel in &mut self.color {
+            *pixel = [color.x, color.y, color.z, color.w];
+        }
+    }
+
+    fn clear_depth(&mut self, depth: f32) {
+        for value in &mut self.depth {
+            *value = depth;
+        }
+    }
+
+    fn resolve(&mut self) {
+        fn to_u8(v: f32) -> u32 {
+            (v.max(0.0).min(1.0) * 255.0 + 0.5) as u32
+        }
+        for (out, c) in self.image.pixels.iter_mut().zip(self.color.iter()) {
+            *out = (to_u8(c[3]) << 24) | (to_u8(c[0]) << 16) | (to_u8(c[1]) << 8) | to_u8(c[2]);
+        }
+    }
+
+    fn fetch(&self, x: isize, y: isize) -> [f32; 4] {
+        if self.image.width == 0 || self.image.height == 0 {
+            return [0.0; 4]
+        }
+        let x = x.max(0).min(self.image.width as isize - 1) as usize;
+        let y = y.max(0).min(self.image.height as isize - 1) as usize;
+        self.color[y * self.image.width + x]
+    }
+}
+
+#[derive(Default)]
+pub struct CxHeadless {
+    pub(crate) windows: Vec<(WindowId, HeadlessSurface)>,
+    pub(crate) render_targets: HashMap<usize, (TextureId, HeadlessSurface)>,
+}
+
+impl CxHeadless {
+    pub(crate) fn window_surface(&self, window_id: WindowId) -> Option<&HeadlessSurface> {
+        self.windows.iter().find( | (id, _) | *id == window_id).map( | (_, s) | s)
+    }
+
+    fn take_window_surface(&mut self, window_id: WindowId) -> HeadlessSurface {
+        if let Some(index) = self.windows.iter().position( | (id, _) | *id == window_id) {
+            self.windows.remove(index).1
+        }
+        else {
+            HeadlessSurface::default()
+        }
+    }
+
+    pub(crate) fn render_target(&self, texture_id: TextureId) -> Option<&HeadlessSurface> {
+        self.render_targets.get(&texture_id.0).filter( | (id, _) | *id == texture_id).map( | (_, s) | s)
+    }
+
+    fn take_render_target(&mut self, texture_id: TextureId) -> HeadlessSurface {
+        match self.render_targets.remove(&texture_id.0) {
+            Some((id, surface)) if id == texture_id => surface,
+            _ => HeadlessSurface::default()
+        }
+    }
+}
+
+enum SamplerSource<'a> {
+    None,
+    Image {width: usize, height: usize, pixels: &'a [u32]},
+    Surface(&'a HeadlessSurface),
+}
+
+struct HeadlessSampler<'a> {
+    slots: Vec<SamplerSource<'a >>,
+}
+
+impl<'a> SamplerSource<'a> {
+    fn size(&self) -> (usize, usize) {
+        match self {
+            Self::None => (0, 0),
+            Self::Image {width, height, ..} => (*width, *height),
+            Self::Surface(surface) => (surface.image.width, surface.image.height)
+        }
+    }
+
+    fn fetch(&self, x: isize, y: isize) -> [f32; 4] {
+        match self {
+            Self::None => [0.0; 4],
+            Self::Image {width, height, pixels} => {
+                let x = x.max(0).min(*width as isize - 1) as usize;
+                let y = y.max(0).min(*height as isize - 1) as usize;
+                let p = pixels[y * width + x];
+                [
+                    ((p >> 16) & 0xff) as f32 / 255.0,
+                    ((p >> 8) & 0xff) as f32 / 255.0,
+                    (p & 0xff) as f32 / 255.0,
+                    ((p >> 24) & 0xff) as f32 / 255.0,
+                ]
+            }
+            Self::Surface(surface) => surface.fetch(x, y)
+        }
+    }
+
+    fn nearest(&self, pos: [f32; 2]) -> [f32; 4] {
+        let (width, height) = self.size();
+        if width == 0 || height == 0 {
+            return [0.0; 4]
+        }
+        self.fetch(
+            (pos[0] * width as f32).floor() as isize,
+            (pos[1] * height as f32).floor() as isize
+        )
+    }
+
+    fn bilinear(&self, pos: [f32; 2]) -> [f32; 4] {
+        let (width, height) = self.size();
+        if width == 0 || height == 0 {
+            return [0.0; 4]
+        }
+        let x = pos[0] * width as f32 - 0.5;
+        let y = pos[1] * height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        let a = self.fetch(x0, y0);
+        let b = self.fetch(x0 + 1, y0);
+        let c = self.fetch(x0, y0 + 1);
+        let d = self.fetch(x0 + 1, y0 + 1);
+        let mut out = [0.0; 4];
+        for i in 0..4 {
+            let top = a[i] + (b[i] - a[i]) * fx;
+            let bottom = c[i] + (d[i] - c[i]) * fx;
+            out[i] = top + (bottom - top) * fy;
+        }
+        out
+    }
+}
+
+impl<'a> ShaderSampler for HeadlessSampler<'a> {
+    fn sample2d(&self, slot: usize, pos: [f32; 2]) -> [f32; 4] {
+        self.slots.get(slot).map_or([0.0; 4], | s | s.bilinear(pos))
+    }
+
+    fn sample2d_rt(&self, slot: usize, pos: [f32; 2]) -> [f32; 4] {
+        self.slots.get(slot).map_or([0.0; 4], | s | s.nearest(pos))
+    }
+}
+
+struct RasterVertex {
+    // screen space x, y, ndc z and 1/w
+    pos: [f64; 4],
+    fields: Vec<Value>,
+    varyings: Vec<f32>,
+}
+
+struct RasterDrawCall<'a, 'b> {
+    interp: &'b ShaderInterp<'a>,
+    live_uniforms: &'b [f32],
+    sampler: &'b HeadlessSampler<'b>,
+    // field index, type, slot offset into RasterVertex::varyings
+    interpolated: Vec<(usize, Ty, usize)>,
+    uses_derivatives: bool,
+    has_depth: bool,
+}
+
+impl<'a, 'b> RasterDrawCall<'a, 'b> {
+    fn run_vertex(&self, fields: &mut Vec<Value>, width: f64, height: f64) -> RasterVertex {
+        let mut derivatives = ShaderDerivatives::default();
+        let mut env = ShaderInterpEnv {
+            fields: &mut fields[..],
+            live_uniforms: self.live_uniforms,
+            sampler: self.sampler,
+            derivatives: &mut derivatives,
+        };
+        let clip = self.interp.call_vertex(&mut env).to_vec4();
+        let mut varyings = Vec::new();
+        for (field_index, _, _) in &self.interpolated {
+            fields[*field_index].to_slots(&mut varyings);
+        }
+        let w = clip[3] as f64;
+        let inv_w = if w > 0.0 {1.0 / w} else {0.0};
+        RasterVertex {
+            pos: [
+                (clip[0] as f64 * inv_w * 0.5 + 0.5) * width,
+                (0.5 - clip[1] as f64 * inv_w * 0.5) * height,
+                clip[2] as f64 * inv_w,
+                inv_w
+            ],
+            fields: fields.clone(),
+            varyings,
+        }
+    }
+
+    fn pixel_fields(&self, tri: [&RasterVertex; 3], b: [f64; 3], out: &mut Vec<Value>, slots: &mut Vec<f32>) {
+        out.clone_from(&tri[0].fields);
+        let w = [b[0] * tri[0].pos[3], b[1] * tri[1].pos[3], b[2] * tri[2].pos[3]];
+        let sum = w[0] + w[1] + w[2];
+        let w = if sum != 0.0 {[w[0] / sum, w[1] / sum, w[2] / sum]} else {b};
+        slots.clear();
+        for i in 0..tri[0].varyings.len() {
+            slots.push((
+                tri[0].varyings[i] as f64 * w[0] +
+                tri[1].varyings[i] as f64 * w[1] +
+                tri[2].varyings[i] as f64 * w[2]
+            ) as f32);
+        }
+        for (field_index, ty, offset) in &self.interpolated {
+            out[*field_index] = Value::from_slots(ty, &slots[*offset..]);
+        }
+    }
+
+    fn run_pixel(&self, fields: &mut Vec<Value>, derivatives: &mut ShaderDerivatives) -> [f32; 4] {
+        let mut env = ShaderInterpEnv {
+            fields: &mut fields[..],
+            live_uniforms: self.live_uniforms,
+            sampler: self.sampler,
+            derivatives,
+        };
+        self.interp.call_pixel(&mut env).to_vec4()
+    }
+
+    fn rasterize(&self, tri: [&RasterVertex; 3], surface: &mut HeadlessSurface) {
+        if tri.iter().any( | v | v.pos[3] <= 0.0) {
+            return
+        }
+        let [p0, p1, p2] = [tri[0].pos, tri[1].pos, tri[2].pos];
+        let area = (p1[0] - p0[0]) * (p2[1] - p0[1]) - (p1[1] - p0[1]) * (p2[0] - p0[0]);
+        if area == 0.0 || !area.is_finite() {
+            return
+        }
+        let width = surface.image.width;
+        let height = surface.image.height;
+        let min_x = p0[0].min(p1[0]).min(p2[0]).floor().max(0.0) as usize;
+        let min_y = p0[1].min(p1[1]).min(p2[1]).floor().max(0.0) as usize;
+        let max_x = (p0[0].max(p1[0]).max(p2[0]).ceil().max(0.0) as usize).min(width);
+        let max_y = (p0[1].max(p1[1]).max(p2[1]).ceil().max(0.0) as usize).min(height);
+
+        // edge functions, normalised so the inside is positive regardless of winding
+        let sign = if area > 0.0 {1.0} else {-1.0};
+        let edge = | a: [f64; 4], b: [f64; 4], x: f64, y: f64 | {
+            sign * ((b[0] - a[0]) * (y - a[1]) - (b[1] - a[1]) * (x - a[0]))
+        };
+        // top-left fill rule so shared edges are only drawn once
+        let is_top_left = | a: [f64; 4], b: [f64; 4] | {
+            let (dx, dy) = (sign * (b[0] - a[0]), sign * (b[1] - a[1]));
+            (dy == 0.0 && dx < 0.0) || dy > 0.0
+        };
+        let bias = [
+            is_top_left(p1, p2),
+            is_top_left(p2, p0),
+            is_top_left(p0, p1),
+        ];
+        let barycentric = | x: f64, y: f64 | {
+            let e = [edge(p1, p2, x, y), edge(p2, p0, x, y), edge(p0, p1, x, y)];
+            let area = area.abs();
+            ([e[0] / area, e[1] / area, e[2] / area], e)
+        };
+
+        let mut fields = Vec::new();
+        let mut slots = Vec::new();
+        let mut derivatives = ShaderDerivatives::default();
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+                let (b, e) = barycentric(px, py);
+                if (0..3).any( | i | e[i] < 0.0 || e[i] == 0.0 && !bias[i]) {
+                    continue;
+                }
+                let index = y * width + x;
+                let depth = (p0[2] * b[0] + p1[2] * b[1] + p2[2] * b[2]) * 0.5 + 0.5;
+                if self.has_depth {
+                    if depth < 0.0 || depth > 1.0 || depth as f32 > surface.depth[index] {
+                        continue;
+                    }
+                }
+                if self.uses_derivatives {
+                    derivatives.record = true;
+                    for (dx, dy, is_x) in [(1.0, 0.0, true), (0.0, 1.0, false)] {
+                        let (b, _) = barycentric(px + dx, py + dy);
+                        self.pixel_fields(tri, b, &mut fields, &mut slots);
+                        derivatives.recorded.clear();
+                        derivatives.cursor = 0;
+                        self.run_pixel(&mut fields, &mut derivatives);
+                        if is_x {
+                            std::mem::swap(&mut derivatives.dx, &mut derivatives.recorded);
+                        }
+                        else {
+                            std::mem::swap(&mut derivatives.dy, &mut derivatives.recorded);
+                        }
+                    }
+                    derivatives.record = false;
+                    derivatives.cursor = 0;
+                }
+                self.pixel_fields(tri, b, &mut fields, &mut slots);
+                let src = self.run_pixel(&mut fields, &mut derivatives);
+                if src.iter().any( | v | v.is_nan()) {
+                    continue;
+                }
+                let dst = &mut surface.color[index];
+                let inv_a = 1.0 - src[3];
+                for i in 0..4 {
+                    dst[i] = src[i] + dst[i] * inv_a;
+                }
+                if self.has_depth {
+                    surface.depth[index] = depth as f32;
+                }
+            }
+        }
+    }
+}
+
+fn input_offset(inputs: &DrawShaderInputs, id: LiveId) -> Option<usize> {
+    inputs.inputs.iter().find( | input | input.id == id).map( | input | input.offset)
+}
+
+impl Cx {
+    pub(crate) fn headless_draw_pass_to_window(&mut self, pass_id: PassId, window_id: WindowId) {
+        let draw_list_id = self.passes[pass_id].main_draw_list_id.unwrap();
+        self.setup_render_pass(pass_id);
+
+        let window_geom = &self.windows[window_id].window_geom;
+        let width = (window_geom.inner_size.x * window_geom.dpi_factor).floor().max(0.0) as usize;
+        let height = (window_geom.inner_size.y * window_geom.dpi_factor).floor().max(0.0) as usize;
+
+        let headless = self.os.headless.as_mut().unwrap();
+        let mut surface = headless.take_window_surface(window_id);
+        surface.resize(width, height);
+        surface.enable_depth();
+
+        let clear_color = if self.passes[pass_id].color_textures.len() == 0 {
+            self.passes[pass_id].clear_color
+        }
+        else {
+            match self.passes[pass_id].color_textures[0].clear_color {
+                PassClearColor::InitWith(color) => color,
+                PassClearColor::ClearWith(color) => color
+            }
+        };
+        let clear_depth = match self.passes[pass_id].clear_depth {
+            PassClearDepth::InitWith(depth) => depth,
+            PassClearDepth::ClearWith(depth) => depth
+        };
+        if !self.passes[pass_id].dont_clear {
+            surface.clear_color(clear_color);
+            surface.clear_depth(clear_depth as f32);
+        }
+
+        let mut zbias = 0.0;
+        let zbias_step = self.passes[pass_id].zbias_step;
+        self.headless_render_view(pass_id, draw_list_id, &mut zbias, zbias_step, &mut surface);
+
+        surface.resolve();
+        self.os.headless.as_mut().unwrap().windows.push((window_id, surface));
+    }
+
+    pub(crate) fn headless_draw_pass_to_texture(&mut self, pass_id: PassId) {
+        let draw_list_id = self.passes[pass_id].main_draw_list_id.unwrap();
+        let pass_size = if let Some(pass_size) = self.setup_render_pass(pass_id) {
+            pass_size
+        }
+        else {
+            return
+        };
+        let dpi_factor = self.passes[pass_id].dpi_factor.unwrap();
+        let width = (pass_size.x * dpi_factor) as usize;
+        let height = (pass_size.y * dpi_factor) as usize;
+
+        let color_textures = self.passes[pass_id].color_textures.clone();
+        let depth_texture = self.passes[pass_id].depth_texture;
+        let headless = self.os.headless.as_mut().unwrap();
+
+        // only the first color attachment is written to, like gl_FragColor
+        let mut surfaces = Vec::new();
+        for color_texture in &color_textures {
+            let mut surface = headless.take_render_target(color_texture.texture_id);
+            let allocated = surface.resize(width, height);
+            match color_texture.clear_color {
+                PassClearColor::InitWith(color) => if allocated {
+                    surface.clear_color(color)
+                },
+                PassClearColor::ClearWith(color) => surface.clear_color(color)
+            }
+            surfaces.push((color_texture.texture_id, surface));
+        }
+
+        let mut depth_surface = None;
+        if let Some(depth_texture_id) = depth_texture {
+            let mut surface = headless.take_render_target(depth_texture_id);
+            let allocated = surface.resize(width, height) | surface.enable_depth();
+            match self.passes[pass_id].clear_depth {
+                PassClearDepth::InitWith(depth) => if allocated {
+                    surface.clear_depth(depth as f32)
+                },
+                PassClearDepth::ClearWith(depth) => surface.clear_depth(depth as f32)
+            }
+            depth_surface = Some((depth_texture_id, surface));
+        }
+
+        if let Some((_, target)) = surfaces.first_mut() {
+            if let Some((_, depth_surface)) = &mut depth_surface {
+                std::mem::swap(&mut target.depth, &mut depth_surface.depth);
+            }
+            let mut zbias = 0.0;
+            let zbias_step = self.passes[pass_id].zbias_step;
+            self.headless_render_view(pass_id, draw_list_id, &mut zbias, zbias_step, target);
+            if let Some((_, depth_surface)) = &mut depth_surface {
+                std::mem::swap(&mut target.depth, &mut depth_surface.depth);
+            }
+        }
+
+        let headless = self.os.headless.as_mut().unwrap();
+        for (texture_id, mut surface) in surfaces.into_iter().chain(depth_surface.into_iter()) {
+            surface.resolve();
+            headless.render_targets.insert(texture_id.0, (texture_id, surface));
+        }
+    }
+
+    fn headless_render_view(
+        &mut self,
+        pass_id: PassId,
+        draw_list_id: DrawListId,
+        zbias: &mut f32,
+        zbias_step: f32,
+        surface: &mut HeadlessSurface,
+    ) {
+        let draw_items_len = self.draw_lists[draw_list_id].draw_items.len();
+        self.draw_lists[draw_list_id].uniform_view_transform(&Mat4::identity());
+
+        for draw_item_id in 0..draw_items_len {
+            if let Some(sub_list_id) = self.draw_lists[draw_list_id].draw_items[draw_item_id].kind.sub_list() {
+                self.headless_render_view(pass_id, sub_list_id, zbias, zbias_step, surface);
+                continue;
+            }
+            if let Some(draw_call) = self.draw_lists[draw_list_id].draw_items[draw_item_id].kind.draw_call_mut() {
+                draw_call.draw_uniforms.set_zbias(*zbias);
+                draw_call.instance_dirty = false;
+                draw_call.uniforms_dirty = false;
+                *zbias += zbias_step;
+            }
+            else {
+                continue;
+            }
+            self.headless_render_draw_item(pass_id, draw_list_id, draw_item_id, surface);
+        }
+    }
+
+    fn headless_render_draw_item(
+        &self,
+        pass_id: PassId,
+        draw_list_id: DrawListId,
+        draw_item_id: usize,
+        surface: &mut HeadlessSurface,
+    ) {
+        let draw_list = &self.draw_lists[draw_list_id];
+        let draw_item = &draw_list.draw_items[draw_item_id];
+        let draw_call = draw_item.kind.draw_call().unwrap();
+        let sh = &self.draw_shaders.shaders[draw_call.draw_shader.draw_shader_id];
+        let mapping = &sh.mapping;
+        let draw_shader_def = if let Some(def) = self.shader_registry.draw_shader_defs.get(&draw_call.draw_shader.draw_shader_ptr) {
+            def
+        }
+        else {
+            return
+        };
+        let instances = if let Some(instances) = &draw_item.instances {instances} else {return};
+        if mapping.instances.total_slots == 0 || instances.len() < mapping.instances.total_slots {
+            return
+        }
+        let geometry = if let Some(geometry_id) = draw_call.geometry_id {
+            &self.geometries[geometry_id]
+        }
+        else {
+            return
+        };
+
+        let pass_uniforms = self.passes[pass_id].pass_uniforms.as_slice();
+        let draw_list_uniforms = draw_list.draw_list_uniforms.as_slice();
+        let draw_list_uniforms = &draw_list_uniforms[0..draw_list_uniforms.len() >> 2];
+        let draw_uniforms = draw_call.draw_uniforms.as_slice();
+        let draw_uniforms = &draw_uniforms[0..draw_uniforms.len() >> 2];
+
+        let interp = ShaderInterp::new(&self.shader_registry, draw_shader_def, &mapping.const_table);
+
+        // the sampler slots follow the texture fields in declaration order
+        let headless = self.os.headless.as_ref().unwrap();
+        let mut sampler = HeadlessSampler {slots: Vec::new()};
+        for i in 0..mapping.textures.len() {
+            let source = if let Some(texture_id) = draw_call.texture_slots[i] {
+                if let Some(surface) = headless.render_target(texture_id) {
+                    SamplerSource::Surface(surface)
+                }
+                else {
+                    let cxtexture = &self.textures[texture_id];
+                    let width = cxtexture.desc.width.unwrap_or(0);
+                    let height = cxtexture.desc.height.unwrap_or(0);
+                    if width * height != 0 && cxtexture.image_u32.len() >= width * height {
+                        SamplerSource::Image {width, height, pixels: &cxtexture.image_u32}
+                    }
+                    else {
+                        SamplerSource::None
+                    }
+                }
+            }
+            else {
+                SamplerSource::None
+            };
+            sampler.slots.push(source);
+        }
+
+        // build the per drawcall field values and remember where per instance
+        // and per vertex inputs come from
+        let mut fields = Vec::new();
+        let mut instance_inputs = Vec::new();
+        let mut geometry_inputs = Vec::new();
+        let mut interpolated = Vec::new();
+        let mut varying_slots = 0;
+        let mut texture_slot = 0;
+        for (field_index, field) in draw_shader_def.fields.iter().enumerate() {
+            let ty = field.ty_expr.ty.borrow().as_ref().unwrap().clone();
+            let value = match &field.kind {
+                DrawShaderFieldKind::Uniform {block_ident, ..} => {
+                    let (inputs, slice): (&DrawShaderInputs, &[f32]) = match block_ident.0 {
+                        live_id!(draw) => (&mapping.draw_uniforms, draw_uniforms),
+                        live_id!(view) => (&mapping.view_uniforms, draw_list_uniforms),
+                        live_id!(pass) => (&mapping.pass_uniforms, pass_uniforms),
+                        live_id!(user) => (&mapping.user_uniforms, &draw_call.user_uniforms),
+                        _ => (&mapping.user_uniforms, &[])
+                    };
+                    if let Some(offset) = input_offset(inputs, field.ident.0) {
+                        Value::from_slots(&ty, slice.get(offset..).unwrap_or(&[]))
+                    }
+                    else {
+                        Value::zero(&ty, &self.shader_registry)
+                    }
+                }
+                DrawShaderFieldKind::Texture {..} => {
+                    texture_slot += 1;
+                    Value::Texture(texture_slot - 1)
+                }
+                DrawShaderFieldKind::Instance {..} => {
+                    if let Some(offset) = input_offset(&mapping.instances, field.ident.0) {
+                        instance_inputs.push((field_index, ty.clone(), offset));
+                    }
+                    Value::zero(&ty, &self.shader_registry)
+                }
+                DrawShaderFieldKind::Geometry {..} => {
+                    if let Some(offset) = input_offset(&mapping.geometries, field.ident.0) {
+                        geometry_inputs.push((field_index, ty.clone(), offset));
+                    }
+                    interpolated.push((field_index, ty.clone(), varying_slots));
+                    varying_slots += ty.slots();
+                    Value::zero(&ty, &self.shader_registry)
+                }
+                DrawShaderFieldKind::Varying {..} => {
+                    interpolated.push((field_index, ty.clone(), varying_slots));
+                    varying_slots += ty.slots();
+                    Value::zero(&ty, &self.shader_registry)
+                }
+            };
+            fields.push(value);
+        }
+
+        let raster = RasterDrawCall {
+            uses_derivatives: interp.uses_derivatives(),
+            interp: &interp,
+            live_uniforms: &mapping.live_uniforms_buf,
+            sampler: &sampler,
+            interpolated,
+            has_depth: surface.depth.len() != 0,
+        };
+
+        let width = surface.image.width as f64;
+        let height = surface.image.height as f64;
+        let instance_slots = mapping.instances.total_slots;
+        let geometry_slots = mapping.geometries.total_slots.max(1);
+        let vertex_count = geometry.vertices.len() / geometry_slots;
+        let mut vertices: Vec<Option<RasterVertex >> = Vec::new();
+
+        for instance in instances.chunks_exact(instance_slots) {
+            for (field_index, ty, offset) in &instance_inputs {
+                fields[*field_index] = Value::from_slots(ty, &instance[*offset..]);
+            }
+            vertices.clear();
+            vertices.resize_with(vertex_count, || None);
+            for tri in geometry.indices.chunks_exact(3) {
+                for index in tri {
+                    let index = *index as usize;
+                    if index < vertex_count && vertices[index].is_none() {
+                        let vertex = &geometry.vertices[index * geometry_slots..];
+                        let mut vertex_fields = fields.clone();
+                        for (field_index, ty, offset) in &geometry_inputs {
+                            vertex_fields[*field_index] = Value::from_slots(ty, &vertex[*offset..]);
+                        }
+                        vertices[index] = Some(raster.run_vertex(&mut vertex_fields, width, height));
+                    }
+                }
+                let tri = [tri[0] as usize, tri[1] as usize, tri[2] as usize];
+                if let [Some(a), Some(b), Some(c)] = tri.map( | i | vertices.get(i).and_then( | v | v.as_ref())) {
+                    raster.rasterize([a, b, c], surface);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            makepad_live_compiler::*,
+            makepad_shader_compiler::{ShaderRegistry, DrawShaderPtr},
+        },
+    };
+
+    struct DrawTest;
+
+    // a draw shader whose pixel() returns the given color, run over vertices placed in screen space
+    fn fill(pixel: &str, tris: &[[[f64; 2]; 3]], surface: &mut HeadlessSurface) {
+        let module_id = LiveModuleId::from_str("test::cpu_raster").unwrap();
+        let live_type_info = LiveTypeInfo {
+            live_type: std::any::TypeId::of::<DrawTest>(),
+            type_name: live_id!(DrawTest),
+            module_id,
+            live_ignore: true,
+            fields: Vec::new(),
+        };
+        let source = format!("DrawTest = {{{{DrawTest}}}} {{
+            fn vertex(self) -> vec4 {{return vec4(0.0);}}
+            fn pixel(self) -> vec4 {{return {};}}
+        }}", pixel);
+        let mut live_registry = LiveRegistry::default();
+        if let Err(err) = live_registry.register_live_file("cpu_raster.rs", "", module_id, source, vec![live_type_info], TextPos::default()) {
+            panic!("{}", err);
+        }
+        let mut errors = Vec::new();
+        live_registry.expand_all_documents(&mut errors);
+        let draw_shader_ptr = DrawShaderPtr(live_registry.module_id_and_name_to_ptr(module_id, live_id!(DrawTest)).unwrap());
+        let mut shader_registry = ShaderRegistry::new();
+        shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | _, _, _, _, _, _ | {}).unwrap();
+        let const_table = shader_registry.compute_const_table(draw_shader_ptr);
+        let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+
+        let interp = ShaderInterp::new(&shader_registry, draw_shader_def, &const_table);
+        let sampler = HeadlessSampler {slots: Vec::new()};
+        let raster = RasterDrawCall {
+            uses_derivatives: interp.uses_derivatives(),
+            interp: &interp,
+            live_uniforms: &[],
+            sampler: &sampler,
+            interpolated: Vec::new(),
+            has_depth: surface.depth.len() != 0,
+        };
+        let fields: Vec<Value> = draw_shader_def.fields.iter().map( | field | {
+            Value::zero(field.ty_expr.ty.borrow().as_ref().unwrap(), &shader_registry)
+        }).collect();
+        for tri in tris {
+            let vertices = tri.map( | p | RasterVertex {
+                pos: [p[0], p[1], 0.0, 1.0],
+                fields: fields.clone(),
+                varyings: Vec::new(),
+            });
+            raster.rasterize([&vertices[0], &vertices[1], &vertices[2]], surface);
+        }
+        surface.resolve();
+    }
+
+    fn surface(width: usize, height: usize) -> HeadlessSurface {
+        let mut surface = HeadlessSurface::default();
+        surface.resize(width, height);
+        surface
+    }
+
+    #[test]
+    fn test_fill_quad() {
+        let mut surface = surface(8, 8);
+        surface.clear_color(vec4(0.0, 0.0, 1.0, 1.0));
+        let quad = [
+            [[2.0, 2.0], [6.0, 2.0], [6.0, 6.0]],
+            [[2.0, 2.0], [6.0, 6.0], [2.0, 6.0]],
+        ];
+        fill("vec4(1.0, 0.0, 0.0, 1.0)", &quad, &mut surface);
+        for y in 0..8 {
+            for x in 0..8 {
+                let inside = (2..6).contains(&x) && (2..6).contains(&y);
+                let expected = if inside {0xffff0000} else {0xff0000ff};
+                assert_eq!(surface.image.pixel(x, y), Some(expected), "pixel {} {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_edge_drawn_once() {
+        // with half transparent white, a pixel drawn twice would come out brighter
+        let mut surface = surface(4, 4);
+        let quad = [
+            [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0]],
+            [[4.0, 4.0], [0.0, 4.0], [0.0, 0.0]],
+        ];
+        fill("vec4(0.5, 0.5, 0.5, 0.5)", &quad, &mut surface);
+        assert!(surface.image.pixels.iter().all( | pixel | *pixel == 0x80808080));
+    }
+
+    #[test]
+    fn test_blend_and_depth() {
+        let mut surface = surface(2, 2);
+        surface.enable_depth();
+        surface.clear_depth(0.25);
+        let tri = [[[0.0, 0.0], [2.0, 0.0], [0.0, 2.0]]];
+        // z = 0 maps to depth 0.5, behind what is already there
+        fill("vec4(1.0)", &tri, &mut surface);
+        assert_eq!(surface.image.pixel(0, 0), Some(0));
+        surface.clear_depth(1.0);
+        fill("vec4(0.0, 0.5, 0.0, 0.5)", &tri, &mut surface);
+        assert_eq!(surface.image.pixel(0, 0), Some(0x80008000));
+        assert_eq!(surface.image.pixel(1, 1), Some(0));
+    }
+
+    #[test]
+    fn test_sampler_filtering() {
+        let pixels = [0xff000000, 0xffffffff];
+        let source = SamplerSource::Image {width: 2, height: 1, pixels: &pixels};
+        assert_eq!(source.nearest([0.25, 0.5]), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(source.nearest([0.75, 0.5]), [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(source.bilinear([0.5, 0.5]), [0.5, 0.5, 0.5, 1.0]);
+        // clamps to the edge
+        assert_eq!(source.bilinear([0.0, 0.5]), [0.0, 0.0, 0.0, 1.0]);
+    }
+}
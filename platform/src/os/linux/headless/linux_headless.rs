@@ -0,0 +1,276 @@
+use {
+    self::super::{
+        cpu_raster::{CxHeadless, HeadlessImage},
+    },
+    self::super::super::{
+        select_timer::SelectTimers,
+        linux_http::make_http_request,
+        linux_websocket::LinuxWebSocket,
+    },
+    crate::{
+        makepad_error_log::*,
+        cx_api::CxOsOp,
+        makepad_math::*,
+        thread::Signal,
+        event::{
+            TimerEvent,
+            Event,
+            WindowGeom,
+        },
+        window::WindowId,
+        pass::CxPassParent,
+        cx::{Cx, OsType,},
+        gpu_info::GpuPerformance,
+        os::cx_native::EventFlow,
+    }
+};
+
+// The headless backend runs the app without a display server or a GPU.
+// Select it with --headless, or just run without a DISPLAY; draw shaders are interpreted on the CPU and every
+// window pass ends up in an in-memory framebuffer (see Cx::headless_framebuffer).
+// The loop exits once the UI has settled and no http request or websocket is
+// still open, when the app quits, or after --headless-frames=N repaints.
+//   --headless-size=WxH    window size in logical pixels (default: what the app asks for)
+//   --headless-scale=F     dpi factor (default 1.0)
+//   --headless-frames=N    stop after N painted frames
+//   --headless-idle=S      seconds of idle time before exiting (default 0.1)
+
+pub struct HeadlessApp {
+    timers: SelectTimers,
+    size: Option<DVec2>,
+    dpi_factor: f64,
+    max_frames: Option<usize>,
+    idle_timeout: f64,
+    open_windows: usize,
+}
+
+impl HeadlessApp {
+    fn new() -> Self {
+        let mut size = None;
+        let mut dpi_factor = 1.0;
+        let mut max_frames = None;
+        let mut idle_timeout = 0.1;
+        for arg in std::env::args() {
+            if let Some(value) = arg.strip_prefix("--headless-size=") {
+                let parsed = value.split_once('x').and_then( | (w, h) | Some(dvec2(w.parse().ok() ?, h.parse().ok() ?)));
+                match parsed {
+                    Some(parsed) => size = Some(parsed),
+                    None => error!("Invalid value {:?} for --headless-size, using the default", value)
+                }
+            }
+            if arg.starts_with("--headless-scale=") {
+                dpi_factor = parse_arg(&arg).filter( | v: &f64 | *v > 0.0).unwrap_or(dpi_factor);
+            }
+            if arg.starts_with("--headless-frames=") {
+                max_frames = parse_arg(&arg).or(max_frames);
+            }
+            if arg.starts_with("--headless-idle=") {
+                idle_timeout = parse_arg(&arg).unwrap_or(idle_timeout);
+            }
+        }
+        Self {
+            timers: SelectTimers::new(),
+            size,
+            dpi_factor,
+            max_frames,
+            idle_timeout,
+            open_windows: 0,
+        }
+    }
+}
+
+// a bad value is reported and the default kept, rather than taking the run down
+fn parse_arg<T: std::str::FromStr>(arg: &str) -> Option<T> {
+    let (name, value) = arg.split_once('=') ?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            error!("Invalid value {:?} for {}, using the default", value, name);
+            None
+        }
+    }
+}
+
+impl Cx {
+    // --headless picks this backend on any linux build, the X11 backend also
+    // falls back to it when there is no display to connect to
+    pub(crate) fn headless_requested() -> bool {
+        std::env::args().any( | v | v == "--headless")
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.os.headless.is_some()
+    }
+
+    // the last frame painted into a window, only available on the headless backend
+    pub fn headless_framebuffer(&self, window_id: WindowId) -> Option<&HeadlessImage> {
+        self.os.headless.as_ref()?.window_surface(window_id).map( | s | &s.image)
+    }
+
+    pub(crate) fn headless_event_loop(&mut self) {
+        self.os_type = OsType::LinuxHeadless;
+        self.gpu_info.performance = GpuPerformance::Tier1;
+        self.os.headless = Some(CxHeadless::default());
+
+        let mut headless_app = HeadlessApp::new();
+
+        self.call_event_handler(&Event::Construct);
+        self.redraw_all();
+        headless_app.timers.start_timer(0, 0.008, true);
+
+        let mut timer_ids = Vec::new();
+        let mut frames = 0;
+        let mut idle_since = None;
+        loop {
+            headless_app.timers.update_timers(&mut timer_ids);
+            let time = headless_app.timers.time_now();
+            for timer_id in &timer_ids {
+                if *timer_id == 0 {
+                    if Signal::check_and_clear_ui_signal() {
+                        self.handle_media_signals();
+                        self.call_event_handler(&Event::Signal);
+                        idle_since = None;
+                    }
+                    self.handle_networking_events();
+                    self.handle_video_decoding_events();
+                }
+                else {
+                    self.call_event_handler(&Event::Timer(TimerEvent {
+                        timer_id: *timer_id,
+                        time: Some(time)
+                    }))
+                }
+            }
+            if let EventFlow::Exit = self.headless_handle_platform_ops(&mut headless_app) {
+                break;
+            }
+            if !self.new_next_frames.is_empty() {
+                self.call_next_frame_event(time);
+            }
+            if self.need_redrawing() {
+                self.call_draw_event();
+                // there is nothing to compile, the interpreter runs the shader AST directly
+                self.draw_shaders.compile_set.clear();
+            }
+            if self.any_passes_dirty() {
+                self.headless_handle_repaint(time);
+                frames += 1;
+            }
+            if let EventFlow::Exit = self.headless_handle_platform_ops(&mut headless_app) {
+                break;
+            }
+            if headless_app.max_frames.is_some_and( | max | frames >= max) {
+                break;
+            }
+            // waiting on the network isn't idle, the replies may still change the UI
+            if self.any_passes_dirty() || self.need_redrawing() || !self.new_next_frames.is_empty()
+                || !self.os.pending_http_requests.is_empty() || !self.os.web_sockets.is_empty() {
+                idle_since = None;
+            }
+            else {
+                let since = *idle_since.get_or_insert(time);
+                if time - since >= headless_app.idle_timeout {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+        }
+    }
+
+    fn headless_handle_repaint(&mut self, time: f64) {
+        let mut passes_todo = Vec::new();
+        self.compute_pass_repaint_order(&mut passes_todo);
+        self.repaint_id += 1;
+        for pass_id in &passes_todo {
+            self.passes[*pass_id].set_time(time as f32);
+            match self.passes[*pass_id].parent.clone() {
+                CxPassParent::Window(window_id) => {
+                    if self.windows[window_id].is_created {
+                        self.headless_draw_pass_to_window(*pass_id, window_id);
+                    }
+                }
+                CxPassParent::Pass(_) | CxPassParent::None => {
+                    self.headless_draw_pass_to_texture(*pass_id);
+                }
+            }
+        }
+    }
+
+    fn headless_handle_platform_ops(&mut self, headless_app: &mut HeadlessApp) -> EventFlow {
+        let mut ret = EventFlow::Poll;
+        while let Some(op) = self.platform_ops.pop() {
+            match op {
+                CxOsOp::CreateWindow(window_id) => {
+                    let window = &mut self.windows[window_id];
+                    let size = headless_app.size.or(window.create_inner_size).unwrap_or(dvec2(800., 600.));
+                    window.window_geom = WindowGeom {
+                        dpi_factor: headless_app.dpi_factor,
+                        can_fullscreen: false,
+                        xr_is_presenting: false,
+                        is_fullscreen: false,
+                        is_topmost: false,
+                        is_maximized: false,
+                        position: window.create_position.unwrap_or(dvec2(0.0, 0.0)),
+                        inner_size: size,
+                        outer_size: size
+                    };
+                    window.is_created = true;
+                    headless_app.open_windows += 1;
+                },
+                CxOsOp::CloseWindow(window_id) => {
+                    if self.windows[window_id].is_created {
+                        self.windows[window_id].is_created = false;
+                        headless_app.open_windows -= 1;
+                        if headless_app.open_windows == 0 {
+                            ret = EventFlow::Exit
+                        }
+                    }
+                },
+                CxOsOp::Quit => {
+                    ret = EventFlow::Exit
+                }
+                CxOsOp::StartTimer {timer_id, interval, repeats} => {
+                    headless_app.timers.start_timer(timer_id, interval, repeats);
+                },
+                CxOsOp::StopTimer(timer_id) => {
+                    headless_app.timers.stop_timer(timer_id);
+                },
+                CxOsOp::HttpRequest {request_id, request} => {
+                    self.os.pending_http_requests.insert(request_id);
+                    make_http_request(request_id, request, self.os.network_response.sender.clone());
+                },
+                CxOsOp::WebSocketOpen {request_id, request} => {
+                    let web_socket = LinuxWebSocket::open(request_id, request, self.os.network_response.sender.clone());
+                    self.os.web_sockets.insert(request_id, web_socket);
+                }
+                CxOsOp::WebSocketSendBinary {request_id, data} => {
+                    if let Some(web_socket) = self.os.web_sockets.get(&request_id) {
+                        web_socket.send_binary(data);
+                    }
+                }
+                CxOsOp::WebSocketSendString {request_id, data} => {
+                    if let Some(web_socket) = self.os.web_sockets.get(&request_id) {
+                        web_socket.send_string(data);
+                    }
+                }
+                CxOsOp::InitializeVideoDecoding(video_id, video, chunk_size) => {
+                    self.os.decoding.initialize(video_id, video, chunk_size);
+                },
+                CxOsOp::DecodeNextVideoChunk(video_id, max_frames_to_decode) => {
+                    self.os.decoding.decode_next_chunk(video_id, max_frames_to_decode);
+                },
+                CxOsOp::FetchNextVideoFrames(video_id, number_frames) => {
+                    self.os.decoding.fetch_next_frames(video_id, number_frames);
+                },
+                CxOsOp::CleanupVideoDecoding(video_id) => {
+                    self.os.decoding.cleanup(video_id);
+                },
+                CxOsOp::ReadClipboard {request_id, ..} => {
+                    self.call_empty_clipboard_event(request_id);
+                }
+                _ => ()
+            }
+        }
+        ret
+    }
+}
@@ -30,6 +30,7 @@ type size_t = usize;
 pub const FD_SETSIZE: usize = 1024;
 pub const EPIPE: c_int = 32;
 pub const O_RDWR: c_int = 2;
+pub const LC_CTYPE: c_int = 0;
 
 #[repr(C)]
 pub struct fd_set {
@@ -55,6 +56,7 @@ extern "C"{
         timeout: *mut timeval,
     ) -> c_int;
     pub fn read(fd: c_int, buf: *mut c_void, count: size_t) -> c_int;
+    pub fn setlocale(category: c_int, locale: *const c_char) -> *mut c_char;
 }
 
 pub unsafe fn FD_SET(fd: c_int, set: *mut fd_set) -> () {
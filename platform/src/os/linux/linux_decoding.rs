@@ -2,23 +2,463 @@ use {
     crate::{
         cx::Cx,
         decoding_api::{CxDecodingApi, VideoDecodingInputFn},
+        event::{
+            Event,
+            VideoDecodingInitializedEvent,
+            VideoDecodingErrorEvent,
+            VideoColorFormat,
+        },
         makepad_live_id::LiveId,
     },
+    makepad_zune_jpeg::{
+        JpegDecoder,
+        makepad_zune_core::{colorspace::ColorSpace, options::DecoderOptions},
+    },
     std::{
-        collections::HashMap,
-        sync::{Arc, Mutex},
+        collections::{HashMap, VecDeque},
+        ops::Range,
+        rc::Rc,
+        sync::{Arc, Mutex, mpsc::{channel, Sender, Receiver}},
     },
 };
 
-#[derive(Default)]
+// Software video decoding for Linux. There is no system codec we can rely on,
+// so we support Motion JPEG, either inside an AVI container or as a bare stream
+// of concatenated JPEG images (.mjpeg). Every video gets a decoder thread which
+// turns the JPEG frames into YUV420 planar frame groups in the same layout the
+// Android decoder produces:
+// | Timestamp (8B) | Y Stride (4B) | U Stride (4B) | V Stride (4B) | isEoS (1B) | Frame data length (4B) | Pixel Data |
+
+// bare MJPEG streams carry no timing information
+const DEFAULT_FRAME_RATE: f64 = 30.0;
+
+type VideoDecodingInputCb = Arc<Mutex<Option<VideoDecodingInputFn>>>;
+
+enum DecoderCommand {
+    DecodeChunk(usize),
+    Fetch(usize, VideoDecodingInputCb),
+}
+
+pub(crate) enum DecoderMessage {
+    Initialized(VideoDecodingInitializedEvent),
+    ChunkDecoded(LiveId),
+    Error(VideoDecodingErrorEvent),
+}
+
 pub struct CxLinuxDecoding {
-    pub video_decoding_input_cb: HashMap<LiveId, Arc<Mutex<Option<VideoDecodingInputFn>>>>,
+    pub video_decoding_input_cb: HashMap<LiveId, VideoDecodingInputCb>,
+    decoders: HashMap<LiveId, Sender<DecoderCommand>>,
+    message_sender: Sender<DecoderMessage>,
+    message_receiver: Receiver<DecoderMessage>,
 }
 
-impl Cx {}
+impl Default for CxLinuxDecoding {
+    fn default() -> Self {
+        let (message_sender, message_receiver) = channel();
+        Self {
+            video_decoding_input_cb: HashMap::new(),
+            decoders: HashMap::new(),
+            message_sender,
+            message_receiver,
+        }
+    }
+}
+
+impl CxLinuxDecoding {
+    pub(crate) fn initialize(&mut self, video_id: LiveId, video: Rc<Vec<u8>>, _chunk_size: usize) {
+        // frames are decoded on demand, so the chunk size has no meaning here
+        let data = video.as_ref().clone();
+        let (sender, receiver) = channel();
+        let message_sender = self.message_sender.clone();
+        std::thread::spawn(move || {
+            decoder_thread(video_id, data, receiver, message_sender)
+        });
+        self.decoders.insert(video_id, sender);
+    }
+
+    pub(crate) fn decode_next_chunk(&mut self, video_id: LiveId, max_frames: usize) {
+        if let Some(decoder) = self.decoders.get(&video_id) {
+            let _ = decoder.send(DecoderCommand::DecodeChunk(max_frames));
+        }
+    }
+
+    pub(crate) fn fetch_next_frames(&mut self, video_id: LiveId, number_frames: usize) {
+        if let (Some(decoder), Some(callback)) = (self.decoders.get(&video_id), self.video_decoding_input_cb.get(&video_id)) {
+            let _ = decoder.send(DecoderCommand::Fetch(number_frames, callback.clone()));
+        }
+    }
+
+    pub(crate) fn cleanup(&mut self, video_id: LiveId) {
+        // dropping the sender ends the decoder thread
+        self.decoders.remove(&video_id);
+        self.video_decoding_input_cb.remove(&video_id);
+    }
+}
+
+struct MjpegVideo {
+    data: Vec<u8>,
+    frames: Vec<Range<usize>>,
+    frame_rate: f64,
+    width: usize,
+    height: usize,
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map( | b | u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+// walks the RIFF tree, collecting the compressed video chunks of the movi list
+fn parse_avi_chunks(data: &[u8], range: Range<usize>, frames: &mut Vec<Range<usize>>, frame_rate: &mut Option<f64>) {
+    let mut offset = range.start;
+    while offset + 8 <= range.end {
+        let fourcc = &data[offset..offset + 4];
+        let size = read_u32_le(data, offset + 4).unwrap() as usize;
+        let body = offset + 8..(offset + 8 + size).min(range.end);
+        match fourcc {
+            b"LIST" | b"RIFF" if body.len() >= 4 => {
+                parse_avi_chunks(data, body.start + 4..body.end, frames, frame_rate);
+            }
+            b"avih" => if frame_rate.is_none() {
+                if let Some(us_per_frame) = read_u32_le(data, body.start).filter( | v | *v != 0) {
+                    *frame_rate = Some(1_000_000.0 / us_per_frame as f64);
+                }
+            }
+            b"strh" if data.get(body.start..body.start + 4) == Some(b"vids") => {
+                // the stream header rate is more precise than the main header
+                if let (Some(scale), Some(rate)) = (read_u32_le(data, body.start + 20), read_u32_le(data, body.start + 24)) {
+                    if scale != 0 && rate != 0 {
+                        *frame_rate = Some(rate as f64 / scale as f64);
+                    }
+                }
+            }
+            _ => if fourcc[2..4] == *b"dc" || fourcc[2..4] == *b"db" {
+                if body.len() > 0 {
+                    frames.push(body.clone());
+                }
+            }
+        }
+        // chunks are padded to an even size
+        offset = body.start + size + (size & 1);
+    }
+}
+
+// Finds the end of the JPEG image starting at `start` by walking its marker
+// segments, so an FFD9 inside a segment (like an EXIF thumbnail) doesn't end it.
+// Returns None if the image is cut off or malformed.
+fn jpeg_end(data: &[u8], start: usize) -> Option<usize> {
+    let mut i = start + 2;
+    loop {
+        if *data.get(i) ? != 0xFF {
+            return None
+        }
+        // markers may be padded with any number of fill bytes
+        while *data.get(i + 1) ? == 0xFF {
+            i += 1;
+        }
+        let marker = data[i + 1];
+        i += 2;
+        match marker {
+            0xD9 => return Some(i),
+            // markers without a segment
+            0x01 | 0xD0..=0xD7 => (),
+            _ => {
+                let len = u16::from_be_bytes([*data.get(i) ?, *data.get(i + 1) ?]) as usize;
+                if len < 2 {
+                    return None
+                }
+                i += len;
+                if marker == 0xDA {
+                    // the entropy coded data runs up to the next marker, FF00 is an
+                    // escaped FF and restart markers belong to the scan
+                    loop {
+                        if *data.get(i) ? != 0xFF {
+                            i += 1;
+                            continue;
+                        }
+                        match *data.get(i + 1) ? {
+                            0x00 | 0xD0..=0xD7 => i += 2,
+                            0xFF => i += 1,
+                            _ => break
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// a bare MJPEG stream is just JPEG images back to back
+fn parse_jpeg_stream(data: &[u8]) -> Vec<Range<usize>> {
+    let mut frames = Vec::new();
+    let mut i = 0;
+    while i + 1 < data.len() {
+        if data[i] == 0xFF && data[i + 1] == 0xD8 {
+            match jpeg_end(data, i) {
+                Some(end) => {
+                    frames.push(i..end);
+                    i = end;
+                    continue;
+                }
+                // a cut off last frame
+                None => break
+            }
+        }
+        i += 1;
+    }
+    frames
+}
+
+impl MjpegVideo {
+    fn parse(data: Vec<u8>) -> Result<MjpegVideo, String> {
+        let (frames, frame_rate) = if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"AVI " {
+            let mut frames = Vec::new();
+            let mut frame_rate = None;
+            parse_avi_chunks(&data, 12..data.len(), &mut frames, &mut frame_rate);
+            (frames, frame_rate.unwrap_or(DEFAULT_FRAME_RATE))
+        }
+        else if data.starts_with(&[0xFF, 0xD8]) {
+            (parse_jpeg_stream(&data), DEFAULT_FRAME_RATE)
+        }
+        else {
+            return Err("Unsupported video format, only Motion JPEG (AVI or raw) is supported on Linux".to_string())
+        };
+        if frames.len() == 0 {
+            return Err("Video contains no frames".to_string())
+        }
+        let mut decoder = JpegDecoder::new(&data[frames[0].clone()]);
+        decoder.decode_headers().map_err( | e | format!("Cannot decode first video frame: {:?}", e)) ?;
+        let (width, height) = decoder.dimensions().ok_or("Cannot read video dimensions") ?;
+        Ok(MjpegVideo {
+            data,
+            frames,
+            frame_rate,
+            width: width as usize,
+            height: height as usize
+        })
+    }
+
+    fn duration_us(&self) -> u128 {
+        (self.frames.len() as f64 * 1_000_000.0 / self.frame_rate) as u128
+    }
+
+    // decodes a frame and serializes it into the frame group layout
+    fn decode_frame(&self, index: usize, out: &mut Vec<u8>) -> Result<(), String> {
+        let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::RGB);
+        let mut decoder = JpegDecoder::new_with_options(&self.data[self.frames[index].clone()], options);
+        let pixels = decoder.decode().map_err( | e | format!("Cannot decode video frame {}: {:?}", index, e)) ?;
+        let channels = decoder.get_output_colorspace().map_or(3, | cs | cs.num_components());
+        if decoder.dimensions() != Some((self.width as u16, self.height as u16)) || pixels.len() < self.width * self.height * channels {
+            return Err(format!("Video frame {} does not match the video dimensions", index))
+        }
+
+        let (width, height) = (self.width, self.height);
+        // odd sizes round up, the last chroma sample covers a single row or column
+        let chroma_width = (width + 1) / 2;
+        let chroma_height = (height + 1) / 2;
+        let timestamp = (index as f64 * 1_000_000.0 / self.frame_rate) as u64;
+        let is_eos = index + 1 == self.frames.len();
+        let frame_len = width * height + 2 * chroma_width * chroma_height;
+
+        out.extend_from_slice(&timestamp.to_be_bytes());
+        out.extend_from_slice(&(width as u32).to_be_bytes());
+        out.extend_from_slice(&(chroma_width as u32).to_be_bytes());
+        out.extend_from_slice(&(chroma_width as u32).to_be_bytes());
+        out.push(is_eos as u8);
+        out.extend_from_slice(&(frame_len as u32).to_be_bytes());
+
+        // BT.601 studio swing, which is what the video shader converts back from
+        let rgb = | x: usize, y: usize | {
+            let p = &pixels[(y * width + x) * channels..];
+            if channels >= 3 {(p[0] as i32, p[1] as i32, p[2] as i32)} else {(p[0] as i32, p[0] as i32, p[0] as i32)}
+        };
+        for y in 0..height {
+            for x in 0..width {
+                let (r, g, b) = rgb(x, y);
+                out.push((((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8);
+            }
+        }
+        let mut v_plane = Vec::with_capacity(chroma_width * chroma_height);
+        for cy in 0..chroma_height {
+            for cx in 0..chroma_width {
+                let (mut r, mut g, mut b, mut n) = (0, 0, 0, 0);
+                for y in cy * 2..(cy * 2 + 2).min(height) {
+                    for x in cx * 2..(cx * 2 + 2).min(width) {
+                        let p = rgb(x, y);
+                        r += p.0;
+                        g += p.1;
+                        b += p.2;
+                        n += 1;
+                    }
+                }
+                let (r, g, b) = (r / n, g / n, b / n);
+                out.push((((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8);
+                v_plane.push((((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8);
+            }
+        }
+        out.extend_from_slice(&v_plane);
+        Ok(())
+    }
+}
+
+fn decoder_thread(video_id: LiveId, data: Vec<u8>, receiver: Receiver<DecoderCommand>, message_sender: Sender<DecoderMessage>) {
+    let send_error = | error: String | {
+        let _ = message_sender.send(DecoderMessage::Error(VideoDecodingErrorEvent {video_id, error}));
+    };
+    let video = match MjpegVideo::parse(data) {
+        Ok(video) => video,
+        Err(err) => return send_error(err)
+    };
+    let _ = message_sender.send(DecoderMessage::Initialized(VideoDecodingInitializedEvent {
+        video_id,
+        frame_rate: video.frame_rate.round() as usize,
+        video_width: video.width as u32,
+        video_height: video.height as u32,
+        color_format: VideoColorFormat::YUV420Planar,
+        duration: video.duration_us(),
+    }));
+
+    let mut decoded: VecDeque<Vec<u8>> = VecDeque::new();
+    let mut next_frame = 0;
+    while let Ok(command) = receiver.recv() {
+        match command {
+            DecoderCommand::DecodeChunk(max_frames) => {
+                for _ in 0..max_frames {
+                    let mut frame = Vec::new();
+                    if let Err(err) = video.decode_frame(next_frame, &mut frame) {
+                        return send_error(err)
+                    }
+                    decoded.push_back(frame);
+                    // after the last frame we start over, the player decides whether to loop
+                    next_frame = (next_frame + 1) % video.frames.len();
+                    if next_frame == 0 {
+                        break;
+                    }
+                }
+                let _ = message_sender.send(DecoderMessage::ChunkDecoded(video_id));
+            }
+            DecoderCommand::Fetch(number_frames, callback) => {
+                let mut frame_group = Vec::new();
+                for _ in 0..number_frames {
+                    match decoded.pop_front() {
+                        Some(frame) => frame_group.extend_from_slice(&frame),
+                        None => break
+                    }
+                }
+                if frame_group.len() > 0 {
+                    if let Ok(mut lock) = callback.lock() {
+                        if let Some(ref mut callback) = *lock {
+                            (*callback)(frame_group);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Cx {
+    pub(crate) fn handle_video_decoding_events(&mut self) {
+        while let Ok(message) = self.os.decoding.message_receiver.try_recv() {
+            let event = match message {
+                DecoderMessage::Initialized(e) => Event::VideoDecodingInitialized(e),
+                DecoderMessage::ChunkDecoded(video_id) => Event::VideoChunkDecoded(video_id),
+                DecoderMessage::Error(e) => {
+                    self.os.decoding.cleanup(e.video_id);
+                    Event::VideoDecodingError(e)
+                }
+            };
+            self.call_event_handler(&event);
+        }
+    }
+}
 
 impl CxDecodingApi for Cx {
-    fn video_decoding_input_box(&mut self, _video_id: LiveId, _f: VideoDecodingInputFn) {
+    fn video_decoding_input_box(&mut self, video_id: LiveId, f: VideoDecodingInputFn) {
+        let callback = Arc::new(Mutex::new(Some(f)));
+        self.os
+            .decoding
+            .video_decoding_input_cb
+            .insert(video_id, callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(marker: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFF, marker];
+        out.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    // A flat grey baseline JPEG small enough to fit a single 8x8 block. Both huffman
+    // tables hold a single symbol (DC difference 0 and end of block), so the whole
+    // scan is the bits 00.
+    fn grey_jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xD8];
+        let mut dqt = vec![0x00];
+        dqt.extend([1u8; 64]);
+        out.extend(segment(0xDB, &dqt));
+        let mut sof = vec![8];
+        sof.extend(height.to_be_bytes());
+        sof.extend(width.to_be_bytes());
+        sof.extend([1, 1, 0x11, 0]);
+        out.extend(segment(0xC0, &sof));
+        for class in [0x00, 0x10] {
+            let mut dht = vec![class, 1];
+            dht.extend([0u8; 15]);
+            dht.push(0x00);
+            out.extend(segment(0xC4, &dht));
+        }
+        out.extend(segment(0xDA, &[1, 1, 0x00, 0, 63, 0]));
+        out.extend([0x3F, 0xFF, 0xD9]);
+        out
+    }
+
+    #[test]
+    fn test_parse_jpeg_stream() {
+        // an EXIF thumbnail is a complete JPEG inside the APP1 segment
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend(grey_jpeg(1, 1));
+        let mut first = vec![0xFF, 0xD8];
+        first.extend(segment(0xE1, &app1));
+        first.extend(segment(0xDA, &[1, 1, 0x00, 0, 63, 0]));
+        // stuffed zeros, restart markers and fill bytes belong to the scan
+        first.extend([0x12, 0xFF, 0x00, 0x34, 0xFF, 0xD0, 0x56, 0xFF, 0xFF, 0xD9]);
+        let second = grey_jpeg(3, 5);
+
+        let mut stream = first.clone();
+        stream.extend(b"junk");
+        stream.extend(&second);
+        // a frame that was cut off
+        stream.extend(&second[..10]);
+
+        let frames = parse_jpeg_stream(&stream);
+        let second_start = first.len() + 4;
+        assert_eq!(frames, vec![0..first.len(), second_start..second_start + second.len()]);
+    }
+
+    #[test]
+    fn test_decode_odd_sized_frame() {
+        let mut stream = grey_jpeg(3, 5);
+        stream.extend(grey_jpeg(3, 5));
+        let video = MjpegVideo::parse(stream).unwrap();
+        assert_eq!((video.width, video.height, video.frames.len()), (3, 5, 2));
 
+        let mut out = Vec::new();
+        video.decode_frame(1, &mut out).unwrap();
+        let (head, pixels) = out.split_at(25);
+        assert_eq!(&head[8..12], &3u32.to_be_bytes());
+        assert_eq!(&head[12..16], &2u32.to_be_bytes());
+        assert_eq!(&head[16..20], &2u32.to_be_bytes());
+        assert_eq!(head[20], 1);
+        // 3x5 luma and two 2x3 chroma planes
+        assert_eq!(&head[21..25], &27u32.to_be_bytes());
+        assert_eq!(pixels.len(), 27);
+        // mid grey in studio swing
+        assert!(pixels[..15].iter().all( | y | *y == 126));
+        assert!(pixels[15..].iter().all( | uv | *uv == 128));
     }
 }
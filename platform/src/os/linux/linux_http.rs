@@ -0,0 +1,404 @@
+use {
+    std::{
+        io::{Read, Write, BufRead, BufReader},
+        net::TcpStream,
+        sync::mpsc::Sender,
+        time::Duration,
+    },
+    crate::{
+        makepad_live_id::*,
+        cx::Cx,
+        event::{
+            Event,
+            NetworkResponseEvent,
+            NetworkResponse,
+            HttpRequest,
+            HttpResponse,
+            HttpMethod,
+        },
+    }
+};
+
+// A small blocking HTTP/1.1 client, every request runs on its own thread and
+// reports back through the network response channel. Only plain http:// is
+// supported, there is no TLS implementation on Linux yet.
+
+const MAX_REDIRECTS: usize = 8;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) struct HttpUrl {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl HttpUrl {
+    pub(crate) fn parse(url: &str, scheme: &str, default_port: u16) -> Result<HttpUrl, String> {
+        let rest = if let Some(rest) = url.strip_prefix(&format!("{}://", scheme)) {
+            rest
+        }
+        else {
+            return Err(format!("Unsupported url (only {}:// is supported on linux): {}", scheme, url))
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/")
+        };
+        // strip userinfo, we don't do authentication
+        let authority = authority.rsplit('@').next().unwrap_or(authority);
+        let (host, port) = match authority.rfind(':') {
+            Some(index) if !authority[index..].contains(']') => {
+                let port = authority[index + 1..].parse().map_err( | _ | format!("Invalid port in url: {}", url)) ?;
+                (&authority[..index], port)
+            }
+            _ => (authority, default_port)
+        };
+        if host.len() == 0 {
+            return Err(format!("Missing host in url: {}", url))
+        }
+        Ok(HttpUrl {
+            host: host.to_string(),
+            port,
+            path: path.to_string()
+        })
+    }
+
+    pub(crate) fn host_header(&self, default_port: u16) -> String {
+        if self.port == default_port {
+            self.host.clone()
+        }
+        else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    pub(crate) fn connect(&self) -> Result<TcpStream, String> {
+        let host = self.host.trim_start_matches('[').trim_end_matches(']');
+        let stream = TcpStream::connect((host, self.port)).map_err( | e | format!("Cannot connect to {}:{} - {}", self.host, self.port, e)) ?;
+        let _ = stream.set_read_timeout(Some(TIMEOUT));
+        let _ = stream.set_write_timeout(Some(TIMEOUT));
+        Ok(stream)
+    }
+}
+
+struct RawResponse {
+    status_code: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl RawResponse {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find( | (k, _) | k.eq_ignore_ascii_case(name)).map( | (_, v) | v.as_str())
+    }
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String, String> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err( | e | format!("Error reading response: {}", e)) ?;
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+fn send_request(
+    url: &HttpUrl,
+    method: &str,
+    request: &HttpRequest,
+    body: Option<&[u8]>,
+    progress: &mut dyn FnMut(usize, usize)
+) -> Result<RawResponse, String> {
+    let mut stream = url.connect() ?;
+
+    let mut head = format!("{} {} HTTP/1.1\r\n", method, url.path);
+    let has_header = | name: &str | request.headers.keys().any( | k | k.eq_ignore_ascii_case(name));
+    if !has_header("host") {
+        head.push_str(&format!("Host: {}\r\n", url.host_header(80)));
+    }
+    if !has_header("user-agent") {
+        head.push_str("User-Agent: makepad\r\n");
+    }
+    if !has_header("accept") {
+        head.push_str("Accept: */*\r\n");
+    }
+    if let Some(body) = body {
+        if !has_header("content-length") {
+            head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+        }
+    }
+    // we read bodies without a length until the server hangs up
+    if !has_header("connection") {
+        head.push_str("Connection: close\r\n");
+    }
+    head.push_str(&request.get_headers_string());
+    head.push_str("\r\n");
+
+    let write_err = | e: std::io::Error | format!("Error sending request: {}", e);
+    stream.write_all(head.as_bytes()).map_err(write_err) ?;
+    if let Some(body) = body {
+        stream.write_all(body).map_err(write_err) ?;
+    }
+    stream.flush().map_err(write_err) ?;
+
+    let mut reader = BufReader::new(stream);
+
+    // skip any 1xx interim responses
+    let (status_code, headers) = loop {
+        let status_line = read_line(&mut reader) ?;
+        let mut parts = status_line.splitn(3, ' ');
+        let version = parts.next().unwrap_or("");
+        if !version.starts_with("HTTP/") {
+            return Err(format!("Invalid response status line: {}", status_line))
+        }
+        let status_code: u16 = parts.next().and_then( | s | s.parse().ok()).ok_or_else( || format!("Invalid response status line: {}", status_line)) ?;
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(&mut reader) ?;
+            if line.len() == 0 {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                headers.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        if status_code >= 200 || status_code < 100 {
+            break (status_code, headers)
+        }
+    };
+
+    let mut response = RawResponse {
+        status_code,
+        headers,
+        body: Vec::new()
+    };
+
+    if method == "HEAD" || status_code == 204 || status_code == 304 {
+        return Ok(response)
+    }
+
+    let content_length: Option<usize> = response.header("content-length").and_then( | v | v.parse().ok());
+    let chunked = response.header("transfer-encoding").map_or(false, | v | v.to_ascii_lowercase().contains("chunked"));
+    let read_err = | e: std::io::Error | format!("Error reading response body: {}", e);
+
+    if chunked {
+        loop {
+            let size_line = read_line(&mut reader) ?;
+            let size_str = size_line.split(';').next().unwrap_or("").trim();
+            let size = usize::from_str_radix(size_str, 16).map_err( | _ | format!("Invalid chunk size: {}", size_line)) ?;
+            if size == 0 {
+                // trailers, until the empty line
+                while read_line(&mut reader) ?.len() != 0 {}
+                break;
+            }
+            let start = response.body.len();
+            response.body.resize(start + size, 0);
+            reader.read_exact(&mut response.body[start..]).map_err(read_err) ?;
+            read_line(&mut reader) ?;
+            progress(response.body.len(), 0);
+        }
+    }
+    else if let Some(length) = content_length {
+        let mut buf = [0u8; 16384];
+        while response.body.len() < length {
+            let want = (length - response.body.len()).min(buf.len());
+            let n = reader.read(&mut buf[..want]).map_err(read_err) ?;
+            if n == 0 {
+                return Err("Connection closed before the response body was complete".to_string())
+            }
+            response.body.extend_from_slice(&buf[..n]);
+            progress(response.body.len(), length);
+        }
+    }
+    else {
+        reader.read_to_end(&mut response.body).map_err(read_err) ?;
+    }
+    Ok(response)
+}
+
+fn http_request_blocking(request: &HttpRequest, progress: &mut dyn FnMut(usize, usize)) -> Result<RawResponse, String> {
+    let mut url_string = request.url.clone();
+    let mut method = request.method.to_string().to_string();
+    let mut body = request.body.as_deref();
+    for _ in 0..MAX_REDIRECTS {
+        let url = HttpUrl::parse(&url_string, "http", 80) ?;
+        let response = send_request(&url, &method, request, body, progress) ?;
+        match response.status_code {
+            301 | 302 | 303 | 307 | 308 => if let Some(location) = response.header("location") {
+                url_string = if location.starts_with("http://") || location.starts_with("https://") {
+                    location.to_string()
+                }
+                else if location.starts_with('/') {
+                    format!("http://{}{}", url.host_header(80), location)
+                }
+                else {
+                    let base = &url.path[..url.path.rfind('/').map_or(0, | i | i + 1)];
+                    format!("http://{}{}{}", url.host_header(80), base, location)
+                };
+                if response.status_code == 303 || (response.status_code != 307 && response.status_code != 308 && request.method == HttpMethod::POST) {
+                    method = "GET".to_string();
+                    body = None;
+                }
+                continue;
+            }
+            _ => ()
+        }
+        return Ok(response)
+    }
+    Err(format!("Too many redirects for {}", request.url))
+}
+
+pub fn make_http_request(request_id: LiveId, request: HttpRequest, networking_sender: Sender<NetworkResponseEvent>) {
+    std::thread::spawn(move || {
+        let mut progress = | loaded: usize, total: usize | {
+            let _ = networking_sender.send(NetworkResponseEvent {
+                request_id,
+                response: NetworkResponse::HttpProgress {loaded: loaded as u32, total: total as u32}
+            });
+        };
+        let response = match http_request_blocking(&request, &mut progress) {
+            Ok(raw) => {
+                let mut response = HttpResponse::new(
+                    request.metadata_id,
+                    raw.status_code,
+                    "".to_string(),
+                    Some(raw.body),
+                );
+                for (key, value) in raw.headers {
+                    response.set_header(key, value);
+                }
+                NetworkResponse::HttpResponse(response)
+            }
+            Err(err) => NetworkResponse::HttpRequestError(err)
+        };
+        let _ = networking_sender.send(NetworkResponseEvent {
+            request_id,
+            response
+        });
+    });
+}
+
+impl Cx {
+    pub(crate) fn handle_networking_events(&mut self) {
+        let mut out = Vec::new();
+        while let Ok(event) = self.os.network_response.receiver.try_recv() {
+            // a websocket is done once it reports a close or an error, a request once it
+            // has its response or error
+            match event.response {
+                NetworkResponse::WebSocketClose | NetworkResponse::WebSocketError(_) => {
+                    self.os.web_sockets.remove(&event.request_id);
+                }
+                NetworkResponse::HttpResponse(_) | NetworkResponse::HttpRequestError(_) => {
+                    self.os.pending_http_requests.remove(&event.request_id);
+                }
+                _ => ()
+            }
+            out.push(event);
+        }
+        if out.len()>0 {
+            self.call_event_handler(&Event::NetworkResponses(out))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            net::TcpListener,
+            thread::JoinHandle,
+        },
+    };
+
+    // serves one canned response per connection and hands back the request heads it saw
+    fn serve(responses: Vec<String>) -> (u16, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let mut heads = Vec::new();
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                loop {
+                    let line = read_line(&mut reader).unwrap();
+                    if line.len() == 0 {
+                        break;
+                    }
+                    head.push_str(&line);
+                    head.push('\n');
+                }
+                let content_length = head.lines().find_map( | line | line.strip_prefix("Content-Length: ")).map_or(0, | v | v.parse().unwrap());
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                head.push_str(std::str::from_utf8(&body).unwrap());
+                reader.into_inner().write_all(response.as_bytes()).unwrap();
+                heads.push(head);
+            }
+            heads
+        });
+        (port, handle)
+    }
+
+    fn get(url: String) -> HttpRequest {
+        HttpRequest::new(url, HttpMethod::GET)
+    }
+
+    #[test]
+    fn test_parse_url() {
+        let url = HttpUrl::parse("http://user@example.com:8080/a/b?c", "http", 80).unwrap();
+        assert_eq!((url.host.as_str(), url.port, url.path.as_str()), ("example.com", 8080, "/a/b?c"));
+        assert_eq!(url.host_header(80), "example.com:8080");
+        let url = HttpUrl::parse("http://[::1]", "http", 80).unwrap();
+        assert_eq!((url.host.as_str(), url.port, url.path.as_str()), ("[::1]", 80, "/"));
+        assert!(HttpUrl::parse("https://example.com", "http", 80).is_err());
+        assert!(HttpUrl::parse("http://:80/", "http", 80).is_err());
+        assert!(HttpUrl::parse("http://example.com:x/", "http", 80).is_err());
+    }
+
+    #[test]
+    fn test_content_length_body() {
+        let (port, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nX-Test: yes\r\n\r\nhello".to_string()
+        ]);
+        let mut request = HttpRequest::new(format!("http://127.0.0.1:{}/post", port), HttpMethod::POST);
+        request.set_body_string("abc");
+        let response = http_request_blocking(&request, &mut | _, _ | {}).unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.header("x-test"), Some("yes"));
+        assert_eq!(response.body, b"hello");
+        let heads = server.join().unwrap();
+        assert!(heads[0].starts_with("POST /post HTTP/1.1\n"));
+        assert!(heads[0].contains("Content-Length: 3\n"));
+        assert!(heads[0].ends_with("abc"));
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let (port, server) = serve(vec![
+            "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4;ext=1\r\nwiki\r\n5\r\npedia\r\n0\r\nTrailer: x\r\n\r\n".to_string()
+        ]);
+        let mut chunks = 0;
+        let response = http_request_blocking(&get(format!("http://127.0.0.1:{}/", port)), &mut | _, _ | chunks += 1).unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, b"wikipedia");
+        assert_eq!(chunks, 2);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_redirect_and_connection_header() {
+        let (port, server) = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /moved\r\nContent-Length: 0\r\n\r\n".to_string(),
+            "HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\ngone".to_string(),
+        ]);
+        let mut request = get(format!("http://127.0.0.1:{}/start", port));
+        request.set_header("connection".to_string(), "close".to_string());
+        let response = http_request_blocking(&request, &mut | _, _ | {}).unwrap();
+        assert_eq!(response.status_code, 404);
+        assert_eq!(response.body, b"gone");
+        let heads = server.join().unwrap();
+        assert!(heads[1].starts_with("GET /moved HTTP/1.1\n"));
+        for head in heads {
+            assert_eq!(head.to_ascii_lowercase().matches("connection:").count(), 1, "{}", head);
+        }
+    }
+}
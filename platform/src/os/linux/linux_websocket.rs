@@ -0,0 +1,316 @@
+use {
+    std::{
+        io::{Read, Write},
+        net::{TcpStream, Shutdown},
+        sync::{Arc, Mutex, mpsc::{channel, Sender}},
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+    },
+    self::super::{
+        linux_http::HttpUrl,
+    },
+    crate::{
+        makepad_live_id::*,
+        makepad_http::{
+            digest::base64_encode,
+            websocket::{WebSocket, WebSocketMessage, OPCODE_TEXT, OPCODE_BINARY, OPCODE_CLOSE, OPCODE_PONG},
+        },
+        event::{
+            NetworkResponseEvent,
+            NetworkResponse,
+            HttpRequest,
+        },
+    }
+};
+
+// Websocket client on top of a plain TcpStream. The connect and handshake run
+// on a thread which then becomes the writer, a second thread reads and parses
+// incoming frames. Only ws:// is supported, there is no TLS on Linux yet.
+
+enum WebSocketOut {
+    String(String),
+    Binary(Vec<u8>),
+}
+
+pub struct LinuxWebSocket {
+    sender: Sender<WebSocketOut>,
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut out = [0u8; N];
+    let mut hasher = RandomState::new().build_hasher();
+    for chunk in out.chunks_mut(8) {
+        hasher.write_usize(chunk.as_ptr() as usize);
+        let bytes = hasher.finish().to_le_bytes();
+        chunk.copy_from_slice(&bytes[0..chunk.len()]);
+    }
+    out
+}
+
+fn write_frame(stream: &Mutex<TcpStream>, opcode: u8, data: &[u8]) -> Result<(), String> {
+    let frame = WebSocket::create_client_frame(opcode, data, random_bytes());
+    stream.lock().unwrap().write_all(&frame).map_err( | e | format!("Error writing to websocket: {}", e))
+}
+
+fn connect(request: &HttpRequest) -> Result<(TcpStream, Vec<u8>), String> {
+    let url = HttpUrl::parse(&request.url, "ws", 80) ?;
+    let mut stream = url.connect() ?;
+    let key = base64_encode(&random_bytes::<16>());
+    let upgrade = WebSocket::create_upgrade_request(
+        &url.host_header(80),
+        &url.path,
+        &key,
+        &request.get_headers_string()
+    );
+    stream.write_all(upgrade.as_bytes()).map_err( | e | format!("Error sending websocket handshake: {}", e)) ?;
+
+    // read the response head, anything after it already belongs to the frame stream
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    let head_end = loop {
+        if let Some(pos) = head.windows(4).position( | w | w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let n = stream.read(&mut buf).map_err( | e | format!("Error reading websocket handshake: {}", e)) ?;
+        if n == 0 {
+            return Err("Connection closed during websocket handshake".to_string());
+        }
+        head.extend_from_slice(&buf[..n]);
+    };
+    let rest = head.split_off(head_end);
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or("");
+    if status_line.split(' ').nth(1) != Some("101") {
+        return Err(format!("Websocket upgrade refused: {}", status_line));
+    }
+    let expected = WebSocket::create_accept_key(&key);
+    let accepted = lines.filter_map( | line | line.split_once(':')).any( | (k, v) | {
+        k.trim().eq_ignore_ascii_case("sec-websocket-accept") && v.trim() == expected
+    });
+    if !accepted {
+        return Err("Websocket handshake failed: invalid Sec-WebSocket-Accept".to_string());
+    }
+    // the socket blocks on reads from here on, only writes keep a timeout
+    let _ = stream.set_read_timeout(None);
+    Ok((stream, rest))
+}
+
+fn read_loop(mut stream: TcpStream, write_stream: Arc<Mutex<TcpStream>>, initial: Vec<u8>, request_id: LiveId, networking_sender: Sender<NetworkResponseEvent>) {
+    let mut web_socket = WebSocket::new();
+    let mut buf = vec![0u8; 65536];
+    let mut input = initial;
+    loop {
+        let mut closed = false;
+        let mut error = None;
+        let mut pongs = Vec::new();
+        web_socket.parse(&input, | result | {
+            if closed || error.is_some() {
+                return
+            }
+            let response = match result {
+                Ok(WebSocketMessage::Text(text)) => NetworkResponse::WebSocketString(text.to_string()),
+                Ok(WebSocketMessage::Binary(data)) => NetworkResponse::WebSocketBinary(data.to_vec()),
+                Ok(WebSocketMessage::Ping(data)) => {
+                    pongs.push(data.to_vec());
+                    return
+                }
+                Ok(WebSocketMessage::Pong(_)) => return,
+                Ok(WebSocketMessage::Close) => {
+                    closed = true;
+                    return
+                }
+                Err(err) => {
+                    error = Some(format!("{:?}", err));
+                    return
+                }
+            };
+            let _ = networking_sender.send(NetworkResponseEvent {request_id, response});
+        });
+        for data in pongs {
+            let _ = write_frame(&write_stream, OPCODE_PONG, &data);
+        }
+        // the frame stream can't be trusted after a protocol error, so that ends the connection too
+        if let Some(err) = error {
+            let _ = stream.shutdown(Shutdown::Both);
+            let _ = networking_sender.send(NetworkResponseEvent {request_id, response: NetworkResponse::WebSocketError(err)});
+            return
+        }
+        if closed {
+            let _ = write_frame(&write_stream, OPCODE_CLOSE, &[]);
+            let _ = stream.shutdown(Shutdown::Both);
+            let _ = networking_sender.send(NetworkResponseEvent {request_id, response: NetworkResponse::WebSocketClose});
+            return
+        }
+        match stream.read(&mut buf) {
+            Ok(0) => {
+                let _ = networking_sender.send(NetworkResponseEvent {request_id, response: NetworkResponse::WebSocketClose});
+                return
+            }
+            Ok(n) => {
+                input.clear();
+                input.extend_from_slice(&buf[..n]);
+            }
+            Err(e) => {
+                // a shutdown from our own side ends up here as well
+                let _ = networking_sender.send(NetworkResponseEvent {
+                    request_id,
+                    response: NetworkResponse::WebSocketError(format!("Error reading from websocket: {}", e))
+                });
+                return
+            }
+        }
+    }
+}
+
+impl LinuxWebSocket {
+    pub fn open(request_id: LiveId, request: HttpRequest, networking_sender: Sender<NetworkResponseEvent>) -> LinuxWebSocket {
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            let (stream, rest) = match connect(&request) {
+                Ok(v) => v,
+                Err(err) => {
+                    let _ = networking_sender.send(NetworkResponseEvent {request_id, response: NetworkResponse::WebSocketError(err)});
+                    return
+                }
+            };
+            let write_stream = match stream.try_clone() {
+                Ok(s) => Arc::new(Mutex::new(s)),
+                Err(e) => {
+                    let _ = networking_sender.send(NetworkResponseEvent {request_id, response: NetworkResponse::WebSocketError(e.to_string())});
+                    return
+                }
+            };
+            let _ = networking_sender.send(NetworkResponseEvent {request_id, response: NetworkResponse::WebSocketOpen});
+
+            let read_write_stream = write_stream.clone();
+            let read_sender = networking_sender.clone();
+            std::thread::spawn(move || {
+                read_loop(stream, read_write_stream, rest, request_id, read_sender)
+            });
+
+            // runs until the LinuxWebSocket is dropped
+            while let Ok(out) = receiver.recv() {
+                let result = match out {
+                    WebSocketOut::String(data) => write_frame(&write_stream, OPCODE_TEXT, data.as_bytes()),
+                    WebSocketOut::Binary(data) => write_frame(&write_stream, OPCODE_BINARY, &data),
+                };
+                if let Err(err) = result {
+                    let _ = networking_sender.send(NetworkResponseEvent {request_id, response: NetworkResponse::WebSocketError(err)});
+                    return
+                }
+            }
+            let _ = write_frame(&write_stream, OPCODE_CLOSE, &[]);
+            let _ = write_stream.lock().unwrap().shutdown(Shutdown::Both);
+        });
+        LinuxWebSocket {
+            sender
+        }
+    }
+
+    pub fn send_string(&self, data: String) {
+        let _ = self.sender.send(WebSocketOut::String(data));
+    }
+
+    pub fn send_binary(&self, data: Vec<u8>) {
+        let _ = self.sender.send(WebSocketOut::Binary(data));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            net::TcpListener,
+            time::{Duration, Instant},
+        },
+        crate::cx::Cx,
+    };
+
+    // accepts one client, echoes its text messages and closes the connection on "bye"
+    fn serve() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !head.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf[..1]).unwrap();
+                head.extend_from_slice(&buf[..n]);
+            }
+            let head = String::from_utf8(head).unwrap();
+            let key = head.lines().find_map( | line | line.strip_prefix("Sec-WebSocket-Key: ")).unwrap();
+            stream.write_all(WebSocket::create_upgrade_response(key).as_bytes()).unwrap();
+            let mut web_socket = WebSocket::new();
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    return
+                }
+                let mut replies = Vec::new();
+                web_socket.parse(&buf[..n], | result | if let Ok(WebSocketMessage::Text(text)) = result {
+                    replies.push(text.to_string());
+                });
+                for reply in replies {
+                    if reply == "bye" {
+                        stream.write_all(&[128 | OPCODE_CLOSE, 0]).unwrap();
+                    }
+                    else {
+                        stream.write_all(&[128 | OPCODE_TEXT, reply.len() as u8]).unwrap();
+                        stream.write_all(reply.as_bytes()).unwrap();
+                    }
+                }
+            }
+        });
+        port
+    }
+
+    fn next_response(cx: &mut Cx) -> NetworkResponse {
+        let event = cx.os.network_response.receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        event.response
+    }
+
+    #[test]
+    fn test_echo_and_close() {
+        let port = serve();
+        let mut cx = Cx::new(Box::new( | _, _ | {}));
+        let request_id = LiveId(1);
+        let request = HttpRequest::new(format!("ws://127.0.0.1:{}/socket", port), crate::event::HttpMethod::GET);
+        let web_socket = LinuxWebSocket::open(request_id, request, cx.os.network_response.sender.clone());
+        cx.os.web_sockets.insert(request_id, web_socket);
+
+        assert!(matches!(next_response(&mut cx), NetworkResponse::WebSocketOpen));
+        cx.os.web_sockets[&request_id].send_string("hello".to_string());
+        match next_response(&mut cx) {
+            NetworkResponse::WebSocketString(text) => assert_eq!(text, "hello"),
+            _ => panic!("expected the echo")
+        }
+        cx.os.web_sockets[&request_id].send_string("bye".to_string());
+
+        // the socket is forgotten once its close has been handed to the app
+        let start = Instant::now();
+        while cx.os.web_sockets.contains_key(&request_id) {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            cx.handle_networking_events();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_refused_connection_is_removed() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut cx = Cx::new(Box::new( | _, _ | {}));
+        let request_id = LiveId(2);
+        let request = HttpRequest::new(format!("ws://127.0.0.1:{}/", port), crate::event::HttpMethod::GET);
+        let web_socket = LinuxWebSocket::open(request_id, request, cx.os.network_response.sender.clone());
+        cx.os.web_sockets.insert(request_id, web_socket);
+        let start = Instant::now();
+        while cx.os.web_sockets.contains_key(&request_id) {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            cx.handle_networking_events();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
@@ -4,6 +4,9 @@ pub mod x11;
 #[cfg(linux_direct)]
 pub mod direct;
 
+#[cfg(not(target_os="android"))]
+pub mod headless;
+
 pub mod egl_sys;
 
 pub mod gl_sys;
@@ -22,6 +25,10 @@ pub mod linux_media;
 #[cfg(not(target_os="android"))]
 pub mod linux_decoding;
 #[cfg(not(target_os="android"))]
+pub mod linux_http;
+#[cfg(not(target_os="android"))]
+pub mod linux_websocket;
+#[cfg(not(target_os="android"))]
 pub mod alsa_audio;
 #[cfg(not(target_os="android"))]
 pub mod alsa_midi;
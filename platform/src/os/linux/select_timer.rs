@@ -36,7 +36,8 @@ impl SelectTimers {
         }
     }
     
-    pub fn select(&mut self, fd: c_int) {
+    // max_timeout wakes the select up in time for deadlines that aren't timers
+    pub fn select(&mut self, fd: c_int, max_timeout: Option<f64>) {
         let mut fds = mem::MaybeUninit::uninit();
         unsafe {
             libc_sys::FD_ZERO(fds.as_mut_ptr());
@@ -47,18 +48,18 @@ impl SelectTimers {
         // If there are any timers, we set the timeout for select to the `delta_timeout`
         // of the first timer that should be fired. Otherwise, we set the timeout to
         // None, so that select will block indefinitely.
-        let timeout = if let Some(timer) = self.timers.front() { 
-            Some(libc_sys::timeval {
-                // `tv_sec` is in seconds, so take the integer part of `delta_timeout`
-                tv_sec: timer.delta_timeout.trunc() as libc_sys::time_t,
-                // `tv_usec` is in microseconds, so take the fractional part of
-                // `delta_timeout` 1000000.0.
-                tv_usec: (timer.delta_timeout.fract() * 1000000.0) as libc_sys::time_t,
-            })
-        }  
-        else { 
-            None
+        let delta_timeout = match (self.timers.front(), max_timeout) {
+            (Some(timer), Some(max_timeout)) => Some(timer.delta_timeout.min(max_timeout)),
+            (Some(timer), None) => Some(timer.delta_timeout),
+            (None, max_timeout) => max_timeout
         };
+        let timeout = delta_timeout.map( | delta_timeout | libc_sys::timeval {
+            // `tv_sec` is in seconds, so take the integer part of `delta_timeout`
+            tv_sec: delta_timeout.trunc() as libc_sys::time_t,
+            // `tv_usec` is in microseconds, so take the fractional part of
+            // `delta_timeout` 1000000.0.
+            tv_usec: (delta_timeout.fract() * 1000000.0) as libc_sys::time_t,
+        });
         let _nfds = unsafe {libc_sys::select(
             fd+1,
             fds.as_mut_ptr(),
@@ -1,6 +1,7 @@
 use {
     std::cell::RefCell,
     std::rc::Rc,
+    std::collections::{HashMap, HashSet},
     self::super::opengl_x11::{
         OpenglWindow,
         OpenglCx
@@ -9,14 +10,20 @@ use {
         egl_sys,
         x11::xlib_event::*,
         x11::xlib_app::*,
-        linux_media::CxLinuxMedia
+        linux_media::CxLinuxMedia,
+        linux_decoding::CxLinuxDecoding,
+        linux_http::make_http_request,
+        linux_websocket::LinuxWebSocket,
+        headless::cpu_raster::CxHeadless,
     },
     crate::{
+        makepad_error_log::*,
         cx_api::{CxOsOp, CxOsApi}, 
         makepad_math::dvec2,
         makepad_live_id::*,
         thread::Signal,
-        event::Event,
+        event::{Event, MouseUpEvent, NetworkResponseChannel},
+        window::CxWindowPool,
         pass::CxPassParent,
         cx::{Cx, OsType,LinuxWindowParams}, 
         gpu_info::GpuPerformance,
@@ -32,6 +39,14 @@ impl Cx {
         });
         cx.borrow_mut().gpu_info.performance = GpuPerformance::Tier1;
 
+        if Cx::headless_requested(){
+            return cx.borrow_mut().headless_event_loop();
+        }
+        if std::env::var_os("DISPLAY").is_none(){
+            log!("No DISPLAY set, running headless");
+            return cx.borrow_mut().headless_event_loop();
+        }
+        
         let opengl_windows = Rc::new(RefCell::new(Vec::new()));
         let is_stdin_loop = std::env::args().find(|v| v=="--stdin-loop").is_some();
         init_xlib_app_global(Box::new({
@@ -162,14 +177,31 @@ impl Cx {
             XlibEvent::TextInput(e) => {
                 self.call_event_handler(&Event::TextInput(e))
             }
+            XlibEvent::TextPreedit(e) => {
+                self.call_event_handler(&Event::TextPreedit(e))
+            }
             XlibEvent::Drag(e) => {
-                self.call_event_handler(&Event::Drag(e))
+                self.call_event_handler(&Event::Drag(e));
+                self.drag_drop.cycle_drag();
             }
             XlibEvent::Drop(e) => {
-                self.call_event_handler(&Event::Drop(e))
+                self.call_event_handler(&Event::Drop(e));
+                self.drag_drop.cycle_drag();
             }
             XlibEvent::DragEnd => {
-                self.call_event_handler(&Event::DragEnd)
+                // the button release went to the drag, so release the mouse here
+                self.call_event_handler(&Event::MouseUp(MouseUpEvent {
+                    abs: dvec2(-100000.0, -100000.0),
+                    button: 0,
+                    window_id: CxWindowPool::id_zero(),
+                    modifiers: Default::default(),
+                    time: 0.0
+                }));
+                self.fingers.mouse_up(0);
+                self.fingers.cycle_hover_area(live_id!(mouse).into());
+                
+                self.call_event_handler(&Event::DragEnd);
+                self.drag_drop.cycle_drag();
             }
             XlibEvent::KeyDown(e) => {
                 self.keyboard.process_key_down(e.clone());
@@ -185,6 +217,9 @@ impl Cx {
             XlibEvent::TextCut(e) => {
                 self.call_event_handler(&Event::TextCut(e))
             }
+            XlibEvent::Clipboard(e) => {
+                self.call_event_handler(&Event::Clipboard(e))
+            }
             XlibEvent::Timer(e) => {
                 //println!("TIMER! {:?}", std::time::Instant::now());
                 if e.timer_id == 0{
@@ -192,6 +227,8 @@ impl Cx {
                         self.handle_media_signals();
                         self.call_event_handler(&Event::Signal);
                     }
+                    self.handle_networking_events();
+                    self.handle_video_decoding_events();
                 }
                 else{
                     self.call_event_handler(&Event::Timer(e))
@@ -207,9 +244,6 @@ impl Cx {
         
     }
 
-    pub(crate) fn handle_networking_events(&mut self) {
-    }
-    
     pub (crate) fn handle_repaint(&mut self, opengl_windows: &mut Vec<OpenglWindow>) {
         self.os.opengl_cx.as_ref().unwrap().make_current();
         let mut passes_todo = Vec::new();
@@ -283,14 +317,28 @@ impl Cx {
                 },
                 CxOsOp::ShowClipboardActions(_) =>{
                 }
-                CxOsOp::FullscreenWindow(_window_id) => {
-                    todo!()
+                CxOsOp::CopyToClipboard(data) => {
+                    unsafe {xlib_app.set_clipboard(data)};
+                }
+                CxOsOp::ReadClipboard {request_id, mime_types} => {
+                    if !unsafe {xlib_app.read_clipboard(request_id, mime_types)} {
+                        self.call_empty_clipboard_event(request_id);
+                    }
+                }
+                CxOsOp::FullscreenWindow(window_id) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.xlib_window.fullscreen();
+                    }
                 },
-                CxOsOp::NormalizeWindow(_window_id) => {
-                    todo!()
+                CxOsOp::NormalizeWindow(window_id) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.xlib_window.normalize();
+                    }
                 }
-                CxOsOp::SetTopmost(_window_id, _is_topmost) => {
-                    todo!()
+                CxOsOp::SetTopmost(window_id, is_topmost) => {
+                    if let Some(window) = opengl_windows.iter_mut().find( | w | w.window_id == window_id) {
+                        window.xlib_window.set_topmost(is_topmost);
+                    }
                 }
                 CxOsOp::XrStartPresenting => {
                     //todo!()
@@ -298,11 +346,17 @@ impl Cx {
                 CxOsOp::XrStopPresenting => {
                     //todo!()
                 },
-                CxOsOp::ShowTextIME(_area, _pos) => {
-                    //todo!()
+                CxOsOp::ShowTextIME(area, pos, line_height) => {
+                    // the spot is on the baseline, so the candidate window goes below the line
+                    let spot = area.get_clipped_rect(self).pos + pos + dvec2(0.0, line_height);
+                    opengl_windows.iter_mut().for_each( | w | {
+                        w.xlib_window.set_ime_spot(spot);
+                    });
                 }
                 CxOsOp::HideTextIME => {
-                    //todo!()
+                    opengl_windows.iter_mut().for_each( | w | {
+                        w.xlib_window.reset_ime();
+                    });
                 },
                 CxOsOp::SetCursor(cursor) => {
                     xlib_app.set_mouse_cursor(cursor);
@@ -313,26 +367,41 @@ impl Cx {
                 CxOsOp::StopTimer(timer_id) => {
                     xlib_app.stop_timer(timer_id);
                 },
-                CxOsOp::StartDragging(_dragged_item) => {
+                CxOsOp::StartDragging(items) => {
+                    xlib_app.start_dragging(items);
                 },
                 CxOsOp::UpdateMacosMenu(_menu) => {
                 },
-                CxOsOp::HttpRequest{request_id:_, request:_} => {
-                    todo!()
+                CxOsOp::HttpRequest{request_id, request} => {
+                    self.os.pending_http_requests.insert(request_id);
+                    make_http_request(request_id, request, self.os.network_response.sender.clone());
                 },
-                CxOsOp::WebSocketOpen{request_id:_, request:_}=>{
-                    todo!()
+                CxOsOp::WebSocketOpen{request_id, request}=>{
+                    let web_socket = LinuxWebSocket::open(request_id, request, self.os.network_response.sender.clone());
+                    self.os.web_sockets.insert(request_id, web_socket);
                 }
-                CxOsOp::WebSocketSendBinary{request_id:_, data:_}=>{
-                    todo!()
+                CxOsOp::WebSocketSendBinary{request_id, data}=>{
+                    if let Some(web_socket) = self.os.web_sockets.get(&request_id){
+                        web_socket.send_binary(data);
+                    }
                 }
-                CxOsOp::WebSocketSendString{request_id:_, data:_}=>{
-                    todo!()
+                CxOsOp::WebSocketSendString{request_id, data}=>{
+                    if let Some(web_socket) = self.os.web_sockets.get(&request_id){
+                        web_socket.send_string(data);
+                    }
+                },
+                CxOsOp::InitializeVideoDecoding(video_id, video, chunk_size) => {
+                    self.os.decoding.initialize(video_id, video, chunk_size);
+                },
+                CxOsOp::DecodeNextVideoChunk(video_id, max_frames_to_decode) => {
+                    self.os.decoding.decode_next_chunk(video_id, max_frames_to_decode);
+                },
+                CxOsOp::FetchNextVideoFrames(video_id, number_frames) => {
+                    self.os.decoding.fetch_next_frames(video_id, number_frames);
+                },
+                CxOsOp::CleanupVideoDecoding(video_id) => {
+                    self.os.decoding.cleanup(video_id);
                 },
-                CxOsOp::InitializeVideoDecoding(_, _, _) => todo!(),
-                CxOsOp::DecodeNextVideoChunk(_, _) => todo!(),
-                CxOsOp::FetchNextVideoFrames(_, _) => todo!(),
-                CxOsOp::CleanupVideoDecoding(_) => todo!(),
             }
         }
         ret
@@ -354,8 +423,14 @@ impl CxOsApi for Cx {
 #[derive(Default)]
 pub struct CxOs {
     pub(crate) media: CxLinuxMedia,
+    pub(crate) network_response: NetworkResponseChannel,
+    pub(crate) web_sockets: HashMap<LiveId, LinuxWebSocket>,
+    // http requests that haven't had their response or error yet
+    pub(crate) pending_http_requests: HashSet<LiveId>,
+    pub(crate) decoding: CxLinuxDecoding,
 
     // HACK(eddyb) generalize this to EGL, properly.
     pub(super) opengl_cx: Option<OpenglCx>,
+    pub(crate) headless: Option<CxHeadless>,
 }
 
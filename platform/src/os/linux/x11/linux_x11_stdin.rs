@@ -21,6 +21,8 @@ use {
         cx_api::CxOsOp,
         cx::Cx,
         gl_sys,
+        linux_http::make_http_request,
+        linux_websocket::LinuxWebSocket,
     } 
 };
 
@@ -128,6 +130,9 @@ impl Cx {
                 HostToStdin::KeyUp(e) => {
                     self.call_event_handler(&Event::KeyUp(e));
                 }
+                HostToStdin::TextInput(e) => {
+                    self.call_event_handler(&Event::TextInput(e));
+                }
                 HostToStdin::MouseDown(e) => {
                     self.fingers.process_tap_count(
                         dvec2(e.x,e.y),
@@ -206,6 +211,7 @@ impl Cx {
                         self.redraw_all();
                     }
                     self.handle_networking_events();
+                    self.handle_video_decoding_events();
                     
                     // we should poll our runloop
                     self.stdin_handle_platform_ops(swapchain.as_ref(), present_index);
@@ -254,6 +260,39 @@ impl Cx {
                 CxOsOp::SetCursor(cursor) => {
                     let _ = io::stdout().write_all(StdinToHost::SetCursor(cursor).to_json().as_bytes());
                 },
+                CxOsOp::HttpRequest{request_id, request} => {
+                    self.os.pending_http_requests.insert(request_id);
+                    make_http_request(request_id, request, self.os.network_response.sender.clone());
+                },
+                CxOsOp::WebSocketOpen {request_id, request} => {
+                    let web_socket = LinuxWebSocket::open(request_id, request, self.os.network_response.sender.clone());
+                    self.os.web_sockets.insert(request_id, web_socket);
+                }
+                CxOsOp::WebSocketSendBinary {request_id, data} => {
+                    if let Some(web_socket) = self.os.web_sockets.get(&request_id) {
+                        web_socket.send_binary(data);
+                    }
+                }
+                CxOsOp::WebSocketSendString {request_id, data} => {
+                    if let Some(web_socket) = self.os.web_sockets.get(&request_id) {
+                        web_socket.send_string(data);
+                    }
+                }
+                CxOsOp::InitializeVideoDecoding(video_id, video, chunk_size) => {
+                    self.os.decoding.initialize(video_id, video, chunk_size);
+                },
+                CxOsOp::DecodeNextVideoChunk(video_id, max_frames_to_decode) => {
+                    self.os.decoding.decode_next_chunk(video_id, max_frames_to_decode);
+                },
+                CxOsOp::FetchNextVideoFrames(video_id, number_frames) => {
+                    self.os.decoding.fetch_next_frames(video_id, number_frames);
+                },
+                CxOsOp::CleanupVideoDecoding(video_id) => {
+                    self.os.decoding.cleanup(video_id);
+                },
+                CxOsOp::ReadClipboard {request_id, ..} => {
+                    self.call_empty_clipboard_event(request_id);
+                }
                 _ => ()
                 /*
                 CxOsOp::CloseWindow(_window_id) => {},
@@ -265,7 +304,7 @@ impl Cx {
                 CxOsOp::SetTopmost(_window_id, _is_topmost) => {}
                 CxOsOp::XrStartPresenting(_) => {},
                 CxOsOp::XrStopPresenting(_) => {},
-                CxOsOp::ShowTextIME(_area, _pos) => {},
+                CxOsOp::ShowTextIME(_area, _pos, _line_height) => {},
                 CxOsOp::HideTextIME => {},
                 CxOsOp::SetCursor(_cursor) => {},
                 CxOsOp::StartTimer {timer_id, interval, repeats} => {},
@@ -9,6 +9,7 @@ use std::os::raw::{
     c_void,
     c_char,
     c_uchar,
+    c_ushort,
 };
 
 
@@ -34,6 +35,11 @@ pub type XrmDatabase = *mut _XrmHashBucketRec;
 pub type XKeyPressedEvent = XKeyEvent;
 pub type XComposeStatus = _XComposeStatus;
 pub type GC = *mut _XGC;
+pub type XIMStyle = c_ulong;
+pub type XIMFeedback = c_ulong;
+pub type XVaNestedList = *mut c_void;
+pub type XIMProc = Option<unsafe extern "C" fn(arg1: XIM, arg2: XPointer, arg3: XPointer)>;
+pub type XICProc = Option<unsafe extern "C" fn(arg1: XIC, arg2: XPointer, arg3: XPointer) -> c_int>;
 
 pub const None: u32 = 0;
 pub const True: u32 = 1;
@@ -46,8 +52,12 @@ pub const SelectionNotify: u32 = 31;
 pub const AnyPropertyType: u32 = 0;
 pub const SelectionRequest: u32 = 30;
 pub const PropModeReplace: u32 = 0;
+pub const PropertyNewValue: u32 = 0;
+pub const PropertyDelete: u32 = 1;
+pub const XA_ATOM: u32 = 4;
 pub const DestroyNotify: u32 = 17;
 pub const ConfigureNotify: u32 = 22;
+pub const PropertyNotify: u32 = 28;
 pub const EnterNotify: u32 = 7;
 pub const LeaveNotify: u32 = 8;
 pub const MotionNotify: u32 = 6;
@@ -59,6 +69,8 @@ pub const KeyRelease: u32 = 3;
 pub const ButtonPress: u32 = 4;
 pub const ButtonRelease: u32 = 5;
 pub const Expose: u32 = 12;
+pub const FocusIn: u32 = 9;
+pub const FocusOut: u32 = 10;
 
 pub const CWBorderPixel: u32 = 8;
 pub const CWColormap: u32 = 8192;
@@ -80,14 +92,33 @@ pub const VisibilityChangeMask: u32 = 65536;
 pub const FocusChangeMask: u32 = 2097152;
 pub const EnterWindowMask: u32 = 16;
 pub const LeaveWindowMask: u32 = 32;
+pub const PropertyChangeMask: u32 = 4194304;
 pub const XBufferOverflow: i32 = -1;
 
+pub const XLookupChars: i32 = 2;
+pub const XLookupBoth: i32 = 4;
+
+pub const XIMPreeditCallbacks: u32 = 2;
+pub const XIMPreeditPosition: u32 = 4;
 pub const XIMPreeditNothing: u32 = 8;
 pub const XIMStatusNothing: u32 = 1024;
 
+pub const XIMForwardChar: c_int = 0;
+pub const XIMBackwardChar: c_int = 1;
+pub const XIMLineStart: c_int = 8;
+pub const XIMLineEnd: c_int = 9;
+pub const XIMAbsolutePosition: c_int = 10;
+
 pub const XNInputStyle: &'static [u8; 11usize] = b"inputStyle\0";
 pub const XNClientWindow: &'static [u8; 13usize] = b"clientWindow\0";
 pub const XNFocusWindow: &'static [u8; 12usize] = b"focusWindow\0";
+pub const XNQueryInputStyle: &'static [u8; 16usize] = b"queryInputStyle\0";
+pub const XNPreeditAttributes: &'static [u8; 18usize] = b"preeditAttributes\0";
+pub const XNSpotLocation: &'static [u8; 13usize] = b"spotLocation\0";
+pub const XNPreeditStartCallback: &'static [u8; 21usize] = b"preeditStartCallback\0";
+pub const XNPreeditDoneCallback: &'static [u8; 20usize] = b"preeditDoneCallback\0";
+pub const XNPreeditDrawCallback: &'static [u8; 20usize] = b"preeditDrawCallback\0";
+pub const XNPreeditCaretCallback: &'static [u8; 21usize] = b"preeditCaretCallback\0";
 
 pub const Mod1Mask: u32 = 8;
 pub const ShiftMask: u32 = 1;
@@ -353,6 +384,24 @@ extern "C" {
     
     pub fn XCreateIC(arg1: XIM, ...) -> XIC;
     
+    pub fn XDestroyIC(arg1: XIC);
+    
+    pub fn XSetICValues(arg1: XIC, ...) -> *mut c_char;
+    
+    pub fn XGetIMValues(arg1: XIM, ...) -> *mut c_char;
+    
+    pub fn XVaCreateNestedList(arg1: c_int, ...) -> XVaNestedList;
+    
+    pub fn XSetICFocus(arg1: XIC);
+    
+    pub fn XUnsetICFocus(arg1: XIC);
+    
+    pub fn Xutf8ResetIC(arg1: XIC) -> *mut c_char;
+    
+    pub fn XFilterEvent(arg1: *mut XEvent, arg2: Window) -> c_int;
+    
+    pub fn XSetLocaleModifiers(arg1: *const c_char) -> *mut c_char;
+    
     pub fn XDestroyWindow(arg1: *mut Display, arg2: Window) -> c_int;
     
     pub fn XIconifyWindow(
@@ -397,6 +446,17 @@ extern "C" {
     
     pub fn XUngrabPointer(arg1: *mut Display, arg2: Time) -> c_int;
     
+    pub fn XTranslateCoordinates(
+        arg1: *mut Display,
+        arg2: Window,
+        arg3: Window,
+        arg4: c_int,
+        arg5: c_int,
+        arg6: *mut c_int,
+        arg7: *mut c_int,
+        arg8: *mut Window,
+    ) -> c_int;
+    
     pub fn XSetSelectionOwner(
         arg1: *mut Display,
         arg2: Atom,
@@ -404,6 +464,18 @@ extern "C" {
         arg4: Time,
     ) -> c_int;
     
+    pub fn XGetSelectionOwner(arg1: *mut Display, arg2: Atom) -> Window;
+    
+    pub fn XSelectInput(arg1: *mut Display, arg2: Window, arg3: c_long) -> c_int;
+    
+    pub fn XDeleteProperty(arg1: *mut Display, arg2: Window, arg3: Atom) -> c_int;
+    
+    pub fn XGetAtomName(arg1: *mut Display, arg2: Atom) -> *mut c_char;
+    
+    pub fn XExtendedMaxRequestSize(arg1: *mut Display) -> c_long;
+    
+    pub fn XMaxRequestSize(arg1: *mut Display) -> c_long;
+    
     pub fn Xutf8LookupString(
         arg1: XIC,
         arg2: *mut XKeyPressedEvent,
@@ -448,6 +520,67 @@ pub struct _XIC {
     _unused: [u8; 0],
 }
 
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct XIMStyles {
+    pub count_styles: c_ushort,
+    pub supported_styles: *mut XIMStyle,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct XPoint {
+    pub x: c_short,
+    pub y: c_short,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct XIMCallback {
+    pub client_data: XPointer,
+    pub callback: XIMProc,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct XICCallback {
+    pub client_data: XPointer,
+    pub callback: XICProc,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union XIMText__bindgen_ty_1 {
+    pub multi_byte: *mut c_char,
+    pub wide_char: *mut u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XIMText {
+    pub length: c_ushort,
+    pub feedback: *mut XIMFeedback,
+    pub encoding_is_wchar: c_int,
+    pub string: XIMText__bindgen_ty_1,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct XIMPreeditDrawCallbackStruct {
+    pub caret: c_int,
+    pub chg_first: c_int,
+    pub chg_length: c_int,
+    pub text: *mut XIMText,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct XIMPreeditCaretCallbackStruct {
+    pub position: c_int,
+    pub direction: c_int,
+    pub style: c_int,
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct _XComposeStatus {
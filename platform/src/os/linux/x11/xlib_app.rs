@@ -5,6 +5,7 @@ use {
         rc::Rc,
         cell::{Cell, RefCell},
         os::raw::{c_char, c_int, c_uint, c_ulong, c_void, c_uchar, c_long},
+        ffi::{CStr, CString},
         ptr,
     },
     self::super::{
@@ -12,13 +13,14 @@ use {
         xlib_event::XlibEvent,
         xlib_window::*,
         super::select_timer::SelectTimers,
+        super::libc_sys,
     },
     crate::{
+        makepad_live_id::LiveId,
         makepad_math::DVec2,
         event::*,
         cursor::MouseCursor,
         os::cx_native::EventFlow,
-        x11::x11_sys::XEvent
     },
 };
 
@@ -40,7 +42,10 @@ pub struct XlibApp {
     pub display: *mut x11_sys::Display,
     event_loop_running: bool,
     pub xim: x11_sys::XIM,
-    pub clipboard: String,
+    pub xim_style: x11_sys::XIMStyle,
+    pub clipboard: ClipboardData,
+    clipboard_reads: Vec<ClipboardRead>,
+    clipboard_writes: Vec<ClipboardIncrWrite>,
     pub display_fd: c_int,
     //pub signal_fds: [c_int; 2],
     pub window_map: HashMap<c_ulong, *mut XlibWindow>,
@@ -50,6 +55,7 @@ pub struct XlibApp {
     pub last_scroll_time: f64,
     pub last_click_time: f64,
     pub last_click_pos: (i32, i32),
+    pub last_button_window: Option<c_ulong>,
     pub event_callback: Option<Box<dyn FnMut(&mut XlibApp, XlibEvent) -> EventFlow >>,
     //pub free_timers: Vec<usize>,
     pub event_flow: EventFlow,
@@ -63,7 +69,16 @@ impl XlibApp {
         unsafe {
             let display = x11_sys::XOpenDisplay(ptr::null());
             let display_fd = x11_sys::XConnectionNumber(display);
-            let xim = x11_sys::XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            // the input method is picked from the locale and XMODIFIERS
+            libc_sys::setlocale(libc_sys::LC_CTYPE, "\0".as_ptr() as *const _);
+            x11_sys::XSetLocaleModifiers("\0".as_ptr() as *const _);
+            let mut xim = x11_sys::XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            if xim == ptr::null_mut() {
+                // fall back to the builtin input method for dead keys and compose
+                x11_sys::XSetLocaleModifiers("@im=none\0".as_ptr() as *const _);
+                xim = x11_sys::XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            }
+            let xim_style = Self::select_xim_style(xim);
             //let mut signal_fds = [0, 0];
             //libc_sys::pipe(signal_fds.as_mut_ptr());
             x11_sys::XrmInitialize();
@@ -72,13 +87,17 @@ impl XlibApp {
                 event_callback: Some(event_callback),
                 atoms: XlibAtoms::new(display),
                 xim,
+                xim_style,
                 display,
                 display_fd,
                 //signal_fds,
-                clipboard: String::new(),
+                clipboard: ClipboardData::new(),
+                clipboard_reads: Vec::new(),
+                clipboard_writes: Vec::new(),
                 last_scroll_time: 0.0,
                 last_click_time: 0.0,
                 last_click_pos: (0, 0),
+                last_button_window: None,
                 window_map: HashMap::new(),
                 timers: SelectTimers::new(),
                 event_flow: EventFlow::Poll,
@@ -89,6 +108,32 @@ impl XlibApp {
         }
     }
     
+    // prefer an input method that lets us draw the composition ourselves,
+    // then one that draws it at the spot we give it
+    unsafe fn select_xim_style(xim: x11_sys::XIM) -> x11_sys::XIMStyle {
+        let fallback = (x11_sys::XIMPreeditNothing | x11_sys::XIMStatusNothing) as x11_sys::XIMStyle;
+        if xim == ptr::null_mut() {
+            return fallback
+        }
+        let mut styles: *mut x11_sys::XIMStyles = ptr::null_mut();
+        let result = x11_sys::XGetIMValues(
+            xim,
+            x11_sys::XNQueryInputStyle.as_ptr(),
+            &mut styles as *mut _,
+            ptr::null_mut() as *mut c_void
+        );
+        if result != ptr::null_mut() || styles == ptr::null_mut() {
+            return fallback
+        }
+        let supported = std::slice::from_raw_parts((*styles).supported_styles, (*styles).count_styles as usize);
+        let style = [
+            x11_sys::XIMPreeditCallbacks | x11_sys::XIMStatusNothing,
+            x11_sys::XIMPreeditPosition | x11_sys::XIMStatusNothing,
+        ].iter().map( | style | *style as x11_sys::XIMStyle).find( | style | supported.contains(style));
+        x11_sys::XFree(styles as *mut c_void);
+        style.unwrap_or(fallback)
+    }
+    
     pub unsafe fn event_loop_poll(&mut self) {
         // Update the current time, and compute the amount of time that elapsed since we
         // last recorded the current time.
@@ -96,109 +141,27 @@ impl XlibApp {
             let mut event = mem::MaybeUninit::uninit();
             x11_sys::XNextEvent(self.display, event.as_mut_ptr());
             let mut event = event.assume_init();
+            // the input method eats the keys it uses for composing
+            if x11_sys::XFilterEvent(&mut event, 0) != 0 {
+                continue;
+            }
             match event.type_ as u32 {
                 x11_sys::SelectionNotify => {
                     let selection = event.xselection;
                     if selection.property == self.dnd.atoms.selection {
                         self.dnd.handle_selection_event(&selection);
-                    } else {
-                        // first get the size of the thing
-                        let mut actual_type = mem::MaybeUninit::uninit();
-                        let mut actual_format = mem::MaybeUninit::uninit();
-                        let mut n_items = mem::MaybeUninit::uninit();
-                        let mut bytes_to_read = mem::MaybeUninit::uninit();
-                        let mut ret = mem::MaybeUninit::uninit();
-                        x11_sys::XGetWindowProperty(
-                            self.display,
-                            selection.requestor,
-                            selection.property,
-                            0,
-                            0,
-                            0,
-                            x11_sys::AnyPropertyType as c_ulong,
-                            actual_type.as_mut_ptr(),
-                            actual_format.as_mut_ptr(),
-                            n_items.as_mut_ptr(),
-                            bytes_to_read.as_mut_ptr(),
-                            ret.as_mut_ptr()
-                        );
-                        //let actual_type = actual_type.assume_init();
-                        //let actual_format = actual_format.assume_init();
-                        //let n_items = n_items.assume_init();
-                        let bytes_to_read = bytes_to_read.assume_init();
-                        //let mut ret = ret.assume_init();
-                        let mut bytes_after = mem::MaybeUninit::uninit();
-                        x11_sys::XGetWindowProperty(
-                            self.display,
-                            selection.requestor,
-                            selection.property,
-                            0,
-                            bytes_to_read as c_long,
-                            0,
-                            x11_sys::AnyPropertyType as c_ulong,
-                            actual_type.as_mut_ptr(),
-                            actual_format.as_mut_ptr(),
-                            n_items.as_mut_ptr(),
-                            bytes_after.as_mut_ptr(),
-                            ret.as_mut_ptr()
-                        );
-                        let ret = ret.assume_init();
-                        //let bytes_after = bytes_after.assume_init();
-                        if ret != ptr::null_mut() && bytes_to_read > 0 {
-                            let utf8_slice = std::slice::from_raw_parts::<u8>(ret as *const _ as *const u8, bytes_to_read as usize);
-                            if let Ok(utf8_string) = String::from_utf8(utf8_slice.to_vec()) {
-                                self.do_callback(XlibEvent::TextInput(TextInputEvent {
-                                    input: utf8_string,
-                                    was_paste: true,
-                                    replace_last: false
-                                }));
-                            }
-                            x11_sys::XFree(ret as *mut _ as *mut c_void);
-                        }
+                    }
+                    else if selection.selection == self.atoms.clipboard {
+                        self.handle_clipboard_notify(&selection);
                     }
                 },
                 x11_sys::SelectionRequest => {
                     let request = event.xselectionrequest;
-                    let mut response = x11_sys::XSelectionEvent {
-                        type_: x11_sys::SelectionNotify as i32,
-                        serial: 0,
-                        send_event: 0,
-                        display: self.display,
-                        requestor: request.requestor,
-                        selection: request.selection,
-                        target: request.target,
-                        time: request.time,
-                        property: request.property,
-                    };
-                    if request.target == self.atoms.targets {
-                        let mut targets = [self.atoms.utf8_string];
-                        x11_sys::XChangeProperty(
-                            self.display,
-                            request.requestor,
-                            request.property,
-                            4,
-                            32,
-                            x11_sys::PropModeReplace as i32,
-                            targets.as_mut() as *mut _ as *mut c_uchar,
-                            targets.len() as i32
-                        );
-                    }
-                    else if request.target == self.atoms.utf8_string {
-                        x11_sys::XChangeProperty(
-                            self.display,
-                            request.requestor,
-                            request.property,
-                            self.atoms.utf8_string,
-                            8,
-                            x11_sys::PropModeReplace as i32,
-                            self.clipboard.as_ptr() as *const _ as *const c_uchar,
-                            self.clipboard.len() as i32
-                        );
-                    }
-                    else {
-                        response.property = 0;
+                    if request.selection == self.dnd.atoms.selection {
+                        self.dnd.handle_source_selection_request(&request);
+                        continue;
                     }
-                    x11_sys::XSendEvent(self.display, request.requestor, 1, 0, &mut response as *mut _ as *mut x11_sys::XEvent);
+                    self.handle_clipboard_request(&request);
                 },
                 x11_sys::DestroyNotify => { // our window got destroyed
                     let destroy_window = event.xdestroywindow;
@@ -218,6 +181,36 @@ impl XlibApp {
                         }
                     }
                 },
+                x11_sys::PropertyNotify => {
+                    // fullscreen and topmost changes only show up in the window state
+                    let property = event.xproperty;
+                    if property.state == x11_sys::PropertyNewValue as c_int && property.atom == self.atoms.clipboard_property {
+                        self.handle_clipboard_incr_read(&property);
+                    }
+                    else if property.state == x11_sys::PropertyDelete as c_int {
+                        self.handle_clipboard_incr_write(&property);
+                    }
+                    if property.atom == self.atoms.net_wm_state {
+                        if let Some(window_ptr) = self.window_map.get(&property.window) {
+                            let window = &mut (**window_ptr);
+                            if window.window.is_some() && window.get_window_geom() != window.last_window_geom {
+                                window.send_change_event();
+                            }
+                        }
+                    }
+                },
+                x11_sys::FocusIn => {
+                    if let Some(window_ptr) = self.window_map.get(&event.xfocus.window) {
+                        let window = &mut (**window_ptr);
+                        window.set_ime_focus(true);
+                    }
+                },
+                x11_sys::FocusOut => {
+                    if let Some(window_ptr) = self.window_map.get(&event.xfocus.window) {
+                        let window = &mut (**window_ptr);
+                        window.set_ime_focus(false);
+                    }
+                },
                 x11_sys::EnterNotify => {},
                 x11_sys::LeaveNotify => {
                     let crossing = event.xcrossing;
@@ -244,6 +237,11 @@ impl XlibApp {
                 },
                 x11_sys::MotionNotify => { // mousemove
                     let motion = event.xmotion;
+                    if self.dnd.source.is_some() {
+                        let modifiers = self.xkeystate_to_modifiers(motion.state);
+                        self.drag_source_motion(motion.x_root, motion.y_root, modifiers, motion.time);
+                        continue;
+                    }
                     if let Some(window_ptr) = self.window_map.get(&motion.window) {
                         let window = &mut (**window_ptr);
                         let x = motion.x;
@@ -323,6 +321,11 @@ impl XlibApp {
                 x11_sys::ButtonPress => { // mouse down
                     let button = event.xbutton;
                     let time_now = self.time_now();
+                    self.last_button_window = Some(button.window);
+                    if self.dnd.source.as_ref().map_or(false, | source | source.drop_sent) {
+                        // the drop target never finished, don't hang on to the drag
+                        self.drag_source_end();
+                    }
                     if let Some(window_ptr) = self.window_map.get(&button.window) {
                         let window = &mut (**window_ptr);
                         x11_sys::XSetInputFocus(
@@ -408,6 +411,10 @@ impl XlibApp {
                 },
                 x11_sys::ButtonRelease => { // mouse up
                     let button = event.xbutton;
+                    if self.dnd.source.as_ref().map_or(false, | source | !source.drop_sent) {
+                        self.drag_source_release();
+                        continue;
+                    }
                     if let Some(window_ptr) = self.window_map.get(&button.window) {
                         let window = &mut (**window_ptr);
                         window.send_mouse_up(button.button as usize, self.xkeystate_to_modifiers(button.state))
@@ -424,13 +431,10 @@ impl XlibApp {
                                 match key_code {
                                     KeyCode::KeyV => { // paste
                                         // request the pasteable text from the other side
-                                        x11_sys::XConvertSelection(
-                                            self.display,
-                                            self.atoms.clipboard,
-                                            self.atoms.utf8_string,
-                                            self.atoms.clipboard,
+                                        self.queue_clipboard_read(
+                                            ClipboardReadKind::Paste,
                                             window.window.unwrap(),
-                                            event.xkey.time
+                                            vec![MIME_TEXT_PLAIN.to_string()]
                                         );
                                         /*
                                         self.do_callback(&mut vec![
@@ -449,7 +453,7 @@ impl XlibApp {
                                         }));
                                         let response = response.borrow();
                                         if let Some(response) = response.as_ref() {
-                                            self.copy_to_clipboard(response, &window, &event);
+                                            self.copy_to_clipboard(ClipboardData::from_text(response), window.window.unwrap(), event.xkey.time);
                                         }
                                     }
                                     KeyCode::KeyX => {
@@ -459,7 +463,7 @@ impl XlibApp {
                                         }));
                                         let response = response.borrow();
                                         if let Some(response) = response.as_ref() {
-                                            self.copy_to_clipboard(response, &window, &event);
+                                            self.copy_to_clipboard(ClipboardData::from_text(response), window.window.unwrap(), event.xkey.time);
                                         }
                                     }
                                     _ => ()
@@ -477,29 +481,42 @@ impl XlibApp {
                         }else {false};
                         
                         if !block_text {
-                            // decode the character
-                            let mut buffer = [0u8; 32];
-                            let mut keysym = mem::MaybeUninit::uninit();
-                            let mut status = mem::MaybeUninit::uninit();
-                            let count = x11_sys::Xutf8LookupString(
-                                window.xic.unwrap(),
-                                &mut event.xkey,
-                                buffer.as_mut_ptr() as *mut c_char,
-                                buffer.len() as c_int,
-                                keysym.as_mut_ptr(),
-                                status.as_mut_ptr(),
-                            );
-                            //let keysym = keysym.assume_init();
-                            let status = status.assume_init();
-                            if status != x11_sys::XBufferOverflow {
-                                let utf8 = std::str::from_utf8(&buffer[..count as usize]).unwrap_or("").to_string();
-                                let char_code = utf8.chars().next().unwrap_or('\0');
-                                if char_code >= ' ' && char_code != 127 as char {
-                                    self.do_callback(XlibEvent::TextInput(TextInputEvent {
-                                        input: utf8,
-                                        was_paste: false,
-                                        replace_last: false
-                                    }));
+                            if let Some(xic) = window.xic {
+                                // decode the characters, text committed by an input method can be long
+                                let mut buffer = vec![0u8; 32];
+                                let mut keysym = mem::MaybeUninit::uninit();
+                                let mut status = mem::MaybeUninit::uninit();
+                                let mut count = x11_sys::Xutf8LookupString(
+                                    xic,
+                                    &mut event.xkey,
+                                    buffer.as_mut_ptr() as *mut c_char,
+                                    buffer.len() as c_int,
+                                    keysym.as_mut_ptr(),
+                                    status.as_mut_ptr(),
+                                );
+                                if status.assume_init() == x11_sys::XBufferOverflow {
+                                    buffer.resize(count as usize, 0);
+                                    count = x11_sys::Xutf8LookupString(
+                                        xic,
+                                        &mut event.xkey,
+                                        buffer.as_mut_ptr() as *mut c_char,
+                                        buffer.len() as c_int,
+                                        keysym.as_mut_ptr(),
+                                        status.as_mut_ptr(),
+                                    );
+                                }
+                                //let keysym = keysym.assume_init();
+                                let status = status.assume_init();
+                                if status == x11_sys::XLookupChars || status == x11_sys::XLookupBoth {
+                                    let utf8 = std::str::from_utf8(&buffer[..count as usize]).unwrap_or("").to_string();
+                                    let char_code = utf8.chars().next().unwrap_or('\0');
+                                    if char_code >= ' ' && char_code != 127 as char {
+                                        self.do_callback(XlibEvent::TextInput(TextInputEvent {
+                                            input: utf8,
+                                            was_paste: false,
+                                            replace_last: false
+                                        }));
+                                    }
                                 }
                             }
                         }
@@ -529,6 +546,10 @@ impl XlibApp {
                         self.dnd.handle_leave_event(&event);
                     } else if event.message_type == self.dnd.atoms.position {
                         self.dnd.handle_position_event(&event);
+                    } else if event.message_type == self.dnd.atoms.status {
+                        self.drag_source_status(&event);
+                    } else if event.message_type == self.dnd.atoms.finished {
+                        self.drag_source_finished(&event);
                     }
                 },
                 x11_sys::Expose => {
@@ -567,7 +588,9 @@ impl XlibApp {
                                 })
                             );
                         }
-                        self.timers.select(self.display_fd);
+                        let clipboard_timeout = self.clipboard_read_timeout();
+                        self.timers.select(self.display_fd, clipboard_timeout);
+                        self.expire_clipboard_read();
                         self.event_flow = EventFlow::Poll;
                     }
                     EventFlow::Poll => { 
@@ -581,6 +604,7 @@ impl XlibApp {
                                 })
                             );
                         }
+                        self.expire_clipboard_read();
                         self.event_loop_poll();
                     }
                 }
@@ -600,11 +624,195 @@ impl XlibApp {
     
     pub fn terminate_event_loop(&mut self) {
         self.event_loop_running = false;
-        unsafe {x11_sys::XCloseIM(self.xim)};
+        if self.xim != ptr::null_mut() {
+            unsafe {x11_sys::XCloseIM(self.xim)};
+        }
         unsafe {x11_sys::XCloseDisplay(self.display)};
         self.display = ptr::null_mut();
     }
     
+    pub fn start_dragging(&mut self, items: Vec<DragItem>) {
+        // drags start while a button is held down, and the implicit pointer grab of
+        // that button keeps sending us motion events when the pointer leaves our windows
+        if let Some(window) = self.last_button_window {
+            unsafe {
+                self.dnd.start_source(window, items, x11_sys::CurrentTime as x11_sys::Time);
+            }
+        }
+    }
+    
+    unsafe fn drag_source_motion(&mut self, root_x: c_int, root_y: c_int, modifiers: KeyModifiers, time: x11_sys::Time) {
+        let target = self.dnd.find_target(root_x, root_y);
+        let new_target = target.map( | (window, _) | if self.window_map.contains_key(&window) {
+            DndTarget::Internal(window)
+        } else {
+            DndTarget::External(window)
+        });
+        let old_target = if let Some(source) = self.dnd.source.as_mut() {
+            source.root_pos = (root_x, root_y);
+            source.time = time;
+            source.modifiers = modifiers;
+            source.target
+        } else {
+            return
+        };
+        if old_target != new_target {
+            match old_target {
+                Some(DndTarget::External(window)) => self.dnd.send_leave_event(window),
+                // drag out of all widgets of the window we left
+                Some(DndTarget::Internal(window)) => {self.send_internal_drag(window, None);},
+                None => ()
+            }
+            if let (Some(DndTarget::External(window)), Some((_, version))) = (new_target, target) {
+                self.dnd.send_enter_event(window, version);
+            }
+            if let Some(source) = self.dnd.source.as_mut() {
+                source.target = new_target;
+                source.response = DragResponse::None;
+                source.status_pending = false;
+                source.position_pending = false;
+            }
+        }
+        match new_target {
+            Some(DndTarget::Internal(window)) => {
+                let response = self.send_internal_drag(window, Some((root_x, root_y)));
+                if let Some(source) = self.dnd.source.as_mut() {
+                    source.response = response;
+                }
+            }
+            Some(DndTarget::External(window)) => {
+                if let Some(source) = self.dnd.source.as_mut() {
+                    if source.status_pending {
+                        source.position_pending = true;
+                    }
+                    else {
+                        source.status_pending = true;
+                        self.dnd.send_position_event(window);
+                    }
+                }
+            }
+            None => ()
+        }
+        self.update_drag_cursor();
+    }
+    
+    unsafe fn send_internal_drag(&mut self, window: c_ulong, root_pos: Option<(c_int, c_int)>) -> DragResponse {
+        let (items, modifiers) = match &self.dnd.source {
+            Some(source) => (source.items.clone(), source.modifiers),
+            None => return DragResponse::None
+        };
+        if let Some(window_ptr) = self.window_map.get(&window) {
+            let window = &mut (**window_ptr);
+            let abs = if let Some((root_x, root_y)) = root_pos {
+                window.root_to_window_pos(root_x, root_y)
+            }
+            else {
+                DVec2 {x: -100000.0, y: -100000.0}
+            };
+            let response = Rc::new(Cell::new(DragResponse::None));
+            window.do_callback(XlibEvent::Drag(DragEvent {
+                modifiers,
+                handled: Cell::new(false),
+                abs,
+                items,
+                response: response.clone()
+            }));
+            return response.get()
+        }
+        DragResponse::None
+    }
+    
+    unsafe fn drag_source_release(&mut self) {
+        let (target, response, items, modifiers, (root_x, root_y)) = match &self.dnd.source {
+            Some(source) => (source.target, source.response, source.items.clone(), source.modifiers, source.root_pos),
+            None => return
+        };
+        match target {
+            Some(DndTarget::Internal(window)) => {
+                if response != DragResponse::None {
+                    if let Some(window_ptr) = self.window_map.get(&window) {
+                        let window = &mut (**window_ptr);
+                        let abs = window.root_to_window_pos(root_x, root_y);
+                        window.do_callback(XlibEvent::Drop(DropEvent {
+                            modifiers,
+                            handled: Cell::new(false),
+                            abs,
+                            items,
+                        }));
+                    }
+                }
+                self.drag_source_end();
+            }
+            Some(DndTarget::External(window)) => {
+                if response != DragResponse::None {
+                    // the drag ends when the target sends XdndFinished
+                    self.dnd.send_drop_event(window);
+                    if let Some(source) = self.dnd.source.as_mut() {
+                        source.drop_sent = true;
+                    }
+                }
+                else {
+                    self.dnd.send_leave_event(window);
+                    self.drag_source_end();
+                }
+            }
+            None => self.drag_source_end()
+        }
+    }
+    
+    unsafe fn drag_source_status(&mut self, event: &x11_sys::XClientMessageEvent) {
+        let accepted = event.data.l[1] & (1 << 0) != 0;
+        let response = if accepted {
+            self.dnd.response_for_action(event.data.l[4] as x11_sys::Atom)
+        }
+        else {
+            DragResponse::None
+        };
+        let window = match self.dnd.source.as_mut() {
+            Some(source) => match source.target {
+                Some(DndTarget::External(window)) if window == event.data.l[0] as x11_sys::Window => {
+                    source.response = response;
+                    source.status_pending = false;
+                    if source.position_pending {
+                        source.position_pending = false;
+                        source.status_pending = true;
+                        Some(window)
+                    }
+                    else {
+                        None
+                    }
+                }
+                _ => return
+            }
+            None => return
+        };
+        if let Some(window) = window {
+            self.dnd.send_position_event(window);
+        }
+        self.update_drag_cursor();
+    }
+    
+    unsafe fn drag_source_finished(&mut self, event: &x11_sys::XClientMessageEvent) {
+        if let Some(source) = &self.dnd.source {
+            if source.drop_sent && source.target == Some(DndTarget::External(event.data.l[0] as x11_sys::Window)) {
+                self.drag_source_end();
+            }
+        }
+    }
+    
+    fn drag_source_end(&mut self) {
+        self.dnd.source = None;
+        self.set_mouse_cursor(MouseCursor::Default);
+        self.do_callback(XlibEvent::DragEnd);
+    }
+    
+    fn update_drag_cursor(&mut self) {
+        if let Some(source) = &self.dnd.source {
+            let cursor = if source.response == DragResponse::None {MouseCursor::NotAllowed} else {MouseCursor::Default};
+            self.set_mouse_cursor(cursor);
+        }
+    }
+    
     pub fn start_timer(&mut self, id: u64, timeout: f64, repeats: bool) {
         self.timers.start_timer(id, timeout, repeats);
     }
@@ -834,18 +1042,394 @@ impl XlibApp {
         }
     }
 
-    unsafe fn copy_to_clipboard(&mut self, text: &String, window: &XlibWindow, event: &XEvent) {
-        // store the text on the clipboard
-        self.clipboard = text.clone();
+    pub unsafe fn copy_to_clipboard(&mut self, data: ClipboardData, window: c_ulong, time: x11_sys::Time) {
+        // store the data on the clipboard
+        self.clipboard = data;
         // lets set the owner
         x11_sys::XSetSelectionOwner(
             self.display,
             self.atoms.clipboard,
-            window.window.unwrap(),
-            event.xkey.time
+            window,
+            time
         );
         x11_sys::XFlush(self.display);
     }
+
+    pub unsafe fn set_clipboard(&mut self, data: ClipboardData) {
+        // one of our windows has to own the selection
+        if let Some(window) = self.window_map.keys().next().cloned() {
+            self.copy_to_clipboard(data, window, x11_sys::CurrentTime as x11_sys::Time);
+        }
+    }
+
+    // returns false when there is no window to receive the data
+    pub unsafe fn read_clipboard(&mut self, request_id: LiveId, mime_types: Vec<String>) -> bool {
+        // the selection owner sends the data to one of our windows
+        if let Some(window) = self.window_map.keys().next().cloned() {
+            self.queue_clipboard_read(ClipboardReadKind::Request(request_id), window, mime_types);
+            return true
+        }
+        false
+    }
+
+    // how long the event loop can wait before the running clipboard read times out
+    fn clipboard_read_timeout(&self) -> Option<f64> {
+        let read = self.clipboard_reads.first() ?;
+        Some((read.start_time + CLIPBOARD_READ_TIMEOUT - self.time_now()).max(0.0))
+    }
+
+    // an owner that never answers would block all the reads after it, so a read
+    // that runs out of time finishes with the formats that did arrive
+    unsafe fn expire_clipboard_read(&mut self) {
+        if self.clipboard_read_timeout() == Some(0.0) {
+            if let Some(targets) = &mut self.clipboard_reads[0].targets {
+                targets.clear();
+            }
+            else {
+                self.clipboard_reads[0].targets = Some(Vec::new());
+            }
+            self.clipboard_reads[0].current = None;
+            self.clipboard_reads[0].incr = None;
+            self.next_clipboard_format();
+        }
+    }
+
+    unsafe fn queue_clipboard_read(&mut self, kind: ClipboardReadKind, window: c_ulong, mime_types: Vec<String>) {
+        let time_now = self.time_now();
+        self.clipboard_reads.push(ClipboardRead {
+            kind,
+            window,
+            mime_types,
+            targets: None,
+            current: None,
+            incr: None,
+            data: ClipboardData::new(),
+            start_time: time_now,
+            started: false,
+        });
+        self.start_clipboard_read();
+    }
+
+    unsafe fn start_clipboard_read(&mut self) {
+        let time_now = self.time_now();
+        if let Some(read) = self.clipboard_reads.first_mut() {
+            if read.started {
+                return
+            }
+            read.started = true;
+            read.start_time = time_now;
+            // first ask which formats the owner has, this also works when we own it ourselves
+            x11_sys::XConvertSelection(
+                self.display,
+                self.atoms.clipboard,
+                self.atoms.targets,
+                self.atoms.clipboard_property,
+                read.window,
+                x11_sys::CurrentTime as x11_sys::Time
+            );
+            x11_sys::XFlush(self.display);
+        }
+    }
+
+    unsafe fn handle_clipboard_notify(&mut self, selection: &x11_sys::XSelectionEvent) {
+        if !self.clipboard_reads.first().map_or(false, | read | read.window == selection.requestor) {
+            return
+        }
+        if selection.property == 0 {
+            // the owner refused the conversion, when it doesn't know TARGETS we just try them all
+            let read = &self.clipboard_reads[0];
+            if read.targets.is_none() {
+                let targets = read.mime_types.iter().filter_map( | mime_type | {
+                    Some((mime_type.clone(), *self.clipboard_atoms_for_mime(mime_type).first() ?))
+                }).collect();
+                self.clipboard_reads[0].targets = Some(targets);
+            }
+            self.clipboard_reads[0].current = None;
+            self.next_clipboard_format();
+            return
+        }
+        let (actual_type, format, data) = self.get_property(selection.requestor, selection.property);
+        if actual_type == self.atoms.incr {
+            // the data is too big for one property, it comes in chunks as PropertyNotify events
+            self.clipboard_reads[0].incr = Some(Vec::new());
+            return
+        }
+        self.receive_clipboard_format(format, data);
+    }
+
+    unsafe fn handle_clipboard_incr_read(&mut self, property: &x11_sys::XPropertyEvent) {
+        if !self.clipboard_reads.first().map_or(false, | read | read.window == property.window && read.incr.is_some()) {
+            return
+        }
+        let (_, format, data) = self.get_property(property.window, property.atom);
+        // a zero length chunk ends the transfer
+        if data.len() > 0 {
+            self.clipboard_reads[0].incr.as_mut().unwrap().extend_from_slice(&data);
+        }
+        else {
+            let data = self.clipboard_reads[0].incr.take().unwrap();
+            self.receive_clipboard_format(format, data);
+        }
+    }
+
+    unsafe fn receive_clipboard_format(&mut self, format: c_int, data: Vec<u8>) {
+        if self.clipboard_reads[0].targets.is_none() {
+            // the list of atoms the owner can convert to
+            let available = if format == 32 {
+                // the bytes come in a Vec<u8>, so they can't be read in place as atoms
+                data.chunks_exact(mem::size_of::<x11_sys::Atom>()).map( | atom | {
+                    x11_sys::Atom::from_ne_bytes(atom.try_into().unwrap())
+                }).collect()
+            }
+            else {
+                Vec::new()
+            };
+            let mut targets = Vec::new();
+            for mime_type in &self.clipboard_reads[0].mime_types {
+                if let Some(atom) = self.clipboard_atoms_for_mime(mime_type).into_iter().find( | atom | available.contains(atom)) {
+                    targets.push((mime_type.clone(), atom));
+                }
+            }
+            self.clipboard_reads[0].targets = Some(targets);
+        }
+        else if let Some(mime_type) = self.clipboard_reads[0].current.take() {
+            self.clipboard_reads[0].data.set_format(&mime_type, data);
+        }
+        self.next_clipboard_format();
+    }
+
+    unsafe fn next_clipboard_format(&mut self) {
+        let read = &mut self.clipboard_reads[0];
+        let targets = read.targets.as_mut().unwrap();
+        if targets.len() > 0 {
+            let (mime_type, atom) = targets.remove(0);
+            read.current = Some(mime_type);
+            x11_sys::XConvertSelection(
+                self.display,
+                self.atoms.clipboard,
+                atom,
+                self.atoms.clipboard_property,
+                read.window,
+                x11_sys::CurrentTime as x11_sys::Time
+            );
+            x11_sys::XFlush(self.display);
+            return
+        }
+        let read = self.clipboard_reads.remove(0);
+        match read.kind {
+            ClipboardReadKind::Paste => if let Some(text) = read.data.text() {
+                self.do_callback(XlibEvent::TextInput(TextInputEvent {
+                    input: text,
+                    was_paste: true,
+                    replace_last: false
+                }));
+            }
+            ClipboardReadKind::Request(request_id) => {
+                self.do_callback(XlibEvent::Clipboard(ClipboardEvent {
+                    request_id,
+                    data: read.data
+                }));
+            }
+        }
+        self.start_clipboard_read();
+    }
+
+    unsafe fn handle_clipboard_request(&mut self, request: &x11_sys::XSelectionRequestEvent) {
+        // obsolete clients leave the property empty and expect the target to be used
+        let property = if request.property == 0 {request.target} else {request.property};
+        let mut response = x11_sys::XSelectionEvent {
+            type_: x11_sys::SelectionNotify as i32,
+            serial: 0,
+            send_event: 0,
+            display: self.display,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            time: request.time,
+            property,
+        };
+        if request.target == self.atoms.targets {
+            let mut targets = vec![self.atoms.targets];
+            for mime_type in self.clipboard.mime_types() {
+                targets.extend(self.clipboard_atoms_for_mime(mime_type));
+            }
+            x11_sys::XChangeProperty(
+                self.display,
+                request.requestor,
+                property,
+                x11_sys::XA_ATOM as x11_sys::Atom,
+                32,
+                x11_sys::PropModeReplace as i32,
+                targets.as_ptr() as *const c_uchar,
+                targets.len() as i32
+            );
+        }
+        else if let Some(data) = self.clipboard_data_for_target(request.target) {
+            if data.len() > self.max_property_size() {
+                // announce the size and send the chunks when the requestor deletes the property
+                if !self.window_map.contains_key(&request.requestor) {
+                    x11_sys::XSelectInput(self.display, request.requestor, x11_sys::PropertyChangeMask as c_long);
+                }
+                let size = [data.len() as c_ulong];
+                x11_sys::XChangeProperty(
+                    self.display,
+                    request.requestor,
+                    property,
+                    self.atoms.incr,
+                    32,
+                    x11_sys::PropModeReplace as i32,
+                    size.as_ptr() as *const c_uchar,
+                    1
+                );
+                self.clipboard_writes.retain( | write | write.requestor != request.requestor || write.property != property);
+                self.clipboard_writes.push(ClipboardIncrWrite {
+                    requestor: request.requestor,
+                    property,
+                    target: request.target,
+                    data,
+                    offset: 0,
+                });
+            }
+            else {
+                x11_sys::XChangeProperty(
+                    self.display,
+                    request.requestor,
+                    property,
+                    request.target,
+                    8,
+                    x11_sys::PropModeReplace as i32,
+                    data.as_ptr(),
+                    data.len() as i32
+                );
+            }
+        }
+        else {
+            response.property = 0;
+        }
+        x11_sys::XSendEvent(self.display, request.requestor, 1, 0, &mut response as *mut _ as *mut x11_sys::XEvent);
+        x11_sys::XFlush(self.display);
+    }
+
+    unsafe fn handle_clipboard_incr_write(&mut self, property: &x11_sys::XPropertyEvent) {
+        let chunk_size = self.max_property_size();
+        let display = self.display;
+        if let Some(index) = self.clipboard_writes.iter().position( | write | write.requestor == property.window && write.property == property.atom) {
+            let write = &mut self.clipboard_writes[index];
+            let end = (write.offset + chunk_size).min(write.data.len());
+            let chunk = &write.data[write.offset..end];
+            x11_sys::XChangeProperty(
+                display,
+                write.requestor,
+                write.property,
+                write.target,
+                8,
+                x11_sys::PropModeReplace as i32,
+                chunk.as_ptr(),
+                chunk.len() as i32
+            );
+            // the zero length chunk we just sent ends the transfer
+            if chunk.len() == 0 {
+                self.clipboard_writes.remove(index);
+            }
+            else {
+                write.offset = end;
+            }
+            x11_sys::XFlush(display);
+        }
+    }
+
+    unsafe fn clipboard_atoms_for_mime(&self, mime_type: &str) -> Vec<x11_sys::Atom> {
+        if mime_type == MIME_TEXT_PLAIN {
+            return vec![self.atoms.utf8_string, self.atoms.text_plain_utf8, self.atoms.text_plain]
+        }
+        // a mime type with a NUL in it can't be an atom name, it has no targets
+        match CString::new(mime_type) {
+            Ok(name) => vec![x11_sys::XInternAtom(self.display, name.as_ptr(), 0)],
+            Err(_) => Vec::new()
+        }
+    }
+
+    unsafe fn clipboard_data_for_target(&self, target: x11_sys::Atom) -> Option<Rc<Vec<u8>>> {
+        if self.clipboard_atoms_for_mime(MIME_TEXT_PLAIN).contains(&target) {
+            return self.clipboard.get(MIME_TEXT_PLAIN).cloned()
+        }
+        let name = x11_sys::XGetAtomName(self.display, target);
+        if name == ptr::null_mut() {
+            return None
+        }
+        let mime_type = CStr::from_ptr(name).to_string_lossy().into_owned();
+        x11_sys::XFree(name as *mut c_void);
+        self.clipboard.get(&mime_type).cloned()
+    }
+
+    unsafe fn max_property_size(&self) -> usize {
+        // leave room for the request header
+        (x11_sys::XMaxRequestSize(self.display) as usize * 4).saturating_sub(100)
+    }
+
+    // reads and deletes a window property, 32 bit items come back as longs
+    unsafe fn get_property(&self, window: c_ulong, property: x11_sys::Atom) -> (x11_sys::Atom, c_int, Vec<u8>) {
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let mut n_items = 0;
+        let mut bytes_after = 0;
+        let mut ret = ptr::null_mut();
+        x11_sys::XGetWindowProperty(
+            self.display,
+            window,
+            property,
+            0,
+            i32::MAX as c_long / 4,
+            x11_sys::True as c_int,
+            x11_sys::AnyPropertyType as c_ulong,
+            &mut actual_type,
+            &mut actual_format,
+            &mut n_items,
+            &mut bytes_after,
+            &mut ret
+        );
+        let mut data = Vec::new();
+        if ret != ptr::null_mut() {
+            let item_size = match actual_format {
+                32 => mem::size_of::<c_long>(),
+                16 => 2,
+                _ => 1
+            };
+            data.extend_from_slice(std::slice::from_raw_parts(ret as *const u8, n_items as usize * item_size));
+            x11_sys::XFree(ret as *mut c_void);
+        }
+        (actual_type, actual_format, data)
+    }
+}
+
+const CLIPBOARD_READ_TIMEOUT: f64 = 2.0;
+
+enum ClipboardReadKind {
+    Paste,
+    Request(LiveId),
+}
+
+// a read of the clipboard selection, the formats are converted one at a time
+struct ClipboardRead {
+    kind: ClipboardReadKind,
+    window: c_ulong,
+    mime_types: Vec<String>,
+    // the formats left to convert, None while waiting for the TARGETS
+    targets: Option<Vec<(String, x11_sys::Atom)>>,
+    current: Option<String>,
+    incr: Option<Vec<u8>>,
+    data: ClipboardData,
+    start_time: f64,
+    started: bool,
+}
+
+// a clipboard format that is too big for one property and is sent in chunks
+struct ClipboardIncrWrite {
+    requestor: c_ulong,
+    property: x11_sys::Atom,
+    target: x11_sys::Atom,
+    data: Rc<Vec<u8>>,
+    offset: usize,
 }
 
 pub struct XlibAtoms {
@@ -857,7 +1441,12 @@ pub struct XlibAtoms {
     pub net_wm_state: x11_sys::Atom,
     pub new_wm_state_maximized_horz: x11_sys::Atom,
     pub new_wm_state_maximized_vert: x11_sys::Atom,
+    pub net_wm_state_fullscreen: x11_sys::Atom,
+    pub net_wm_state_above: x11_sys::Atom,
     pub targets: x11_sys::Atom,
+    pub incr: x11_sys::Atom,
+    pub clipboard_property: x11_sys::Atom,
+    pub text_plain_utf8: x11_sys::Atom,
     pub utf8_string: x11_sys::Atom,
     pub text: x11_sys::Atom,
     pub multiple: x11_sys::Atom,
@@ -868,7 +1457,7 @@ pub struct XlibAtoms {
 impl XlibAtoms {
     fn new(display: *mut x11_sys::Display) -> Self {
         unsafe {Self {
-            clipboard: x11_sys::XInternAtom(display, "CLIPBOARD\n".as_ptr() as *const _, 0),
+            clipboard: x11_sys::XInternAtom(display, "CLIPBOARD\0".as_ptr() as *const _, 0),
             net_wm_moveresize: x11_sys::XInternAtom(display, "_NET_WM_MOVERESIZE\0".as_ptr() as *const _, 0),
             wm_delete_window: x11_sys::XInternAtom(display, "WM_DELETE_WINDOW\0".as_ptr() as *const _, 0),
             wm_protocols: x11_sys::XInternAtom(display, "WM_PROTOCOLS\0".as_ptr() as *const _, 0),
@@ -876,7 +1465,12 @@ impl XlibAtoms {
             net_wm_state: x11_sys::XInternAtom(display, "_NET_WM_STATE\0".as_ptr() as *const _, 0),
             new_wm_state_maximized_horz: x11_sys::XInternAtom(display, "_NET_WM_STATE_MAXIMIZED_HORZ\0".as_ptr() as *const _, 0),
             new_wm_state_maximized_vert: x11_sys::XInternAtom(display, "_NET_WM_STATE_MAXIMIZED_VERT\0".as_ptr() as *const _, 0),
+            net_wm_state_fullscreen: x11_sys::XInternAtom(display, "_NET_WM_STATE_FULLSCREEN\0".as_ptr() as *const _, 0),
+            net_wm_state_above: x11_sys::XInternAtom(display, "_NET_WM_STATE_ABOVE\0".as_ptr() as *const _, 0),
             targets: x11_sys::XInternAtom(display, "TARGETS\0".as_ptr() as *const _, 0),
+            incr: x11_sys::XInternAtom(display, "INCR\0".as_ptr() as *const _, 0),
+            clipboard_property: x11_sys::XInternAtom(display, "MAKEPAD_CLIPBOARD\0".as_ptr() as *const _, 0),
+            text_plain_utf8: x11_sys::XInternAtom(display, "text/plain;charset=utf-8\0".as_ptr() as *const _, 0),
             utf8_string: x11_sys::XInternAtom(display, "UTF8_STRING\0".as_ptr() as *const _, 1),
             atom: x11_sys::XInternAtom(display, "ATOM\0".as_ptr() as *const _, 0),
             text: x11_sys::XInternAtom(display, "TEXT\0".as_ptr() as *const _, 0),
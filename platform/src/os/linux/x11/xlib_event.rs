@@ -10,10 +10,12 @@ use {
             WindowCloseRequestedEvent,
             WindowClosedEvent,
             TextInputEvent,
+            TextPreeditEvent,
             KeyEvent,
             DragEvent,
             DropEvent,
             TextClipboardEvent,
+            ClipboardEvent,
             TimerEvent,
         },
     }
@@ -35,6 +37,7 @@ pub enum XlibEvent {
     WindowDragQuery(WindowDragQueryEvent),
     WindowCloseRequested(WindowCloseRequestedEvent),
     TextInput(TextInputEvent),
+    TextPreedit(TextPreeditEvent),
     Drag(DragEvent),
     Drop(DropEvent),
     DragEnd,
@@ -42,5 +45,6 @@ pub enum XlibEvent {
     KeyUp(KeyEvent),
     TextCopy(TextClipboardEvent),
     TextCut(TextClipboardEvent),
+    Clipboard(ClipboardEvent),
     Timer(TimerEvent),
 }
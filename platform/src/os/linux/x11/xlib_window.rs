@@ -3,7 +3,7 @@ use {
         mem,
         cell::Cell,
         rc::Rc,
-        os::raw::{c_ulong, c_long, c_void, c_char},
+        os::raw::{c_ulong, c_long, c_void, c_char, c_int, c_short},
         ptr,
         ffi::{CStr,CString}, 
     },
@@ -17,6 +17,7 @@ use {
         window::WindowId,
         makepad_math::{DVec2},
         event::*,
+        event::clipboard::encode_uri_path,
         cursor::MouseCursor,
     },
 };
@@ -34,6 +35,8 @@ pub struct XlibWindow {
     pub last_window_geom: WindowGeom,
     
     pub ime_spot: DVec2,
+    pub ime_preedit: Vec<char>,
+    pub ime_caret: usize,
     pub current_cursor: MouseCursor,
     pub last_mouse_pos: DVec2,
 }
@@ -62,6 +65,8 @@ impl XlibWindow {
             last_window_geom: WindowGeom::default(),
             last_nc_mode: None,
             ime_spot: DVec2::default(),
+            ime_preedit: Vec::new(),
+            ime_caret: 0,
             current_cursor: MouseCursor::Default,
             last_mouse_pos: DVec2::default(),
         }
@@ -96,6 +101,7 @@ impl XlibWindow {
                     | x11_sys::FocusChangeMask
                     | x11_sys::EnterWindowMask
                     | x11_sys::LeaveWindowMask
+                    | x11_sys::PropertyChangeMask
             ) as c_long;
             
             let dpi_factor = self.get_dpi_factor();
@@ -150,16 +156,7 @@ impl XlibWindow {
             let title_bytes = format!("{}\0", title);
             x11_sys::XStoreName(display, window, title_bytes.as_bytes().as_ptr() as *const c_char);
             
-            let xic = x11_sys::XCreateIC(
-                get_xlib_app_global().xim,
-                x11_sys::XNInputStyle.as_ptr(),
-                (x11_sys::XIMPreeditNothing | x11_sys::XIMStatusNothing) as i32,
-                x11_sys::XNClientWindow.as_ptr(),
-                window,
-                x11_sys::XNFocusWindow.as_ptr(),
-                window,
-                ptr::null_mut() as *mut c_void
-            );
+            let xic = self.create_xic(window);
             
             // Create a window
             get_xlib_app_global().window_map.insert(window, self);
@@ -167,7 +164,7 @@ impl XlibWindow {
             self.attributes = Some(attributes);
             self.visual_info = Some(visual_info);
             self.window = Some(window);
-            self.xic = Some(xic);
+            self.xic = xic;
             self.last_window_geom = self.get_window_geom();
             
             let new_geom = self.get_window_geom();
@@ -180,7 +177,7 @@ impl XlibWindow {
         }
     }
     
-    fn restore_or_maximize(&self, add_remove: c_long) {
+    fn change_wm_state(&self, add_remove: c_long, state1: x11_sys::Atom, state2: x11_sys::Atom) {
         unsafe {
             let default_screen = x11_sys::XDefaultScreen(get_xlib_app_global().display);
             let root_window = x11_sys::XRootWindow(get_xlib_app_global().display, default_screen);
@@ -195,8 +192,8 @@ impl XlibWindow {
                 data: {
                     let mut msg = mem::zeroed::<x11_sys::XClientMessageEvent__bindgen_ty_1>();
                     msg.l[0] = add_remove;
-                    msg.l[1] = get_xlib_app_global().atoms.new_wm_state_maximized_horz as c_long;
-                    msg.l[2] = get_xlib_app_global().atoms.new_wm_state_maximized_vert as c_long;
+                    msg.l[1] = state1 as c_long;
+                    msg.l[2] = state2 as c_long;
                     msg
                 }
             };
@@ -207,9 +204,15 @@ impl XlibWindow {
                 (x11_sys::SubstructureNotifyMask | x11_sys::SubstructureRedirectMask) as c_long,
                 &mut xclient as *mut _ as *mut x11_sys::XEvent
             );
+            x11_sys::XFlush(get_xlib_app_global().display);
         }
     }
     
+    fn restore_or_maximize(&self, add_remove: c_long) {
+        let atoms = &get_xlib_app_global().atoms;
+        self.change_wm_state(add_remove, atoms.new_wm_state_maximized_horz, atoms.new_wm_state_maximized_vert);
+    }
+    
     pub fn restore(&self) {
         self.restore_or_maximize(_NET_WM_STATE_REMOVE);
     }
@@ -218,8 +221,19 @@ impl XlibWindow {
         self.restore_or_maximize(_NET_WM_STATE_ADD);
     }
     
+    pub fn fullscreen(&self) {
+        self.change_wm_state(_NET_WM_STATE_ADD, get_xlib_app_global().atoms.net_wm_state_fullscreen, 0);
+    }
+    
+    pub fn normalize(&self) {
+        self.change_wm_state(_NET_WM_STATE_REMOVE, get_xlib_app_global().atoms.net_wm_state_fullscreen, 0);
+    }
+    
     pub fn close_window(&mut self) {
         unsafe {
+            if let Some(xic) = self.xic.take() {
+                x11_sys::XDestroyIC(xic);
+            }
             x11_sys::XDestroyWindow(get_xlib_app_global().display, self.window.unwrap());
             self.window = None;
             // lets remove us from the mapping
@@ -235,19 +249,26 @@ impl XlibWindow {
         }
     }
     
-    pub fn set_topmost(&self, _topmost: bool) {
+    pub fn set_topmost(&self, topmost: bool) {
+        let add_remove = if topmost {_NET_WM_STATE_ADD} else {_NET_WM_STATE_REMOVE};
+        self.change_wm_state(add_remove, get_xlib_app_global().atoms.net_wm_state_above, 0);
     }
     
     pub fn get_is_topmost(&self) -> bool {
-        false
+        self.get_wm_state().contains(&get_xlib_app_global().atoms.net_wm_state_above)
+    }
+    
+    pub fn get_is_fullscreen(&self) -> bool {
+        self.get_wm_state().contains(&get_xlib_app_global().atoms.net_wm_state_fullscreen)
     }
     
     pub fn get_window_geom(&self) -> WindowGeom {
         WindowGeom {
             xr_is_presenting: false,
-            can_fullscreen: false,
+            can_fullscreen: true,
             is_topmost: self.get_is_topmost(),
-            is_fullscreen: self.get_is_maximized(),
+            is_fullscreen: self.get_is_fullscreen(),
+            is_maximized: self.get_is_maximized(),
             inner_size: self.get_inner_size(),
             outer_size: self.get_outer_size(),
             dpi_factor: self.get_dpi_factor(),
@@ -256,7 +277,15 @@ impl XlibWindow {
     }
     
     pub fn get_is_maximized(&self) -> bool {
-        let mut maximized = false;
+        let atoms = &get_xlib_app_global().atoms;
+        self.get_wm_state().iter().any( | state | {
+            *state == atoms.new_wm_state_maximized_horz || *state == atoms.new_wm_state_maximized_vert
+        })
+    }
+    
+    // the _NET_WM_STATE atoms the window manager has set on the window
+    fn get_wm_state(&self) -> Vec<x11_sys::Atom> {
+        let mut state = Vec::new();
         unsafe {
             let mut prop_type = mem::MaybeUninit::uninit();
             let mut format = mem::MaybeUninit::uninit();
@@ -284,21 +313,166 @@ impl XlibWindow {
             let properties = properties.assume_init();
             if result == 0 && properties != ptr::null_mut() {
                 let items = std::slice::from_raw_parts::<c_ulong>(properties as *mut _, n_item as usize);
-                for item in items {
-                    if *item == get_xlib_app_global().atoms.new_wm_state_maximized_horz
-                        || *item == get_xlib_app_global().atoms.new_wm_state_maximized_vert {
-                        maximized = true;
-                        break;
-                    }
-                }
+                state.extend_from_slice(items);
                 x11_sys::XFree(properties as *mut _);
             }
         }
-        maximized
+        state
     }
     
+    unsafe fn create_xic(&mut self, window: c_ulong) -> Option<x11_sys::XIC> {
+        let xim = get_xlib_app_global().xim;
+        let xim_style = get_xlib_app_global().xim_style;
+        if xim == ptr::null_mut() {
+            return None
+        }
+        // with preedit callbacks the composition is sent to us as TextPreedit events
+        let client_data = self as *mut _ as x11_sys::XPointer;
+        let start = x11_sys::XICCallback {client_data, callback: Some(xim_preedit_start)};
+        let done = x11_sys::XIMCallback {client_data, callback: Some(xim_preedit_done)};
+        let draw = x11_sys::XIMCallback {client_data, callback: Some(xim_preedit_draw)};
+        let caret = x11_sys::XIMCallback {client_data, callback: Some(xim_preedit_caret)};
+        let spot = x11_sys::XPoint {x: 0, y: 0};
+        let preedit_attributes = if xim_style & x11_sys::XIMPreeditCallbacks as x11_sys::XIMStyle != 0 {
+            x11_sys::XVaCreateNestedList(
+                0,
+                x11_sys::XNPreeditStartCallback.as_ptr(),
+                &start,
+                x11_sys::XNPreeditDoneCallback.as_ptr(),
+                &done,
+                x11_sys::XNPreeditDrawCallback.as_ptr(),
+                &draw,
+                x11_sys::XNPreeditCaretCallback.as_ptr(),
+                &caret,
+                ptr::null_mut() as *mut c_void
+            )
+        }
+        else if xim_style & x11_sys::XIMPreeditPosition as x11_sys::XIMStyle != 0 {
+            x11_sys::XVaCreateNestedList(
+                0,
+                x11_sys::XNSpotLocation.as_ptr(),
+                &spot,
+                ptr::null_mut() as *mut c_void
+            )
+        }
+        else {
+            ptr::null_mut()
+        };
+        let xic = if preedit_attributes != ptr::null_mut() {
+            let xic = x11_sys::XCreateIC(
+                xim,
+                x11_sys::XNInputStyle.as_ptr(),
+                xim_style,
+                x11_sys::XNClientWindow.as_ptr(),
+                window,
+                x11_sys::XNFocusWindow.as_ptr(),
+                window,
+                x11_sys::XNPreeditAttributes.as_ptr(),
+                preedit_attributes,
+                ptr::null_mut() as *mut c_void
+            );
+            x11_sys::XFree(preedit_attributes);
+            xic
+        }
+        else {
+            x11_sys::XCreateIC(
+                xim,
+                x11_sys::XNInputStyle.as_ptr(),
+                xim_style,
+                x11_sys::XNClientWindow.as_ptr(),
+                window,
+                x11_sys::XNFocusWindow.as_ptr(),
+                window,
+                ptr::null_mut() as *mut c_void
+            )
+        };
+        if xic == ptr::null_mut() {None} else {Some(xic)}
+    }
+    
+    pub fn set_ime_focus(&mut self, focus: bool) {
+        if let Some(xic) = self.xic {
+            unsafe {
+                if focus {
+                    x11_sys::XSetICFocus(xic);
+                }
+                else {
+                    x11_sys::XUnsetICFocus(xic);
+                }
+            }
+        }
+    }
+    
+    // moves the candidate window of the input method, spot is in logical window coordinates
     pub fn set_ime_spot(&mut self, spot: DVec2) {
+        if self.ime_spot == spot {
+            return
+        }
         self.ime_spot = spot;
+        // with preedit callbacks we draw the composition ourselves, the spot only
+        // applies when the input method draws it
+        let xim_style = get_xlib_app_global().xim_style;
+        if xim_style & x11_sys::XIMPreeditPosition as x11_sys::XIMStyle == 0 {
+            return
+        }
+        if let Some(xic) = self.xic {
+            let dpi_factor = self.last_window_geom.dpi_factor;
+            let spot = x11_sys::XPoint {
+                x: (spot.x * dpi_factor) as c_short,
+                y: (spot.y * dpi_factor) as c_short
+            };
+            unsafe {
+                let preedit_attributes = x11_sys::XVaCreateNestedList(
+                    0,
+                    x11_sys::XNSpotLocation.as_ptr(),
+                    &spot,
+                    ptr::null_mut() as *mut c_void
+                );
+                x11_sys::XSetICValues(
+                    xic,
+                    x11_sys::XNPreeditAttributes.as_ptr(),
+                    preedit_attributes,
+                    ptr::null_mut() as *mut c_void
+                );
+                x11_sys::XFree(preedit_attributes);
+            }
+        }
+    }
+    
+    // cancels a running composition. This runs from the platform ops so no
+    // event is sent, widgets drop their preedit text when they lose focus
+    pub fn reset_ime(&mut self) {
+        if let Some(xic) = self.xic {
+            unsafe {
+                let pending = x11_sys::Xutf8ResetIC(xic);
+                if pending != ptr::null_mut() {
+                    x11_sys::XFree(pending as *mut c_void);
+                }
+            }
+        }
+        self.ime_preedit.clear();
+        self.ime_caret = 0;
+    }
+    
+    fn send_preedit_event(&mut self) {
+        self.do_callback(XlibEvent::TextPreedit(TextPreeditEvent {
+            text: self.ime_preedit.iter().collect(),
+            cursor: self.ime_caret,
+        }));
+    }
+    
+    // converts a position on the root window to logical window coordinates
+    pub fn root_to_window_pos(&self, root_x: c_int, root_y: c_int) -> DVec2 {
+        unsafe {
+            let display = get_xlib_app_global().display;
+            let default_screen = x11_sys::XDefaultScreen(display);
+            let root_window = x11_sys::XRootWindow(display, default_screen);
+            let mut x = 0;
+            let mut y = 0;
+            let mut child = 0;
+            x11_sys::XTranslateCoordinates(display, root_window, self.window.unwrap(), root_x, root_y, &mut x, &mut y, &mut child);
+            let dpi_factor = self.last_window_geom.dpi_factor;
+            DVec2 {x: x as f64 / dpi_factor, y: y as f64 / dpi_factor}
+        }
     }
     
     pub fn get_position(&self) -> DVec2 {
@@ -393,11 +567,7 @@ impl XlibWindow {
     
     pub fn send_change_event(&mut self) {
         
-        let mut new_geom = self.get_window_geom();
-        if new_geom.inner_size.x < self.last_window_geom.inner_size.x ||
-        new_geom.inner_size.y < self.last_window_geom.inner_size.y {
-            new_geom.is_fullscreen = false;
-        }
+        let new_geom = self.get_window_geom();
         let old_geom = self.last_window_geom.clone();
         self.last_window_geom = new_geom.clone();
         
@@ -515,6 +685,32 @@ pub struct Dnd {
     pub display: *mut x11_sys::Display,
     pub type_list: Option<Vec<x11_sys::Atom >>,
     pub selection: Option<CString>,
+    pub source: Option<DndSource>,
+}
+
+/// A drag started by us with cx.start_dragging.
+pub struct DndSource {
+    pub items: Rc<Vec<DragItem >>,
+    pub source_window: x11_sys::Window,
+    pub types: Vec<x11_sys::Atom>,
+    pub target: Option<DndTarget>,
+    pub root_pos: (c_int, c_int),
+    pub time: x11_sys::Time,
+    pub modifiers: KeyModifiers,
+    pub response: DragResponse,
+    // an external target answers every XdndPosition with a XdndStatus, and we
+    // may only send the next position once that status came in
+    pub status_pending: bool,
+    pub position_pending: bool,
+    pub drop_sent: bool,
+}
+
+/// The window under the pointer during a drag. Drags over our own windows are
+/// delivered as Drag and Drop events directly, without going through Xdnd.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DndTarget {
+    Internal(x11_sys::Window),
+    External(x11_sys::Window),
 }
 
 impl Dnd {
@@ -524,6 +720,7 @@ impl Dnd {
             display,
             type_list: None,
             selection: None,
+            source: None,
         }
     }
     
@@ -735,6 +932,239 @@ impl Dnd {
         x11_sys::XFlush(self.display);
     }
     
+    /// Makes us the owner of the XdndSelection for the given drag items.
+    pub unsafe fn start_source(&mut self, source_window: x11_sys::Window, items: Vec<DragItem>, time: x11_sys::Time) {
+        let mut types = Vec::new();
+        if items.iter().any( | item | matches!(item, DragItem::FilePath {..})) {
+            types.push(self.atoms.uri_list);
+        }
+        types.push(self.atoms.utf8_string);
+        types.push(self.atoms.text_plain_utf8);
+        types.push(self.atoms.text_plain);
+        
+        x11_sys::XSetSelectionOwner(self.display, self.atoms.selection, source_window, time);
+        x11_sys::XChangeProperty(
+            self.display,
+            source_window,
+            self.atoms.type_list,
+            4, // XA_ATOM
+            32,
+            x11_sys::PropModeReplace as c_int,
+            types.as_ptr() as *const std::os::raw::c_uchar,
+            types.len() as c_int
+        );
+        
+        self.source = Some(DndSource {
+            items: Rc::new(items),
+            source_window,
+            types,
+            target: None,
+            root_pos: (0, 0),
+            time,
+            modifiers: KeyModifiers::default(),
+            response: DragResponse::None,
+            status_pending: false,
+            position_pending: false,
+            drop_sent: false,
+        });
+    }
+    
+    /// Finds the XdndAware window under the given root position, with the protocol version it speaks.
+    pub unsafe fn find_target(&self, root_x: c_int, root_y: c_int) -> Option<(x11_sys::Window, c_ulong)> {
+        let default_screen = x11_sys::XDefaultScreen(self.display);
+        let root_window = x11_sys::XRootWindow(self.display, default_screen);
+        // the window manager puts a frame around toplevels, so walk down until we
+        // hit a window that says it takes drops
+        let mut window = root_window;
+        loop {
+            let mut x = 0;
+            let mut y = 0;
+            let mut child = 0;
+            x11_sys::XTranslateCoordinates(self.display, root_window, window, root_x, root_y, &mut x, &mut y, &mut child);
+            if child == 0 {
+                return None
+            }
+            if let Some(version) = self.get_aware_version(child) {
+                return Some((child, version))
+            }
+            window = child;
+        }
+    }
+    
+    unsafe fn get_aware_version(&self, window: x11_sys::Window) -> Option<c_ulong> {
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let mut nitems = 0;
+        let mut bytes_after = 0;
+        let mut prop = ptr::null_mut();
+        x11_sys::XGetWindowProperty(
+            self.display,
+            window,
+            self.atoms.aware,
+            0,
+            1,
+            x11_sys::False as c_int,
+            4, // XA_ATOM
+            &mut actual_type,
+            &mut actual_format,
+            &mut nitems,
+            &mut bytes_after,
+            &mut prop,
+        );
+        let mut version = None;
+        if prop != ptr::null_mut() {
+            if nitems > 0 {
+                version = Some(*(prop as *mut c_ulong));
+            }
+            x11_sys::XFree(prop as *mut c_void);
+        }
+        version
+    }
+    
+    pub fn action_for_response(&self, response: DragResponse) -> x11_sys::Atom {
+        match response {
+            DragResponse::None => self.atoms.none,
+            DragResponse::Copy => self.atoms.action_copy,
+            DragResponse::Move => self.atoms.action_move,
+            DragResponse::Link => self.atoms.action_link,
+        }
+    }
+    
+    pub fn response_for_action(&self, action: x11_sys::Atom) -> DragResponse {
+        if action == self.atoms.action_move {DragResponse::Move}
+        else if action == self.atoms.action_link {DragResponse::Link}
+        else if action == self.atoms.none {DragResponse::None}
+        else {DragResponse::Copy}
+    }
+    
+    unsafe fn send_client_message(&self, window: x11_sys::Window, message_type: x11_sys::Atom, data: [c_long; 5]) {
+        x11_sys::XSendEvent(
+            self.display,
+            window,
+            x11_sys::False as c_int,
+            x11_sys::NoEventMask as c_long,
+            &mut x11_sys::XClientMessageEvent {
+                type_: x11_sys::ClientMessage as c_int,
+                serial: 0,
+                send_event: 0,
+                display: self.display,
+                window,
+                message_type,
+                format: 32,
+                data: {
+                    let mut msg = mem::zeroed::<x11_sys::XClientMessageEvent__bindgen_ty_1>();
+                    msg.l = data;
+                    msg
+                }
+            } as *mut x11_sys::XClientMessageEvent as *mut x11_sys::XEvent
+        );
+        x11_sys::XFlush(self.display);
+    }
+    
+    /// Sends a XDndEnter event to the target window.
+    pub unsafe fn send_enter_event(&self, target_window: x11_sys::Window, version: c_ulong) {
+        if let Some(source) = &self.source {
+            let mut data = [0; 5];
+            data[0] = source.source_window as c_long;
+            data[1] = ((version.min(5) as c_long) << 24) | if source.types.len() > 3 {1} else {0};
+            for (i, ty) in source.types.iter().take(3).enumerate() {
+                data[2 + i] = *ty as c_long;
+            }
+            self.send_client_message(target_window, self.atoms.enter, data);
+        }
+    }
+    
+    /// Sends a XDndPosition event to the target window.
+    pub unsafe fn send_position_event(&self, target_window: x11_sys::Window) {
+        if let Some(source) = &self.source {
+            // shift asks for a move, like in other toolkits
+            let action = if source.modifiers.shift {self.atoms.action_move} else {self.atoms.action_copy};
+            self.send_client_message(target_window, self.atoms.position, [
+                source.source_window as c_long,
+                0,
+                ((source.root_pos.0 as c_long) << 16) | (source.root_pos.1 as c_long & 0xffff),
+                source.time as c_long,
+                action as c_long
+            ]);
+        }
+    }
+    
+    /// Sends a XDndLeave event to the target window.
+    pub unsafe fn send_leave_event(&self, target_window: x11_sys::Window) {
+        if let Some(source) = &self.source {
+            self.send_client_message(target_window, self.atoms.leave, [source.source_window as c_long, 0, 0, 0, 0]);
+        }
+    }
+    
+    /// Sends a XDndDrop event to the target window.
+    pub unsafe fn send_drop_event(&self, target_window: x11_sys::Window) {
+        if let Some(source) = &self.source {
+            self.send_client_message(target_window, self.atoms.drop, [
+                source.source_window as c_long,
+                0,
+                source.time as c_long,
+                0,
+                0
+            ]);
+        }
+    }
+    
+    /// Answers a request from the drop target for the dragged data.
+    pub unsafe fn handle_source_selection_request(&self, request: &x11_sys::XSelectionRequestEvent) {
+        let mut response = x11_sys::XSelectionEvent {
+            type_: x11_sys::SelectionNotify as i32,
+            serial: 0,
+            send_event: 0,
+            display: self.display,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            time: request.time,
+            property: request.property,
+        };
+        if let Some(source) = &self.source {
+            if request.target == self.atoms.uri_list {
+                let mut uri_list = String::new();
+                for item in source.items.iter() {
+                    if let DragItem::FilePath {path, ..} = item {
+                        uri_list.push_str("file://");
+                        uri_list.push_str(&encode_uri_path(path));
+                        uri_list.push_str("\r\n");
+                    }
+                }
+                self.change_property(request, request.target, 8, uri_list.as_ptr(), uri_list.len());
+            }
+            else if source.types.contains(&request.target) {
+                let text = source.items.iter().map( | item | match item {
+                    DragItem::FilePath {path, ..} => path.as_str(),
+                    DragItem::String {value, ..} => value.as_str()
+                }).collect::<Vec<_ >>().join("\n");
+                self.change_property(request, request.target, 8, text.as_ptr(), text.len());
+            }
+            else {
+                response.property = 0;
+            }
+        }
+        else {
+            response.property = 0;
+        }
+        x11_sys::XSendEvent(self.display, request.requestor, 1, 0, &mut response as *mut _ as *mut x11_sys::XEvent);
+        x11_sys::XFlush(self.display);
+    }
+    
+    unsafe fn change_property(&self, request: &x11_sys::XSelectionRequestEvent, ty: x11_sys::Atom, format: c_int, data: *const u8, len: usize) {
+        x11_sys::XChangeProperty(
+            self.display,
+            request.requestor,
+            request.property,
+            ty,
+            format,
+            x11_sys::PropModeReplace as c_int,
+            data as *const std::os::raw::c_uchar,
+            len as c_int
+        );
+    }
+    
     // Requests that the selection representing the thing being dragged is converted to the
     // appropriate data type (in our case, a URI list).
     pub unsafe fn convert_selection(&self, target_window: x11_sys::Window) {
@@ -751,9 +1181,13 @@ impl Dnd {
 
 pub struct DndAtoms {
     pub action_private: x11_sys::Atom,
+    pub action_copy: x11_sys::Atom,
+    pub action_move: x11_sys::Atom,
+    pub action_link: x11_sys::Atom,
     pub aware: x11_sys::Atom,
     pub drop: x11_sys::Atom,
     pub enter: x11_sys::Atom,
+    pub finished: x11_sys::Atom,
     pub leave: x11_sys::Atom,
     pub none: x11_sys::Atom,
     pub position: x11_sys::Atom,
@@ -761,15 +1195,22 @@ pub struct DndAtoms {
     pub status: x11_sys::Atom,
     pub type_list: x11_sys::Atom,
     pub uri_list: x11_sys::Atom,
+    pub utf8_string: x11_sys::Atom,
+    pub text_plain: x11_sys::Atom,
+    pub text_plain_utf8: x11_sys::Atom,
 }
 
 impl DndAtoms {
     pub unsafe fn new(display: *mut x11_sys::Display) -> DndAtoms {
         DndAtoms {
             action_private: x11_sys::XInternAtom(display, "XdndActionPrivate\0".as_ptr() as *const _, 0),
+            action_copy: x11_sys::XInternAtom(display, "XdndActionCopy\0".as_ptr() as *const _, 0),
+            action_move: x11_sys::XInternAtom(display, "XdndActionMove\0".as_ptr() as *const _, 0),
+            action_link: x11_sys::XInternAtom(display, "XdndActionLink\0".as_ptr() as *const _, 0),
             aware: x11_sys::XInternAtom(display, "XdndAware\0".as_ptr() as *const _, 0),
             drop: x11_sys::XInternAtom(display, "XdndDrop\0".as_ptr() as *const _, 0),
             enter: x11_sys::XInternAtom(display, "XdndEnter\0".as_ptr() as *const _, 0),
+            finished: x11_sys::XInternAtom(display, "XdndFinished\0".as_ptr() as *const _, 0),
             leave: x11_sys::XInternAtom(display, "XdndLeave\0".as_ptr() as *const _, 0),
             none: x11_sys::XInternAtom(display, "None\0".as_ptr() as *const _, 0),
             position: x11_sys::XInternAtom(display, "XdndPosition\0".as_ptr() as *const _, 0),
@@ -777,6 +1218,67 @@ impl DndAtoms {
             status: x11_sys::XInternAtom(display, "XdndStatus\0".as_ptr() as *const _, 0),
             type_list: x11_sys::XInternAtom(display, "XdndTypeList\0".as_ptr() as *const _, 0),
             uri_list: x11_sys::XInternAtom(display, "text/uri-list\0".as_ptr() as *const _, 0),
+            utf8_string: x11_sys::XInternAtom(display, "UTF8_STRING\0".as_ptr() as *const _, 0),
+            text_plain: x11_sys::XInternAtom(display, "text/plain\0".as_ptr() as *const _, 0),
+            text_plain_utf8: x11_sys::XInternAtom(display, "text/plain;charset=utf-8\0".as_ptr() as *const _, 0),
         }
     }
 }
+
+unsafe extern "C" fn xim_preedit_start(_xic: x11_sys::XIC, client_data: x11_sys::XPointer, _call_data: x11_sys::XPointer) -> c_int {
+    let window = &mut *(client_data as *mut XlibWindow);
+    window.ime_preedit.clear();
+    window.ime_caret = 0;
+    // no limit on the length of the composition
+    -1
+}
+
+unsafe extern "C" fn xim_preedit_done(_xim: x11_sys::XIM, client_data: x11_sys::XPointer, _call_data: x11_sys::XPointer) {
+    let window = &mut *(client_data as *mut XlibWindow);
+    window.ime_preedit.clear();
+    window.ime_caret = 0;
+    window.send_preedit_event();
+}
+
+unsafe extern "C" fn xim_preedit_draw(_xim: x11_sys::XIM, client_data: x11_sys::XPointer, call_data: x11_sys::XPointer) {
+    let window = &mut *(client_data as *mut XlibWindow);
+    let draw = &*(call_data as *mut x11_sys::XIMPreeditDrawCallbackStruct);
+    let len = window.ime_preedit.len();
+    let first = (draw.chg_first.max(0) as usize).min(len);
+    let end = (first + draw.chg_length.max(0) as usize).min(len);
+    if draw.text == ptr::null_mut() {
+        window.ime_preedit.drain(first..end);
+    }
+    else {
+        let text = &*draw.text;
+        // a text without a string only changes the highlighting
+        if text.encoding_is_wchar != 0 {
+            if text.string.wide_char != ptr::null_mut() {
+                let wide = std::slice::from_raw_parts(text.string.wide_char, text.length as usize);
+                window.ime_preedit.splice(first..end, wide.iter().filter_map( | c | char::from_u32(*c)));
+            }
+        }
+        else if text.string.multi_byte != ptr::null_mut() {
+            let multi_byte = CStr::from_ptr(text.string.multi_byte).to_string_lossy();
+            window.ime_preedit.splice(first..end, multi_byte.chars());
+        }
+    }
+    window.ime_caret = (draw.caret.max(0) as usize).min(window.ime_preedit.len());
+    window.send_preedit_event();
+}
+
+unsafe extern "C" fn xim_preedit_caret(_xim: x11_sys::XIM, client_data: x11_sys::XPointer, call_data: x11_sys::XPointer) {
+    let window = &mut *(client_data as *mut XlibWindow);
+    let caret = &mut *(call_data as *mut x11_sys::XIMPreeditCaretCallbackStruct);
+    let len = window.ime_preedit.len();
+    window.ime_caret = match caret.direction {
+        x11_sys::XIMForwardChar => (window.ime_caret + 1).min(len),
+        x11_sys::XIMBackwardChar => window.ime_caret.saturating_sub(1),
+        x11_sys::XIMLineStart => 0,
+        x11_sys::XIMLineEnd => len,
+        x11_sys::XIMAbsolutePosition => (caret.position.max(0) as usize).min(len),
+        _ => window.ime_caret
+    };
+    caret.position = window.ime_caret as c_int;
+    window.send_preedit_event();
+}
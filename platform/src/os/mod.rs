@@ -7,6 +7,8 @@ pub mod cx_shared;
 
 pub mod cx_stdin;
 
+pub mod cx_test_driver;
+
 #[cfg(any(target_os = "macos", target_os="ios"))]
 pub mod apple;
 
@@ -90,6 +90,7 @@ impl Into<WindowGeom> for WWindowInfo {
         WindowGeom {
             is_fullscreen: self.is_fullscreen,
             is_topmost: false,
+            is_maximized: false,
             inner_size: DVec2 {x: self.inner_width, y: self.inner_height},
             dpi_factor: self.dpi_factor,
             outer_size: DVec2 {x: 0., y: 0.},
@@ -430,7 +430,7 @@ impl Cx {
                 CxOsOp::XrStopPresenting => {
                     self.os.from_wasm(FromWasmXrStopPresenting {});
                 },
-                CxOsOp::ShowTextIME(area, pos) => {
+                CxOsOp::ShowTextIME(area, pos, _line_height) => {
                     let pos = area.get_clipped_rect(self).pos + pos;
                     self.os.from_wasm(FromWasmShowTextIME {x: pos.x, y: pos.y});
                 },
@@ -439,6 +439,11 @@ impl Cx {
                 },
                 CxOsOp::ShowClipboardActions(_) =>{
                 }
+                CxOsOp::CopyToClipboard(_) => {
+                }
+                CxOsOp::ReadClipboard{request_id, ..} => {
+                    self.call_empty_clipboard_event(request_id);
+                }
                 CxOsOp::SetCursor(cursor) => {
                     self.os.from_wasm(FromWasmSetMouseCursor::new(cursor));
                 },
@@ -855,6 +855,7 @@ impl Win32Window {
             can_fullscreen: false,
             is_topmost: self.get_is_topmost(),
             is_fullscreen: self.get_is_maximized(),
+            is_maximized: self.get_is_maximized(),
             inner_size: if self.get_is_maximized(){self.get_outer_size()}else{self.get_inner_size()},
             outer_size: self.get_outer_size(),
             dpi_factor: self.get_dpi_factor(),
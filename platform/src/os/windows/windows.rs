@@ -313,13 +313,18 @@ impl Cx {
                 }
                 CxOsOp::ShowClipboardActions(_) => {
                 }
+                CxOsOp::CopyToClipboard(_) => {
+                }
+                CxOsOp::ReadClipboard {request_id, ..} => {
+                    self.call_empty_clipboard_event(request_id);
+                }
                 CxOsOp::XrStartPresenting => {
                     //todo!()
                 },
                 CxOsOp::XrStopPresenting => {
                     //todo!()
                 },
-                CxOsOp::ShowTextIME(_area, _pos) => {
+                CxOsOp::ShowTextIME(_area, _pos, _line_height) => {
                     //todo!()
                 }
                 CxOsOp::HideTextIME => {
@@ -162,6 +162,11 @@ impl Texture {
         std::mem::swap(&mut cxtexture.image_u32, image_u32);
         cxtexture.update_image = true;
     }
+    
+    // the cpu side pixels of an image texture. render targets only exist on the gpu so these come back empty
+    pub fn get_image_u32<'a>(&self, cx: &'a Cx) -> &'a [u32] {
+        &cx.textures[self.texture_id()].image_u32
+    }
 }
 
 
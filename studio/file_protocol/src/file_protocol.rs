@@ -39,7 +39,24 @@ pub enum FileRequest {
     /// Requests the collab server to apply the given delta to the given revision of the file with
     /// the given id.
     SaveFile(String, String, u64),
+    /// Requests the collab server to search the files in its file tree for the given query. The
+    /// matches are streamed back as `SearchResults` notifications with the given id, followed by a
+    /// `SearchFinished` notification. Starting a new search cancels the previous one.
+    Search(SearchQuery, u64),
+}
 
+/// A type for representing a search across the files in a file tree.
+#[derive(Clone, Debug, Default, SerBin, DeBin)]
+pub struct SearchQuery {
+    /// The text to search for, or a regular expression if `is_regex` is set.
+    pub pattern: String,
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Globs for the files to search. If empty, all files are searched.
+    pub include: Vec<String>,
+    /// Globs for the files and directories to skip.
+    pub exclude: Vec<String>,
 }
 
 /// A type for representing either a response or a notification from the collab server.
@@ -62,6 +79,9 @@ pub enum FileResponse {
     /// The result of requesting the collab server to apply a delta to a revision of the file with
     /// the given id.
     SaveFile(Result<(String,String,String, u64), FileError>),
+    /// The result of requesting the collab server to start a search with the given id. This is an
+    /// error if the query is not a valid regular expression.
+    Search(Result<u64, (u64, FileError)>),
 }
 
 /// A type for representing data about a file tree.
@@ -97,11 +117,32 @@ pub struct DirectoryEntry {
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileNotification {
     FileChangedOnDisk,
+    /// Notifies the client of the next batch of matches for the search with the given id.
+    SearchResults {id: u64, results: Vec<SearchResult>},
+    /// Notifies the client that the search with the given id is done, and found `match_count`
+    /// matches in `file_count` files. If `is_truncated` is set, the search stopped early because
+    /// it found too many matches.
+    SearchFinished {id: u64, file_count: usize, match_count: usize, is_truncated: bool},
     // Notifies the client that another client applied the given delta to the file with the given
     // id. This is only sent for files for which the client is a participant.
    // DeltaWasApplied(TextFileId),
 }
 
+/// A type for representing a match of a search.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct SearchResult {
+    /// The path of the file, relative to the root of the file tree.
+    pub path: String,
+    pub line_index: usize,
+    /// The byte range of the match within the line.
+    pub start_byte_index: usize,
+    pub end_byte_index: usize,
+    /// The part of the line around the match, for display.
+    pub preview: String,
+    /// The byte index within the line at which the preview starts.
+    pub preview_byte_index: usize,
+}
+
 /// A type for representing errors from the collab server.
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum FileError {
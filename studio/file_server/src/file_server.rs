@@ -8,14 +8,21 @@ use {
             FileNotification,
             FileRequest,
             FileResponse,
+            SearchQuery,
         },
+        search::Search,
     },
     std::{
         cmp::Ordering,
         fmt,
         fs,
         path::{Path, PathBuf},
-        sync::{Arc, RwLock},
+        sync::{
+            atomic::{AtomicU64, Ordering as AtomicOrdering},
+            Arc,
+            RwLock,
+        },
+        thread,
     },
 };
 
@@ -47,7 +54,8 @@ impl FileServer {
         FileServerConnection {
             _connection_id:connection_id,
             shared: self.shared.clone(),
-            _notification_sender: notification_sender
+            notification_sender,
+            search_id: Arc::new(AtomicU64::new(0)),
         }
     }
 }
@@ -59,7 +67,9 @@ pub struct FileServerConnection {
     // State is shared between every connection.
     shared: Arc<RwLock<Shared >>,
     // Used to send notifications for this connection.
-    _notification_sender: Box<dyn NotificationSender>,
+    notification_sender: Box<dyn NotificationSender>,
+    // The id of the most recently started search for this connection.
+    search_id: Arc<AtomicU64>,
 }
 
 impl FileServerConnection {
@@ -74,6 +84,7 @@ impl FileServerConnection {
             FileRequest::LoadFileTree {with_data} => FileResponse::LoadFileTree(self.load_file_tree(with_data)),
             FileRequest::OpenFile(path,id) => FileResponse::OpenFile(self.open_file(path, id)),
             FileRequest::SaveFile(path, delta, id) => FileResponse::SaveFile(self.save_file(path, delta, id)),
+            FileRequest::Search(query, id) => FileResponse::Search(self.search(query, id)),
         }
     }
     
@@ -202,6 +213,19 @@ impl FileServerConnection {
         
         Ok((child_path, old_content, new_content, id))
     }
+    
+    // Handles a `Search` request. The search itself runs on its own thread, and sends its results
+    // as notifications.
+    fn search(&self, query: SearchQuery, id: u64) -> Result<u64, (u64, FileError)> {
+        // This cancels the previous search, if it is still running.
+        self.search_id.store(id, AtomicOrdering::Relaxed);
+        let search = Search::new(&query, id, self.search_id.clone(), self.notification_sender.clone()).map_err(
+            | error | (id, FileError::Unknown(error.to_string()))
+        ) ?;
+        let root_path = self.shared.read().unwrap().root_path.clone();
+        thread::spawn(move || search.run(&root_path));
+        Ok(id)
+    }
 }
 
 /// A trait for sending notifications over a connection.
@@ -0,0 +1,673 @@
+use {
+    crate::{
+        makepad_file_protocol::{
+            FileNotification,
+            SearchQuery,
+            SearchResult,
+        },
+        makepad_regex::{self, Regex, RegexError},
+        NotificationSender,
+    },
+    std::{
+        fs,
+        mem,
+        ops::Range,
+        path::Path,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+// A search stops once it has found this many matches.
+const MAX_MATCH_COUNT: usize = 10_000;
+// Files larger than this are not searched.
+const MAX_FILE_LEN: u64 = 8 * 1024 * 1024;
+// Files with a NUL byte within this many bytes from the start are considered binary, and are not
+// searched.
+const BINARY_CHECK_LEN: usize = 8 * 1024;
+// Lines longer than this are not searched. They are usually minified or generated, and the regex
+// takes time and memory proportional to the length of the line for every match on it.
+const MAX_LINE_LEN: usize = 32 * 1024;
+// Previews of matches on long lines are cut to roughly this many bytes.
+const MAX_PREVIEW_LEN: usize = 200;
+// Matches are sent in batches, whenever this many have been found, or this much time has passed.
+const MAX_BATCH_LEN: usize = 256;
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A search across the files below a root directory.
+///
+/// The search walks the directory tree in the same way as the file tree does, skipping hidden
+/// entries and "target" directories, as well as the paths that are ignored by `.gitignore` files
+/// and the exclude globs of the query. Matches are sent as notifications while the search runs.
+pub struct Search {
+    id: u64,
+    regex: Regex,
+    // If the query is a case sensitive literal, files that don't contain it can be skipped
+    // without running the regex on every line.
+    literal: Option<String>,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    // The id of the most recently started search on the connection. If this is no longer our id,
+    // the search was cancelled.
+    current_id: Arc<AtomicU64>,
+    notification_sender: Box<dyn NotificationSender>,
+    results: Vec<SearchResult>,
+    last_send_time: Instant,
+    file_count: usize,
+    match_count: usize,
+    is_truncated: bool,
+}
+
+impl Search {
+    pub fn new(
+        query: &SearchQuery,
+        id: u64,
+        current_id: Arc<AtomicU64>,
+        notification_sender: Box<dyn NotificationSender>
+    ) -> Result<Search, RegexError> {
+        // Whole words are matched in the same way as by find in the code editor.
+        let regex = if query.is_regex {
+            if query.whole_word {
+                Regex::new(&format!("\\b(?:{})\\b", query.pattern), query.case_sensitive) ?
+            }
+            else {
+                Regex::new(&query.pattern, query.case_sensitive) ?
+            }
+        }
+        else {
+            // Only require a word boundary next to the word chars of the pattern, so that a whole
+            // word search for `-x` still finds it after a space.
+            let mut escaped = Regex::escape(&query.pattern);
+            if query.whole_word {
+                if query.pattern.chars().next().is_some_and(makepad_regex::is_word_char) {
+                    escaped.insert_str(0, "\\b");
+                }
+                if query.pattern.chars().next_back().is_some_and(makepad_regex::is_word_char) {
+                    escaped.push_str("\\b");
+                }
+            }
+            Regex::new(&escaped, query.case_sensitive) ?
+        };
+        Ok(Search {
+            id,
+            regex,
+            literal: if !query.is_regex && query.case_sensitive {Some(query.pattern.clone())} else {None},
+            include: parse_globs(&query.include) ?,
+            exclude: parse_globs(&query.exclude) ?,
+            current_id,
+            notification_sender,
+            results: Vec::new(),
+            last_send_time: Instant::now(),
+            file_count: 0,
+            match_count: 0,
+            is_truncated: false,
+        })
+    }
+
+    /// Runs the search to completion, or until it is cancelled.
+    pub fn run(mut self, root_path: &Path) {
+        self.search_directory(root_path, "", &mut Vec::new());
+        if self.is_cancelled() {
+            return
+        }
+        self.send_results();
+        self.notification_sender.send_notification(FileNotification::SearchFinished {
+            id: self.id,
+            file_count: self.file_count,
+            match_count: self.match_count,
+            is_truncated: self.is_truncated,
+        });
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.current_id.load(Ordering::Relaxed) != self.id
+    }
+
+    // Searches the entries of the directory at `path`, which is at `relative_path` relative to the
+    // root. `gitignores` holds the `.gitignore` files of the directories above it.
+    fn search_directory(&mut self, path: &Path, relative_path: &str, gitignores: &mut Vec<Gitignore>) {
+        let has_gitignore = if let Ok(source) = fs::read_to_string(path.join(".gitignore")) {
+            gitignores.push(Gitignore::parse(relative_path, &source));
+            true
+        }
+        else {
+            false
+        };
+        let Ok(entries) = fs::read_dir(path) else {
+            return
+        };
+        let mut entries: Vec<_> = entries.filter_map( | entry | entry.ok()).collect();
+        entries.sort_by_key( | entry | entry.file_name());
+        for entry in entries {
+            if self.is_truncated || self.is_cancelled() {
+                break
+            }
+            let Ok(name) = entry.file_name().into_string() else {
+                continue
+            };
+            let entry_path = entry.path();
+            let is_dir = entry_path.is_dir();
+            // Skip the same entries as the file tree, so every match can be opened.
+            if name.starts_with('.') || is_dir && name == "target" {
+                continue
+            }
+            let entry_relative_path = if relative_path.is_empty() {
+                name
+            }
+            else {
+                format!("{}/{}", relative_path, name)
+            };
+            if is_ignored(gitignores, &entry_relative_path, is_dir)
+                || self.exclude.iter().any( | glob | glob.is_match(&entry_relative_path, is_dir)) {
+                continue
+            }
+            if is_dir {
+                self.search_directory(&entry_path, &entry_relative_path, gitignores);
+            }
+            else if entry_path.is_file() && self.is_included(&entry_relative_path) {
+                self.search_file(&entry_path, entry_relative_path);
+            }
+        }
+        if has_gitignore {
+            gitignores.pop();
+        }
+    }
+
+    // A file is included if an include glob matches either the file or one of its directories.
+    fn is_included(&self, relative_path: &str) -> bool {
+        self.include.is_empty() || self.include.iter().any( | glob | {
+            glob.is_match(relative_path, false) || relative_path
+                .match_indices('/')
+                .any( | (index, _) | glob.is_match(&relative_path[..index], true))
+        })
+    }
+
+    fn search_file(&mut self, path: &Path, relative_path: String) {
+        if fs::metadata(path).map_or(true, | metadata | metadata.len() > MAX_FILE_LEN) {
+            return
+        }
+        let Ok(bytes) = fs::read(path) else {
+            return
+        };
+        if bytes[..bytes.len().min(BINARY_CHECK_LEN)].contains(&0) {
+            return
+        }
+        let text = String::from_utf8_lossy(&bytes);
+        if let Some(literal) = &self.literal {
+            if !text.contains(literal.as_str()) {
+                return
+            }
+        }
+        let match_count = self.match_count;
+        // Lines are split in the same way as the code editor does, so the positions match.
+        'lines: for (line_index, line) in text.lines().enumerate() {
+            if self.is_cancelled() {
+                return
+            }
+            if line.len() > MAX_LINE_LEN {
+                continue
+            }
+            for captures in self.regex.captures_iter(line) {
+                let range = captures.range();
+                if range.is_empty() {
+                    continue
+                }
+                if self.match_count == MAX_MATCH_COUNT {
+                    self.is_truncated = true;
+                    break 'lines
+                }
+                self.match_count += 1;
+                let preview_range = preview_range(line, range.clone());
+                self.results.push(SearchResult {
+                    path: relative_path.clone(),
+                    line_index,
+                    start_byte_index: range.start,
+                    end_byte_index: range.end,
+                    preview: line[preview_range.clone()].to_string(),
+                    preview_byte_index: preview_range.start,
+                });
+            }
+        }
+        if self.match_count > match_count {
+            self.file_count += 1;
+        }
+        if self.results.len() >= MAX_BATCH_LEN || self.last_send_time.elapsed() >= BATCH_INTERVAL {
+            self.send_results();
+        }
+    }
+
+    fn send_results(&mut self) {
+        if !self.results.is_empty() {
+            self.notification_sender.send_notification(FileNotification::SearchResults {
+                id: self.id,
+                results: mem::take(&mut self.results),
+            });
+        }
+        self.last_send_time = Instant::now();
+    }
+}
+
+// Returns the part of the line to show for the match at `range`. Leading whitespace is left out,
+// and long lines are cut around the match.
+fn preview_range(line: &str, range: Range<usize>) -> Range<usize> {
+    let indent_len = line.len() - line.trim_start().len();
+    let mut start = indent_len.min(range.start);
+    if line.len() - start <= MAX_PREVIEW_LEN {
+        return start..line.len()
+    }
+    start = start.max(range.start.saturating_sub(MAX_PREVIEW_LEN / 4));
+    while !line.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (start + MAX_PREVIEW_LEN).max(range.end).min(line.len());
+    while !line.is_char_boundary(end) {
+        end += 1;
+    }
+    start..end
+}
+
+fn parse_globs(globs: &[String]) -> Result<Vec<Glob>, RegexError> {
+    globs.iter()
+        .map( | glob | glob.trim())
+        .filter( | glob | !glob.is_empty())
+        .map(Glob::new)
+        .collect()
+}
+
+/// A glob pattern, as used in `.gitignore` files.
+///
+/// `*` and `?` match any chars except `/`, `**` matches any number of directories, `[...]` matches
+/// a class of chars, and `{a,b}` matches either alternative. A glob that ends with a `/` only
+/// matches directories. A glob without any other `/` matches the name of an entry at any depth,
+/// otherwise it matches the whole relative path.
+#[derive(Clone, Debug)]
+pub struct Glob {
+    regex: Regex,
+    is_anchored: bool,
+    is_dir_only: bool,
+}
+
+impl Glob {
+    pub fn new(glob: &str) -> Result<Glob, RegexError> {
+        let (glob, is_dir_only) = match glob.strip_suffix('/') {
+            Some(glob) => (glob, true),
+            None => (glob, false),
+        };
+        let is_anchored = glob.contains('/');
+        let glob = glob.strip_prefix('/').unwrap_or(glob);
+        Ok(Glob {
+            regex: Regex::new(&glob_to_regex(glob), true) ?,
+            is_anchored,
+            is_dir_only,
+        })
+    }
+
+    /// Returns whether the glob matches the entry at the given path relative to the root.
+    pub fn is_match(&self, path: &str, is_dir: bool) -> bool {
+        if self.is_dir_only && !is_dir {
+            return false
+        }
+        if self.is_anchored {
+            self.regex.is_match(path)
+        }
+        else {
+            self.regex.is_match(path.rsplit('/').next().unwrap())
+        }
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::from("^");
+    let mut brace_depth = 0;
+    let mut index = 0;
+    while index < chars.len() {
+        let char = chars[index];
+        index += 1;
+        match char {
+            '*' if chars.get(index) == Some(&'*') => {
+                index += 1;
+                let is_segment_start = index == 2 || chars[index - 3] == '/';
+                if is_segment_start && chars.get(index) == Some(&'/') {
+                    // `**/` matches zero or more directories.
+                    index += 1;
+                    regex.push_str("(?:.*/)?");
+                }
+                else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => match glob_class_len(&chars[index..]) {
+                Some(len) => {
+                    regex.push('[');
+                    let mut class = &chars[index..index + len];
+                    if let Some(('!' | '^', rest)) = class.split_first() {
+                        regex.push('^');
+                        class = rest;
+                    }
+                    let mut class_chars = class.iter();
+                    while let Some(&char) = class_chars.next() {
+                        let char = match char {
+                            '\\' => class_chars.next().copied().unwrap_or('\\'),
+                            char => char,
+                        };
+                        if matches!(char, '\\' | '[' | ']' | '^') {
+                            regex.push('\\');
+                        }
+                        regex.push(char);
+                    }
+                    regex.push(']');
+                    index += len + 1;
+                }
+                None => regex.push_str("\\["),
+            },
+            '{' => {
+                brace_depth += 1;
+                regex.push_str("(?:");
+            }
+            ',' if brace_depth > 0 => regex.push('|'),
+            '}' if brace_depth > 0 => {
+                brace_depth -= 1;
+                regex.push(')');
+            }
+            '\\' if index < chars.len() => {
+                regex.push_str(&Regex::escape(&chars[index].to_string()));
+                index += 1;
+            }
+            char => regex.push_str(&Regex::escape(&char.to_string())),
+        }
+    }
+    for _ in 0..brace_depth {
+        regex.push(')');
+    }
+    regex.push('$');
+    regex
+}
+
+// Returns the number of chars in the class that starts right after a `[`, up to but not including
+// the closing `]`, or `None` if the class is not closed.
+fn glob_class_len(chars: &[char]) -> Option<usize> {
+    let mut index = 0;
+    if matches!(chars.first(), Some('!' | '^')) {
+        index += 1;
+    }
+    // A `]` right at the start is a literal.
+    if chars.get(index) == Some(&']') {
+        index += 1;
+    }
+    while index < chars.len() {
+        match chars[index] {
+            ']' => return Some(index),
+            '\\' => index += 2,
+            _ => index += 1,
+        }
+    }
+    None
+}
+
+/// The rules of a `.gitignore` file, which apply to the entries below the directory it is in.
+struct Gitignore {
+    // The path of the directory relative to the root, or the empty string for the root.
+    directory_path: String,
+    // Later rules take precedence over earlier ones. A negated rule re-includes what an earlier
+    // rule ignored.
+    rules: Vec<(Glob, bool)>,
+}
+
+impl Gitignore {
+    fn parse(directory_path: &str, source: &str) -> Gitignore {
+        let mut rules = Vec::new();
+        for line in source.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let (pattern, is_negated) = match line.strip_prefix('!') {
+                Some(pattern) => (pattern, true),
+                None => (line, false),
+            };
+            // Skip invalid patterns rather than failing the whole search.
+            if let Ok(glob) = Glob::new(pattern) {
+                rules.push((glob, is_negated));
+            }
+        }
+        Gitignore {
+            directory_path: directory_path.to_string(),
+            rules,
+        }
+    }
+
+    // Returns whether the entry at the given path relative to the root is ignored, or `None` if
+    // no rule matches it.
+    fn is_ignored(&self, path: &str, is_dir: bool) -> Option<bool> {
+        let path = if self.directory_path.is_empty() {
+            path
+        }
+        else {
+            path.strip_prefix(&self.directory_path) ?.strip_prefix('/') ?
+        };
+        self.rules.iter()
+            .rev()
+            .find( | (glob, _) | glob.is_match(path, is_dir))
+            .map( | (_, is_negated) | !is_negated)
+    }
+}
+
+// The `.gitignore` files of deeper directories take precedence over those of their parents.
+fn is_ignored(gitignores: &[Gitignore], path: &str, is_dir: bool) -> bool {
+    gitignores.iter()
+        .rev()
+        .find_map( | gitignore | gitignore.is_ignored(path, is_dir))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{path::PathBuf, sync::Mutex},
+    };
+
+    fn glob_matches(glob: &str, path: &str, is_dir: bool) -> bool {
+        Glob::new(glob).unwrap().is_match(path, is_dir)
+    }
+
+    #[test]
+    fn glob_regexes() {
+        assert_eq!(glob_to_regex("*.rs"), "^[^/]*\\.rs$");
+        assert_eq!(glob_to_regex("a?/**/b"), "^a[^/]/(?:.*/)?b$");
+        assert_eq!(glob_to_regex("**/x/**"), "^(?:.*/)?x/.*$");
+        assert_eq!(glob_to_regex("a**b"), "^a.*b$");
+        assert_eq!(glob_to_regex("*.{rs,toml}"), "^[^/]*\\.(?:rs|toml)$");
+        assert_eq!(glob_to_regex("{a,b"), "^(?:a|b)$");
+        assert_eq!(glob_to_regex("[!a-c]"), "^[^a-c]$");
+        assert_eq!(glob_to_regex("[]^]"), "^[\\]\\^]$");
+        assert_eq!(glob_to_regex("[ab"), "^\\[ab$");
+        assert_eq!(glob_to_regex("\\*,}"), "^\\*,\\}$");
+    }
+
+    #[test]
+    fn globs() {
+        // without a slash a glob matches the name at any depth
+        assert!(glob_matches("*.rs", "a.rs", false));
+        assert!(glob_matches("*.rs", "src/a.rs", false));
+        assert!(!glob_matches("*.rs", "a.rsx", false));
+        assert!(glob_matches("target", "a/target", true));
+        // with a slash it matches the whole path
+        assert!(glob_matches("src/*.rs", "src/a.rs", false));
+        assert!(!glob_matches("src/*.rs", "src/b/a.rs", false));
+        assert!(!glob_matches("src/*.rs", "x/src/a.rs", false));
+        assert!(glob_matches("/a.rs", "a.rs", false));
+        assert!(!glob_matches("/a.rs", "b/a.rs", false));
+        assert!(glob_matches("src/**/*.rs", "src/a.rs", false));
+        assert!(glob_matches("src/**/*.rs", "src/b/c/a.rs", false));
+        assert!(glob_matches("**/b", "a/b", true));
+        assert!(glob_matches("**/b", "b", true));
+        // a trailing slash only matches directories
+        assert!(glob_matches("build/", "x/build", true));
+        assert!(!glob_matches("build/", "x/build", false));
+        assert!(glob_matches("*.{rs,toml}", "Cargo.toml", false));
+        assert!(glob_matches("[]a]x", "]x", false));
+        assert!(!glob_matches("[!a]x", "ax", false));
+        assert!(glob_matches("\\*", "*", false));
+        assert!(!glob_matches("\\*", "a", false));
+        // globs are case sensitive
+        assert!(!glob_matches("*.RS", "a.rs", false));
+    }
+
+    #[test]
+    fn gitignores() {
+        let gitignore = Gitignore::parse("", "# comment\n\n*.log\n!keep.log\n/root.txt\nbuild/\ndoc/*.html  \n");
+        assert_eq!(gitignore.is_ignored("a.log", false), Some(true));
+        assert_eq!(gitignore.is_ignored("x/a.log", false), Some(true));
+        // a later negated rule re-includes
+        assert_eq!(gitignore.is_ignored("keep.log", false), Some(false));
+        assert_eq!(gitignore.is_ignored("x/keep.log", false), Some(false));
+        assert_eq!(gitignore.is_ignored("a.rs", false), None);
+        // a rule with a leading slash is anchored to the directory of the file
+        assert_eq!(gitignore.is_ignored("root.txt", false), Some(true));
+        assert_eq!(gitignore.is_ignored("x/root.txt", false), None);
+        assert_eq!(gitignore.is_ignored("doc/a.html", false), Some(true));
+        assert_eq!(gitignore.is_ignored("x/doc/a.html", false), None);
+        // a rule with a trailing slash only matches directories
+        assert_eq!(gitignore.is_ignored("x/build", true), Some(true));
+        assert_eq!(gitignore.is_ignored("x/build", false), None);
+
+        // rules apply relative to the directory of the file, and only below it
+        let gitignore = Gitignore::parse("sub", "/a\nb\n!*.rs\n");
+        assert_eq!(gitignore.is_ignored("sub/a", false), Some(true));
+        assert_eq!(gitignore.is_ignored("sub/x/a", false), None);
+        assert_eq!(gitignore.is_ignored("sub/x/b", false), Some(true));
+        assert_eq!(gitignore.is_ignored("a", false), None);
+        assert_eq!(gitignore.is_ignored("subway/a", false), None);
+
+        // deeper files take precedence
+        let gitignores = [Gitignore::parse("", "*.rs\n"), Gitignore::parse("sub", "!a.rs\n")];
+        assert!(is_ignored(&gitignores, "b.rs", false));
+        assert!(is_ignored(&gitignores, "sub/b.rs", false));
+        assert!(!is_ignored(&gitignores, "sub/a.rs", false));
+        assert!(!is_ignored(&gitignores, "sub/a.txt", false));
+    }
+
+    #[test]
+    fn preview_ranges() {
+        let line = "    let x = 1;";
+        assert_eq!(preview_range(line, 8..9), 4..line.len());
+        assert_eq!(preview_range(line, 2..5), 2..line.len());
+        // long lines are cut around the match
+        let line = "a".repeat(1000);
+        assert_eq!(preview_range(&line, 500..503), 450..650);
+        assert_eq!(preview_range(&line, 10..13), 0..200);
+        assert_eq!(preview_range(&line, 990..1000), 940..1000);
+        assert_eq!(preview_range(&line, 100..900), 50..900);
+        // and the cuts are at char boundaries
+        let line = "€".repeat(400);
+        assert_eq!(preview_range(&line, 600..603), 549..750);
+    }
+
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str, files: &[(&str, &str)]) -> TestDir {
+            let path = std::env::temp_dir().join(format!("makepad_search_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            for (file_path, contents) in files {
+                let file_path = path.join(file_path);
+                fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+                fs::write(file_path, contents).unwrap();
+            }
+            TestDir(path)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // The path, line index and byte range of a match.
+    type Match = (String, usize, Range<usize>);
+
+    fn search(dir: &TestDir, query: SearchQuery, current_id: u64) -> (Vec<Match>, Option<(usize, usize)>) {
+        let notifications = Arc::new(Mutex::new(Vec::new()));
+        let search = Search::new(&query, 1, Arc::new(AtomicU64::new(current_id)), Box::new({
+            let notifications = notifications.clone();
+            move | notification | notifications.lock().unwrap().push(notification)
+        })).unwrap();
+        search.run(&dir.0);
+        let mut results = Vec::new();
+        let mut finished = None;
+        for notification in notifications.lock().unwrap().drain(..) {
+            match notification {
+                FileNotification::SearchResults {results: batch, ..} => {
+                    for result in batch {
+                        assert_eq!(result.preview_byte_index, 0);
+                        assert_eq!(&result.preview[result.start_byte_index..result.end_byte_index], "ab");
+                        results.push((result.path, result.line_index, result.start_byte_index..result.end_byte_index));
+                    }
+                }
+                FileNotification::SearchFinished {file_count, match_count, ..} => finished = Some((file_count, match_count)),
+                _ => ()
+            }
+        }
+        (results, finished)
+    }
+
+    fn query(pattern: &str, is_regex: bool) -> SearchQuery {
+        SearchQuery {
+            pattern: pattern.to_string(),
+            is_regex,
+            case_sensitive: true,
+            whole_word: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn search_files() {
+        let long_line = "ab ".repeat(MAX_LINE_LEN);
+        let dir = TestDir::new("files", &[
+            (".gitignore", "*.log\nbuild/\n"),
+            ("a.rs", "ab abc\nfn ab() {}\n"),
+            ("b.log", "ab"),
+            ("build/c.rs", "ab"),
+            ("long.txt", &format!("{}\nab", long_line)),
+            ("sub/d.rs", "xab ab"),
+            (".hidden/e.rs", "ab"),
+        ]);
+        let (results, finished) = search(&dir, query("ab", false), 1);
+        assert_eq!(results, [
+            ("a.rs".to_string(), 0, 0..2),
+            ("a.rs".to_string(), 1, 3..5),
+            ("long.txt".to_string(), 1, 0..2),
+            ("sub/d.rs".to_string(), 0, 4..6),
+        ]);
+        assert_eq!(finished, Some((3, 4)));
+
+        // a later alternative can still be the whole word
+        let (results, _) = search(&dir, query("a|ab", true), 1);
+        assert_eq!(results.len(), 4);
+
+        let mut include_query = query("ab", false);
+        include_query.include = vec!["*.rs".to_string()];
+        include_query.exclude = vec!["sub/".to_string()];
+        let (results, _) = search(&dir, include_query, 1);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all( | (path, _, _) | path == "a.rs"));
+    }
+
+    #[test]
+    fn cancelled_search_sends_nothing() {
+        let dir = TestDir::new("cancelled", &[("a.rs", "ab")]);
+        let (results, finished) = search(&dir, query("ab", false), 2);
+        assert!(results.is_empty());
+        assert_eq!(finished, None);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     makepad_code_editor::code_editor::*,
+    makepad_code_editor::find_bar::*,
     makepad_platform::*,
     makepad_draw::*,
     makepad_widgets::*,
@@ -17,7 +18,15 @@ use crate::{
             BuildManager,
             BuildManagerAction
         },
-    }
+    },
+    lsp::lsp_manager::{
+        LspManager,
+        LspManagerAction
+    },
+    search::{
+        search_manager::SearchManager,
+        search_list::SearchListAction,
+    },
 };
 
 live_design!{
@@ -25,10 +34,18 @@ live_design!{
     import makepad_widgets::base::*;
     import makepad_widgets::theme_desktop_dark::*;
     import makepad_code_editor::code_editor::CodeEditor;
+    import makepad_code_editor::find_bar::FindBar;
     
     import makepad_studio::build_manager::run_view::RunView;
     import makepad_studio::build_manager::log_list::LogList;
     import makepad_studio::build_manager::run_list::RunList;
+    import makepad_studio::search::search_list::SearchPanel;
+    
+    CodeEditorTab = <View> {
+        flow: Down,
+        find_bar = <FindBar> {}
+        editor = <CodeEditor> {}
+    }
     
     Logo = <Button> {
         draw_icon: {
@@ -77,7 +94,7 @@ live_design!{
                 paste = Item {name: "Paste", enabled: false}
                 find = Item {name: "Find", enabled: false}
                 replace = Item {name: "Replace", enabled: false}
-                find_in_files = Item {name: "Find in Files", enabled: false}
+                find_in_files = Item {name: "Find in Files", shift: true, key: KeyF}
                 replace_in_files = Item {name: "Replace in Files", enabled: false}
                 
                 selection = Sub {name: "Selection", items: [select_all]}
@@ -188,7 +205,7 @@ live_design!{
                     kind: LogList
                 }
                 
-                CodeEditor = <CodeEditor> {}
+                CodeEditor = <CodeEditorTab> {}
                 EditFirst = <RectView> {
                     draw_bg: {color: #052329}
                     <View> {
@@ -239,9 +256,7 @@ live_design!{
                 }
                 RunList = <RunList> {
                 }
-                Search = <RectView> {
-                    draw_bg: {color: #2}
-                }
+                Search = <SearchPanel> {}
                 RunView = <RunView> {}
                 FileTree = <FileTree> {}
                 LogList = <LogList> {}
@@ -254,7 +269,9 @@ live_design!{
 pub struct App {
     #[live] ui: WidgetRef,
     #[live] build_manager: BuildManager,
+    #[live] lsp_manager: LspManager,
     #[rust] file_system: FileSystem,
+    #[rust] search_manager: SearchManager,
 }
 
 impl LiveHook for App {
@@ -265,6 +282,8 @@ impl LiveHook for App {
         crate::build_manager::run_list::live_design(cx);
         crate::build_manager::log_list::live_design(cx);
         crate::build_manager::run_view::live_design(cx);
+        crate::lsp::lsp_manager::live_design(cx);
+        crate::search::search_list::live_design(cx);
         // for macos
         cx.start_stdin_service();
     }
@@ -272,6 +291,7 @@ impl LiveHook for App {
     fn after_new_from_doc(&mut self, cx: &mut Cx) {
         self.file_system.init(cx);
         self.build_manager.init(cx);
+        self.lsp_manager.init(cx, &self.file_system);
         
         //self.file_system.request_open_file(live_id!(file1), "examples/news_feed/src/app.rs".into());
     }
@@ -289,6 +309,30 @@ impl App {
             self.file_system.ensure_unique_tab_names(cx, &dock)
         }
     }
+    
+    // Selects the tab of the given file, or opens a new one if there is none.
+    fn select_or_open_file_tab(&mut self, cx: &mut Cx, dock: &DockRef, file_id: FileNodeId) -> LiveId {
+        match self.file_system.file_node_id_to_tab_id(file_id) {
+            Some(tab_id) => {
+                dock.select_tab(cx, tab_id);
+                tab_id
+            }
+            None => {
+                let tab_id = LiveId::unique();
+                self.file_system.request_open_file(tab_id, file_id);
+                dock.create_and_select_tab(cx, live_id!(edit_tabs), tab_id, live_id!(CodeEditor), "".to_string(), TabClosable::Yes);
+                self.file_system.ensure_unique_tab_names(cx, dock);
+                tab_id
+            }
+        }
+    }
+    
+    fn open_search(&self, cx: &mut Cx, dock: &DockRef) {
+        dock.select_tab(cx, live_id!(search));
+        if let Some(query) = dock.item(live_id!(search)).text_input(id!(search_query)).borrow() {
+            query.set_key_focus(cx);
+        }
+    }
 }
 
 impl AppMain for App {
@@ -299,6 +343,7 @@ impl AppMain for App {
         let file_tree = self.ui.file_tree(id!(file_tree));
         let log_list = self.ui.portal_list(id!(log_list));
         let run_list = self.ui.flat_list(id!(run_list));
+        let search_list = self.ui.portal_list(id!(search_results));
         if let Event::Draw(event) = event {
             //let dt = profile_start();
             let cx = &mut Cx2d::new(cx, event);
@@ -322,6 +367,9 @@ impl AppMain for App {
                 else if let Some(mut run_list) = run_list.has_widget(&next).borrow_mut() {
                     self.build_manager.draw_run_list(cx, &mut *run_list);
                 }
+                else if let Some(mut search_list) = search_list.has_widget(&next).borrow_mut() {
+                    self.search_manager.draw_search_list(cx, &mut *search_list);
+                }
                 else if let Some(mut code_editor) = next.as_code_editor().borrow_mut() {
                     // lets fetch a session
                     let current_id = dock.drawing_item_id().unwrap();
@@ -336,6 +384,13 @@ impl AppMain for App {
         
         if let Event::Destruct = event {
             self.build_manager.clear_active_builds();
+            self.lsp_manager.shutdown();
+        }
+        
+        if let Event::MacosMenuCommand(item) = event {
+            if *item == live_id!(find_in_files) {
+                self.open_search(cx, &dock);
+            }
         }
         
         if let Event::KeyDown(KeyEvent {
@@ -359,6 +414,9 @@ impl AppMain for App {
                 FileSystemAction::TreeLoaded => {
                     self.open_code_file_by_path(cx, "examples/news_feed/src/app.rs");
                 }
+                FileSystemAction::FileSaved(file_id) => {
+                    self.lsp_manager.did_save(file_id);
+                }
                 FileSystemAction::RecompileNeeded => {
                     self.build_manager.start_recompile_timer(cx, &self.ui);
                 }
@@ -367,6 +425,36 @@ impl AppMain for App {
                     self.build_manager.clear_log(cx, &dock, &mut self.file_system);
                     log_list.redraw(cx);
                 }
+                FileSystemAction::SearchResults {id, results} => {
+                    self.search_manager.add_results(cx, &self.ui, id, results);
+                }
+                FileSystemAction::SearchFinished {id, file_count, match_count, is_truncated} => {
+                    self.search_manager.search_finished(cx, &self.ui, id, file_count, match_count, is_truncated);
+                }
+                FileSystemAction::SearchFailed {id, error} => {
+                    self.search_manager.search_failed(cx, &self.ui, id, &error);
+                }
+            }
+        }
+        
+        self.search_manager.handle_event(cx, event, &self.ui, &mut self.file_system, &dock);
+        
+        for action in self.lsp_manager.handle_event(cx, event, &mut self.file_system, &dock) {
+            match action {
+                LspManagerAction::ShowInfo {tab_id, position, text} => {
+                    if let Some(mut editor) = dock.item(tab_id).code_editor(id!(editor)).borrow_mut() {
+                        editor.show_info(cx, position, text);
+                    }
+                }
+                LspManagerAction::Completions {tab_id, position, items} => {
+                    if let (Some(mut editor), Some(session)) = (dock.item(tab_id).code_editor(id!(editor)).borrow_mut(), self.file_system.get_session_mut(tab_id)) {
+                        editor.show_completions(cx, session, position, items);
+                    }
+                }
+                LspManagerAction::JumpTo {file_id, position} => {
+                    let tab_id = self.select_or_open_file_tab(cx, &dock, file_id);
+                    self.lsp_manager.jump_to(cx, &mut self.file_system, &dock, tab_id, position);
+                }
             }
         }
         
@@ -375,17 +463,58 @@ impl AppMain for App {
             if let Some(mut run_view) = item.as_run_view().borrow_mut() {
                 run_view.handle_event(cx, event, item_id, &mut self.build_manager);
             }
-            else if let Some(mut code_editor) = item.as_code_editor().borrow_mut() {
+            else if let Some(mut code_editor) = item.code_editor(id!(editor)).borrow_mut() {
+                let mut did_change = false;
+                let mut lsp_requests = Vec::new();
                 if let Some(session) = self.file_system.get_session_mut(item_id) {
+                    let find_bar = item.find_bar(id!(find_bar));
                     for action in code_editor.handle_event(cx, event, session) {
                         match action {
+                            CodeEditorAction::Hover(_) |
+                            CodeEditorAction::GoToDefinition(_) |
+                            CodeEditorAction::Complete(_) |
+                            CodeEditorAction::DocumentSymbols => {
+                                let cursor = session.selections()[session.last_added_selection_index().unwrap()].cursor.position;
+                                lsp_requests.push((action, cursor));
+                            }
                             CodeEditorAction::TextDidChange => {
-                                // lets write the file
-                                self.file_system.request_save_file(item_id)
+                                did_change = true;
+                                if find_bar.is_open() {
+                                    find_bar.set_match_count(cx, session.selected_find_match_index(), session.find_matches().len());
+                                }
+                            }
+                            CodeEditorAction::Find => {
+                                // use a selection on a single line as the query
+                                let selection = session.selections()[session.last_added_selection_index().unwrap()];
+                                let query = if !selection.is_empty() && selection.start().line_index == selection.end().line_index {
+                                    Some(session.copy())
+                                }
+                                else {
+                                    None
+                                };
+                                find_bar.open(cx, query.as_deref());
+                                session.set_find_query(find_bar.find_query(cx));
+                                session.find_nearest();
+                                find_bar.set_match_count(cx, session.selected_find_match_index(), session.find_matches().len());
+                                code_editor.scroll_cursor_into_view(cx);
+                                item.redraw(cx);
                             }
                         }
                     }
                 }
+                if did_change {
+                    // lets write the file
+                    self.file_system.request_save_file(item_id);
+                }
+                for (action, cursor) in lsp_requests {
+                    match action {
+                        CodeEditorAction::Hover(position) => self.lsp_manager.hover(&self.file_system, item_id, position),
+                        CodeEditorAction::GoToDefinition(position) => self.lsp_manager.definition(&self.file_system, item_id, position),
+                        CodeEditorAction::Complete(position) => self.lsp_manager.completion(&self.file_system, item_id, position),
+                        CodeEditorAction::DocumentSymbols => self.lsp_manager.document_symbols(&self.file_system, item_id, cursor),
+                        _ => ()
+                    }
+                }
             }
         }
         
@@ -406,6 +535,54 @@ impl AppMain for App {
         
         let actions = self.ui.handle_widget_event(cx, event);
         
+        for (item_id, item) in dock.borrow_mut().unwrap().visible_items() {
+            let find_bar = item.find_bar(id!(find_bar));
+            let action = find_bar.action(&actions);
+            if let FindBarAction::None = action {
+                continue
+            }
+            if let Some(mut code_editor) = item.code_editor(id!(editor)).borrow_mut() {
+                let mut did_edit = false;
+                if let Some(session) = self.file_system.get_session_mut(item_id) {
+                    match action {
+                        FindBarAction::Find(query) => {
+                            session.set_find_query(query);
+                            session.find_nearest();
+                        }
+                        FindBarAction::FindNext => {
+                            session.find_next();
+                        }
+                        FindBarAction::FindPrev => {
+                            session.find_prev();
+                        }
+                        FindBarAction::SelectAll => {
+                            if session.select_all_occurrences() {
+                                code_editor.set_key_focus(cx);
+                            }
+                        }
+                        FindBarAction::Replace(replacement) => {
+                            did_edit = session.replace(&replacement);
+                        }
+                        FindBarAction::ReplaceAll(replacement) => {
+                            did_edit = session.replace_all(&replacement) > 0;
+                        }
+                        FindBarAction::Close => {
+                            session.set_find_query(None);
+                            find_bar.close(cx);
+                            code_editor.set_key_focus(cx);
+                        }
+                        FindBarAction::None => ()
+                    }
+                    find_bar.set_match_count(cx, session.selected_find_match_index(), session.find_matches().len());
+                    code_editor.scroll_cursor_into_view(cx);
+                }
+                if did_edit {
+                    self.file_system.request_save_file(item_id);
+                }
+            }
+            item.redraw(cx);
+        }
+        
         for (item_id, item) in run_list.items_with_actions(&actions) {
             for action in self.build_manager.handle_run_list(cx, &run_list, item_id, item, &actions) {
                 match action {
@@ -424,6 +601,22 @@ impl AppMain for App {
             }
         }
         
+        self.search_manager.handle_search_panel(cx, &self.ui, &actions, &mut self.file_system);
+        
+        for (item_id, item) in search_list.items_with_actions(&actions) {
+            for action in self.search_manager.handle_search_list(cx, &search_list, item_id, item, &actions) {
+                match action {
+                    SearchListAction::JumpTo {path, start, length} => {
+                        if let Some(file_id) = self.file_system.path_to_file_node_id(&path) {
+                            let tab_id = self.select_or_open_file_tab(cx, &dock, file_id);
+                            self.search_manager.jump_to(cx, &mut self.file_system, &dock, tab_id, start, length);
+                        }
+                    }
+                    _ => ()
+                }
+            }
+        }
+        
         for (item_id, item) in log_list.items_with_actions(&actions) {
             for action in self.build_manager.handle_log_list(cx, &log_list, item_id, item, &actions) {
                 match action {
@@ -433,7 +626,7 @@ impl AppMain for App {
                             if let Some(tab_id) = self.file_system.file_node_id_to_tab_id(file_id){
                                 dock.select_tab(cx, tab_id);
                                 // ok lets scroll into view
-                                if let Some(mut editor) = dock.item(tab_id).as_code_editor().borrow_mut() {
+                                if let Some(mut editor) = dock.item(tab_id).code_editor(id!(editor)).borrow_mut() {
                                     if let Some(session) = self.file_system.get_session_mut(tab_id) {
                                         editor.set_cursor_and_scroll(cx, start, length, session);
                                         editor.set_key_focus(cx);
@@ -42,6 +42,8 @@ live_design!{
     }
 }
 pub const MAX_SWAPCHAIN_HISTORY: usize = 4;
+/// The decoration id of build errors and warnings.
+pub const BUILD_DECORATION_ID: usize = 0;
 pub struct ActiveBuild {
     pub log_index: String,
     pub item_id: LiveId,
@@ -257,7 +259,7 @@ impl BuildManager {
     
     pub fn clear_log(&mut self, cx: &mut Cx, dock: &DockRef, file_system: &mut FileSystem) {
         // lets clear all log related decorations
-        file_system.clear_all_decorations();
+        file_system.remove_all_decorations(BUILD_DECORATION_ID);
         file_system.redraw_all_views(cx, dock);
         self.log.clear();
     }
@@ -337,7 +339,7 @@ impl BuildManager {
                         if loc.level == LogItemLevel::Warning ||
                         loc.level == LogItemLevel::Error {
                             file_system.add_decoration(file_id, Decoration::new(
-                                0,
+                                BUILD_DECORATION_ID,
                                 pos,
                                 pos + loc.length
                             ));
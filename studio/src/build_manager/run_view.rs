@@ -127,6 +127,9 @@ impl RunView {
             Hit::KeyUp(e) => {
                 manager.send_host_to_stdin(run_view_id, HostToStdin::KeyUp(e));
             }
+            Hit::TextInput(e) => {
+                manager.send_host_to_stdin(run_view_id, HostToStdin::TextInput(e));
+            }
             _ => ()
         }
         let rect = self.draw_app.area().get_rect(cx);
@@ -11,7 +11,7 @@ use {
         net::{TcpListener, TcpStream},
         sync::mpsc::{self, Receiver, Sender, TryRecvError},
         thread,
-        path::PathBuf
+        path::{Path, PathBuf}
     },
 };
 
@@ -26,6 +26,8 @@ pub struct FileClientInner {
     pub request_sender: Sender<FileRequest>,
     pub action_signal: Signal,
     pub action_receiver: Receiver<FileClientAction>,
+    /// The directory served by the local file server, `None` when connected to a remote one.
+    pub root_path: Option<PathBuf>,
 }
 
 impl FileClient {
@@ -35,6 +37,11 @@ impl FileClient {
         }
     }
     
+    /// The directory on this machine that the file tree is loaded from, if it is local.
+    pub fn root_path(&self) -> Option<&Path> {
+        self.inner.as_ref()?.root_path.as_deref()
+    }
+    
     pub fn send_request(&mut self, request: FileRequest) {
         self.inner.as_ref().unwrap().request_sender.send(request).unwrap();
     }
@@ -76,7 +83,7 @@ impl FileClientInner {
         
         let base_path = env::current_dir().unwrap();
         let final_path = base_path.join(subdir.split('/').collect::<PathBuf>());
-        let mut server = FileServer::new(final_path);
+        let mut server = FileServer::new(final_path.clone());
         spawn_local_request_handler(
             request_receiver,
             server.connect(Box::new({
@@ -95,7 +102,8 @@ impl FileClientInner {
         Self {
             request_sender,
             action_signal,
-            action_receiver
+            action_receiver,
+            root_path: Some(final_path),
         }
     }
     
@@ -111,7 +119,8 @@ impl FileClientInner {
         Self {
             request_sender,
             action_signal,
-            action_receiver
+            action_receiver,
+            root_path: None,
         }
     }
     
@@ -1,7 +1,7 @@
 use {
     std::collections::{HashMap, hash_map},
     crate::{
-        makepad_code_editor::{Document, decoration::{Decoration, DecorationSet}, Session},
+        makepad_code_editor::{Document, decoration::{Decoration, DecorationSet}, Session, tokenizer::Language},
         makepad_platform::*,
         makepad_platform::makepad_live_compiler::LiveFileChange,
         makepad_draw::*,
@@ -13,8 +13,11 @@ use {
             FileError,
             FileResponse,
             FileClientAction,
+            FileNotification,
             FileNodeData,
             FileTreeData,
+            SearchQuery,
+            SearchResult,
         },
     },
 };
@@ -27,7 +30,8 @@ pub struct FileSystem {
     pub path_to_file_node_id: HashMap<String, FileNodeId>,
     pub tab_id_to_file_node_id: HashMap<LiveId, FileNodeId>,
     pub tab_id_to_session: HashMap<LiveId, Session>,
-    pub open_documents: HashMap<FileNodeId, OpenDoc>
+    pub open_documents: HashMap<FileNodeId, OpenDoc>,
+    pub last_search_id: u64,
 }
 
 pub enum OpenDoc {
@@ -57,8 +61,12 @@ pub struct FileEdge {
 
 pub enum FileSystemAction {
     TreeLoaded,
+    FileSaved(FileNodeId),
     RecompileNeeded,
-    LiveReloadNeeded(LiveFileChange)
+    LiveReloadNeeded(LiveFileChange),
+    SearchResults {id: u64, results: Vec<SearchResult>},
+    SearchFinished {id: u64, file_count: usize, match_count: usize, is_truncated: bool},
+    SearchFailed {id: u64, error: String},
 }
 
 impl FileSystem {
@@ -115,7 +123,7 @@ impl FileSystem {
                         // dock.select_tab(cx, dock, state, live_id!(file_tree).into(), live_id!(file_tree).into(), Animate::No);
                     }
                     FileResponse::OpenFile(result) => match result {
-                        Ok((_unix_path, data, id)) => {
+                        Ok((unix_path, data, id)) => {
                             let file_id = FileNodeId(LiveId(id));
                             let dock = ui.dock(id!(dock));
                             for (tab_id, file_id) in &self.tab_id_to_file_node_id {
@@ -125,7 +133,7 @@ impl FileSystem {
                             }
                             if let Some(OpenDoc::Decorations(dec)) = self.open_documents.get(&file_id) {
                                 let dec = dec.clone();
-                                self.open_documents.insert(file_id, OpenDoc::Document(Document::new(data.into(), dec)));
+                                self.open_documents.insert(file_id, OpenDoc::Document(Document::new(data.into(), dec, Language::from_path(&unix_path))));
                             }else {panic!()}
                             
                             ui.redraw(cx);
@@ -138,7 +146,8 @@ impl FileSystem {
                         }
                     }
                     FileResponse::SaveFile(result) => match result {
-                        Ok((path, old, new, _id)) => {
+                        Ok((path, old, new, id)) => {
+                            dispatch_action(cx, FileSystemAction::FileSaved(FileNodeId(LiveId(id))));
                             // alright file has been saved
                             // now we need to check if a live_design!{} changed or something outside it
                             if old != new {
@@ -174,9 +183,21 @@ impl FileSystem {
                         // to see if we need a recompile
                         
                     }
+                    FileResponse::Search(result) => match result {
+                        Ok(_id) => {}
+                        Err((id, FileError::Unknown(error) | FileError::CannotOpen(error))) => {
+                            dispatch_action(cx, FileSystemAction::SearchFailed {id, error})
+                        }
+                    }
                 },
-                FileClientAction::Notification(_notification) => {
-                    //self.editors.handle_collab_notification(cx, &mut state.editor_state, notification)
+                FileClientAction::Notification(notification) => match notification {
+                    FileNotification::SearchResults {id, results} => {
+                        dispatch_action(cx, FileSystemAction::SearchResults {id, results})
+                    }
+                    FileNotification::SearchFinished {id, file_count, match_count, is_truncated} => {
+                        dispatch_action(cx, FileSystemAction::SearchFinished {id, file_count, match_count, is_truncated})
+                    }
+                    FileNotification::FileChangedOnDisk => {}
                 }
             }
         }
@@ -207,6 +228,14 @@ impl FileSystem {
         };
     }
     
+    /// Starts a search across the files in the file tree, and returns its id. This cancels the
+    /// previous search, if it is still running.
+    pub fn request_search(&mut self, query: SearchQuery) -> u64 {
+        self.last_search_id += 1;
+        self.file_client.send_request(FileRequest::Search(query, self.last_search_id));
+        self.last_search_id
+    }
+    
     pub fn clear_decorations(&mut self, file_node_id: &FileNodeId) {
         // ok lets see if we have a document
         // ifnot, we create a new one
@@ -217,6 +246,15 @@ impl FileSystem {
         };
     }
     
+    pub fn remove_all_decorations(&mut self, id: usize) {
+        for document in self.open_documents.values_mut() {
+            match document {
+                OpenDoc::Decorations(dec) => dec.remove_decorations(id),
+                OpenDoc::Document(doc) => doc.remove_decorations(id),
+            }
+        }
+    }
+    
     pub fn clear_all_decorations(&mut self) {
         // ok lets see if we have a document
         // ifnot, we create a new one
@@ -3,6 +3,8 @@
 pub mod app;
 pub mod file_system;
 pub mod build_manager;
+pub mod lsp;
+pub mod search;
 
 //pub use makepad_code_editor;
 pub use makepad_file_protocol;
@@ -0,0 +1,466 @@
+use std::fmt;
+
+/// A dynamically typed json value.
+///
+/// Language server messages have a lot of optional and union typed fields, and servers are free
+/// to send fields we don't know about, which the derived `DeJson` impls reject. So the lsp
+/// client parses messages into a `JsonValue`, and picks out the fields it needs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonError {
+    pub msg: String,
+    pub offset: usize,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Json parse error: {} at byte {}", self.msg, self.offset)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl JsonValue {
+    pub fn parse(input: &str) -> Result<JsonValue, JsonError> {
+        let mut parser = JsonParser {input: input.as_bytes(), pos: 0};
+        let value = parser.value(0) ?;
+        parser.skip_whitespace();
+        if parser.pos != parser.input.len() {
+            return Err(parser.err("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// Builds an object from a list of fields, leaving out the fields that are null.
+    pub fn object<K: Into<String>>(fields: impl IntoIterator<Item = (K, JsonValue)>) -> JsonValue {
+        JsonValue::Object(
+            fields.into_iter()
+                .filter( | (_, value) | !value.is_null())
+                .map( | (key, value) | (key.into(), value))
+                .collect()
+        )
+    }
+
+    /// Returns the field with the given key, or `Null` if this isn't an object or the field
+    /// doesn't exist.
+    pub fn get(&self, key: &str) -> &JsonValue {
+        if let JsonValue::Object(fields) = self {
+            for (field_key, value) in fields {
+                if field_key == key {
+                    return value
+                }
+            }
+        }
+        &JsonValue::Null
+    }
+
+    /// Follows a path of keys through nested objects.
+    pub fn path(&self, keys: &[&str]) -> &JsonValue {
+        keys.iter().fold(self, | value, key | value.get(key))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(value) if *value >= 0.0 && value.fract() == 0.0 => Some(*value as u64),
+            _ => None
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Number(value) if value.fract() == 0.0 => Some(*value as i64),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, JsonValue)]> {
+        match self {
+            JsonValue::Object(fields) => Some(fields),
+            _ => None
+        }
+    }
+
+    fn write_string(f: &mut fmt::Formatter<'_>, string: &str) -> fmt::Result {
+        f.write_str("\"") ?;
+        for c in string.chars() {
+            match c {
+                '"' => f.write_str("\\\"") ?,
+                '\\' => f.write_str("\\\\") ?,
+                '\n' => f.write_str("\\n") ?,
+                '\r' => f.write_str("\\r") ?,
+                '\t' => f.write_str("\\t") ?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32) ?,
+                c => write!(f, "{}", c) ?
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(value) => write!(f, "{}", value),
+            JsonValue::Number(value) => {
+                if value.is_finite() {
+                    write!(f, "{}", value)
+                }
+                else {
+                    f.write_str("null")
+                }
+            }
+            JsonValue::String(value) => JsonValue::write_string(f, value),
+            JsonValue::Array(values) => {
+                f.write_str("[") ?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",") ?;
+                    }
+                    write!(f, "{}", value) ?;
+                }
+                f.write_str("]")
+            }
+            JsonValue::Object(fields) => {
+                f.write_str("{") ?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",") ?;
+                    }
+                    JsonValue::write_string(f, key) ?;
+                    write!(f, ":{}", value) ?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {JsonValue::Bool(value)}
+}
+
+impl From<u64> for JsonValue {
+    fn from(value: u64) -> Self {JsonValue::Number(value as f64)}
+}
+
+impl From<i64> for JsonValue {
+    fn from(value: i64) -> Self {JsonValue::Number(value as f64)}
+}
+
+impl From<usize> for JsonValue {
+    fn from(value: usize) -> Self {JsonValue::Number(value as f64)}
+}
+
+impl From<f64> for JsonValue {
+    fn from(value: f64) -> Self {JsonValue::Number(value)}
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {JsonValue::String(value.to_string())}
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {JsonValue::String(value)}
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(values: Vec<JsonValue>) -> Self {JsonValue::Array(values)}
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => JsonValue::Null
+        }
+    }
+}
+
+// arbitrarily deep nesting would let a server overflow our stack
+const MAX_DEPTH: usize = 256;
+
+struct JsonParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn err(&self, msg: &str) -> JsonError {
+        JsonError {msg: msg.to_string(), offset: self.pos}
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), JsonError> {
+        if self.input[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        }
+        else {
+            Err(self.err(&format!("expected {}", literal)))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.err("nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.input.get(self.pos) {
+            Some(b'n') => {
+                self.expect("null") ?;
+                Ok(JsonValue::Null)
+            }
+            Some(b't') => {
+                self.expect("true") ?;
+                Ok(JsonValue::Bool(true))
+            }
+            Some(b'f') => {
+                self.expect("false") ?;
+                Ok(JsonValue::Bool(false))
+            }
+            Some(b'"') => Ok(JsonValue::String(self.string() ?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                self.skip_whitespace();
+                if let Some(b']') = self.input.get(self.pos) {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(values));
+                }
+                loop {
+                    values.push(self.value(depth + 1) ?);
+                    self.skip_whitespace();
+                    match self.input.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(JsonValue::Array(values));
+                        }
+                        _ => return Err(self.err("expected , or ]"))
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if let Some(b'}') = self.input.get(self.pos) {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.input.get(self.pos) != Some(&b'"') {
+                        return Err(self.err("expected key"));
+                    }
+                    let key = self.string() ?;
+                    self.skip_whitespace();
+                    self.expect(":") ?;
+                    fields.push((key, self.value(depth + 1) ?));
+                    self.skip_whitespace();
+                    match self.input.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(JsonValue::Object(fields));
+                        }
+                        _ => return Err(self.err("expected , or }"))
+                    }
+                }
+            }
+            Some(_) => Err(self.err("unexpected character")),
+            None => Err(self.err("unexpected end of input")),
+        }
+    }
+
+    fn number(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.input[start..self.pos]).unwrap();
+        match text.parse() {
+            Ok(value) => Ok(JsonValue::Number(value)),
+            Err(_) => Err(JsonError {msg: format!("invalid number {}", text), offset: start})
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self.input.get(self.pos..self.pos + 4).ok_or_else( || self.err("unexpected end of input")) ?;
+        // from_str_radix also takes a sign, which json doesn't
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(self.err("invalid unicode escape"));
+        }
+        let value = u32::from_str_radix(std::str::from_utf8(digits).unwrap(), 16).unwrap();
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        // skip the opening quote
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.input.get(self.pos) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    // the input is a str, and we only ever split it at ascii characters
+                    return Ok(String::from_utf8(bytes).unwrap());
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escape = *self.input.get(self.pos).ok_or_else( || self.err("unexpected end of input")) ?;
+                    self.pos += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4() ?;
+                            if (0xD800..0xDC00).contains(&code) {
+                                // a high surrogate has to be followed by an escaped low surrogate
+                                self.expect("\\u") ?;
+                                let low = self.hex4() ?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(self.err("invalid surrogate pair"));
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err(self.err("invalid escape"))
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(byte) => {
+                    bytes.push(*byte);
+                    self.pos += 1;
+                }
+                None => return Err(self.err("unterminated string"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(JsonValue::parse(" null "), Ok(JsonValue::Null));
+        assert_eq!(JsonValue::parse("true"), Ok(JsonValue::Bool(true)));
+        assert_eq!(JsonValue::parse("-1.5e2"), Ok(JsonValue::Number(-150.0)));
+        assert_eq!(JsonValue::parse(r#""a\"\\\/\n\u00e9\ud83d\ude00""#), Ok(JsonValue::String("a\"\\/\né😀".into())));
+        assert_eq!(JsonValue::parse("\"é\""), Ok(JsonValue::String("é".into())));
+        let value = JsonValue::parse(r#"{"a": [1, {"b": null}], "c": "d"}"#).unwrap();
+        assert_eq!(value.path(&["a"]).as_array().unwrap()[0].as_u64(), Some(1));
+        assert!(value.path(&["a"]).as_array().unwrap()[1].get("b").is_null());
+        assert_eq!(value.get("c").as_str(), Some("d"));
+        assert!(value.path(&["c", "missing"]).is_null());
+    }
+
+    #[test]
+    fn parse_errors() {
+        for input in [
+            "",
+            "nul",
+            "[1,",
+            "[1 2]",
+            "{\"a\" 1}",
+            "{1: 2}",
+            "\"abc",
+            "\"\\x\"",
+            "\"\\u12\"",
+            "\"\\u+123\"",
+            "\"\\ud83d\\u0041\"",
+            "1 2",
+            "-",
+        ] {
+            assert!(JsonValue::parse(input).is_err(), "{:?}", input);
+        }
+        assert_eq!(JsonValue::parse("[1] x").unwrap_err().offset, 4);
+        let deep = "[".repeat(MAX_DEPTH + 2);
+        assert_eq!(JsonValue::parse(&deep).unwrap_err().msg, "nested too deeply");
+    }
+
+    #[test]
+    fn numbers() {
+        let value = JsonValue::parse("[3, -3, 3.5, 1e20]").unwrap();
+        let values = value.as_array().unwrap();
+        assert_eq!(values[0].as_u64(), Some(3));
+        assert_eq!(values[0].as_i64(), Some(3));
+        assert_eq!(values[1].as_u64(), None);
+        assert_eq!(values[1].as_i64(), Some(-3));
+        assert_eq!(values[2].as_u64(), None);
+        assert_eq!(values[2].as_f64(), Some(3.5));
+    }
+
+    #[test]
+    fn display_parses_back() {
+        let input = r#"{"a":[1,2.5,true,null],"b":"x\"\\\n\t\u0001é","c":{}}"#;
+        let value = JsonValue::parse(input).unwrap();
+        assert_eq!(value.to_string(), input);
+        assert_eq!(JsonValue::parse(&value.to_string()), Ok(value));
+    }
+
+    #[test]
+    fn object_leaves_out_null_fields() {
+        let value = JsonValue::object([("a", 1u64.into()), ("b", JsonValue::Null), ("c", None::<u64>.into())]);
+        assert_eq!(value.to_string(), r#"{"a":1}"#);
+    }
+}
@@ -0,0 +1,758 @@
+use {
+    crate::{
+        makepad_platform::*,
+        makepad_code_editor::text::{Change, Position, Text},
+        lsp::{
+            json::JsonValue,
+            lsp_protocol::*,
+        },
+    },
+    std::{
+        collections::HashMap,
+        io::{self, BufReader, Read, Write},
+        path::Path,
+        process::{Child, Command, Stdio},
+        sync::mpsc::{self, Sender, TryRecvError},
+        thread,
+    },
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LspRequestId(pub u64);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LspEvent {
+    /// The server finished initializing, and queued messages have been sent.
+    Initialized,
+    Diagnostics {uri: String, diagnostics: Vec<LspDiagnostic>},
+    Hover {request_id: LspRequestId, uri: String, position: LspPosition, hover: Option<LspHover>},
+    Definition {request_id: LspRequestId, uri: String, locations: Vec<LspLocation>},
+    Completion {request_id: LspRequestId, uri: String, position: LspPosition, items: Vec<LspCompletionItem>},
+    DocumentSymbols {request_id: LspRequestId, uri: String, symbols: Vec<LspDocumentSymbol>},
+    /// A request failed, or the server sent something we could not make sense of.
+    Error {request_id: Option<LspRequestId>, message: String},
+    /// A `window/showMessage` or `window/logMessage` from the server.
+    Message(String),
+    /// The server closed its output, which means it exited or crashed.
+    Exited,
+}
+
+enum LspIncoming {
+    Message(LspMessage),
+    Error(String),
+    Closed,
+}
+
+enum PendingRequest {
+    Initialize,
+    Shutdown,
+    Hover {uri: String, position: LspPosition},
+    Definition {uri: String},
+    Completion {uri: String, position: LspPosition},
+    DocumentSymbols {uri: String},
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TextDocumentSyncKind {
+    None,
+    Full,
+    Incremental,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LspClientState {
+    Initializing,
+    Running,
+    ShuttingDown,
+    Exited,
+}
+
+/// The text of an open document as the server has last been told it is.
+///
+/// Edits are sent to the server as ranges into the text before the edit, in utf16 offsets, so we
+/// keep our own copy to convert the byte positions of code editor changes against.
+struct LspDocument {
+    version: i64,
+    text: Text,
+}
+
+/// A client for a language server that talks json-rpc over a pair of byte streams.
+///
+/// The streams are serviced by background threads, and incoming messages are picked up on the
+/// UI thread by `handle_messages`. A client can be attached to any reader/writer pair, so a fake
+/// server can drive it just as well as a real one started with `spawn`.
+pub struct LspClient {
+    child: Option<Child>,
+    outgoing: Sender<String>,
+    incoming: ToUIReceiver<LspIncoming>,
+    state: LspClientState,
+    next_request_id: u64,
+    pending_requests: HashMap<u64, PendingRequest>,
+    // messages held back until the server has answered initialize
+    queued_messages: Vec<String>,
+    sync_kind: TextDocumentSyncKind,
+    capabilities: JsonValue,
+    documents: HashMap<String, LspDocument>,
+}
+
+impl LspClient {
+    /// Starts a language server process, and talks to it over its stdin and stdout.
+    pub fn spawn(command: &str, args: &[String], root_path: &Path) -> io::Result<LspClient> {
+        let mut child = Command::new(command)
+            .args(args)
+            .current_dir(root_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn() ?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let mut client = LspClient::new(stdout, stdin, &path_to_uri(root_path));
+        client.child = Some(child);
+        Ok(client)
+    }
+
+    /// Attaches to a server that reads from `writer` and writes to `reader`, and starts
+    /// initializing it.
+    pub fn new(reader: impl Read + Send + 'static, writer: impl Write + Send + 'static, root_uri: &str) -> LspClient {
+        let incoming: ToUIReceiver<LspIncoming> = Default::default();
+        let incoming_sender = incoming.sender();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            loop {
+                match read_message(&mut reader) {
+                    Ok(Some(content)) => {
+                        let message = match JsonValue::parse(&content) {
+                            Ok(value) => match LspMessage::from_json(&value) {
+                                Some(message) => LspIncoming::Message(message),
+                                None => LspIncoming::Error(format!("Invalid message {}", content))
+                            }
+                            Err(err) => LspIncoming::Error(err.to_string())
+                        };
+                        if incoming_sender.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => {
+                        let _ = incoming_sender.send(LspIncoming::Closed);
+                        break;
+                    }
+                    Err(err) => {
+                        let _ = incoming_sender.send(LspIncoming::Error(err.to_string()));
+                        let _ = incoming_sender.send(LspIncoming::Closed);
+                        break;
+                    }
+                }
+            }
+        });
+
+        let (outgoing, outgoing_receiver) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            let mut writer = writer;
+            while let Ok(content) = outgoing_receiver.recv() {
+                if write_message(&mut writer, &content).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut client = LspClient {
+            child: None,
+            outgoing,
+            incoming,
+            state: LspClientState::Initializing,
+            next_request_id: 1,
+            pending_requests: HashMap::new(),
+            queued_messages: Vec::new(),
+            sync_kind: TextDocumentSyncKind::Incremental,
+            capabilities: JsonValue::Null,
+            documents: HashMap::new(),
+        };
+        client.send_initialize(root_uri);
+        client
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, LspClientState::Initializing | LspClientState::Running)
+    }
+
+    /// The capabilities the server announced in its initialize response.
+    pub fn capabilities(&self) -> &JsonValue {
+        &self.capabilities
+    }
+
+    pub fn is_document_open(&self, uri: &str) -> bool {
+        self.documents.contains_key(uri)
+    }
+
+    pub fn did_open(&mut self, uri: &str, language_id: &str, text: &Text) {
+        if self.documents.contains_key(uri) {
+            return
+        }
+        self.documents.insert(uri.to_string(), LspDocument {version: 0, text: text.clone()});
+        self.send_notification("textDocument/didOpen", JsonValue::object([
+            ("textDocument", JsonValue::object([
+                ("uri", uri.into()),
+                ("languageId", language_id.into()),
+                ("version", 0i64.into()),
+                ("text", text.to_string().into()),
+            ]))
+        ]));
+    }
+
+    /// Sends the changes made to an open document, in the order they were applied to it.
+    pub fn did_change(&mut self, uri: &str, changes: &[Change]) {
+        let Some(document) = self.documents.get_mut(uri) else {
+            return
+        };
+        if changes.is_empty() {
+            return
+        }
+        let mut content_changes = Vec::new();
+        for change in changes {
+            if self.sync_kind == TextDocumentSyncKind::Incremental {
+                let lines = document.text.as_lines();
+                let (range, text) = match change {
+                    Change::Insert(position, text) => {
+                        let position = LspPosition::from_position(lines, *position);
+                        (LspRange {start: position, end: position}, text.to_string())
+                    }
+                    Change::Delete(start, length) => (
+                        LspRange {
+                            start: LspPosition::from_position(lines, *start),
+                            end: LspPosition::from_position(lines, *start + *length)
+                        },
+                        String::new()
+                    )
+                };
+                content_changes.push(JsonValue::object([
+                    ("range", range.to_json()),
+                    ("text", text.into()),
+                ]));
+            }
+            document.text.apply_change(change.clone());
+        }
+        document.version += 1;
+        match self.sync_kind {
+            TextDocumentSyncKind::None => return,
+            TextDocumentSyncKind::Full => {
+                content_changes = vec![JsonValue::object([("text", document.text.to_string().into())])];
+            }
+            TextDocumentSyncKind::Incremental => ()
+        }
+        let params = JsonValue::object([
+            ("textDocument", JsonValue::object([
+                ("uri", uri.into()),
+                ("version", document.version.into()),
+            ])),
+            ("contentChanges", content_changes.into()),
+        ]);
+        self.send_notification("textDocument/didChange", params);
+    }
+
+    pub fn did_save(&mut self, uri: &str) {
+        if !self.documents.contains_key(uri) {
+            return
+        }
+        self.send_notification("textDocument/didSave", JsonValue::object([
+            ("textDocument", JsonValue::object([("uri", uri.into())]))
+        ]));
+    }
+
+    pub fn did_close(&mut self, uri: &str) {
+        if self.documents.remove(uri).is_none() {
+            return
+        }
+        self.send_notification("textDocument/didClose", JsonValue::object([
+            ("textDocument", JsonValue::object([("uri", uri.into())]))
+        ]));
+    }
+
+    pub fn hover(&mut self, uri: &str, position: Position) -> Option<LspRequestId> {
+        let position = self.lsp_position(uri, position) ?;
+        let params = text_document_position(uri, position);
+        Some(self.send_request("textDocument/hover", params, PendingRequest::Hover {uri: uri.to_string(), position}))
+    }
+
+    pub fn definition(&mut self, uri: &str, position: Position) -> Option<LspRequestId> {
+        let position = self.lsp_position(uri, position) ?;
+        let params = text_document_position(uri, position);
+        Some(self.send_request("textDocument/definition", params, PendingRequest::Definition {uri: uri.to_string()}))
+    }
+
+    pub fn completion(&mut self, uri: &str, position: Position) -> Option<LspRequestId> {
+        let position = self.lsp_position(uri, position) ?;
+        let params = text_document_position(uri, position);
+        Some(self.send_request("textDocument/completion", params, PendingRequest::Completion {uri: uri.to_string(), position}))
+    }
+
+    pub fn document_symbols(&mut self, uri: &str) -> Option<LspRequestId> {
+        if !self.documents.contains_key(uri) {
+            return None
+        }
+        let params = JsonValue::object([
+            ("textDocument", JsonValue::object([("uri", uri.into())]))
+        ]);
+        Some(self.send_request("textDocument/documentSymbol", params, PendingRequest::DocumentSymbols {uri: uri.to_string()}))
+    }
+
+    /// Asks the server to shut down. The server process is killed when the client is dropped if it
+    /// hasn't exited by then.
+    pub fn shutdown(&mut self) {
+        if self.is_running() {
+            self.send_request("shutdown", JsonValue::Null, PendingRequest::Shutdown);
+            self.state = LspClientState::ShuttingDown;
+        }
+    }
+
+    /// Processes the messages that came in from the server since the last call.
+    pub fn handle_messages(&mut self) -> Vec<LspEvent> {
+        let mut events = Vec::new();
+        loop {
+            match self.incoming.try_recv() {
+                Ok(LspIncoming::Message(message)) => self.handle_message(message, &mut events),
+                Ok(LspIncoming::Error(message)) => events.push(LspEvent::Error {request_id: None, message}),
+                Ok(LspIncoming::Closed) | Err(TryRecvError::Disconnected) => {
+                    if self.state != LspClientState::Exited {
+                        self.state = LspClientState::Exited;
+                        self.pending_requests.clear();
+                        events.push(LspEvent::Exited);
+                    }
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+        events
+    }
+
+    fn handle_message(&mut self, message: LspMessage, events: &mut Vec<LspEvent>) {
+        match message {
+            LspMessage::Response {id, result} => {
+                let Some(id) = id.as_u64() else {
+                    return
+                };
+                let Some(request) = self.pending_requests.remove(&id) else {
+                    return
+                };
+                let request_id = LspRequestId(id);
+                let result = match result {
+                    Ok(result) => result,
+                    Err(error) => {
+                        // a request the server cancelled is not worth reporting
+                        if error.code != REQUEST_CANCELLED && error.code != CONTENT_MODIFIED {
+                            events.push(LspEvent::Error {request_id: Some(request_id), message: error.message});
+                        }
+                        return
+                    }
+                };
+                match request {
+                    PendingRequest::Initialize => {
+                        self.sync_kind = match result.path(&["capabilities", "textDocumentSync"]) {
+                            JsonValue::Number(_) => sync_kind_from_json(result.path(&["capabilities", "textDocumentSync"])),
+                            JsonValue::Object(_) => sync_kind_from_json(result.path(&["capabilities", "textDocumentSync", "change"])),
+                            _ => TextDocumentSyncKind::None,
+                        };
+                        self.capabilities = result.get("capabilities").clone();
+                        self.state = LspClientState::Running;
+                        self.send_message(LspMessage::Notification {
+                            method: "initialized".into(),
+                            params: JsonValue::object::<&str>([])
+                        });
+                        for content in self.queued_messages.drain(..) {
+                            let _ = self.outgoing.send(content);
+                        }
+                        events.push(LspEvent::Initialized);
+                    }
+                    PendingRequest::Shutdown => {
+                        self.send_message(LspMessage::Notification {
+                            method: "exit".into(),
+                            params: JsonValue::Null
+                        });
+                    }
+                    PendingRequest::Hover {uri, position} => events.push(LspEvent::Hover {
+                        request_id,
+                        uri,
+                        position,
+                        hover: LspHover::from_json(&result)
+                    }),
+                    PendingRequest::Definition {uri} => events.push(LspEvent::Definition {
+                        request_id,
+                        uri,
+                        locations: LspLocation::from_json_result(&result)
+                    }),
+                    PendingRequest::Completion {uri, position} => events.push(LspEvent::Completion {
+                        request_id,
+                        uri,
+                        position,
+                        items: LspCompletionItem::from_json_result(&result)
+                    }),
+                    PendingRequest::DocumentSymbols {uri} => events.push(LspEvent::DocumentSymbols {
+                        request_id,
+                        uri,
+                        symbols: LspDocumentSymbol::from_json_result(&result)
+                    }),
+                }
+            }
+            LspMessage::Notification {method, params} => match method.as_str() {
+                "textDocument/publishDiagnostics" => {
+                    if let Some(uri) = params.get("uri").as_str() {
+                        events.push(LspEvent::Diagnostics {
+                            uri: uri.to_string(),
+                            diagnostics: params.get("diagnostics").as_array().unwrap_or(&[])
+                                .iter()
+                                .filter_map(LspDiagnostic::from_json)
+                                .collect()
+                        });
+                    }
+                }
+                "window/showMessage" | "window/logMessage" => {
+                    if let Some(message) = params.get("message").as_str() {
+                        events.push(LspEvent::Message(message.to_string()));
+                    }
+                }
+                _ => ()
+            }
+            LspMessage::Request {id, method, params} => {
+                // we don't act on server requests, but the server may wait for an answer
+                let result = match method.as_str() {
+                    "workspace/configuration" => JsonValue::Array(
+                        params.get("items").as_array().unwrap_or(&[]).iter().map( | _ | JsonValue::Null).collect()
+                    ),
+                    _ => JsonValue::Null
+                };
+                self.send_message(LspMessage::Response {id, result: Ok(result)});
+            }
+        }
+    }
+
+    fn lsp_position(&self, uri: &str, position: Position) -> Option<LspPosition> {
+        let document = self.documents.get(uri) ?;
+        Some(LspPosition::from_position(document.text.as_lines(), position))
+    }
+
+    fn send_initialize(&mut self, root_uri: &str) {
+        let name = root_uri.trim_end_matches('/').rsplit('/').next().unwrap_or("root");
+        let params = JsonValue::object([
+            ("processId", (std::process::id() as u64).into()),
+            ("clientInfo", JsonValue::object([("name", "Makepad Studio".into())])),
+            ("rootUri", root_uri.into()),
+            ("workspaceFolders", vec![JsonValue::object([
+                ("uri", root_uri.into()),
+                ("name", name.into()),
+            ])].into()),
+            ("capabilities", JsonValue::object([
+                ("general", JsonValue::object([
+                    ("positionEncodings", vec!["utf-16".into()].into()),
+                ])),
+                ("textDocument", JsonValue::object([
+                    ("synchronization", JsonValue::object([("didSave", true.into())])),
+                    ("publishDiagnostics", JsonValue::object([("relatedInformation", false.into())])),
+                    ("hover", JsonValue::object([
+                        ("contentFormat", vec!["plaintext".into(), "markdown".into()].into()),
+                    ])),
+                    ("definition", JsonValue::object([("linkSupport", true.into())])),
+                    ("completion", JsonValue::object([
+                        ("completionItem", JsonValue::object([("snippetSupport", true.into())])),
+                    ])),
+                    ("documentSymbol", JsonValue::object([
+                        ("hierarchicalDocumentSymbolSupport", true.into()),
+                    ])),
+                ])),
+            ])),
+        ]);
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+        self.pending_requests.insert(id, PendingRequest::Initialize);
+        // initialize goes out before anything that is queued
+        let _ = self.outgoing.send(LspMessage::Request {
+            id: id.into(),
+            method: "initialize".into(),
+            params
+        }.to_json().to_string());
+    }
+
+    fn send_request(&mut self, method: &str, params: JsonValue, request: PendingRequest) -> LspRequestId {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+        self.pending_requests.insert(id, request);
+        self.send_message(LspMessage::Request {
+            id: id.into(),
+            method: method.to_string(),
+            params
+        });
+        LspRequestId(id)
+    }
+
+    fn send_notification(&mut self, method: &str, params: JsonValue) {
+        self.send_message(LspMessage::Notification {
+            method: method.to_string(),
+            params
+        });
+    }
+
+    fn send_message(&mut self, message: LspMessage) {
+        let content = message.to_json().to_string();
+        match self.state {
+            LspClientState::Initializing => self.queued_messages.push(content),
+            LspClientState::Running | LspClientState::ShuttingDown => {
+                let _ = self.outgoing.send(content);
+            }
+            LspClientState::Exited => ()
+        }
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+const REQUEST_CANCELLED: i64 = -32800;
+const CONTENT_MODIFIED: i64 = -32801;
+
+fn sync_kind_from_json(value: &JsonValue) -> TextDocumentSyncKind {
+    match value.as_u64() {
+        Some(1) => TextDocumentSyncKind::Full,
+        Some(2) => TextDocumentSyncKind::Incremental,
+        _ => TextDocumentSyncKind::None,
+    }
+}
+
+fn text_document_position(uri: &str, position: LspPosition) -> JsonValue {
+    JsonValue::object([
+        ("textDocument", JsonValue::object([("uri", uri.into())])),
+        ("position", position.to_json()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::makepad_code_editor::text::Length,
+        std::{
+            sync::mpsc::Receiver,
+            time::{Duration, Instant},
+        },
+    };
+
+    /// The reading end of an in-memory pipe, which ends when all writers are dropped.
+    struct PipeReader {
+        receiver: Receiver<Vec<u8>>,
+        buffer: Vec<u8>,
+        pos: usize,
+    }
+
+    impl Read for PipeReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pos == self.buffer.len() {
+                match self.receiver.recv() {
+                    Ok(bytes) => {
+                        self.buffer = bytes;
+                        self.pos = 0;
+                    }
+                    Err(_) => return Ok(0)
+                }
+            }
+            let len = buf.len().min(self.buffer.len() - self.pos);
+            buf[..len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+            self.pos += len;
+            Ok(len)
+        }
+    }
+
+    struct PipeWriter(Sender<Vec<u8>>);
+
+    impl Write for PipeWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.send(buf.to_vec()).map_err( | _ | io::Error::from(io::ErrorKind::BrokenPipe)) ?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn pipe() -> (PipeReader, PipeWriter) {
+        let (sender, receiver) = mpsc::channel();
+        (PipeReader {receiver, buffer: Vec::new(), pos: 0}, PipeWriter(sender))
+    }
+
+    /// The other end of the streams of a client, which the test answers by hand.
+    struct FakeServer {
+        reader: BufReader<PipeReader>,
+        writer: PipeWriter,
+    }
+
+    impl FakeServer {
+        fn start(root_uri: &str) -> (LspClient, FakeServer) {
+            let (client_reader, server_writer) = pipe();
+            let (server_reader, client_writer) = pipe();
+            let client = LspClient::new(client_reader, client_writer, root_uri);
+            (client, FakeServer {reader: BufReader::new(server_reader), writer: server_writer})
+        }
+
+        fn receive(&mut self) -> LspMessage {
+            let content = read_message(&mut self.reader).unwrap().unwrap();
+            LspMessage::from_json(&JsonValue::parse(&content).unwrap()).unwrap()
+        }
+
+        fn receive_request(&mut self, expected_method: &str) -> (JsonValue, JsonValue) {
+            match self.receive() {
+                LspMessage::Request {id, method, params} if method == expected_method => (id, params),
+                message => panic!("expected a {} request, got {:?}", expected_method, message)
+            }
+        }
+
+        fn receive_notification(&mut self, expected_method: &str) -> JsonValue {
+            match self.receive() {
+                LspMessage::Notification {method, params} if method == expected_method => params,
+                message => panic!("expected a {} notification, got {:?}", expected_method, message)
+            }
+        }
+
+        fn respond(&mut self, id: JsonValue, result: JsonValue) {
+            let content = LspMessage::Response {id, result: Ok(result)}.to_json().to_string();
+            write_message(&mut self.writer, &content).unwrap();
+        }
+    }
+
+    /// Polls the client until it produces an event, because its messages arrive on another thread.
+    fn next_event(client: &mut LspClient) -> LspEvent {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let mut events = client.handle_messages();
+            if !events.is_empty() {
+                assert_eq!(events.len(), 1, "{:?}", events);
+                return events.remove(0)
+            }
+            assert!(Instant::now() < deadline, "timed out waiting for an event");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn talks_to_a_server() {
+        let uri = "file:///project/src/main.rs";
+        let (mut client, mut server) = FakeServer::start("file:///project");
+
+        let (id, params) = server.receive_request("initialize");
+        assert_eq!(params.get("rootUri").as_str(), Some("file:///project"));
+        assert_eq!(params.path(&["workspaceFolders"]).as_array().unwrap()[0].get("name").as_str(), Some("project"));
+
+        // these are held back until the server has answered initialize
+        client.did_open(uri, "rust", &Text::from("fn main() {\n    é\n}"));
+        client.did_change(uri, &[Change::Insert(Position {line_index: 1, byte_index: 6}, Text::from("x."))]);
+        let request_id = client.completion(uri, Position {line_index: 1, byte_index: 8}).unwrap();
+        assert!(client.handle_messages().is_empty());
+
+        server.respond(id, JsonValue::parse(r#"{"capabilities": {"textDocumentSync": {"change": 2}}}"#).unwrap());
+        assert_eq!(next_event(&mut client), LspEvent::Initialized);
+
+        server.receive_notification("initialized");
+        let params = server.receive_notification("textDocument/didOpen");
+        assert_eq!(params.path(&["textDocument", "uri"]).as_str(), Some(uri));
+        assert_eq!(params.path(&["textDocument", "languageId"]).as_str(), Some("rust"));
+        assert_eq!(params.path(&["textDocument", "version"]).as_i64(), Some(0));
+        assert_eq!(params.path(&["textDocument", "text"]).as_str(), Some("fn main() {\n    é\n}"));
+
+        let params = server.receive_notification("textDocument/didChange");
+        assert_eq!(params.path(&["textDocument", "version"]).as_i64(), Some(1));
+        let change = &params.get("contentChanges").as_array().unwrap()[0];
+        // é is two bytes but a single utf16 code unit
+        let position = LspPosition {line: 1, character: 5};
+        assert_eq!(LspRange::from_json(change.get("range")), Some(LspRange {start: position, end: position}));
+        assert_eq!(change.get("text").as_str(), Some("x."));
+
+        let (id, params) = server.receive_request("textDocument/completion");
+        assert_eq!(id.as_u64(), Some(request_id.0));
+        assert_eq!(params.path(&["textDocument", "uri"]).as_str(), Some(uri));
+        assert_eq!(LspPosition::from_json(params.get("position")), Some(LspPosition {line: 1, character: 7}));
+        server.respond(id, JsonValue::parse(r#"{"isIncomplete": false, "items": [
+            {"label": "len", "detail": "fn(&self) -> usize"},
+            {"label": "push", "insertText": "push(${1:value})", "insertTextFormat": 2}
+        ]}"#).unwrap());
+        match next_event(&mut client) {
+            LspEvent::Completion {request_id: id, uri: event_uri, position: event_position, items} => {
+                assert_eq!(id, request_id);
+                assert_eq!(event_uri, uri);
+                assert_eq!(event_position, LspPosition {line: 1, character: 7});
+                assert_eq!(items.len(), 2);
+                assert_eq!(items[0].label, "len");
+                assert_eq!(items[0].detail.as_deref(), Some("fn(&self) -> usize"));
+                assert_eq!(items[0].insert_text, "len");
+                assert!(!items[0].is_snippet);
+                assert_eq!(items[1].insert_text, "push(${1:value})");
+                assert!(items[1].is_snippet);
+            }
+            event => panic!("expected completions, got {:?}", event)
+        }
+
+        client.did_close(uri);
+        let params = server.receive_notification("textDocument/didClose");
+        assert_eq!(params.path(&["textDocument", "uri"]).as_str(), Some(uri));
+        assert!(!client.is_document_open(uri));
+        // requests for a closed document are not sent
+        assert_eq!(client.completion(uri, Position::zero()), None);
+
+        drop(server);
+        assert_eq!(next_event(&mut client), LspEvent::Exited);
+        assert!(!client.is_running());
+    }
+
+    #[test]
+    fn sends_full_text_when_the_server_wants_it() {
+        let uri = "file:///project/a.rs";
+        let (mut client, mut server) = FakeServer::start("file:///project");
+        let (id, _) = server.receive_request("initialize");
+        server.respond(id, JsonValue::parse(r#"{"capabilities": {"textDocumentSync": 1}}"#).unwrap());
+        assert_eq!(next_event(&mut client), LspEvent::Initialized);
+        server.receive_notification("initialized");
+
+        client.did_open(uri, "rust", &Text::from("ab\ncd"));
+        server.receive_notification("textDocument/didOpen");
+        client.did_change(uri, &[
+            Change::Delete(Position {line_index: 0, byte_index: 1}, Length {line_count: 1, byte_count: 1}),
+            Change::Insert(Position {line_index: 0, byte_index: 1}, Text::from("x")),
+        ]);
+        let params = server.receive_notification("textDocument/didChange");
+        let changes = params.get("contentChanges").as_array().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].get("range").is_null());
+        assert_eq!(changes[0].get("text").as_str(), Some("axd"));
+    }
+
+    #[test]
+    fn answers_server_requests() {
+        let (mut client, mut server) = FakeServer::start("file:///project");
+        let (id, _) = server.receive_request("initialize");
+        server.respond(id, JsonValue::parse(r#"{"capabilities": {}}"#).unwrap());
+        assert_eq!(next_event(&mut client), LspEvent::Initialized);
+        server.receive_notification("initialized");
+
+        let request = LspMessage::Request {
+            id: 7u64.into(),
+            method: "workspace/configuration".into(),
+            params: JsonValue::parse(r#"{"items": [{"section": "a"}, {"section": "b"}]}"#).unwrap()
+        };
+        write_message(&mut server.writer, &request.to_json().to_string()).unwrap();
+        // messages are handled in order, so once this shows up the request has been answered
+        let notification = LspMessage::Notification {
+            method: "window/showMessage".into(),
+            params: JsonValue::object([("type", 3u64.into()), ("message", "hello".into())])
+        };
+        write_message(&mut server.writer, &notification.to_json().to_string()).unwrap();
+        assert_eq!(next_event(&mut client), LspEvent::Message("hello".into()));
+        let response = server.receive();
+        assert_eq!(response, LspMessage::Response {
+            id: 7u64.into(),
+            result: Ok(JsonValue::Array(vec![JsonValue::Null, JsonValue::Null]))
+        });
+    }
+}
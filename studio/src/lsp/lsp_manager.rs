@@ -0,0 +1,366 @@
+use {
+    crate::{
+        file_system::file_system::{FileSystem, OpenDoc},
+        makepad_code_editor::{
+            code_editor::*,
+            completion::CompletionItem,
+            decoration::Decoration,
+            text::{Change, Position},
+        },
+        makepad_platform::*,
+        makepad_widgets::*,
+        makepad_widgets::file_tree::FileNodeId,
+        lsp::{
+            lsp_client::{LspClient, LspEvent, LspRequestId},
+            lsp_protocol::*,
+        },
+    },
+    std::{
+        collections::HashMap,
+        env,
+        fmt::Write,
+        path::{Path, PathBuf},
+        sync::mpsc::{self, Receiver},
+    },
+};
+
+live_design!{
+    LspManager = {{LspManager}} {
+        // overridden by the MAKEPAD_LSP_SERVER environment variable, an empty command disables it
+        server_command: "rust-analyzer"
+        file_extensions: "rs"
+    }
+}
+
+/// The decoration id of language server diagnostics, so they can be replaced without touching
+/// the decorations from builds.
+pub const LSP_DECORATION_ID: usize = 1;
+
+struct LspDocumentState {
+    uri: String,
+    change_receiver: Receiver<Vec<Change>>,
+}
+
+#[derive(Live, LiveHook)]
+pub struct LspManager {
+    #[live] server_command: String,
+    #[live] file_extensions: String,
+    #[rust] client: Option<LspClient>,
+    #[rust] root_path: PathBuf,
+    #[rust] documents: HashMap<FileNodeId, LspDocumentState>,
+    #[rust] diagnostics: HashMap<FileNodeId, Vec<LspDiagnostic>>,
+    #[rust] pending_requests: HashMap<LspRequestId, (LiveId, Position)>,
+    #[rust] pending_jumps: Vec<(LiveId, LspPosition)>,
+}
+
+pub enum LspManagerAction {
+    /// Show some text in the code editor of a tab, below the given position.
+    ShowInfo {tab_id: LiveId, position: Position, text: String},
+    /// Add completions to the completion popup of the code editor of a tab.
+    Completions {tab_id: LiveId, position: Position, items: Vec<CompletionItem>},
+    /// Open a file, and put the cursor at the given position.
+    JumpTo {file_id: FileNodeId, position: LspPosition},
+}
+
+impl LspManager {
+    pub fn init(&mut self, _cx: &mut Cx, file_system: &FileSystem) {
+        if cfg!(target_arch = "wasm32") {
+            return
+        }
+        // the server needs the files on disk, so there is nothing to do for a remote file server
+        let Some(root_path) = file_system.file_client.root_path() else {
+            return
+        };
+        let command = env::var("MAKEPAD_LSP_SERVER").unwrap_or_else( | _ | self.server_command.clone());
+        let mut parts = command.split_whitespace();
+        let Some(program) = parts.next() else {
+            return
+        };
+        let args: Vec<String> = parts.map( | arg | arg.to_string()).collect();
+        self.root_path = root_path.to_path_buf();
+        match LspClient::spawn(program, &args, &self.root_path) {
+            Ok(client) => self.client = Some(client),
+            Err(err) => log!("Cannot start language server {}: {}", program, err)
+        }
+    }
+
+    pub fn shutdown(&mut self) {
+        if let Some(client) = &mut self.client {
+            client.shutdown();
+        }
+    }
+
+    pub fn handle_event(&mut self, cx: &mut Cx, event: &Event, file_system: &mut FileSystem, dock: &DockRef) -> Vec<LspManagerAction> {
+        let mut actions = Vec::new();
+        self.handle_event_with(cx, event, file_system, dock, &mut | _, action | actions.push(action));
+        actions
+    }
+
+    pub fn handle_event_with(&mut self, cx: &mut Cx, event: &Event, file_system: &mut FileSystem, dock: &DockRef, dispatch_action: &mut dyn FnMut(&mut Cx, LspManagerAction)) {
+        if self.client.is_none() {
+            return
+        }
+        self.open_new_documents(file_system);
+        self.send_changes();
+        self.close_old_documents(file_system);
+        self.apply_pending_jumps(cx, file_system, dock);
+
+        if let Event::Signal = event {
+            let events = self.client.as_mut().unwrap().handle_messages();
+            for event in events {
+                self.handle_lsp_event(cx, event, file_system, dock, dispatch_action);
+            }
+        }
+    }
+
+    pub fn did_save(&mut self, file_id: FileNodeId) {
+        if let (Some(client), Some(document)) = (&mut self.client, self.documents.get(&file_id)) {
+            client.did_save(&document.uri);
+        }
+    }
+
+    pub fn hover(&mut self, file_system: &FileSystem, tab_id: LiveId, position: Position) {
+        self.request(file_system, tab_id, position, | client, uri | client.hover(uri, position));
+    }
+
+    pub fn definition(&mut self, file_system: &FileSystem, tab_id: LiveId, position: Position) {
+        self.request(file_system, tab_id, position, | client, uri | client.definition(uri, position));
+    }
+
+    pub fn completion(&mut self, file_system: &FileSystem, tab_id: LiveId, position: Position) {
+        self.request(file_system, tab_id, position, | client, uri | client.completion(uri, position));
+    }
+
+    pub fn document_symbols(&mut self, file_system: &FileSystem, tab_id: LiveId, position: Position) {
+        self.request(file_system, tab_id, position, | client, uri | client.document_symbols(uri));
+    }
+
+    /// Moves the cursor of a tab to `position`, right away if the tab's document is loaded, or
+    /// otherwise as soon as it is.
+    pub fn jump_to(&mut self, cx: &mut Cx, file_system: &mut FileSystem, dock: &DockRef, tab_id: LiveId, position: LspPosition) {
+        self.pending_jumps.push((tab_id, position));
+        self.apply_pending_jumps(cx, file_system, dock);
+    }
+
+    fn request(&mut self, file_system: &FileSystem, tab_id: LiveId, position: Position, f: impl FnOnce(&mut LspClient, &str) -> Option<LspRequestId>) {
+        // the server has to see all edits before it can answer about the current text
+        self.send_changes();
+        let Some(file_id) = file_system.tab_id_to_file_node_id.get(&tab_id) else {
+            return
+        };
+        let (Some(client), Some(document)) = (&mut self.client, self.documents.get(file_id)) else {
+            return
+        };
+        if let Some(request_id) = f(client, &document.uri) {
+            self.pending_requests.insert(request_id, (tab_id, position));
+        }
+    }
+
+    fn open_new_documents(&mut self, file_system: &mut FileSystem) {
+        let file_ids: Vec<FileNodeId> = file_system.open_documents.iter()
+            .filter( | (file_id, open_doc) | {
+                matches!(open_doc, OpenDoc::Document(_))
+                    && !self.documents.contains_key(file_id)
+                    && file_system.file_node_id_to_tab_id(**file_id).is_some()
+            })
+            .map( | (file_id, _) | *file_id)
+            .collect();
+        for file_id in file_ids {
+            let name = file_system.file_node_name(file_id);
+            let extension = Path::new(&name).extension().and_then( | ext | ext.to_str()).unwrap_or("");
+            if !self.file_extensions.split( | c: char | c == ',' || c.is_whitespace()).any( | ext | ext == extension) {
+                continue
+            }
+            let uri = path_to_uri(&self.root_path.join(file_system.file_node_path(file_id)));
+            let Some(OpenDoc::Document(document)) = file_system.open_documents.get_mut(&file_id) else {
+                continue
+            };
+            let (change_sender, change_receiver) = mpsc::channel();
+            document.add_change_sender(change_sender);
+            self.client.as_mut().unwrap().did_open(&uri, language_id(extension), &document.as_text());
+            self.documents.insert(file_id, LspDocumentState {uri, change_receiver});
+        }
+    }
+
+    fn send_changes(&mut self) {
+        let Some(client) = &mut self.client else {
+            return
+        };
+        for document in self.documents.values() {
+            while let Ok(changes) = document.change_receiver.try_recv() {
+                client.did_change(&document.uri, &changes);
+            }
+        }
+    }
+
+    /// Closes the documents that are no longer shown in any tab, the file system keeps them
+    /// loaded but the server should stop tracking them.
+    fn close_old_documents(&mut self, file_system: &mut FileSystem) {
+        let file_ids: Vec<FileNodeId> = self.documents.keys()
+            .filter( | file_id | file_system.file_node_id_to_tab_id(**file_id).is_none())
+            .cloned()
+            .collect();
+        for file_id in file_ids {
+            let document = self.documents.remove(&file_id).unwrap();
+            self.client.as_mut().unwrap().did_close(&document.uri);
+            self.diagnostics.remove(&file_id);
+            if let Some(OpenDoc::Document(document)) = file_system.open_documents.get_mut(&file_id) {
+                document.remove_decorations(LSP_DECORATION_ID);
+            }
+        }
+    }
+
+    fn apply_pending_jumps(&mut self, cx: &mut Cx, file_system: &mut FileSystem, dock: &DockRef) {
+        self.pending_jumps.retain( | (tab_id, position) | {
+            if !file_system.tab_id_to_file_node_id.contains_key(tab_id) {
+                // the tab was closed before its document loaded
+                return false
+            }
+            let Some(session) = file_system.get_session_mut(*tab_id) else {
+                return true
+            };
+            let position = position.to_position(session.document().as_text().as_lines());
+            if let Some(mut editor) = dock.item(*tab_id).code_editor(id!(editor)).borrow_mut() {
+                editor.set_cursor_and_scroll(cx, position, Default::default(), session);
+                editor.set_key_focus(cx);
+            }
+            false
+        });
+    }
+
+    fn file_id_from_uri(&self, file_system: &FileSystem, uri: &str) -> Option<FileNodeId> {
+        let path = uri_to_path(uri) ?;
+        let path = path.strip_prefix(&self.root_path).ok() ?;
+        let path = path.components()
+            .map( | component | component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        file_system.path_to_file_node_id(&path)
+    }
+
+    fn handle_lsp_event(&mut self, cx: &mut Cx, event: LspEvent, file_system: &mut FileSystem, dock: &DockRef, dispatch_action: &mut dyn FnMut(&mut Cx, LspManagerAction)) {
+        match event {
+            LspEvent::Diagnostics {uri, diagnostics} => {
+                let Some(file_id) = self.file_id_from_uri(file_system, &uri) else {
+                    return
+                };
+                let Some(OpenDoc::Document(document)) = file_system.open_documents.get_mut(&file_id) else {
+                    return
+                };
+                document.remove_decorations(LSP_DECORATION_ID);
+                let text = document.as_text().clone();
+                for diagnostic in &diagnostics {
+                    if diagnostic.severity != LspDiagnosticSeverity::Error && diagnostic.severity != LspDiagnosticSeverity::Warning {
+                        continue
+                    }
+                    let start = diagnostic.range.start.to_position(text.as_lines());
+                    let end = diagnostic.range.end.to_position(text.as_lines());
+                    document.add_decoration(Decoration::new(LSP_DECORATION_ID, start, end));
+                }
+                self.diagnostics.insert(file_id, diagnostics);
+                file_system.redraw_view_by_file_id(cx, file_id, dock);
+            }
+            LspEvent::Hover {request_id, position, hover, ..} => {
+                let Some((tab_id, pick_position)) = self.pending_requests.remove(&request_id) else {
+                    return
+                };
+                let mut text = String::new();
+                if let Some(file_id) = file_system.tab_id_to_file_node_id.get(&tab_id) {
+                    for diagnostic in self.diagnostics.get(file_id).into_iter().flatten() {
+                        if diagnostic.range.start <= position && position <= diagnostic.range.end {
+                            let _ = writeln!(text, "{}", diagnostic.message);
+                        }
+                    }
+                }
+                if let Some(hover) = hover {
+                    text.push_str(&hover.contents);
+                }
+                if !text.trim().is_empty() {
+                    dispatch_action(cx, LspManagerAction::ShowInfo {tab_id, position: pick_position, text});
+                }
+            }
+            LspEvent::Definition {request_id, locations, ..} => {
+                let Some((tab_id, position)) = self.pending_requests.remove(&request_id) else {
+                    return
+                };
+                let Some(location) = locations.first() else {
+                    dispatch_action(cx, LspManagerAction::ShowInfo {tab_id, position, text: "No definition found".to_string()});
+                    return
+                };
+                match self.file_id_from_uri(file_system, &location.uri) {
+                    Some(file_id) => dispatch_action(cx, LspManagerAction::JumpTo {file_id, position: location.range.start}),
+                    None => {
+                        // the definition is outside of the project, so all we can do is say where
+                        let path = uri_to_path(&location.uri).map_or(location.uri.clone(), | path | path.to_string_lossy().to_string());
+                        let text = format!("Defined in {}:{}", path, location.range.start.line + 1);
+                        dispatch_action(cx, LspManagerAction::ShowInfo {tab_id, position, text});
+                    }
+                }
+            }
+            LspEvent::Completion {request_id, items, ..} => {
+                let Some((tab_id, position)) = self.pending_requests.remove(&request_id) else {
+                    return
+                };
+                let items = items.into_iter().map( | item | CompletionItem {
+                    label: item.label,
+                    detail: item.detail,
+                    insert_text: item.insert_text,
+                    is_snippet: item.is_snippet,
+                    filter_text: item.filter_text,
+                }).collect();
+                dispatch_action(cx, LspManagerAction::Completions {tab_id, position, items});
+            }
+            LspEvent::DocumentSymbols {request_id, symbols, ..} => {
+                let Some((tab_id, position)) = self.pending_requests.remove(&request_id) else {
+                    return
+                };
+                fn write_symbols(text: &mut String, symbols: &[LspDocumentSymbol], depth: usize) {
+                    for symbol in symbols {
+                        let _ = writeln!(
+                            text,
+                            "{:indent$}{} {}  :{}",
+                            "",
+                            symbol.kind_name(),
+                            symbol.name,
+                            symbol.selection_range.start.line + 1,
+                            indent = depth * 2
+                        );
+                        write_symbols(text, &symbol.children, depth + 1);
+                    }
+                }
+                let mut text = String::new();
+                write_symbols(&mut text, &symbols, 0);
+                if text.is_empty() {
+                    text.push_str("No symbols");
+                }
+                dispatch_action(cx, LspManagerAction::ShowInfo {tab_id, position, text});
+            }
+            LspEvent::Error {request_id, message} => {
+                if let Some(request_id) = request_id {
+                    self.pending_requests.remove(&request_id);
+                }
+                log!("Language server error: {}", message);
+            }
+            LspEvent::Exited => {
+                log!("Language server exited");
+                self.client = None;
+                self.documents.clear();
+                self.pending_requests.clear();
+            }
+            LspEvent::Initialized | LspEvent::Message(_) => ()
+        }
+    }
+}
+
+fn language_id(extension: &str) -> &str {
+    match extension {
+        "rs" => "rust",
+        "toml" => "toml",
+        "js" => "javascript",
+        "ts" => "typescript",
+        "py" => "python",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "hpp" => "cpp",
+        extension => extension
+    }
+}
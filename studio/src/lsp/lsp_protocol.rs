@@ -0,0 +1,586 @@
+use {
+    crate::{
+        makepad_code_editor::text::Position,
+        lsp::json::JsonValue,
+    },
+    std::{
+        io::{self, BufRead, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+// Base protocol
+
+/// Reads a single message from a language server. Returns `None` if the stream ended cleanly
+/// before the next message.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line) ? == 0 {
+            if content_length.is_none() {
+                return Ok(None)
+            }
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended inside a message header"));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            // tolerate stray empty lines between messages
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.trim().parse::<usize>().map_err( | _ | {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid Content-Length")
+                }) ?);
+            }
+        }
+    }
+    let mut content = vec![0; content_length.unwrap()];
+    reader.read_exact(&mut content) ?;
+    String::from_utf8(content).map(Some).map_err( | _ | {
+        io::Error::new(io::ErrorKind::InvalidData, "message is not valid utf8")
+    })
+}
+
+pub fn write_message(writer: &mut impl Write, content: &str) -> io::Result<()> {
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content) ?;
+    writer.flush()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspResponseError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LspMessage {
+    Request {id: JsonValue, method: String, params: JsonValue},
+    Response {id: JsonValue, result: Result<JsonValue, LspResponseError>},
+    Notification {method: String, params: JsonValue},
+}
+
+impl LspMessage {
+    pub fn from_json(value: &JsonValue) -> Option<LspMessage> {
+        let id = value.get("id");
+        match value.get("method").as_str() {
+            Some(method) if id.is_null() => Some(LspMessage::Notification {
+                method: method.to_string(),
+                params: value.get("params").clone()
+            }),
+            Some(method) => Some(LspMessage::Request {
+                id: id.clone(),
+                method: method.to_string(),
+                params: value.get("params").clone()
+            }),
+            None => {
+                let error = value.get("error");
+                Some(LspMessage::Response {
+                    id: id.clone(),
+                    result: if error.is_null() {
+                        Ok(value.get("result").clone())
+                    }
+                    else {
+                        Err(LspResponseError {
+                            code: error.get("code").as_i64() ?,
+                            message: error.get("message").as_str().unwrap_or("").to_string()
+                        })
+                    }
+                })
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            LspMessage::Request {id, method, params} => JsonValue::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("method", method.as_str().into()),
+                ("params", params.clone()),
+            ]),
+            LspMessage::Response {id, result: Ok(result)} => JsonValue::Object(vec![
+                ("jsonrpc".into(), "2.0".into()),
+                ("id".into(), id.clone()),
+                // a null result still has to be sent
+                ("result".into(), result.clone()),
+            ]),
+            LspMessage::Response {id, result: Err(error)} => JsonValue::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("error", JsonValue::object([
+                    ("code", error.code.into()),
+                    ("message", error.message.as_str().into()),
+                ])),
+            ]),
+            LspMessage::Notification {method, params} => JsonValue::object([
+                ("jsonrpc", "2.0".into()),
+                ("method", method.as_str().into()),
+                ("params", params.clone()),
+            ]),
+        }
+    }
+}
+
+// Uris
+
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            // keep the drive letter colon readable, which is what most servers send back
+            b':' if uri.len() == "file:///C".len() => uri.push(':'),
+            _ => uri.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    uri
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://") ?;
+    let mut bytes = Vec::new();
+    let mut iter = encoded.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let high = (iter.next()? as char).to_digit(16) ?;
+            let low = (iter.next()? as char).to_digit(16) ?;
+            bytes.push((high * 16 + low) as u8);
+        }
+        else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok() ?;
+    // file:///C:/dir is C:/dir on windows
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &path[1..],
+        _ => &path
+    };
+    Some(PathBuf::from(path))
+}
+
+// Positions
+
+/// A position in a document as the language server sees it, which is a line index and an offset
+/// in utf16 code units into that line.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LspPosition {
+    pub line: usize,
+    pub character: usize,
+}
+
+impl LspPosition {
+    pub fn from_position(lines: &[String], position: Position) -> LspPosition {
+        let character = match lines.get(position.line_index) {
+            Some(line) => line[..position.byte_index.min(line.len())].encode_utf16().count(),
+            None => 0
+        };
+        LspPosition {
+            line: position.line_index,
+            character
+        }
+    }
+
+    /// Converts to a position in the given lines. Positions past the end of a line or of the
+    /// document are clamped, because the server may be looking at an older version.
+    pub fn to_position(self, lines: &[String]) -> Position {
+        let line_index = self.line.min(lines.len().saturating_sub(1));
+        let line = match lines.get(line_index) {
+            Some(line) if line_index == self.line => line,
+            Some(line) => return Position {line_index, byte_index: line.len()},
+            None => return Position::zero()
+        };
+        let mut utf16_count = 0;
+        for (byte_index, c) in line.char_indices() {
+            if utf16_count >= self.character {
+                return Position {line_index, byte_index}
+            }
+            utf16_count += c.len_utf16();
+        }
+        Position {line_index, byte_index: line.len()}
+    }
+
+    pub fn from_json(value: &JsonValue) -> Option<LspPosition> {
+        Some(LspPosition {
+            line: value.get("line").as_u64()? as usize,
+            character: value.get("character").as_u64()? as usize
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("line", self.line.into()),
+            ("character", self.character.into()),
+        ])
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+impl LspRange {
+    pub fn from_json(value: &JsonValue) -> Option<LspRange> {
+        Some(LspRange {
+            start: LspPosition::from_json(value.get("start")) ?,
+            end: LspPosition::from_json(value.get("end")) ?
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("start", self.start.to_json()),
+            ("end", self.end.to_json()),
+        ])
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspLocation {
+    pub uri: String,
+    pub range: LspRange,
+}
+
+impl LspLocation {
+    /// Parses a `Location` or a `LocationLink`.
+    pub fn from_json(value: &JsonValue) -> Option<LspLocation> {
+        if let Some(uri) = value.get("targetUri").as_str() {
+            return Some(LspLocation {
+                uri: uri.to_string(),
+                range: LspRange::from_json(value.get("targetSelectionRange"))
+                    .or_else( || LspRange::from_json(value.get("targetRange"))) ?
+            })
+        }
+        Some(LspLocation {
+            uri: value.get("uri").as_str()?.to_string(),
+            range: LspRange::from_json(value.get("range")) ?
+        })
+    }
+
+    /// Parses the result of a definition request, which is a single location, an array of
+    /// locations or location links, or null.
+    pub fn from_json_result(value: &JsonValue) -> Vec<LspLocation> {
+        match value.as_array() {
+            Some(values) => values.iter().filter_map(LspLocation::from_json).collect(),
+            None => LspLocation::from_json(value).into_iter().collect()
+        }
+    }
+}
+
+// Diagnostics
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LspDiagnosticSeverity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspDiagnostic {
+    pub range: LspRange,
+    pub severity: LspDiagnosticSeverity,
+    pub message: String,
+    pub source: Option<String>,
+}
+
+impl LspDiagnostic {
+    pub fn from_json(value: &JsonValue) -> Option<LspDiagnostic> {
+        Some(LspDiagnostic {
+            range: LspRange::from_json(value.get("range")) ?,
+            // a missing severity is up to the client to interpret
+            severity: match value.get("severity").as_u64() {
+                Some(2) => LspDiagnosticSeverity::Warning,
+                Some(3) => LspDiagnosticSeverity::Information,
+                Some(4) => LspDiagnosticSeverity::Hint,
+                _ => LspDiagnosticSeverity::Error,
+            },
+            message: value.get("message").as_str()?.to_string(),
+            source: value.get("source").as_str().map( | source | source.to_string())
+        })
+    }
+}
+
+// Hover
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspHover {
+    pub contents: String,
+    pub range: Option<LspRange>,
+}
+
+impl LspHover {
+    pub fn from_json(value: &JsonValue) -> Option<LspHover> {
+        fn marked_string(value: &JsonValue, out: &mut String) {
+            let text = match value {
+                JsonValue::String(text) => text.as_str(),
+                // MarkupContent or MarkedString with a language
+                value => value.get("value").as_str().unwrap_or("")
+            };
+            if text.trim().is_empty() {
+                return
+            }
+            if !out.is_empty() {
+                out.push_str("\n\n");
+            }
+            out.push_str(text.trim());
+        }
+
+        let mut contents = String::new();
+        match value.get("contents") {
+            JsonValue::Array(values) => for value in values {
+                marked_string(value, &mut contents);
+            }
+            value => marked_string(value, &mut contents)
+        }
+        if contents.is_empty() {
+            return None
+        }
+        Some(LspHover {
+            contents,
+            range: LspRange::from_json(value.get("range"))
+        })
+    }
+}
+
+// Completion
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspCompletionItem {
+    pub label: String,
+    pub kind: Option<u64>,
+    pub detail: Option<String>,
+    /// The text to insert, which is in snippet syntax if `is_snippet` is set.
+    pub insert_text: String,
+    pub is_snippet: bool,
+    /// The range to replace with `insert_text`, if the server gave one.
+    pub range: Option<LspRange>,
+    pub sort_text: Option<String>,
+    pub filter_text: Option<String>,
+}
+
+impl LspCompletionItem {
+    pub fn from_json(value: &JsonValue) -> Option<LspCompletionItem> {
+        let label = value.get("label").as_str()?.to_string();
+        let text_edit = value.get("textEdit");
+        let insert_text = text_edit.get("newText").as_str()
+            .or_else( || value.get("insertText").as_str())
+            .unwrap_or(&label)
+            .to_string();
+        Some(LspCompletionItem {
+            kind: value.get("kind").as_u64(),
+            detail: value.get("detail").as_str().map( | detail | detail.to_string()),
+            insert_text,
+            is_snippet: value.get("insertTextFormat").as_u64() == Some(2),
+            // an InsertReplaceEdit has an insert range instead of a range
+            range: LspRange::from_json(text_edit.get("range"))
+                .or_else( || LspRange::from_json(text_edit.get("insert"))),
+            sort_text: value.get("sortText").as_str().map( | text | text.to_string()),
+            filter_text: value.get("filterText").as_str().map( | text | text.to_string()),
+            label,
+        })
+    }
+
+    /// Parses the result of a completion request, which is an array of items, a completion list,
+    /// or null.
+    pub fn from_json_result(value: &JsonValue) -> Vec<LspCompletionItem> {
+        let items = match value.as_array() {
+            Some(items) => items,
+            None => value.get("items").as_array().unwrap_or(&[])
+        };
+        let mut items: Vec<_> = items.iter().filter_map(LspCompletionItem::from_json).collect();
+        items.sort_by( | a, b | {
+            a.sort_text.as_ref().unwrap_or(&a.label).cmp(b.sort_text.as_ref().unwrap_or(&b.label))
+        });
+        items
+    }
+}
+
+// Document symbols
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspDocumentSymbol {
+    pub name: String,
+    pub kind: u64,
+    pub detail: Option<String>,
+    pub range: LspRange,
+    pub selection_range: LspRange,
+    pub children: Vec<LspDocumentSymbol>,
+}
+
+impl LspDocumentSymbol {
+    /// Parses a `DocumentSymbol` or a flat `SymbolInformation`.
+    pub fn from_json(value: &JsonValue) -> Option<LspDocumentSymbol> {
+        let (range, selection_range) = match LspRange::from_json(value.get("range")) {
+            Some(range) => (range, LspRange::from_json(value.get("selectionRange")).unwrap_or(range)),
+            None => {
+                let range = LspRange::from_json(value.path(&["location", "range"])) ?;
+                (range, range)
+            }
+        };
+        Some(LspDocumentSymbol {
+            name: value.get("name").as_str()?.to_string(),
+            kind: value.get("kind").as_u64().unwrap_or(0),
+            detail: value.get("detail").as_str().map( | detail | detail.to_string()),
+            range,
+            selection_range,
+            children: value.get("children").as_array().unwrap_or(&[])
+                .iter()
+                .filter_map(LspDocumentSymbol::from_json)
+                .collect()
+        })
+    }
+
+    pub fn from_json_result(value: &JsonValue) -> Vec<LspDocumentSymbol> {
+        value.as_array().unwrap_or(&[]).iter().filter_map(LspDocumentSymbol::from_json).collect()
+    }
+
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            1 => "file",
+            2 => "module",
+            3 => "namespace",
+            4 => "package",
+            5 => "class",
+            6 => "method",
+            7 => "property",
+            8 => "field",
+            9 => "constructor",
+            10 => "enum",
+            11 => "interface",
+            12 => "fn",
+            13 => "variable",
+            14 => "const",
+            22 => "variant",
+            23 => "struct",
+            25 => "operator",
+            26 => "type parameter",
+            _ => "symbol"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::io::Cursor,
+    };
+
+    fn read_all(input: &str) -> io::Result<Vec<String>> {
+        let mut reader = Cursor::new(input.as_bytes());
+        let mut messages = Vec::new();
+        while let Some(message) = read_message(&mut reader) ? {
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+
+    #[test]
+    fn read_messages() {
+        assert_eq!(read_all("").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            read_all("Content-Length: 2\r\n\r\n{}Content-Length: 4\r\n\r\nnull").unwrap(),
+            vec!["{}".to_string(), "null".to_string()]
+        );
+        // other headers, header names in any case, and stray newlines between messages
+        assert_eq!(
+            read_all("content-length:3\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n[1]\r\n").unwrap(),
+            vec!["[1]".to_string()]
+        );
+        // the length is in bytes, not chars
+        assert_eq!(read_all("Content-Length: 4\r\n\r\n\"é\"").unwrap(), vec!["\"é\"".to_string()]);
+    }
+
+    #[test]
+    fn read_message_errors() {
+        let kind = | input | read_all(input).unwrap_err().kind();
+        assert_eq!(kind("Content-Length: 2\r\n"), io::ErrorKind::UnexpectedEof);
+        assert_eq!(kind("Content-Length: 5\r\n\r\n{}"), io::ErrorKind::UnexpectedEof);
+        assert_eq!(kind("Content-Length: x\r\n\r\n"), io::ErrorKind::InvalidData);
+        assert_eq!(kind("Content-Length: 1\r\n\r\n\u{e9}"), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn written_messages_read_back() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, "{\"a\":\"é\"}").unwrap();
+        write_message(&mut buffer, "{}").unwrap();
+        assert!(buffer.starts_with(b"Content-Length: 10\r\n\r\n"));
+        assert_eq!(
+            read_all(std::str::from_utf8(&buffer).unwrap()).unwrap(),
+            vec!["{\"a\":\"é\"}".to_string(), "{}".to_string()]
+        );
+    }
+
+    #[test]
+    fn messages_from_json() {
+        let message = | input | LspMessage::from_json(&JsonValue::parse(input).unwrap());
+        assert_eq!(
+            message(r#"{"jsonrpc": "2.0", "method": "exit"}"#),
+            Some(LspMessage::Notification {method: "exit".into(), params: JsonValue::Null})
+        );
+        assert_eq!(
+            message(r#"{"id": 1, "method": "a", "params": []}"#),
+            Some(LspMessage::Request {id: 1u64.into(), method: "a".into(), params: JsonValue::Array(vec![])})
+        );
+        // a null result is still a result
+        assert_eq!(message(r#"{"id": "x"}"#), Some(LspMessage::Response {id: "x".into(), result: Ok(JsonValue::Null)}));
+        assert_eq!(
+            message(r#"{"id": 2, "error": {"code": -32800, "message": "cancelled"}}"#),
+            Some(LspMessage::Response {
+                id: 2u64.into(),
+                result: Err(LspResponseError {code: -32800, message: "cancelled".into()})
+            })
+        );
+        assert_eq!(message(r#"{"id": 2, "error": {"message": "no code"}}"#), None);
+        for input in [
+            r#"{"jsonrpc":"2.0","id":1,"method":"a","params":{"b":true}}"#,
+            r#"{"jsonrpc":"2.0","id":1,"result":null}"#,
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"m"}}"#,
+        ] {
+            assert_eq!(message(input).unwrap().to_json().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn uris() {
+        assert_eq!(path_to_uri(Path::new("/a b/c#d.rs")), "file:///a%20b/c%23d.rs");
+        assert_eq!(path_to_uri(Path::new("C:\\dir\\é.rs")), "file:///C:/dir/%C3%A9.rs");
+        assert_eq!(uri_to_path("file:///a%20b/c%23d.rs"), Some(PathBuf::from("/a b/c#d.rs")));
+        assert_eq!(uri_to_path("file:///C:/dir/%C3%A9.rs"), Some(PathBuf::from("C:/dir/é.rs")));
+        assert_eq!(uri_to_path("file:///c%3A/dir"), Some(PathBuf::from("c:/dir")));
+        assert_eq!(uri_to_path("http://a/b"), None);
+        assert_eq!(uri_to_path("file:///a%2"), None);
+        assert_eq!(uri_to_path("file:///a%FF"), None);
+    }
+
+    #[test]
+    fn positions_count_utf16() {
+        let lines = vec!["aé😀b".to_string(), "".to_string()];
+        let position = | line_index, byte_index | Position {line_index, byte_index};
+        let lsp_position = | line, character | LspPosition {line, character};
+        assert_eq!(LspPosition::from_position(&lines, position(0, 3)), lsp_position(0, 2));
+        assert_eq!(LspPosition::from_position(&lines, position(0, 7)), lsp_position(0, 4));
+        assert_eq!(LspPosition::from_position(&lines, position(0, 8)), lsp_position(0, 5));
+        assert_eq!(lsp_position(0, 2).to_position(&lines), position(0, 3));
+        assert_eq!(lsp_position(0, 4).to_position(&lines), position(0, 7));
+        // past the end of a line or of the document is clamped
+        assert_eq!(lsp_position(0, 9).to_position(&lines), position(0, 8));
+        assert_eq!(lsp_position(5, 1).to_position(&lines), position(1, 0));
+        assert_eq!(lsp_position(0, 0).to_position(&[]), Position::zero());
+    }
+}
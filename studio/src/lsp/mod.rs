@@ -0,0 +1,4 @@
+pub mod json;
+pub mod lsp_protocol;
+pub mod lsp_client;
+pub mod lsp_manager;
@@ -0,0 +1,2 @@
+pub mod search_manager;
+pub mod search_list;
@@ -0,0 +1,207 @@
+use {
+    crate::{
+        makepad_platform::*,
+        makepad_widgets::*,
+        makepad_code_editor::text::{Position, Length},
+        makepad_widgets::portal_list::PortalList,
+        search::search_manager::{SearchManager, SearchRow},
+    },
+};
+
+live_design!{
+    import makepad_draw::shader::std::*;
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    SearchInput = <TextInput> {
+        width: Fill,
+        height: Fit,
+        on_focus_select_all: true,
+        draw_bg: {
+            color: #2
+            border_width: 1.0
+            border_color: #x00000044
+        }
+    }
+
+    SearchItem = <RectView> {
+        height: Fit,
+        width: Fill
+        padding: {top: 3, bottom: 3}
+        flow: Right
+        spacing: 4
+        cursor: Hand
+
+        draw_bg: {
+            instance is_even: 0.0
+            instance hover: 0.0
+            fn pixel(self) -> vec4 {
+                return mix(
+                    mix(
+                        THEME_COLOR_BG_EDITOR,
+                        THEME_COLOR_BG_ODD,
+                        self.is_even
+                    ),
+                    THEME_COLOR_BG_SELECTED,
+                    self.hover * 0.5
+                );
+            }
+        }
+        animator: {
+            ignore_missing: true,
+            hover = {
+                default: off
+                off = {
+                    from: {all: Forward {duration: 0.1}}
+                    apply: {
+                        draw_bg: {hover: 0.0}
+                    }
+                }
+                on = {
+                    from: {all: Snap}
+                    apply: {
+                        draw_bg: {hover: 1.0}
+                    },
+                }
+            }
+        }
+    }
+
+    SearchList = <PortalList> {
+        grab_key_focus: true
+        auto_tail: false
+        allow_empty: true
+        drag_scrolling: false
+        height: Fill,
+        width: Fill
+        flow: Down
+        File = <SearchItem> {
+            padding: {left: 5, top: 5, bottom: 3}
+            name = <Label> {draw_text: {color: #c, wrap: Ellipsis}}
+            directory = <Label> {width: Fill, draw_text: {color: #6, wrap: Ellipsis}}
+        }
+        Match = <SearchItem> {
+            padding: {left: 15}
+            spacing: 0
+            line = <Label> {width: 40, draw_text: {color: #6}}
+            before = <Label> {draw_text: {wrap: Ellipsis}}
+            matched = <Label> {draw_text: {color: #xF0C674, wrap: Ellipsis}}
+            after = <Label> {width: Fill, draw_text: {wrap: Ellipsis}}
+        }
+        Empty = <SearchItem> {
+            cursor: Default
+            height: 24,
+            width: Fill
+        }
+    }
+
+    SearchPanel = <View> {
+        width: Fill,
+        height: Fill,
+        flow: Down
+        <View> {
+            width: Fill,
+            height: Fit,
+            flow: Down,
+            spacing: 4,
+            padding: 4,
+            show_bg: true,
+            draw_bg: {color: #3}
+            search_query = <SearchInput> {empty_message: "Search"}
+            <View> {
+                width: Fill,
+                height: Fit,
+                flow: Right,
+                spacing: 4,
+                align: {y: 0.5}
+                search_case_sensitive = <CheckBox> {text: "Aa"}
+                search_whole_word = <CheckBox> {text: "Word"}
+                search_regex = <CheckBox> {text: ".*"}
+            }
+            search_include = <SearchInput> {empty_message: "Files to include"}
+            search_exclude = <SearchInput> {empty_message: "Files to exclude"}
+            search_status = <Label> {
+                width: Fill,
+                draw_text: {color: #a}
+                text: ""
+            }
+        }
+        search_results = <SearchList> {}
+    }
+}
+
+pub enum SearchListAction {
+    JumpTo {path: String, start: Position, length: Length},
+    None
+}
+
+impl SearchManager {
+
+    pub fn draw_search_list(&self, cx: &mut Cx2d, list: &mut PortalList) {
+        list.set_item_range(cx, 0, self.rows.len() as u64);
+        while let Some(item_id) = list.next_visible_item(cx) {
+            let is_even = item_id & 1 == 0;
+            match self.rows.get(item_id as usize) {
+                Some(SearchRow::File(index)) => {
+                    let path = &self.results[*index].path;
+                    let (directory, name) = path.rsplit_once('/').unwrap_or(("", path));
+                    let item = list.item(cx, item_id, live_id!(File)).unwrap().as_view();
+                    item.apply_over(cx, live!{
+                        name = {text: (name)}
+                        directory = {text: (directory)}
+                        draw_bg: {is_even: (if is_even {1.0} else {0.0})}
+                    });
+                    item.draw_widget_all(cx);
+                }
+                Some(SearchRow::Match(index)) => {
+                    let result = &self.results[*index];
+                    let preview = &result.preview;
+                    // the match may run past the end of the preview if it is very long
+                    let start = (result.start_byte_index - result.preview_byte_index).min(preview.len());
+                    let end = (result.end_byte_index - result.preview_byte_index).min(preview.len());
+                    let item = list.item(cx, item_id, live_id!(Match)).unwrap().as_view();
+                    item.apply_over(cx, live!{
+                        line = {text: (format!("{}", result.line_index + 1))}
+                        before = {text: (&preview[..start])}
+                        matched = {text: (&preview[start..end])}
+                        after = {text: (&preview[end..])}
+                        draw_bg: {is_even: (if is_even {1.0} else {0.0})}
+                    });
+                    item.draw_widget_all(cx);
+                }
+                None => {
+                    let item = list.item(cx, item_id, live_id!(Empty)).unwrap().as_view();
+                    item.apply_over(cx, live!{draw_bg: {is_even: (if is_even {1.0} else {0.0})}});
+                    item.draw_widget_all(cx);
+                }
+            }
+        }
+    }
+
+    pub fn handle_search_list(&mut self, _cx: &mut Cx, _search_list: &PortalListRef, item_id: u64, item: WidgetRef, actions: &WidgetActions) -> Vec<SearchListAction> {
+        let mut ret = Vec::new();
+        if let Some(fe) = item.as_view().finger_up(actions) {
+            if !fe.is_over {
+                return ret
+            }
+            // clicking a file opens it at its first match
+            let index = match self.rows.get(item_id as usize) {
+                Some(SearchRow::File(index)) | Some(SearchRow::Match(index)) => *index,
+                None => return ret
+            };
+            let result = &self.results[index];
+            ret.push(SearchListAction::JumpTo {
+                path: result.path.clone(),
+                start: Position {
+                    line_index: result.line_index,
+                    byte_index: result.start_byte_index,
+                },
+                length: Length {
+                    line_count: 0,
+                    byte_count: result.end_byte_index - result.start_byte_index,
+                }
+            });
+        }
+        ret
+    }
+}
@@ -0,0 +1,200 @@
+use {
+    crate::{
+        file_system::file_system::FileSystem,
+        makepad_code_editor::{
+            code_editor::*,
+            selection::Affinity,
+            text::{Length, Position},
+        },
+        makepad_file_protocol::{SearchQuery, SearchResult},
+        makepad_platform::*,
+        makepad_widgets::*,
+    },
+};
+
+// How long to wait after the last change to the query before searching.
+const SEARCH_DELAY: f64 = 0.25;
+
+/// Runs searches across the project on the file server, and keeps the results of the last one.
+#[derive(Default)]
+pub struct SearchManager {
+    // The id of the search whose results are shown, if any.
+    search_id: Option<u64>,
+    pub results: Vec<SearchResult>,
+    pub rows: Vec<SearchRow>,
+    search_timer: Timer,
+    pending_jumps: Vec<(LiveId, Position, Length)>,
+}
+
+/// A row in the list of search results. Both kinds of rows refer to a result by index, for a file
+/// it is the first match in that file.
+#[derive(Clone, Copy, Debug)]
+pub enum SearchRow {
+    File(usize),
+    Match(usize),
+}
+
+impl SearchManager {
+    pub fn handle_event(&mut self, cx: &mut Cx, event: &Event, ui: &WidgetRef, file_system: &mut FileSystem, dock: &DockRef) {
+        self.apply_pending_jumps(cx, file_system, dock);
+        if self.search_timer.is_event(event).is_some() {
+            self.search_timer = Timer::default();
+            self.start_search(cx, ui, file_system);
+        }
+    }
+
+    /// Searches again whenever the query or its options change. Typing waits for a pause, return
+    /// searches right away.
+    pub fn handle_search_panel(&mut self, cx: &mut Cx, ui: &WidgetRef, actions: &WidgetActions, file_system: &mut FileSystem) {
+        let inputs = [
+            ui.text_input(id!(search_query)),
+            ui.text_input(id!(search_include)),
+            ui.text_input(id!(search_exclude)),
+        ];
+        let is_return = inputs.iter().any( | input | {
+            matches!(actions.find_single_action(input.widget_uid()).map( | item | item.action()), Some(TextInputAction::Return(_)))
+        });
+        if is_return
+            || ui.check_box(id!(search_case_sensitive)).changed(actions).is_some()
+            || ui.check_box(id!(search_whole_word)).changed(actions).is_some()
+            || ui.check_box(id!(search_regex)).changed(actions).is_some() {
+            cx.stop_timer(self.search_timer);
+            self.search_timer = Timer::default();
+            self.start_search(cx, ui, file_system);
+        }
+        else if inputs.iter().any( | input | input.changed(actions).is_some()) {
+            cx.stop_timer(self.search_timer);
+            self.search_timer = cx.start_timeout(SEARCH_DELAY);
+        }
+    }
+
+    fn start_search(&mut self, cx: &mut Cx, ui: &WidgetRef, file_system: &mut FileSystem) {
+        let query = SearchQuery {
+            pattern: ui.text_input(id!(search_query)).text(),
+            is_regex: ui.check_box(id!(search_regex)).selected(cx),
+            case_sensitive: ui.check_box(id!(search_case_sensitive)).selected(cx),
+            whole_word: ui.check_box(id!(search_whole_word)).selected(cx),
+            include: split_globs(&ui.text_input(id!(search_include)).text()),
+            exclude: split_globs(&ui.text_input(id!(search_exclude)).text()),
+        };
+        self.results.clear();
+        self.rows.clear();
+        if query.pattern.is_empty() {
+            self.search_id = None;
+            ui.label(id!(search_status)).set_text_and_redraw(cx, "");
+        }
+        else {
+            self.search_id = Some(file_system.request_search(query));
+            ui.label(id!(search_status)).set_text_and_redraw(cx, "Searching...");
+        }
+        ui.portal_list(id!(search_results)).set_first_id_and_scroll(0, 0.0);
+        ui.portal_list(id!(search_results)).redraw(cx);
+    }
+
+    pub fn add_results(&mut self, cx: &mut Cx, ui: &WidgetRef, id: u64, results: Vec<SearchResult>) {
+        if self.search_id != Some(id) {
+            return
+        }
+        for result in results {
+            let index = self.results.len();
+            if self.results.last().map_or(true, | last | last.path != result.path) {
+                self.rows.push(SearchRow::File(index));
+            }
+            self.rows.push(SearchRow::Match(index));
+            self.results.push(result);
+        }
+        ui.portal_list(id!(search_results)).redraw(cx);
+    }
+
+    pub fn search_finished(&mut self, cx: &mut Cx, ui: &WidgetRef, id: u64, file_count: usize, match_count: usize, is_truncated: bool) {
+        if self.search_id != Some(id) {
+            return
+        }
+        let text = if match_count == 0 {
+            "No results".to_string()
+        }
+        else {
+            format!(
+                "{}{} {} in {} {}",
+                if is_truncated {"First "} else {""},
+                match_count,
+                if match_count == 1 {"result"} else {"results"},
+                file_count,
+                if file_count == 1 {"file"} else {"files"}
+            )
+        };
+        ui.label(id!(search_status)).set_text_and_redraw(cx, &text);
+    }
+
+    pub fn search_failed(&mut self, cx: &mut Cx, ui: &WidgetRef, id: u64, error: &str) {
+        if self.search_id != Some(id) {
+            return
+        }
+        ui.label(id!(search_status)).set_text_and_redraw(cx, error);
+    }
+
+    /// Selects the match in the given tab once its document has loaded.
+    pub fn jump_to(&mut self, cx: &mut Cx, file_system: &mut FileSystem, dock: &DockRef, tab_id: LiveId, start: Position, length: Length) {
+        self.pending_jumps.push((tab_id, start, length));
+        self.apply_pending_jumps(cx, file_system, dock);
+    }
+
+    fn apply_pending_jumps(&mut self, cx: &mut Cx, file_system: &mut FileSystem, dock: &DockRef) {
+        self.pending_jumps.retain( | (tab_id, start, length) | {
+            if !file_system.tab_id_to_file_node_id.contains_key(tab_id) {
+                // the tab was closed before its document loaded
+                return false
+            }
+            let Some(session) = file_system.get_session_mut(*tab_id) else {
+                return true
+            };
+            // the file may have changed since it was searched
+            let text = session.document().as_text();
+            let lines = text.as_lines();
+            let end_byte_index = start.byte_index + length.byte_count;
+            let (start, end) = match lines.get(start.line_index) {
+                Some(line) if end_byte_index <= line.len()
+                    && line.is_char_boundary(start.byte_index)
+                    && line.is_char_boundary(end_byte_index) => (*start, *start + *length),
+                _ => {
+                    let position = Position {
+                        line_index: start.line_index.min(lines.len().saturating_sub(1)),
+                        byte_index: 0,
+                    };
+                    (position, position)
+                }
+            };
+            drop(text);
+            session.set_selection(start, Affinity::Before, 1);
+            session.move_to(end, Affinity::After);
+            if let Some(mut editor) = dock.item(*tab_id).code_editor(id!(editor)).borrow_mut() {
+                editor.scroll_cursor_into_view(cx);
+                editor.set_key_focus(cx);
+            }
+            false
+        });
+    }
+}
+
+// Splits a comma separated list of globs, leaving the commas within braces alone.
+fn split_globs(text: &str) -> Vec<String> {
+    let mut globs = Vec::new();
+    let mut glob = String::new();
+    let mut brace_depth = 0usize;
+    for char in text.chars() {
+        match char {
+            '{' => brace_depth += 1,
+            '}' => brace_depth = brace_depth.saturating_sub(1),
+            ',' if brace_depth == 0 => {
+                globs.push(glob.trim().to_string());
+                glob.clear();
+                continue
+            }
+            _ => ()
+        }
+        glob.push(char);
+    }
+    globs.push(glob.trim().to_string());
+    globs.retain( | glob | !glob.is_empty());
+    globs
+}
@@ -19,6 +19,8 @@ live_design!{
     import crate::video::VideoBase;
     import crate::popup_menu::PopupMenuBase;
     import crate::label::LabelBase;
+    import crate::rich_text::RichTextBase;
+    import crate::markdown::MarkdownBase;
     import crate::link_label::LinkLabelBase;
     import crate::portal_list::PortalListBase;
     import crate::flat_list::FlatListBase;
@@ -42,6 +44,7 @@ live_design!{
     import crate::page_flip::PageFlipBase;
     import crate::keyboard_view::KeyboardViewBase;
     import crate::window_menu::WindowMenuBase;
+    import crate::menu_bar::MenuBarBase;
     
     import makepad_draw::shader::std::*;
     import makepad_draw::shader::draw_color::DrawColor;
@@ -592,6 +595,8 @@ live_design!{
     RotatedImageBase = <RotatedImageBase> {}
     VideoBase = <VideoBase> {}
     LabelBase = <LabelBase> {}
+    RichTextBase = <RichTextBase> {}
+    MarkdownBase = <MarkdownBase> {}
     LinkLabelBase = <LinkLabelBase> {}
     PortalListBase = <PortalListBase> {}
     FlatListBase = <FlatListBase>{}
@@ -611,4 +616,5 @@ live_design!{
     TextInputBase = <TextInputBase>{}
     DrawScrollShadowBase = <DrawScrollShadowBase>{}
     WindowMenuBase = <WindowMenuBase>{}
+    MenuBarBase = <MenuBarBase>{}
 }
@@ -135,6 +135,10 @@ impl Widget for CheckBox {
         self.draw_check.redraw(cx);
     }
     
+    fn area(&self) -> Area {
+        self.draw_check.area()
+    }
+    
     fn handle_widget_event_with(&mut self, cx: &mut Cx, event: &Event, dispatch_action: &mut dyn FnMut(&mut Cx, WidgetActionItem)) {
         let uid = self.widget_uid();
         self.handle_event_with(cx, event, &mut | cx, action | {
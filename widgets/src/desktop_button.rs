@@ -39,6 +39,10 @@ impl Widget for DesktopButton{
         self.draw_bg.redraw(cx)
     }
     
+    fn area(&self) -> Area {
+        self.draw_bg.area()
+    }
+    
     fn draw_walk_widget(&mut self, cx: &mut Cx2d, walk: Walk) -> WidgetDraw {
         let _ = self.draw_walk(cx, walk);
         WidgetDraw::done()
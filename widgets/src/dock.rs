@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use crate::{
     makepad_derive_widget::*,
+    makepad_micro_serde::*,
     widget::*,
     makepad_draw::*,
     splitter::{SplitterAction, Splitter, SplitterAlign},
@@ -199,6 +200,83 @@ pub enum DockItem {
     }
 }
 
+// A snapshot of the dock items that can be stored with micro-serde (RON or JSON)
+// and applied again with set_layout. Ids are stored as the value of their LiveId.
+#[derive(Clone, Debug, Default, SerRon, DeRon, SerJson, DeJson)]
+pub struct DockLayout {
+    pub items: Vec<DockLayoutEntry>
+}
+
+#[derive(Clone, Debug, SerRon, DeRon, SerJson, DeJson)]
+pub struct DockLayoutEntry {
+    pub id: u64,
+    pub item: DockLayoutItem
+}
+
+#[derive(Clone, Debug, SerRon, DeRon, SerJson, DeJson)]
+pub enum DockLayoutItem {
+    Splitter {
+        vertical: bool,
+        align: SplitterAlign,
+        a: u64,
+        b: u64
+    },
+    Tabs {
+        tabs: Vec<u64>,
+        selected: usize,
+        closable: bool
+    },
+    Tab {
+        name: String,
+        closable: bool,
+        kind: u64
+    }
+}
+
+impl DockLayoutItem {
+    fn from_dock_item(item: &DockItem) -> Self {
+        match item {
+            DockItem::Splitter {axis, align, a, b} => Self::Splitter {
+                vertical: matches!(axis, Axis::Vertical),
+                align: *align,
+                a: a.0,
+                b: b.0
+            },
+            DockItem::Tabs {tabs, selected, closable} => Self::Tabs {
+                tabs: tabs.iter().map( | id | id.0).collect(),
+                selected: *selected,
+                closable: *closable
+            },
+            DockItem::Tab {name, closable, kind} => Self::Tab {
+                name: name.clone(),
+                closable: *closable,
+                kind: kind.0
+            }
+        }
+    }
+    
+    fn to_dock_item(&self) -> DockItem {
+        match self {
+            Self::Splitter {vertical, align, a, b} => DockItem::Splitter {
+                axis: if *vertical {Axis::Vertical} else {Axis::Horizontal},
+                align: *align,
+                a: LiveId(*a),
+                b: LiveId(*b)
+            },
+            Self::Tabs {tabs, selected, closable} => DockItem::Tabs {
+                tabs: tabs.iter().map( | id | LiveId(*id)).collect(),
+                selected: *selected,
+                closable: *closable
+            },
+            Self::Tab {name, closable, kind} => DockItem::Tab {
+                name: name.clone(),
+                closable: *closable,
+                kind: LiveId(*kind)
+            }
+        }
+    }
+}
+
 impl LiveHook for Dock {
     fn apply_value_instance(&mut self, cx: &mut Cx, from: ApplyFrom, index: usize, nodes: &[LiveNode]) -> usize {
         let id = nodes[index].id;
@@ -720,6 +798,40 @@ impl Dock {
         }
     }
     
+    pub fn layout(&self) -> DockLayout {
+        let mut items: Vec<DockLayoutEntry> = self.dock_items.iter().map( | (id, item) | DockLayoutEntry {
+            id: id.0,
+            item: DockLayoutItem::from_dock_item(item)
+        }).collect();
+        // keep the output stable between saves
+        items.sort_by_key( | entry | entry.id);
+        DockLayout {items}
+    }
+    
+    // Replaces the dock items with a saved layout. Tabs of a kind without a template
+    // are left out, splitters and closable tab bars that end up empty fold away.
+    // Returns false and keeps the current layout when the saved one has no root.
+    pub fn set_layout(&mut self, cx: &mut Cx, layout: &DockLayout) -> bool {
+        let dock_items = match resolve_layout(layout, | kind | self.templates.contains_key(&kind)) {
+            Some(dock_items) => dock_items,
+            None => return false
+        };
+        
+        // a widget made from another template than the layout asks for is rebuilt
+        self.items.retain( | id, (kind, _) | match dock_items.get(id) {
+            Some(DockItem::Tab {kind: layout_kind, ..}) => layout_kind == kind,
+            _ => false
+        });
+        self.dock_items = dock_items;
+        for (item_id, kind) in self.dock_items.iter().filter_map( | (id, item) | {
+            if let DockItem::Tab {kind, ..} = item {Some((*id, *kind))} else {None}
+        }).collect::<Vec<_ >> () {
+            self.item_or_create(cx, item_id, kind);
+        }
+        self.area.redraw(cx);
+        true
+    }
+    
     pub fn drawing_item_id(&self) -> Option<LiveId> {
         if let Some(stack) = self.draw_state.as_ref() {
             match stack.last() {
@@ -950,6 +1062,76 @@ impl Widget for Dock {
     }
 }
 
+// Turns a saved layout into dock items, see Dock::set_layout.
+fn resolve_layout(layout: &DockLayout, has_template: impl Fn(LiveId) -> bool) -> Option<HashMap<LiveId, DockItem>> {
+    let mut dock_items: HashMap<LiveId, DockItem> = layout.items.iter().map( | entry | {
+        (LiveId(entry.id), entry.item.to_dock_item())
+    }).collect();
+    if !dock_items.contains_key(&live_id!(root)) {
+        return None
+    }
+    
+    dock_items.retain( | _, item | match item {
+        DockItem::Tab {kind, ..} => has_template(*kind),
+        _ => true
+    });
+    let tab_ids: Vec<LiveId> = dock_items.iter().filter_map( | (id, item) | {
+        if let DockItem::Tab {..} = item {Some(*id)} else {None}
+    }).collect();
+    for item in dock_items.values_mut() {
+        if let DockItem::Tabs {tabs, selected, ..} = item {
+            // keep the same tab selected, wherever it ends up
+            let selected_id = tabs.get(*selected).copied();
+            tabs.retain( | tab | tab_ids.contains(tab));
+            *selected = selected_id.and_then( | id | tabs.iter().position( | tab | *tab == id)).unwrap_or(0);
+        }
+    }
+    
+    // fold away what is empty now, from the leaves up
+    fn fold(dock_items: &mut HashMap<LiveId, DockItem>, id: LiveId) -> Option<LiveId> {
+        match dock_items.get(&id).cloned() {
+            Some(DockItem::Splitter {axis, align, a, b}) => {
+                match (fold(dock_items, a), fold(dock_items, b)) {
+                    (Some(a), Some(b)) => {
+                        dock_items.insert(id, DockItem::Splitter {axis, align, a, b});
+                        Some(id)
+                    }
+                    (Some(keep), None) | (None, Some(keep)) => {
+                        dock_items.remove(&id);
+                        Some(keep)
+                    }
+                    (None, None) => {
+                        dock_items.remove(&id);
+                        None
+                    }
+                }
+            }
+            Some(DockItem::Tabs {tabs, closable, ..}) => {
+                if tabs.is_empty() && closable {
+                    dock_items.remove(&id);
+                    None
+                }
+                else {
+                    Some(id)
+                }
+            }
+            _ => None
+        }
+    }
+    match fold(&mut dock_items, live_id!(root)) {
+        // the root keeps its id
+        Some(id) if id != live_id!(root) => {
+            let item = dock_items.remove(&id).unwrap();
+            dock_items.insert(live_id!(root), item);
+        }
+        Some(_) => (),
+        None => {
+            dock_items.insert(live_id!(root), DockItem::Tabs {tabs: vec![], selected: 0, closable: false});
+        }
+    }
+    Some(dock_items)
+}
+
 #[derive(Clone, Debug, PartialEq, WidgetRef)]
 pub struct DockRef(WidgetRef);
 
@@ -1035,6 +1217,20 @@ impl DockRef {
         None
     }
     
+    pub fn layout(&self) -> Option<DockLayout> {
+        if let Some(dock) = self.borrow() {
+            return Some(dock.layout());
+        }
+        None
+    }
+    
+    pub fn set_layout(&self, cx: &mut Cx, layout: &DockLayout) -> bool {
+        if let Some(mut dock) = self.borrow_mut() {
+            return dock.set_layout(cx, layout);
+        }
+        false
+    }
+    
     pub fn drop_clone(&self, cx: &mut Cx, abs: DVec2, old_item: LiveId, new_item: LiveId) {
         if let Some(mut dock) = self.borrow_mut() {
             dock.drop_clone(cx, abs, old_item, new_item);
@@ -1099,3 +1295,102 @@ impl DockRef {
 
 #[derive(Clone, WidgetSet)]
 pub struct DockSet(WidgetSet);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn entry(id: LiveId, item: DockLayoutItem) -> DockLayoutEntry {
+        DockLayoutEntry {id: id.0, item}
+    }
+    
+    fn tab(name: &str, kind: LiveId) -> DockLayoutItem {
+        DockLayoutItem::Tab {name: name.to_string(), closable: true, kind: kind.0}
+    }
+    
+    // root splits into a file tree on the left and closable editor tabs on the right
+    fn sample_layout() -> DockLayout {
+        DockLayout {items: vec![
+            entry(live_id!(root), DockLayoutItem::Splitter {
+                vertical: true,
+                align: SplitterAlign::FromA(300.0),
+                a: live_id!(file_tree_tabs).0,
+                b: live_id!(edit_tabs).0
+            }),
+            entry(live_id!(file_tree_tabs), DockLayoutItem::Tabs {
+                tabs: vec![live_id!(file_tree).0],
+                selected: 0,
+                closable: false
+            }),
+            entry(live_id!(edit_tabs), DockLayoutItem::Tabs {
+                tabs: vec![live_id!(edit_first).0, live_id!(log).0, live_id!(edit_second).0],
+                selected: 2,
+                closable: true
+            }),
+            entry(live_id!(file_tree), tab("Explore", live_id!(FileTree))),
+            entry(live_id!(edit_first), tab("first.rs", live_id!(CodeEditor))),
+            entry(live_id!(log), tab("Log", live_id!(LogList))),
+            entry(live_id!(edit_second), tab("second.rs", live_id!(CodeEditor))),
+        ]}
+    }
+    
+    #[test]
+    fn test_layout_round_trip() {
+        let layout = sample_layout();
+        
+        let ron = layout.serialize_ron();
+        let from_ron = DockLayout::deserialize_ron(&ron).unwrap();
+        assert_eq!(from_ron.serialize_ron(), ron);
+        
+        let json = layout.serialize_json();
+        let from_json = DockLayout::deserialize_json(&json).unwrap();
+        assert_eq!(from_json.serialize_json(), json);
+        assert_eq!(from_json.serialize_ron(), ron);
+    }
+    
+    #[test]
+    fn test_resolve_layout_without_root() {
+        let mut layout = sample_layout();
+        layout.items.retain( | entry | entry.id != live_id!(root).0);
+        assert!(resolve_layout(&layout, | _ | true).is_none());
+    }
+    
+    #[test]
+    fn test_resolve_layout_drops_tabs() {
+        // the log has no template, the selected second editor moves up one place
+        let items = resolve_layout(&sample_layout(), | kind | kind != live_id!(LogList)).unwrap();
+        assert!(!items.contains_key(&live_id!(log)));
+        assert!(matches!(
+            items.get(&live_id!(edit_tabs)),
+            Some(DockItem::Tabs {tabs, selected: 1, ..}) if *tabs == vec![live_id!(edit_first), live_id!(edit_second)]
+        ));
+        assert!(matches!(items.get(&live_id!(root)), Some(DockItem::Splitter {..})));
+        
+        // a selected tab that is dropped falls back to the first one
+        let items = resolve_layout(&sample_layout(), | kind | kind == live_id!(FileTree) || kind == live_id!(LogList)).unwrap();
+        assert!(matches!(
+            items.get(&live_id!(edit_tabs)),
+            Some(DockItem::Tabs {tabs, selected: 0, ..}) if *tabs == vec![live_id!(log)]
+        ));
+    }
+    
+    #[test]
+    fn test_resolve_layout_collapses_splitters() {
+        // the editor tabs end up empty and are closable, the splitter folds into the file tree tabs
+        let items = resolve_layout(&sample_layout(), | kind | kind == live_id!(FileTree)).unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(matches!(
+            items.get(&live_id!(root)),
+            Some(DockItem::Tabs {tabs, selected: 0, closable: false}) if *tabs == vec![live_id!(file_tree)]
+        ));
+        assert!(items.contains_key(&live_id!(file_tree)));
+        
+        // without any tabs the file tree tab bar is kept as an empty root, it is not closable
+        let items = resolve_layout(&sample_layout(), | _ | false).unwrap();
+        assert_eq!(items.len(), 1);
+        assert!(matches!(
+            items.get(&live_id!(root)),
+            Some(DockItem::Tabs {tabs, closable: false, ..}) if tabs.is_empty()
+        ));
+    }
+}
@@ -50,6 +50,10 @@ impl Widget for Image {
         self.draw_bg.redraw(cx)
     }
     
+    fn area(&self) -> Area {
+        self.draw_bg.area()
+    }
+    
     fn walk(&mut self, _cx:&mut Cx) -> Walk {
         self.walk
     }
@@ -74,6 +74,52 @@ impl ImageBuffer {
     }
     
     
+    // reads back the cpu side pixels of an ImageBGRA texture
+    pub fn from_texture(cx: &mut Cx, texture: &Texture) -> Result<Self, String> {
+        let desc = texture.get_desc(cx);
+        if desc.format != TextureFormat::ImageBGRA {
+            return Err("ImageBuffer::from_texture only image textures can be read back".to_string())
+        }
+        let width = desc.width.unwrap_or(0);
+        let height = desc.height.unwrap_or(0);
+        let data = texture.get_image_u32(cx);
+        if width * height == 0 || data.len() != width * height {
+            return Err("ImageBuffer::from_texture texture has no image data".to_string())
+        }
+        Ok(ImageBuffer {
+            width,
+            height,
+            data: data.to_vec()
+        })
+    }
+    
+    fn to_image_formats(&self) -> makepad_image_formats::ImageBuffer {
+        makepad_image_formats::ImageBuffer {
+            width: self.width,
+            height: self.height,
+            data: self.data.clone()
+        }
+    }
+    
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        makepad_image_formats::png::encode(&self.to_image_formats())
+    }
+    
+    pub fn to_bmp(&self) -> Result<Vec<u8>, String> {
+        makepad_image_formats::bmp::encode(&self.to_image_formats())
+    }
+
+    pub fn to_clipboard(&self) -> Result<ClipboardData, String> {
+        Ok(ClipboardData::new().with_png(self.to_png()?))
+    }
+
+    pub fn from_clipboard(data: &ClipboardData) -> Result<Self, String> {
+        match data.png() {
+            Some(png) => Self::from_png(png),
+            None => Err("No PNG image on the clipboard".to_string())
+        }
+    }
+
     pub fn from_png(
         data: &[u8]
     ) -> Result<Self, String> {
@@ -31,6 +31,10 @@ impl Widget for Label {
         self.draw_text.redraw(cx)
     }
     
+    fn area(&self) -> Area {
+        self.draw_text.area()
+    }
+    
     fn walk(&mut self, _cx:&mut Cx)->Walk{
         self.walk
     }
@@ -1,5 +1,6 @@
 pub use makepad_draw::makepad_platform;
 pub use makepad_draw;
+pub use makepad_markdown;
 
 pub use makepad_derive_widget;
 pub use makepad_draw::*;
@@ -7,6 +8,8 @@ pub use makepad_derive_widget::*;
 
 pub mod button;
 pub mod label;
+pub mod rich_text;
+pub mod markdown;
 pub mod image;
 pub mod link_label;
 pub mod drop_down;
@@ -32,6 +35,7 @@ pub mod desktop_button;
 pub mod window;
 pub mod scroll_shadow;
 pub mod window_menu;
+pub mod menu_bar;
 // Only available on Android at the moment
 // #[cfg(target_os="android")]
 pub mod video;
@@ -50,6 +54,8 @@ pub mod nav_control;
 
 pub mod view;
 pub mod widget;
+#[cfg(any(test, feature = "ui_test"))]
+pub mod ui_test;
 
 #[macro_use]
 pub mod data_binding;
@@ -58,12 +64,17 @@ mod base;
 mod theme_desktop_dark;
 pub mod image_cache;
 
+#[cfg(any(test, feature = "ui_test"))]
+pub use crate::ui_test::UiTest;
+
 pub use crate::{
     data_binding::{DataBindingStore, DataBindingMap},
     button::*,
     view::*,
     image::*,
     label::*,
+    rich_text::*,
+    markdown::*,
     slider::*,
     check_box::*,
     drop_down::*,
@@ -77,6 +88,7 @@ pub use crate::{
     slide_panel::*,
     fold_button::*,
     dock::*,
+    menu_bar::*,
     window::*,
     tab::TabClosable,
     scroll_bars::{ScrollBars},
@@ -84,6 +96,7 @@ pub use crate::{
     scroll_bar::{ScrollBar},
     slides_view::{SlidesView},
     widget::{
+        WidgetCache,
         WidgetSet,
         WidgetSetIterator,
         WidgetUid,
@@ -113,6 +126,8 @@ pub fn live_design(cx: &mut Cx) {
     crate::theme_desktop_dark::live_design(cx);
     crate::slider::live_design(cx);
     crate::label::live_design(cx);
+    crate::rich_text::live_design(cx);
+    crate::markdown::live_design(cx);
     crate::nav_control::live_design(cx);
     crate::image::live_design(cx);
     crate::rotated_image::live_design(cx);
@@ -126,6 +141,7 @@ pub fn live_design(cx: &mut Cx) {
     crate::desktop_button::live_design(cx);
     crate::window::live_design(cx);
     crate::window_menu::live_design(cx);
+    crate::menu_bar::live_design(cx);
     crate::scroll_bar::live_design(cx);
     crate::scroll_bars::live_design(cx);
     crate::check_box::live_design(cx);
@@ -36,6 +36,10 @@ impl Widget for LinkLabel {
         self.button.redraw(cx)
     }
     
+    fn area(&self) -> Area {
+        self.button.area()
+    }
+    
     fn walk(&mut self, cx:&mut Cx) -> Walk {
         self.button.walk(cx)
     }
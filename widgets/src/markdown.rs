@@ -0,0 +1,301 @@
+use {
+    crate::{
+        makepad_derive_widget::*,
+        makepad_draw::*,
+        makepad_markdown::*,
+        widget::*
+    }
+};
+
+live_design!{
+    MarkdownBase = {{Markdown}} {}
+}
+
+// Renders markdown text. Every block becomes a wrapped span flow laid out top to
+// bottom, indented by its list and quote depth. Text can be appended while it
+// streams in, only the trailing block is reparsed.
+
+// link spans get ids of their own so they can't be mistaken for other live ids
+const LINK_ID_SEED: u64 = 0x6d61_726b_646f_776e;
+
+#[derive(Clone, WidgetAction)]
+pub enum MarkdownAction {
+    None,
+    LinkClicked(String),
+}
+
+#[derive(Live)]
+pub struct Markdown {
+    #[live] draw_text: DrawText,
+    #[live] draw_code_bg: DrawColor,
+    #[live] draw_quote: DrawColor,
+    #[live] draw_rule: DrawColor,
+    #[live] draw_underline: DrawColor,
+    #[walk] walk: Walk,
+    #[layout] layout: Layout,
+
+    // only the font of these styles is used, fonts without a path fall back to
+    // the one of draw_text. Code also takes the font size of its style
+    #[live] strong_style: TextStyle,
+    #[live] emphasis_style: TextStyle,
+    #[live] heading_style: TextStyle,
+    #[live] code_style: TextStyle,
+    // sizes of heading levels 1 and up, deeper levels use the last one
+    #[live] heading_font_sizes: Vec<f64>,
+    #[live] emphasis_color: Vec4,
+    #[live] link_color: Vec4,
+    #[live] code_color: Vec4,
+
+    #[live(20.0)] list_indent: f64,
+    #[live(12.0)] quote_indent: f64,
+    #[live(3.0)] quote_bar_width: f64,
+    #[live(1.0)] rule_size: f64,
+    #[live(1.0)] underline_size: f64,
+    #[live] code_padding: Padding,
+
+    #[live] text: RcStringMut,
+
+    #[rust] doc: MarkdownDoc,
+    #[rust] link_rects: Vec<(Rect, String)>,
+    #[rust] over_link: bool,
+    #[rust] area: Area,
+}
+
+impl LiveHook for Markdown {
+    fn before_live_design(cx: &mut Cx) {
+        register_widget!(cx, Markdown)
+    }
+
+    fn after_apply(&mut self, _cx: &mut Cx, _from: ApplyFrom, _index: usize, _nodes: &[LiveNode]) {
+        if self.doc.source() != self.text.as_ref() {
+            self.doc = MarkdownDoc::parse(self.text.as_ref());
+        }
+    }
+}
+
+impl Widget for Markdown {
+    fn handle_widget_event_with(
+        &mut self,
+        cx: &mut Cx,
+        event: &Event,
+        dispatch_action: &mut dyn FnMut(&mut Cx, WidgetActionItem)
+    ) {
+        let uid = self.widget_uid();
+        self.handle_event_with(cx, event, &mut | cx, action | {
+            dispatch_action(cx, WidgetActionItem::new(action.into(), uid));
+        });
+    }
+
+    fn redraw(&mut self, cx: &mut Cx) {
+        self.area.redraw(cx)
+    }
+
+    fn area(&self) -> Area {
+        self.area
+    }
+
+    fn walk(&mut self, _cx: &mut Cx) -> Walk {
+        self.walk
+    }
+
+    fn draw_walk_widget(&mut self, cx: &mut Cx2d, walk: Walk) -> WidgetDraw {
+        self.draw_walk(cx, walk);
+        WidgetDraw::done()
+    }
+
+    fn text(&self) -> String {
+        self.doc.source().to_string()
+    }
+
+    fn set_text(&mut self, v: &str) {
+        self.text.as_mut_empty().push_str(v);
+        self.doc = MarkdownDoc::parse(v);
+    }
+}
+
+impl Markdown {
+    pub fn handle_event_with(&mut self, cx: &mut Cx, event: &Event, dispatch_action: &mut dyn FnMut(&mut Cx, MarkdownAction)) {
+        match event.hits(cx, self.area) {
+            Hit::FingerHoverIn(fe) | Hit::FingerHoverOver(fe) => {
+                // the cursor of the text around links is left alone, it is only
+                // put back when the finger moves off a link
+                let over_link = self.link_at(fe.abs).is_some();
+                if over_link {
+                    cx.set_cursor(MouseCursor::Hand);
+                }
+                else if self.over_link {
+                    cx.set_cursor(MouseCursor::Default);
+                }
+                self.over_link = over_link;
+            }
+            Hit::FingerHoverOut(_) => {
+                if self.over_link {
+                    cx.set_cursor(MouseCursor::Default);
+                }
+                self.over_link = false;
+            }
+            Hit::FingerUp(fe) if fe.is_over => {
+                if let Some(link) = self.link_at(fe.abs) {
+                    dispatch_action(cx, MarkdownAction::LinkClicked(link.to_string()));
+                }
+            }
+            _ => ()
+        }
+    }
+
+    pub fn link_at(&self, abs: DVec2) -> Option<&str> {
+        self.link_rects.iter().find( | (rect, _) | rect.contains(abs)).map( | (_, link) | link.as_str())
+    }
+
+    // adds text to the end, for responses that stream in
+    pub fn append(&mut self, cx: &mut Cx, text: &str) {
+        self.text.as_mut().push_str(text);
+        self.doc.append(text);
+        self.redraw(cx);
+    }
+
+    pub fn draw_walk(&mut self, cx: &mut Cx2d, walk: Walk) {
+        cx.begin_turtle(walk, self.layout);
+        self.link_rects.clear();
+        let text_walk = if walk.width.is_fit() {Walk::fit()} else {Walk::fill_fit()};
+        let doc = std::mem::take(&mut self.doc);
+        let mut last_quote_depth = 0;
+        for block in &doc.blocks {
+            let mut indent = block.quote_depth as f64 * self.quote_indent + block.list_depth as f64 * self.list_indent;
+            // the marker of a list item hangs in its indent
+            if let MarkdownBlockKind::ListItem(_) = block.kind {
+                indent -= self.list_indent;
+            }
+            cx.begin_turtle(text_walk.with_margin_left(indent), Layout::flow_down());
+            self.draw_block(cx, text_walk, block);
+            let rect = cx.end_turtle();
+
+            // quote bars run on between the blocks of one quote
+            let left = rect.pos.x - indent;
+            for depth in 0..block.quote_depth {
+                let gap = if depth < last_quote_depth {self.layout.spacing} else {0.0};
+                self.draw_quote.draw_abs(cx, Rect {
+                    pos: dvec2(left + depth as f64 * self.quote_indent, rect.pos.y - gap),
+                    size: dvec2(self.quote_bar_width, rect.size.y + gap)
+                });
+            }
+            last_quote_depth = block.quote_depth;
+        }
+        self.doc = doc;
+        cx.end_turtle_with_area(&mut self.area);
+    }
+
+    fn draw_block(&mut self, cx: &mut Cx2d, text_walk: Walk, block: &MarkdownBlock) {
+        match &block.kind {
+            MarkdownBlockKind::Rule => {
+                self.draw_rule.draw_walk(cx, Walk {
+                    width: Size::fill(),
+                    height: Size::Fixed(self.rule_size),
+                    ..Walk::default()
+                });
+            }
+            MarkdownBlockKind::CodeBlock {..} => {
+                let span = TextSpan {
+                    text: block.text(),
+                    font: font_if_set(&self.code_style),
+                    font_size: Some(self.code_style.font_size),
+                    color: Some(self.code_color),
+                    ..Default::default()
+                };
+                self.draw_code_bg.begin(cx, text_walk, Layout::flow_down().with_padding(self.code_padding));
+                // code keeps its lines, it does not wrap
+                self.draw_text.draw_spans_walk(cx, Walk::fit(), Align::default(), &[span]);
+                self.draw_code_bg.end(cx);
+            }
+            MarkdownBlockKind::ListItem(marker) => {
+                let marker = match marker {
+                    MarkdownListMarker::Bullet => "•".to_string(),
+                    MarkdownListMarker::Number(number) => format!("{}.", number)
+                };
+                cx.begin_turtle(text_walk, Layout::flow_right());
+                self.draw_text.draw_spans_walk(cx, Walk {
+                    width: Size::Fixed(self.list_indent),
+                    height: Size::Fit,
+                    ..Walk::default()
+                }, Align::default(), &[TextSpan::new(&marker)]);
+                self.draw_inlines(cx, text_walk, &block.inlines, &TextSpan::default());
+                cx.end_turtle();
+            }
+            MarkdownBlockKind::Heading(level) => {
+                let heading = TextSpan {
+                    font: font_if_set(&self.heading_style).or_else( || font_if_set(&self.strong_style)),
+                    font_size: self.heading_font_sizes.get(level - 1).or(self.heading_font_sizes.last()).copied(),
+                    ..Default::default()
+                };
+                self.draw_inlines(cx, text_walk, &block.inlines, &heading);
+            }
+            MarkdownBlockKind::Paragraph => {
+                self.draw_inlines(cx, text_walk, &block.inlines, &TextSpan::default());
+            }
+        }
+    }
+
+    // base holds the style of the whole block, inline styles go on top of it
+    fn draw_inlines(&mut self, cx: &mut Cx2d, walk: Walk, inlines: &[MarkdownInline], base: &TextSpan) {
+        let spans: Vec<TextSpan> = inlines.iter().enumerate().map( | (index, inline) | {
+            let mut span = TextSpan {text: inline.text.clone(), ..base.clone()};
+            if inline.style.strong {
+                span.font = font_if_set(&self.strong_style).or(span.font);
+            }
+            if inline.style.emphasis {
+                span.font = font_if_set(&self.emphasis_style).or(span.font);
+                span.color = Some(self.emphasis_color);
+            }
+            if inline.style.code {
+                span.font = font_if_set(&self.code_style);
+                span.font_size = Some(self.code_style.font_size);
+                span.color = Some(self.code_color);
+            }
+            if inline.link.is_some() {
+                span.color = Some(self.link_color);
+                span.underline = true;
+                span.link = Some(LiveId::from_num(LINK_ID_SEED, index as u64));
+            }
+            span
+        }).collect();
+
+        let rects = self.draw_text.draw_spans_walk(cx, walk, Align::default(), &spans);
+        for rect in rects {
+            let span = &spans[rect.span];
+            if span.underline {
+                self.draw_underline.color = span.color.unwrap_or(self.draw_text.color);
+                self.draw_underline.draw_abs(cx, Rect {
+                    pos: dvec2(rect.rect.pos.x, rect.baseline + self.underline_size),
+                    size: dvec2(rect.rect.size.x, self.underline_size)
+                });
+            }
+            if let Some(link) = &inlines[rect.span].link {
+                self.link_rects.push((rect.rect, link.clone()));
+            }
+        }
+    }
+}
+
+fn font_if_set(text_style: &TextStyle) -> Option<Font> {
+    if !text_style.font.path.as_str().is_empty() {Some(text_style.font.clone())} else {None}
+}
+
+#[derive(Clone, PartialEq, WidgetRef)]
+pub struct MarkdownRef(WidgetRef);
+
+impl MarkdownRef {
+    pub fn append(&self, cx: &mut Cx, text: &str) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.append(cx, text);
+        }
+    }
+
+    pub fn link_clicked(&self, actions: &WidgetActions) -> Option<String> {
+        if let Some(item) = actions.find_single_action(self.widget_uid()) {
+            if let MarkdownAction::LinkClicked(link) = item.action() {
+                return Some(link)
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,534 @@
+use {
+    crate::{
+        makepad_derive_widget::*,
+        makepad_draw::*,
+        popup_menu::{PopupMenu, PopupMenuAction, PopupMenuItemId},
+        widget::*,
+    }
+};
+
+live_design!{
+    DrawMenuBarItem = {{DrawMenuBarItem}} {}
+    MenuBarBase = {{MenuBar}} {}
+}
+
+// A menu bar drawn in the window, for platforms without a native one. It shows
+// the same MacosMenu tree the macos menu is made of, opens submenus as popup
+// menus and sends Event::MacosMenuCommand for the chosen item, so apps handle
+// their menu commands the same way everywhere. Shortcuts of menu items don't go
+// through the menu bar, the window menu registers them as menu item commands and
+// they arrive as Event::MacosMenuCommand too.
+
+#[derive(Live, LiveHook)]#[repr(C)]
+struct DrawMenuBarItem {
+    #[deref] draw_super: DrawQuad,
+    #[live] hover: f32,
+    #[live] open: f32,
+}
+
+#[derive(Live)]
+pub struct MenuBar {
+    #[live] draw_bg: DrawColor,
+    #[live] draw_item: DrawMenuBarItem,
+    #[live] draw_text: DrawText,
+    #[live] draw_mnemonic: DrawColor,
+
+    #[walk] walk: Walk,
+    #[layout] layout: Layout,
+    #[live] item_walk: Walk,
+    #[live] item_layout: Layout,
+    #[live(1.0)] mnemonic_size: f64,
+
+    #[live] popup_menu: Option<LivePtr>,
+
+    #[rust] items: Vec<MacosMenu>,
+    // byte offset of the alt key letter in the name of every bar item
+    #[rust] mnemonics: Vec<Option<usize>>,
+    #[rust] item_areas: Vec<Area>,
+    #[rust] popups: ComponentMap<usize, PopupMenu>,
+    // the index of the open submenu on every level, starting in the bar
+    #[rust] open: Vec<usize>,
+    // the keyboard selection in the deepest open submenu
+    #[rust] selected: Option<usize>,
+    #[rust] select_on_draw: bool,
+    #[rust] hover: Option<usize>,
+    #[rust] show_mnemonics: bool,
+}
+
+impl LiveHook for MenuBar {
+    fn before_live_design(cx: &mut Cx) {
+        register_widget!(cx, MenuBar)
+    }
+
+    fn after_apply(&mut self, _cx: &mut Cx, from: ApplyFrom, _index: usize, _nodes: &[LiveNode]) {
+        if from.is_from_doc() {
+            self.popups.clear();
+        }
+    }
+}
+
+impl Widget for MenuBar {
+    fn handle_widget_event_with(&mut self, cx: &mut Cx, event: &Event, _dispatch_action: &mut dyn FnMut(&mut Cx, WidgetActionItem)) {
+        self.handle_event(cx, event);
+    }
+
+    fn redraw(&mut self, cx: &mut Cx) {
+        self.draw_bg.redraw(cx);
+        for popup in self.popups.values_mut() {
+            popup.redraw(cx);
+        }
+    }
+
+    fn area(&self) -> Area {
+        self.draw_bg.area()
+    }
+
+    fn walk(&mut self, _cx: &mut Cx) -> Walk {
+        self.walk
+    }
+
+    fn draw_walk_widget(&mut self, cx: &mut Cx2d, walk: Walk) -> WidgetDraw {
+        self.draw_walk(cx, walk);
+        WidgetDraw::done()
+    }
+}
+
+impl MenuBar {
+    pub fn set_menu(&mut self, cx: &mut Cx, menu: MacosMenu) {
+        self.items = match menu {
+            MacosMenu::Main {items} => items,
+            menu => vec![menu]
+        };
+        self.mnemonics = mnemonics(&self.items);
+        self.close(cx);
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.open.is_empty()
+    }
+
+    pub fn close(&mut self, cx: &mut Cx) {
+        if self.is_open() {
+            cx.sweep_unlock(self.draw_bg.area());
+        }
+        self.open.clear();
+        self.selected = None;
+        self.redraw(cx);
+    }
+
+    fn open_menu(&mut self, cx: &mut Cx, index: usize, select_first: bool) {
+        if let Some(MacosMenu::Sub {..}) = self.items.get(index) {
+            if !self.is_open() {
+                cx.sweep_lock(self.draw_bg.area());
+            }
+            self.open = vec![index];
+            self.select(if select_first {first_selectable(self.entries(1))} else {None});
+            self.redraw(cx);
+        }
+    }
+
+    // the entries shown at a depth, depth 0 is the bar itself
+    fn entries(&self, depth: usize) -> &[MacosMenu] {
+        entries(&self.items, &self.open[0..depth.min(self.open.len())])
+    }
+
+    fn select(&mut self, selected: Option<usize>) {
+        self.selected = selected;
+        self.select_on_draw = true;
+    }
+
+    fn activate(&mut self, cx: &mut Cx, depth: usize, index: usize) {
+        match self.entries(depth).get(index) {
+            Some(MacosMenu::Item {command, enabled: true, ..}) => {
+                cx.send_menu_command(*command);
+                self.close(cx);
+            }
+            Some(MacosMenu::Sub {..}) if depth == 0 => {
+                self.open_menu(cx, index, true);
+            }
+            Some(MacosMenu::Sub {..}) => {
+                self.open.truncate(depth);
+                self.open.push(index);
+                self.select(first_selectable(self.entries(depth + 1)));
+                self.redraw(cx);
+            }
+            _ => ()
+        }
+    }
+
+    fn hover_entry(&mut self, cx: &mut Cx, depth: usize, index: usize) {
+        let is_sub = matches!(self.entries(depth).get(index), Some(MacosMenu::Sub {..}));
+        if is_sub {
+            if self.open.get(depth) != Some(&index) {
+                self.open.truncate(depth);
+                self.open.push(index);
+                self.selected = None;
+                self.redraw(cx);
+            }
+        }
+        else {
+            if self.open.len() > depth {
+                self.open.truncate(depth);
+                self.redraw(cx);
+            }
+            self.selected = Some(index);
+        }
+    }
+
+    fn move_selection(&mut self, cx: &mut Cx, dir: isize) {
+        let entries = self.entries(self.open.len());
+        let count = entries.len() as isize;
+        let start = self.selected.map( | s | s as isize).unwrap_or(if dir > 0 {-1} else {count});
+        for step in 1..=count {
+            let index = (start + dir * step).rem_euclid(count) as usize;
+            if is_selectable(&entries[index]) {
+                self.select(Some(index));
+                self.redraw(cx);
+                return
+            }
+        }
+    }
+
+    fn step_menu(&mut self, cx: &mut Cx, dir: isize) {
+        let count = self.items.len() as isize;
+        let start = self.open[0] as isize;
+        for step in 1..=count {
+            let index = (start + dir * step).rem_euclid(count) as usize;
+            if let MacosMenu::Sub {..} = self.items[index] {
+                self.open_menu(cx, index, true);
+                return
+            }
+        }
+    }
+
+    fn close_level(&mut self, cx: &mut Cx) {
+        let index = self.open.pop();
+        if self.open.is_empty() {
+            self.close(cx);
+            cx.revert_key_focus();
+        }
+        else {
+            self.select(index);
+            self.redraw(cx);
+        }
+    }
+
+    fn handle_key(&mut self, cx: &mut Cx, ke: &KeyEvent) {
+        let depth = self.open.len();
+        match ke.key_code {
+            KeyCode::Escape => self.close_level(cx),
+            KeyCode::ArrowDown => self.move_selection(cx, 1),
+            KeyCode::ArrowUp => self.move_selection(cx, -1),
+            KeyCode::ArrowRight => match self.selected {
+                Some(index) if matches!(self.entries(depth).get(index), Some(MacosMenu::Sub {..})) => {
+                    self.activate(cx, depth, index)
+                }
+                _ => self.step_menu(cx, 1)
+            }
+            KeyCode::ArrowLeft => if depth > 1 {
+                self.close_level(cx)
+            }
+            else {
+                self.step_menu(cx, -1)
+            }
+            KeyCode::ReturnKey | KeyCode::Space => if let Some(index) = self.selected {
+                self.activate(cx, depth, index)
+            }
+            _ => ()
+        }
+    }
+
+    fn item_at(&self, cx: &Cx, abs: DVec2) -> Option<usize> {
+        self.item_areas.iter().position( | area | !area.is_empty() && area.get_rect(cx).contains(abs))
+    }
+
+    pub fn handle_event(&mut self, cx: &mut Cx, event: &Event) {
+        match event {
+            Event::KeyDown(ke) if ke.key_code == KeyCode::Alt => if !self.show_mnemonics {
+                self.show_mnemonics = true;
+                self.draw_bg.redraw(cx);
+            }
+            Event::KeyUp(ke) if ke.key_code == KeyCode::Alt => if self.show_mnemonics {
+                self.show_mnemonics = false;
+                self.draw_bg.redraw(cx);
+            }
+            Event::KeyDown(ke) if ke.modifiers.alt && !ke.modifiers.control && !ke.modifiers.logo => {
+                if let Some(c) = ke.key_code.to_char(false) {
+                    let index = self.items.iter().zip(&self.mnemonics).position( | (item, at) | {
+                        match (item_name(item), at) {
+                            (Some(name), Some(at)) => name[*at..].chars().next().map( | m | m.to_ascii_lowercase()) == Some(c),
+                            _ => false
+                        }
+                    });
+                    if let Some(index) = index {
+                        cx.set_key_focus(self.draw_bg.area());
+                        self.activate(cx, 0, index);
+                    }
+                }
+            }
+            _ => ()
+        }
+
+        let mut depth = 0;
+        while depth < self.open.len() {
+            let Some(popup) = self.popups.get_mut(&depth) else {break};
+            let mut actions = Vec::new();
+            popup.handle_event_with(cx, event, self.draw_bg.area(), &mut | _, action | actions.push(action));
+            for action in actions {
+                match action {
+                    PopupMenuAction::WasHovered(id) => self.hover_entry(cx, depth + 1, id.0.0 as usize),
+                    PopupMenuAction::WasSelected(id) => self.activate(cx, depth + 1, id.0.0 as usize),
+                    _ => ()
+                }
+            }
+            depth += 1;
+        }
+
+        // clicking outside of the bar and its menus closes them
+        if let Event::MouseDown(e) = event {
+            if self.is_open() && !self.draw_bg.area().get_clipped_rect(cx).contains(e.abs) {
+                let in_popup = (0..self.open.len()).any( | depth | {
+                    self.popups.get(&depth).map_or(false, | popup | popup.menu_contains_pos(cx, e.abs))
+                });
+                if !in_popup {
+                    self.close(cx);
+                }
+            }
+        }
+
+        match event.hits_with_sweep_area(cx, self.draw_bg.area(), self.draw_bg.area()) {
+            Hit::FingerDown(fe) => if let Some(index) = self.item_at(cx, fe.abs) {
+                if self.open.first() == Some(&index) {
+                    self.close(cx);
+                }
+                else {
+                    cx.set_key_focus(self.draw_bg.area());
+                    if let MacosMenu::Sub {..} = self.items[index] {
+                        self.open_menu(cx, index, false);
+                    }
+                    else {
+                        self.activate(cx, 0, index);
+                    }
+                }
+            }
+            Hit::FingerHoverIn(fe) | Hit::FingerHoverOver(fe) => {
+                let hover = self.item_at(cx, fe.abs);
+                if hover != self.hover {
+                    self.hover = hover;
+                    // with a menu open, hovering the bar switches between menus
+                    if let Some(index) = hover {
+                        if self.is_open() && self.open[0] != index {
+                            self.open_menu(cx, index, false);
+                        }
+                    }
+                    self.draw_bg.redraw(cx);
+                }
+            }
+            Hit::FingerHoverOut(_) => {
+                self.hover = None;
+                self.draw_bg.redraw(cx);
+            }
+            Hit::KeyDown(ke) => if self.is_open() {
+                self.handle_key(cx, &ke);
+            }
+            Hit::KeyFocusLost(_) => {
+                self.close(cx);
+            }
+            _ => ()
+        }
+    }
+
+    pub fn draw_walk(&mut self, cx: &mut Cx2d, walk: Walk) {
+        // without items the bar is still drawn with no height, so setting a menu can redraw it
+        self.draw_bg.begin(cx, walk, self.layout);
+        self.item_areas.clear();
+        for index in 0..self.items.len() {
+            let Some(name) = item_name(&self.items[index]) else {
+                self.item_areas.push(Area::Empty);
+                continue
+            };
+            self.draw_item.hover = if self.hover == Some(index) {1.0} else {0.0};
+            self.draw_item.open = if self.open.first() == Some(&index) {1.0} else {0.0};
+            self.draw_item.begin(cx, self.item_walk, self.item_layout);
+            match self.mnemonics[index].filter( | _ | self.show_mnemonics) {
+                Some(at) => {
+                    let end = at + name[at..].chars().next().map_or(0, | c | c.len_utf8());
+                    let spans = [
+                        TextSpan::new(&name[..at]),
+                        TextSpan::new(&name[at..end]),
+                        TextSpan::new(&name[end..])
+                    ];
+                    let rects = self.draw_text.draw_spans_walk(cx, Walk::fit(), Align::default(), &spans);
+                    for rect in rects.iter().filter( | rect | rect.span == 1) {
+                        self.draw_mnemonic.draw_abs(cx, Rect {
+                            pos: dvec2(rect.rect.pos.x, rect.baseline + self.mnemonic_size),
+                            size: dvec2(rect.rect.size.x, self.mnemonic_size)
+                        });
+                    }
+                }
+                None => self.draw_text.draw_walk(cx, Walk::fit(), Align::default(), name)
+            }
+            self.draw_item.end(cx);
+            self.item_areas.push(self.draw_item.area());
+        }
+        self.draw_bg.end(cx);
+        self.draw_popups(cx);
+    }
+
+    fn draw_popups(&mut self, cx: &mut Cx2d) {
+        for depth in 0..self.open.len() {
+            // the first menu hangs below its bar item, submenus open next to their item
+            let item_id = PopupMenuItemId(LiveId(self.open[depth] as u64));
+            let (area, below) = if depth == 0 {
+                (self.item_areas.get(self.open[0]).copied().unwrap_or(Area::Empty), true)
+            }
+            else {
+                (self.popups.get(&(depth - 1)).map_or(Area::Empty, | popup | popup.item_area(item_id)), false)
+            };
+            let rect = area.get_rect(cx);
+            let shift = if below {dvec2(0.0, rect.size.y)} else {dvec2(rect.size.x, 0.0)};
+
+            let popup_menu = self.popup_menu;
+            let popup = self.popups.get_or_insert(cx, depth, | cx | {
+                PopupMenu::new_from_ptr(cx, popup_menu)
+            });
+            popup.begin(cx);
+            for (index, entry) in entries(&self.items, &self.open[0..depth + 1]).iter().enumerate() {
+                let item_id = PopupMenuItemId(LiveId(index as u64));
+                match entry {
+                    MacosMenu::Item {name, command, shift, key, enabled} => {
+                        let hint = shortcut_hint(cx, *command, *shift, *key);
+                        popup.draw_item_with_hint(cx, item_id, name, &hint, *enabled);
+                    }
+                    MacosMenu::Sub {name, ..} => {
+                        popup.draw_item_with_hint(cx, item_id, name, ">", true);
+                    }
+                    MacosMenu::Line => popup.draw_line(cx),
+                    MacosMenu::Main {..} => ()
+                }
+            }
+            if depth + 1 == self.open.len() && self.select_on_draw {
+                if let Some(selected) = self.selected {
+                    popup.init_select_item(PopupMenuItemId(LiveId(selected as u64)));
+                }
+            }
+            popup.end(cx, area, shift);
+        }
+        self.select_on_draw = false;
+    }
+}
+
+fn item_name(item: &MacosMenu) -> Option<&str> {
+    match item {
+        MacosMenu::Item {name, ..} | MacosMenu::Sub {name, ..} => Some(name),
+        _ => None
+    }
+}
+
+fn is_selectable(item: &MacosMenu) -> bool {
+    matches!(item, MacosMenu::Item {enabled: true, ..} | MacosMenu::Sub {..})
+}
+
+fn first_selectable(items: &[MacosMenu]) -> Option<usize> {
+    items.iter().position(is_selectable)
+}
+
+fn entries<'a>(items: &'a [MacosMenu], open: &[usize]) -> &'a [MacosMenu] {
+    let mut items = items;
+    for index in open {
+        match items.get(*index) {
+            Some(MacosMenu::Sub {items: sub, ..}) => items = sub,
+            _ => return &[]
+        }
+    }
+    items
+}
+
+// every bar item gets the first letter of its name that no item before it uses
+fn mnemonics(items: &[MacosMenu]) -> Vec<Option<usize>> {
+    let mut used = Vec::new();
+    items.iter().map( | item | {
+        let (at, c) = item_name(item)?.char_indices().find( | (_, c) | {
+            c.is_alphanumeric() && !used.contains(&c.to_ascii_lowercase())
+        }) ?;
+        used.push(c.to_ascii_lowercase());
+        Some(at)
+    }).collect()
+}
+
+// the bound shortcut of the command, or the key of the menu item itself
+fn shortcut_hint(cx: &Cx, command: LiveId, shift: bool, key: KeyCode) -> String {
+    if let Some(chord) = cx.commands.chords(command).first() {
+        return chord.to_string()
+    }
+    if key.is_unknown() {
+        return String::new()
+    }
+    let chord = KeyChord::cmd(key);
+    if shift {chord.with_shift()} else {chord}.to_string()
+}
+
+#[derive(Clone, PartialEq, WidgetRef)]
+pub struct MenuBarRef(WidgetRef);
+
+impl MenuBarRef {
+    pub fn set_menu(&self, cx: &mut Cx, menu: MacosMenu) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_menu(cx, menu);
+        }
+    }
+
+    pub fn close(&self, cx: &mut Cx) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.close(cx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn item(name: &str) -> MacosMenu {
+        MacosMenu::Item {name: name.to_string(), command: LiveId::from_str(name), shift: false, key: KeyCode::Unknown, enabled: true}
+    }
+    
+    fn sub(name: &str, items: Vec<MacosMenu>) -> MacosMenu {
+        MacosMenu::Sub {name: name.to_string(), items}
+    }
+    
+    #[test]
+    fn test_mnemonics() {
+        let bar = vec![
+            sub("File", vec![]),
+            sub("Edit", vec![]),
+            sub("Find", vec![]),
+            MacosMenu::Line,
+            sub("fold", vec![]),
+            sub("-Go", vec![]),
+            sub("eFi", vec![]),
+            item("Run"),
+        ];
+        // f and e are taken, Find gets its i and fold its o
+        assert_eq!(mnemonics(&bar), vec![Some(0), Some(0), Some(1), None, Some(1), Some(1), None, Some(0)]);
+        assert!(mnemonics(&[]).is_empty());
+    }
+    
+    #[test]
+    fn test_entries() {
+        let bar = vec![
+            sub("File", vec![item("New"), MacosMenu::Line, sub("Recent", vec![item("a.rs"), item("b.rs")])]),
+            sub("Edit", vec![item("Undo")]),
+            item("Run"),
+        ];
+        assert_eq!(entries(&bar, &[]).len(), 3);
+        assert_eq!(item_name(&entries(&bar, &[1])[0]), Some("Undo"));
+        let recent = entries(&bar, &[0, 2]);
+        assert_eq!(recent.iter().map( | item | item_name(item).unwrap()).collect::<Vec<_ >> (), vec!["a.rs", "b.rs"]);
+        // items, lines and indices past the end don't open anything
+        assert!(entries(&bar, &[2]).is_empty());
+        assert!(entries(&bar, &[0, 1]).is_empty());
+        assert!(entries(&bar, &[3]).is_empty());
+        assert!(entries(&bar, &[0, 2, 0]).is_empty());
+    }
+}
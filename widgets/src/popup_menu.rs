@@ -7,16 +7,24 @@ use {
 };
 
 live_design!{
+    DrawPopupMenuText = {{DrawPopupMenuText}} {}
     PopupMenuItemBase = {{PopupMenuItem}} {}
     PopupMenuBase = {{PopupMenu}} {}
 }
 
 
+#[derive(Live, LiveHook)]#[repr(C)]
+struct DrawPopupMenuText {
+    #[deref] draw_super: DrawText,
+    #[live] disabled: f32,
+}
+
 #[derive(Live, LiveHook)]
 pub struct PopupMenuItem {
     
     #[live] draw_bg: DrawQuad,
-    #[live] draw_name: DrawText,
+    #[live] draw_name: DrawPopupMenuText,
+    #[live] draw_hint: DrawText,
     
     #[layout] layout: Layout,
     #[animator] animator: Animator,
@@ -28,6 +36,7 @@ pub struct PopupMenuItem {
     #[live] opened: f32,
     #[live] hover: f32,
     #[live] selected: f32,
+    #[rust(true)] enabled: bool,
 }
 
 #[derive(Live)]
@@ -39,6 +48,8 @@ pub struct PopupMenu {
     #[layout] layout: Layout,
     #[walk] walk: Walk,
     #[live] items: Vec<String>,
+    #[live] draw_line: DrawColor,
+    #[live] line_walk: Walk,
     #[rust] first_tap: bool,
     #[rust] menu_items: ComponentMap<PopupMenuItemId, PopupMenuItem>,
     #[rust] init_select_item: Option<PopupMenuItemId>,
@@ -58,6 +69,7 @@ impl LiveHook for PopupMenu {
 }
 
 pub enum PopupMenuItemAction {
+    WasHovered,
     WasSweeped,
     WasSelected,
     MightBeSelected,
@@ -66,6 +78,7 @@ pub enum PopupMenuItemAction {
 
 #[derive(Clone, WidgetAction)]
 pub enum PopupMenuAction {
+    WasHovered(PopupMenuItemId),
     WasSweeped(PopupMenuItemId),
     WasSelected(PopupMenuItemId),
     None,
@@ -80,9 +93,14 @@ impl PopupMenuItem {
         &mut self,
         cx: &mut Cx2d,
         label: &str,
+        hint: &str,
     ) {
+        self.draw_name.disabled = if self.enabled {0.0} else {1.0};
         self.draw_bg.begin(cx, self.walk, self.layout);
         self.draw_name.draw_walk(cx, Walk::fit(), Align::default(), label);
+        if hint.len() > 0 {
+            self.draw_hint.draw_walk(cx, Walk::fill_fit(), Align {x: 1.0, y: 0.0}, hint);
+        }
         self.draw_bg.end(cx);
     }
     
@@ -96,6 +114,9 @@ impl PopupMenuItem {
         if self.animator_handle_event(cx, event).must_redraw() {
             self.draw_bg.area().redraw(cx);
         }
+        if !self.enabled {
+            return
+        }
         
         match event.hits_with_options(
             cx,
@@ -104,6 +125,7 @@ impl PopupMenuItem {
         ) {
             Hit::FingerHoverIn(_) => {
                 self.animator_play(cx, id!(hover.on));
+                dispatch_action(cx, PopupMenuItemAction::WasHovered);
             }
             Hit::FingerHoverOut(_) => {
                 self.animator_play(cx, id!(hover.off));
@@ -178,6 +200,19 @@ impl PopupMenu {
         cx: &mut Cx2d,
         item_id: PopupMenuItemId,
         label: &str,
+    ) {
+        self.draw_item_with_hint(cx, item_id, label, "", true)
+    }
+    
+    // the hint is drawn right aligned, like the shortcut of a menu command.
+    // disabled items are drawn dimmed and can't be hovered or selected
+    pub fn draw_item_with_hint(
+        &mut self,
+        cx: &mut Cx2d,
+        item_id: PopupMenuItemId,
+        label: &str,
+        hint: &str,
+        enabled: bool,
     ) {
         self.count += 1;
         
@@ -185,7 +220,20 @@ impl PopupMenu {
         let menu_item = self.menu_items.get_or_insert(cx, item_id, | cx | {
             PopupMenuItem::new_from_ptr(cx, menu_item)
         });
-        menu_item.draw_item(cx, label);
+        menu_item.enabled = enabled;
+        menu_item.draw_item(cx, label, hint);
+    }
+    
+    pub fn draw_line(&mut self, cx: &mut Cx2d) {
+        self.draw_line.draw_walk(cx, self.line_walk);
+    }
+    
+    pub fn item_area(&self, item_id: PopupMenuItemId) -> Area {
+        self.menu_items.get(&item_id).map( | item | item.draw_bg.area()).unwrap_or(Area::Empty)
+    }
+    
+    pub fn set_selected_item(&mut self, cx: &mut Cx, which_id: PopupMenuItemId) {
+        self.select_item_state(cx, which_id);
     }
     
     pub fn init_select_item(&mut self, which_id: PopupMenuItemId) {
@@ -229,6 +277,9 @@ impl PopupMenu {
                         dispatch_action(cx, PopupMenuAction::WasSelected(node_id));
                     }
                 }
+                PopupMenuItemAction::WasHovered => {
+                    dispatch_action(cx, PopupMenuAction::WasHovered(node_id));
+                }
                 PopupMenuItemAction::WasSweeped => {
                     self.select_item_state(cx, node_id);
                     dispatch_action(cx, PopupMenuAction::WasSweeped(node_id));
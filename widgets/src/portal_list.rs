@@ -317,8 +317,10 @@ impl PortalList {
                     cx.begin_turtle(Walk {
                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y + self.first_scroll)),
                         margin: Default::default(),
-                        width: Size::Fill,
-                        height: Size::Fit
+                        width: Size::fill(),
+                        height: Size::Fit,
+                        cell: GridCell::default(),
+                        constraint: Constraint::default()
                     }, Layout::flow_down());
                     return Some(self.first_id)
                 }
@@ -346,8 +348,10 @@ impl PortalList {
                             cx.begin_turtle(Walk {
                                 abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                                 margin: Default::default(),
-                                width: Size::Fill,
-                                height: Size::Fit
+                                width: Size::fill(),
+                                height: Size::Fit,
+                                cell: GridCell::default(),
+                                constraint: Constraint::default()
                             }, Layout::flow_down());
                             return Some(self.first_id - 1);
                         }
@@ -373,8 +377,10 @@ impl PortalList {
                     cx.begin_turtle(Walk {
                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y + pos + rect.size.index(vi))),
                         margin: Default::default(),
-                        width: Size::Fill,
-                        height: Size::Fit
+                        width: Size::fill(),
+                        height: Size::Fit,
+                        cell: GridCell::default(),
+                        constraint: Constraint::default()
                     }, Layout::flow_down());
                     return Some(index + 1)
                 }
@@ -405,8 +411,10 @@ impl PortalList {
                                 cx.begin_turtle(Walk {
                                     abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                                     margin: Default::default(),
-                                    width: Size::Fill,
-                                    height: Size::Fit
+                                    width: Size::fill(),
+                                    height: Size::Fit,
+                                    cell: GridCell::default(),
+                                    constraint: Constraint::default()
                                 }, Layout::flow_down());
                                 return Some(last_index + 1);
                             }
@@ -430,8 +438,10 @@ impl PortalList {
                     cx.begin_turtle(Walk {
                         abs_pos: Some(dvec2(viewport.pos.x, viewport.pos.y)),
                         margin: Default::default(),
-                        width: Size::Fill,
-                        height: Size::Fit
+                        width: Size::fill(),
+                        height: Size::Fit,
+                        cell: GridCell::default(),
+                        constraint: Constraint::default()
                     }, Layout::flow_down());
                     
                     return Some(index - 1);
@@ -0,0 +1,168 @@
+use {
+    crate::{
+        makepad_derive_widget::*,
+        makepad_draw::*,
+        widget::*
+    }
+};
+
+live_design!{
+    RichTextBase = {{RichText}} {}
+}
+
+// A block of attributed text. The content is a list of TextSpans that can each
+// override the font, size and color of draw_text, be underlined or carry a link id.
+// All spans wrap together as one paragraph flow. Without spans the text property
+// is drawn as a single span.
+
+#[derive(Clone, WidgetAction)]
+pub enum RichTextAction {
+    None,
+    LinkClicked(LiveId),
+}
+
+#[derive(Live)]
+pub struct RichText {
+    #[live] draw_bg: DrawColor,
+    #[live] draw_text: DrawText,
+    #[live] draw_underline: DrawColor,
+    #[walk] walk: Walk,
+    #[layout] layout: Layout,
+    #[live(1.0)] underline_size: f64,
+    #[live] text: RcStringMut,
+
+    #[rust] spans: Vec<TextSpan>,
+    #[rust] span_rects: Vec<TextSpanRect>,
+}
+
+impl LiveHook for RichText {
+    fn before_live_design(cx: &mut Cx) {
+        register_widget!(cx, RichText)
+    }
+}
+
+impl Widget for RichText {
+    fn handle_widget_event_with(
+        &mut self,
+        cx: &mut Cx,
+        event: &Event,
+        dispatch_action: &mut dyn FnMut(&mut Cx, WidgetActionItem)
+    ) {
+        let uid = self.widget_uid();
+        self.handle_event_with(cx, event, &mut | cx, action | {
+            dispatch_action(cx, WidgetActionItem::new(action.into(), uid));
+        });
+    }
+
+    fn redraw(&mut self, cx: &mut Cx) {
+        self.draw_bg.redraw(cx)
+    }
+
+    fn area(&self) -> Area {
+        self.draw_bg.area()
+    }
+
+    fn walk(&mut self, _cx: &mut Cx) -> Walk {
+        self.walk
+    }
+
+    fn draw_walk_widget(&mut self, cx: &mut Cx2d, walk: Walk) -> WidgetDraw {
+        self.draw_walk(cx, walk);
+        WidgetDraw::done()
+    }
+
+    fn text(&self) -> String {
+        if self.spans.len() == 0 {
+            return self.text.as_ref().to_string()
+        }
+        self.spans.iter().map( | span | span.text.as_str()).collect()
+    }
+
+    fn set_text(&mut self, v: &str) {
+        self.spans.clear();
+        self.text.as_mut_empty().push_str(v);
+    }
+}
+
+impl RichText {
+    pub fn handle_event_with(&mut self, cx: &mut Cx, event: &Event, dispatch_action: &mut dyn FnMut(&mut Cx, RichTextAction)) {
+        match event.hits(cx, self.draw_bg.area()) {
+            Hit::FingerHoverIn(fe) | Hit::FingerHoverOver(fe) => {
+                if self.link_at(fe.abs).is_some() {
+                    cx.set_cursor(MouseCursor::Hand);
+                }
+                else {
+                    cx.set_cursor(MouseCursor::Default);
+                }
+            }
+            Hit::FingerUp(fe) => if fe.is_over {
+                if let Some(link) = self.link_at(fe.abs) {
+                    dispatch_action(cx, RichTextAction::LinkClicked(link));
+                }
+            }
+            _ => ()
+        }
+    }
+
+    pub fn draw_walk(&mut self, cx: &mut Cx2d, walk: Walk) {
+        self.draw_bg.begin(cx, walk, self.layout);
+        let text_walk = if walk.width.is_fit() {Walk::fit()} else {Walk::fill_fit()};
+        self.span_rects = if self.spans.len() == 0 {
+            let span = TextSpan::new(self.text.as_ref());
+            self.draw_text.draw_spans_walk(cx, text_walk, self.layout.align, &[span])
+        }
+        else {
+            self.draw_text.draw_spans_walk(cx, text_walk, self.layout.align, &self.spans)
+        };
+        for rect in &self.span_rects {
+            if let Some(span) = self.spans.get(rect.span) {
+                if span.underline {
+                    self.draw_underline.color = span.color.unwrap_or(self.draw_text.color);
+                    self.draw_underline.draw_abs(cx, Rect {
+                        pos: dvec2(rect.rect.pos.x, rect.baseline + self.underline_size),
+                        size: dvec2(rect.rect.size.x, self.underline_size)
+                    });
+                }
+            }
+        }
+        self.draw_bg.end(cx);
+    }
+
+    pub fn set_spans(&mut self, cx: &mut Cx, spans: Vec<TextSpan>) {
+        self.spans = spans;
+        self.redraw(cx);
+    }
+
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+
+    // the index of the span at an absolute position, as of the last draw
+    pub fn span_at(&self, abs: DVec2) -> Option<usize> {
+        TextSpanRect::find(&self.span_rects, abs)
+    }
+
+    pub fn link_at(&self, abs: DVec2) -> Option<LiveId> {
+        self.span_at(abs).and_then( | index | self.spans.get(index)).and_then( | span | span.link)
+    }
+}
+
+#[derive(Clone, PartialEq, WidgetRef)]
+pub struct RichTextRef(WidgetRef);
+
+impl RichTextRef {
+    pub fn set_spans(&self, cx: &mut Cx, spans: Vec<TextSpan>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_spans(cx, spans);
+        }
+    }
+
+    pub fn link_clicked(&self, actions: &WidgetActions) -> Option<LiveId> {
+        if let Some(item) = actions.find_single_action(self.widget_uid()) {
+            if let RichTextAction::LinkClicked(link) = item.action() {
+                return Some(link)
+            }
+        }
+        None
+    }
+}
@@ -200,6 +200,10 @@ impl Widget for Slider {
         self.draw_slider.redraw(cx);
     }
     
+    fn area(&self) -> Area {
+        self.draw_slider.area()
+    }
+    
     fn handle_widget_event_with(&mut self, cx: &mut Cx, event: &Event, dispatch_action: &mut dyn FnMut(&mut Cx, WidgetActionItem)) {
         let uid = self.widget_uid();
         self.handle_event_with(cx, event, &mut | cx, action | {
@@ -1,6 +1,7 @@
 use crate::{
     makepad_derive_widget::*,
     makepad_draw::*,
+    makepad_micro_serde::*,
     widget::*,
 };
 
@@ -127,8 +128,8 @@ impl Splitter {
         self.position = self.align.to_position(self.axis, self.rect);
         
         let walk = match self.axis {
-            Axis::Horizontal => Walk::size(Size::Fixed(self.position), Size::Fill),
-            Axis::Vertical => Walk::size(Size::Fill, Size::Fixed(self.position)),
+            Axis::Horizontal => Walk::size(Size::Fixed(self.position), Size::fill()),
+            Axis::Vertical => Walk::size(Size::fill(), Size::Fixed(self.position)),
         };
         cx.begin_turtle(walk, Layout::flow_down());
     }
@@ -138,11 +139,11 @@ impl Splitter {
         match self.axis {
             Axis::Horizontal => {
                 self.draw_splitter.is_vertical = 1.0;
-                self.draw_splitter.draw_walk(cx, Walk::size(Size::Fixed(self.split_bar_size), Size::Fill));
+                self.draw_splitter.draw_walk(cx, Walk::size(Size::Fixed(self.split_bar_size), Size::fill()));
             }
             Axis::Vertical => {
                 self.draw_splitter.is_vertical = 0.0;
-                self.draw_splitter.draw_walk(cx, Walk::size(Size::Fill, Size::Fixed(self.split_bar_size)));
+                self.draw_splitter.draw_walk(cx, Walk::size(Size::fill(), Size::Fixed(self.split_bar_size)));
             }
         }
         cx.begin_turtle(Walk::default(), Layout::flow_down());
@@ -268,7 +269,7 @@ fn margin(&self) -> Margin {
 }
 }
 
-#[derive(Clone, Copy, Debug, Live, LiveHook)]
+#[derive(Clone, Copy, Debug, Live, LiveHook, SerRon, DeRon, SerJson, DeJson)]
 #[live_ignore]
 pub enum SplitterAlign {
     #[live(50.0)] FromA(f64),
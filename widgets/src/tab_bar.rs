@@ -61,14 +61,14 @@ impl TabBar {
             self.draw_drag.draw_walk(
                 cx,
                 Walk {
-                    width: Size::Fill,
-                    height: Size::Fill,
+                    width: Size::fill(),
+                    height: Size::fill(),
                     ..Walk::default()
                 },
             );
         }
         self.tabs.retain_visible();
-        self.draw_fill.draw_walk(cx, Walk::size(Size::Fill, Size::Fill));
+        self.draw_fill.draw_walk(cx, Walk::size(Size::fill(), Size::fill()));
         self.scroll_bars.end(cx);
     }
     
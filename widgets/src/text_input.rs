@@ -44,6 +44,7 @@ pub struct TextInput {
     #[live] draw_bg: DrawColor,
     #[live] draw_select: DrawQuad,
     #[live] draw_cursor: DrawQuad,
+    #[live] draw_preedit: DrawColor,
     #[live] draw_text: DrawLabel,
     
     #[walk] walk: Walk,
@@ -52,6 +53,7 @@ pub struct TextInput {
     #[live] label_align: Align,
     
     #[live] cursor_size: f64,
+    #[live(1.0)] preedit_underline: f64,
     #[live] cursor_margin_bottom: f64,
     #[live] cursor_margin_top: f64,
     #[live] select_pad_edges: f64,
@@ -72,7 +74,9 @@ pub struct TextInput {
     #[rust] undo_stack: Vec<UndoItem>,
     #[rust] redo_stack: Vec<UndoItem>,
     #[rust] cursor_tail: usize,
-    #[rust] cursor_head: usize
+    #[rust] cursor_head: usize,
+    #[rust] preedit: String,
+    #[rust] preedit_cursor: usize,
 }
 
 impl LiveHook for TextInput {
@@ -94,6 +98,10 @@ impl Widget for TextInput {
         self.draw_bg.redraw(cx);
     }
     
+    fn area(&self) -> Area {
+        self.draw_bg.area()
+    }
+    
     fn handle_widget_event_with(&mut self, cx: &mut Cx, event: &Event, dispatch_action: &mut dyn FnMut(&mut Cx, WidgetActionItem)) {
         let uid = self.widget_uid();
         self.handle_event_with(cx, event, &mut | cx, action | {
@@ -244,6 +252,24 @@ impl TextInput {
         self.text = new;
     }
     
+    // the text with the input method composition in place of the selection
+    fn text_with_preedit(&self) -> String {
+        let (left, right) = self.sorted_cursor();
+        let mut out = String::new();
+        for (i, c) in self.text.chars().enumerate() {
+            if i == left {
+                out.push_str(&self.preedit);
+            }
+            if i < left || i >= right {
+                out.push(c);
+            }
+        }
+        if left >= self.text.chars().count() {
+            out.push_str(&self.preedit);
+        }
+        out
+    }
+    
     pub fn select_word(&mut self, around: usize) {
         let mut first_ws = Some(0);
         let mut last_ws = None;
@@ -325,6 +351,7 @@ impl TextInput {
         match event.hits(cx, self.draw_bg.area()) {
             Hit::KeyFocusLost(_) => {
                 self.animator_play(cx, id!(focus.off));
+                self.preedit.clear();
                 cx.hide_text_ime();
                 dispatch_action(cx, TextInputAction::Return(self.text.clone()));
                 dispatch_action(cx, TextInputAction::KeyFocusLost);
@@ -339,7 +366,20 @@ impl TextInput {
                 self.draw_bg.redraw(cx);
                 dispatch_action(cx, TextInputAction::KeyFocus);
             }
+            Hit::TextPreedit(tp) => {
+                if self.read_only {
+                    return
+                }
+                self.preedit = tp.text.clone();
+                self.preedit_cursor = tp.cursor;
+                self.draw_bg.redraw(cx);
+            }
             Hit::TextInput(te) => {
+                // committing ends the composition
+                if !self.preedit.is_empty() {
+                    self.preedit.clear();
+                    self.draw_bg.redraw(cx);
+                }
                 let mut input = String::new();
                 self.filter_input(&te.input, Some(&mut input));
                 if input.len() == 0 {
@@ -374,121 +414,124 @@ impl TextInput {
                     self.change(cx, "", dispatch_action);
                 }
             }
-            Hit::KeyDown(ke) => match ke.key_code {
+            Hit::KeyDown(ke) => {
+                // keys the text input doesn't use can still run a command
+                let mut handled = true;
+                match ke.key_code {
                 
-                KeyCode::Tab => {
-                    // dispatch_action(cx, self, TextInputAction::Tab(key.mod_shift));
-                }
-                KeyCode::ReturnKey => {
-                    cx.hide_text_ime();
-                    dispatch_action(cx, TextInputAction::Return(self.text.clone()));
-                },
-                KeyCode::Escape => {
-                    dispatch_action(cx, TextInputAction::Escape);
-                },
-                KeyCode::KeyZ if ke.modifiers.logo || ke.modifiers.shift => {
-                    if self.read_only {
-                        return
-                    }
-                    self.undo_id += 1;
-                    if ke.modifiers.shift {
-                        self.redo();
-                    }
-                    else {
-                        self.undo();
+                    KeyCode::Tab => {
+                        // dispatch_action(cx, self, TextInputAction::Tab(key.mod_shift));
+                        // left unhandled so focus traversal still gets it
+                        handled = false;
                     }
-                    dispatch_action(cx, TextInputAction::Change(self.text.clone()));
-                    self.draw_bg.redraw(cx);
-                }
-                KeyCode::KeyA if ke.modifiers.logo || ke.modifiers.control => {
-                    self.undo_id += 1;
-                    self.cursor_tail = 0;
-                    self.cursor_head = self.text.chars().count();
-                    self.draw_bg.redraw(cx);
-                }
-                KeyCode::ArrowLeft => if !ke.modifiers.logo {
-                    
-                    self.undo_id += 1;
-                    if self.cursor_head>0 {
-                        self.cursor_head -= 1;
-                    }
-                    if !ke.modifiers.shift {
-                        self.cursor_tail = self.cursor_head;
-                    }
-                    self.draw_bg.redraw(cx);
-                },
-                KeyCode::ArrowRight => if !ke.modifiers.logo {
-                    self.undo_id += 1;
-                    if self.cursor_head < self.text.chars().count() {
-                        self.cursor_head += 1;
+                    KeyCode::ReturnKey => {
+                        cx.hide_text_ime();
+                        dispatch_action(cx, TextInputAction::Return(self.text.clone()));
+                    },
+                    KeyCode::Escape => {
+                        dispatch_action(cx, TextInputAction::Escape);
+                    },
+                    // a read only input still owns undo, it shouldn't undo something else in the app
+                    KeyCode::KeyZ if ke.modifiers.logo || ke.modifiers.shift => if !self.read_only {
+                        self.undo_id += 1;
+                        if ke.modifiers.shift {
+                            self.redo();
+                        }
+                        else {
+                            self.undo();
+                        }
+                        dispatch_action(cx, TextInputAction::Change(self.text.clone()));
+                        self.draw_bg.redraw(cx);
                     }
-                    if !ke.modifiers.shift {
-                        self.cursor_tail = self.cursor_head;
+                    KeyCode::KeyA if ke.modifiers.logo || ke.modifiers.control => {
+                        self.undo_id += 1;
+                        self.cursor_tail = 0;
+                        self.cursor_head = self.text.chars().count();
+                        self.draw_bg.redraw(cx);
                     }
-                    self.draw_bg.redraw(cx);
-                }
-                KeyCode::ArrowDown => if !ke.modifiers.logo {
-                    self.undo_id += 1;
-                    // we need to figure out what is below our current cursor
-                    if let Some(pos) = self.draw_text.get_cursor_pos(cx, 0.0, self.cursor_head) {
-                        if let Some(pos) = self.draw_text.closest_offset(cx, dvec2(pos.x, pos.y + self.draw_text.get_line_spacing() * 1.5)) {
-                            self.cursor_head = pos;
-                            if !ke.modifiers.shift {
-                                self.cursor_tail = self.cursor_head;
+                    KeyCode::ArrowLeft | KeyCode::ArrowRight => if !ke.modifiers.logo {
+                        self.undo_id += 1;
+                        // in right to left text the arrows move the other way through the string
+                        let forward = (ke.key_code == KeyCode::ArrowRight) != self.draw_text.get_is_rtl(cx, self.cursor_head);
+                        if forward {
+                            if self.cursor_head < self.text.chars().count() {
+                                self.cursor_head += 1;
                             }
-                            self.draw_bg.redraw(cx);
                         }
+                        else if self.cursor_head>0 {
+                            self.cursor_head -= 1;
+                        }
+                        if !ke.modifiers.shift {
+                            self.cursor_tail = self.cursor_head;
+                        }
+                        self.draw_bg.redraw(cx);
                     }
-                },
-                KeyCode::ArrowUp => if !ke.modifiers.logo {
-                    self.undo_id += 1;
-                    // we need to figure out what is below our current cursor
-                    if let Some(pos) = self.draw_text.get_cursor_pos(cx, 0.0, self.cursor_head) {
-                        if let Some(pos) = self.draw_text.closest_offset(cx, dvec2(pos.x, pos.y - self.draw_text.get_line_spacing() * 0.5)) {
-                            self.cursor_head = pos;
-                            if !ke.modifiers.shift {
-                                self.cursor_tail = self.cursor_head;
+                    KeyCode::ArrowDown => if !ke.modifiers.logo {
+                        self.undo_id += 1;
+                        // we need to figure out what is below our current cursor
+                        if let Some(pos) = self.draw_text.get_cursor_pos(cx, 0.0, self.cursor_head) {
+                            if let Some(pos) = self.draw_text.closest_offset(cx, dvec2(pos.x, pos.y + self.draw_text.get_line_spacing() * 1.5)) {
+                                self.cursor_head = pos;
+                                if !ke.modifiers.shift {
+                                    self.cursor_tail = self.cursor_head;
+                                }
+                                self.draw_bg.redraw(cx);
                             }
-                            self.draw_bg.redraw(cx);
                         }
+                    },
+                    KeyCode::ArrowUp => if !ke.modifiers.logo {
+                        self.undo_id += 1;
+                        // we need to figure out what is below our current cursor
+                        if let Some(pos) = self.draw_text.get_cursor_pos(cx, 0.0, self.cursor_head) {
+                            if let Some(pos) = self.draw_text.closest_offset(cx, dvec2(pos.x, pos.y - self.draw_text.get_line_spacing() * 0.5)) {
+                                self.cursor_head = pos;
+                                if !ke.modifiers.shift {
+                                    self.cursor_tail = self.cursor_head;
+                                }
+                                self.draw_bg.redraw(cx);
+                            }
+                        }
+                    },
+                    KeyCode::Home => if !ke.modifiers.logo {
+                        self.undo_id += 1;
+                        self.cursor_head = 0;
+                        if !ke.modifiers.shift {
+                            self.cursor_tail = self.cursor_head;
+                        }
+                        self.draw_bg.redraw(cx);
                     }
-                },
-                KeyCode::Home => if !ke.modifiers.logo {
-                    self.undo_id += 1;
-                    self.cursor_head = 0;
-                    if !ke.modifiers.shift {
-                        self.cursor_tail = self.cursor_head;
-                    }
-                    self.draw_bg.redraw(cx);
-                }
-                KeyCode::End => if !ke.modifiers.logo {
-                    self.undo_id += 1;
-                    self.cursor_head = self.text.chars().count();
+                    KeyCode::End => if !ke.modifiers.logo {
+                        self.undo_id += 1;
+                        self.cursor_head = self.text.chars().count();
                     
-                    if !ke.modifiers.shift {
-                        self.cursor_tail = self.cursor_head;
+                        if !ke.modifiers.shift {
+                            self.cursor_tail = self.cursor_head;
+                        }
+                        self.draw_bg.redraw(cx);
                     }
-                    self.draw_bg.redraw(cx);
-                }
-                KeyCode::Backspace => {
-                    self.create_undo(UndoGroup::Backspace(self.undo_id));
-                    if self.cursor_head == self.cursor_tail {
-                        if self.cursor_tail > 0 {
-                            self.cursor_tail -= 1;
+                    KeyCode::Backspace => {
+                        self.create_undo(UndoGroup::Backspace(self.undo_id));
+                        if self.cursor_head == self.cursor_tail {
+                            if self.cursor_tail > 0 {
+                                self.cursor_tail -= 1;
+                            }
                         }
+                        self.change(cx, "", dispatch_action);
                     }
-                    self.change(cx, "", dispatch_action);
-                }
-                KeyCode::Delete => {
-                    self.create_undo(UndoGroup::Delete(self.undo_id));
-                    if self.cursor_head == self.cursor_tail {
-                        if self.cursor_head < self.text.chars().count() {
-                            self.cursor_head += 1;
+                    KeyCode::Delete => {
+                        self.create_undo(UndoGroup::Delete(self.undo_id));
+                        if self.cursor_head == self.cursor_tail {
+                            if self.cursor_head < self.text.chars().count() {
+                                self.cursor_head += 1;
+                            }
                         }
+                        self.change(cx, "", dispatch_action);
                     }
-                    self.change(cx, "", dispatch_action);
+                    _ => handled = false
+                }
+                if handled {
+                    cx.set_key_down_handled();
                 }
-                _ => ()
             }
             Hit::FingerHoverIn(_) => {
                 cx.set_cursor(MouseCursor::Text);
@@ -586,7 +629,20 @@ impl TextInput {
         // this makes sure selection goes behind the text
         self.draw_select.append_to_draw_call(cx);
         
-        if self.text.len() == 0 {
+        // an input method composition is drawn in place of the selection
+        let composing = !self.preedit.is_empty();
+        let preedit_text;
+        let text = if composing {
+            preedit_text = self.text_with_preedit();
+            &preedit_text
+        }
+        else {
+            &self.text
+        };
+        let (left, _) = self.sorted_cursor();
+        let cursor_head = if composing {left + self.preedit_cursor} else {self.cursor_head};
+        
+        if text.len() == 0 {
             self.draw_text.is_empty = 1.0;
             self.draw_text.draw_walk(cx, Walk::size(self.walk.width, self.walk.height), self.label_align, &self.empty_message);
         }
@@ -596,13 +652,13 @@ impl TextInput {
                 self.draw_text.draw_walk(cx, Walk::size(
                     self.walk.width,
                     self.walk.height
-                ), self.label_align, &"*".repeat(self.text.len()));
+                ), self.label_align, &"*".repeat(text.len()));
             }
             else {
                 self.draw_text.draw_walk(cx, Walk::size(
                     self.walk.width,
                     self.walk.height
-                ), self.label_align, &self.text);
+                ), self.label_align, text);
             }
         }
         
@@ -612,10 +668,10 @@ impl TextInput {
         // move the IME
         let line_spacing = self.draw_text.get_line_spacing();
         let top_drop = self.draw_text.get_font_size() * 0.2;
-        let head = self.draw_text.get_cursor_pos(cx, 0.0, self.cursor_head)
+        let head = self.draw_text.get_cursor_pos(cx, 0.0, cursor_head)
             .unwrap_or(dvec2(turtle.pos.x, 0.0));
         
-        if !self.read_only && self.cursor_head == self.cursor_tail {
+        if !self.read_only && (composing || self.cursor_head == self.cursor_tail) {
             self.draw_cursor.draw_abs(cx, Rect {
                 pos: dvec2(head.x - 0.5 * self.cursor_size, head.y - top_drop),
                 size: dvec2(self.cursor_size, line_spacing)
@@ -624,7 +680,18 @@ impl TextInput {
         
         // draw selection rects
         
-        if self.cursor_head != self.cursor_tail {
+        if composing {
+            let bottom_drop = self.draw_text.get_font_size() * 0.1;
+            let end = left + self.preedit.chars().count();
+            let rects = self.draw_text.get_selection_rects(cx, left, end, dvec2(0.0, 0.0), dvec2(0.0, bottom_drop));
+            for rect in rects {
+                self.draw_preedit.draw_abs(cx, Rect {
+                    pos: dvec2(rect.pos.x, rect.pos.y + rect.size.y - self.preedit_underline),
+                    size: dvec2(rect.size.x, self.preedit_underline)
+                });
+            }
+        }
+        else if self.cursor_head != self.cursor_tail {
             let top_drop = self.draw_text.get_font_size() * 0.3;
             let bottom_drop = self.draw_text.get_font_size() * 0.1;
             
@@ -638,7 +705,7 @@ impl TextInput {
         
         if  cx.has_key_focus(self.draw_bg.area()) {
             // ok so. if we have the IME we should inject a tracking point
-            let ime_x = self.draw_text.get_cursor_pos(cx, 0.5, self.cursor_head)
+            let ime_x = self.draw_text.get_cursor_pos(cx, 0.5, cursor_head)
                 .unwrap_or(dvec2(turtle.pos.x, 0.0)).x;
             
             if self.numeric_only {
@@ -646,7 +713,7 @@ impl TextInput {
             }
             else {
                 let ime_abs = dvec2(ime_x, turtle.pos.y);
-                cx.show_text_ime(self.draw_bg.area(), ime_abs - turtle_rect.pos);
+                cx.show_text_ime(self.draw_bg.area(), ime_abs - turtle_rect.pos, self.draw_text.get_line_spacing());
             }
         }
         
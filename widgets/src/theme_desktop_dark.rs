@@ -241,6 +241,39 @@ live_design!{
         }
     }
     
+    RichText = <RichTextBase> {
+        width: Fill
+        height: Fit
+        draw_bg: {color: #0000}
+        draw_text: {
+            color: #8,
+            text_style: <THEME_FONT_LABEL>{}
+            wrap: Word
+        }
+        draw_underline: {color: #8}
+    }
+    
+    Markdown = <MarkdownBase> {
+        width: Fill
+        height: Fit
+        flow: Down
+        spacing: 8.0
+        draw_text: {
+            color: #a,
+            text_style: <THEME_FONT_LABEL>{}
+        }
+        strong_style: {font: {path: dep("crate://self/resources/IBMPlexSans-SemiBold.ttf")}}
+        code_style: <THEME_FONT_CODE>{font_size: 8.5}
+        heading_font_sizes: [16.0, 13.0, 11.0, 10.0]
+        emphasis_color: #d
+        link_color: #x6CA7E6
+        code_color: #c
+        code_padding: {left: 8, top: 6, right: 8, bottom: 6}
+        draw_code_bg: {color: #2}
+        draw_quote: {color: #5}
+        draw_rule: {color: #4}
+    }
+    
     // Button
     
     
@@ -676,97 +709,6 @@ live_design!{
     WindowMenu = <WindowMenuBase>{
     }
     
-    Window = <WindowBase> {
-        pass: {clear_color: (THEME_COLOR_CLEAR)}
-        flow: Down
-        nav_control: <NavControl> {}
-        caption_bar = <SolidView> {
-            visible: false,
-            
-            flow: Right
-            
-            draw_bg: {color: (THEME_COLOR_BG_APP)}
-            height: 27
-            caption_label = <View> {
-                width: Fill,
-                height: Fill
-                align: {x: 0.5, y: 0.5},
-                label = <Label> {text: "Makepad", margin: {left: 100}}
-            }
-            windows_buttons = <View> {
-                visible: false,
-                width: Fit,
-                height: Fit
-                min = <DesktopButton> {draw_bg: {button_type: WindowsMin}}
-                max = <DesktopButton> {draw_bg: {button_type: WindowsMax}}
-                close = <DesktopButton> {draw_bg: {button_type: WindowsClose}}
-            }
-            web_fullscreen = <View> {
-                visible: false,
-                width: Fit,
-                height: Fit
-                fullscreen = <DesktopButton> {draw_bg: {button_type: Fullscreen}}
-            }
-            web_xr = <View> {
-                visible: false,
-                width: Fit,
-                height: Fit
-                xr_on = <DesktopButton> {draw_bg: {button_type: XRMode}}
-            }
-        }
-        window_menu = <WindowMenu>{
-            main = Main{items:[app]}
-            app = Sub{name:"Makepad",items:[quit]}
-            quit = Item{
-                name:"Quit",
-                shift: false,
-                key: KeyQ,
-                enabled: true
-            }
-        }
-        body = <KeyboardView>{
-            keyboard_min_shift: 30,
-            width: Fill,
-            height: Fill
-        }
-        
-        cursor: Default
-        mouse_cursor_size: vec2(20, 20),
-        draw_cursor: {
-            instance border_width: 1.5
-            instance color: #000
-            instance border_color: #fff
-            
-            fn get_color(self) -> vec4 {
-                return self.color
-            }
-            
-            fn get_border_color(self) -> vec4 {
-                return self.border_color
-            }
-            
-            fn pixel(self) -> vec4 {
-                let sdf = Sdf2d::viewport(self.pos * self.rect_size)
-                sdf.move_to(1.0, 1.0);
-                sdf.line_to(self.rect_size.x - 1.0, self.rect_size.y * 0.5)
-                sdf.line_to(self.rect_size.x * 0.5, self.rect_size.y - 1.0)
-                sdf.close_path();
-                sdf.fill_keep(self.get_color())
-                if self.border_width > 0.0 {
-                    sdf.stroke(self.get_border_color(), self.border_width)
-                }
-                return sdf.result
-            }
-        }
-        window: {
-            inner_size: vec2(1024, 768)
-        }
-    }
-    
-    
-    // Dock
-    
-    
     Splitter = <SplitterBase> {
         draw_splitter: {
             uniform border_radius: 1.0
@@ -1079,16 +1021,25 @@ live_design!{
             fn get_color(self) -> vec4 {
                 return mix(
                     mix(
-                        THEME_COLOR_TEXT_DEFAULT,
-                        THEME_COLOR_TEXT_SELECTED,
-                        self.selected
+                        mix(
+                            THEME_COLOR_TEXT_DEFAULT,
+                            THEME_COLOR_TEXT_SELECTED,
+                            self.selected
+                        ),
+                        THEME_COLOR_TEXT_HOVER,
+                        self.hover
                     ),
-                    THEME_COLOR_TEXT_HOVER,
-                    self.hover
+                    THEME_COLOR_TEXT_META,
+                    self.disabled
                 )
             }
         }
         
+        draw_hint: {
+            text_style: <THEME_FONT_LABEL> {}
+            color: (THEME_COLOR_TEXT_META)
+        }
+        
         draw_bg: {
             instance selected: 0.0
             instance hover: 0.0
@@ -1167,6 +1118,9 @@ live_design!{
         flow: Down,
         padding: 5
         
+        draw_line: {color: (THEME_COLOR_UP_10)}
+        line_walk: {width: Fill, height: 1, margin: {top: 3, bottom: 3}}
+        
         
         width: 100,
         height: Fit
@@ -1205,6 +1159,127 @@ live_design!{
     
     
     
+    // the menu bar opens PopupMenus, so it and the Window holding it are defined after them
+    MenuBar = <MenuBarBase> {
+        width: Fill,
+        height: Fit,
+        flow: Right,
+        padding: {left: 4}
+        
+        item_walk: {width: Fit, height: Fit}
+        item_layout: {padding: {left: 8, top: 5, right: 8, bottom: 5}}
+        mnemonic_size: 1.0
+        
+        draw_bg: {color: (THEME_COLOR_BG_HEADER)}
+        draw_item: {
+            fn pixel(self) -> vec4 {
+                return mix(#0000, THEME_COLOR_UP_10, max(self.hover, self.open))
+            }
+        }
+        draw_text: {
+            text_style: <THEME_FONT_LABEL> {}
+            color: (THEME_COLOR_TEXT_DEFAULT)
+        }
+        draw_mnemonic: {color: (THEME_COLOR_TEXT_DEFAULT)}
+        
+        popup_menu: <PopupMenu> {
+            width: 250
+            menu_item: {padding: {right: 15}}
+        }
+    }
+    
+    Window = <WindowBase> {
+        pass: {clear_color: (THEME_COLOR_CLEAR)}
+        flow: Down
+        nav_control: <NavControl> {}
+        caption_bar = <SolidView> {
+            visible: false,
+            
+            flow: Right
+            
+            draw_bg: {color: (THEME_COLOR_BG_APP)}
+            height: 27
+            caption_label = <View> {
+                width: Fill,
+                height: Fill
+                align: {x: 0.5, y: 0.5},
+                label = <Label> {text: "Makepad", margin: {left: 100}}
+            }
+            windows_buttons = <View> {
+                visible: false,
+                width: Fit,
+                height: Fit
+                min = <DesktopButton> {draw_bg: {button_type: WindowsMin}}
+                max = <DesktopButton> {draw_bg: {button_type: WindowsMax}}
+                close = <DesktopButton> {draw_bg: {button_type: WindowsClose}}
+            }
+            web_fullscreen = <View> {
+                visible: false,
+                width: Fit,
+                height: Fit
+                fullscreen = <DesktopButton> {draw_bg: {button_type: Fullscreen}}
+            }
+            web_xr = <View> {
+                visible: false,
+                width: Fit,
+                height: Fit
+                xr_on = <DesktopButton> {draw_bg: {button_type: XRMode}}
+            }
+        }
+        menu_bar = <MenuBar> {}
+        window_menu = <WindowMenu>{
+            main = Main{items:[app]}
+            app = Sub{name:"Makepad",items:[quit]}
+            quit = Item{
+                name:"Quit",
+                shift: false,
+                key: KeyQ,
+                enabled: true
+            }
+        }
+        body = <KeyboardView>{
+            keyboard_min_shift: 30,
+            width: Fill,
+            height: Fill
+        }
+        
+        cursor: Default
+        mouse_cursor_size: vec2(20, 20),
+        draw_cursor: {
+            instance border_width: 1.5
+            instance color: #000
+            instance border_color: #fff
+            
+            fn get_color(self) -> vec4 {
+                return self.color
+            }
+            
+            fn get_border_color(self) -> vec4 {
+                return self.border_color
+            }
+            
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                sdf.move_to(1.0, 1.0);
+                sdf.line_to(self.rect_size.x - 1.0, self.rect_size.y * 0.5)
+                sdf.line_to(self.rect_size.x * 0.5, self.rect_size.y - 1.0)
+                sdf.close_path();
+                sdf.fill_keep(self.get_color())
+                if self.border_width > 0.0 {
+                    sdf.stroke(self.get_border_color(), self.border_width)
+                }
+                return sdf.result
+            }
+        }
+        window: {
+            inner_size: vec2(1024, 768)
+        }
+    }
+    
+    
+    // Dock
+    
+    
     DropDown = <DropDownBase> {
         draw_text: {
             fn get_color(self) -> vec4 {
@@ -1982,6 +2057,10 @@ live_design!{
             }
         }
         
+        draw_preedit: {
+            color: #xFFFFFFCC
+        }
+        
         cursor_margin_bottom: 3.0,
         cursor_margin_top: 4.0,
         select_pad_edges: 3.0
@@ -0,0 +1,285 @@
+use {
+    std::{
+        rc::Rc,
+        cell::RefCell,
+    },
+    crate::{
+        makepad_draw::*,
+        makepad_platform::os::{
+            cx_stdin::{HostToStdin, StdinMouseDown, StdinMouseMove, StdinMouseUp, StdinScroll},
+            cx_test_driver::CxTestDriver,
+        },
+        widget::*,
+    }
+};
+
+// Scripted UI tests. UiTest boots an app the same way app_main! does, but
+// instead of an OS event loop it feeds HostToStdin messages through a
+// CxTestDriver. Widgets are addressed with the same paths as WidgetRef::widget,
+// input is aimed at the center of their last drawn rect, and every action the
+// root widget emits is recorded so tests can assert on it. The recording hooks
+// into WidgetRef event handling, so this is behind the ui_test feature.
+//
+//   let mut test = UiTest::new(live_design, | app: &App | app.ui.clone());
+//   let actions = test.click(id!(button1));
+//   assert!(test.root().button(id!(button1)).clicked(&actions));
+//   assert_eq!(test.text(id!(label1)), "Counter: 1");
+
+#[derive(Default)]
+pub(crate) struct UiTestActions {
+    root: Option<WidgetUid>,
+    actions: WidgetActions,
+}
+
+impl UiTestActions {
+    pub(crate) fn is_root(cx: &mut Cx, widget: &WidgetRef) -> bool {
+        cx.has_global::<UiTestActions>() && cx.get_global::<UiTestActions>().root == Some(widget.widget_uid())
+    }
+
+    pub(crate) fn record(cx: &mut Cx, actions: &[WidgetActionItem]) {
+        cx.get_global::<UiTestActions>().actions.extend_from_slice(actions);
+    }
+}
+
+pub struct UiTest<A> {
+    pub cx: Cx,
+    app: Rc<RefCell<Option<A >>>,
+    root: fn(&A) -> WidgetRef,
+    driver: CxTestDriver,
+}
+
+impl<A: AppMain + LiveNew + 'static> UiTest<A> {
+    pub fn new(live_design: fn(&mut Cx), root: fn(&A) -> WidgetRef) -> Self {
+        Self::new_with_size(live_design, root, dvec2(800.0, 600.0), 1.0)
+    }
+
+    pub fn new_with_size(live_design: fn(&mut Cx), root: fn(&A) -> WidgetRef, inner_size: DVec2, dpi_factor: f64) -> Self {
+        let app = Rc::new(RefCell::new(None));
+        let app_handler = app.clone();
+        let mut cx = Cx::new(Box::new(move | cx, event | {
+            if let Event::Construct = event {
+                *app_handler.borrow_mut() = Some(A::new_main(cx));
+            }
+            if let Event::LiveEdit = event {
+                if let Some(app) = app_handler.borrow_mut().as_mut() {
+                    app.update_main(cx);
+                }
+            }
+            if let Some(app) = app_handler.borrow_mut().as_mut() {
+                <dyn AppMain>::handle_event(app, cx, event);
+            }
+        }));
+        live_design(&mut cx);
+        cx.init_cx_os();
+        cx.set_global(UiTestActions::default());
+
+        let mut test = Self {
+            cx,
+            app,
+            root,
+            driver: CxTestDriver::new(inner_size, dpi_factor),
+        };
+        test.driver.start(&mut test.cx);
+        test.settle();
+        test.take_actions();
+        test
+    }
+
+    pub fn root(&self) -> WidgetRef {
+        (self.root)(self.app.borrow().as_ref().unwrap())
+    }
+
+    pub fn widget(&self, path: &[LiveId]) -> WidgetRef {
+        self.root().widget(path)
+    }
+
+    pub fn text(&self, path: &[LiveId]) -> String {
+        self.widget(path).text()
+    }
+
+    // the rect the widget was last drawn in, empty if it wasn't drawn
+    pub fn rect(&self, path: &[LiveId]) -> Rect {
+        self.widget(path).area().get_rect(&self.cx)
+    }
+
+    pub fn time(&self) -> f64 {
+        self.driver.time()
+    }
+
+    // all actions the root widget emitted since the last call
+    pub fn take_actions(&mut self) -> WidgetActions {
+        std::mem::take(&mut self.cx.get_global::<UiTestActions>().actions)
+    }
+
+    // dispatches a raw message, draws and returns the actions it caused
+    pub fn send(&mut self, msg: HostToStdin) -> WidgetActions {
+        // the root can be replaced by a live edit, so look it up every time
+        let root_uid = self.root().widget_uid();
+        self.cx.get_global::<UiTestActions>().root = Some(root_uid);
+        self.driver.send(&mut self.cx, msg);
+        self.driver.step(&mut self.cx);
+        self.take_actions()
+    }
+
+    pub fn draw(&mut self) {
+        self.driver.step(&mut self.cx);
+    }
+
+    // runs animations and timers until the UI is idle, up to 10 seconds of app time
+    pub fn settle(&mut self) -> WidgetActions {
+        self.driver.settle(&mut self.cx, 10.0);
+        self.take_actions()
+    }
+
+    pub fn advance(&mut self, seconds: f64) -> WidgetActions {
+        let time = self.driver.time() + seconds;
+        self.driver.advance_to(&mut self.cx, time);
+        self.take_actions()
+    }
+
+    pub fn mouse_move_to(&mut self, pos: DVec2) -> WidgetActions {
+        let time = self.time();
+        self.send(HostToStdin::MouseMove(StdinMouseMove {time, x: pos.x, y: pos.y}))
+    }
+
+    pub fn mouse_down_at(&mut self, pos: DVec2, button: usize) -> WidgetActions {
+        let time = self.time();
+        self.send(HostToStdin::MouseDown(StdinMouseDown {button, x: pos.x, y: pos.y, time}))
+    }
+
+    pub fn mouse_up_at(&mut self, pos: DVec2, button: usize) -> WidgetActions {
+        let time = self.time();
+        self.send(HostToStdin::MouseUp(StdinMouseUp {button, x: pos.x, y: pos.y, time}))
+    }
+
+    pub fn scroll_at(&mut self, pos: DVec2, scroll: DVec2) -> WidgetActions {
+        let time = self.time();
+        self.send(HostToStdin::Scroll(StdinScroll {time, sx: scroll.x, sy: scroll.y, x: pos.x, y: pos.y, is_mouse: true}))
+    }
+
+    // the composite helpers below return the actions of the one event that does
+    // the work (mouse up, key down, text input), the way an app sees them in a
+    // single handle_event call
+    pub fn click_at(&mut self, pos: DVec2) -> WidgetActions {
+        self.mouse_move_to(pos);
+        self.mouse_down_at(pos, 0);
+        self.mouse_up_at(pos, 0)
+    }
+
+    // clicks the center of the widget at path
+    pub fn click(&mut self, path: &[LiveId]) -> WidgetActions {
+        let rect = self.rect(path);
+        if rect.size.x <= 0.0 || rect.size.y <= 0.0 {
+            panic!("UiTest: widget {:?} has not been drawn", path);
+        }
+        self.click_at(rect.center())
+    }
+
+    pub fn mouse_move(&mut self, path: &[LiveId]) -> WidgetActions {
+        let rect = self.rect(path);
+        self.mouse_move_to(rect.center())
+    }
+
+    pub fn key_down(&mut self, key_code: KeyCode, modifiers: KeyModifiers) -> WidgetActions {
+        let time = self.time();
+        self.send(HostToStdin::KeyDown(KeyEvent {key_code, is_repeat: false, modifiers, time}))
+    }
+
+    pub fn key_up(&mut self, key_code: KeyCode, modifiers: KeyModifiers) -> WidgetActions {
+        let time = self.time();
+        self.send(HostToStdin::KeyUp(KeyEvent {key_code, is_repeat: false, modifiers, time}))
+    }
+
+    pub fn key_press(&mut self, key_code: KeyCode) -> WidgetActions {
+        let actions = self.key_down(key_code, Default::default());
+        self.key_up(key_code, Default::default());
+        actions
+    }
+
+    // text input goes to whatever has key focus
+    pub fn text_input(&mut self, input: &str) -> WidgetActions {
+        self.send(HostToStdin::TextInput(TextInputEvent {
+            input: input.to_string(),
+            replace_last: false,
+            was_paste: false
+        }))
+    }
+
+    // focuses the widget at path with a click and types into it
+    pub fn type_text(&mut self, path: &[LiveId], input: &str) -> WidgetActions {
+        self.click(path);
+        self.text_input(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            button::ButtonWidgetRefExt,
+            label::LabelWidgetRefExt,
+            text_input::TextInputWidgetRefExt,
+        },
+    };
+
+    live_design!{
+        import crate::base::*;
+        import crate::theme_desktop_dark::*;
+
+        UiTestApp = {{UiTestApp}} {
+            ui: <Window> {
+                body = <View> {
+                    flow: Down,
+                    button1 = <Button> {text: "Count"}
+                    input1 = <TextInput> {width: 100, height: 30, text: ""}
+                    label1 = <Label> {text: "Counter: 0"}
+                }
+            }
+        }
+    }
+
+    #[derive(Live, LiveHook)]
+    struct UiTestApp {
+        #[live] ui: WidgetRef,
+        #[rust] counter: usize,
+    }
+
+    impl AppMain for UiTestApp {
+        fn handle_event(&mut self, cx: &mut Cx, event: &Event) {
+            if let Event::Draw(event) = event {
+                return self.ui.draw_widget_all(&mut Cx2d::new(cx, event));
+            }
+            let actions = self.ui.handle_widget_event(cx, event);
+            if self.ui.button(id!(button1)).clicked(&actions) {
+                self.counter += 1;
+                self.ui.label(id!(label1)).set_text_and_redraw(cx, &format!("Counter: {}", self.counter));
+            }
+        }
+    }
+
+    fn app_live_design(cx: &mut Cx) {
+        crate::live_design(cx);
+        live_design(cx);
+    }
+
+    #[test]
+    fn test_click_and_type() {
+        let mut test = UiTest::new(app_live_design, | app: &UiTestApp | app.ui.clone());
+        assert!(test.rect(id!(button1)).size.x > 0.0);
+
+        let actions = test.click(id!(button1));
+        assert!(test.root().button(id!(button1)).clicked(&actions));
+        assert_eq!(test.text(id!(label1)), "Counter: 1");
+
+        // clicking next to the button does nothing
+        let rect = test.rect(id!(button1));
+        let actions = test.click_at(rect.pos + rect.size + dvec2(200.0, 200.0));
+        assert!(!test.root().button(id!(button1)).clicked(&actions));
+        assert_eq!(test.text(id!(label1)), "Counter: 1");
+
+        let actions = test.type_text(id!(input1), "abc");
+        assert_eq!(test.root().text_input(id!(input1)).changed(&actions), Some("abc".to_string()));
+        assert_eq!(test.text(id!(input1)), "abc");
+    }
+}
@@ -694,7 +694,7 @@ fn planar_to_u32(
 
     let y_start = 0;
     let u_start = y_stride * height;
-    let v_start = u_start + u_stride * (height / 2);
+    let v_start = u_start + u_stride * ((height + 1) / 2);
 
     for row in 0..height {
         let y_row_start = y_start + row * y_stride;
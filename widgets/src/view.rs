@@ -66,6 +66,8 @@ pub struct View { // draw info per UI element
     #[live(false)] block_signal_event: bool,
     #[live] cursor: Option<MouseCursor>,
     #[live] scroll_bars: Option<LivePtr>,
+    // column and row tracks for flow: Grid
+    #[live] grid: Grid,
     #[live(false)] design_mode: bool,
     
     #[rust] find_cache: HashMap<u64, WidgetSet>,
@@ -520,7 +522,11 @@ impl Widget for View {
             child.redraw(cx);
         }
     }
-    
+
+    fn area(&self) -> Area {
+        self.area
+    }
+
     fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet) {
         match cached {
             WidgetCache::Yes | WidgetCache::Clear => {
@@ -596,7 +602,9 @@ impl View {
             abs_pos: walk.abs_pos,
             width: if walk.width.is_fill() {walk.width}else {Size::Fixed(view_size.x)},
             height: if walk.height.is_fill() {walk.height}else {Size::Fixed(view_size.y)},
-            margin: walk.margin
+            margin: walk.margin,
+            cell: walk.cell,
+            constraint: walk.constraint
         }
     }
     
@@ -669,6 +677,9 @@ impl View {
             else {
                 cx.begin_turtle(walk, self.layout.with_scroll(scroll)); //.with_scale(2.0 / self.dpi_factor.unwrap_or(2.0)));
             }
+            if let Flow::Grid = self.layout.flow {
+                cx.turtle_mut().set_grid(&self.grid);
+            }
         }
         
         while let Some(DrawState::Drawing(step, resume)) = self.draw_state.get() {
@@ -6,6 +6,8 @@ use {
     std::cell::RefCell,
     std::rc::Rc
 };
+#[cfg(any(test, feature = "ui_test"))]
+use crate::ui_test::UiTestActions;
 pub use crate::register_widget;
 
 #[derive(Clone, Copy)]
@@ -63,6 +65,8 @@ pub trait Widget: LiveApply {
     fn draw_walk_widget(&mut self, cx: &mut Cx2d, walk: Walk) -> WidgetDraw;
     fn walk(&mut self, _cx:&mut Cx) -> Walk {Walk::default()}
     fn redraw(&mut self, _cx: &mut Cx);
+    // the area the widget was last drawn in, used to find its rect on screen
+    fn area(&self) -> Area {Area::Empty}
     
     fn is_visible(&self) -> bool {
         true
@@ -407,12 +411,30 @@ impl WidgetRef {
     }
     
     pub fn handle_widget_event_with(&self, cx: &mut Cx, event: &Event, dispatch_action: &mut dyn FnMut(&mut Cx, WidgetActionItem)) {
+        #[cfg(any(test, feature = "ui_test"))]
+        if UiTestActions::is_root(cx, self) {
+            if let Some(inner) = self.0.borrow_mut().as_mut() {
+                inner.handle_widget_event_with(cx, event, &mut | cx, action | {
+                    UiTestActions::record(cx, std::slice::from_ref(&action));
+                    dispatch_action(cx, action)
+                })
+            }
+            return
+        }
         if let Some(inner) = self.0.borrow_mut().as_mut() {
             return inner.handle_widget_event_with(cx, event, dispatch_action)
         }
     }
     
     pub fn handle_widget_event(&self, cx: &mut Cx, event: &Event) -> Vec<WidgetActionItem> {
+        #[cfg(any(test, feature = "ui_test"))]
+        if UiTestActions::is_root(cx, self) {
+            if let Some(inner) = self.0.borrow_mut().as_mut() {
+                let actions = inner.handle_widget_event(cx, event);
+                UiTestActions::record(cx, &actions);
+                return actions
+            }
+        }
         if let Some(inner) = self.0.borrow_mut().as_mut() {
             return inner.handle_widget_event(cx, event)
         }
@@ -491,6 +513,13 @@ impl WidgetRef {
     }
     
     
+    pub fn area(&self) -> Area {
+        if let Some(inner) = self.0.borrow().as_ref() {
+            return inner.area()
+        }
+        Area::Empty
+    }
+    
     pub fn is_visible(&self) -> bool {
         if let Some(inner) = self.0.borrow().as_ref() {
             return inner.is_visible()
@@ -3,6 +3,8 @@ use crate::{
     debug_view::DebugView,
     makepad_draw::*,
     nav_control::NavControl,
+    menu_bar::*,
+    window_menu::*,
     button::*,
     view::*,
     widget::*,
@@ -78,6 +80,7 @@ impl LiveHook for Window {
             }
             OsType::LinuxWindow(_) |
             OsType::LinuxDirect |
+            OsType::LinuxHeadless |
             OsType::Android(_) => {
                 //self.frame.get_view(id!(caption_bar)).set_visible(false);
             }
@@ -86,6 +89,12 @@ impl LiveHook for Window {
             }
             _ => ()
         }
+        // platforms without a native menu draw the window menu in the window
+        if let OsType::Windows | OsType::LinuxWindow(_) = cx.os_type() {
+            if let Some(menu) = self.window_menu(id!(window_menu)).menu() {
+                self.menu_bar(id!(menu_bar)).set_menu(cx, menu);
+            }
+        }
     }
     
     fn after_apply(&mut self, cx: &mut Cx, _apply_from: ApplyFrom, _index: usize, _nodes: &[LiveNode]) {
@@ -183,7 +192,7 @@ impl Window {
                     self.window.minimize(cx);
                 }
                 if self.button(id!(max)).clicked(&actions) {
-                    if self.window.is_fullscreen(cx) {
+                    if self.window.is_maximized(cx) {
                         self.window.restore(cx);
                     }
                     else {
@@ -281,6 +290,10 @@ impl Widget for Window {
         self.view.redraw(cx)
     }
     
+    fn area(&self) -> Area {
+        self.view.area()
+    }
+    
     fn find_widgets(&mut self, path: &[LiveId], cached: WidgetCache, results: &mut WidgetSet) {
         self.view.find_widgets(path, cached, results);
     }
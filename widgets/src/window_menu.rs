@@ -67,50 +67,22 @@ impl LiveHook for WindowMenu {
         nodes.skip_node(index)
     }
     
-    fn after_new_from_doc(&mut self, _cx: &mut Cx) {
-        // lets translate the menu into a macos menu
-        #[cfg(target_os="macos")]{
-            // alright lets fetch this thing
-            fn recur_menu(command:LiveId,menu_items:&HashMap<LiveId, WindowMenuItem>)->MacosMenu{
-                
-                if let Some(item) = menu_items.get(&command){
-                    match item.clone(){
-                        WindowMenuItem::Main{items}=>{
-                            let mut out = Vec::new();
-                            for item in items{
-                                out.push(recur_menu(item, menu_items));
-                            }
-                            return MacosMenu::Main{items:out}
-                        }
-                        WindowMenuItem::Item{name, shift, key, enabled}=>{
-                            return MacosMenu::Item{
-                                command,
-                                name,
-                                shift,
-                                key,
-                                enabled
-                            }
-                        }
-                        WindowMenuItem::Sub{name, items}=>{
-                            let mut out = Vec::new();
-                            for item in items{
-                                out.push(recur_menu(item, menu_items));
-                            }
-                            return MacosMenu::Sub{name, items:out}
-                        }
-                        WindowMenuItem::Line=>{
-                            return MacosMenu::Line
-                        }
-                    }
-                }
-                else{
-                    log!("Menu cannot find item {}", command);
-                    MacosMenu::Line
+    fn after_new_from_doc(&mut self, cx: &mut Cx) {
+        // without a native menu, whose key equivalents already send menu commands,
+        // menu keys are registered as menu item commands. Either way a menu command
+        // arrives as Event::MacosMenuCommand
+        #[cfg(not(target_os="macos"))]
+        for (id, item) in &self.menu_items {
+            if let WindowMenuItem::Item {shift, key, enabled: true, ..} = item {
+                if !key.is_unknown() {
+                    let chord = KeyChord::cmd(*key);
+                    let chord = if *shift {chord.with_shift()} else {chord};
+                    cx.commands.register_menu_item(*id, &[chord]);
                 }
             }
-            let menu = recur_menu(live_id!(main), &self.menu_items);
-            _cx.update_macos_menu(menu)
         }
+        #[cfg(target_os="macos")]
+        cx.update_macos_menu(self.menu());
     }
     
     fn before_live_design(cx: &mut Cx) {
@@ -119,6 +91,50 @@ impl LiveHook for WindowMenu {
 }
 
 
+impl WindowMenu {
+    // the menu as a MacosMenu tree, for the native menu or a drawn menu bar
+    pub fn menu(&self) -> MacosMenu {
+        fn recur_menu(command:LiveId,menu_items:&HashMap<LiveId, WindowMenuItem>)->MacosMenu{
+            
+            if let Some(item) = menu_items.get(&command){
+                match item.clone(){
+                    WindowMenuItem::Main{items}=>{
+                        let mut out = Vec::new();
+                        for item in items{
+                            out.push(recur_menu(item, menu_items));
+                        }
+                        return MacosMenu::Main{items:out}
+                    }
+                    WindowMenuItem::Item{name, shift, key, enabled}=>{
+                        return MacosMenu::Item{
+                            command,
+                            name,
+                            shift,
+                            key,
+                            enabled
+                        }
+                    }
+                    WindowMenuItem::Sub{name, items}=>{
+                        let mut out = Vec::new();
+                        for item in items{
+                            out.push(recur_menu(item, menu_items));
+                        }
+                        return MacosMenu::Sub{name, items:out}
+                    }
+                    WindowMenuItem::Line=>{
+                        return MacosMenu::Line
+                    }
+                }
+            }
+            else{
+                log!("Menu cannot find item {}", command);
+                MacosMenu::Line
+            }
+        }
+        recur_menu(live_id!(main), &self.menu_items)
+    }
+}
+
 impl Widget for WindowMenu {
     fn redraw(&mut self, _cx: &mut Cx) {
     }
@@ -148,6 +164,10 @@ impl Widget for WindowMenu {
 pub struct WindowMenuRef(WidgetRef);
 
 impl WindowMenuRef {
+    pub fn menu(&self) -> Option<MacosMenu> {
+        self.borrow().map( | inner | inner.menu())
+    }
+    
     pub fn command(&self) -> Option<LiveId> {
         if let Some(mut _dock) = self.borrow_mut() {
           